@@ -0,0 +1,114 @@
+//! Data client: market data subscriptions.
+//!
+//! Maps Nautilus data subscriptions onto Qot_Sub sub types the way the
+//! Python data client does: quote ticks on basic quotes, trade ticks on
+//! tickers, order books on order book pushes and bars on the K-line sub type
+//! of their specification, which is the core's `quote::bars::BarSpec`.
+//! Pushes arrive through `FutuClient::subscribe_push`.
+
+use std::sync::Arc;
+
+use nautilus_futu::client::FutuClient;
+use nautilus_futu::quote::subscribe::{check_sub_results, subscribe};
+use nautilus_futu::types::SubType;
+
+use crate::identifiers::InstrumentId;
+use crate::AdapterError;
+
+pub use nautilus_futu::quote::bars::{BarAggregation, BarSpec, PriceType};
+
+/// Subscribes and unsubscribes market data of instruments.
+pub struct FutuDataClient {
+    client: Arc<FutuClient>,
+}
+
+impl FutuDataClient {
+    pub fn new(client: Arc<FutuClient>) -> Self {
+        Self { client }
+    }
+
+    pub fn client(&self) -> &Arc<FutuClient> {
+        &self.client
+    }
+
+    pub async fn subscribe_quotes(&self, id: &InstrumentId) -> Result<(), AdapterError> {
+        self.sub(id, SubType::Basic, true).await
+    }
+
+    pub async fn unsubscribe_quotes(&self, id: &InstrumentId) -> Result<(), AdapterError> {
+        self.sub(id, SubType::Basic, false).await
+    }
+
+    pub async fn subscribe_trades(&self, id: &InstrumentId) -> Result<(), AdapterError> {
+        self.sub(id, SubType::Ticker, true).await
+    }
+
+    pub async fn unsubscribe_trades(&self, id: &InstrumentId) -> Result<(), AdapterError> {
+        self.sub(id, SubType::Ticker, false).await
+    }
+
+    pub async fn subscribe_order_book(&self, id: &InstrumentId) -> Result<(), AdapterError> {
+        self.sub(id, SubType::OrderBook, true).await
+    }
+
+    pub async fn unsubscribe_order_book(&self, id: &InstrumentId) -> Result<(), AdapterError> {
+        self.sub(id, SubType::OrderBook, false).await
+    }
+
+    pub async fn subscribe_bars(&self, id: &InstrumentId, spec: BarSpec) -> Result<(), AdapterError> {
+        self.sub(id, bar_sub_type(spec)?, true).await
+    }
+
+    pub async fn unsubscribe_bars(&self, id: &InstrumentId, spec: BarSpec) -> Result<(), AdapterError> {
+        self.sub(id, bar_sub_type(spec)?, false).await
+    }
+
+    async fn sub(&self, id: &InstrumentId, sub_type: SubType, is_sub: bool) -> Result<(), AdapterError> {
+        let results = subscribe(&self.client, vec![id.to_futu()?], [sub_type], is_sub).await?;
+        Ok(check_sub_results(results)?)
+    }
+}
+
+fn bar_sub_type(spec: BarSpec) -> Result<SubType, AdapterError> {
+    spec.sub_type().ok_or_else(|| AdapterError::Unsupported(format!("bars of {}", spec)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nautilus_futu::client::mock::MockOpenD;
+    use nautilus_futu::config::FutuConfig;
+
+    #[tokio::test]
+    async fn test_subscriptions_against_mock() {
+        let mock = MockOpenD::start().await.unwrap();
+        let config = FutuConfig { port: mock.port(), enable_encryption: false, ..FutuConfig::default() };
+        let mut client = FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+        let data = FutuDataClient::new(Arc::new(client));
+
+        let id: InstrumentId = "00700.HKEX".parse().unwrap();
+        let day = BarSpec { step: 1, aggregation: BarAggregation::Day, price_type: PriceType::Last };
+        data.subscribe_quotes(&id).await.unwrap();
+        data.subscribe_trades(&id).await.unwrap();
+        data.subscribe_order_book(&id).await.unwrap();
+        data.subscribe_bars(&id, day).await.unwrap();
+        let subscriptions = data.client().subscriptions();
+        let security = (1, "00700".to_string());
+        for sub_type in [SubType::Basic, SubType::Ticker, SubType::OrderBook, SubType::KlDay] {
+            assert!(subscriptions.is_subscribed(&security, sub_type.into()));
+        }
+
+        data.unsubscribe_quotes(&id).await.unwrap();
+        data.unsubscribe_bars(&id, day).await.unwrap();
+        assert!(!subscriptions.is_subscribed(&security, SubType::Basic.into()));
+        assert!(!subscriptions.is_subscribed(&security, SubType::KlDay.into()));
+        assert!(subscriptions.is_subscribed(&security, SubType::Ticker.into()));
+        for spec in [BarSpec { step: 2, ..day }, "1-MINUTE-MID".parse().unwrap()] {
+            let err = data.subscribe_bars(&id, spec).await.unwrap_err();
+            assert!(matches!(err, AdapterError::Unsupported(_)));
+        }
+        let err = data.subscribe_quotes(&InstrumentId::new("VOD", "LSE")).await.unwrap_err();
+        assert!(matches!(err, AdapterError::UnknownVenue(_)));
+    }
+}
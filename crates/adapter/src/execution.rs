@@ -0,0 +1,189 @@
+//! Execution client: order routing for one account.
+//!
+//! Routes Nautilus order commands to Trd_PlaceOrder and Trd_ModifyOrder
+//! the way the Python execution client does: limit orders are Futu normal
+//! orders, market orders Futu market orders, and the order's venue selects
+//! the TrdSecMarket. Order and fill updates arrive as Trd_UpdateOrder and
+//! Trd_UpdateOrderFill pushes through `FutuClient::subscribe_push`.
+
+use std::sync::Arc;
+
+use nautilus_futu::client::FutuClient;
+use nautilus_futu::trade::account::TradeError;
+use nautilus_futu::trade::order::{self, CancelAllResult};
+use nautilus_futu::types::{ModifyOrderOp, OrderType, TrdSide};
+
+use crate::identifiers::InstrumentId;
+use crate::AdapterError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderSide {
+    Buy,
+    Sell,
+}
+
+impl OrderSide {
+    pub fn trd_side(self) -> TrdSide {
+        match self {
+            OrderSide::Buy => TrdSide::Buy,
+            OrderSide::Sell => TrdSide::Sell,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OrderKind {
+    Market,
+    Limit { price: f64 },
+}
+
+impl OrderKind {
+    pub fn order_type(self) -> OrderType {
+        match self {
+            OrderKind::Market => OrderType::Market,
+            OrderKind::Limit { .. } => OrderType::Normal,
+        }
+    }
+
+    pub fn price(self) -> Option<f64> {
+        match self {
+            OrderKind::Market => None,
+            OrderKind::Limit { price } => Some(price),
+        }
+    }
+}
+
+/// A new order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubmitOrder {
+    pub instrument_id: InstrumentId,
+    pub side: OrderSide,
+    pub kind: OrderKind,
+    pub quantity: f64,
+    /// Sent as the order's remark, so it shows in order pushes.
+    pub client_order_id: Option<String>,
+}
+
+/// Places, modifies and cancels the orders of one account.
+pub struct FutuExecutionClient {
+    client: Arc<FutuClient>,
+    trd_env: i32,
+    acc_id: u64,
+    trd_market: i32,
+}
+
+impl FutuExecutionClient {
+    pub fn new(client: Arc<FutuClient>, trd_env: impl Into<i32>, acc_id: u64, trd_market: impl Into<i32>) -> Self {
+        Self { client, trd_env: trd_env.into(), acc_id, trd_market: trd_market.into() }
+    }
+
+    pub fn client(&self) -> &Arc<FutuClient> {
+        &self.client
+    }
+
+    /// Place `command`. Returns the venue order ID.
+    pub async fn submit_order(&self, command: &SubmitOrder) -> Result<u64, AdapterError> {
+        let (_, code) = command.instrument_id.to_futu()?;
+        let sec_market = command.instrument_id.trd_sec_market()?;
+        let response = order::place_order(
+            &self.client,
+            self.trd_env,
+            self.acc_id,
+            self.trd_market,
+            command.side.trd_side(),
+            command.kind.order_type(),
+            code,
+            command.quantity,
+            command.kind.price(),
+            None,
+            Some(sec_market),
+            command.client_order_id.clone(),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        ).await?;
+        response
+            .s2c
+            .and_then(|s| s.order_id)
+            .ok_or_else(|| AdapterError::Trade(TradeError::Decode("no order ID in place order response".to_string())))
+    }
+
+    /// Change the quantity and/or price of an open order.
+    pub async fn modify_order(&self, order_id: u64, quantity: Option<f64>, price: Option<f64>) -> Result<(), AdapterError> {
+        order::modify_order(
+            &self.client, self.trd_env, self.acc_id, self.trd_market,
+            order_id, ModifyOrderOp::Normal, quantity, price, None,
+        ).await?;
+        Ok(())
+    }
+
+    pub async fn cancel_order(&self, order_id: u64) -> Result<(), AdapterError> {
+        order::modify_order(
+            &self.client, self.trd_env, self.acc_id, self.trd_market,
+            order_id, ModifyOrderOp::Cancel, None, None, None,
+        ).await?;
+        Ok(())
+    }
+
+    /// Cancel every open order of the account in its market.
+    pub async fn cancel_all_orders(&self) -> Result<CancelAllResult, AdapterError> {
+        Ok(order::cancel_all_orders(&self.client, self.trd_env, self.acc_id, self.trd_market, None).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nautilus_futu::client::mock::MockOpenD;
+    use nautilus_futu::config::FutuConfig;
+    use nautilus_futu::generated::trd_place_order;
+    use nautilus_futu::types::{TrdEnv, TrdMarket};
+    use prost::Message;
+
+    #[test]
+    fn test_order_mapping() {
+        assert_eq!(OrderSide::Sell.trd_side(), TrdSide::Sell);
+        assert_eq!(OrderKind::Market.order_type(), OrderType::Market);
+        assert_eq!(OrderKind::Market.price(), None);
+        let limit = OrderKind::Limit { price: 350.2 };
+        assert_eq!((limit.order_type(), limit.price()), (OrderType::Normal, Some(350.2)));
+    }
+
+    #[tokio::test]
+    async fn test_order_routing_against_mock() {
+        let mock = MockOpenD::start().await.unwrap();
+        let config = FutuConfig { port: mock.port(), enable_encryption: false, ..FutuConfig::default() };
+        let mut client = FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+        let exec = FutuExecutionClient::new(Arc::new(client), TrdEnv::Simulate, 1, TrdMarket::Hk);
+
+        let command = SubmitOrder {
+            instrument_id: "00700.HKEX".parse().unwrap(),
+            side: OrderSide::Buy,
+            kind: OrderKind::Limit { price: 350.0 },
+            quantity: 100.0,
+            client_order_id: Some("O-1".to_string()),
+        };
+        // Without an order ID the response is unusable
+        assert!(exec.submit_order(&command).await.is_err());
+
+        let placed = trd_place_order::Response {
+            ret_type: 0,
+            s2c: Some(trd_place_order::S2c { order_id: Some(42), ..Default::default() }),
+            ..Default::default()
+        };
+        mock.respond(2202, placed.encode_to_vec());
+        assert_eq!(exec.submit_order(&command).await.unwrap(), 42);
+        exec.modify_order(42, Some(200.0), None).await.unwrap();
+        exec.cancel_order(42).await.unwrap();
+        let kinds: Vec<&str> = exec.client().order_history().chain(42).iter().map(|a| a.kind.as_str()).collect();
+        assert_eq!(kinds, vec!["placed", "modified", "cancelled"]);
+        assert!(exec.cancel_all_orders().await.unwrap().for_all);
+
+        let unrouted = SubmitOrder { instrument_id: InstrumentId::new("VOD", "LSE"), ..command };
+        assert!(matches!(exec.submit_order(&unrouted).await, Err(AdapterError::UnknownVenue(_))));
+    }
+}
@@ -0,0 +1,113 @@
+//! Instrument IDs and their Futu markets.
+//!
+//! Nautilus names an instrument `SYMBOL.VENUE`, e.g. `00700.HKEX` or
+//! `AAPL.NYSE`. The venue maps to a QotMarket for quotes and a TrdSecMarket
+//! for orders, as in the Python adapter's `constants.py`.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::AdapterError;
+
+/// Venue of securities whose market has no venue of its own.
+pub const FUTU_VENUE: &str = "FUTU";
+
+/// (venue, QotMarket, TrdSecMarket) of the venues traded through Futu.
+const VENUES: [(&str, i32, i32); 6] = [
+    ("HKEX", 1, 1),
+    ("NYSE", 11, 2),
+    ("NASDAQ", 11, 2),
+    ("SSE", 21, 31),
+    ("SZSE", 22, 32),
+    ("SGX", 31, 41),
+];
+
+/// A Nautilus instrument ID.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct InstrumentId {
+    pub symbol: String,
+    pub venue: String,
+}
+
+impl InstrumentId {
+    pub fn new(symbol: impl Into<String>, venue: impl Into<String>) -> Self {
+        Self { symbol: symbol.into(), venue: venue.into() }
+    }
+
+    /// Instrument ID of a Futu security. US securities map to NYSE, the
+    /// HK futures market to HKEX and unknown markets to `FUTU_VENUE`.
+    pub fn from_futu(market: i32, code: &str) -> Self {
+        let venue = match market {
+            2 => "HKEX",
+            _ => VENUES.iter().find(|v| v.1 == market).map_or(FUTU_VENUE, |v| v.0),
+        };
+        Self::new(code, venue)
+    }
+
+    /// Futu security (QotMarket, code) of the instrument.
+    pub fn to_futu(&self) -> Result<(i32, String), AdapterError> {
+        let (_, market, _) = self.venue_entry()?;
+        Ok((market, self.symbol.clone()))
+    }
+
+    /// TrdSecMarket orders for the instrument are placed in.
+    pub fn trd_sec_market(&self) -> Result<i32, AdapterError> {
+        Ok(self.venue_entry()?.2)
+    }
+
+    fn venue_entry(&self) -> Result<(&'static str, i32, i32), AdapterError> {
+        VENUES
+            .iter()
+            .find(|v| v.0 == self.venue)
+            .copied()
+            .ok_or_else(|| AdapterError::UnknownVenue(self.venue.clone()))
+    }
+}
+
+impl fmt::Display for InstrumentId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.symbol, self.venue)
+    }
+}
+
+impl FromStr for InstrumentId {
+    type Err = AdapterError;
+
+    /// Parse `SYMBOL.VENUE`; the venue follows the last dot, so symbols may
+    /// contain dots (`BRK.B.NYSE`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.rsplit_once('.') {
+            Some((symbol, venue)) if !symbol.is_empty() && !venue.is_empty() => Ok(Self::new(symbol, venue)),
+            _ => Err(AdapterError::InvalidInstrumentId(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() {
+        let id: InstrumentId = "00700.HKEX".parse().unwrap();
+        assert_eq!(id, InstrumentId::new("00700", "HKEX"));
+        assert_eq!(id.to_string(), "00700.HKEX");
+        assert_eq!("BRK.B.NYSE".parse::<InstrumentId>().unwrap().symbol, "BRK.B");
+        assert!("00700".parse::<InstrumentId>().is_err());
+        assert!(".HKEX".parse::<InstrumentId>().is_err());
+    }
+
+    #[test]
+    fn test_futu_markets() {
+        assert_eq!(InstrumentId::from_futu(1, "00700").to_string(), "00700.HKEX");
+        assert_eq!(InstrumentId::from_futu(2, "HSImain").venue, "HKEX");
+        assert_eq!(InstrumentId::from_futu(11, "AAPL").to_string(), "AAPL.NYSE");
+        assert_eq!(InstrumentId::from_futu(81, "USDHKD").venue, FUTU_VENUE);
+
+        let id = InstrumentId::new("AAPL", "NASDAQ");
+        assert_eq!(id.to_futu().unwrap(), (11, "AAPL".to_string()));
+        assert_eq!(id.trd_sec_market().unwrap(), 2);
+        assert_eq!(InstrumentId::new("600519", "SSE").trd_sec_market().unwrap(), 31);
+        assert!(matches!(InstrumentId::new("X", "LSE").to_futu(), Err(AdapterError::UnknownVenue(v)) if v == "LSE"));
+    }
+}
@@ -0,0 +1,41 @@
+//! NautilusTrader adapter core for Futu OpenD, in Rust.
+//!
+//! The Python adapter (`nautilus_futu`) drives the core client through
+//! PyO3. This crate is the same adapter without Python, on top of
+//! `nautilus_futu::client::FutuClient`: instrument IDs, an instrument
+//! provider, a data client for subscriptions and an execution client for
+//! order routing.
+//!
+//! This crate does not implement NautilusTrader's `DataClient` and
+//! `ExecutionClient` traits: NautilusTrader's Rust crates are not
+//! dependencies of this workspace, and adding them is left to a separate
+//! change. Until then the clients carry the operations of those traits as
+//! inherent methods, bars use the core's `quote::bars::BarSpec`, and only
+//! the types the core has no counterpart for (`InstrumentId`, `OrderSide`,
+//! `OrderKind`) are defined here.
+
+pub mod identifiers;
+pub mod provider;
+pub mod data;
+pub mod execution;
+
+use nautilus_futu::quote::instrument::InstrumentError;
+use nautilus_futu::quote::QuoteError;
+use nautilus_futu::trade::account::TradeError;
+
+/// Errors of the adapter clients.
+#[derive(Debug, thiserror::Error)]
+pub enum AdapterError {
+    #[error("invalid instrument ID {0:?}, expected SYMBOL.VENUE")]
+    InvalidInstrumentId(String),
+    #[error("venue {0} is not traded through Futu")]
+    UnknownVenue(String),
+    #[error("unsupported: {0}")]
+    Unsupported(String),
+    #[error(transparent)]
+    Quote(#[from] QuoteError),
+    #[error(transparent)]
+    Trade(#[from] TradeError),
+    #[error(transparent)]
+    Instrument(#[from] InstrumentError),
+}
@@ -0,0 +1,77 @@
+//! Instrument provider.
+//!
+//! Loads instrument records by instrument ID through the core client's
+//! `InstrumentCache`, which joins static info, snapshot and tick tables.
+//! Futu has no instrument listing to load everything from, so instruments
+//! are loaded by ID.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use nautilus_futu::client::FutuClient;
+use nautilus_futu::quote::instrument::{Instrument, InstrumentCache};
+use parking_lot::Mutex;
+
+use crate::identifiers::InstrumentId;
+use crate::AdapterError;
+
+/// Instruments loaded by ID, kept until loaded again.
+pub struct FutuInstrumentProvider {
+    client: Arc<FutuClient>,
+    cache: InstrumentCache,
+    loaded: Mutex<BTreeMap<InstrumentId, Instrument>>,
+}
+
+impl FutuInstrumentProvider {
+    pub fn new(client: Arc<FutuClient>) -> Self {
+        Self { client, cache: InstrumentCache::default(), loaded: Mutex::new(BTreeMap::new()) }
+    }
+
+    /// Load (or reload) the instrument of `id`.
+    pub async fn load(&self, id: &InstrumentId) -> Result<Instrument, AdapterError> {
+        let security = id.to_futu()?;
+        let instrument = self.cache.get(&self.client, security, None, true).await?;
+        self.loaded.lock().insert(id.clone(), instrument.clone());
+        Ok(instrument)
+    }
+
+    /// Load the instruments of `ids`, stopping at the first failure.
+    pub async fn load_ids(&self, ids: &[InstrumentId]) -> Result<(), AdapterError> {
+        for id in ids {
+            self.load(id).await?;
+        }
+        Ok(())
+    }
+
+    /// A loaded instrument.
+    pub fn find(&self, id: &InstrumentId) -> Option<Instrument> {
+        self.loaded.lock().get(id).cloned()
+    }
+
+    /// IDs of the loaded instruments, sorted.
+    pub fn list_ids(&self) -> Vec<InstrumentId> {
+        self.loaded.lock().keys().cloned().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nautilus_futu::client::mock::MockOpenD;
+    use nautilus_futu::config::FutuConfig;
+
+    #[tokio::test]
+    async fn test_unrouted_venue_not_loaded() {
+        let mock = MockOpenD::start().await.unwrap();
+        let config = FutuConfig { port: mock.port(), enable_encryption: false, ..FutuConfig::default() };
+        let mut client = FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+        let provider = FutuInstrumentProvider::new(Arc::new(client));
+
+        let id = InstrumentId::new("VOD", "LSE");
+        assert!(matches!(provider.load(&id).await, Err(AdapterError::UnknownVenue(_))));
+        assert!(matches!(provider.load_ids(std::slice::from_ref(&id)).await, Err(AdapterError::UnknownVenue(_))));
+        assert!(provider.find(&id).is_none());
+        assert!(provider.list_ids().is_empty());
+    }
+}
@@ -0,0 +1,88 @@
+//! Messages per second a Python poller drains from a push channel, for the
+//! former tokio channel behind an async Mutex, entered with `block_on` on
+//! every poll, and the lock-free crossbeam channel `poll_push()` reads now.
+//!
+//! A producer task sends the pushes on the runtime while the consumer
+//! thread polls one message at a time (`poll_push()`) or in batches
+//! (`poll_push_batch()`).
+//!
+//! Run with `cargo bench --bench push_drain`.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::runtime::Runtime;
+use tokio::sync::{mpsc, Mutex};
+
+const MESSAGES: usize = 200_000;
+const BATCH: usize = 256;
+const TIMEOUT: Duration = Duration::from_millis(100);
+
+type Message = (u32, Vec<u8>);
+
+fn message(i: usize) -> Message {
+    (3011, vec![(i % 251) as u8; 64])
+}
+
+/// Messages per second drained through a tokio channel, locking the
+/// receiver and entering the runtime on every poll.
+fn drain_tokio(runtime: &Runtime, batch: usize) -> f64 {
+    let (tx, rx) = mpsc::unbounded_channel::<Message>();
+    let rx = Arc::new(Mutex::new(rx));
+    let start = Instant::now();
+    runtime.spawn(async move {
+        for i in 0..MESSAGES {
+            let _ = tx.send(message(i));
+        }
+    });
+    let mut received = 0;
+    while received < MESSAGES {
+        received += runtime.block_on(async {
+            let mut guard = rx.lock().await;
+            let mut count = 0;
+            if let Ok(Some(_)) = tokio::time::timeout(TIMEOUT, guard.recv()).await {
+                count += 1;
+                while count < batch && guard.try_recv().is_ok() {
+                    count += 1;
+                }
+            }
+            count
+        });
+    }
+    MESSAGES as f64 / start.elapsed().as_secs_f64()
+}
+
+/// Messages per second drained through a crossbeam channel without
+/// entering the runtime.
+fn drain_crossbeam(runtime: &Runtime, batch: usize) -> f64 {
+    let (tx, rx) = crossbeam_channel::unbounded::<Message>();
+    let start = Instant::now();
+    runtime.spawn(async move {
+        for i in 0..MESSAGES {
+            let _ = tx.send(message(i));
+        }
+    });
+    let mut received = 0;
+    while received < MESSAGES {
+        if rx.recv_timeout(TIMEOUT).is_ok() {
+            received += 1 + rx.try_iter().take(batch - 1).count();
+        }
+    }
+    MESSAGES as f64 / start.elapsed().as_secs_f64()
+}
+
+fn main() {
+    let runtime = tokio::runtime::Builder::new_multi_thread().worker_threads(2).enable_all().build().unwrap();
+    println!("{} pushes drained from another thread", MESSAGES);
+    for (name, batch) in [("poll_push", 1), ("poll_push_batch", BATCH)] {
+        let tokio = drain_tokio(&runtime, batch);
+        let crossbeam = drain_crossbeam(&runtime, batch);
+        println!(
+            "{:<16} tokio+Mutex msgs/s={:>11.0} crossbeam msgs/s={:>11.0} speedup={:.2}x",
+            name,
+            tokio,
+            crossbeam,
+            crossbeam / tokio,
+        );
+    }
+}
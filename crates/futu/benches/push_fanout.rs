@@ -0,0 +1,100 @@
+//! Allocation counts and timing for the push pipeline: decode frames from the
+//! read buffer, then fan each one out to several subscribers.
+//!
+//! Run with `cargo bench --bench push_fanout`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use bytes::{Bytes, BytesMut};
+use tokio_util::codec::{Decoder, Encoder};
+
+use nautilus_futu::client::dispatcher::Dispatcher;
+use nautilus_futu::protocol::{FutuCodec, FutuMessage, ProtoFmt};
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+const MESSAGES: usize = 20_000;
+/// Typical ticker push size.
+const BODY_LEN: usize = 320;
+/// Chunk size of socket reads fed to the codec.
+const READ_CHUNK: usize = 8 * 1024;
+
+/// Encoded stream of ticker pushes.
+fn wire_stream() -> Vec<u8> {
+    let mut codec = FutuCodec;
+    let mut buf = BytesMut::new();
+    let body = Bytes::from(vec![0x5a; BODY_LEN]);
+    for serial_no in 0..MESSAGES as u32 {
+        let msg = FutuMessage { proto_id: 3011, serial_no, body: body.clone(), proto_fmt: ProtoFmt::Protobuf };
+        codec.encode(msg, &mut buf).unwrap();
+    }
+    buf.to_vec()
+}
+
+async fn run(wire: &[u8], subscribers: usize) -> (usize, f64) {
+    let dispatcher = Dispatcher::new();
+    let mut receivers = Vec::new();
+    for _ in 0..subscribers {
+        receivers.push(dispatcher.register_push(3011).await);
+    }
+    let mut codec = FutuCodec;
+    let mut read_buf = BytesMut::with_capacity(READ_CHUNK);
+
+    let start_allocs = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let mut received = 0;
+    for chunk in wire.chunks(READ_CHUNK) {
+        read_buf.extend_from_slice(chunk);
+        while let Some(msg) = codec.decode(&mut read_buf).unwrap() {
+            dispatcher.dispatch(msg).await;
+        }
+        // Drain like the consumers would, so bodies are released
+        for rx in &mut receivers {
+            while let Ok(msg) = rx.try_recv() {
+                received += msg.body.len();
+            }
+        }
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    let allocs = ALLOCATIONS.load(Ordering::Relaxed) - start_allocs;
+    assert_eq!(received, MESSAGES * BODY_LEN * subscribers);
+    (allocs, elapsed)
+}
+
+fn main() {
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    let wire = wire_stream();
+    println!("{} pushes of {} bytes, read in {} byte chunks", MESSAGES, BODY_LEN, READ_CHUNK);
+    for subscribers in [1, 2, 4, 8] {
+        let (allocs, elapsed) = runtime.block_on(run(&wire, subscribers));
+        println!(
+            "subscribers={:<2} allocations/msg={:>6.3} ns/msg={:>7.1}",
+            subscribers,
+            allocs as f64 / MESSAGES as f64,
+            elapsed * 1e9 / MESSAGES as f64,
+        );
+    }
+}
@@ -0,0 +1,137 @@
+//! Requests per second written by concurrent tasks sending bursts of
+//! Qot_GetSecuritySnapshot requests, for the former send path, where every
+//! sender locked the cipher and then the writer and flushed its own message,
+//! and `FutuConnection::send`, which queues messages for a writer task that
+//! flushes once per batch.
+//!
+//! Both paths encrypt with AES and write to a loopback peer that reads and
+//! discards the frames.
+//!
+//! Run with `cargo bench --bench request_burst`.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use bytes::Bytes;
+use futures::{SinkExt, StreamExt};
+use prost::Message;
+use tokio::net::tcp::OwnedWriteHalf;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::runtime::Runtime;
+use tokio::sync::Mutex;
+use tokio_util::codec::{FramedRead, FramedWrite};
+
+use nautilus_futu::client::connection::FutuConnection;
+use nautilus_futu::config::FutuConfig;
+use nautilus_futu::generated::{qot_common, qot_get_security_snapshot};
+use nautilus_futu::protocol::{AesEcbCipher, FutuCodec, FutuMessage, ProtoFmt};
+
+const PROTO_QOT_GET_SECURITY_SNAPSHOT: u32 = 3203;
+const REQUESTS_PER_TASK: usize = 2_000;
+const KEY: [u8; 16] = *b"0123456789abcdef";
+
+/// A snapshot request for 20 HK securities.
+fn snapshot_body() -> Vec<u8> {
+    let security_list = (0..20)
+        .map(|i| qot_common::Security { market: 1, code: format!("{:05}", 700 + i) })
+        .collect();
+    let c2s = qot_get_security_snapshot::C2s { security_list };
+    qot_get_security_snapshot::Request { c2s }.encode_to_vec()
+}
+
+/// A loopback peer that reads frames until the client hangs up.
+async fn sink() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let mut frames = FramedRead::new(stream, FutuCodec);
+                while let Some(Ok(_)) = frames.next().await {}
+            });
+        }
+    });
+    port
+}
+
+/// The former send path: cipher and writer behind async mutexes, one flush
+/// per message.
+struct LockedWriter {
+    cipher: Mutex<AesEcbCipher>,
+    writer: Mutex<FramedWrite<OwnedWriteHalf, FutuCodec>>,
+}
+
+impl LockedWriter {
+    async fn send(&self, serial_no: u32, body: &[u8]) {
+        let cipher = self.cipher.lock().await;
+        let body = Bytes::from(cipher.encrypt(body));
+        drop(cipher);
+        let msg = FutuMessage { proto_id: PROTO_QOT_GET_SECURITY_SNAPSHOT, serial_no, body, proto_fmt: ProtoFmt::Protobuf };
+        self.writer.lock().await.send(msg).await.unwrap();
+    }
+}
+
+/// Requests per second sent by `tasks` tasks through locked writers.
+fn burst_locked(runtime: &Runtime, tasks: usize, body: &Arc<Vec<u8>>) -> f64 {
+    runtime.block_on(async {
+        let port = sink().await;
+        let (_, write_half) = TcpStream::connect(("127.0.0.1", port)).await.unwrap().into_split();
+        let writer = Arc::new(LockedWriter {
+            cipher: Mutex::new(AesEcbCipher::new(&KEY)),
+            writer: Mutex::new(FramedWrite::new(write_half, FutuCodec)),
+        });
+        let start = Instant::now();
+        let handles: Vec<_> = (0..tasks)
+            .map(|task| {
+                let (writer, body) = (Arc::clone(&writer), Arc::clone(body));
+                tokio::spawn(async move {
+                    for i in 0..REQUESTS_PER_TASK {
+                        writer.send((task * REQUESTS_PER_TASK + i) as u32, &body).await;
+                    }
+                })
+            })
+            .collect();
+        futures::future::join_all(handles).await;
+        (tasks * REQUESTS_PER_TASK) as f64 / start.elapsed().as_secs_f64()
+    })
+}
+
+/// Requests per second sent by `tasks` tasks through `FutuConnection`.
+fn burst_connection(runtime: &Runtime, tasks: usize, body: &Arc<Vec<u8>>) -> f64 {
+    runtime.block_on(async {
+        let port = sink().await;
+        let config = FutuConfig { port, ..FutuConfig::default() };
+        let conn = Arc::new(FutuConnection::connect(config).await.unwrap());
+        conn.set_cipher(&KEY).await;
+        let start = Instant::now();
+        let handles: Vec<_> = (0..tasks)
+            .map(|_| {
+                let (conn, body) = (Arc::clone(&conn), Arc::clone(body));
+                tokio::spawn(async move {
+                    for _ in 0..REQUESTS_PER_TASK {
+                        conn.send(PROTO_QOT_GET_SECURITY_SNAPSHOT, &body).await.unwrap();
+                    }
+                })
+            })
+            .collect();
+        futures::future::join_all(handles).await;
+        (tasks * REQUESTS_PER_TASK) as f64 / start.elapsed().as_secs_f64()
+    })
+}
+
+fn main() {
+    let runtime = tokio::runtime::Builder::new_multi_thread().worker_threads(4).enable_all().build().unwrap();
+    let body = Arc::new(snapshot_body());
+    println!("{} snapshot requests per task, {} byte bodies", REQUESTS_PER_TASK, body.len());
+    for tasks in [1, 8, 32] {
+        let locked = burst_locked(&runtime, tasks, &body);
+        let queued = burst_connection(&runtime, tasks, &body);
+        println!(
+            "tasks={:<3} locked writer req/s={:>10.0} writer task req/s={:>10.0} speedup={:.2}x",
+            tasks,
+            locked,
+            queued,
+            queued / locked,
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! Print a packet capture written by `FutuConnection::start_capture`.
+//!
+//! Usage: futu_capture <file> [--hex] [--proto <proto_id>]
+
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use nautilus_futu::client::capture::CaptureReader;
+
+fn main() -> ExitCode {
+    let mut path: Option<PathBuf> = None;
+    let mut hex = false;
+    let mut proto: Option<u32> = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--hex" => hex = true,
+            "--proto" => match args.next().and_then(|p| p.parse().ok()) {
+                Some(p) => proto = Some(p),
+                None => return usage(),
+            },
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(PathBuf::from(arg)),
+            _ => return usage(),
+        }
+    }
+    let Some(path) = path else { return usage() };
+
+    let reader = match CaptureReader::open(&path) {
+        Ok(reader) => reader,
+        Err(e) => {
+            eprintln!("{}: {}", path.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+    for packet in reader {
+        let packet = match packet {
+            Ok(packet) => packet,
+            Err(e) => {
+                eprintln!("{}: {}", path.display(), e);
+                return ExitCode::FAILURE;
+            }
+        };
+        if proto.is_some_and(|p| p != packet.proto_id) {
+            continue;
+        }
+        println!(
+            "{}.{:06} {} {:<5} proto_id={} serial_no={} body_len={}{}",
+            packet.timestamp_us / 1_000_000,
+            packet.timestamp_us % 1_000_000,
+            packet.direction.as_str(),
+            packet.layer.as_str(),
+            packet.proto_id,
+            packet.serial_no,
+            packet.body.len(),
+            if packet.redacted { " redacted" } else { "" },
+        );
+        if hex && !packet.body.is_empty() {
+            for line in packet.body.chunks(32) {
+                let bytes: Vec<String> = line.iter().map(|b| format!("{:02x}", b)).collect();
+                println!("    {}", bytes.join(" "));
+            }
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn usage() -> ExitCode {
+    eprintln!("usage: futu_capture <file> [--hex] [--proto <proto_id>]");
+    ExitCode::from(2)
+}
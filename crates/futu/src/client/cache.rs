@@ -0,0 +1,290 @@
+//! Read-through cache of request responses.
+//!
+//! Reference data such as static info, plate sets and trading calendars
+//! changes rarely but is requested often, and every request counts against
+//! OpenD's frequency limits. `ResponseCache` keeps successful responses keyed
+//! by proto_id and request body for a TTL configured per protocol. Protocols
+//! without a TTL (snapshots, quotes, all trading protocols) are never cached.
+//! The cache is off unless enabled in `ResponseCacheConfig`.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+use parking_lot::Mutex;
+
+use crate::protocol::FutuMessage;
+
+const DAY: Duration = Duration::from_secs(86_400);
+const HOUR: Duration = Duration::from_secs(3_600);
+
+/// Which responses are cached and for how long.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponseCacheConfig {
+    pub enabled: bool,
+    /// TTL per proto_id; protocols not listed are not cached.
+    pub ttls: HashMap<u32, Duration>,
+    /// Responses kept at most; expired and then oldest ones are evicted.
+    pub max_entries: usize,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        let ttls = HashMap::from([
+            (3202, DAY),  // Qot_GetStaticInfo
+            (3204, HOUR), // Qot_GetPlateSet
+            (3205, HOUR), // Qot_GetPlateSecurity
+            (3206, HOUR), // Qot_GetReference
+            (3207, HOUR), // Qot_GetOwnerPlate
+            (3216, HOUR), // Qot_GetCodeChange
+            (3218, DAY),  // Qot_GetFutureInfo
+            (3219, DAY),  // Qot_RequestTradeDate
+            (3224, HOUR), // Qot_GetOptionExpirationDate
+        ]);
+        Self { enabled: false, ttls, max_entries: 1024 }
+    }
+}
+
+impl ResponseCacheConfig {
+    /// TTL of `proto_id`, or None if its responses are not cached.
+    pub fn ttl(&self, proto_id: u32) -> Option<Duration> {
+        if !self.enabled {
+            return None;
+        }
+        self.ttls.get(&proto_id).copied().filter(|ttl| !ttl.is_zero())
+    }
+}
+
+/// Hits and misses of one protocol.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProtoCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Responses currently cached.
+    pub entries: usize,
+}
+
+/// Counters of the cache since it was created.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    /// Entries dropped to stay within `max_entries`.
+    pub evictions: u64,
+    /// Entries dropped by `invalidate`.
+    pub invalidations: u64,
+    pub entries: usize,
+    pub per_proto: BTreeMap<u32, ProtoCacheStats>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    /// Request body, compared on lookup so hash collisions never mix up responses.
+    request: Vec<u8>,
+    response: FutuMessage,
+    stored_at: Instant,
+    expires_at: Instant,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<(u32, u64), Entry>,
+    stats: CacheStats,
+}
+
+/// Thread-safe response cache; see the module docs.
+#[derive(Debug)]
+pub struct ResponseCache {
+    config: Mutex<ResponseCacheConfig>,
+    state: Mutex<CacheState>,
+}
+
+impl Default for ResponseCache {
+    fn default() -> Self {
+        Self::new(ResponseCacheConfig::default())
+    }
+}
+
+impl ResponseCache {
+    pub fn new(config: ResponseCacheConfig) -> Self {
+        Self { config: Mutex::new(config), state: Mutex::new(CacheState::default()) }
+    }
+
+    pub fn config(&self) -> ResponseCacheConfig {
+        self.config.lock().clone()
+    }
+
+    /// Replace the configuration. Cached responses of protocols that lose
+    /// their TTL, or of a disabled cache, are dropped.
+    pub fn set_config(&self, config: ResponseCacheConfig) {
+        let mut state = self.state.lock();
+        state.entries.retain(|(proto_id, _), _| config.ttl(*proto_id).is_some());
+        *self.config.lock() = config;
+    }
+
+    /// Cached response to `body` for `proto_id`, if there is an unexpired one.
+    /// Counts a hit or miss for protocols that are cached.
+    pub fn get(&self, proto_id: u32, body: &[u8], now: Instant) -> Option<FutuMessage> {
+        self.config.lock().ttl(proto_id)?;
+        let key = (proto_id, body_hash(body));
+        let mut state = self.state.lock();
+        let hit = state
+            .entries
+            .get(&key)
+            .filter(|e| e.request == body && now < e.expires_at)
+            .map(|e| e.response.clone());
+        let stats = state.stats.per_proto.entry(proto_id).or_default();
+        if hit.is_some() {
+            stats.hits += 1;
+            state.stats.hits += 1;
+        } else {
+            stats.misses += 1;
+            state.stats.misses += 1;
+        }
+        hit
+    }
+
+    /// Store the response to `body` if `proto_id` is cached and the
+    /// response succeeded; failures are never cached.
+    pub fn insert(&self, proto_id: u32, body: &[u8], response: &FutuMessage, now: Instant) {
+        let (ttl, max_entries) = {
+            let config = self.config.lock();
+            match config.ttl(proto_id) {
+                Some(ttl) => (ttl, config.max_entries),
+                None => return,
+            }
+        };
+        if !super::flow_control::is_success(&response.body) || max_entries == 0 {
+            return;
+        }
+        let mut state = self.state.lock();
+        let key = (proto_id, body_hash(body));
+        if !state.entries.contains_key(&key) && state.entries.len() >= max_entries {
+            state.evict(now, max_entries - 1);
+        }
+        state.entries.insert(key, Entry {
+            request: body.to_vec(),
+            response: response.clone(),
+            stored_at: now,
+            expires_at: now + ttl,
+        });
+    }
+
+    /// Drop the cached responses of `proto_id`, or all of them. Returns the
+    /// number dropped.
+    pub fn invalidate(&self, proto_id: Option<u32>) -> usize {
+        let mut state = self.state.lock();
+        let before = state.entries.len();
+        state.entries.retain(|(p, _), _| proto_id.is_some_and(|id| id != *p));
+        let removed = before - state.entries.len();
+        state.stats.invalidations += removed as u64;
+        removed
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let state = self.state.lock();
+        let mut stats = state.stats.clone();
+        stats.entries = state.entries.len();
+        for p in stats.per_proto.values_mut() {
+            p.entries = 0;
+        }
+        for (proto_id, _) in state.entries.keys() {
+            stats.per_proto.entry(*proto_id).or_default().entries += 1;
+        }
+        stats
+    }
+}
+
+impl CacheState {
+    /// Drop expired entries, then the oldest ones, until at most `keep` remain.
+    fn evict(&mut self, now: Instant, keep: usize) {
+        let before = self.entries.len();
+        self.entries.retain(|_, e| now < e.expires_at);
+        if self.entries.len() > keep {
+            let mut by_age: Vec<((u32, u64), Instant)> =
+                self.entries.iter().map(|(k, e)| (*k, e.stored_at)).collect();
+            by_age.sort_by_key(|(_, stored_at)| *stored_at);
+            let excess = self.entries.len() - keep;
+            for (key, _) in by_age.into_iter().take(excess) {
+                self.entries.remove(&key);
+            }
+        }
+        self.stats.evictions += (before - self.entries.len()) as u64;
+    }
+}
+
+fn body_hash(body: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    body.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use crate::protocol::ProtoFmt;
+
+    const STATIC_INFO: u32 = 3202;
+    const SNAPSHOT: u32 = 3203;
+
+    fn message(proto_id: u32, body: &'static [u8]) -> FutuMessage {
+        FutuMessage { proto_id, serial_no: 1, body: Bytes::from_static(body), proto_fmt: ProtoFmt::Protobuf }
+    }
+
+    fn enabled() -> ResponseCache {
+        ResponseCache::new(ResponseCacheConfig { enabled: true, ..Default::default() })
+    }
+
+    #[test]
+    fn test_hits_until_ttl() {
+        let cache = enabled();
+        let now = Instant::now();
+        assert!(cache.get(STATIC_INFO, b"req", now).is_none());
+        cache.insert(STATIC_INFO, b"req", &message(STATIC_INFO, b"\x08\x00ok"), now);
+
+        let hit = cache.get(STATIC_INFO, b"req", now + HOUR).unwrap();
+        assert_eq!(&hit.body[..], b"\x08\x00ok");
+        assert!(cache.get(STATIC_INFO, b"other", now).is_none());
+        assert!(cache.get(STATIC_INFO, b"req", now + DAY).is_none());
+
+        let stats = cache.stats();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 3, 1));
+        assert_eq!(stats.per_proto[&STATIC_INFO].hits, 1);
+    }
+
+    #[test]
+    fn test_uncached_responses() {
+        let now = Instant::now();
+        let cache = enabled();
+        // No TTL for snapshots
+        cache.insert(SNAPSHOT, b"req", &message(SNAPSHOT, b"\x08\x00"), now);
+        assert!(cache.get(SNAPSHOT, b"req", now).is_none());
+        // Failed responses (ret_type -1)
+        cache.insert(STATIC_INFO, b"req", &message(STATIC_INFO, b"\x08\xff\xff\xff\xff\x0f"), now);
+        assert!(cache.get(STATIC_INFO, b"req", now).is_none());
+        // Disabled cache
+        let cache = ResponseCache::default();
+        cache.insert(STATIC_INFO, b"req", &message(STATIC_INFO, b"\x08\x00"), now);
+        assert!(cache.get(STATIC_INFO, b"req", now).is_none());
+        assert_eq!(cache.stats(), CacheStats::default());
+    }
+
+    #[test]
+    fn test_eviction_and_invalidation() {
+        let cache = ResponseCache::new(ResponseCacheConfig { enabled: true, max_entries: 2, ..Default::default() });
+        let now = Instant::now();
+        let ok = message(STATIC_INFO, b"\x08\x00");
+        cache.insert(STATIC_INFO, b"a", &ok, now);
+        cache.insert(STATIC_INFO, b"b", &ok, now + Duration::from_secs(1));
+        cache.insert(3204, b"c", &ok, now + Duration::from_secs(2));
+        assert!(cache.get(STATIC_INFO, b"a", now).is_none());
+        assert!(cache.get(STATIC_INFO, b"b", now).is_some());
+        assert_eq!(cache.stats().evictions, 1);
+
+        assert_eq!(cache.invalidate(Some(3204)), 1);
+        assert_eq!(cache.invalidate(None), 1);
+        assert_eq!(cache.stats().invalidations, 2);
+        assert_eq!(cache.stats().entries, 0);
+    }
+}
@@ -0,0 +1,277 @@
+//! Capture of raw protocol packets to a file, for offline analysis.
+//!
+//! Unlike the message trace, which keeps summaries of recent messages, a
+//! capture records every packet body: as sent over the wire (AES-encrypted
+//! when encryption is on) and as the application sees it (decrypted), with
+//! direction and timestamp. Bodies of InitConnect, which carries the
+//! connection's AES key, and UnlockTrade, which carries the trade password
+//! hash, are never written, so a capture can be shared without exposing
+//! credentials. `CaptureReader` reads a capture back; the `futu_capture`
+//! binary prints one.
+//!
+//! File layout, all integers little-endian:
+//!
+//! ```text
+//! header: b"FUTUCAP" version:u8
+//! record: direction:u8 layer:u8 flags:u8 timestamp_us:u64
+//!         proto_id:u32 serial_no:u32 body_len:u32 body
+//! ```
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use parking_lot::Mutex;
+
+use super::trace::TraceDirection;
+
+const MAGIC: &[u8; 7] = b"FUTUCAP";
+const VERSION: u8 = 1;
+
+/// Body dropped because it holds credentials.
+const FLAG_REDACTED: u8 = 0x01;
+
+/// InitConnect (AES key) and Trd_UnlockTrade (password hash).
+const REDACTED_PROTOS: [u32; 2] = [1001, 2005];
+
+/// Which form of a body a record holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaptureLayer {
+    /// As framed on the TCP stream, encrypted if the connection is.
+    Wire,
+    /// Decrypted body handed to or received from the application.
+    Plain,
+}
+
+impl CaptureLayer {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CaptureLayer::Wire => "wire",
+            CaptureLayer::Plain => "plain",
+        }
+    }
+}
+
+/// One captured packet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapturedPacket {
+    pub direction: TraceDirection,
+    pub layer: CaptureLayer,
+    /// Whether the body was left out because it holds credentials.
+    pub redacted: bool,
+    /// Unix timestamp in microseconds.
+    pub timestamp_us: u64,
+    pub proto_id: u32,
+    pub serial_no: u32,
+    pub body: Vec<u8>,
+}
+
+/// Appends packets to a capture file. Writes are buffered; a write error
+/// stops the capture and is logged once.
+pub struct PacketCapture {
+    writer: Mutex<Option<BufWriter<File>>>,
+    include_wire: bool,
+    packets: std::sync::atomic::AtomicU64,
+}
+
+impl PacketCapture {
+    /// Create (or truncate) the capture file at `path`. `include_wire`
+    /// also records the encrypted wire form of each body.
+    pub fn create(path: &Path, include_wire: bool) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        Ok(Self {
+            writer: Mutex::new(Some(writer)),
+            include_wire,
+            packets: std::sync::atomic::AtomicU64::new(0),
+        })
+    }
+
+    /// Record a packet body. Wire bodies are skipped unless enabled.
+    pub fn record(
+        &self,
+        direction: TraceDirection,
+        layer: CaptureLayer,
+        proto_id: u32,
+        serial_no: u32,
+        body: &[u8],
+        timestamp: f64,
+    ) {
+        if layer == CaptureLayer::Wire && !self.include_wire {
+            return;
+        }
+        let redacted = REDACTED_PROTOS.contains(&proto_id);
+        let body = if redacted { &[][..] } else { body };
+        let mut guard = self.writer.lock();
+        let Some(writer) = guard.as_mut() else {
+            return;
+        };
+        let result = write_record(writer, direction, layer, redacted, proto_id, serial_no, body, timestamp);
+        match result {
+            Ok(()) => {
+                self.packets.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+            }
+            Err(e) => {
+                tracing::error!("Packet capture stopped, write failed: {}", e);
+                *guard = None;
+            }
+        }
+    }
+
+    /// Packets written so far.
+    pub fn packets(&self) -> u64 {
+        self.packets.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Flush buffered packets to the file.
+    pub fn flush(&self) -> io::Result<()> {
+        match self.writer.lock().as_mut() {
+            Some(writer) => writer.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for PacketCapture {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            tracing::error!("Failed to flush packet capture: {}", e);
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn write_record(
+    w: &mut impl Write,
+    direction: TraceDirection,
+    layer: CaptureLayer,
+    redacted: bool,
+    proto_id: u32,
+    serial_no: u32,
+    body: &[u8],
+    timestamp: f64,
+) -> io::Result<()> {
+    let direction = match direction {
+        TraceDirection::Send => 0u8,
+        TraceDirection::Recv => 1,
+    };
+    let layer = match layer {
+        CaptureLayer::Wire => 0u8,
+        CaptureLayer::Plain => 1,
+    };
+    let flags = if redacted { FLAG_REDACTED } else { 0 };
+    w.write_all(&[direction, layer, flags])?;
+    w.write_all(&((timestamp * 1e6) as u64).to_le_bytes())?;
+    w.write_all(&proto_id.to_le_bytes())?;
+    w.write_all(&serial_no.to_le_bytes())?;
+    w.write_all(&(body.len() as u32).to_le_bytes())?;
+    w.write_all(body)
+}
+
+/// Reads the packets of a capture file in order.
+pub struct CaptureReader<R> {
+    reader: R,
+}
+
+impl CaptureReader<BufReader<File>> {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read> CaptureReader<R> {
+    /// Check the header of `reader` and read records after it.
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        if &header[..7] != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a packet capture file"));
+        }
+        if header[7] != VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported capture version {}", header[7]),
+            ));
+        }
+        Ok(Self { reader })
+    }
+
+    fn read_packet(&mut self) -> io::Result<Option<CapturedPacket>> {
+        let mut fixed = [0u8; 23];
+        match self.reader.read_exact(&mut fixed[..1]) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+        self.reader.read_exact(&mut fixed[1..])?;
+        let u32_at = |i: usize| u32::from_le_bytes([fixed[i], fixed[i + 1], fixed[i + 2], fixed[i + 3]]);
+        let invalid = |what: &str| io::Error::new(io::ErrorKind::InvalidData, format!("invalid {}", what));
+        let direction = match fixed[0] {
+            0 => TraceDirection::Send,
+            1 => TraceDirection::Recv,
+            _ => return Err(invalid("direction")),
+        };
+        let layer = match fixed[1] {
+            0 => CaptureLayer::Wire,
+            1 => CaptureLayer::Plain,
+            _ => return Err(invalid("layer")),
+        };
+        let mut timestamp = [0u8; 8];
+        timestamp.copy_from_slice(&fixed[3..11]);
+        let mut body = vec![0u8; u32_at(19) as usize];
+        self.reader.read_exact(&mut body)?;
+        Ok(Some(CapturedPacket {
+            direction,
+            layer,
+            redacted: fixed[2] & FLAG_REDACTED != 0,
+            timestamp_us: u64::from_le_bytes(timestamp),
+            proto_id: u32_at(11),
+            serial_no: u32_at(15),
+            body,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for CaptureReader<R> {
+    type Item = io::Result<CapturedPacket>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_packet().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_roundtrip_and_redaction() {
+        let path = std::env::temp_dir().join(format!("futu_capture_test_{}.cap", std::process::id()));
+        let capture = PacketCapture::create(&path, false).unwrap();
+        capture.record(TraceDirection::Send, CaptureLayer::Wire, 3004, 7, b"encrypted", 1.5);
+        capture.record(TraceDirection::Send, CaptureLayer::Plain, 3004, 7, b"plain", 1.5);
+        capture.record(TraceDirection::Recv, CaptureLayer::Plain, 2005, 8, b"pwd_md5", 2.0);
+        assert_eq!(capture.packets(), 2);
+        drop(capture);
+
+        let packets: Vec<CapturedPacket> = CaptureReader::open(&path).unwrap().map(Result::unwrap).collect();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(packets.len(), 2);
+        assert_eq!(packets[0].layer, CaptureLayer::Plain);
+        assert_eq!(packets[0].timestamp_us, 1_500_000);
+        assert_eq!((packets[0].proto_id, packets[0].serial_no), (3004, 7));
+        assert_eq!(packets[0].body, b"plain");
+        assert_eq!(packets[1].direction, TraceDirection::Recv);
+        assert!(packets[1].redacted);
+        assert!(packets[1].body.is_empty());
+    }
+
+    #[test]
+    fn test_rejects_other_files() {
+        assert!(CaptureReader::new(&b"NOTACAPTURE"[..]).is_err());
+        let mut truncated = b"FUTUCAP\x01".to_vec();
+        truncated.extend_from_slice(&[0, 1, 0]);
+        let mut reader = CaptureReader::new(truncated.as_slice()).unwrap();
+        assert!(reader.next().unwrap().is_err());
+    }
+}
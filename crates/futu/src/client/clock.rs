@@ -0,0 +1,174 @@
+//! Time source of the client's time-dependent subsystems.
+//!
+//! Keepalive, the order throttle, request retries, bar aggregation, the
+//! staleness monitor and the suspension and instrument caches read time and
+//! sleep through a `Clock` instead of calling `Instant::now` and
+//! `tokio::time::sleep` directly. `SystemClock` is the real time used by
+//! default; `MockClock` only moves when `advance` is called, so tests can
+//! step through timers and TTLs without waiting.
+
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use parking_lot::Mutex;
+use tokio::sync::oneshot;
+
+pub type Sleep = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// Monotonic and wall-clock time, and sleeping.
+pub trait Clock: Debug + Send + Sync + 'static {
+    /// Monotonic time, for durations and TTLs.
+    fn now(&self) -> Instant;
+
+    /// Wall-clock time as a Unix timestamp in seconds.
+    fn unix_time(&self) -> f64;
+
+    /// Resolve once `duration` has passed on this clock.
+    fn sleep(&self, duration: Duration) -> Sleep;
+
+    /// Resolve once this clock reaches `deadline`.
+    fn sleep_until(&self, deadline: Instant) -> Sleep {
+        self.sleep(deadline.saturating_duration_since(self.now()))
+    }
+}
+
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The system clock and tokio timers.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn unix_time(&self) -> f64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64()
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// The default clock, `SystemClock`.
+pub fn system_clock() -> SharedClock {
+    Arc::new(SystemClock)
+}
+
+#[derive(Debug, Default)]
+struct MockState {
+    elapsed: Duration,
+    /// Wake-up time (as elapsed time) and waker of each pending sleep.
+    sleepers: Vec<(Duration, oneshot::Sender<()>)>,
+}
+
+/// A clock that stands still until advanced. Sleeps resolve when
+/// `advance` moves the clock past their deadline.
+#[derive(Debug)]
+pub struct MockClock {
+    start: Instant,
+    unix_start: f64,
+    state: Mutex<MockState>,
+}
+
+impl MockClock {
+    /// A clock reading `unix_start` as wall-clock time.
+    pub fn new(unix_start: f64) -> Self {
+        Self { start: Instant::now(), unix_start, state: Mutex::new(MockState::default()) }
+    }
+
+    /// Move the clock forward, waking the sleeps that are due.
+    pub fn advance(&self, duration: Duration) {
+        let mut state = self.state.lock();
+        state.elapsed += duration;
+        let elapsed = state.elapsed;
+        let (due, pending) = std::mem::take(&mut state.sleepers)
+            .into_iter()
+            .partition(|(wake, _)| *wake <= elapsed);
+        state.sleepers = pending;
+        drop(state);
+        for (_, waker) in due {
+            let _ = waker.send(());
+        }
+    }
+
+    /// Time advanced since creation.
+    pub fn elapsed(&self) -> Duration {
+        self.state.lock().elapsed
+    }
+
+    /// Sleeps still waiting, not counting dropped ones.
+    pub fn sleepers(&self) -> usize {
+        let mut state = self.state.lock();
+        state.sleepers.retain(|(_, waker)| !waker.is_closed());
+        state.sleepers.len()
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.start + self.state.lock().elapsed
+    }
+
+    fn unix_time(&self) -> f64 {
+        self.unix_start + self.state.lock().elapsed.as_secs_f64()
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        if duration.is_zero() {
+            return Box::pin(std::future::ready(()));
+        }
+        let (waker, woken) = oneshot::channel();
+        let mut state = self.state.lock();
+        let wake = state.elapsed + duration;
+        state.sleepers.push((wake, waker));
+        Box::pin(async move {
+            let _ = woken.await;
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mock_clock_sleeps() {
+        let clock = Arc::new(MockClock::new(1_700_000_000.0));
+        let start = clock.now();
+        let c = Arc::clone(&clock);
+        let sleeper = tokio::spawn(async move {
+            c.sleep(Duration::from_secs(10)).await;
+            c.unix_time()
+        });
+        tokio::task::yield_now().await;
+        assert_eq!(clock.sleepers(), 1);
+
+        clock.advance(Duration::from_secs(9));
+        tokio::task::yield_now().await;
+        assert!(!sleeper.is_finished());
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(sleeper.await.unwrap(), 1_700_000_010.0);
+        assert_eq!(clock.now() - start, Duration::from_secs(10));
+        assert_eq!(clock.sleepers(), 0);
+
+        // Zero and past deadlines resolve at once
+        clock.sleep(Duration::ZERO).await;
+        clock.sleep_until(start).await;
+    }
+
+    #[tokio::test]
+    async fn test_dropped_sleeps_are_not_counted() {
+        let clock = MockClock::new(0.0);
+        drop(clock.sleep(Duration::from_secs(1)));
+        let _pending = clock.sleep(Duration::from_secs(1));
+        assert_eq!(clock.sleepers(), 1);
+    }
+}
@@ -1,49 +1,199 @@
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use bytes::Bytes;
 use tokio::net::TcpStream;
 use tokio::net::tcp::{OwnedReadHalf, OwnedWriteHalf};
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, Mutex};
 use tokio_util::codec::{FramedRead, FramedWrite};
 use futures::stream::StreamExt;
 use futures::sink::SinkExt;
 
 use crate::config::FutuConfig;
-use crate::protocol::{FutuCodec, FutuMessage};
-use crate::protocol::encryption::AesEcbCipher;
+use crate::protocol::{FutuCodec, FutuMessage, ProtoFmt, HEADER_SIZE};
+use crate::protocol::decode::{decode_message, DecodeMode};
+use crate::protocol::encryption::{AesEcbCipher, AesPadding, RsaCipher};
+use super::capture::{CaptureLayer, PacketCapture};
+use super::trace::{MessageTrace, TraceDirection};
 
 type Writer = FramedWrite<OwnedWriteHalf, FutuCodec>;
 type Reader = FramedRead<OwnedReadHalf, FutuCodec>;
 
+/// InitConnect is never AES-encrypted, not even when repeated to rotate keys.
+const PROTO_ID_INIT_CONNECT: u32 = 1001;
+
+/// Most messages the writer task writes back to back before one flush.
+const WRITE_BATCH: usize = 64;
+
+/// Messages queued for the writer task before senders wait for room.
+const WRITE_QUEUE: usize = 1024;
+
+/// AES key of the connection. OpenD encrypts with the key of an
+/// InitConnect response from the frame after that response on, so `recv`
+/// installs the key when it reads the response: frames before it decrypt
+/// with the old key and frames after it with the new one.
+#[derive(Default)]
+struct CipherKeys {
+    /// Shared so senders encrypt without holding the lock.
+    current: Option<([u8; 16], Arc<AesEcbCipher>)>,
+}
+
+impl CipherKeys {
+    /// Install `key`. Returns whether it replaced a different key.
+    fn install(&mut self, key: &[u8; 16], padding: AesPadding) -> bool {
+        if self.current.as_ref().is_some_and(|(current, _)| current == key) {
+            return false;
+        }
+        let rotated = self.current.is_some();
+        self.current = Some((*key, Arc::new(AesEcbCipher::with_padding(key, padding))));
+        rotated
+    }
+}
+
 /// Manages the TCP connection to Futu OpenD.
-/// Read and write halves are split to avoid deadlocks.
+/// Read and write halves are split to avoid deadlocks. The write half
+/// belongs to a writer task: senders encrypt and frame their message and
+/// queue it, so concurrent requests never wait on each other for a lock.
+/// The task writes whatever is queued back to back and flushes once per
+/// batch. A failed write ends the task; later sends fail with
+/// `Disconnected` and the reader sees the broken stream.
 pub struct FutuConnection {
     config: FutuConfig,
-    writer: Mutex<Writer>,
+    /// Replaced along with the stream by `adopt`.
+    endpoint: parking_lot::Mutex<(String, u16)>,
+    /// Queue of the writer task; replaced along with the stream by `adopt`.
+    outbox: parking_lot::Mutex<mpsc::Sender<FutuMessage>>,
+    /// The writer task, until `close` waits for it.
+    writer: parking_lot::Mutex<Option<tokio::task::JoinHandle<()>>>,
     reader: Mutex<Reader>,
     serial_counter: AtomicU32,
-    cipher: Mutex<Option<AesEcbCipher>>,
+    cipher: parking_lot::Mutex<CipherKeys>,
+    /// Key encrypting the InitConnect handshake, from `rsa_key_path`.
+    rsa: Option<Arc<RsaCipher>>,
+    /// Times `set_cipher` replaced a key with a different one.
+    key_rotations: AtomicU64,
+    /// Times `adopt` replaced the stream after a disconnect.
+    reconnects: AtomicU64,
+    /// Bytes queued for writing and read, headers included, across
+    /// reconnects.
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
     conn_id: Mutex<u64>,
+    trace: MessageTrace,
+    /// Packet capture, if one is running.
+    capture: parking_lot::Mutex<Option<Arc<PacketCapture>>>,
 }
 
 impl FutuConnection {
     /// Connect to Futu OpenD gateway.
+    /// Endpoints from `FutuConfig::endpoints()` are tried in order; the first
+    /// one that accepts the TCP connection is used.
     pub async fn connect(config: FutuConfig) -> Result<Self, ConnectionError> {
-        let addr = format!("{}:{}", config.host, config.port);
-        tracing::info!("Connecting to Futu OpenD at {}", addr);
-        let stream = TcpStream::connect(&addr).await?;
-        stream.set_nodelay(true)?;
+        let rsa = match config.rsa_key_path.as_deref() {
+            Some(path) => Some(Arc::new(RsaCipher::load(path).map_err(|e| ConnectionError::Encryption(e.to_string()))?)),
+            None => None,
+        };
+        let mut last_err = None;
+        for (host, port) in config.endpoints() {
+            let endpoint = format_endpoint(&host, port);
+            tracing::info!("Connecting to Futu OpenD at {}", endpoint);
+            match dial(&config, &host, port).await {
+                Ok(stream) => return Ok(Self::from_stream(config, stream, (host, port), rsa)),
+                Err(e) => {
+                    tracing::warn!("Failed to connect to {}: {}", endpoint, e);
+                    last_err = Some(e);
+                }
+            }
+        }
+        Err(last_err.unwrap_or(ConnectionError::Disconnected))
+    }
+
+    fn from_stream(config: FutuConfig, stream: TcpStream, endpoint: (String, u16), rsa: Option<Arc<RsaCipher>>) -> Self {
         // Split TCP stream into independent read/write halves (no shared lock)
         let (read_half, write_half) = stream.into_split();
         let reader = FramedRead::new(read_half, FutuCodec);
         let writer = FramedWrite::new(write_half, FutuCodec);
+        let (outbox, queue) = mpsc::channel(WRITE_QUEUE);
+        let writer = tokio::spawn(write_loop(writer, queue));
 
-        Ok(Self {
+        let trace = MessageTrace::new(config.trace_capacity, config.trace_dump_path.clone());
+        let capture = config.capture_path.as_deref().and_then(|path| match PacketCapture::create(path, true) {
+            Ok(capture) => {
+                tracing::info!("Capturing packets to {}", path.display());
+                Some(Arc::new(capture))
+            }
+            Err(e) => {
+                tracing::error!("Failed to create packet capture {}: {}", path.display(), e);
+                None
+            }
+        });
+        Self {
             config,
-            writer: Mutex::new(writer),
+            endpoint: parking_lot::Mutex::new(endpoint),
+            outbox: parking_lot::Mutex::new(outbox),
+            writer: parking_lot::Mutex::new(Some(writer)),
             reader: Mutex::new(reader),
             serial_counter: AtomicU32::new(1),
-            cipher: Mutex::new(None),
+            cipher: parking_lot::Mutex::new(CipherKeys::default()),
+            rsa,
+            key_rotations: AtomicU64::new(0),
+            reconnects: AtomicU64::new(0),
+            bytes_sent: AtomicU64::new(0),
+            bytes_received: AtomicU64::new(0),
             conn_id: Mutex::new(0),
-        })
+            trace,
+            capture: parking_lot::Mutex::new(capture),
+        }
+    }
+
+    /// The (host, port) endpoint this connection is attached to.
+    pub fn endpoint(&self) -> (String, u16) {
+        self.endpoint.lock().clone()
+    }
+
+    /// Take over the stream, endpoint, AES keys and connection ID of
+    /// `other`, a new connection that completed InitConnect, in place of a
+    /// lost one. Serial numbers, the trace and packet capture carry on.
+    pub async fn adopt(&self, other: FutuConnection) {
+        let conn_id = other.conn_id().await;
+        let keys = std::mem::take(&mut *other.cipher.lock());
+        // The lost stream's writer task ends with its queue
+        *self.outbox.lock() = other.outbox.into_inner();
+        *self.writer.lock() = other.writer.into_inner();
+        *self.reader.lock().await = other.reader.into_inner();
+        *self.cipher.lock() = keys;
+        *self.endpoint.lock() = other.endpoint.into_inner();
+        self.set_conn_id(conn_id).await;
+        self.reconnects.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Number of times the connection was re-established.
+    pub fn reconnects(&self) -> u64 {
+        self.reconnects.load(Ordering::Relaxed)
+    }
+
+    /// Bytes sent on this connection, headers included, counted as they
+    /// are queued for the writer task.
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent.load(Ordering::Relaxed)
+    }
+
+    /// Bytes received on this connection, headers included.
+    pub fn bytes_received(&self) -> u64 {
+        self.bytes_received.load(Ordering::Relaxed)
+    }
+
+    /// Stop sending: the writer task writes what is already queued, then
+    /// ends and closes the stream's write half. Later sends fail with
+    /// `Disconnected`. Resolves once the writer task has ended.
+    pub async fn close(&self) {
+        let (closed, _) = mpsc::channel(1);
+        drop(std::mem::replace(&mut *self.outbox.lock(), closed));
+        let writer = self.writer.lock().take();
+        if let Some(writer) = writer {
+            let _ = writer.await;
+        }
     }
 
     /// Get the next serial number.
@@ -60,67 +210,160 @@ impl FutuConnection {
 
     /// Send a message with a specific serial number (with optional encryption).
     pub async fn send_with_serial(&self, proto_id: u32, body: &[u8], serial_no: u32) -> Result<(), ConnectionError> {
-        let cipher = self.cipher.lock().await;
-        let encrypted = cipher.is_some();
-        let body_to_send = if let Some(ref aes) = *cipher {
-            aes.encrypt(body)
-        } else {
-            body.to_vec()
+        let aes = self.cipher.lock().current.as_ref().map(|(_, aes)| Arc::clone(aes)).filter(|_| proto_id != PROTO_ID_INIT_CONNECT);
+        let rsa = self.rsa.as_ref().filter(|_| proto_id == PROTO_ID_INIT_CONNECT);
+        let encrypted = aes.is_some() || rsa.is_some();
+        let body_to_send = match (aes, rsa) {
+            (Some(aes), _) => Bytes::from(aes.encrypt(body)),
+            (None, Some(rsa)) => Bytes::from(rsa.encrypt(body).map_err(|e| ConnectionError::Encryption(e.to_string()))?),
+            (None, None) => Bytes::copy_from_slice(body),
         };
-        drop(cipher);
 
         tracing::debug!("SEND proto_id={}, serial_no={}, body_len={}, encrypted={}", proto_id, serial_no, body_to_send.len(), encrypted);
 
+        self.trace.record(TraceDirection::Send, proto_id, serial_no, &body_to_send);
+        if let Some(capture) = self.capture() {
+            let now = self.config.clock.unix_time();
+            capture.record(TraceDirection::Send, CaptureLayer::Plain, proto_id, serial_no, body, now);
+            if encrypted {
+                capture.record(TraceDirection::Send, CaptureLayer::Wire, proto_id, serial_no, &body_to_send, now);
+            }
+        }
+        let wire_len = (HEADER_SIZE + body_to_send.len()) as u64;
         let msg = FutuMessage {
             proto_id,
             serial_no,
             body: body_to_send,
+            proto_fmt: ProtoFmt::Protobuf,
         };
 
-        let mut writer = self.writer.lock().await;
-        writer.send(msg).await.map_err(|e| ConnectionError::Send(e.to_string()))?;
+        // Clone the sender so a full queue is waited on without the lock
+        let outbox = self.outbox.lock().clone();
+        outbox.send(msg).await.map_err(|_| ConnectionError::Disconnected)?;
+        self.bytes_sent.fetch_add(wire_len, Ordering::Relaxed);
         Ok(())
     }
 
     /// Receive the next message (with optional decryption).
     pub async fn recv(&self) -> Result<FutuMessage, ConnectionError> {
         let mut reader = self.reader.lock().await;
-        match reader.next().await {
+        let next = match self.config.read_timeout {
+            Some(timeout) => match tokio::time::timeout(timeout, reader.next()).await {
+                Ok(next) => next,
+                Err(_) => {
+                    tracing::error!("No message from OpenD within {:?}", timeout);
+                    self.trace.dump("read timeout");
+                    return Err(ConnectionError::ReadTimeout(timeout));
+                }
+            },
+            None => reader.next().await,
+        };
+        match next {
             Some(Ok(mut msg)) => {
                 tracing::debug!("RECV proto_id={}, serial_no={}, body_len={}", msg.proto_id, msg.serial_no, msg.body.len());
-                drop(reader); // Release reader lock before acquiring cipher lock
-                let mut cipher = self.cipher.lock().await;
-                if let Some(ref aes) = *cipher {
+                self.bytes_received.fetch_add((HEADER_SIZE + msg.body.len()) as u64, Ordering::Relaxed);
+                let capture = self.capture();
+                let now = self.config.clock.unix_time();
+                // Take the cipher lock before releasing the reader, so
+                // concurrent receivers see key changes in wire order.
+                // Nothing takes the reader while holding the cipher.
+                let mut keys = self.cipher.lock();
+                drop(reader);
+                if msg.proto_id == PROTO_ID_INIT_CONNECT {
+                    if let Some(rsa) = self.rsa.as_ref().filter(|_| !msg.body.is_empty()) {
+                        if msg.body.len().is_multiple_of(rsa.block_size()) {
+                            if let Some(capture) = &capture {
+                                capture.record(TraceDirection::Recv, CaptureLayer::Wire, msg.proto_id, msg.serial_no, &msg.body, now);
+                            }
+                            msg.body = match rsa.decrypt(&msg.body) {
+                                Ok(body) => Bytes::from(body),
+                                Err(e) => {
+                                    self.trace.dump(&format!("RSA decryption error on InitConnect: {}", e));
+                                    return Err(ConnectionError::Decryption(e.to_string()));
+                                }
+                            };
+                        } else {
+                            // OpenD has no private key configured and answers in plain
+                            tracing::warn!("InitConnect response not RSA-encrypted (body_len={})", msg.body.len());
+                        }
+                    }
+                    // Installed before the next frame, the first under the new key, can be read
+                    if let Some(key) = self.init_connect_key(&msg) {
+                        self.install_key(&mut keys, &key);
+                    }
+                } else if let Some((_, ref aes)) = keys.current {
                     if !msg.body.is_empty() {
                         if msg.body.len().is_multiple_of(16) {
-                            msg.body = aes.decrypt(&msg.body)
-                                .map_err(|e| ConnectionError::Decryption(e.to_string()))?;
+                            if let Some(capture) = &capture {
+                                capture.record(TraceDirection::Recv, CaptureLayer::Wire, msg.proto_id, msg.serial_no, &msg.body, now);
+                            }
+                            msg.body = match aes.decrypt(&msg.body) {
+                                Ok(body) => Bytes::from(body),
+                                Err(e) => {
+                                    self.trace.dump(&format!("decryption error on proto_id={}: {}", msg.proto_id, e));
+                                    return Err(ConnectionError::Decryption(e.to_string()));
+                                }
+                            };
                         } else {
                             // Body length is not a multiple of 16 — server is NOT encrypting.
                             // This happens when FutuOpenD has no RSA keys configured.
                             // Disable encryption for all subsequent communication.
                             tracing::warn!("Server response not encrypted (body_len={}), disabling cipher", msg.body.len());
-                            *cipher = None;
+                            *keys = CipherKeys::default();
                         }
                     }
                 }
+                drop(keys);
+                self.trace.record(TraceDirection::Recv, msg.proto_id, msg.serial_no, &msg.body);
+                if let Some(capture) = &capture {
+                    capture.record(TraceDirection::Recv, CaptureLayer::Plain, msg.proto_id, msg.serial_no, &msg.body, now);
+                }
                 Ok(msg)
             }
             Some(Err(e)) => {
                 tracing::error!("Receive error: {}", e);
+                self.trace.dump(&format!("receive error: {}", e));
                 Err(ConnectionError::Receive(e.to_string()))
             }
             None => {
                 tracing::warn!("Connection disconnected");
+                self.trace.dump("unexpected disconnect");
                 Err(ConnectionError::Disconnected)
             }
         }
     }
 
-    /// Set the AES encryption key (after InitConnect).
+    /// Set the AES encryption key (after InitConnect). `recv` already
+    /// installs the key of an InitConnect response as it reads it; replacing
+    /// a key with a different one counts as a rotation.
     pub async fn set_cipher(&self, key: &[u8; 16]) {
-        let mut cipher = self.cipher.lock().await;
-        *cipher = Some(AesEcbCipher::new(key));
+        let mut keys = self.cipher.lock();
+        self.install_key(&mut keys, key);
+    }
+
+    fn install_key(&self, keys: &mut CipherKeys, key: &[u8; 16]) {
+        if keys.install(key, self.config.aes_padding) {
+            self.key_rotations.fetch_add(1, Ordering::Relaxed);
+            tracing::info!("AES key rotated");
+        }
+    }
+
+    /// The AES key an InitConnect response hands out, when encryption is on.
+    fn init_connect_key(&self, msg: &FutuMessage) -> Option<[u8; 16]> {
+        if !self.config.encryption_enabled() {
+            return None;
+        }
+        let response: crate::generated::init_connect::Response =
+            decode_message(&msg.body, msg.proto_fmt, DecodeMode::Lenient).ok()?;
+        if response.ret_type != 0 {
+            return None;
+        }
+        response.s2c?.conn_aes_key.as_bytes().try_into().ok()
+    }
+
+    /// Number of AES key rotations on this connection.
+    pub fn key_rotations(&self) -> u64 {
+        self.key_rotations.load(Ordering::Relaxed)
     }
 
     /// Set the connection ID.
@@ -134,11 +377,48 @@ impl FutuConnection {
         *self.conn_id.lock().await
     }
 
+    /// Ring buffer of recent message summaries.
+    pub fn trace(&self) -> &MessageTrace {
+        &self.trace
+    }
+
+    /// Start capturing packets to `path`, replacing any running capture.
+    /// `include_wire` also records the encrypted form of each body.
+    pub fn start_capture(&self, path: &Path, include_wire: bool) -> std::io::Result<()> {
+        let capture = PacketCapture::create(path, include_wire)?;
+        tracing::info!("Capturing packets to {}", path.display());
+        *self.capture.lock() = Some(Arc::new(capture));
+        Ok(())
+    }
+
+    /// Stop the running capture, flushing it. Returns the number of packets
+    /// it wrote, or None if no capture was running.
+    pub fn stop_capture(&self) -> Option<u64> {
+        let capture = self.capture.lock().take()?;
+        if let Err(e) = capture.flush() {
+            tracing::error!("Failed to flush packet capture: {}", e);
+        }
+        Some(capture.packets())
+    }
+
+    fn capture(&self) -> Option<Arc<PacketCapture>> {
+        self.capture.lock().clone()
+    }
+
     pub fn config(&self) -> &FutuConfig {
         &self.config
     }
 }
 
+/// Format a host/port pair, bracketing IPv6 literals (`[::1]:11111`).
+pub fn format_endpoint(host: &str, port: u16) -> String {
+    if host.contains(':') {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ConnectionError {
     #[error("IO error: {0}")]
@@ -149,6 +429,285 @@ pub enum ConnectionError {
     Receive(String),
     #[error("decryption error: {0}")]
     Decryption(String),
+    #[error("encryption error: {0}")]
+    Encryption(String),
     #[error("connection disconnected")]
     Disconnected,
+    #[error("connect to {endpoint} timed out after {timeout:?}")]
+    ConnectTimeout { endpoint: String, timeout: Duration },
+    #[error("no message from OpenD within {0:?}")]
+    ReadTimeout(Duration),
+    #[error("rate limit of proto {proto_id} reached, retry after {retry_after:?}")]
+    RateLimited { proto_id: u32, retry_after: Duration },
+}
+
+/// Open a TCP stream to `host:port` with the socket options of `config`.
+async fn dial(config: &FutuConfig, host: &str, port: u16) -> Result<TcpStream, ConnectionError> {
+    let connect = TcpStream::connect((host, port));
+    let stream = match config.connect_timeout {
+        Some(timeout) => tokio::time::timeout(timeout, connect)
+            .await
+            .map_err(|_| ConnectionError::ConnectTimeout { endpoint: format_endpoint(host, port), timeout })??,
+        None => connect.await?,
+    };
+    stream.set_nodelay(config.nodelay)?;
+    if let Some(idle) = config.tcp_keepalive {
+        socket2::SockRef::from(&stream).set_tcp_keepalive(&socket2::TcpKeepalive::new().with_time(idle))?;
+    }
+    Ok(stream)
+}
+
+/// Write queued messages until every sender is gone or a write fails.
+/// Whatever is queued is fed back to back and flushed once.
+async fn write_loop(mut writer: Writer, mut queue: mpsc::Receiver<FutuMessage>) {
+    let mut batch = Vec::with_capacity(WRITE_BATCH);
+    while queue.recv_many(&mut batch, WRITE_BATCH).await > 0 {
+        let mut result = Ok(());
+        for msg in batch.drain(..) {
+            if result.is_ok() {
+                result = writer.feed(msg).await;
+            }
+        }
+        if result.is_ok() {
+            result = writer.flush().await;
+        }
+        if let Err(e) = result {
+            tracing::error!("Send error: {}", e);
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_endpoint() {
+        assert_eq!(format_endpoint("127.0.0.1", 11111), "127.0.0.1:11111");
+        assert_eq!(format_endpoint("::1", 11111), "[::1]:11111");
+        assert_eq!(format_endpoint("localhost", 1), "localhost:1");
+    }
+
+    #[tokio::test]
+    async fn test_connect_falls_back_to_next_endpoint() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        // Port 1 on localhost is almost never listening.
+        let config = FutuConfig {
+            host: "127.0.0.1".to_string(),
+            port: 1,
+            fallback_endpoints: vec![("127.0.0.1".to_string(), port)],
+            ..FutuConfig::default()
+        };
+        let conn = FutuConnection::connect(config).await.unwrap();
+        assert_eq!(conn.endpoint(), ("127.0.0.1".to_string(), port));
+    }
+
+    #[tokio::test]
+    async fn test_set_cipher_counts_rotations() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let conn = FutuConnection::connect(FutuConfig { port, ..FutuConfig::default() }).await.unwrap();
+
+        let (old_key, new_key) = (*b"0123456789abcdef", *b"fedcba9876543210");
+        conn.set_cipher(&old_key).await;
+        conn.set_cipher(&old_key).await;
+        assert_eq!(conn.key_rotations(), 0);
+        conn.set_cipher(&new_key).await;
+        assert_eq!(conn.key_rotations(), 1);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_key_rotation_at_init_connect_response() {
+        use prost::Message;
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let config = FutuConfig { port, enable_encryption: true, ..FutuConfig::default() };
+        let conn = FutuConnection::connect(config).await.unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+        let mut server = FramedWrite::new(server, FutuCodec);
+
+        let (old_key, new_key) = (*b"0123456789abcdef", *b"fedcba9876543210");
+        conn.set_cipher(&old_key).await;
+        let frame = |serial_no: u32, key: &[u8; 16], payload: &[u8]| FutuMessage {
+            proto_id: 3005,
+            serial_no,
+            body: Bytes::from(AesEcbCipher::new(key).encrypt(payload)),
+            proto_fmt: ProtoFmt::Protobuf,
+        };
+        let response = crate::generated::init_connect::Response {
+            ret_type: 0,
+            s2c: Some(crate::generated::init_connect::S2c {
+                conn_aes_key: String::from_utf8(new_key.to_vec()).unwrap(),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        // Old-key frames up to the InitConnect response, new-key frames after it
+        server.send(frame(1, &old_key, b"before")).await.unwrap();
+        server.send(FutuMessage {
+            proto_id: PROTO_ID_INIT_CONNECT,
+            serial_no: 2,
+            body: Bytes::from(response.encode_to_vec()),
+            proto_fmt: ProtoFmt::Protobuf,
+        }).await.unwrap();
+        server.send(frame(3, &new_key, b"after")).await.unwrap();
+        server.send(frame(4, &new_key, b"after again")).await.unwrap();
+
+        // Receivers running at once each get the key of their frame's position
+        let (first, second, third) = tokio::join!(conn.recv(), conn.recv(), conn.recv());
+        assert_eq!(first.unwrap().body, &b"before"[..]);
+        assert_eq!(second.unwrap().proto_id, PROTO_ID_INIT_CONNECT);
+        assert_eq!(third.unwrap().body, &b"after"[..]);
+        assert_eq!(conn.recv().await.unwrap().body, &b"after again"[..]);
+        assert_eq!(conn.key_rotations(), 1);
+
+        // The key is in place before the caller handles the response
+        conn.set_cipher(&new_key).await;
+        assert_eq!(conn.key_rotations(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rsa_encrypted_handshake() {
+        use crate::protocol::encryption::TEST_RSA_KEY;
+        use tokio_util::codec::Framed;
+
+        let key_path = std::env::temp_dir().join(format!("nautilus_futu_conn_rsa_{}.pem", std::process::id()));
+        let missing = FutuConfig { rsa_key_path: Some(key_path.clone()), ..FutuConfig::default() };
+        assert!(matches!(FutuConnection::connect(missing).await, Err(ConnectionError::Encryption(_))));
+
+        std::fs::write(&key_path, TEST_RSA_KEY).unwrap();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let config = FutuConfig { port, rsa_key_path: Some(key_path.clone()), ..FutuConfig::default() };
+        assert!(config.encryption_enabled());
+        let conn = FutuConnection::connect(config).await.unwrap();
+        std::fs::remove_file(&key_path).unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+        let mut server = Framed::new(server, FutuCodec);
+        let rsa = RsaCipher::from_pem(TEST_RSA_KEY).unwrap();
+
+        // Only InitConnect is RSA-encrypted
+        conn.send(PROTO_ID_INIT_CONNECT, b"hello").await.unwrap();
+        conn.send(1004, b"keepalive").await.unwrap();
+        let request = server.next().await.unwrap().unwrap();
+        assert_eq!(request.body.len(), 128);
+        assert_eq!(rsa.decrypt(&request.body).unwrap(), b"hello");
+        assert_eq!(server.next().await.unwrap().unwrap().body, &b"keepalive"[..]);
+
+        let body = Bytes::from(rsa.encrypt(b"welcome").unwrap());
+        server.send(FutuMessage { proto_id: PROTO_ID_INIT_CONNECT, serial_no: 1, body, proto_fmt: ProtoFmt::Protobuf }).await.unwrap();
+        assert_eq!(conn.recv().await.unwrap().body, &b"welcome"[..]);
+        // OpenD without the key answers in plain
+        server.send(FutuMessage { proto_id: PROTO_ID_INIT_CONNECT, serial_no: 2, body: Bytes::from_static(b"plain"), proto_fmt: ProtoFmt::Protobuf }).await.unwrap();
+        assert_eq!(conn.recv().await.unwrap().body, &b"plain"[..]);
+    }
+
+    #[tokio::test]
+    async fn test_connect_all_endpoints_fail() {
+        let config = FutuConfig {
+            host: "127.0.0.1".to_string(),
+            port: 1,
+            ..FutuConfig::default()
+        };
+        assert!(matches!(
+            FutuConnection::connect(config).await,
+            Err(ConnectionError::Io(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_socket_options_and_timeouts() {
+        // A listener whose accept queue is full drops further SYNs, so
+        // connecting to it hangs
+        let full = socket2::Socket::new(socket2::Domain::IPV4, socket2::Type::STREAM, None).unwrap();
+        full.bind(&"127.0.0.1:0".parse::<std::net::SocketAddr>().unwrap().into()).unwrap();
+        full.listen(0).unwrap();
+        let full_port = full.local_addr().unwrap().as_socket().unwrap().port();
+        let _queued: Vec<_> = (0..2).filter_map(|_| {
+            std::net::TcpStream::connect_timeout(&([127, 0, 0, 1], full_port).into(), Duration::from_millis(100)).ok()
+        }).collect();
+        let config = FutuConfig {
+            port: full_port,
+            connect_timeout: Some(Duration::from_millis(100)),
+            ..FutuConfig::default()
+        };
+        assert!(matches!(FutuConnection::connect(config).await, Err(ConnectionError::ConnectTimeout { .. })));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let config = FutuConfig {
+            port,
+            tcp_keepalive: Some(Duration::from_secs(30)),
+            read_timeout: Some(Duration::from_millis(50)),
+            ..FutuConfig::default()
+        };
+        let conn = FutuConnection::connect(config).await.unwrap();
+        let _server = listener.accept().await.unwrap();
+        let err = conn.recv().await.unwrap_err();
+        assert!(matches!(err, ConnectionError::ReadTimeout(t) if t == Duration::from_millis(50)));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_cipher_disabled_in_wire_order() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let conn = FutuConnection::connect(FutuConfig { port, ..FutuConfig::default() }).await.unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+        let mut server = FramedWrite::new(server, FutuCodec);
+        conn.set_cipher(b"0123456789abcdef").await;
+
+        // OpenD without RSA keys: a short plain body, then a plain body that
+        // happens to be a multiple of 16 bytes long
+        let long = Bytes::from_static(b"sixteen byte msg");
+        server.send(FutuMessage { proto_id: 3005, serial_no: 1, body: Bytes::from_static(b"short"), proto_fmt: ProtoFmt::Protobuf }).await.unwrap();
+        server.send(FutuMessage { proto_id: 3005, serial_no: 2, body: long.clone(), proto_fmt: ProtoFmt::Protobuf }).await.unwrap();
+
+        // Both receivers run at once; the one that reads the short body
+        // disables the cipher before the other can read the long one
+        let (first, second) = tokio::join!(conn.recv(), conn.recv());
+        assert_eq!(first.unwrap().body, &b"short"[..]);
+        assert_eq!(second.unwrap().body, long);
+        assert!(conn.cipher.lock().current.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_sends_share_writer() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let conn = FutuConnection::connect(FutuConfig { port, ..FutuConfig::default() }).await.unwrap();
+        let (server, _) = listener.accept().await.unwrap();
+        let mut server = FramedRead::new(server, FutuCodec);
+
+        let bodies: Vec<String> = (0..200).map(|i| format!("request {}", i)).collect();
+        let sends = bodies.iter().map(|body| conn.send(3203, body.as_bytes()));
+        let mut serials: Vec<u32> = futures::future::try_join_all(sends).await.unwrap();
+        serials.sort_unstable();
+        let mut received = Vec::new();
+        while received.len() < serials.len() {
+            let msg = server.next().await.unwrap().unwrap();
+            assert_eq!(msg.body, format!("request {}", msg.serial_no - 1).into_bytes());
+            received.push(msg.serial_no);
+        }
+        received.sort_unstable();
+        assert_eq!(received, serials);
+        let body_bytes: usize = bodies.iter().map(String::len).sum();
+        assert_eq!(conn.bytes_sent(), (200 * HEADER_SIZE + body_bytes) as u64);
+
+        // A send after the writer lost its stream fails rather than hangs
+        drop(server);
+        let mut result = Ok(0);
+        for _ in 0..100 {
+            result = conn.send(3203, &[0; 4096]).await;
+            if result.is_err() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+        }
+        assert!(result.is_err());
+    }
 }
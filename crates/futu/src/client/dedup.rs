@@ -0,0 +1,204 @@
+//! Suppression of duplicate pushes.
+//!
+//! Subscribing a security that is already subscribed, e.g. by a second
+//! profile or push channel, makes OpenD push its latest data again, so a
+//! subscriber can receive the same update twice. With dedup enabled,
+//! `Dispatcher` keeps a `PushDedup` per subscriber and drops pushes whose
+//! every stream is at a version that subscriber already received.
+//!
+//! A stream is a security of a quote push, or an order or fill of a trade
+//! push. Tickers are versioned by their sequence, so a ticker push is a
+//! duplicate when it brings no newer sequence. Other pushes are versioned
+//! by their content, which includes the update timestamp where OpenD sends
+//! one, and are duplicates only when identical to the previous push of the
+//! stream.
+
+use std::collections::HashMap;
+
+/// How a stream's pushes are ordered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushVersion {
+    /// Increasing sequence; a push not above the last one is a duplicate.
+    Sequence(i64),
+    /// Fingerprint of the content; a push equal to the last one is a duplicate.
+    Content(u64),
+}
+
+impl PushVersion {
+    fn repeats(&self, last: &PushVersion) -> bool {
+        match (self, last) {
+            (PushVersion::Sequence(seq), PushVersion::Sequence(last)) => seq <= last,
+            _ => self == last,
+        }
+    }
+}
+
+/// Streams a push carries, with their versions. Empty for pushes that are
+/// never deduplicated.
+pub fn push_versions(proto_id: u32, body: &[u8]) -> Vec<(String, PushVersion)> {
+    #[cfg(feature = "quote")]
+    if let Some(versions) = quote_versions(proto_id, body) {
+        return versions;
+    }
+    #[cfg(feature = "trade")]
+    if let Some(versions) = trade_versions(proto_id, body) {
+        return versions;
+    }
+    let _ = (proto_id, body);
+    Vec::new()
+}
+
+#[cfg(any(feature = "quote", feature = "trade"))]
+fn fingerprint(bytes: &[u8]) -> PushVersion {
+    use std::hash::{DefaultHasher, Hash, Hasher};
+
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    PushVersion::Content(hasher.finish())
+}
+
+#[cfg(feature = "quote")]
+fn quote_versions(proto_id: u32, body: &[u8]) -> Option<Vec<(String, PushVersion)>> {
+    use prost::Message;
+    use crate::generated::qot_common::Security;
+    use crate::quote::push_stats::{
+        PROTO_QOT_UPDATE_BASIC_QOT, PROTO_QOT_UPDATE_KL, PROTO_QOT_UPDATE_ORDER_BOOK, PROTO_QOT_UPDATE_TICKER,
+    };
+
+    let stream = |s: &Security| format!("{}.{}", s.market, s.code);
+    let versions = match proto_id {
+        PROTO_QOT_UPDATE_BASIC_QOT => crate::generated::qot_update_basic_qot::Response::decode(body)
+            .ok()?
+            .s2c?
+            .basic_qot_list
+            .iter()
+            .map(|q| (stream(&q.security), fingerprint(&q.encode_to_vec())))
+            .collect(),
+        PROTO_QOT_UPDATE_TICKER => {
+            let s2c = crate::generated::qot_update_ticker::Response::decode(body).ok()?.s2c?;
+            let sequence = s2c.ticker_list.iter().map(|t| t.sequence).max()?;
+            vec![(stream(&s2c.security), PushVersion::Sequence(sequence))]
+        }
+        PROTO_QOT_UPDATE_ORDER_BOOK => {
+            let s2c = crate::generated::qot_update_order_book::Response::decode(body).ok()?.s2c?;
+            vec![(stream(&s2c.security), fingerprint(body))]
+        }
+        PROTO_QOT_UPDATE_KL => {
+            // The bar in progress keeps its time while its prices change
+            let s2c = crate::generated::qot_update_kl::Response::decode(body).ok()?.s2c?;
+            vec![(format!("{}:{}", stream(&s2c.security), s2c.kl_type), fingerprint(body))]
+        }
+        _ => return None,
+    };
+    Some(versions)
+}
+
+#[cfg(feature = "trade")]
+fn trade_versions(proto_id: u32, body: &[u8]) -> Option<Vec<(String, PushVersion)>> {
+    use prost::Message;
+    use crate::trade::sequence::{PROTO_TRD_UPDATE_ORDER, PROTO_TRD_UPDATE_ORDER_FILL};
+
+    let versions = match proto_id {
+        PROTO_TRD_UPDATE_ORDER => {
+            let s2c = crate::generated::trd_update_order::Response::decode(body).ok()?.s2c?;
+            vec![(format!("order:{}:{}", s2c.header.acc_id, s2c.order.order_id), fingerprint(body))]
+        }
+        PROTO_TRD_UPDATE_ORDER_FILL => {
+            let s2c = crate::generated::trd_update_order_fill::Response::decode(body).ok()?.s2c?;
+            vec![(format!("fill:{}:{}", s2c.header.acc_id, s2c.order_fill.fill_id), fingerprint(body))]
+        }
+        _ => return None,
+    };
+    Some(versions)
+}
+
+/// Streams above this many are forgotten at once, so a long session with
+/// many orders does not grow the state without bound.
+const MAX_STREAMS: usize = 100_000;
+
+/// Last version of every stream one subscriber received.
+#[derive(Debug, Default)]
+pub struct PushDedup {
+    last: HashMap<(u32, String), PushVersion>,
+}
+
+impl PushDedup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether a push of `proto_id` with `versions` repeats what was already
+    /// received. A push that is not a duplicate updates the streams' versions.
+    pub fn is_duplicate(&mut self, proto_id: u32, versions: &[(String, PushVersion)]) -> bool {
+        if versions.is_empty() {
+            return false;
+        }
+        let duplicate = versions.iter().all(|(stream, version)| {
+            self.last.get(&(proto_id, stream.clone())).is_some_and(|last| version.repeats(last))
+        });
+        if duplicate {
+            return true;
+        }
+        if self.last.len() + versions.len() > MAX_STREAMS {
+            self.last.clear();
+        }
+        for (stream, version) in versions {
+            let last = self.last.entry((proto_id, stream.clone())).or_insert(*version);
+            if !version.repeats(last) {
+                *last = *version;
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versions(items: &[(&str, PushVersion)]) -> Vec<(String, PushVersion)> {
+        items.iter().map(|(s, v)| (s.to_string(), *v)).collect()
+    }
+
+    #[test]
+    fn test_sequence_and_content_versions() {
+        let mut dedup = PushDedup::new();
+        let tick = |seq| versions(&[("1.00700", PushVersion::Sequence(seq))]);
+        assert!(!dedup.is_duplicate(3011, &tick(5)));
+        assert!(dedup.is_duplicate(3011, &tick(5)));
+        assert!(dedup.is_duplicate(3011, &tick(4)));
+        assert!(!dedup.is_duplicate(3011, &tick(6)));
+        // Streams are per proto_id
+        assert!(!dedup.is_duplicate(3013, &tick(6)));
+
+        let qot = |a, b| versions(&[("1.00700", PushVersion::Content(a)), ("1.09988", PushVersion::Content(b))]);
+        assert!(!dedup.is_duplicate(3005, &qot(1, 2)));
+        assert!(dedup.is_duplicate(3005, &qot(1, 2)));
+        // One changed security delivers the whole push
+        assert!(!dedup.is_duplicate(3005, &qot(1, 3)));
+        // Content going back is a new update, not a duplicate
+        assert!(!dedup.is_duplicate(3005, &qot(1, 2)));
+        assert!(!dedup.is_duplicate(3005, &[]));
+    }
+
+    #[cfg(feature = "quote")]
+    #[test]
+    fn test_ticker_push_versions() {
+        use prost::Message;
+        use crate::generated::{qot_common, qot_update_ticker};
+
+        let ticker = |sequence| qot_common::Ticker { sequence, ..Default::default() };
+        let body = qot_update_ticker::Response {
+            ret_type: 0,
+            s2c: Some(qot_update_ticker::S2c {
+                security: qot_common::Security { market: 1, code: "00700".to_string() },
+                ticker_list: vec![ticker(7), ticker(9), ticker(8)],
+                ..Default::default()
+            }),
+            ..Default::default()
+        }.encode_to_vec();
+        assert_eq!(push_versions(3011, &body), versions(&[("1.00700", PushVersion::Sequence(9))]));
+        assert!(push_versions(3011, b"garbage").is_empty());
+        assert!(push_versions(3001, &body).is_empty());
+    }
+}
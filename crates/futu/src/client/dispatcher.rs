@@ -1,15 +1,117 @@
-use std::collections::HashMap;
-use tokio::sync::{mpsc, oneshot, Mutex};
+use std::cell::OnceCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot, Mutex, Notify};
 use crate::protocol::FutuMessage;
+use super::dedup::{push_versions, PushDedup};
+
+/// Where `Dispatcher::dispatch` delivered a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Dispatched {
+    /// Matched a pending request.
+    Response,
+    /// Sent to at least one push subscriber.
+    Push,
+    /// No pending request or open push subscriber.
+    Unhandled,
+}
+
+/// Requests waiting for a response.
+#[derive(Default)]
+struct Pending {
+    senders: HashMap<u32, oneshot::Sender<FutuMessage>>,
+    /// Set once no response will ever be dispatched again.
+    closed: bool,
+    /// Set while shutting down: requests still pending get their responses,
+    /// new ones fail at once.
+    draining: bool,
+}
+
+/// Which pushes of its proto_id a subscriber receives.
+#[derive(Clone, Default)]
+pub enum PushFilter {
+    #[default]
+    All,
+    /// Pushes naming one of these securities (market, code). A push listing
+    /// several securities, such as a basic quote push, is delivered whole if
+    /// any of them is wanted. Pushes naming no security are not delivered.
+    Securities(HashSet<(i32, String)>),
+    /// Pushes the predicate accepts. Called under the dispatcher's lock, so
+    /// it must be quick.
+    Predicate(Arc<dyn Fn(&FutuMessage) -> bool + Send + Sync>),
+}
+
+impl PushFilter {
+    /// Pushes of one security.
+    pub fn security(market: i32, code: impl Into<String>) -> Self {
+        PushFilter::Securities(HashSet::from([(market, code.into())]))
+    }
+
+    /// Pushes the predicate accepts.
+    pub fn predicate(f: impl Fn(&FutuMessage) -> bool + Send + Sync + 'static) -> Self {
+        PushFilter::Predicate(Arc::new(f))
+    }
+
+    /// Whether `msg` passes; `securities` decodes the push's securities once
+    /// for all subscribers.
+    fn accepts(&self, msg: &FutuMessage, securities: &OnceCell<Vec<(i32, String)>>) -> bool {
+        match self {
+            PushFilter::All => true,
+            PushFilter::Securities(wanted) => securities
+                .get_or_init(|| push_securities(msg.proto_id, &msg.body))
+                .iter()
+                .any(|s| wanted.contains(s)),
+            PushFilter::Predicate(f) => f(msg),
+        }
+    }
+}
+
+impl fmt::Debug for PushFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PushFilter::All => f.write_str("All"),
+            PushFilter::Securities(securities) => f.debug_tuple("Securities").field(securities).finish(),
+            PushFilter::Predicate(_) => f.write_str("Predicate(..)"),
+        }
+    }
+}
+
+/// Securities a push is about; empty when built without quote support.
+fn push_securities(proto_id: u32, body: &[u8]) -> Vec<(i32, String)> {
+    #[cfg(feature = "quote")]
+    return crate::quote::push_stats::push_securities(proto_id, body);
+    #[cfg(not(feature = "quote"))]
+    {
+        let _ = (proto_id, body);
+        Vec::new()
+    }
+}
+
+/// A push subscriber and the pushes it already received.
+struct PushHandler {
+    tx: mpsc::UnboundedSender<FutuMessage>,
+    filter: PushFilter,
+    dedup: PushDedup,
+}
 
 /// Dispatches incoming messages to the appropriate handler.
 /// - Request/response messages are matched by serial number.
-/// - Push messages are dispatched by proto_id.
+/// - Push messages are dispatched by proto_id, optionally without
+///   duplicates (see `client::dedup`).
 pub struct Dispatcher {
-    /// Pending request-response pairs, keyed by serial number.
-    pending: Mutex<HashMap<u32, oneshot::Sender<FutuMessage>>>,
+    /// Pending request-response pairs, keyed by serial number. A plain
+    /// mutex, so a request dropped mid-flight can remove its entry.
+    pending: parking_lot::Mutex<Pending>,
     /// Push notification handlers, keyed by proto_id.
-    push_handlers: Mutex<HashMap<u32, Vec<mpsc::UnboundedSender<FutuMessage>>>>,
+    push_handlers: Mutex<HashMap<u32, Vec<PushHandler>>>,
+    /// Whether duplicate pushes are suppressed per subscriber.
+    dedup: AtomicBool,
+    /// Pushes suppressed as duplicates, per subscriber, keyed by proto_id.
+    duplicates: parking_lot::Mutex<BTreeMap<u32, u64>>,
+    /// Notified whenever the last pending request is answered or dropped.
+    idle: Notify,
 }
 
 impl Default for Dispatcher {
@@ -21,77 +123,209 @@ impl Default for Dispatcher {
 impl Dispatcher {
     pub fn new() -> Self {
         Self {
-            pending: Mutex::new(HashMap::new()),
+            pending: parking_lot::Mutex::new(Pending::default()),
             push_handlers: Mutex::new(HashMap::new()),
+            dedup: AtomicBool::new(false),
+            duplicates: parking_lot::Mutex::new(BTreeMap::new()),
+            idle: Notify::new(),
         }
     }
 
+    /// Enable or disable suppressing duplicate pushes.
+    pub fn set_dedup(&self, enabled: bool) {
+        self.dedup.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Whether duplicate pushes are suppressed.
+    pub fn dedup(&self) -> bool {
+        self.dedup.load(Ordering::Relaxed)
+    }
+
+    /// Pushes suppressed as duplicates so far, keyed by proto_id. A push
+    /// suppressed for two subscribers counts twice.
+    pub fn suppressed_duplicates(&self) -> BTreeMap<u32, u64> {
+        self.duplicates.lock().clone()
+    }
+
     /// Register a pending request. Returns a receiver for the response.
+    /// Once the dispatcher is closed or draining the receiver fails at once,
+    /// since no response would ever reach it.
     pub async fn register_request(&self, serial_no: u32) -> oneshot::Receiver<FutuMessage> {
         let (tx, rx) = oneshot::channel();
-        self.pending.lock().await.insert(serial_no, tx);
+        let mut pending = self.pending.lock();
+        if !pending.closed && !pending.draining {
+            pending.senders.insert(serial_no, tx);
+        }
         rx
     }
 
+    /// Forget a pending request whose caller stopped waiting, e.g. because
+    /// sending it failed.
+    pub fn cancel_request(&self, serial_no: u32) {
+        let mut pending = self.pending.lock();
+        if pending.senders.remove(&serial_no).is_some() && pending.senders.is_empty() {
+            self.idle.notify_waiters();
+        }
+    }
+
     /// Register a push handler for a specific proto_id.
     /// Returns a receiver that will receive push messages.
     pub async fn register_push(&self, proto_id: u32) -> mpsc::UnboundedReceiver<FutuMessage> {
+        self.register_push_filtered(proto_id, PushFilter::All).await
+    }
+
+    /// Register a push handler for the pushes of `proto_id` that pass
+    /// `filter`. Filtered pushes are never queued on the receiver.
+    pub async fn register_push_filtered(&self, proto_id: u32, filter: PushFilter) -> mpsc::UnboundedReceiver<FutuMessage> {
         let (tx, rx) = mpsc::unbounded_channel();
         self.push_handlers.lock().await
             .entry(proto_id)
             .or_default()
-            .push(tx);
+            .push(PushHandler { tx, filter, dedup: PushDedup::new() });
         rx
     }
 
+    /// Drop the push handlers of `proto_id` whose receivers are gone, rather
+    /// than waiting for the next push to find them closed. Returns the
+    /// number of handlers left.
+    pub async fn prune_push(&self, proto_id: u32) -> usize {
+        let mut handlers = self.push_handlers.lock().await;
+        let Some(senders) = handlers.get_mut(&proto_id) else {
+            return 0;
+        };
+        senders.retain(|h| !h.tx.is_closed());
+        let left = senders.len();
+        if left == 0 {
+            handlers.remove(&proto_id);
+        }
+        left
+    }
+
     /// Clear all pending request senders.
     /// Dropping the oneshot senders causes callers to receive `RecvError`,
     /// which maps to `ConnectionError::Disconnected`.
     pub async fn clear_pending(&self) {
-        let mut pending = self.pending.lock().await;
-        let count = pending.len();
-        pending.clear();
+        let mut pending = self.pending.lock();
+        let count = pending.senders.len();
+        pending.senders.clear();
         if count > 0 {
+            self.idle.notify_waiters();
             tracing::debug!("Cleared {} pending requests", count);
         }
     }
 
+    /// Clear all pending requests and fail the ones registered from now on.
+    /// Both happen under one lock, so a request registered concurrently
+    /// either is cleared or fails at once; none is left waiting.
+    pub async fn close(&self) {
+        let mut pending = self.pending.lock();
+        pending.closed = true;
+        let count = pending.senders.len();
+        pending.senders.clear();
+        if count > 0 {
+            self.idle.notify_waiters();
+            tracing::debug!("Closed with {} pending requests", count);
+        }
+    }
+
+    /// Fail requests registered from now on, while those pending still
+    /// get their responses.
+    pub fn drain(&self) {
+        self.pending.lock().draining = true;
+    }
+
+    /// Wait until no request is pending.
+    pub async fn wait_idle(&self) {
+        loop {
+            // Registered before checking, so an answer in between is not missed
+            let idle = self.idle.notified();
+            if self.pending_count() == 0 {
+                return;
+            }
+            idle.await;
+        }
+    }
+
+    /// Drop every push handler, ending their receivers. Returns the number
+    /// of handlers dropped.
+    pub async fn clear_push(&self) -> usize {
+        let mut handlers = self.push_handlers.lock().await;
+        let count = handlers.values().map(Vec::len).sum();
+        handlers.clear();
+        count
+    }
+
+    /// Whether `close` was called.
+    pub fn is_closed(&self) -> bool {
+        self.pending.lock().closed
+    }
+
+    /// Number of requests waiting for a response.
+    pub fn pending_count(&self) -> usize {
+        self.pending.lock().senders.len()
+    }
+
     /// Dispatch an incoming message.
-    pub async fn dispatch(&self, msg: FutuMessage) {
+    pub async fn dispatch(&self, msg: FutuMessage) -> Dispatched {
         // First try to match as a response to a pending request
-        let mut pending = self.pending.lock().await;
-        if let Some(tx) = pending.remove(&msg.serial_no) {
+        let tx = {
+            let mut pending = self.pending.lock();
+            let tx = pending.senders.remove(&msg.serial_no);
+            if tx.is_some() && pending.senders.is_empty() {
+                self.idle.notify_waiters();
+            }
+            tx
+        };
+        if let Some(tx) = tx {
             let _ = tx.send(msg);
-            return;
+            return Dispatched::Response;
         }
-        drop(pending);
-
-        // Push: copy senders under lock, then send without lock held
-        let senders = {
-            let mut handlers = self.push_handlers.lock().await;
-            if let Some(senders) = handlers.get_mut(&msg.proto_id) {
-                senders.retain(|s| !s.is_closed());
-                senders.clone()
-            } else {
-                tracing::debug!("No handler for proto_id={}, serial_no={}", msg.proto_id, msg.serial_no);
-                return;
+
+        // Decode the push's streams before taking the lock
+        let versions = if self.dedup() { push_versions(msg.proto_id, &msg.body) } else { Vec::new() };
+
+        // Push: unbounded sends never block, so send under the lock rather
+        // than copying the sender list. Clones share the body buffer.
+        let mut handlers = self.push_handlers.lock().await;
+        match handlers.get_mut(&msg.proto_id) {
+            Some(senders) => {
+                let mut suppressed = 0;
+                let securities = OnceCell::new();
+                senders.retain_mut(|h| {
+                    if !h.filter.accepts(&msg, &securities) {
+                        return !h.tx.is_closed();
+                    }
+                    if h.dedup.is_duplicate(msg.proto_id, &versions) {
+                        suppressed += 1;
+                        return !h.tx.is_closed();
+                    }
+                    h.tx.send(msg.clone()).is_ok()
+                });
+                if suppressed > 0 {
+                    *self.duplicates.lock().entry(msg.proto_id).or_default() += suppressed;
+                }
+                if !senders.is_empty() {
+                    return Dispatched::Push;
+                }
             }
-        };
-        for sender in &senders {
-            let _ = sender.send(msg.clone());
+            None => tracing::debug!("No handler for proto_id={}, serial_no={}", msg.proto_id, msg.serial_no),
         }
+        Dispatched::Unhandled
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use bytes::Bytes;
+    use crate::protocol::ProtoFmt;
 
     fn make_msg(proto_id: u32, serial_no: u32, body: &[u8]) -> FutuMessage {
         FutuMessage {
             proto_id,
             serial_no,
-            body: body.to_vec(),
+            body: Bytes::copy_from_slice(body),
+            proto_fmt: ProtoFmt::Protobuf,
         }
     }
 
@@ -103,7 +337,7 @@ mod tests {
         dispatcher.dispatch(msg).await;
         let received = rx.await.unwrap();
         assert_eq!(received.serial_no, 100);
-        assert_eq!(received.body, b"response");
+        assert_eq!(received.body, &b"response"[..]);
     }
 
     #[tokio::test]
@@ -122,7 +356,7 @@ mod tests {
         dispatcher.dispatch(msg).await;
         let received = rx.recv().await.unwrap();
         assert_eq!(received.proto_id, 3001);
-        assert_eq!(received.body, b"push data");
+        assert_eq!(received.body, &b"push data"[..]);
     }
 
     #[tokio::test]
@@ -134,8 +368,112 @@ mod tests {
         dispatcher.dispatch(msg).await;
         let r1 = rx1.recv().await.unwrap();
         let r2 = rx2.recv().await.unwrap();
-        assert_eq!(r1.body, b"broadcast");
-        assert_eq!(r2.body, b"broadcast");
+        assert_eq!(r1.body, &b"broadcast"[..]);
+        assert_eq!(r2.body, &b"broadcast"[..]);
+    }
+
+    #[tokio::test]
+    async fn test_push_listeners_share_body() {
+        let dispatcher = Dispatcher::new();
+        let mut rx1 = dispatcher.register_push(3001).await;
+        let mut rx2 = dispatcher.register_push(3001).await;
+        drop(dispatcher.register_push(3001).await);
+        let msg = make_msg(3001, 0, b"shared");
+        let body_ptr = msg.body.as_ptr();
+        assert_eq!(dispatcher.dispatch(msg).await, Dispatched::Push);
+        assert_eq!(rx1.recv().await.unwrap().body.as_ptr(), body_ptr);
+        assert_eq!(rx2.recv().await.unwrap().body.as_ptr(), body_ptr);
+        // The closed listener was dropped
+        assert_eq!(dispatcher.push_handlers.lock().await[&3001].len(), 2);
+    }
+
+    #[cfg(feature = "quote")]
+    fn ticker_push(sequence: i64) -> FutuMessage {
+        use prost::Message;
+        use crate::generated::{qot_common, qot_update_ticker};
+
+        let body = qot_update_ticker::Response {
+            ret_type: 0,
+            s2c: Some(qot_update_ticker::S2c {
+                security: qot_common::Security { market: 1, code: "00700".to_string() },
+                ticker_list: vec![qot_common::Ticker { sequence, ..Default::default() }],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        make_msg(3011, 0, &body.encode_to_vec())
+    }
+
+    #[cfg(feature = "quote")]
+    #[tokio::test]
+    async fn test_push_dedup_per_subscriber() {
+        let dispatcher = Dispatcher::new();
+        let mut rx1 = dispatcher.register_push(3011).await;
+
+        // Off by default
+        dispatcher.dispatch(ticker_push(1)).await;
+        dispatcher.dispatch(ticker_push(1)).await;
+        assert!(rx1.try_recv().is_ok() && rx1.try_recv().is_ok());
+
+        dispatcher.set_dedup(true);
+        dispatcher.dispatch(ticker_push(2)).await;
+        // A subscriber registered later still gets the repeated push
+        let mut rx2 = dispatcher.register_push(3011).await;
+        assert_eq!(dispatcher.dispatch(ticker_push(2)).await, Dispatched::Push);
+        dispatcher.dispatch(ticker_push(3)).await;
+        let received = |rx: &mut mpsc::UnboundedReceiver<FutuMessage>| std::iter::from_fn(|| rx.try_recv().ok()).count();
+        assert_eq!(received(&mut rx1), 2);
+        assert_eq!(received(&mut rx2), 2);
+        assert_eq!(dispatcher.suppressed_duplicates(), BTreeMap::from([(3011, 1)]));
+
+        // Pushes that are not deduplicated always pass
+        let mut rx3 = dispatcher.register_push(3001).await;
+        dispatcher.dispatch(make_msg(3001, 0, b"same")).await;
+        dispatcher.dispatch(make_msg(3001, 0, b"same")).await;
+        assert_eq!(received(&mut rx3), 2);
+    }
+
+    #[cfg(feature = "quote")]
+    #[tokio::test]
+    async fn test_push_filters() {
+        let dispatcher = Dispatcher::new();
+        let mut all = dispatcher.register_push(3011).await;
+        let mut tencent = dispatcher.register_push_filtered(3011, PushFilter::security(1, "00700")).await;
+        let mut other = dispatcher.register_push_filtered(3011, PushFilter::security(1, "09988")).await;
+        let mut later = dispatcher
+            .register_push_filtered(3011, PushFilter::predicate(|msg| msg.body.len() > 16))
+            .await;
+
+        assert_eq!(dispatcher.dispatch(ticker_push(1)).await, Dispatched::Push);
+        assert!(all.try_recv().is_ok());
+        assert!(tencent.try_recv().is_ok());
+        assert!(other.try_recv().is_err());
+        assert!(later.try_recv().is_ok());
+
+        // Undecodable pushes name no security
+        dispatcher.dispatch(make_msg(3011, 0, b"x")).await;
+        assert!(all.try_recv().is_ok());
+        assert!(tencent.try_recv().is_err());
+        assert!(later.try_recv().is_err());
+
+        // A filtered subscriber that went away is still dropped
+        drop(other);
+        dispatcher.dispatch(ticker_push(2)).await;
+        assert_eq!(dispatcher.push_handlers.lock().await[&3011].len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_prune_push() {
+        let dispatcher = Dispatcher::new();
+        let rx1 = dispatcher.register_push(3001).await;
+        let rx2 = dispatcher.register_push(3001).await;
+        assert_eq!(dispatcher.prune_push(3001).await, 2);
+        drop(rx1);
+        assert_eq!(dispatcher.prune_push(3001).await, 1);
+        drop(rx2);
+        assert_eq!(dispatcher.prune_push(3001).await, 0);
+        assert!(!dispatcher.push_handlers.lock().await.contains_key(&3001));
+        assert_eq!(dispatcher.prune_push(3005).await, 0);
     }
 
     #[tokio::test]
@@ -148,7 +486,7 @@ mod tests {
         let msg = make_msg(3001, 50, b"priority");
         dispatcher.dispatch(msg).await;
         let received = rx_req.await.unwrap();
-        assert_eq!(received.body, b"priority");
+        assert_eq!(received.body, &b"priority"[..]);
         // Push handler should NOT have received anything
         assert!(rx_push.try_recv().is_err());
     }
@@ -160,8 +498,59 @@ mod tests {
         // First dispatch — consumed by the oneshot
         dispatcher.dispatch(make_msg(1001, 77, b"first")).await;
         let received = rx.await.unwrap();
-        assert_eq!(received.body, b"first");
+        assert_eq!(received.body, &b"first"[..]);
         // Second dispatch with same serial_no — no handler, should not panic
-        dispatcher.dispatch(make_msg(1001, 77, b"second")).await;
+        assert_eq!(dispatcher.dispatch(make_msg(1001, 77, b"second")).await, Dispatched::Unhandled);
+    }
+
+    #[tokio::test]
+    async fn test_close_races_register() {
+        let dispatcher = Dispatcher::new();
+        let before = dispatcher.register_request(1).await;
+        dispatcher.close().await;
+        assert!(dispatcher.is_closed());
+        let after = dispatcher.register_request(2).await;
+        // Both fail instead of waiting for a response that cannot come
+        assert!(before.await.is_err());
+        assert!(after.await.is_err());
+        assert_eq!(dispatcher.pending_count(), 0);
+        assert_eq!(dispatcher.dispatch(make_msg(1002, 2, b"late")).await, Dispatched::Unhandled);
+    }
+
+    #[tokio::test]
+    async fn test_drain_answers_pending_and_fails_new() {
+        let dispatcher = Arc::new(Dispatcher::new());
+        let pending = dispatcher.register_request(1).await;
+        dispatcher.drain();
+        assert!(dispatcher.register_request(2).await.await.is_err());
+
+        let idle = tokio::spawn({
+            let dispatcher = Arc::clone(&dispatcher);
+            async move { dispatcher.wait_idle().await }
+        });
+        tokio::task::yield_now().await;
+        assert!(!idle.is_finished());
+        assert_eq!(dispatcher.dispatch(make_msg(1002, 1, b"order")).await, Dispatched::Response);
+        assert_eq!(pending.await.unwrap().body, &b"order"[..]);
+        tokio::time::timeout(std::time::Duration::from_secs(5), idle).await.unwrap().unwrap();
+
+        let _rx = dispatcher.register_push(3005).await;
+        let _other = dispatcher.register_push(3011).await;
+        assert_eq!(dispatcher.clear_push().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_response_before_receiver_awaited() {
+        // The recv loop may dispatch the response before the requester
+        // gets to await it; registering before sending keeps it
+        let dispatcher = Dispatcher::new();
+        let rx = dispatcher.register_request(5).await;
+        assert_eq!(dispatcher.dispatch(make_msg(1002, 5, b"fast")).await, Dispatched::Response);
+        assert_eq!(rx.await.unwrap().body, &b"fast"[..]);
+
+        let rx = dispatcher.register_request(6).await;
+        dispatcher.cancel_request(6);
+        assert!(rx.await.is_err());
+        assert_eq!(dispatcher.dispatch(make_msg(1002, 6, b"late")).await, Dispatched::Unhandled);
     }
 }
@@ -0,0 +1,151 @@
+//! OpenD flow-control responses.
+//!
+//! When OpenD is busy or a protocol's request frequency limit is hit, the
+//! response carries a failed ret_type and a message such as "请求频率太高，
+//! 每30秒最多请求60次" instead of data. `throttle_delay` recognises these
+//! messages and estimates how long to wait; `FutuClient::request` resends
+//! throttled requests according to the `RetryPolicy`, and the request
+//! functions report the rest as `Throttled` errors.
+
+use std::time::Duration;
+
+/// Wait assumed for "busy" responses.
+const BUSY_DELAY: Duration = Duration::from_secs(1);
+/// Wait assumed for frequency limits that do not name their window.
+const FREQUENCY_DELAY: Duration = Duration::from_secs(5);
+
+const FREQUENCY_PATTERNS: [&str; 5] = ["频率太高", "太频繁", "频率过高", "frequency", "too frequent"];
+const BUSY_PATTERNS: [&str; 3] = ["繁忙", "busy", "flow control"];
+
+// Requests carrying a PacketID, which OpenD rejects when reused
+const PROTO_TRD_PLACE_ORDER: u32 = 2202;
+const PROTO_TRD_MODIFY_ORDER: u32 = 2205;
+
+/// How throttled requests are resent by `FutuClient::request`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Resends per request; 0 disables retrying.
+    pub max_retries: u32,
+    /// Longer waits than this are not retried but returned as `Throttled`.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self { max_retries: 2, max_delay: Duration::from_secs(2) }
+    }
+}
+
+impl RetryPolicy {
+    /// Wait before resending attempt `retry` (0-based) of `proto_id`, or
+    /// None if the response should be returned as it is. Order requests are
+    /// never resent since their PacketID must be unique.
+    pub fn delay(&self, proto_id: u32, retry: u32, retry_after: Duration) -> Option<Duration> {
+        if retry >= self.max_retries || retry_after > self.max_delay {
+            return None;
+        }
+        if matches!(proto_id, PROTO_TRD_PLACE_ORDER | PROTO_TRD_MODIFY_ORDER) {
+            return None;
+        }
+        Some(retry_after)
+    }
+}
+
+/// ret_type and ret_msg, the fields every response starts with.
+#[derive(Clone, PartialEq, ::prost::Message)]
+struct RetStatus {
+    #[prost(int32, required, tag = "1")]
+    ret_type: i32,
+    #[prost(string, optional, tag = "2")]
+    ret_msg: Option<String>,
+}
+
+/// Wait suggested by a throttled response, or None if `ret_type`/`msg`
+/// are not a busy or frequency limit response.
+pub fn throttle_delay(ret_type: i32, msg: &str) -> Option<Duration> {
+    if ret_type == 0 {
+        return None;
+    }
+    let lower = msg.to_lowercase();
+    if FREQUENCY_PATTERNS.iter().any(|p| lower.contains(p)) {
+        return Some(window_secs(msg).map_or(FREQUENCY_DELAY, Duration::from_secs));
+    }
+    if BUSY_PATTERNS.iter().any(|p| lower.contains(p)) {
+        return Some(BUSY_DELAY);
+    }
+    None
+}
+
+/// Whether an encoded response succeeded, judged from its first field
+/// without decoding the rest.
+pub fn is_success(body: &[u8]) -> bool {
+    // Field 1 (ret_type) as a varint: tag 0x08 followed by 0 for success
+    body.starts_with(&[0x08, 0x00])
+}
+
+/// `throttle_delay` of an encoded response.
+pub fn peek_throttled(body: &[u8]) -> Option<Duration> {
+    if is_success(body) {
+        return None;
+    }
+    let status: RetStatus = prost::Message::decode(body).ok()?;
+    throttle_delay(status.ret_type, status.ret_msg.as_deref().unwrap_or_default())
+}
+
+/// Window of a limit such as "每30秒最多请求60次" or "60 requests per 30 seconds".
+fn window_secs(msg: &str) -> Option<u64> {
+    let lower = msg.to_lowercase();
+    let end = lower.find('秒').or_else(|| lower.find(" second"))?;
+    let digits: String = lower[..end]
+        .trim_end()
+        .chars()
+        .rev()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.chars().rev().collect::<String>().parse().ok().filter(|secs| *secs > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+
+    #[test]
+    fn test_throttle_delay() {
+        assert_eq!(throttle_delay(-1, "请求频率太高，每30秒最多请求60次"), Some(Duration::from_secs(30)));
+        assert_eq!(throttle_delay(-1, "Request frequency too high, 10 requests per 3 seconds"), Some(Duration::from_secs(3)));
+        assert_eq!(throttle_delay(-1, "请求太频繁"), Some(FREQUENCY_DELAY));
+        assert_eq!(throttle_delay(-1, "服务器繁忙，请稍后再试"), Some(BUSY_DELAY));
+        assert_eq!(throttle_delay(-1, "Server busy"), Some(BUSY_DELAY));
+        assert_eq!(throttle_delay(-1, "股票代码不存在"), None);
+        assert_eq!(throttle_delay(0, "busy"), None);
+    }
+
+    #[test]
+    fn test_peek_throttled() {
+        let encode = |ret_type: i32, msg: &str| RetStatus { ret_type, ret_msg: Some(msg.to_string()) }.encode_to_vec();
+        assert_eq!(peek_throttled(&encode(0, "")), None);
+        assert_eq!(peek_throttled(&encode(-1, "busy")), Some(BUSY_DELAY));
+        assert_eq!(peek_throttled(&encode(-1, "unknown stock")), None);
+
+        // Works on full responses, whatever follows the status fields
+        let resp = crate::generated::get_global_state::Response {
+            ret_type: -1,
+            ret_msg: Some("频率太高，每30秒最多请求60次".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(peek_throttled(&resp.encode_to_vec()), Some(Duration::from_secs(30)));
+        assert_eq!(peek_throttled(b"not protobuf"), None);
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let policy = RetryPolicy::default();
+        assert_eq!(policy.delay(3004, 0, BUSY_DELAY), Some(BUSY_DELAY));
+        assert_eq!(policy.delay(3004, 2, BUSY_DELAY), None);
+        assert_eq!(policy.delay(3004, 0, Duration::from_secs(30)), None);
+        assert_eq!(policy.delay(PROTO_TRD_PLACE_ORDER, 0, BUSY_DELAY), None);
+        let off = RetryPolicy { max_retries: 0, ..Default::default() };
+        assert_eq!(off.delay(3004, 0, BUSY_DELAY), None);
+    }
+}
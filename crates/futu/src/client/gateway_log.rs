@@ -0,0 +1,277 @@
+//! Correlation of OpenD's own log with rejected requests.
+//!
+//! OpenD writes a log of its own to a directory set in its configuration.
+//! With `FutuConfig::gateway_log_dir` set, the client tails the newest file
+//! of that directory and keeps the recent lines that name a connection ID
+//! (`connID=`, `conn_id:`, ...) or a serial number (`serialNo=`, ...).
+//! Responses with a non-zero `retType` are recorded as rejections, and
+//! `rejections()` reports each with the gateway lines of its connection and
+//! serial number. Lines are matched when the report is read rather than when
+//! the response arrives, as OpenD may log a rejection after answering it.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use parking_lot::Mutex;
+
+/// Gateway lines kept for matching.
+const LINE_CAPACITY: usize = 2048;
+
+/// Rejections kept for `rejections()`.
+const REJECTION_CAPACITY: usize = 64;
+
+/// A gateway log line naming a connection or serial number.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GatewayLogLine {
+    pub conn_id: Option<u64>,
+    pub serial_no: Option<u32>,
+    pub line: String,
+}
+
+/// A response OpenD answered with a non-zero `retType`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rejection {
+    pub conn_id: u64,
+    pub proto_id: u32,
+    pub serial_no: u32,
+    pub ret_type: i32,
+    /// Unix timestamp in seconds.
+    pub timestamp: f64,
+}
+
+/// A rejection with the gateway log lines of its request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RejectionReport {
+    pub rejection: Rejection,
+    pub gateway_lines: Vec<String>,
+}
+
+#[derive(Default)]
+struct TailState {
+    file: Option<PathBuf>,
+    offset: u64,
+    /// Start of a line OpenD has not finished writing.
+    partial: String,
+    lines: VecDeque<GatewayLogLine>,
+    rejections: VecDeque<Rejection>,
+}
+
+/// Tail of the OpenD log directory and the recent rejections.
+pub struct GatewayLog {
+    dir: PathBuf,
+    state: Mutex<TailState>,
+}
+
+impl GatewayLog {
+    /// Tail `dir` from the end of its newest file; earlier lines are skipped.
+    pub fn new(dir: PathBuf) -> Self {
+        let mut state = TailState::default();
+        if let Some(file) = newest_file(&dir) {
+            state.offset = std::fs::metadata(&file).map_or(0, |m| m.len());
+            state.file = Some(file);
+        }
+        Self { dir, state: Mutex::new(state) }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Read the lines appended since the last poll. A newer file (OpenD
+    /// rotated its log) is read from the start, as is a truncated one.
+    /// Returns the number of lines kept.
+    pub fn poll(&self) -> io::Result<usize> {
+        let Some(newest) = newest_file(&self.dir) else {
+            return Ok(0);
+        };
+        let mut state = self.state.lock();
+        if state.file.as_ref() != Some(&newest) {
+            state.file = Some(newest.clone());
+            state.offset = 0;
+            state.partial.clear();
+        }
+        let mut file = File::open(&newest)?;
+        let len = file.metadata()?.len();
+        if len < state.offset {
+            state.offset = 0;
+            state.partial.clear();
+        }
+        file.seek(SeekFrom::Start(state.offset))?;
+        let mut bytes = Vec::new();
+        file.take(len - state.offset).read_to_end(&mut bytes)?;
+        state.offset += bytes.len() as u64;
+
+        let mut text = std::mem::take(&mut state.partial);
+        text.push_str(&String::from_utf8_lossy(&bytes));
+        let mut kept = 0;
+        let mut rest = text.as_str();
+        while let Some(end) = rest.find('\n') {
+            let line = rest[..end].trim_end_matches('\r');
+            rest = &rest[end + 1..];
+            let (conn_id, serial_no) = (find_id(line, "connid"), find_id(line, "serialno"));
+            if conn_id.is_none() && serial_no.is_none() {
+                continue;
+            }
+            if state.lines.len() == LINE_CAPACITY {
+                state.lines.pop_front();
+            }
+            state.lines.push_back(GatewayLogLine {
+                conn_id,
+                serial_no: serial_no.and_then(|s| u32::try_from(s).ok()),
+                line: line.to_string(),
+            });
+            kept += 1;
+        }
+        state.partial = rest.to_string();
+        Ok(kept)
+    }
+
+    /// Kept lines of request `serial_no` on connection `conn_id`. Lines
+    /// with a serial number but no connection ID match any connection.
+    pub fn lines_for(&self, conn_id: u64, serial_no: u32) -> Vec<String> {
+        if let Err(e) = self.poll() {
+            tracing::warn!("Reading OpenD log in {} failed: {}", self.dir.display(), e);
+        }
+        self.state
+            .lock()
+            .lines
+            .iter()
+            .filter(|l| l.serial_no == Some(serial_no) && l.conn_id.is_none_or(|c| c == conn_id))
+            .map(|l| l.line.clone())
+            .collect()
+    }
+
+    /// Record a rejected request, evicting the oldest when full.
+    pub fn record_rejection(&self, conn_id: u64, proto_id: u32, serial_no: u32, ret_type: i32) {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs_f64();
+        let mut state = self.state.lock();
+        if state.rejections.len() == REJECTION_CAPACITY {
+            state.rejections.pop_front();
+        }
+        state.rejections.push_back(Rejection { conn_id, proto_id, serial_no, ret_type, timestamp });
+    }
+
+    /// The recent rejections, oldest first, with their gateway lines.
+    pub fn rejections(&self) -> Vec<RejectionReport> {
+        let rejections: Vec<Rejection> = self.state.lock().rejections.iter().cloned().collect();
+        rejections
+            .into_iter()
+            .map(|rejection| RejectionReport {
+                gateway_lines: self.lines_for(rejection.conn_id, rejection.serial_no),
+                rejection,
+            })
+            .collect()
+    }
+}
+
+/// Most recently modified file of `dir`.
+fn newest_file(dir: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(dir)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            meta.is_file().then(|| (meta.modified().ok(), entry.path()))
+        })
+        .max()
+        .map(|(_, path)| path)
+}
+
+/// Number after `key` in `line`, comparing case-insensitively and ignoring
+/// underscores, so `connid` finds `connID=5`, `conn_id: 5` and `ConnId 5`.
+fn find_id(line: &str, key: &str) -> Option<u64> {
+    let norm: String = line.chars().filter(|c| *c != '_').flat_map(char::to_lowercase).collect();
+    norm.match_indices(key).find_map(|(i, _)| {
+        let value = norm[i + key.len()..].trim_start_matches([' ', '=', ':', '"', '[', '(']);
+        let digits: &str = &value[..value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len())];
+        digits.parse().ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("futu_gateway_log_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn append(path: &Path, text: &str) {
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path).unwrap();
+        file.write_all(text.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn test_find_id() {
+        assert_eq!(find_id("2024-06-03 [E] connID=7 serialNo=42 ret=-1", "connid"), Some(7));
+        assert_eq!(find_id("conn_id: 7, serial_no: 42", "serialno"), Some(42));
+        assert_eq!(find_id("SerialNo(42)", "serialno"), Some(42));
+        assert_eq!(find_id("serialNo=abc", "serialno"), None);
+        assert_eq!(find_id("nothing here", "connid"), None);
+    }
+
+    #[test]
+    fn test_tail_matches_new_lines_and_follows_rotation() {
+        let dir = temp_dir("tail");
+        let first = dir.join("GTWLog_1.log");
+        append(&first, "connID=1 serialNo=1 before tailing\n");
+        let log = GatewayLog::new(dir.clone());
+
+        append(&first, "[I] startup\nconnID=1 serialNo=5 req proto=3004\n[E] connID=1 serialNo=5 sec not found\nconnID=2 serialNo=5 other client\nserialNo=6 half");
+        assert_eq!(log.lines_for(1, 5), vec!["connID=1 serialNo=5 req proto=3004", "[E] connID=1 serialNo=5 sec not found"]);
+        assert!(log.lines_for(1, 1).is_empty());
+        append(&first, " written\n");
+        assert_eq!(log.lines_for(1, 6), vec!["serialNo=6 half written"]);
+
+        // OpenD starts a new file
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        append(&dir.join("GTWLog_2.log"), "[E] connID=1 serialNo=9 rejected\n");
+        assert_eq!(log.lines_for(1, 9), vec!["[E] connID=1 serialNo=9 rejected"]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_client_reports_rejections_with_gateway_lines() {
+        use prost::Message;
+        use crate::client::mock::MockOpenD;
+        use crate::config::FutuConfig;
+
+        let dir = temp_dir("client");
+        let mock = MockOpenD::start().await.unwrap();
+        let config = FutuConfig {
+            port: mock.port(),
+            enable_encryption: false,
+            gateway_log_dir: Some(dir.clone()),
+            ..FutuConfig::default()
+        };
+        let mut client = crate::client::FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+
+        crate::client::init::get_global_state(&client, 0).await.unwrap();
+        let rejected = crate::generated::get_global_state::Response {
+            ret_type: -1,
+            ret_msg: Some("unknown security".to_string()),
+            ..Default::default()
+        };
+        mock.respond(1002, rejected.encode_to_vec());
+        crate::client::init::get_global_state(&client, 0).await.unwrap_err();
+
+        let gateway_log = client.gateway_log().unwrap();
+        let reports = gateway_log.rejections();
+        assert_eq!(reports.len(), 1);
+        let rejection = &reports[0].rejection;
+        assert_eq!((rejection.conn_id, rejection.proto_id, rejection.ret_type), (1, 1002, -1));
+        assert!(reports[0].gateway_lines.is_empty());
+
+        let line = format!("[E] connID=1 serialNo={} GetGlobalState failed", rejection.serial_no);
+        append(&dir.join("GTWLog.log"), &format!("{}\n", line));
+        assert_eq!(gateway_log.rejections()[0].gateway_lines, vec![line]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
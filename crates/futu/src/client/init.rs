@@ -1,5 +1,7 @@
 use prost::Message;
 use crate::client::connection::{FutuConnection, ConnectionError};
+use crate::protocol::encryption::PACKET_ENC_ALGO_NONE;
+use crate::protocol::FutuMessage;
 
 /// ProtoID for InitConnect
 const PROTO_ID_INIT_CONNECT: u32 = 1001;
@@ -16,35 +18,60 @@ pub struct InitConnectResponse {
 
 /// Perform the InitConnect handshake.
 pub async fn init_connect(conn: &FutuConnection) -> Result<InitConnectResponse, InitError> {
+    let _serial = conn.send(PROTO_ID_INIT_CONNECT, &init_request(conn)).await
+        .map_err(InitError::Connection)?;
+
+    // Receive response
+    let msg = conn.recv().await.map_err(InitError::Connection)?;
+    if msg.proto_id != PROTO_ID_INIT_CONNECT {
+        return Err(InitError::UnexpectedProto(msg.proto_id));
+    }
+
+    apply_response(conn, &msg).await
+}
+
+/// Repeat the InitConnect handshake on a running client to obtain a new AES
+/// key. The response is routed by the recv loop like any other request, and
+/// the TCP connection (with its subscriptions) stays open.
+pub async fn reinit_connect(client: &crate::client::FutuClient) -> Result<InitConnectResponse, InitError> {
+    let conn = client.connection();
+    let msg = client.request(PROTO_ID_INIT_CONNECT, &init_request(conn)).await
+        .map_err(InitError::Connection)?;
+    apply_response(conn, &msg).await
+}
+
+fn init_request(conn: &FutuConnection) -> Vec<u8> {
     let c2s = crate::generated::init_connect::C2s {
         client_ver: conn.config().client_ver,
         client_id: conn.config().client_id.clone(),
         recv_notify: Some(true),
         // Encryption requires RSA keys configured in both FutuOpenD and client.
-        // -1 = PacketEncAlgo_None, 0 = FTAES_ECB
-        packet_enc_algo: Some(if conn.config().enable_encryption { 0 } else { -1 }),
-        push_proto_fmt: Some(0), // Protobuf
+        packet_enc_algo: Some(if conn.config().encryption_enabled() {
+            conn.config().aes_padding.packet_enc_algo()
+        } else {
+            PACKET_ENC_ALGO_NONE
+        }),
+        push_proto_fmt: Some(conn.config().push_proto_fmt.as_i32()),
         programming_language: Some("Rust".to_string()),
     };
 
     let request = crate::generated::init_connect::Request { c2s };
+    request.encode_to_vec()
+}
 
-    let body = request.encode_to_vec();
-    let _serial = conn.send(PROTO_ID_INIT_CONNECT, &body).await
-        .map_err(InitError::Connection)?;
-
-    // Receive response
-    let msg = conn.recv().await.map_err(InitError::Connection)?;
-    if msg.proto_id != PROTO_ID_INIT_CONNECT {
-        return Err(InitError::UnexpectedProto(msg.proto_id));
-    }
-
-    let response = crate::generated::init_connect::Response::decode(msg.body.as_slice())
-        .map_err(|e| InitError::Decode(e.to_string()))?;
+/// Check an InitConnect response and install its AES key and connection ID.
+async fn apply_response(conn: &FutuConnection, msg: &FutuMessage) -> Result<InitConnectResponse, InitError> {
+    let response = crate::protocol::decode::decode_message::<crate::generated::init_connect::Response>(
+        &msg.body,
+        msg.proto_fmt,
+        conn.config().decode_mode,
+    )
+    .map_err(InitError::Decode)?;
 
     if response.ret_type != 0 {
         return Err(InitError::ServerError {
             ret_type: response.ret_type,
+            err_code: response.err_code,
             msg: response.ret_msg.unwrap_or_default(),
         });
     }
@@ -63,12 +90,12 @@ pub async fn init_connect(conn: &FutuConnection) -> Result<InitConnectResponse,
     // Encryption requires RSA keys configured in FutuOpenD; without RSA keys,
     // the server never encrypts regardless of this setting.
     let key_bytes = result.conn_aes_key.as_bytes();
-    if conn.config().enable_encryption && key_bytes.len() == 16 {
+    if conn.config().encryption_enabled() && key_bytes.len() == 16 {
         let mut key = [0u8; 16];
         key.copy_from_slice(key_bytes);
         conn.set_cipher(&key).await;
         tracing::info!("AES-ECB encryption enabled");
-    } else if conn.config().enable_encryption {
+    } else if conn.config().encryption_enabled() {
         tracing::warn!("Encryption requested but connAESKey is {} bytes (expected 16)", key_bytes.len());
     }
 
@@ -98,12 +125,13 @@ pub async fn get_global_state(
     let msg = client.request(PROTO_ID_GET_GLOBAL_STATE, &body).await
         .map_err(InitError::Connection)?;
 
-    let response = crate::generated::get_global_state::Response::decode(msg.body.as_slice())
-        .map_err(|e| InitError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::get_global_state::Response>(&msg)
+        .map_err(InitError::Decode)?;
 
     if response.ret_type != 0 {
         return Err(InitError::ServerError {
             ret_type: response.ret_type,
+            err_code: response.err_code,
             msg: response.ret_msg.clone().unwrap_or_default(),
         });
     }
@@ -111,6 +139,36 @@ pub async fn get_global_state(
     Ok(response)
 }
 
+/// ProtoID for GetUserInfo
+const PROTO_ID_GET_USER_INFO: u32 = 1005;
+
+/// Query the logged-in user's API level, quote rights and quotas. `flag`
+/// selects UserInfoField bits; None returns every field.
+pub async fn get_user_info(
+    client: &crate::client::FutuClient,
+    flag: Option<i32>,
+) -> Result<crate::generated::get_user_info::S2c, InitError> {
+    let c2s = crate::generated::get_user_info::C2s { flag };
+    let request = crate::generated::get_user_info::Request { c2s };
+    let body = request.encode_to_vec();
+
+    let msg = client.request(PROTO_ID_GET_USER_INFO, &body).await
+        .map_err(InitError::Connection)?;
+
+    let response = client.decode::<crate::generated::get_user_info::Response>(&msg)
+        .map_err(InitError::Decode)?;
+
+    if response.ret_type != 0 {
+        return Err(InitError::ServerError {
+            ret_type: response.ret_type,
+            err_code: response.err_code,
+            msg: response.ret_msg.clone().unwrap_or_default(),
+        });
+    }
+
+    response.s2c.ok_or(InitError::MissingS2C)
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum InitError {
     #[error("connection error: {0}")]
@@ -120,7 +178,7 @@ pub enum InitError {
     #[error("decode error: {0}")]
     Decode(String),
     #[error("server error (retType={ret_type}): {msg}")]
-    ServerError { ret_type: i32, msg: String },
+    ServerError { ret_type: i32, err_code: Option<i32>, msg: String },
     #[error("missing S2C in response")]
     MissingS2C,
 }
@@ -302,4 +360,38 @@ mod tests {
         assert!(!s.qot_logined);
         assert!(s.trd_logined);
     }
+
+    #[tokio::test]
+    async fn test_get_user_info_against_mock() {
+        use crate::client::mock::MockOpenD;
+        use crate::client::FutuClient;
+        use crate::config::FutuConfig;
+
+        let mock = MockOpenD::start().await.unwrap();
+        let config = FutuConfig { port: mock.port(), enable_encryption: false, ..FutuConfig::default() };
+        let mut client = FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+
+        let response = crate::generated::get_user_info::Response {
+            ret_type: 0,
+            ret_msg: None,
+            err_code: None,
+            s2c: Some(crate::generated::get_user_info::S2c {
+                api_level: Some("LV1".to_string()),
+                hk_qot_right: Some(3),
+                us_qot_right: Some(5),
+                sub_quota: Some(100),
+                history_kl_quota: Some(30),
+                ..Default::default()
+            }),
+        };
+        mock.respond(PROTO_ID_GET_USER_INFO, response.encode_to_vec());
+        let s2c = get_user_info(&client, None).await.unwrap();
+        assert_eq!(s2c.api_level.as_deref(), Some("LV1"));
+        assert_eq!(s2c.hk_qot_right, Some(3));
+        assert_eq!(s2c.sub_quota, Some(100));
+
+        mock.respond(PROTO_ID_GET_USER_INFO, vec![0x08, 0x00]);
+        assert!(matches!(get_user_info(&client, None).await, Err(InitError::MissingS2C)));
+    }
 }
@@ -1,62 +1,205 @@
+use std::collections::VecDeque;
+use std::future::Future;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+use parking_lot::Mutex;
 use prost::Message;
-use tokio::sync::oneshot;
-use tokio::time;
+use tokio::sync::Notify;
 
+use crate::client::clock::{Clock, SharedClock};
 use crate::client::connection::{FutuConnection, ConnectionError};
 
 /// ProtoID for KeepAlive
-const PROTO_ID_KEEP_ALIVE: u32 = 1004;
+pub(crate) const PROTO_ID_KEEP_ALIVE: u32 = 1004;
 
-/// Start the keepalive heartbeat loop.
-/// Returns a JoinHandle that can be used to cancel the loop.
+const MAX_FAILURES: u32 = 3;
+
+/// Heartbeats awaiting a reply that are remembered; older ones are dropped.
+const MAX_OUTSTANDING: usize = 64;
+
+/// Heartbeat replies and round trip times of a connection.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConnectionHealth {
+    /// Unix timestamp (seconds) of the last heartbeat sent.
+    pub last_sent: Option<f64>,
+    /// Unix timestamp (seconds) of the last heartbeat reply.
+    pub last_reply: Option<f64>,
+    /// Unix timestamp (seconds) of the last message of any kind from OpenD.
+    pub last_received: Option<f64>,
+    pub heartbeats_sent: u64,
+    pub replies: u64,
+    /// Heartbeats sent since the last reply.
+    pub missed: u32,
+    /// Whether `missed` reached the configured limit.
+    pub stale: bool,
+    /// Times the connection was declared stale.
+    pub stale_count: u64,
+    pub rtt_last: Option<Duration>,
+    pub rtt_min: Option<Duration>,
+    pub rtt_max: Option<Duration>,
+    pub rtt_avg: Option<Duration>,
+}
+
+#[derive(Debug, Default)]
+struct HealthState {
+    /// Serial numbers and send times of heartbeats awaiting a reply, oldest first.
+    outstanding: VecDeque<(u32, Instant)>,
+    health: ConnectionHealth,
+    /// Sum and number of the round trips timed.
+    rtt_total: Duration,
+    rtt_count: u32,
+}
+
+/// Matches KeepAlive replies to the heartbeats sent, timing them and
+/// counting those left unanswered.
+#[derive(Debug, Default)]
+pub struct KeepaliveHealth {
+    state: Mutex<HealthState>,
+    /// Unanswered heartbeats after which the connection is stale (None never).
+    max_missed: Option<u32>,
+}
+
+impl KeepaliveHealth {
+    pub fn new(max_missed: Option<u32>) -> Self {
+        Self { state: Mutex::default(), max_missed }
+    }
+
+    pub fn snapshot(&self) -> ConnectionHealth {
+        let state = self.state.lock();
+        let mut health = state.health.clone();
+        health.missed = state.outstanding.len() as u32;
+        health.stale = self.max_missed.is_some_and(|max| health.missed >= max);
+        health
+    }
+
+    pub(crate) fn record_sent(&self, serial_no: u32, now: Instant, unix_time: f64) {
+        let mut state = self.state.lock();
+        if state.outstanding.len() == MAX_OUTSTANDING {
+            state.outstanding.pop_front();
+        }
+        state.outstanding.push_back((serial_no, now));
+        state.health.heartbeats_sent += 1;
+        state.health.last_sent = Some(unix_time);
+    }
+
+    /// Record a KeepAlive reply. Heartbeats sent before the one it answers
+    /// will not be answered any more and are forgotten.
+    pub(crate) fn record_reply(&self, serial_no: u32, now: Instant, unix_time: f64) {
+        let mut state = self.state.lock();
+        state.health.replies += 1;
+        state.health.last_reply = Some(unix_time);
+        let Some(index) = state.outstanding.iter().position(|(serial, _)| *serial == serial_no) else {
+            return;
+        };
+        let (_, sent) = state.outstanding[index];
+        state.outstanding.drain(..=index);
+        let rtt = now.saturating_duration_since(sent);
+        state.rtt_total += rtt;
+        state.rtt_count += 1;
+        let avg = state.rtt_total / state.rtt_count;
+        let h = &mut state.health;
+        h.rtt_last = Some(rtt);
+        h.rtt_min = Some(h.rtt_min.map_or(rtt, |m| m.min(rtt)));
+        h.rtt_max = Some(h.rtt_max.map_or(rtt, |m| m.max(rtt)));
+        h.rtt_avg = Some(avg);
+    }
+
+    pub(crate) fn record_received(&self, unix_time: f64) {
+        self.state.lock().health.last_received = Some(unix_time);
+    }
+
+    /// Whether the heartbeats awaiting a reply reached the limit. If so they
+    /// are forgotten, so the next stale connection is counted afresh.
+    fn take_stale(&self) -> bool {
+        let mut state = self.state.lock();
+        let stale = self.max_missed.is_some_and(|max| state.outstanding.len() as u32 >= max);
+        if stale {
+            state.outstanding.clear();
+            state.health.stale_count += 1;
+        }
+        stale
+    }
+}
+
+/// Run the keepalive heartbeat loop.
 ///
-/// When keepalive fails `MAX_FAILURES` consecutive times, a signal is sent
-/// via `failure_tx` so the recv loop can detect the dead connection.
-pub fn start_keepalive(
+/// When keepalive fails `MAX_FAILURES` consecutive times, or `health`
+/// counts too many heartbeats without a reply, `failure` is notified so the
+/// recv loop can detect the dead connection. The loop then ends, unless the
+/// client reconnects on its own; heartbeats resume on the new connection.
+pub async fn run_keepalive(
     conn: Arc<FutuConnection>,
     interval_secs: i32,
-    failure_tx: oneshot::Sender<()>,
-) -> tokio::task::JoinHandle<()> {
+    failure: Arc<Notify>,
+    health: Arc<KeepaliveHealth>,
+    clock: SharedClock,
+) {
     let interval = Duration::from_secs(interval_secs.max(1) as u64);
+    let keep_running = conn.config().reconnect;
+    keepalive_loop(&*clock, interval, &failure, keep_running, &health, || send_keepalive(&conn, clock.unix_time() as i64)).await;
+}
+
+/// Call `send` every `interval` of `clock` until it fails `MAX_FAILURES`
+/// times in a row or the connection turns stale, then notify `failure`.
+/// With `keep_running` the loop starts counting again instead of ending.
+async fn keepalive_loop<F, Fut>(
+    clock: &dyn Clock,
+    interval: Duration,
+    failure: &Notify,
+    keep_running: bool,
+    health: &KeepaliveHealth,
+    mut send: F,
+) where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<u32, ConnectionError>>,
+{
+    // The first heartbeat is due one interval after start
+    let mut next = clock.now() + interval;
+    let mut consecutive_failures: u32 = 0;
 
-    tokio::spawn(async move {
-        let mut ticker = time::interval(interval);
-        ticker.tick().await; // Skip the first immediate tick
-        let mut consecutive_failures: u32 = 0;
-        const MAX_FAILURES: u32 = 3;
-
-        loop {
-            ticker.tick().await;
-            if let Err(e) = send_keepalive(&conn).await {
-                consecutive_failures += 1;
-                if consecutive_failures >= MAX_FAILURES {
-                    tracing::error!("KeepAlive failed {} consecutive times, stopping: {}", MAX_FAILURES, e);
-                    let _ = failure_tx.send(());
-                    break;
+    loop {
+        clock.sleep_until(next).await;
+        next += interval;
+        if health.take_stale() {
+            failure.notify_one();
+            if !keep_running {
+                tracing::error!("KeepAlive unanswered {} times, connection stale, stopping", health.max_missed.unwrap_or_default());
+                break;
+            }
+            tracing::warn!("KeepAlive unanswered {} times, connection stale, waiting for reconnect", health.max_missed.unwrap_or_default());
+            consecutive_failures = 0;
+        }
+        let result = send().await;
+        if let Ok(serial_no) = result {
+            health.record_sent(serial_no, clock.now(), clock.unix_time());
+        }
+        if let Err(e) = result {
+            consecutive_failures += 1;
+            if consecutive_failures >= MAX_FAILURES {
+                failure.notify_one();
+                if keep_running {
+                    tracing::warn!("KeepAlive failed {} consecutive times, waiting for reconnect: {}", MAX_FAILURES, e);
+                    consecutive_failures = 0;
+                    continue;
                 }
-                tracing::warn!("KeepAlive failed (attempt {}/{}): {}", consecutive_failures, MAX_FAILURES, e);
-            } else {
-                consecutive_failures = 0;
+                tracing::error!("KeepAlive failed {} consecutive times, stopping: {}", MAX_FAILURES, e);
+                break;
             }
+            tracing::warn!("KeepAlive failed (attempt {}/{}): {}", consecutive_failures, MAX_FAILURES, e);
+        } else {
+            consecutive_failures = 0;
         }
-    })
+    }
 }
 
-async fn send_keepalive(conn: &FutuConnection) -> Result<(), ConnectionError> {
-    let now = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs() as i64;
-
+async fn send_keepalive(conn: &FutuConnection, now: i64) -> Result<u32, ConnectionError> {
     let c2s = crate::generated::keep_alive::C2s { time: now };
     let request = crate::generated::keep_alive::Request { c2s };
 
     let body = request.encode_to_vec();
-    conn.send(PROTO_ID_KEEP_ALIVE, &body).await?;
+    let serial_no = conn.send(PROTO_ID_KEEP_ALIVE, &body).await?;
     tracing::debug!("KeepAlive sent, time={}", now);
-    Ok(())
+    Ok(serial_no)
 }
 
 #[cfg(test)]
@@ -80,7 +223,7 @@ mod tests {
 
     #[test]
     fn test_interval_minimum_clamp() {
-        // Test the clamping logic used in start_keepalive
+        // Test the clamping logic used in run_keepalive
         fn clamp_interval(secs: i32) -> Duration {
             Duration::from_secs(secs.max(1) as u64)
         }
@@ -89,4 +232,146 @@ mod tests {
         assert_eq!(clamp_interval(-5), Duration::from_secs(1));
         assert_eq!(clamp_interval(10), Duration::from_secs(10));
     }
+
+    #[tokio::test]
+    async fn test_keepalive_loop_on_mock_clock() {
+        use crate::client::clock::MockClock;
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let clock = Arc::new(MockClock::new(0.0));
+        let failure = Arc::new(Notify::new());
+        let sends = Arc::new(AtomicU32::new(0));
+        let task = tokio::spawn({
+            let (clock, failure, sends) = (Arc::clone(&clock), Arc::clone(&failure), Arc::clone(&sends));
+            async move {
+                keepalive_loop(&*clock, Duration::from_secs(10), &failure, false, &KeepaliveHealth::default(), || {
+                    // The first heartbeat succeeds, every later one fails
+                    let ok = sends.fetch_add(1, Ordering::SeqCst) == 0;
+                    async move { if ok { Ok(1) } else { Err(ConnectionError::Disconnected) } }
+                }).await
+            }
+        });
+
+        for expected in 1..=4 {
+            tokio::task::yield_now().await;
+            assert_eq!(clock.sleepers(), 1);
+            clock.advance(Duration::from_secs(10));
+            tokio::task::yield_now().await;
+            assert_eq!(sends.load(Ordering::SeqCst), expected);
+        }
+        // Three failures in a row stop the loop and signal the recv loop
+        task.await.unwrap();
+        tokio::time::timeout(Duration::from_secs(1), failure.notified()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_keepalive_loop_keeps_running_for_reconnect() {
+        use crate::client::clock::MockClock;
+
+        let clock = Arc::new(MockClock::new(0.0));
+        let failure = Arc::new(Notify::new());
+        let task = tokio::spawn({
+            let (clock, failure) = (Arc::clone(&clock), Arc::clone(&failure));
+            async move {
+                keepalive_loop(&*clock, Duration::from_secs(10), &failure, true, &KeepaliveHealth::default(), || async { Err(ConnectionError::Disconnected) }).await
+            }
+        });
+        for _ in 0..MAX_FAILURES {
+            tokio::task::yield_now().await;
+            clock.advance(Duration::from_secs(10));
+        }
+        tokio::time::timeout(Duration::from_secs(1), failure.notified()).await.unwrap();
+        // Still sending heartbeats
+        tokio::task::yield_now().await;
+        assert_eq!(clock.sleepers(), 1);
+        assert!(!task.is_finished());
+        task.abort();
+    }
+
+    #[test]
+    fn test_health_times_replies() {
+        let health = KeepaliveHealth::new(Some(3));
+        let start = Instant::now();
+        health.record_sent(1, start, 100.0);
+        health.record_sent(2, start + Duration::from_secs(10), 110.0);
+        assert_eq!(health.snapshot().missed, 2);
+
+        // A reply to the second heartbeat: the first will not be answered
+        health.record_reply(2, start + Duration::from_millis(10_040), 110.04);
+        health.record_sent(3, start + Duration::from_secs(20), 120.0);
+        health.record_reply(3, start + Duration::from_millis(20_020), 120.02);
+        let snapshot = health.snapshot();
+        assert_eq!(snapshot.missed, 0);
+        assert_eq!(snapshot.replies, 2);
+        assert_eq!(snapshot.heartbeats_sent, 3);
+        assert_eq!(snapshot.last_reply, Some(120.02));
+        assert_eq!(snapshot.rtt_last, Some(Duration::from_millis(20)));
+        assert_eq!(snapshot.rtt_min, Some(Duration::from_millis(20)));
+        assert_eq!(snapshot.rtt_max, Some(Duration::from_millis(40)));
+        assert_eq!(snapshot.rtt_avg, Some(Duration::from_millis(30)));
+        assert!(!snapshot.stale);
+    }
+
+    #[tokio::test]
+    async fn test_unanswered_heartbeats_mark_stale() {
+        use crate::client::clock::MockClock;
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let clock = Arc::new(MockClock::new(0.0));
+        let failure = Arc::new(Notify::new());
+        let health = Arc::new(KeepaliveHealth::new(Some(2)));
+        let serial = Arc::new(AtomicU32::new(0));
+        let task = tokio::spawn({
+            let (clock, failure, health, serial) = (Arc::clone(&clock), Arc::clone(&failure), Arc::clone(&health), Arc::clone(&serial));
+            async move {
+                // Every heartbeat is sent, none is answered
+                keepalive_loop(&*clock, Duration::from_secs(10), &failure, false, &health, || {
+                    let serial_no = serial.fetch_add(1, Ordering::SeqCst) + 1;
+                    async move { Ok(serial_no) }
+                }).await
+            }
+        });
+        for _ in 0..2 {
+            tokio::task::yield_now().await;
+            clock.advance(Duration::from_secs(10));
+        }
+        tokio::task::yield_now().await;
+        assert!(health.snapshot().stale);
+        // Due for the third heartbeat: declared stale instead
+        clock.advance(Duration::from_secs(10));
+        task.await.unwrap();
+        tokio::time::timeout(Duration::from_secs(1), failure.notified()).await.unwrap();
+        assert_eq!(serial.load(Ordering::SeqCst), 2);
+        assert_eq!(health.snapshot().stale_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_client_matches_replies() {
+        use crate::client::clock::MockClock;
+        use crate::client::mock::MockOpenD;
+        use crate::client::FutuClient;
+        use crate::config::FutuConfig;
+
+        let mock = MockOpenD::start().await.unwrap();
+        let clock = Arc::new(MockClock::new(1000.0));
+        let config = FutuConfig { port: mock.port(), enable_encryption: false, clock: clock.clone(), ..FutuConfig::default() };
+        let mut client = FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+        assert_eq!(client.connection_health().heartbeats_sent, 0);
+
+        // The mock hands out a 10s interval and answers every heartbeat
+        tokio::task::yield_now().await;
+        clock.advance(Duration::from_secs(10));
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while client.connection_health().replies == 0 {
+                tokio::task::yield_now().await;
+            }
+        }).await.unwrap();
+        let health = client.connection_health();
+        assert_eq!(health.heartbeats_sent, 1);
+        assert_eq!(health.missed, 0);
+        assert_eq!(health.rtt_last, Some(Duration::ZERO));
+        assert_eq!(health.last_reply, Some(1010.0));
+        assert!(!health.stale);
+    }
 }
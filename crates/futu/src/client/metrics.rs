@@ -0,0 +1,518 @@
+//! Client metrics in the Prometheus text exposition format.
+//!
+//! `ClientMetrics` is updated by `FutuClient::request` and the recv loop;
+//! `render_prometheus` combines it with push statistics, suppressed
+//! duplicate pushes, task health, key rotations, reconnects and session
+//! state. Every sample carries an `endpoint` label so several
+//! clients can be scraped into one registry. With the `server` feature,
+//! `serve_metrics` exposes the text on `/metrics`. `FutuClient::stats`
+//! returns the same counters as a `ClientStats` snapshot, for callers that
+//! monitor the client themselves.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+use parking_lot::Mutex;
+
+use super::FutuClient;
+use super::connection::format_endpoint;
+
+/// Upper bounds (seconds) of the request latency histogram buckets.
+const LATENCY_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+#[derive(Debug, Default, Clone)]
+struct Latency {
+    /// Non-cumulative counts per bucket, plus one for +Inf.
+    buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    count: u64,
+    sum: f64,
+    failures: u64,
+    /// Responses of OpenD being busy or over a frequency limit.
+    throttled: u64,
+    /// Throttled requests that were resent.
+    retries: u64,
+}
+
+#[derive(Debug, Default, Clone)]
+struct PushCount {
+    received: u64,
+    /// Pushes no subscriber was registered for.
+    dropped: u64,
+}
+
+/// Request and push counters of one client, keyed by proto_id.
+#[derive(Debug)]
+pub struct ClientMetrics {
+    requests: Mutex<BTreeMap<u32, Latency>>,
+    pushes: Mutex<BTreeMap<u32, PushCount>>,
+    started: Instant,
+}
+
+impl Default for ClientMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ClientMetrics {
+    pub fn new() -> Self {
+        Self { requests: Mutex::default(), pushes: Mutex::default(), started: Instant::now() }
+    }
+
+    /// Time since the counters started.
+    pub fn uptime(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Request counters per proto_id.
+    pub fn request_stats(&self) -> BTreeMap<u32, RequestStats> {
+        self.requests.lock().iter().map(|(proto_id, latency)| (*proto_id, RequestStats::from(latency))).collect()
+    }
+
+    /// Push counters per proto_id, with rates averaged over `uptime`.
+    pub fn push_stats(&self, uptime: Duration) -> BTreeMap<u32, PushCounts> {
+        let secs = uptime.as_secs_f64();
+        self.pushes
+            .lock()
+            .iter()
+            .map(|(proto_id, count)| {
+                let per_sec = if secs > 0.0 { count.received as f64 / secs } else { 0.0 };
+                (*proto_id, PushCounts { received: count.received, dropped: count.dropped, per_sec })
+            })
+            .collect()
+    }
+
+    /// Record a request round trip. Failed requests count towards
+    /// `futu_request_failures_total` only.
+    pub fn record_request(&self, proto_id: u32, elapsed: Duration, ok: bool) {
+        let mut requests = self.requests.lock();
+        let latency = requests.entry(proto_id).or_default();
+        if !ok {
+            latency.failures += 1;
+            return;
+        }
+        let secs = elapsed.as_secs_f64();
+        let bucket = LATENCY_BUCKETS.iter().position(|b| secs <= *b).unwrap_or(LATENCY_BUCKETS.len());
+        latency.buckets[bucket] += 1;
+        latency.count += 1;
+        latency.sum += secs;
+    }
+
+    /// Record a busy or frequency limit response, and whether it was resent.
+    pub fn record_throttled(&self, proto_id: u32, retried: bool) {
+        let mut requests = self.requests.lock();
+        let latency = requests.entry(proto_id).or_default();
+        latency.throttled += 1;
+        if retried {
+            latency.retries += 1;
+        }
+    }
+
+    /// Record a push message and whether any subscriber received it.
+    pub fn record_push(&self, proto_id: u32, delivered: bool) {
+        let mut pushes = self.pushes.lock();
+        let count = pushes.entry(proto_id).or_default();
+        count.received += 1;
+        if !delivered {
+            count.dropped += 1;
+        }
+    }
+}
+
+/// Requests of one proto_id.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RequestStats {
+    /// Requests that got a response.
+    pub count: u64,
+    /// Requests that got no response.
+    pub failures: u64,
+    pub throttled: u64,
+    pub retries: u64,
+    /// Mean round trip of the requests that got a response.
+    pub avg_latency: Option<Duration>,
+}
+
+impl From<&Latency> for RequestStats {
+    fn from(latency: &Latency) -> Self {
+        let avg_latency = (latency.count > 0).then(|| Duration::from_secs_f64(latency.sum / latency.count as f64));
+        Self {
+            count: latency.count,
+            failures: latency.failures,
+            throttled: latency.throttled,
+            retries: latency.retries,
+            avg_latency,
+        }
+    }
+}
+
+/// Pushes of one proto_id.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PushCounts {
+    pub received: u64,
+    /// Pushes no subscriber was registered for.
+    pub dropped: u64,
+    /// Pushes received per second, averaged since the client connected.
+    pub per_sec: f64,
+}
+
+/// Connection-level counters of a client, from `FutuClient::stats`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClientStats {
+    /// `host:port` of the current connection.
+    pub endpoint: String,
+    /// Time since the client connected.
+    pub uptime: Duration,
+    pub requests: BTreeMap<u32, RequestStats>,
+    pub pushes: BTreeMap<u32, PushCounts>,
+    /// Bytes on the wire, headers included, across reconnects.
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub reconnects: u64,
+    pub key_rotations: u64,
+    pub pending_requests: usize,
+}
+
+impl ClientStats {
+    /// Requests of all proto_ids, answered or not.
+    pub fn total_requests(&self) -> u64 {
+        self.requests.values().map(|r| r.count + r.failures).sum()
+    }
+
+    /// Mean round trip of all answered requests.
+    pub fn avg_latency(&self) -> Option<Duration> {
+        let (count, secs) = self.requests.values().fold((0, 0.0), |(count, secs), r| {
+            (count + r.count, secs + r.avg_latency.unwrap_or_default().as_secs_f64() * r.count as f64)
+        });
+        (count > 0).then(|| Duration::from_secs_f64(secs / count as f64))
+    }
+
+    /// Pushes of all proto_ids received per second.
+    pub fn push_rate(&self) -> f64 {
+        self.pushes.values().map(|p| p.per_sec).sum()
+    }
+}
+
+/// One metric family: `# HELP`, `# TYPE` and its samples.
+pub struct Family<'a> {
+    out: &'a mut String,
+    name: &'static str,
+}
+
+impl<'a> Family<'a> {
+    pub fn new(out: &'a mut String, name: &'static str, kind: &str, help: &str) -> Self {
+        let _ = writeln!(out, "# HELP {} {}", name, help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        Self { out, name }
+    }
+
+    /// Write a sample of this family; `suffix` is e.g. `_bucket` for histograms.
+    pub fn sample(&mut self, suffix: &str, labels: &[(&str, &str)], value: f64) {
+        let _ = write!(self.out, "{}{}", self.name, suffix);
+        if !labels.is_empty() {
+            self.out.push('{');
+            for (i, (key, val)) in labels.iter().enumerate() {
+                if i > 0 {
+                    self.out.push(',');
+                }
+                let _ = write!(self.out, "{}=\"{}\"", key, escape_label(val));
+            }
+            self.out.push('}');
+        }
+        let _ = writeln!(self.out, " {}", format_value(value));
+    }
+}
+
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_infinite() {
+        if value > 0.0 { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Render the metrics of a client.
+pub async fn render_prometheus(client: &FutuClient) -> String {
+    let (host, port) = client.connection().endpoint();
+    let endpoint = format_endpoint(&host, port);
+    let ep = ("endpoint", endpoint.as_str());
+    let mut out = String::new();
+
+    let requests = client.metrics().requests.lock().clone();
+    let mut family = Family::new(&mut out, "futu_request_duration_seconds", "histogram", "Round trip time of OpenD requests.");
+    for (proto_id, latency) in &requests {
+        let proto_id = proto_id.to_string();
+        let mut cumulative = 0;
+        for (i, count) in latency.buckets.iter().enumerate() {
+            cumulative += count;
+            let le = LATENCY_BUCKETS.get(i).map_or("+Inf".to_string(), |b| b.to_string());
+            family.sample("_bucket", &[ep, ("proto_id", &proto_id), ("le", &le)], cumulative as f64);
+        }
+        family.sample("_sum", &[ep, ("proto_id", &proto_id)], latency.sum);
+        family.sample("_count", &[ep, ("proto_id", &proto_id)], latency.count as f64);
+    }
+    let mut family = Family::new(&mut out, "futu_request_failures_total", "counter", "OpenD requests that got no response.");
+    for (proto_id, latency) in &requests {
+        family.sample("", &[ep, ("proto_id", &proto_id.to_string())], latency.failures as f64);
+    }
+    let mut family = Family::new(&mut out, "futu_request_throttled_total", "counter", "Responses of OpenD being busy or over a frequency limit.");
+    for (proto_id, latency) in &requests {
+        family.sample("", &[ep, ("proto_id", &proto_id.to_string())], latency.throttled as f64);
+    }
+    let mut family = Family::new(&mut out, "futu_request_retries_total", "counter", "Throttled requests resent after a delay.");
+    for (proto_id, latency) in &requests {
+        family.sample("", &[ep, ("proto_id", &proto_id.to_string())], latency.retries as f64);
+    }
+
+    let pushes = client.metrics().pushes.lock().clone();
+    let mut family = Family::new(&mut out, "futu_pushes_total", "counter", "Push messages received.");
+    for (proto_id, count) in &pushes {
+        family.sample("", &[ep, ("proto_id", &proto_id.to_string())], count.received as f64);
+    }
+    let mut family = Family::new(&mut out, "futu_pushes_dropped_total", "counter", "Push messages without a subscriber.");
+    for (proto_id, count) in &pushes {
+        family.sample("", &[ep, ("proto_id", &proto_id.to_string())], count.dropped as f64);
+    }
+    let mut family = Family::new(&mut out, "futu_pushes_duplicate_total", "counter", "Push messages suppressed as duplicates, per subscriber.");
+    for (proto_id, count) in client.suppressed_duplicates() {
+        family.sample("", &[ep, ("proto_id", &proto_id.to_string())], count as f64);
+    }
+
+    #[cfg(feature = "quote")]
+    {
+        let mut family = Family::new(&mut out, "futu_quote_pushes_total", "counter", "Quote pushes per security and sub type.");
+        for stat in client.push_stats().snapshot() {
+            let market = stat.security.0.to_string();
+            let sub_type = stat.sub_type.to_string();
+            let labels = [ep, ("market", &market), ("code", &stat.security.1), ("sub_type", &sub_type)];
+            family.sample("", &labels, stat.count as f64);
+        }
+    }
+
+    let pending = client.dispatcher.pending_count();
+    Family::new(&mut out, "futu_pending_requests", "gauge", "Requests waiting for a response.")
+        .sample("", &[ep], pending as f64);
+
+    let health = client.health();
+    let mut family = Family::new(&mut out, "futu_task_restarts_total", "counter", "Restarts of supervised background tasks.");
+    for task in &health {
+        family.sample("", &[ep, ("task", &task.name)], f64::from(task.restarts));
+    }
+    let mut family = Family::new(&mut out, "futu_task_up", "gauge", "Whether a supervised background task is running.");
+    for task in &health {
+        let up = task.state == super::supervisor::TaskState::Running;
+        family.sample("", &[ep, ("task", &task.name)], f64::from(u8::from(up)));
+    }
+
+    Family::new(&mut out, "futu_aes_key_rotations_total", "counter", "AES key rotations of the connection.")
+        .sample("", &[ep], client.connection().key_rotations() as f64);
+    Family::new(&mut out, "futu_reconnects_total", "counter", "Times the connection was re-established after a disconnect.")
+        .sample("", &[ep], client.connection().reconnects() as f64);
+    Family::new(&mut out, "futu_bytes_sent_total", "counter", "Bytes sent to OpenD, headers included.")
+        .sample("", &[ep], client.connection().bytes_sent() as f64);
+    Family::new(&mut out, "futu_bytes_received_total", "counter", "Bytes received from OpenD, headers included.")
+        .sample("", &[ep], client.connection().bytes_received() as f64);
+
+    let preempted = client.session().preempted().is_some();
+    Family::new(&mut out, "futu_session_preempted", "gauge", "Whether another login has preempted the session.")
+        .sample("", &[ep], f64::from(u8::from(preempted)));
+
+    out
+}
+
+/// Time a scrape connection is given to send its request.
+#[cfg(feature = "server")]
+pub const METRICS_READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Serve `render()` on `GET /metrics` until the listener fails. Connections
+/// that send no request within `read_timeout` are closed. Scrapes run on a
+/// `JoinSet` owned by the returned future, so aborting it also ends them.
+#[cfg(feature = "server")]
+pub async fn serve_metrics<F, Fut>(listener: tokio::net::TcpListener, read_timeout: Duration, render: F) -> std::io::Result<()>
+where
+    F: Fn() -> Fut + Clone + Send + 'static,
+    Fut: std::future::Future<Output = String> + Send,
+{
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut scrapes = tokio::task::JoinSet::new();
+    loop {
+        let (mut stream, _) = tokio::select! {
+            accepted = listener.accept() => accepted?,
+            // Reap finished scrapes so the set does not grow
+            Some(_) = scrapes.join_next() => continue,
+        };
+        let render = render.clone();
+        scrapes.spawn(async move {
+            let mut buf = [0u8; 1024];
+            let Ok(Ok(n)) = tokio::time::timeout(read_timeout, stream.read(&mut buf)).await else {
+                return;
+            };
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let target = request.split_whitespace().take(2).collect::<Vec<_>>();
+            let response = match target.as_slice() {
+                ["GET", path] if path.split('?').next() == Some("/metrics") => {
+                    let body = render().await;
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                }
+                _ => "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string(),
+            };
+            let _ = stream.write_all(response.as_bytes()).await;
+            let _ = stream.shutdown().await;
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_request_latency_buckets() {
+        let metrics = ClientMetrics::new();
+        metrics.record_request(3004, Duration::from_millis(3), true);
+        metrics.record_request(3004, Duration::from_millis(40), true);
+        metrics.record_request(3004, Duration::from_secs(30), true);
+        metrics.record_request(3004, Duration::from_secs(1), false);
+
+        let requests = metrics.requests.lock();
+        let latency = &requests[&3004];
+        assert_eq!(latency.count, 3);
+        assert_eq!(latency.failures, 1);
+        assert_eq!(latency.buckets[0], 1);
+        assert_eq!(latency.buckets[3], 1);
+        assert_eq!(latency.buckets[LATENCY_BUCKETS.len()], 1);
+    }
+
+    #[test]
+    fn test_family_format() {
+        let mut out = String::new();
+        let mut family = Family::new(&mut out, "futu_pushes_total", "counter", "Push messages received.");
+        family.sample("", &[("endpoint", "[::1]:11111"), ("code", "a\"b")], 3.0);
+        family.sample("_bucket", &[("le", "+Inf")], f64::INFINITY);
+        assert_eq!(
+            out,
+            "# HELP futu_pushes_total Push messages received.\n\
+             # TYPE futu_pushes_total counter\n\
+             futu_pushes_total{endpoint=\"[::1]:11111\",code=\"a\\\"b\"} 3\n\
+             futu_pushes_total_bucket{le=\"+Inf\"} +Inf\n"
+        );
+    }
+
+    #[test]
+    fn test_throttled_counts() {
+        let metrics = ClientMetrics::new();
+        metrics.record_request(3004, Duration::from_millis(3), true);
+        metrics.record_throttled(3004, true);
+        metrics.record_throttled(3004, false);
+        let requests = metrics.requests.lock();
+        assert_eq!(requests[&3004].throttled, 2);
+        assert_eq!(requests[&3004].retries, 1);
+        assert_eq!(requests[&3004].failures, 0);
+    }
+
+    #[test]
+    fn test_push_drops() {
+        let metrics = ClientMetrics::new();
+        metrics.record_push(3011, true);
+        metrics.record_push(3011, false);
+        let pushes = metrics.pushes.lock();
+        assert_eq!(pushes[&3011].received, 2);
+        assert_eq!(pushes[&3011].dropped, 1);
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_serve_metrics() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve_metrics(listener, METRICS_READ_TIMEOUT, || async { "futu_up 1\n".to_string() }));
+
+        let get = |path: &'static str| async move {
+            let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+            stream.write_all(format!("GET {} HTTP/1.1\r\nHost: x\r\n\r\n", path).as_bytes()).await.unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).await.unwrap();
+            response
+        };
+        let response = get("/metrics").await;
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        assert!(response.ends_with("\r\n\r\nfutu_up 1\n"), "{}", response);
+        assert!(get("/").await.starts_with("HTTP/1.1 404"));
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_serve_metrics_closes_idle_and_stopped_scrapes() {
+        use tokio::io::AsyncReadExt;
+
+        let render = || async { String::new() };
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(serve_metrics(listener, Duration::from_millis(50), render));
+
+        // A connection that never sends a request is closed after the timeout
+        let mut idle = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let mut response = Vec::new();
+        tokio::time::timeout(Duration::from_secs(5), idle.read_to_end(&mut response)).await.unwrap().unwrap();
+        assert!(response.is_empty());
+        server.abort();
+
+        // Stopping the server ends the scrapes it accepted
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = tokio::spawn(serve_metrics(listener, Duration::from_secs(3600), render));
+        let mut open = tokio::net::TcpStream::connect(addr).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        server.abort();
+        let _ = server.await;
+        tokio::time::timeout(Duration::from_secs(5), open.read_to_end(&mut response)).await.unwrap().unwrap();
+        assert!(tokio::net::TcpStream::connect(addr).await.is_err());
+    }
+
+    #[test]
+    fn test_stats_snapshot() {
+        let metrics = ClientMetrics::new();
+        metrics.record_request(3004, Duration::from_millis(10), true);
+        metrics.record_request(3004, Duration::from_millis(30), true);
+        metrics.record_request(3004, Duration::from_secs(1), false);
+        metrics.record_request(2201, Duration::from_millis(80), true);
+        metrics.record_push(3011, true);
+        metrics.record_push(3011, false);
+
+        let requests = metrics.request_stats();
+        assert_eq!(requests[&3004].count, 2);
+        assert_eq!(requests[&3004].failures, 1);
+        let avg = requests[&3004].avg_latency.unwrap();
+        assert!((avg.as_secs_f64() - 0.02).abs() < 1e-9, "{:?}", avg);
+
+        let pushes = metrics.push_stats(Duration::from_secs(4));
+        assert_eq!(pushes[&3011], PushCounts { received: 2, dropped: 1, per_sec: 0.5 });
+        assert_eq!(metrics.push_stats(Duration::ZERO)[&3011].per_sec, 0.0);
+
+        let stats = ClientStats {
+            endpoint: "127.0.0.1:11111".to_string(),
+            uptime: Duration::from_secs(4),
+            requests,
+            pushes,
+            bytes_sent: 0,
+            bytes_received: 0,
+            reconnects: 0,
+            key_rotations: 0,
+            pending_requests: 0,
+        };
+        assert_eq!(stats.total_requests(), 4);
+        let avg = stats.avg_latency().unwrap();
+        assert!((avg.as_secs_f64() - 0.04).abs() < 1e-6, "{:?}", avg);
+        assert_eq!(stats.push_rate(), 0.5);
+    }
+}
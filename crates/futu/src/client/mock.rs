@@ -0,0 +1,221 @@
+//! In-process stand-in for OpenD, for tests that run without a gateway.
+//!
+//! `MockOpenD` listens on a loopback port and speaks the framing of the
+//! real gateway. It accepts InitConnect, answers every other request with
+//! the body registered for its proto_id through `respond`, or with an empty
+//! success response (`retType` 0, no s2c) otherwise, and sends the pushes
+//! given to `push` to every connected client. Bodies are sent unencrypted,
+//! so clients must connect with encryption off.
+
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use futures::{SinkExt, StreamExt};
+use parking_lot::Mutex;
+use prost::Message;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast;
+use tokio_util::codec::Framed;
+
+use crate::protocol::{FutuCodec, FutuMessage, ProtoFmt};
+
+/// ProtoID for InitConnect
+const PROTO_ID_INIT_CONNECT: u32 = 1001;
+
+/// Keepalive interval the mock hands out, in seconds.
+const KEEP_ALIVE_INTERVAL: i32 = 10;
+
+/// Pushes buffered per client before a slow client misses some.
+const PUSH_BUFFER: usize = 4096;
+
+/// `retType` 0 and nothing else; every response starts with retType at tag 1.
+const EMPTY_SUCCESS: &[u8] = &[0x08, 0x00];
+
+/// A mock OpenD serving on `127.0.0.1:port()` until dropped.
+pub struct MockOpenD {
+    port: u16,
+    responses: Arc<Mutex<HashMap<u32, Bytes>>>,
+    pushes: broadcast::Sender<FutuMessage>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl MockOpenD {
+    /// Bind a free loopback port and start serving. Must be called within a
+    /// tokio runtime.
+    pub async fn start() -> io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let port = listener.local_addr()?.port();
+        let responses: Arc<Mutex<HashMap<u32, Bytes>>> = Arc::new(Mutex::new(HashMap::new()));
+        let (pushes, _) = broadcast::channel(PUSH_BUFFER);
+
+        let (task_responses, task_pushes) = (Arc::clone(&responses), pushes.clone());
+        let task = tokio::spawn(async move {
+            let mut conn_id = 0;
+            while let Ok((stream, _)) = listener.accept().await {
+                conn_id += 1;
+                tokio::spawn(serve(stream, conn_id, Arc::clone(&task_responses), task_pushes.subscribe()));
+            }
+        });
+        tracing::info!("Mock OpenD listening on 127.0.0.1:{}", port);
+        Ok(Self { port, responses, pushes, task })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// Answer requests of `proto_id` with `body`, an encoded Response.
+    pub fn respond(&self, proto_id: u32, body: Vec<u8>) {
+        self.responses.lock().insert(proto_id, Bytes::from(body));
+    }
+
+    /// Send a push of `proto_id` to every connected client. Returns the
+    /// number of clients it was sent to.
+    pub fn push(&self, proto_id: u32, body: Vec<u8>) -> usize {
+        self.push_fmt(proto_id, ProtoFmt::Protobuf, body)
+    }
+
+    /// `push` of a body in `proto_fmt`, e.g. a JSON push.
+    pub fn push_fmt(&self, proto_id: u32, proto_fmt: ProtoFmt, body: Vec<u8>) -> usize {
+        let msg = FutuMessage { proto_id, serial_no: 0, body: Bytes::from(body), proto_fmt };
+        self.pushes.send(msg).unwrap_or(0)
+    }
+}
+
+impl Drop for MockOpenD {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Serve one client until it disconnects.
+async fn serve(
+    stream: TcpStream,
+    conn_id: u64,
+    responses: Arc<Mutex<HashMap<u32, Bytes>>>,
+    mut pushes: broadcast::Receiver<FutuMessage>,
+) {
+    let mut framed = Framed::new(stream, FutuCodec);
+    loop {
+        let msg = tokio::select! {
+            req = framed.next() => match req {
+                Some(Ok(req)) => {
+                    let body = match req.proto_id {
+                        PROTO_ID_INIT_CONNECT => init_connect_response(conn_id),
+                        proto_id => responses.lock().get(&proto_id).cloned().unwrap_or(Bytes::from_static(EMPTY_SUCCESS)),
+                    };
+                    FutuMessage { proto_id: req.proto_id, serial_no: req.serial_no, body, proto_fmt: req.proto_fmt }
+                }
+                _ => break,
+            },
+            push = pushes.recv() => match push {
+                Ok(push) => push,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    tracing::warn!("Mock OpenD client {} missed {} pushes", conn_id, missed);
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        };
+        if framed.send(msg).await.is_err() {
+            break;
+        }
+    }
+}
+
+fn init_connect_response(conn_id: u64) -> Bytes {
+    let resp = crate::generated::init_connect::Response {
+        ret_type: 0,
+        s2c: Some(crate::generated::init_connect::S2c {
+            conn_id,
+            keep_alive_interval: KEEP_ALIVE_INTERVAL,
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+    resp.encode_to_vec().into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::config::FutuConfig;
+
+    #[tokio::test]
+    async fn test_client_against_mock() {
+        let mock = MockOpenD::start().await.unwrap();
+        let config = FutuConfig { port: mock.port(), enable_encryption: false, ..FutuConfig::default() };
+        let mut client = crate::client::FutuClient::connect(config).await.unwrap();
+        assert_eq!(client.init().await.unwrap().conn_id, 1);
+
+        // Unregistered requests succeed without data
+        let state = crate::client::init::get_global_state(&client, 0).await.unwrap();
+        assert_eq!(state.ret_type, 0);
+        assert!(state.s2c.is_none());
+
+        let canned = crate::generated::get_global_state::Response {
+            ret_type: -1,
+            ret_msg: Some("closed".to_string()),
+            ..Default::default()
+        };
+        mock.respond(1002, canned.encode_to_vec());
+        let err = crate::client::init::get_global_state(&client, 0).await.unwrap_err();
+        assert!(err.to_string().contains("closed"));
+
+        let mut rx = client.subscribe_push(3011).await;
+        assert_eq!(mock.push(3011, vec![1, 2, 3]), 1);
+        let push = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await.unwrap().unwrap();
+        assert_eq!(&push.body[..], &[1, 2, 3]);
+
+        let stats = client.stats();
+        assert_eq!(stats.requests[&1002].count, 2);
+        assert_eq!(stats.pushes[&3011].received, 1);
+        assert_eq!(stats.pending_requests, 0);
+        // InitConnect and two requests went out, their responses and a push
+        // came back, each at least a header
+        let header = crate::protocol::HEADER_SIZE as u64;
+        assert!(stats.bytes_sent >= 3 * header);
+        assert!(stats.bytes_received >= 4 * header);
+    }
+
+    #[tokio::test]
+    async fn test_requests_fail_after_disconnect() {
+        let mock = MockOpenD::start().await.unwrap();
+        let config = FutuConfig { port: mock.port(), enable_encryption: false, ..FutuConfig::default() };
+        let mut client = crate::client::FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+        crate::client::init::get_global_state(&client, 0).await.unwrap();
+
+        // The recv loop is gone, so nothing would ever answer these
+        client.disconnect().await;
+        let state = tokio::time::timeout(Duration::from_secs(5), crate::client::init::get_global_state(&client, 0)).await;
+        assert!(state.expect("request hung after disconnect").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_slow_request_threshold_keeps_waiting() {
+        let mock = MockOpenD::start().await.unwrap();
+        let config = FutuConfig {
+            port: mock.port(),
+            enable_encryption: false,
+            slow_request_threshold: Some(Duration::from_secs(30)),
+            ..FutuConfig::default()
+        };
+        let mut client = crate::client::FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+        assert_eq!(client.slow_request_threshold(), Some(Duration::from_secs(30)));
+
+        // Every request outlasts a zero threshold; it is still answered
+        client.set_slow_request_threshold(Some(Duration::ZERO));
+        let state = crate::client::init::get_global_state(&client, 0).await.unwrap();
+        assert_eq!(state.ret_type, 0);
+        assert_eq!(client.stats().pending_requests, 0);
+
+        client.set_slow_request_threshold(None);
+        assert!(client.slow_request_threshold().is_none());
+        crate::client::init::get_global_state(&client, 0).await.unwrap();
+    }
+}
@@ -1,37 +1,164 @@
+pub mod cache;
+pub mod capture;
+pub mod clock;
 pub mod connection;
 pub mod init;
 pub mod keepalive;
 pub mod dispatcher;
+pub mod dedup;
+pub mod reconnect;
+pub mod flow_control;
+pub mod trace;
+pub mod supervisor;
+pub mod session;
+pub mod notify;
+pub mod metrics;
+pub mod mock;
+pub mod gateway_log;
+pub mod rate_limit;
+#[cfg(feature = "quote")]
+pub mod pool;
 
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::sync::{mpsc, oneshot};
+use std::time::{Duration, Instant};
+use futures::FutureExt;
+use tokio::sync::{mpsc, Notify};
+use tracing::Instrument;
 
 use crate::config::FutuConfig;
 use crate::protocol::FutuMessage;
+use crate::protocol::json::JsonMessage;
+#[cfg(feature = "quote")]
+use crate::quote::orderbook::OrderBookEngine;
+#[cfg(feature = "quote")]
+use crate::quote::push_stats::{PushStats, QuotePush};
+#[cfg(feature = "quote")]
+use crate::quote::sub_manager::SubscriptionManager;
+#[cfg(feature = "trade")]
+use crate::trade::history::OrderHistory;
+#[cfg(feature = "trade")]
+use crate::trade::push::AccPushAccounts;
+#[cfg(feature = "trade")]
+use crate::trade::order::{OrderGuard, DEFAULT_GUARD_WINDOW};
+#[cfg(feature = "trade")]
+use crate::trade::throttle::OrderThrottle;
 use connection::{FutuConnection, ConnectionError};
 use init::InitConnectResponse;
-use dispatcher::Dispatcher;
+use keepalive::{ConnectionHealth, KeepaliveHealth};
+use session::SessionMonitor;
+use notify::{NotifyAction, NotifyMonitor};
+use dispatcher::{Dispatched, Dispatcher, PushFilter};
+use cache::ResponseCache;
+use clock::SharedClock;
+use flow_control::RetryPolicy;
+use gateway_log::GatewayLog;
+use metrics::{ClientMetrics, ClientStats};
+use rate_limit::RateLimiter;
+use supervisor::{RestartPolicy, TaskHealth, TaskSupervisor};
 
 /// The main Futu client that manages connection, heartbeat, and message dispatch.
 pub struct FutuClient {
     conn: Arc<FutuConnection>,
     dispatcher: Arc<Dispatcher>,
-    keepalive_handle: Option<tokio::task::JoinHandle<()>>,
-    recv_handle: Option<tokio::task::JoinHandle<()>>,
+    /// Owns the keepalive and recv loop tasks.
+    supervisor: TaskSupervisor,
+    /// Per-security quote push statistics, updated by the recv loop.
+    #[cfg(feature = "quote")]
+    push_stats: Arc<PushStats>,
+    /// Order books kept from order book pushes, updated by the recv loop.
+    #[cfg(feature = "quote")]
+    order_books: Arc<OrderBookEngine>,
+    /// Quote subscriptions made through `subscribe`, replayed after a reconnect.
+    #[cfg(feature = "quote")]
+    subscriptions: Arc<SubscriptionManager>,
+    /// Order amendment chains, updated by trade calls and the recv loop.
+    #[cfg(feature = "trade")]
+    order_history: Arc<OrderHistory>,
+    /// Accounts subscribed to trade pushes, replayed after a reconnect.
+    #[cfg(feature = "trade")]
+    acc_push: Arc<AccPushAccounts>,
+    /// Preemption by another login of the account, from Notify pushes.
+    session: Arc<SessionMonitor>,
+    /// Latest gateway notifications, from Notify pushes.
+    notify: Arc<NotifyMonitor>,
+    /// Client-side budgets for order requests.
+    #[cfg(feature = "trade")]
+    order_throttle: OrderThrottle,
+    /// Recent submissions of `place_order_idempotent`.
+    #[cfg(feature = "trade")]
+    order_guard: OrderGuard,
+    /// Request latencies and push counts, updated by `request` and the recv loop.
+    metrics: Arc<ClientMetrics>,
+    /// Resending of requests OpenD answered as busy or over its frequency limit.
+    retry_policy: parking_lot::Mutex<RetryPolicy>,
+    /// Whether the protocols of `trade::funds` may be used.
+    funds_protocols: AtomicBool,
+    /// Responses of reference data requests, consulted by `request`.
+    response_cache: ResponseCache,
+    /// Per-protocol budgets taken by every request sent.
+    rate_limiter: RateLimiter,
+    /// Wait after which an unanswered request is logged as slow.
+    slow_request_threshold: parking_lot::Mutex<Option<Duration>>,
+    /// Heartbeat replies and round trips, updated by keepalive and the recv loop.
+    keepalive_health: Arc<KeepaliveHealth>,
+    /// Tail of the OpenD log and the rejected requests, if configured.
+    gateway_log: Option<GatewayLog>,
     init_response: Option<InitConnectResponse>,
 }
 
 impl FutuClient {
     /// Create a new FutuClient and connect to OpenD.
     pub async fn connect(config: FutuConfig) -> Result<Self, ConnectionError> {
+        let session = Arc::new(SessionMonitor::new(config.pause_trading_on_preempt));
+        #[cfg(feature = "trade")]
+        let order_throttle = OrderThrottle::with_clock(config.order_throttle.clone(), Arc::clone(&config.clock));
+        #[cfg(feature = "trade")]
+        let order_guard = OrderGuard::with_clock(DEFAULT_GUARD_WINDOW, Arc::clone(&config.clock));
+        let retry_policy = parking_lot::Mutex::new(config.retry_policy.clone());
+        let funds_protocols = AtomicBool::new(config.enable_funds_protocols);
+        let response_cache = ResponseCache::new(config.response_cache.clone());
+        let rate_limiter = RateLimiter::with_clock(config.rate_limit.clone(), Arc::clone(&config.clock));
+        let slow_request_threshold = parking_lot::Mutex::new(config.slow_request_threshold);
+        let dedup_pushes = config.dedup_pushes;
+        let keepalive_health = Arc::new(KeepaliveHealth::new(config.keepalive_max_missed));
+        let gateway_log = config.gateway_log_dir.clone().map(GatewayLog::new);
+        #[cfg(feature = "quote")]
+        let subscriptions = SubscriptionManager::new();
+        #[cfg(feature = "quote")]
+        subscriptions.set_quota(config.sub_quota);
         let conn = Arc::new(FutuConnection::connect(config).await?);
         let dispatcher = Arc::new(Dispatcher::new());
+        dispatcher.set_dedup(dedup_pushes);
 
         Ok(Self {
             conn,
             dispatcher,
-            keepalive_handle: None,
-            recv_handle: None,
+            supervisor: TaskSupervisor::new(),
+            #[cfg(feature = "quote")]
+            push_stats: Arc::new(PushStats::new()),
+            #[cfg(feature = "quote")]
+            order_books: Arc::new(OrderBookEngine::default()),
+            #[cfg(feature = "quote")]
+            subscriptions: Arc::new(subscriptions),
+            #[cfg(feature = "trade")]
+            order_history: Arc::new(OrderHistory::new()),
+            #[cfg(feature = "trade")]
+            acc_push: Arc::new(AccPushAccounts::new()),
+            session,
+            notify: Arc::new(NotifyMonitor::new()),
+            #[cfg(feature = "trade")]
+            order_throttle,
+            #[cfg(feature = "trade")]
+            order_guard,
+            metrics: Arc::new(ClientMetrics::new()),
+            retry_policy,
+            funds_protocols,
+            response_cache,
+            rate_limiter,
+            slow_request_threshold,
+            keepalive_health,
+            gateway_log,
             init_response: None,
         })
     }
@@ -46,48 +173,51 @@ impl FutuClient {
         let resp = init::init_connect(&self.conn).await?;
         tracing::info!("InitConnect success, keepalive_interval={}s", resp.keep_alive_interval);
 
-        // Start keepalive with failure notification channel
-        let (ka_fail_tx, ka_fail_rx) = oneshot::channel();
-        let keepalive_handle = keepalive::start_keepalive(
-            Arc::clone(&self.conn),
-            resp.keep_alive_interval,
-            ka_fail_tx,
-        );
-        self.keepalive_handle = Some(keepalive_handle);
+        // Both loops are restarted if they panic; a normal exit means the
+        // connection is gone and is left to the caller to handle.
+        let policy = RestartPolicy::OnPanic { max_restarts: 3, backoff: Duration::from_secs(1) };
+
+        // Start keepalive with failure notification
+        let ka_failure = Arc::new(Notify::new());
+        let conn = Arc::clone(&self.conn);
+        let failure = Arc::clone(&ka_failure);
+        let interval = resp.keep_alive_interval;
+        let health = Arc::clone(&self.keepalive_health);
+        let clock = Arc::clone(self.clock());
+        self.supervisor.spawn("keepalive", policy, move || {
+            keepalive::run_keepalive(Arc::clone(&conn), interval, Arc::clone(&failure), Arc::clone(&health), Arc::clone(&clock))
+        });
 
         // Start receive loop — also monitors keepalive failure signal
         let conn = Arc::clone(&self.conn);
         let dispatcher = Arc::clone(&self.dispatcher);
-        let recv_handle = tokio::spawn(async move {
-            tracing::debug!("Recv loop started");
-            let mut ka_fail_rx = ka_fail_rx;
-            loop {
-                tokio::select! {
-                    result = conn.recv() => {
-                        match result {
-                            Ok(msg) => {
-                                dispatcher.dispatch(msg).await;
-                            }
-                            Err(ConnectionError::Disconnected) => {
-                                tracing::warn!("Connection disconnected");
-                                break;
-                            }
-                            Err(e) => {
-                                tracing::error!("Receive error: {}", e);
-                                break;
-                            }
-                        }
-                    }
-                    _ = &mut ka_fail_rx => {
-                        tracing::warn!("Keepalive failure detected, closing recv loop");
-                        break;
-                    }
-                }
-            }
-            // Clear pending requests so callers don't hang forever
-            dispatcher.clear_pending().await;
+        let observers = PushObservers {
+            #[cfg(feature = "quote")]
+            push_stats: Arc::clone(&self.push_stats),
+            #[cfg(feature = "quote")]
+            order_books: Arc::clone(&self.order_books),
+            #[cfg(feature = "quote")]
+            subscriptions: Arc::clone(&self.subscriptions),
+            #[cfg(feature = "trade")]
+            order_history: Arc::clone(&self.order_history),
+            #[cfg(feature = "trade")]
+            acc_push: Arc::clone(&self.acc_push),
+            notify: Arc::clone(&self.notify),
+        };
+        let session = Arc::clone(&self.session);
+        let metrics = Arc::clone(&self.metrics);
+        let health = Arc::clone(&self.keepalive_health);
+        self.supervisor.spawn("recv_loop", policy, move || {
+            recv_loop(
+                Arc::clone(&conn),
+                Arc::clone(&dispatcher),
+                observers.clone(),
+                Arc::clone(&session),
+                Arc::clone(&metrics),
+                Arc::clone(&health),
+                Arc::clone(&ka_failure),
+            )
         });
-        self.recv_handle = Some(recv_handle);
 
         self.init_response = Some(resp);
         // SAFETY: init_response was set to Some on the line above
@@ -95,12 +225,158 @@ impl FutuClient {
     }
 
     /// Send a request and wait for the response.
+    /// Protocols with a response cache TTL are answered from the cache while
+    /// an identical request's response is fresh. Responses of OpenD being
+    /// busy or over a frequency limit are resent according to the retry
+    /// policy; the last one is returned as is.
     pub async fn request(&self, proto_id: u32, body: &[u8]) -> Result<FutuMessage, ConnectionError> {
-        // Register BEFORE sending to avoid race with recv loop
-        let serial_no = self.conn.next_serial();
-        let rx = self.dispatcher.register_request(serial_no).await;
-        self.conn.send_with_serial(proto_id, body, serial_no).await?;
-        rx.await.map_err(|_| ConnectionError::Disconnected)
+        let span = tracing::debug_span!(
+            "futu_request",
+            proto_id,
+            serial_no = tracing::field::Empty,
+            cached = false,
+            retries = 0u32,
+            encode_us = tracing::field::Empty,
+            wait_us = tracing::field::Empty,
+            ret_type = tracing::field::Empty,
+        );
+        async {
+            if let Some(resp) = self.response_cache.get(proto_id, body, self.clock().now()) {
+                tracing::Span::current().record("cached", true);
+                return Ok(resp);
+            }
+            let resp = self.request_with_retry(proto_id, body).await?;
+            self.response_cache.insert(proto_id, body, &resp, self.clock().now());
+            Ok(resp)
+        }
+        .instrument(span)
+        .await
+    }
+
+    async fn request_with_retry(&self, proto_id: u32, body: &[u8]) -> Result<FutuMessage, ConnectionError> {
+        let mut retry = 0;
+        loop {
+            tracing::Span::current().record("retries", retry);
+            let resp = self.request_once(proto_id, body).await?;
+            let Some(retry_after) = flow_control::peek_throttled(&resp.body) else {
+                return Ok(resp);
+            };
+            let delay = self.retry_policy.lock().delay(proto_id, retry, retry_after);
+            self.metrics.record_throttled(proto_id, delay.is_some());
+            let Some(delay) = delay else {
+                return Ok(resp);
+            };
+            tracing::debug!("OpenD throttled proto {}, retrying in {:?}", proto_id, delay);
+            self.clock().sleep(delay).await;
+            retry += 1;
+        }
+    }
+
+    async fn request_once(&self, proto_id: u32, body: &[u8]) -> Result<FutuMessage, ConnectionError> {
+        self.rate_limiter.acquire(proto_id).await?;
+        let slow = self.slow_request_threshold().map(|threshold| (self.clock(), threshold));
+        let start = Instant::now();
+        let result = send_request_watched(&self.conn, &self.dispatcher, proto_id, body, slow).await;
+        let elapsed = start.elapsed();
+        self.metrics.record_request(proto_id, elapsed, result.is_ok());
+        if let Ok(resp) = &result {
+            let ret_type = trace::peek_ret_type(&resp.body);
+            if let Some(ret_type) = ret_type {
+                tracing::Span::current().record("ret_type", ret_type);
+            }
+            if let (Some(gateway_log), Some(ret_type)) = (&self.gateway_log, ret_type.filter(|r| *r != 0)) {
+                gateway_log.record_rejection(self.conn.conn_id().await, proto_id, resp.serial_no, ret_type);
+            }
+            if slow.is_some_and(|(_, threshold)| elapsed >= threshold) {
+                tracing::warn!(proto_id, serial_no = resp.serial_no, ret_type, "Slow request answered after {:?}", elapsed);
+            }
+        }
+        result
+    }
+
+    /// Wait after which an unanswered request is logged as slow, if set.
+    pub fn slow_request_threshold(&self) -> Option<Duration> {
+        *self.slow_request_threshold.lock()
+    }
+
+    /// Log requests unanswered after `threshold` as warnings; None stops it.
+    pub fn set_slow_request_threshold(&self, threshold: Option<Duration>) {
+        *self.slow_request_threshold.lock() = threshold;
+    }
+
+    /// Rotate the AES key: repeat InitConnect on the open connection and
+    /// switch to the key it returns. OpenD sends no notification when a key
+    /// should change, so long-lived connections rotate on their own schedule.
+    /// Subscriptions and push handlers are kept.
+    pub async fn rekey(&self) -> Result<InitConnectResponse, init::InitError> {
+        init::reinit_connect(self).await
+    }
+
+    /// Current policy for resending throttled requests.
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy.lock().clone()
+    }
+
+    /// Replace the policy for resending throttled requests.
+    pub fn set_retry_policy(&self, policy: RetryPolicy) {
+        *self.retry_policy.lock() = policy;
+    }
+
+    /// Whether the cash flow and funds protocols are enabled.
+    pub fn funds_protocols(&self) -> bool {
+        self.funds_protocols.load(Ordering::Relaxed)
+    }
+
+    /// Enable or disable the cash flow and funds protocols.
+    pub fn set_funds_protocols(&self, enabled: bool) {
+        self.funds_protocols.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Enable or disable suppressing pushes a subscriber already received.
+    pub fn set_dedup_pushes(&self, enabled: bool) {
+        self.dispatcher.set_dedup(enabled);
+    }
+
+    /// Pushes suppressed as duplicates, keyed by proto_id.
+    pub fn suppressed_duplicates(&self) -> std::collections::BTreeMap<u32, u64> {
+        self.dispatcher.suppressed_duplicates()
+    }
+
+    /// Read-through cache of reference data responses.
+    pub fn response_cache(&self) -> &ResponseCache {
+        &self.response_cache
+    }
+
+    /// Client-side per-protocol request rate limits.
+    pub fn rate_limiter(&self) -> &RateLimiter {
+        &self.rate_limiter
+    }
+
+    /// Rejected requests with their OpenD log lines; None unless
+    /// `FutuConfig::gateway_log_dir` is set.
+    pub fn gateway_log(&self) -> Option<&GatewayLog> {
+        self.gateway_log.as_ref()
+    }
+
+    /// Time source of the client's timers, rate limits and caches.
+    pub fn clock(&self) -> &SharedClock {
+        &self.conn.config().clock
+    }
+
+    /// Decode the body of a response or push, in the format its header
+    /// gives, with the configured `DecodeMode`.
+    pub fn decode<M: prost::Message + Default + JsonMessage>(&self, msg: &FutuMessage) -> Result<M, String> {
+        let span = tracing::debug_span!(
+            "futu_decode",
+            message = std::any::type_name::<M>().trim_start_matches("nautilus_futu::generated::"),
+            body_len = msg.body.len(),
+            decode_us = tracing::field::Empty,
+        );
+        let _entered = span.enter();
+        let start = Instant::now();
+        let result = crate::protocol::decode::decode_message(&msg.body, msg.proto_fmt, self.conn.config().decode_mode);
+        span.record("decode_us", start.elapsed().as_micros() as u64);
+        result
     }
 
     /// Send a message without waiting for response (fire-and-forget).
@@ -113,42 +389,511 @@ impl FutuClient {
         self.dispatcher.register_push(proto_id).await
     }
 
+    /// Register a handler for the pushes of `proto_id` that pass `filter`,
+    /// e.g. those of one security.
+    pub async fn subscribe_push_filtered(&self, proto_id: u32, filter: PushFilter) -> mpsc::UnboundedReceiver<FutuMessage> {
+        self.dispatcher.register_push_filtered(proto_id, filter).await
+    }
+
+    /// Unregister push handlers of `proto_id` whose receivers were dropped.
+    /// Returns the number still registered.
+    pub async fn prune_push(&self, proto_id: u32) -> usize {
+        self.dispatcher.prune_push(proto_id).await
+    }
+
+    /// Order, fill, funds and position changes of `account` (trd_env,
+    /// acc_id, trd_market) as one stream; see `trade::account_stream`.
+    /// Must be called within a tokio runtime; dropping the stream stops it.
+    #[cfg(feature = "trade")]
+    pub fn account_stream(
+        self: &Arc<Self>,
+        account: crate::trade::account_stream::Account,
+        options: crate::trade::account_stream::AccountStreamOptions,
+    ) -> crate::trade::account_stream::AccountStream {
+        crate::trade::account_stream::AccountStream::new(Arc::clone(self), account, options)
+    }
+
+    /// Candles of `security` (market, code) and `kl_type` assembled from
+    /// K-line pushes; see `quote::kline_stream`. Dropping the stream stops it.
+    #[cfg(feature = "quote")]
+    pub async fn kline_stream(
+        self: &Arc<Self>,
+        security: (i32, String),
+        kl_type: i32,
+        options: crate::quote::kline_stream::KlineStreamOptions,
+    ) -> Result<crate::quote::kline_stream::KlineStream, crate::quote::subscribe::QuoteError> {
+        crate::quote::kline_stream::KlineStream::start(Arc::clone(self), security, kl_type, options).await
+    }
+
+    /// Lifecycle events of every order pushed to this connection; see
+    /// `trade::tracker`. Dropping the stream stops it.
+    #[cfg(feature = "trade")]
+    pub async fn order_events(self: &Arc<Self>) -> crate::trade::tracker::OrderEventStream {
+        crate::trade::tracker::OrderEventStream::start(Arc::clone(self)).await
+    }
+
     /// Get the connection reference.
     pub fn connection(&self) -> &Arc<FutuConnection> {
         &self.conn
     }
 
+    /// Recent sent/received message summaries, oldest first.
+    pub fn trace_snapshot(&self) -> Vec<trace::TraceEntry> {
+        self.conn.trace().snapshot()
+    }
+
     /// Get the init response.
     pub fn init_response(&self) -> Option<&InitConnectResponse> {
         self.init_response.as_ref()
     }
 
+    /// Quote push statistics per security and sub type.
+    #[cfg(feature = "quote")]
+    pub fn push_stats(&self) -> &Arc<PushStats> {
+        &self.push_stats
+    }
+
+    /// Order books kept from order book pushes; see `quote::orderbook`.
+    #[cfg(feature = "quote")]
+    pub fn order_books(&self) -> &Arc<OrderBookEngine> {
+        &self.order_books
+    }
+
+    /// Quote subscriptions made through `subscribe`.
+    #[cfg(feature = "quote")]
+    pub fn subscriptions(&self) -> &Arc<SubscriptionManager> {
+        &self.subscriptions
+    }
+
+    /// Amendment chains of orders placed, modified or updated on this client.
+    #[cfg(feature = "trade")]
+    pub fn order_history(&self) -> &Arc<OrderHistory> {
+        &self.order_history
+    }
+
+    /// Accounts subscribed to trade pushes through `trade::push`.
+    #[cfg(feature = "trade")]
+    pub fn acc_push(&self) -> &Arc<AccPushAccounts> {
+        &self.acc_push
+    }
+
+    /// Preemption state of the session.
+    pub fn session(&self) -> &Arc<SessionMonitor> {
+        &self.session
+    }
+
+    /// Latest gateway notifications such as quote rights and API quotas.
+    pub fn notify(&self) -> &Arc<NotifyMonitor> {
+        &self.notify
+    }
+
+    /// Client-side budgets for order requests.
+    #[cfg(feature = "trade")]
+    pub fn order_throttle(&self) -> &OrderThrottle {
+        &self.order_throttle
+    }
+
+    /// Recent submissions of `place_order_idempotent`.
+    #[cfg(feature = "trade")]
+    pub fn order_guard(&self) -> &OrderGuard {
+        &self.order_guard
+    }
+
+    /// Request latencies and push counts.
+    pub fn metrics(&self) -> &Arc<ClientMetrics> {
+        &self.metrics
+    }
+
+    /// Snapshot of the connection's request, push, byte and reconnect
+    /// counters.
+    pub fn stats(&self) -> ClientStats {
+        let (host, port) = self.conn.endpoint();
+        let uptime = self.metrics.uptime();
+        ClientStats {
+            endpoint: connection::format_endpoint(&host, port),
+            uptime,
+            requests: self.metrics.request_stats(),
+            pushes: self.metrics.push_stats(uptime),
+            bytes_sent: self.conn.bytes_sent(),
+            bytes_received: self.conn.bytes_received(),
+            reconnects: self.conn.reconnects(),
+            key_rotations: self.conn.key_rotations(),
+            pending_requests: self.dispatcher.pending_count(),
+        }
+    }
+
+    /// Health of the client's background tasks.
+    pub fn health(&self) -> Vec<TaskHealth> {
+        self.supervisor.health()
+    }
+
+    /// Heartbeat replies, round trip times and staleness of the connection.
+    pub fn connection_health(&self) -> ConnectionHealth {
+        self.keepalive_health.snapshot()
+    }
+
     /// Clear all pending requests so callers get `Disconnected` instead of hanging.
     pub async fn clear_pending(&self) {
         self.dispatcher.clear_pending().await;
     }
 
+    /// Fail all pending requests and every request made from now on with
+    /// `Disconnected`, for shutting down while other tasks still hold the
+    /// client.
+    pub async fn close_requests(&self) {
+        self.dispatcher.close().await;
+    }
+
+    /// Disconnect gracefully: stop accepting requests, give those in flight
+    /// up to `timeout` to be answered (e.g. order placements), drop the
+    /// quote subscriptions and push handlers, then close the socket once
+    /// everything queued is written. Requests still unanswered at the
+    /// deadline fail with `Disconnected`.
+    pub async fn shutdown(&self, timeout: Duration) -> ShutdownReport {
+        self.dispatcher.drain();
+        let in_flight = self.dispatcher.pending_count();
+        if tokio::time::timeout(timeout, self.dispatcher.wait_idle()).await.is_err() {
+            tracing::warn!("Shutdown deadline passed with {} requests unanswered", self.dispatcher.pending_count());
+        }
+        let abandoned = self.dispatcher.pending_count();
+
+        // No response is awaited, so the request does not need the dispatcher
+        #[cfg(feature = "quote")]
+        let unsubscribed = if self.subscriptions.current_subscriptions().is_empty() {
+            false
+        } else {
+            let sent = self.conn.send(crate::quote::subscribe::PROTO_QOT_SUB, &crate::quote::subscribe::unsub_all_request()).await;
+            self.subscriptions.clear();
+            sent.is_ok()
+        };
+        #[cfg(not(feature = "quote"))]
+        let unsubscribed = false;
+        let push_handlers = self.dispatcher.clear_push().await;
+
+        self.dispatcher.close().await;
+        self.supervisor.abort_all();
+        self.conn.close().await;
+        tracing::info!("Shut down connection to Futu OpenD");
+        ShutdownReport { answered: in_flight.saturating_sub(abandoned), abandoned, unsubscribed, push_handlers }
+    }
+
     /// Disconnect and clean up.
     pub async fn disconnect(&mut self) {
-        // Clear pending requests first so callers get Disconnected error
-        self.dispatcher.clear_pending().await;
-        if let Some(handle) = self.keepalive_handle.take() {
-            handle.abort();
+        // Close the dispatcher first so callers get Disconnected error,
+        // including those that register while the tasks are aborted
+        self.dispatcher.close().await;
+        self.supervisor.abort_all();
+        tracing::info!("Disconnected from Futu OpenD");
+    }
+}
+
+/// What `FutuClient::shutdown` did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ShutdownReport {
+    /// Requests in flight that were answered within the deadline.
+    pub answered: usize,
+    /// Requests in flight still unanswered at the deadline.
+    pub abandoned: usize,
+    /// Whether quote subscriptions were dropped at OpenD.
+    pub unsubscribed: bool,
+    /// Push handlers dropped.
+    pub push_handlers: usize,
+}
+
+/// State the recv loop updates from every push or restores after a
+/// reconnect, per enabled feature.
+#[derive(Clone)]
+struct PushObservers {
+    #[cfg(feature = "quote")]
+    push_stats: Arc<PushStats>,
+    #[cfg(feature = "quote")]
+    order_books: Arc<OrderBookEngine>,
+    #[cfg(feature = "quote")]
+    subscriptions: Arc<SubscriptionManager>,
+    #[cfg(feature = "trade")]
+    order_history: Arc<OrderHistory>,
+    #[cfg(feature = "trade")]
+    acc_push: Arc<AccPushAccounts>,
+    notify: Arc<NotifyMonitor>,
+}
+
+impl PushObservers {
+    fn record(&self, msg: &FutuMessage, now: f64) {
+        // Decoded once here and shared by the quote observers
+        #[cfg(feature = "quote")]
+        if let Some(push) = QuotePush::decode(msg.proto_id, &msg.body) {
+            self.push_stats.record_decoded(&push, now);
+            self.order_books.record_decoded(&push, now);
         }
-        if let Some(handle) = self.recv_handle.take() {
-            handle.abort();
+        #[cfg(feature = "trade")]
+        self.order_history.record_push(msg.proto_id, &msg.body, now);
+        // Nothing to record when built without quote and trade
+        let _ = (msg, now);
+    }
+
+    /// Carry out what a Notify push asks of the client.
+    fn react(&self, action: NotifyAction, conn: &Arc<FutuConnection>, dispatcher: &Arc<Dispatcher>) {
+        match action {
+            NotifyAction::Resubscribe => {
+                tracing::info!("Quote rights regained, resubscribing");
+                self.resubscribe(conn, dispatcher);
+            }
+            NotifyAction::SetSubQuota(quota) => {
+                // Only replaces a quota the config asked to check locally
+                #[cfg(feature = "quote")]
+                if self.subscriptions.quota().is_some() {
+                    self.subscriptions.set_quota(Some(quota));
+                }
+                let _ = quota;
+            }
         }
-        tracing::info!("Disconnected from Futu OpenD");
     }
+
+    /// Replay the recorded quote subscriptions in the background.
+    fn resubscribe(&self, conn: &Arc<FutuConnection>, dispatcher: &Arc<Dispatcher>) {
+        #[cfg(feature = "quote")]
+        tokio::spawn({
+            let (conn, dispatcher, subscriptions) = (Arc::clone(conn), Arc::clone(dispatcher), Arc::clone(&self.subscriptions));
+            async move { reconnect::replay_subscriptions(&conn, &dispatcher, &subscriptions).await }
+        });
+        // Nothing to replay when built without quote
+        let _ = (conn, dispatcher);
+    }
+
+    /// Subscribe the recorded trade push accounts of a new connection in the
+    /// background.
+    fn resubscribe_acc_push(&self, conn: &Arc<FutuConnection>, dispatcher: &Arc<Dispatcher>) {
+        #[cfg(feature = "trade")]
+        tokio::spawn({
+            let (conn, dispatcher, accounts) = (Arc::clone(conn), Arc::clone(dispatcher), Arc::clone(&self.acc_push));
+            async move { reconnect::replay_acc_push(&conn, &dispatcher, &accounts).await }
+        });
+        // Nothing to replay when built without trade
+        let _ = (conn, dispatcher);
+    }
+}
+
+/// Send a request on `conn` and wait for the response `dispatcher` routes back.
+#[cfg(any(feature = "quote", feature = "trade"))]
+async fn send_request(
+    conn: &FutuConnection,
+    dispatcher: &Dispatcher,
+    proto_id: u32,
+    body: &[u8],
+) -> Result<FutuMessage, ConnectionError> {
+    send_request_watched(conn, dispatcher, proto_id, body, None).await
 }
 
-impl Drop for FutuClient {
+/// `send_request`, logging a warning if no response arrived after the
+/// threshold of `slow` on its clock. Records serial_no, encode_us (encrypting
+/// and queueing for the writer) and wait_us on the current span.
+async fn send_request_watched(
+    conn: &FutuConnection,
+    dispatcher: &Dispatcher,
+    proto_id: u32,
+    body: &[u8],
+    slow: Option<(&SharedClock, Duration)>,
+) -> Result<FutuMessage, ConnectionError> {
+    let span = tracing::Span::current();
+    // Register BEFORE sending to avoid race with recv loop
+    let serial_no = conn.next_serial();
+    span.record("serial_no", serial_no);
+    let mut rx = dispatcher.register_request(serial_no).await;
+    // Unregister if the send fails or the caller stops waiting; after the
+    // response arrived this finds nothing to remove
+    let _pending = PendingRequest { dispatcher, serial_no };
+    let start = Instant::now();
+    conn.send_with_serial(proto_id, body, serial_no).await?;
+    span.record("encode_us", start.elapsed().as_micros() as u64);
+
+    let start = Instant::now();
+    let resp = match slow {
+        Some((clock, threshold)) => tokio::select! {
+            resp = &mut rx => resp,
+            _ = clock.sleep(threshold) => {
+                tracing::warn!(proto_id, serial_no, "Request unanswered after {:?}", threshold);
+                rx.await
+            }
+        },
+        None => rx.await,
+    };
+    span.record("wait_us", start.elapsed().as_micros() as u64);
+    resp.map_err(|_| ConnectionError::Disconnected)
+}
+
+/// Removes a request's dispatcher entry when dropped.
+struct PendingRequest<'a> {
+    dispatcher: &'a Dispatcher,
+    serial_no: u32,
+}
+
+impl Drop for PendingRequest<'_> {
     fn drop(&mut self) {
-        if let Some(handle) = self.keepalive_handle.take() {
-            handle.abort();
+        self.dispatcher.cancel_request(self.serial_no);
+    }
+}
+
+/// Receive messages and dispatch them until the connection closes or
+/// keepalive fails, then reconnect if the config allows it and carry on.
+async fn recv_loop(
+    conn: Arc<FutuConnection>,
+    dispatcher: Arc<Dispatcher>,
+    observers: PushObservers,
+    session: Arc<SessionMonitor>,
+    metrics: Arc<ClientMetrics>,
+    health: Arc<KeepaliveHealth>,
+    ka_failure: Arc<Notify>,
+) {
+    tracing::debug!("Recv loop started");
+    loop {
+        receive(&conn, &dispatcher, &observers, &session, &metrics, &health, &ka_failure).await;
+        // Clear pending requests so callers don't hang forever
+        dispatcher.clear_pending().await;
+        if !conn.config().reconnect || reconnect::reconnect(&conn).await.is_none() {
+            // Nothing will receive responses any more: fail requests that
+            // register from now on instead of leaving them waiting
+            dispatcher.close().await;
+            break;
         }
-        if let Some(handle) = self.recv_handle.take() {
-            handle.abort();
+        // Requests sent on the lost stream get no response, and a keepalive
+        // failure signalled while reconnecting is stale
+        dispatcher.clear_pending().await;
+        let _ = ka_failure.notified().now_or_never();
+        observers.resubscribe(&conn, &dispatcher);
+        observers.resubscribe_acc_push(&conn, &dispatcher);
+    }
+}
+
+/// Receive messages and dispatch them until the connection closes or keepalive fails.
+async fn receive(
+    conn: &Arc<FutuConnection>,
+    dispatcher: &Arc<Dispatcher>,
+    observers: &PushObservers,
+    session: &SessionMonitor,
+    metrics: &ClientMetrics,
+    health: &KeepaliveHealth,
+    ka_failure: &Notify,
+) {
+    loop {
+        tokio::select! {
+            result = conn.recv() => {
+                match result {
+                    Ok(msg) => {
+                        let now = conn.config().clock.unix_time();
+                        health.record_received(now);
+                        observers.record(&msg, now);
+                        session.record_push(msg.proto_id, &msg.body, now);
+                        if let Some(action) = observers.notify.record_push(msg.proto_id, &msg.body, now) {
+                            observers.react(action, conn, dispatcher);
+                        }
+                        let (proto_id, serial_no) = (msg.proto_id, msg.serial_no);
+                        match dispatcher.dispatch(msg).await {
+                            // Keepalives are sent without waiting for the reply
+                            Dispatched::Response => {}
+                            Dispatched::Unhandled if proto_id == keepalive::PROTO_ID_KEEP_ALIVE => {
+                                health.record_reply(serial_no, conn.config().clock.now(), now);
+                            }
+                            Dispatched::Push => metrics.record_push(proto_id, true),
+                            Dispatched::Unhandled => metrics.record_push(proto_id, false),
+                        }
+                    }
+                    Err(ConnectionError::Disconnected) => {
+                        tracing::warn!("Connection disconnected");
+                        break;
+                    }
+                    Err(e) => {
+                        tracing::error!("Receive error: {}", e);
+                        break;
+                    }
+                }
+            }
+            _ = ka_failure.notified() => {
+                tracing::warn!("Keepalive failure detected, closing recv loop");
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A connection to a peer that reads requests and never answers them.
+    async fn silent_peer() -> (FutuConnection, tokio::net::TcpStream) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = FutuConfig { port: listener.local_addr().unwrap().port(), enable_encryption: false, ..FutuConfig::default() };
+        let conn = FutuConnection::connect(config).await.unwrap();
+        let (peer, _) = listener.accept().await.unwrap();
+        (conn, peer)
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_close_fails_inflight_and_later_requests() {
+        let (conn, _peer) = silent_peer().await;
+        let dispatcher = Dispatcher::new();
+
+        // Registered and sent, waiting for the response when the client closes
+        let mut inflight = Box::pin(send_request_watched(&conn, &dispatcher, 1002, b"", None));
+        assert!(futures::poll!(&mut inflight).is_pending());
+        assert_eq!(dispatcher.pending_count(), 1);
+        dispatcher.close().await;
+        assert!(matches!(inflight.await, Err(ConnectionError::Disconnected)));
+
+        // Registered after closing: fails at once rather than waiting forever
+        let late = tokio::time::timeout(Duration::from_secs(5), send_request_watched(&conn, &dispatcher, 1002, b"", None)).await;
+        assert!(matches!(late, Ok(Err(ConnectionError::Disconnected))));
+        assert_eq!(dispatcher.pending_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_times_out_unanswered_requests() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let config = FutuConfig { port: listener.local_addr().unwrap().port(), enable_encryption: false, ..FutuConfig::default() };
+        let client = Arc::new(FutuClient::connect(config).await.unwrap());
+        let (mut peer, _) = listener.accept().await.unwrap();
+        let inflight = tokio::spawn({
+            let client = Arc::clone(&client);
+            async move { client.request(1002, b"").await }
+        });
+        while client.dispatcher.pending_count() == 0 {
+            tokio::task::yield_now().await;
         }
+
+        let report = client.shutdown(Duration::from_millis(50)).await;
+        assert_eq!((report.answered, report.abandoned), (0, 1));
+        assert!(matches!(inflight.await.unwrap(), Err(ConnectionError::Disconnected)));
+        assert!(matches!(client.request(1002, b"").await, Err(ConnectionError::Disconnected)));
+        // The peer reads the request, then the closed stream
+        let mut read = Vec::new();
+        tokio::io::AsyncReadExt::read_to_end(&mut peer, &mut read).await.unwrap();
+        assert_eq!(read.len(), crate::protocol::HEADER_SIZE);
+    }
+
+    #[cfg(feature = "quote")]
+    #[tokio::test]
+    async fn test_shutdown_drops_subscriptions_and_pushes() {
+        let mock = mock::MockOpenD::start().await.unwrap();
+        let config = FutuConfig { port: mock.port(), enable_encryption: false, ..FutuConfig::default() };
+        let mut client = FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+        crate::quote::subscribe::subscribe(&client, vec![(1, "00700".to_string())], [4], true).await.unwrap();
+        let mut rx = client.subscribe_push(3011).await;
+
+        let report = client.shutdown(Duration::from_secs(5)).await;
+        assert_eq!(report, ShutdownReport { answered: 0, abandoned: 0, unsubscribed: true, push_handlers: 1 });
+        assert!(client.subscriptions().current_subscriptions().is_empty());
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_abandoned_request_unregisters() {
+        let (conn, _peer) = silent_peer().await;
+        let dispatcher = Dispatcher::new();
+        let mut request = Box::pin(send_request_watched(&conn, &dispatcher, 1002, b"", None));
+        assert!(futures::poll!(&mut request).is_pending());
+        assert_eq!(dispatcher.pending_count(), 1);
+        drop(request);
+        assert_eq!(dispatcher.pending_count(), 0);
     }
 }
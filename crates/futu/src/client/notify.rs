@@ -0,0 +1,262 @@
+//! Gateway notifications OpenD pushes on proto 1003.
+//!
+//! Besides the preemption `session` watches for, Notify pushes report
+//! gateway events (failed logins, forced upgrades), OpenD's program and
+//! connection status, changed quote rights and the account's API level and
+//! quotas. The recv loop feeds every push into a `NotifyMonitor`, which keeps
+//! the latest values, broadcasts each notification as a `NotifyEvent` and
+//! returns what the client should do about it: replay the quote
+//! subscriptions when real-time quote rights come back, as OpenD stops
+//! pushing the quotes of a market while its rights are lost, and adopt a new
+//! subscription quota.
+
+use parking_lot::Mutex;
+use prost::Message;
+use tokio::sync::mpsc;
+
+use crate::generated::notify;
+use super::session::{downgraded, rights_of};
+
+pub const PROTO_NOTIFY: u32 = 1003;
+
+// NotifyType values
+pub const NOTIFY_TYPE_GTW_EVENT: i32 = 1;
+pub const NOTIFY_TYPE_PROGRAM_STATUS: i32 = 2;
+pub const NOTIFY_TYPE_CONN_STATUS: i32 = 3;
+pub const NOTIFY_TYPE_QOT_RIGHT: i32 = 4;
+pub const NOTIFY_TYPE_API_LEVEL: i32 = 5;
+pub const NOTIFY_TYPE_API_QUOTA: i32 = 6;
+pub const NOTIFY_TYPE_USED_QUOTA: i32 = 7;
+
+/// One Notify push.
+#[derive(Debug, Clone, PartialEq)]
+pub enum NotifyKind {
+    /// Gateway event, e.g. a failed login or a forced upgrade (GtwEventType).
+    GtwEvent { event_type: i32, desc: String },
+    /// OpenD's state (Common.ProgramStatusType).
+    ProgramStatus { status_type: i32, desc: Option<String> },
+    ConnectStatus { qot_logined: bool, trd_logined: bool },
+    QotRight(notify::QotRight),
+    ApiLevel(String),
+    ApiQuota { sub_quota: i32, history_kl_quota: i32 },
+    UsedQuota { used_sub_quota: Option<i32>, used_kl_quota: Option<i32> },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotifyEvent {
+    pub kind: NotifyKind,
+    /// Unix timestamp (seconds) of receipt.
+    pub timestamp: f64,
+}
+
+impl NotifyEvent {
+    /// The notification of `s2c`, or None if its type is unknown or the
+    /// message it announces is missing.
+    pub fn from_s2c(s2c: &notify::S2c, timestamp: f64) -> Option<Self> {
+        let kind = match s2c.r#type {
+            NOTIFY_TYPE_GTW_EVENT => s2c.event.as_ref().map(|e| NotifyKind::GtwEvent {
+                event_type: e.event_type,
+                desc: e.desc.clone(),
+            }),
+            NOTIFY_TYPE_PROGRAM_STATUS => s2c.program_status.as_ref().map(|p| NotifyKind::ProgramStatus {
+                status_type: p.r#type,
+                desc: p.str_ext_desc.clone(),
+            }),
+            NOTIFY_TYPE_CONN_STATUS => s2c.connect_status.map(|c| NotifyKind::ConnectStatus {
+                qot_logined: c.qot_logined,
+                trd_logined: c.trd_logined,
+            }),
+            NOTIFY_TYPE_QOT_RIGHT => s2c.qot_right.map(NotifyKind::QotRight),
+            NOTIFY_TYPE_API_LEVEL => s2c.api_level.as_ref().map(|l| NotifyKind::ApiLevel(l.api_level.clone())),
+            NOTIFY_TYPE_API_QUOTA => s2c.api_quota.map(|q| NotifyKind::ApiQuota {
+                sub_quota: q.sub_quota,
+                history_kl_quota: q.history_kl_quota,
+            }),
+            NOTIFY_TYPE_USED_QUOTA => s2c.used_quota.map(|q| NotifyKind::UsedQuota {
+                used_sub_quota: q.used_sub_quota,
+                used_kl_quota: q.used_k_line_quota,
+            }),
+            _ => None,
+        }?;
+        Some(Self { kind, timestamp })
+    }
+}
+
+/// What the client does about a notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NotifyAction {
+    /// Subscribe the recorded quote subscriptions again.
+    Resubscribe,
+    /// Check subscriptions against OpenD's new quota.
+    SetSubQuota(u32),
+}
+
+#[derive(Debug, Default)]
+struct NotifyState {
+    last_event: Option<NotifyEvent>,
+    /// Latest quote rights per market (hk, us, cn, hk option, hk future).
+    rights: Option<[i32; 5]>,
+    api_level: Option<String>,
+    /// (subscription quota, history K-line quota)
+    api_quota: Option<(i32, i32)>,
+    subscribers: Vec<mpsc::UnboundedSender<NotifyEvent>>,
+}
+
+/// Latest gateway notifications and their subscribers.
+#[derive(Debug, Default)]
+pub struct NotifyMonitor {
+    state: Mutex<NotifyState>,
+}
+
+impl NotifyMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Receive every notification from now on.
+    pub fn subscribe(&self) -> mpsc::UnboundedReceiver<NotifyEvent> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.state.lock().subscribers.push(tx);
+        rx
+    }
+
+    pub fn last_event(&self) -> Option<NotifyEvent> {
+        self.state.lock().last_event.clone()
+    }
+
+    /// Quote rights of the latest QotRight notification (hk, us, cn, hk
+    /// option, hk future), as Qot_Common.QotRight values.
+    pub fn qot_rights(&self) -> Option<[i32; 5]> {
+        self.state.lock().rights
+    }
+
+    pub fn api_level(&self) -> Option<String> {
+        self.state.lock().api_level.clone()
+    }
+
+    /// Subscription and history K-line quotas of the latest APIQuota
+    /// notification.
+    pub fn api_quota(&self) -> Option<(i32, i32)> {
+        self.state.lock().api_quota
+    }
+
+    /// Record a push message. Other proto ids and undecodable bodies are ignored.
+    pub(crate) fn record_push(&self, proto_id: u32, body: &[u8], now: f64) -> Option<NotifyAction> {
+        if proto_id != PROTO_NOTIFY {
+            return None;
+        }
+        let s2c = notify::Response::decode(body).ok()?.s2c?;
+        self.handle_notify(&s2c, now)
+    }
+
+    /// Apply one Notify message.
+    pub(crate) fn handle_notify(&self, s2c: &notify::S2c, now: f64) -> Option<NotifyAction> {
+        let event = NotifyEvent::from_s2c(s2c, now)?;
+        let mut state = self.state.lock();
+        let action = match &event.kind {
+            NotifyKind::GtwEvent { event_type, desc } => {
+                tracing::info!("OpenD gateway event {}: {}", event_type, desc);
+                None
+            }
+            NotifyKind::QotRight(right) => {
+                let rights = rights_of(right);
+                // Rights regained: a market went from BMP or none back to real time
+                let regained = state.rights.replace(rights).is_some_and(|previous| downgraded(&rights, &previous));
+                regained.then_some(NotifyAction::Resubscribe)
+            }
+            NotifyKind::ApiLevel(level) => {
+                state.api_level = Some(level.clone());
+                None
+            }
+            NotifyKind::ApiQuota { sub_quota, history_kl_quota } => {
+                state.api_quota = Some((*sub_quota, *history_kl_quota));
+                u32::try_from(*sub_quota).ok().map(NotifyAction::SetSubQuota)
+            }
+            _ => None,
+        };
+        state.last_event = Some(event.clone());
+        state.subscribers.retain(|tx| tx.send(event.clone()).is_ok());
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::mock::MockOpenD;
+    use crate::client::FutuClient;
+    use crate::config::FutuConfig;
+    use crate::generated::notify::{ApiQuota, GtwEvent, QotRight};
+
+    fn qot_right(hk: i32) -> notify::S2c {
+        notify::S2c {
+            r#type: NOTIFY_TYPE_QOT_RIGHT,
+            qot_right: Some(QotRight { hk_qot_right: hk, us_qot_right: 2, cn_qot_right: 5, ..Default::default() }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_regained_rights_resubscribe() {
+        let monitor = NotifyMonitor::new();
+        let mut rx = monitor.subscribe();
+        // The first rights are no change
+        assert_eq!(monitor.handle_notify(&qot_right(3), 0.0), None);
+        assert_eq!(monitor.handle_notify(&qot_right(1), 1.0), None);
+        assert_eq!(monitor.handle_notify(&qot_right(3), 2.0), Some(NotifyAction::Resubscribe));
+        assert_eq!(monitor.qot_rights(), Some([3, 2, 5, 0, 0]));
+        assert_eq!(std::iter::from_fn(|| rx.try_recv().ok()).count(), 3);
+    }
+
+    #[test]
+    fn test_quota_and_events() {
+        let monitor = NotifyMonitor::new();
+        let quota = notify::S2c {
+            r#type: NOTIFY_TYPE_API_QUOTA,
+            api_quota: Some(ApiQuota { sub_quota: 300, history_kl_quota: 100 }),
+            ..Default::default()
+        };
+        let body = notify::Response { ret_type: 0, s2c: Some(quota), ..Default::default() }.encode_to_vec();
+        assert_eq!(monitor.record_push(PROTO_NOTIFY, &body, 1.0), Some(NotifyAction::SetSubQuota(300)));
+        assert_eq!(monitor.record_push(3005, &body, 1.0), None);
+        assert_eq!(monitor.api_quota(), Some((300, 100)));
+
+        let upgrade = notify::S2c {
+            r#type: NOTIFY_TYPE_GTW_EVENT,
+            event: Some(GtwEvent { event_type: 3, desc: "OpenD needs an update".to_string() }),
+            ..Default::default()
+        };
+        assert_eq!(monitor.handle_notify(&upgrade, 2.0), None);
+        let expected = NotifyKind::GtwEvent { event_type: 3, desc: "OpenD needs an update".to_string() };
+        assert_eq!(monitor.last_event().map(|e| e.kind), Some(expected));
+        // Announced message missing
+        let empty = notify::S2c { r#type: NOTIFY_TYPE_API_LEVEL, ..Default::default() };
+        assert_eq!(monitor.handle_notify(&empty, 3.0), None);
+        assert_eq!(monitor.last_event().map(|e| e.timestamp), Some(2.0));
+    }
+
+    #[tokio::test]
+    async fn test_client_records_notify_pushes() {
+        let mock = MockOpenD::start().await.unwrap();
+        let config = FutuConfig { port: mock.port(), enable_encryption: false, sub_quota: Some(100), ..FutuConfig::default() };
+        let mut client = FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+        let mut rx = client.notify().subscribe();
+
+        let quota = notify::S2c {
+            r#type: NOTIFY_TYPE_API_QUOTA,
+            api_quota: Some(ApiQuota { sub_quota: 300, history_kl_quota: 100 }),
+            ..Default::default()
+        };
+        mock.push(PROTO_NOTIFY, notify::Response { ret_type: 0, s2c: Some(quota), ..Default::default() }.encode_to_vec());
+        let event = rx.recv().await.unwrap();
+        assert!(matches!(event.kind, NotifyKind::ApiQuota { sub_quota: 300, .. }));
+        // The recv loop adopts the quota after broadcasting the event
+        #[cfg(feature = "quote")]
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            while client.subscriptions().quota() != Some(300) {
+                tokio::task::yield_now().await;
+            }
+        }).await.unwrap();
+    }
+}
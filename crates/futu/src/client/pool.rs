@@ -0,0 +1,366 @@
+//! Several OpenD connections used as one.
+//!
+//! Every OpenD connection has its own subscription quota and request
+//! limits, so a heavy quote workload can be spread over several.
+//! `FutuClientPool` connects one `FutuClient` per config and routes each
+//! security it subscribes to one of them with a `RoutingStrategy`. A routed
+//! security stays on its client until it is unsubscribed from every sub
+//! type, so further subscriptions, unsubscriptions and its pushes use the
+//! same connection. Requests without a security go to the client the
+//! strategy picks, and `subscribe_push` merges the pushes of all clients.
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use parking_lot::Mutex;
+use tokio::sync::mpsc;
+
+use crate::config::FutuConfig;
+use crate::protocol::FutuMessage;
+use crate::quote::subscribe::{self, QuoteError, SubOptions, SubResult};
+use super::connection::ConnectionError;
+use super::init::InitError;
+use super::FutuClient;
+
+type SecurityKey = (i32, String);
+
+/// Load of one pool member, as seen by a `RoutingStrategy`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemberLoad {
+    pub index: usize,
+    /// Subscribed (security, sub type) pairs, including those being routed.
+    pub subscriptions: u32,
+    /// Subscription quota left, if a quota is set on the member.
+    pub remaining_quota: Option<u32>,
+    /// Requests awaiting a response.
+    pub pending_requests: usize,
+}
+
+/// Picks the pool member that serves a security or a request.
+pub trait RoutingStrategy: Debug + Send + Sync + 'static {
+    /// Index into `members` of the member for `security`, or for a request
+    /// without one when None. `members` is never empty.
+    fn route(&self, security: Option<&SecurityKey>, members: &[MemberLoad]) -> usize;
+}
+
+pub type SharedStrategy = Arc<dyn RoutingStrategy>;
+
+/// Members in turn.
+#[derive(Debug, Default)]
+pub struct RoundRobin {
+    next: AtomicUsize,
+}
+
+impl RoutingStrategy for RoundRobin {
+    fn route(&self, _security: Option<&SecurityKey>, members: &[MemberLoad]) -> usize {
+        self.next.fetch_add(1, Ordering::Relaxed) % members.len()
+    }
+}
+
+/// Securities to the member with the most quota left, or the fewest
+/// subscriptions without quotas; requests to the one with the fewest
+/// pending requests. Ties go to the lowest index.
+#[derive(Debug, Default)]
+pub struct LeastLoaded;
+
+impl RoutingStrategy for LeastLoaded {
+    fn route(&self, security: Option<&SecurityKey>, members: &[MemberLoad]) -> usize {
+        let best = match security {
+            Some(_) => members.iter().min_by_key(|m| (std::cmp::Reverse(m.remaining_quota), m.subscriptions)),
+            None => members.iter().min_by_key(|m| m.pending_requests),
+        };
+        best.map_or(0, |m| m.index)
+    }
+}
+
+/// Securities by a hash of market and code, so a security maps to the same
+/// member across runs of the same pool size; requests in turn.
+#[derive(Debug, Default)]
+pub struct HashBySecurity {
+    requests: RoundRobin,
+}
+
+impl RoutingStrategy for HashBySecurity {
+    fn route(&self, security: Option<&SecurityKey>, members: &[MemberLoad]) -> usize {
+        let Some(security) = security else {
+            return self.requests.route(None, members);
+        };
+        let mut hasher = DefaultHasher::new();
+        security.hash(&mut hasher);
+        (hasher.finish() % members.len() as u64) as usize
+    }
+}
+
+/// The built-in strategy named `name`: "round_robin", "least_loaded" or
+/// "hash".
+pub fn strategy_from_name(name: &str) -> Result<SharedStrategy, String> {
+    match name {
+        "round_robin" => Ok(Arc::new(RoundRobin::default())),
+        "least_loaded" => Ok(Arc::new(LeastLoaded)),
+        "hash" => Ok(Arc::new(HashBySecurity::default())),
+        other => Err(format!("unknown routing strategy '{}', expected round_robin, least_loaded or hash", other)),
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PoolError {
+    #[error("a pool needs at least one connection")]
+    Empty,
+    #[error("connection {index} of the pool failed: {source}")]
+    Member { index: usize, source: InitError },
+}
+
+/// A pool of OpenD connections with subscriptions routed across them.
+pub struct FutuClientPool {
+    clients: Vec<Arc<FutuClient>>,
+    strategy: SharedStrategy,
+    /// Member index of every subscribed security.
+    routes: Mutex<HashMap<SecurityKey, usize>>,
+}
+
+impl FutuClientPool {
+    /// Connect and initialize one client per config.
+    pub async fn connect(configs: Vec<FutuConfig>, strategy: SharedStrategy) -> Result<Self, PoolError> {
+        let mut clients = Vec::with_capacity(configs.len());
+        for (index, config) in configs.into_iter().enumerate() {
+            let member = |source| PoolError::Member { index, source };
+            let mut client = FutuClient::connect(config).await.map_err(|e| member(InitError::Connection(e)))?;
+            client.init().await.map_err(member)?;
+            clients.push(Arc::new(client));
+        }
+        Self::from_clients(clients, strategy)
+    }
+
+    /// A pool of clients that are already connected and initialized.
+    pub fn from_clients(clients: Vec<Arc<FutuClient>>, strategy: SharedStrategy) -> Result<Self, PoolError> {
+        if clients.is_empty() {
+            return Err(PoolError::Empty);
+        }
+        Ok(Self { clients, strategy, routes: Mutex::new(HashMap::new()) })
+    }
+
+    pub fn len(&self) -> usize {
+        self.clients.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clients.is_empty()
+    }
+
+    pub fn clients(&self) -> &[Arc<FutuClient>] {
+        &self.clients
+    }
+
+    /// Member index serving `security`, if it is subscribed.
+    pub fn route_of(&self, security: &SecurityKey) -> Option<usize> {
+        self.routes.lock().get(security).copied()
+    }
+
+    /// The client serving `security`, if it is subscribed.
+    pub fn client_for(&self, security: &SecurityKey) -> Option<&Arc<FutuClient>> {
+        self.route_of(security).map(|index| &self.clients[index])
+    }
+
+    pub fn loads(&self) -> Vec<MemberLoad> {
+        self.clients
+            .iter()
+            .enumerate()
+            .map(|(index, client)| MemberLoad {
+                index,
+                subscriptions: client.subscriptions().used_quota(),
+                remaining_quota: client.subscriptions().remaining_quota(),
+                pending_requests: client.stats().pending_requests,
+            })
+            .collect()
+    }
+
+    /// Subscribe (or unsubscribe) `securities` on the members serving them,
+    /// routing securities not yet subscribed first. Members are asked
+    /// concurrently. Returns one result per security in input order;
+    /// unsubscribing a security the pool does not hold is reported as a
+    /// rejection.
+    pub async fn subscribe(
+        &self,
+        securities: Vec<SecurityKey>,
+        sub_types: Vec<i32>,
+        is_sub: bool,
+        options: &SubOptions,
+    ) -> Result<Vec<SubResult>, QuoteError> {
+        let mut batches: Vec<Vec<SecurityKey>> = vec![Vec::new(); self.clients.len()];
+        let mut results: HashMap<SecurityKey, SubResult> = HashMap::new();
+        {
+            let mut routes = self.routes.lock();
+            let mut loads = self.loads();
+            for security in &securities {
+                let index = match routes.get(security) {
+                    Some(&index) => index,
+                    None if is_sub => {
+                        let index = self.strategy.route(Some(security), &loads).min(loads.len() - 1);
+                        // Later securities of the batch see this one's load
+                        let load = &mut loads[index];
+                        load.subscriptions += sub_types.len() as u32;
+                        load.remaining_quota = load.remaining_quota.map(|q| q.saturating_sub(sub_types.len() as u32));
+                        routes.insert(security.clone(), index);
+                        index
+                    }
+                    None => {
+                        let error = Some("not subscribed in the pool".to_string());
+                        results.insert(security.clone(), SubResult { security: security.clone(), error });
+                        continue;
+                    }
+                };
+                if !batches[index].contains(security) {
+                    batches[index].push(security.clone());
+                }
+            }
+        }
+
+        let requests = batches.iter().enumerate().filter(|(_, batch)| !batch.is_empty()).map(|(index, batch)| {
+            let client = &self.clients[index];
+            subscribe::subscribe_with_options(client, batch.clone(), sub_types.clone(), is_sub, options)
+        });
+        let outcomes = futures::future::join_all(requests).await;
+
+        // Securities stay routed while their member holds any of their sub types
+        {
+            let mut routes = self.routes.lock();
+            for (index, batch) in batches.iter().enumerate() {
+                for security in batch {
+                    if self.clients[index].subscriptions().sub_types(security).is_empty() {
+                        routes.remove(security);
+                    }
+                }
+            }
+        }
+        for outcome in outcomes {
+            results.extend(outcome?.into_iter().map(|r| (r.security.clone(), r)));
+        }
+        Ok(securities.iter().filter_map(|s| results.get(s).cloned()).collect())
+    }
+
+    /// Unsubscribe everything on every member, concurrently, and forget the
+    /// routes. Returns the pairs released per member, in member order.
+    pub async fn unsubscribe_all(&self) -> Result<Vec<u32>, QuoteError> {
+        let outcomes = futures::future::join_all(self.clients.iter().map(|client| subscribe::unsubscribe_all(client))).await;
+        // Members that succeeded hold nothing; the others keep their routes
+        self.routes.lock().retain(|security, index| !self.clients[*index].subscriptions().sub_types(security).is_empty());
+        outcomes.into_iter().collect()
+    }
+
+    /// Send a request on the member the strategy picks.
+    pub async fn request(&self, proto_id: u32, body: &[u8]) -> Result<FutuMessage, ConnectionError> {
+        let index = self.strategy.route(None, &self.loads()).min(self.clients.len() - 1);
+        self.clients[index].request(proto_id, body).await
+    }
+
+    /// Pushes of `proto_id` from every member, as one receiver. Must be
+    /// called within a tokio runtime; forwarding stops once the receiver is
+    /// dropped.
+    pub async fn subscribe_push(&self, proto_id: u32) -> mpsc::UnboundedReceiver<FutuMessage> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        for client in &self.clients {
+            let mut member = client.subscribe_push(proto_id).await;
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                while let Some(msg) = member.recv().await {
+                    if tx.send(msg).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        rx
+    }
+
+    /// Disconnect every member. Members still shared elsewhere fail their
+    /// requests from now on instead.
+    pub async fn disconnect(self) {
+        for client in self.clients {
+            match Arc::try_unwrap(client) {
+                Ok(mut client) => client.disconnect().await,
+                Err(shared) => shared.close_requests().await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::mock::MockOpenD;
+
+    fn load(index: usize, subscriptions: u32, remaining_quota: Option<u32>, pending_requests: usize) -> MemberLoad {
+        MemberLoad { index, subscriptions, remaining_quota, pending_requests }
+    }
+
+    #[test]
+    fn test_strategies() {
+        let members = [load(0, 5, None, 3), load(1, 2, None, 0), load(2, 2, None, 1)];
+        let tencent = (1, "00700".to_string());
+
+        let least = LeastLoaded;
+        assert_eq!(least.route(Some(&tencent), &members), 1);
+        assert_eq!(least.route(None, &members), 1);
+        // The most quota left beats the fewest subscriptions
+        let quotas = [load(0, 5, Some(95), 0), load(1, 2, Some(8), 0)];
+        assert_eq!(least.route(Some(&tencent), &quotas), 0);
+
+        let rr = RoundRobin::default();
+        let picks: Vec<usize> = (0..4).map(|_| rr.route(Some(&tencent), &members)).collect();
+        assert_eq!(picks, vec![0, 1, 2, 0]);
+
+        let hash = HashBySecurity::default();
+        let first = hash.route(Some(&tencent), &members);
+        assert!((0..10).all(|_| hash.route(Some(&tencent), &members) == first));
+
+        assert!(strategy_from_name("hash").is_ok());
+        assert!(strategy_from_name("random").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pool_routes_subscriptions() {
+        let mock = MockOpenD::start().await.unwrap();
+        let config = FutuConfig { port: mock.port(), enable_encryption: false, ..FutuConfig::default() };
+        let pool = FutuClientPool::connect(vec![config.clone(), config], Arc::new(RoundRobin::default())).await.unwrap();
+        assert_eq!(pool.len(), 2);
+
+        let securities = vec![(1, "00700".to_string()), (1, "09988".to_string()), (11, "AAPL".to_string())];
+        let results = pool.subscribe(securities.clone(), vec![1], true, &SubOptions::default()).await.unwrap();
+        assert!(results.iter().all(SubResult::is_ok));
+        let routes: Vec<Option<usize>> = securities.iter().map(|s| pool.route_of(s)).collect();
+        assert_eq!(routes, vec![Some(0), Some(1), Some(0)]);
+        assert_eq!(pool.clients()[0].subscriptions().used_quota(), 2);
+
+        // More sub types stay on the same member
+        pool.subscribe(vec![(1, "09988".to_string())], vec![4], true, &SubOptions::default()).await.unwrap();
+        assert_eq!(pool.clients()[1].subscriptions().sub_types(&(1, "09988".to_string())), vec![1, 4]);
+
+        // Unsubscribing every sub type releases the route
+        let results = pool.subscribe(vec![(1, "00700".to_string()), (1, "00005".to_string())], vec![1], false, &SubOptions::default()).await.unwrap();
+        assert!(results[0].is_ok());
+        assert!(results[1].error.is_some());
+        assert_eq!(pool.route_of(&(1, "00700".to_string())), None);
+
+        // Pushes of both members arrive on one receiver
+        let mut rx = pool.subscribe_push(3005).await;
+        assert_eq!(mock.push(3005, vec![0x08, 0x00]), 2);
+        for _ in 0..2 {
+            tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv()).await.unwrap().unwrap();
+        }
+
+        pool.request(1002, b"").await.unwrap();
+
+        // Unsubscribing all empties every member and forgets the routes
+        assert_eq!(pool.unsubscribe_all().await.unwrap(), vec![1, 2]);
+        assert!(pool.clients().iter().all(|c| c.subscriptions().current_subscriptions().is_empty()));
+        assert_eq!(pool.route_of(&(1, "09988".to_string())), None);
+        pool.disconnect().await;
+    }
+
+    #[test]
+    fn test_empty_pool() {
+        assert!(matches!(FutuClientPool::from_clients(Vec::new(), Arc::new(LeastLoaded)), Err(PoolError::Empty)));
+    }
+}
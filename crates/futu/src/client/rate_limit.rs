@@ -0,0 +1,324 @@
+//! Client-side request rate limits.
+//!
+//! OpenD limits how often each protocol may be requested, e.g. 60 snapshot
+//! requests per 30 seconds, and answers requests over the limit with a
+//! frequency error. `RateLimiter` keeps a token bucket per proto_id so the
+//! client stays within those limits instead of relying on OpenD to reject
+//! it: `FutuClient::request` takes a token before every request it sends
+//! and, depending on `RateLimitMode`, waits for one or fails with
+//! `ConnectionError::RateLimited`. Cached responses take no token. The
+//! limiter is off unless enabled in `RateLimitConfig`; `pace` applies a
+//! fixed limit regardless, for calls that send bursts of one protocol.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use parking_lot::Mutex;
+
+use super::clock::{system_clock, SharedClock};
+use super::connection::ConnectionError;
+
+const WINDOW: Duration = Duration::from_secs(30);
+
+/// What a request over its protocol's limit does.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RateLimitMode {
+    /// Wait until the bucket allows the request.
+    #[default]
+    Delay,
+    /// Fail with `ConnectionError::RateLimited`.
+    FailFast,
+}
+
+/// At most `requests` requests per `window`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub requests: u32,
+    pub window: Duration,
+}
+
+impl RateLimit {
+    pub fn per_30s(requests: u32) -> Self {
+        Self { requests, window: WINDOW }
+    }
+}
+
+/// Which protocols are limited and how.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitConfig {
+    pub enabled: bool,
+    pub mode: RateLimitMode,
+    /// Limit per proto_id; protocols not listed are not limited.
+    pub limits: HashMap<u32, RateLimit>,
+}
+
+impl Default for RateLimitConfig {
+    /// OpenD's documented limits. Order placement and modification are
+    /// budgeted per account by `trade::throttle` instead.
+    fn default() -> Self {
+        let limits = HashMap::from([
+            (3103, RateLimit::per_30s(60)), // Qot_RequestHistoryKL
+            (3203, RateLimit::per_30s(60)), // Qot_GetSecuritySnapshot
+            (3204, RateLimit::per_30s(10)), // Qot_GetPlateSet
+            (3205, RateLimit::per_30s(10)), // Qot_GetPlateSecurity
+            (3206, RateLimit::per_30s(10)), // Qot_GetReference
+            (3207, RateLimit::per_30s(10)), // Qot_GetOwnerPlate
+            (3209, RateLimit::per_30s(10)), // Qot_GetOptionChain
+            (3210, RateLimit::per_30s(60)), // Qot_GetWarrant
+            (3211, RateLimit::per_30s(30)), // Qot_GetCapitalFlow
+            (3212, RateLimit::per_30s(30)), // Qot_GetCapitalDistribution
+            (3215, RateLimit::per_30s(10)), // Qot_StockFilter
+            (2101, RateLimit::per_30s(10)), // Trd_GetFunds
+            (2111, RateLimit::per_30s(10)), // Trd_GetMaxTrdQtys
+            (2221, RateLimit::per_30s(10)), // Trd_GetHistoryOrderList
+            (2222, RateLimit::per_30s(10)), // Trd_GetHistoryOrderFillList
+        ]);
+        Self { enabled: false, mode: RateLimitMode::Delay, limits }
+    }
+}
+
+impl RateLimitConfig {
+    /// Limit of `proto_id`, or None if its requests are not limited.
+    pub fn limit(&self, proto_id: u32) -> Option<RateLimit> {
+        if !self.enabled {
+            return None;
+        }
+        self.limits.get(&proto_id).copied()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for (proto_id, limit) in &self.limits {
+            if limit.requests == 0 || limit.window.is_zero() {
+                return Err(format!("rate limit of proto {} must be positive", proto_id));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Token bucket refilling `capacity` tokens per window.
+#[derive(Debug)]
+pub(crate) struct Bucket {
+    capacity: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    pub(crate) fn new(capacity: u32, now: Instant) -> Self {
+        Self { capacity: f64::from(capacity), tokens: f64::from(capacity), updated: now }
+    }
+
+    /// Take a token, keeping at least `floor` tokens. Err holds the time
+    /// until the token is available.
+    pub(crate) fn take(&mut self, floor: f64, window: Duration, now: Instant) -> Result<(), Duration> {
+        let rate = self.capacity / window.as_secs_f64();
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(self.capacity);
+        self.updated = now;
+        let missing = floor + 1.0 - self.tokens;
+        if missing <= 0.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64(missing / rate))
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct LimiterState {
+    config: RateLimitConfig,
+    buckets: HashMap<u32, Bucket>,
+    /// Buckets of `pace`, kept across configuration changes.
+    paced: HashMap<u32, Bucket>,
+    /// Requests delayed or rejected per proto_id.
+    limited: HashMap<u32, u64>,
+}
+
+/// Per-protocol request rate limiter shared by all requests of a client.
+#[derive(Debug)]
+pub struct RateLimiter {
+    state: Mutex<LimiterState>,
+    /// Time buckets refill by and delayed requests wait on.
+    clock: SharedClock,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new(RateLimitConfig::default())
+    }
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self::with_clock(config, system_clock())
+    }
+
+    pub fn with_clock(config: RateLimitConfig, clock: SharedClock) -> Self {
+        Self { state: Mutex::new(LimiterState { config, ..LimiterState::default() }), clock }
+    }
+
+    pub fn config(&self) -> RateLimitConfig {
+        self.state.lock().config.clone()
+    }
+
+    /// Replace the configuration. Buckets start full again.
+    pub fn set_config(&self, config: RateLimitConfig) {
+        let mut state = self.state.lock();
+        state.config = config;
+        state.buckets.clear();
+    }
+
+    /// Requests delayed or rejected so far, keyed by proto_id.
+    pub fn limited(&self) -> HashMap<u32, u64> {
+        self.state.lock().limited.clone()
+    }
+
+    /// Take a request from the protocol's budget, or return how long to wait.
+    pub fn try_acquire(&self, proto_id: u32, now: Instant) -> Result<(), Duration> {
+        let mut state = self.state.lock();
+        let Some(limit) = state.config.limit(proto_id) else {
+            return Ok(());
+        };
+        let result = state
+            .buckets
+            .entry(proto_id)
+            .or_insert_with(|| Bucket::new(limit.requests, now))
+            .take(0.0, limit.window, now);
+        if result.is_err() {
+            *state.limited.entry(proto_id).or_default() += 1;
+        }
+        result
+    }
+
+    /// Wait until a request of `proto_id` fits `limit`, whether or not the
+    /// limiter is enabled. For calls that send a burst of requests of a
+    /// protocol OpenD limits, so the burst is paced across calls.
+    pub async fn pace(&self, proto_id: u32, limit: RateLimit) {
+        loop {
+            let now = self.clock.now();
+            let wait = {
+                let mut state = self.state.lock();
+                let result = state
+                    .paced
+                    .entry(proto_id)
+                    .or_insert_with(|| Bucket::new(limit.requests, now))
+                    .take(0.0, limit.window, now);
+                match result {
+                    Ok(()) => return,
+                    Err(wait) => {
+                        *state.limited.entry(proto_id).or_default() += 1;
+                        wait
+                    }
+                }
+            };
+            tracing::debug!("Rate limit: proto {} paced by {:?}", proto_id, wait);
+            self.clock.sleep(wait).await;
+        }
+    }
+
+    /// Take a request from the protocol's budget, waiting for it in `Delay`
+    /// mode and failing with `RateLimited` in `FailFast` mode.
+    pub async fn acquire(&self, proto_id: u32) -> Result<(), ConnectionError> {
+        while let Err(wait) = self.try_acquire(proto_id, self.clock.now()) {
+            if self.state.lock().config.mode == RateLimitMode::FailFast {
+                return Err(ConnectionError::RateLimited { proto_id, retry_after: wait });
+            }
+            tracing::debug!("Rate limit: proto {} delayed by {:?}", proto_id, wait);
+            self.clock.sleep(wait).await;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::clock::MockClock;
+    use crate::client::mock::MockOpenD;
+    use crate::client::FutuClient;
+    use crate::config::FutuConfig;
+    use std::sync::Arc;
+
+    fn limiter(mode: RateLimitMode, requests: u32) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            enabled: true,
+            mode,
+            limits: HashMap::from([(3203, RateLimit::per_30s(requests))]),
+        })
+    }
+
+    #[test]
+    fn test_bucket_refills_over_window() {
+        let l = limiter(RateLimitMode::Delay, 2);
+        let now = Instant::now();
+        assert!(l.try_acquire(3203, now).is_ok());
+        assert!(l.try_acquire(3203, now).is_ok());
+        let wait = l.try_acquire(3203, now).unwrap_err();
+        assert_eq!(wait, Duration::from_secs(15));
+        assert!(l.try_acquire(3203, now + Duration::from_secs(15)).is_ok());
+        assert_eq!(l.limited()[&3203], 1);
+        // Other protocols are not limited
+        for _ in 0..10 {
+            assert!(l.try_acquire(3004, now).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_disabled_and_invalid_config() {
+        let l = RateLimiter::default();
+        let now = Instant::now();
+        for _ in 0..100 {
+            assert!(l.try_acquire(3203, now).is_ok());
+        }
+        let mut config = RateLimitConfig::default();
+        assert!(config.validate().is_ok());
+        config.limits.insert(3203, RateLimit::per_30s(0));
+        assert!(config.validate().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_fail_fast_and_delay() {
+        let l = limiter(RateLimitMode::FailFast, 1);
+        l.acquire(3203).await.unwrap();
+        let err = l.acquire(3203).await.unwrap_err();
+        assert!(matches!(err, ConnectionError::RateLimited { proto_id: 3203, retry_after } if retry_after > Duration::ZERO));
+
+        let clock = Arc::new(MockClock::new(0.0));
+        let l = Arc::new(RateLimiter::with_clock(
+            RateLimitConfig { mode: RateLimitMode::Delay, ..l.config() },
+            clock.clone(),
+        ));
+        l.acquire(3203).await.unwrap();
+        let waiting = tokio::spawn({
+            let l = Arc::clone(&l);
+            async move { l.acquire(3203).await }
+        });
+        tokio::task::yield_now().await;
+        assert_eq!(clock.sleepers(), 1);
+        clock.advance(Duration::from_secs(30));
+        waiting.await.unwrap().unwrap();
+        assert_eq!(l.limited()[&3203], 1);
+    }
+
+    #[tokio::test]
+    async fn test_client_requests_take_tokens() {
+        let mock = MockOpenD::start().await.unwrap();
+        let config = FutuConfig {
+            port: mock.port(),
+            enable_encryption: false,
+            rate_limit: limiter(RateLimitMode::FailFast, 1).config(),
+            ..FutuConfig::default()
+        };
+        let mut client = FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+        mock.respond(3203, vec![0x08, 0x00]);
+
+        client.request(3203, b"").await.unwrap();
+        let err = client.request(3203, b"").await.unwrap_err();
+        assert!(matches!(err, ConnectionError::RateLimited { proto_id: 3203, .. }));
+        // The rejected request never reached OpenD
+        assert_eq!(client.stats().requests[&3203].count, 1);
+        assert_eq!(client.rate_limiter().limited()[&3203], 1);
+    }
+}
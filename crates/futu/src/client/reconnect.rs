@@ -0,0 +1,203 @@
+//! Re-establishing a lost connection to OpenD.
+//!
+//! When the connection drops and `FutuConfig::reconnect` is set, the recv
+//! loop calls `reconnect` instead of ending. It dials the configured
+//! endpoints again after a delay that doubles with every failed attempt, up
+//! to `reconnect_max_interval_secs`, runs InitConnect on the new stream and
+//! lets the existing `FutuConnection` adopt it, which installs the new AES
+//! key and connection ID. Requests made while reconnecting fail with a
+//! connection error. Once the recv loop runs again, `replay_subscriptions`
+//! subscribes the quotes that `quote::sub_manager` recorded once more, and
+//! `replay_acc_push` the trade push accounts of `trade::push`.
+
+use std::time::Duration;
+
+use crate::config::FutuConfig;
+use super::connection::FutuConnection;
+use super::init::{self, InitConnectResponse, InitError};
+
+/// Delay before reconnect attempt `attempt` (counting from 0).
+pub fn backoff(config: &FutuConfig, attempt: u32) -> Duration {
+    let initial = config.reconnect_interval_secs;
+    let max = config.reconnect_max_interval_secs.max(initial);
+    let secs = initial.saturating_mul(1u64 << attempt.min(32)).min(max);
+    Duration::from_secs(secs)
+}
+
+/// Dial the endpoints of `conn` once, run InitConnect and let `conn` adopt
+/// the new stream.
+pub async fn redial(conn: &FutuConnection) -> Result<InitConnectResponse, InitError> {
+    let mut config = conn.config().clone();
+    // `conn` keeps its trace and capture; the new stream needs neither
+    config.trace_capacity = 0;
+    config.capture_path = None;
+    let new = FutuConnection::connect(config).await?;
+    let resp = init::init_connect(&new).await?;
+    conn.adopt(new).await;
+    tracing::info!("Reconnected to Futu OpenD, conn_id={}", resp.conn_id);
+    Ok(resp)
+}
+
+/// Redial with backoff until it succeeds, or None once
+/// `reconnect_max_retries` attempts have failed.
+pub(crate) async fn reconnect(conn: &FutuConnection) -> Option<InitConnectResponse> {
+    let config = conn.config();
+    let mut attempt = 0;
+    loop {
+        if config.reconnect_max_retries.is_some_and(|max| attempt >= max) {
+            tracing::error!("Giving up reconnecting to Futu OpenD after {} attempts", attempt);
+            return None;
+        }
+        let delay = backoff(config, attempt);
+        tracing::info!("Reconnecting to Futu OpenD in {:?} (attempt {})", delay, attempt + 1);
+        config.clock.sleep(delay).await;
+        match redial(conn).await {
+            Ok(resp) => return Some(resp),
+            Err(e) => tracing::warn!("Reconnect attempt {} failed: {}", attempt + 1, e),
+        }
+        attempt += 1;
+    }
+}
+
+/// Subscribe the pairs `subscriptions` recorded again, one Qot_Sub request
+/// per sub type (and order book detail flag).
+#[cfg(feature = "quote")]
+pub(crate) async fn replay_subscriptions(
+    conn: &FutuConnection,
+    dispatcher: &super::dispatcher::Dispatcher,
+    subscriptions: &crate::quote::sub_manager::SubscriptionManager,
+) {
+    use prost::Message;
+    use crate::quote::subscribe::{sub_c2s, PROTO_QOT_SUB};
+
+    for (sub_type, options, securities) in subscriptions.replay_batches() {
+        let count = securities.len();
+        let c2s = sub_c2s(securities, vec![sub_type], true, &options);
+        let body = crate::generated::qot_sub::Request { c2s }.encode_to_vec();
+        let result = super::send_request(conn, dispatcher, PROTO_QOT_SUB, &body).await
+            .map_err(|e| e.to_string())
+            .and_then(|resp| {
+                crate::protocol::decode::decode_message::<crate::generated::qot_sub::Response>(&resp.body, resp.proto_fmt, conn.config().decode_mode)
+            })
+            .and_then(|resp| if resp.ret_type == 0 { Ok(()) } else { Err(resp.ret_msg.unwrap_or_default()) });
+        match result {
+            Ok(()) => tracing::info!("Resubscribed {} securities to sub type {}", count, sub_type),
+            Err(e) => tracing::warn!("Resubscribing {} securities to sub type {} failed: {}", count, sub_type, e),
+        }
+    }
+}
+
+/// Subscribe the trade pushes of the accounts `accounts` recorded again.
+#[cfg(feature = "trade")]
+pub(crate) async fn replay_acc_push(
+    conn: &FutuConnection,
+    dispatcher: &super::dispatcher::Dispatcher,
+    accounts: &crate::trade::push::AccPushAccounts,
+) {
+    use crate::trade::push::{sub_acc_push_request, PROTO_TRD_SUB_ACC_PUSH};
+
+    let acc_ids = accounts.accounts();
+    if acc_ids.is_empty() {
+        return;
+    }
+    let count = acc_ids.len();
+    let body = sub_acc_push_request(acc_ids);
+    let result = super::send_request(conn, dispatcher, PROTO_TRD_SUB_ACC_PUSH, &body).await
+        .map_err(|e| e.to_string())
+        .and_then(|resp| {
+            crate::protocol::decode::decode_message::<crate::generated::trd_sub_acc_push::Response>(&resp.body, resp.proto_fmt, conn.config().decode_mode)
+        })
+        .and_then(|resp| if resp.ret_type == 0 { Ok(()) } else { Err(resp.ret_msg.unwrap_or_default()) });
+    match result {
+        Ok(()) => tracing::info!("Resubscribed trade pushes of {} accounts", count),
+        Err(e) => tracing::warn!("Resubscribing trade pushes of {} accounts failed: {}", count, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::{SinkExt, StreamExt};
+    use prost::Message;
+    use tokio_util::codec::Framed;
+    use crate::protocol::{FutuCodec, FutuMessage};
+
+    #[test]
+    fn test_backoff_doubles_up_to_max() {
+        let config = FutuConfig { reconnect_interval_secs: 2, reconnect_max_interval_secs: 30, ..FutuConfig::default() };
+        let delays: Vec<u64> = (0..6).map(|a| backoff(&config, a).as_secs()).collect();
+        assert_eq!(delays, vec![2, 4, 8, 16, 30, 30]);
+        assert_eq!(backoff(&config, 200).as_secs(), 30);
+    }
+
+    /// Accept one connection and answer its InitConnect with `conn_id`.
+    async fn serve_init(listener: &tokio::net::TcpListener, conn_id: u64) -> Framed<tokio::net::TcpStream, FutuCodec> {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut framed = Framed::new(stream, FutuCodec);
+        let req = framed.next().await.unwrap().unwrap();
+        let resp = crate::generated::init_connect::Response {
+            ret_type: 0,
+            s2c: Some(crate::generated::init_connect::S2c {
+                conn_id,
+                keep_alive_interval: 10,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let body = resp.encode_to_vec().into();
+        framed.send(FutuMessage { proto_id: req.proto_id, serial_no: req.serial_no, body, proto_fmt: req.proto_fmt }).await.unwrap();
+        framed
+    }
+
+    #[tokio::test]
+    async fn test_redial_adopts_new_stream() {
+        let first = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let second = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let (first_port, second_port) = (first.local_addr().unwrap().port(), second.local_addr().unwrap().port());
+        let config = FutuConfig {
+            port: first_port,
+            fallback_endpoints: vec![("127.0.0.1".to_string(), second_port)],
+            ..FutuConfig::default()
+        };
+        let conn = FutuConnection::connect(config).await.unwrap();
+        let serial = conn.next_serial();
+
+        // The primary endpoint is gone; the fallback answers
+        drop(first);
+        tokio::spawn(async move {
+            let mut framed = serve_init(&second, 42).await;
+            // Keep the stream open until the client is done
+            let _ = framed.next().await;
+        });
+        let resp = redial(&conn).await.unwrap();
+        assert_eq!(resp.conn_id, 42);
+        assert_eq!(conn.conn_id().await, 42);
+        assert_eq!(conn.endpoint(), ("127.0.0.1".to_string(), second_port));
+        assert_eq!(conn.reconnects(), 1);
+        // Serial numbers carry on
+        assert!(conn.next_serial() > serial);
+    }
+
+    #[tokio::test]
+    async fn test_client_reconnects_after_disconnect() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            // OpenD restarts right after the handshake
+            drop(serve_init(&listener, 1).await);
+            let mut framed = serve_init(&listener, 2).await;
+            let _ = framed.next().await;
+        });
+        let config = FutuConfig { port, reconnect_interval_secs: 0, ..FutuConfig::default() };
+        let mut client = crate::client::FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while client.connection().conn_id().await != 2 {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }).await.unwrap();
+        assert_eq!(client.connection().reconnects(), 1);
+        server.abort();
+    }
+}
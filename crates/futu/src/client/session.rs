@@ -0,0 +1,289 @@
+//! Detection of the account being logged in elsewhere.
+//!
+//! When the same Futu account logs in on another device, OpenD either gets
+//! kicked out (a `KickedOut` gateway event) or keeps running with downgraded
+//! quote rights (a `QotRight` notify). Both arrive as Notify pushes, which the
+//! recv loop feeds into a `SessionMonitor`.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use parking_lot::Mutex;
+use prost::Message;
+use tokio::sync::mpsc;
+
+use crate::generated::notify::{self, QotRight};
+use super::notify::{NOTIFY_TYPE_CONN_STATUS, NOTIFY_TYPE_GTW_EVENT, NOTIFY_TYPE_QOT_RIGHT, PROTO_NOTIFY};
+
+// GtwEventType values
+const GTW_EVENT_KICKED_OUT: i32 = 7;
+
+/// Why the session was preempted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreemptionReason {
+    /// OpenD was logged out because the account logged in elsewhere.
+    KickedOut,
+    /// Quote rights were taken over by another device.
+    QuoteRightLost,
+}
+
+impl PreemptionReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PreemptionReason::KickedOut => "kicked_out",
+            PreemptionReason::QuoteRightLost => "quote_right_lost",
+        }
+    }
+}
+
+/// The session was preempted by another login of the same account.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("session preempted ({}): {desc}", reason.as_str())]
+pub struct SessionPreempted {
+    pub reason: PreemptionReason,
+    pub desc: String,
+    /// Unix timestamp (seconds) of detection.
+    pub timestamp: f64,
+}
+
+/// Change of the preemption state.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SessionEvent {
+    Preempted(SessionPreempted),
+    /// The preemption ended: OpenD logged back in, quote rights came back, or
+    /// `resume` was called.
+    Resolved { timestamp: f64 },
+}
+
+#[derive(Debug, Default)]
+struct MonitorState {
+    preempted: Option<SessionPreempted>,
+    /// Latest quote rights per market (hk, us, cn, hk option, hk future).
+    rights: Option<[i32; 5]>,
+    /// Rights before a `QuoteRightLost` preemption; restoring them resolves it.
+    rights_before_loss: Option<[i32; 5]>,
+    subscribers: Vec<mpsc::UnboundedSender<SessionEvent>>,
+}
+
+/// Tracks preemption from Notify pushes and optionally pauses trading.
+#[derive(Debug, Default)]
+pub struct SessionMonitor {
+    state: Mutex<MonitorState>,
+    pause_trading: AtomicBool,
+}
+
+impl SessionMonitor {
+    pub fn new(pause_trading: bool) -> Self {
+        Self {
+            state: Mutex::new(MonitorState::default()),
+            pause_trading: AtomicBool::new(pause_trading),
+        }
+    }
+
+    /// Whether trade calls are refused while the session is preempted.
+    pub fn set_pause_trading(&self, enabled: bool) {
+        self.pause_trading.store(enabled, Ordering::Relaxed);
+    }
+
+    pub fn pause_trading(&self) -> bool {
+        self.pause_trading.load(Ordering::Relaxed)
+    }
+
+    /// Current preemption, if any.
+    pub fn preempted(&self) -> Option<SessionPreempted> {
+        self.state.lock().preempted.clone()
+    }
+
+    /// Err while preempted and trading is paused.
+    pub fn check_trading(&self) -> Result<(), SessionPreempted> {
+        if !self.pause_trading() {
+            return Ok(());
+        }
+        match self.preempted() {
+            Some(p) => Err(p),
+            None => Ok(()),
+        }
+    }
+
+    /// Clear the preemption manually, e.g. after logging OpenD back in.
+    pub fn resume(&self, now: f64) {
+        resolve(&mut self.state.lock(), now);
+    }
+
+    /// Receive preemption and resolution events from now on.
+    pub fn subscribe(&self) -> mpsc::UnboundedReceiver<SessionEvent> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.state.lock().subscribers.push(tx);
+        rx
+    }
+
+    /// Record a push message. Other proto ids and undecodable bodies are ignored.
+    pub fn record_push(&self, proto_id: u32, body: &[u8], now: f64) {
+        if proto_id != PROTO_NOTIFY {
+            return;
+        }
+        if let Some(s2c) = notify::Response::decode(body).ok().and_then(|r| r.s2c) {
+            self.handle_notify(&s2c, now);
+        }
+    }
+
+    /// Apply one Notify message.
+    pub fn handle_notify(&self, s2c: &notify::S2c, now: f64) {
+        let mut state = self.state.lock();
+        match s2c.r#type {
+            NOTIFY_TYPE_GTW_EVENT => {
+                if let Some(event) = s2c.event.as_ref().filter(|e| e.event_type == GTW_EVENT_KICKED_OUT) {
+                    preempt(&mut state, PreemptionReason::KickedOut, event.desc.clone(), now);
+                }
+            }
+            NOTIFY_TYPE_CONN_STATUS => {
+                let kicked = state.preempted.as_ref().is_some_and(|p| p.reason == PreemptionReason::KickedOut);
+                if let Some(status) = s2c.connect_status.filter(|s| kicked && s.qot_logined && s.trd_logined) {
+                    tracing::debug!("OpenD logged back in: {:?}", status);
+                    resolve(&mut state, now);
+                }
+            }
+            NOTIFY_TYPE_QOT_RIGHT => {
+                let Some(rights) = s2c.qot_right.as_ref().map(rights_of) else {
+                    return;
+                };
+                let previous = state.rights.replace(rights);
+                let lost = state.preempted.as_ref().is_some_and(|p| p.reason == PreemptionReason::QuoteRightLost);
+                if lost {
+                    let restored = state.rights_before_loss.is_some_and(|before| !downgraded(&before, &rights));
+                    if restored {
+                        resolve(&mut state, now);
+                    }
+                } else if let Some(previous) = previous.filter(|p| downgraded(p, &rights)) {
+                    state.rights_before_loss = Some(previous);
+                    let desc = format!("quote rights downgraded from {:?} to {:?}", previous, rights);
+                    preempt(&mut state, PreemptionReason::QuoteRightLost, desc, now);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn preempt(state: &mut MonitorState, reason: PreemptionReason, desc: String, now: f64) {
+    if state.preempted.is_some() {
+        return;
+    }
+    let preempted = SessionPreempted { reason, desc, timestamp: now };
+    tracing::warn!("Futu session preempted by another login: {}", preempted);
+    state.preempted = Some(preempted.clone());
+    broadcast(state, SessionEvent::Preempted(preempted));
+}
+
+fn resolve(state: &mut MonitorState, now: f64) {
+    if state.preempted.take().is_none() {
+        return;
+    }
+    state.rights_before_loss = None;
+    tracing::info!("Futu session preemption resolved");
+    broadcast(state, SessionEvent::Resolved { timestamp: now });
+}
+
+fn broadcast(state: &mut MonitorState, event: SessionEvent) {
+    state.subscribers.retain(|tx| tx.send(event.clone()).is_ok());
+}
+
+pub(crate) fn rights_of(right: &QotRight) -> [i32; 5] {
+    [
+        right.hk_qot_right,
+        right.us_qot_right,
+        right.cn_qot_right,
+        right.hk_option_qot_right.unwrap_or(0),
+        right.hk_future_qot_right.unwrap_or(0),
+    ]
+}
+
+/// Rank of a QotRight value: No/Unknown < Bmp < Level1 < Level2 < SF.
+fn rank(right: i32) -> i32 {
+    match right {
+        1 => 1, // Bmp
+        2 => 2, // Level1
+        3 => 3, // Level2
+        4 => 4, // SF
+        _ => 0, // Unknow, No
+    }
+}
+
+/// Whether any market dropped from real-time rights (Level1 or better) to
+/// BMP or none.
+pub(crate) fn downgraded(before: &[i32; 5], after: &[i32; 5]) -> bool {
+    before.iter().zip(after).any(|(b, a)| rank(*b) >= 2 && rank(*a) < 2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generated::notify::{ConnectStatus, GtwEvent};
+
+    fn qot_right(hk: i32, us: i32) -> notify::S2c {
+        notify::S2c {
+            r#type: NOTIFY_TYPE_QOT_RIGHT,
+            qot_right: Some(QotRight { hk_qot_right: hk, us_qot_right: us, cn_qot_right: 5, ..Default::default() }),
+            ..Default::default()
+        }
+    }
+
+    fn kicked_out() -> notify::S2c {
+        notify::S2c {
+            r#type: NOTIFY_TYPE_GTW_EVENT,
+            event: Some(GtwEvent { event_type: GTW_EVENT_KICKED_OUT, desc: "logged in elsewhere".to_string() }),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_kicked_out_pauses_until_logged_in() {
+        let monitor = SessionMonitor::new(true);
+        let mut rx = monitor.subscribe();
+        monitor.record_push(PROTO_NOTIFY, &notify::Response { ret_type: 0, s2c: Some(kicked_out()), ..Default::default() }.encode_to_vec(), 1.0);
+
+        let err = monitor.check_trading().unwrap_err();
+        assert_eq!(err.reason, PreemptionReason::KickedOut);
+        assert_eq!(err.desc, "logged in elsewhere");
+        assert!(matches!(rx.try_recv(), Ok(SessionEvent::Preempted(_))));
+
+        let status = notify::S2c {
+            r#type: NOTIFY_TYPE_CONN_STATUS,
+            connect_status: Some(ConnectStatus { qot_logined: true, trd_logined: true }),
+            ..Default::default()
+        };
+        monitor.handle_notify(&status, 2.0);
+        assert!(monitor.check_trading().is_ok());
+        assert_eq!(rx.try_recv().ok(), Some(SessionEvent::Resolved { timestamp: 2.0 }));
+    }
+
+    #[test]
+    fn test_quote_right_loss_and_restore() {
+        let monitor = SessionMonitor::new(false);
+        monitor.handle_notify(&qot_right(3, 2), 0.0);
+        assert!(monitor.preempted().is_none());
+
+        monitor.handle_notify(&qot_right(1, 2), 1.0);
+        assert_eq!(monitor.preempted().map(|p| p.reason), Some(PreemptionReason::QuoteRightLost));
+        // Not paused: trading stays allowed
+        assert!(monitor.check_trading().is_ok());
+
+        monitor.handle_notify(&qot_right(3, 2), 2.0);
+        assert!(monitor.preempted().is_none());
+    }
+
+    #[test]
+    fn test_manual_resume_and_other_events() {
+        let monitor = SessionMonitor::new(true);
+        let other = notify::S2c {
+            r#type: NOTIFY_TYPE_GTW_EVENT,
+            event: Some(GtwEvent { event_type: 4, desc: "login failed".to_string() }),
+            ..Default::default()
+        };
+        monitor.handle_notify(&other, 0.0);
+        assert!(monitor.preempted().is_none());
+
+        monitor.handle_notify(&kicked_out(), 1.0);
+        assert!(monitor.check_trading().is_err());
+        monitor.resume(2.0);
+        assert!(monitor.check_trading().is_ok());
+    }
+}
@@ -0,0 +1,341 @@
+//! Supervision of background tasks with restart policies and health reporting.
+
+use std::future::Future;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use parking_lot::Mutex;
+use tokio::task::{AbortHandle, JoinHandle};
+
+/// What to do when a supervised task exits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RestartPolicy {
+    /// Never restart.
+    Never,
+    /// Restart after a panic, up to `max_restarts` times.
+    OnPanic { max_restarts: u32, backoff: Duration },
+    /// Restart after a panic or a normal exit, up to `max_restarts` times.
+    Always { max_restarts: u32, backoff: Duration },
+}
+
+impl RestartPolicy {
+    fn should_restart(&self, panicked: bool, restarts: u32) -> Option<Duration> {
+        match *self {
+            RestartPolicy::Never => None,
+            RestartPolicy::OnPanic { max_restarts, backoff } => {
+                (panicked && restarts < max_restarts).then_some(backoff)
+            }
+            RestartPolicy::Always { max_restarts, backoff } => {
+                (restarts < max_restarts).then_some(backoff)
+            }
+        }
+    }
+}
+
+/// Lifecycle state of a supervised task.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskState {
+    Running,
+    Restarting,
+    Completed,
+    Panicked,
+    Aborted,
+}
+
+impl TaskState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskState::Running => "running",
+            TaskState::Restarting => "restarting",
+            TaskState::Completed => "completed",
+            TaskState::Panicked => "panicked",
+            TaskState::Aborted => "aborted",
+        }
+    }
+}
+
+/// Health report entry for one supervised task.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TaskHealth {
+    pub name: String,
+    pub state: TaskState,
+    pub restarts: u32,
+    /// Panic message of the most recent panic, if any.
+    pub last_error: Option<String>,
+    /// Unix timestamp (seconds) of the most recent start.
+    pub started_at: f64,
+}
+
+struct TaskRecord {
+    health: Mutex<TaskHealth>,
+    /// Abort handle of the current incarnation, and whether the task was aborted.
+    /// Kept under one lock so an abort cannot miss a freshly spawned incarnation.
+    current: Mutex<(Option<AbortHandle>, bool)>,
+}
+
+/// Owns a set of background tasks, restarting them according to their policy.
+///
+/// Each task runs under a small monitor task that awaits it, records panics and
+/// exits, and respawns it when the policy allows. Must be used inside a tokio runtime.
+#[derive(Default)]
+pub struct TaskSupervisor {
+    tasks: Mutex<Vec<(Arc<TaskRecord>, JoinHandle<()>)>>,
+}
+
+impl TaskSupervisor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Spawn a restartable task. `factory` builds a fresh future for every (re)start.
+    pub fn spawn<F, Fut>(&self, name: &str, policy: RestartPolicy, factory: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let record = Arc::new(TaskRecord {
+            health: Mutex::new(TaskHealth {
+                name: name.to_string(),
+                state: TaskState::Running,
+                restarts: 0,
+                last_error: None,
+                started_at: now(),
+            }),
+            current: Mutex::new((None, false)),
+        });
+        let rec = Arc::clone(&record);
+
+        let monitor = tokio::spawn(async move {
+            loop {
+                let inner = tokio::spawn(factory());
+                {
+                    let mut current = rec.current.lock();
+                    if current.1 {
+                        inner.abort();
+                    }
+                    current.0 = Some(inner.abort_handle());
+                }
+                let result = inner.await;
+
+                let Some(backoff) = record_exit(&rec, policy, result) else {
+                    return;
+                };
+                tokio::time::sleep(backoff).await;
+                let mut health = rec.health.lock();
+                health.state = TaskState::Running;
+                health.started_at = now();
+            }
+        });
+        self.tasks.lock().push((record, monitor));
+    }
+
+    /// Spawn a task that is monitored but never restarted.
+    /// Use for tasks owning state that cannot be rebuilt, such as a channel receiver.
+    pub fn spawn_once<Fut>(&self, name: &str, future: Fut)
+    where
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let slot = Mutex::new(Some(future));
+        self.spawn(name, RestartPolicy::Never, move || {
+            let future = slot.lock().take();
+            async move {
+                if let Some(future) = future {
+                    future.await;
+                }
+            }
+        });
+    }
+
+    /// Current health of every task, in spawn order.
+    pub fn health(&self) -> Vec<TaskHealth> {
+        self.tasks.lock().iter().map(|(r, _)| r.health.lock().clone()).collect()
+    }
+
+    /// Abort every task and forget them. The supervisor can be reused afterwards.
+    pub fn abort_all(&self) {
+        for (record, monitor) in self.tasks.lock().drain(..) {
+            monitor.abort();
+            abort_current(&record);
+        }
+    }
+
+    /// Abort the tasks named `name` and forget them. Resolves once their
+    /// futures have been dropped, so any channel receivers they own are
+    /// closed. Returns whether a task was found.
+    pub async fn abort(&self, name: &str) -> bool {
+        let monitors: Vec<_> = {
+            let mut tasks = self.tasks.lock();
+            let (matching, rest) = tasks.drain(..).partition(|(r, _)| r.health.lock().name == name);
+            *tasks = rest;
+            matching
+        };
+        let found = !monitors.is_empty();
+        for (record, monitor) in monitors {
+            // The monitor exits once the aborted incarnation has been dropped
+            abort_current(&record);
+            let _ = monitor.await;
+        }
+        found
+    }
+}
+
+impl Drop for TaskSupervisor {
+    fn drop(&mut self) {
+        self.abort_all();
+    }
+}
+
+/// Flag `rec` as aborted and abort its running incarnation, if any.
+fn abort_current(rec: &TaskRecord) {
+    let mut current = rec.current.lock();
+    current.1 = true;
+    if let Some(handle) = current.0.take() {
+        handle.abort();
+    }
+}
+
+/// Record how an incarnation ended and decide whether to restart it.
+fn record_exit(
+    rec: &TaskRecord,
+    policy: RestartPolicy,
+    result: Result<(), tokio::task::JoinError>,
+) -> Option<Duration> {
+    let mut health = rec.health.lock();
+    let panicked = match result {
+        Ok(()) => {
+            health.state = TaskState::Completed;
+            false
+        }
+        Err(e) if e.is_panic() => {
+            let msg = panic_message(e.into_panic());
+            tracing::error!("Task '{}' panicked: {}", health.name, msg);
+            health.state = TaskState::Panicked;
+            health.last_error = Some(msg);
+            true
+        }
+        Err(_) => {
+            health.state = TaskState::Aborted;
+            return None;
+        }
+    };
+    if rec.current.lock().1 {
+        return None;
+    }
+    let backoff = policy.should_restart(panicked, health.restarts)?;
+    health.restarts += 1;
+    health.state = TaskState::Restarting;
+    tracing::warn!("Restarting task '{}' (restart {})", health.name, health.restarts);
+    Some(backoff)
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    async fn settle() {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    /// Wait (up to 5s) until every task has left the running/restarting states.
+    async fn wait_finished(sup: &TaskSupervisor) {
+        for _ in 0..500 {
+            let done = sup
+                .health()
+                .iter()
+                .all(|h| !matches!(h.state, TaskState::Running | TaskState::Restarting));
+            if done {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    #[tokio::test]
+    async fn test_restart_on_panic_until_limit() {
+        let sup = TaskSupervisor::new();
+        let runs = Arc::new(AtomicU32::new(0));
+        let r = Arc::clone(&runs);
+        let policy = RestartPolicy::OnPanic { max_restarts: 2, backoff: Duration::ZERO };
+        sup.spawn("boom", policy, move || {
+            r.fetch_add(1, Ordering::SeqCst);
+            async { panic!("boom") }
+        });
+        wait_finished(&sup).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        let health = &sup.health()[0];
+        assert_eq!(health.state, TaskState::Panicked);
+        assert_eq!(health.restarts, 2);
+        assert_eq!(health.last_error.as_deref(), Some("boom"));
+    }
+
+    #[tokio::test]
+    async fn test_completed_not_restarted_on_panic_policy() {
+        let sup = TaskSupervisor::new();
+        let policy = RestartPolicy::OnPanic { max_restarts: 5, backoff: Duration::ZERO };
+        sup.spawn("done", policy, || async {});
+        sup.spawn_once("once", async {});
+        wait_finished(&sup).await;
+        for health in sup.health() {
+            assert_eq!(health.state, TaskState::Completed);
+            assert_eq!(health.restarts, 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_always_restarts_normal_exit() {
+        let sup = TaskSupervisor::new();
+        let policy = RestartPolicy::Always { max_restarts: 1, backoff: Duration::ZERO };
+        sup.spawn("loop", policy, || async {});
+        wait_finished(&sup).await;
+        assert_eq!(sup.health()[0].restarts, 1);
+    }
+
+    #[tokio::test]
+    async fn test_abort_all() {
+        let sup = TaskSupervisor::new();
+        let policy = RestartPolicy::Always { max_restarts: 5, backoff: Duration::ZERO };
+        sup.spawn("forever", policy, std::future::pending::<()>);
+        settle().await;
+        assert_eq!(sup.health()[0].state, TaskState::Running);
+        sup.abort_all();
+        settle().await;
+        assert!(sup.health().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_abort_by_name() {
+        let sup = TaskSupervisor::new();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<()>();
+        sup.spawn_once("holder", async move {
+            let _tx = tx;
+            std::future::pending::<()>().await;
+        });
+        let policy = RestartPolicy::Always { max_restarts: 5, backoff: Duration::ZERO };
+        sup.spawn("other", policy, std::future::pending::<()>);
+        settle().await;
+
+        assert!(sup.abort("holder").await);
+        // The aborted future was dropped before abort() returned
+        assert!(matches!(rx.try_recv(), Err(tokio::sync::mpsc::error::TryRecvError::Disconnected)));
+        assert!(!sup.abort("holder").await);
+        let names: Vec<String> = sup.health().into_iter().map(|h| h.name).collect();
+        assert_eq!(names, vec!["other"]);
+    }
+}
@@ -0,0 +1,196 @@
+//! Bounded ring buffer of recent message summaries, dumped on protocol errors.
+
+use std::collections::VecDeque;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use parking_lot::Mutex;
+
+/// Direction of a traced message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceDirection {
+    Send,
+    Recv,
+}
+
+impl TraceDirection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TraceDirection::Send => "SEND",
+            TraceDirection::Recv => "RECV",
+        }
+    }
+}
+
+/// Summary of a single sent or received message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TraceEntry {
+    pub direction: TraceDirection,
+    pub proto_id: u32,
+    pub serial_no: u32,
+    pub body_len: usize,
+    /// Unix timestamp in seconds.
+    pub timestamp: f64,
+    /// `retType` of a received response, when it could be read cheaply.
+    pub ret_type: Option<i32>,
+}
+
+/// Thread-safe ring of the last `capacity` message summaries.
+/// A capacity of 0 disables recording.
+pub struct MessageTrace {
+    capacity: usize,
+    entries: Mutex<VecDeque<TraceEntry>>,
+    dump_path: Option<PathBuf>,
+}
+
+impl MessageTrace {
+    pub fn new(capacity: usize, dump_path: Option<PathBuf>) -> Self {
+        Self {
+            capacity,
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+            dump_path,
+        }
+    }
+
+    /// Record a message summary, evicting the oldest entry when full.
+    pub fn record(&self, direction: TraceDirection, proto_id: u32, serial_no: u32, body: &[u8]) {
+        if self.capacity == 0 {
+            return;
+        }
+        let entry = TraceEntry {
+            direction,
+            proto_id,
+            serial_no,
+            body_len: body.len(),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs_f64(),
+            ret_type: match direction {
+                TraceDirection::Recv => peek_ret_type(body),
+                TraceDirection::Send => None,
+            },
+        };
+        let mut entries = self.entries.lock();
+        if entries.len() == self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+
+    /// Copy of the buffered entries, oldest first.
+    pub fn snapshot(&self) -> Vec<TraceEntry> {
+        self.entries.lock().iter().cloned().collect()
+    }
+
+    /// Write the buffered entries to the log and, if configured, append them to the dump file.
+    pub fn dump(&self, reason: &str) {
+        let entries = self.snapshot();
+        if entries.is_empty() {
+            return;
+        }
+        tracing::warn!("Message trace dump ({}), last {} messages:", reason, entries.len());
+        let lines: Vec<String> = entries.iter().map(format_entry).collect();
+        for line in &lines {
+            tracing::warn!("  {}", line);
+        }
+        if let Some(ref path) = self.dump_path {
+            let result = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .and_then(|mut f| {
+                    writeln!(f, "# trace dump: {}", reason)?;
+                    for line in &lines {
+                        writeln!(f, "{}", line)?;
+                    }
+                    Ok(())
+                });
+            if let Err(e) = result {
+                tracing::error!("Failed to write trace dump to {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+fn format_entry(e: &TraceEntry) -> String {
+    format!(
+        "{:.6} {} proto_id={} serial_no={} body_len={} ret_type={}",
+        e.timestamp,
+        e.direction.as_str(),
+        e.proto_id,
+        e.serial_no,
+        e.body_len,
+        e.ret_type.map_or("-".to_string(), |r| r.to_string()),
+    )
+}
+
+/// Read `retType` (field 1, varint) if it is the first field of the body.
+/// Every Futu `Response` starts with it; anything else yields `None`.
+pub(crate) fn peek_ret_type(body: &[u8]) -> Option<i32> {
+    if body.first() != Some(&0x08) {
+        return None;
+    }
+    let mut value: u64 = 0;
+    for (i, &b) in body[1..].iter().enumerate().take(10) {
+        value |= u64::from(b & 0x7F) << (7 * i);
+        if b & 0x80 == 0 {
+            return Some(value as i64 as i32);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+
+    #[test]
+    fn test_ring_evicts_oldest() {
+        let trace = MessageTrace::new(2, None);
+        trace.record(TraceDirection::Send, 1001, 1, b"a");
+        trace.record(TraceDirection::Send, 1002, 2, b"bb");
+        trace.record(TraceDirection::Send, 1003, 3, b"ccc");
+        let entries = trace.snapshot();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].proto_id, 1002);
+        assert_eq!(entries[1].proto_id, 1003);
+        assert_eq!(entries[1].body_len, 3);
+    }
+
+    #[test]
+    fn test_zero_capacity_disables() {
+        let trace = MessageTrace::new(0, None);
+        trace.record(TraceDirection::Recv, 1001, 1, b"a");
+        assert!(trace.snapshot().is_empty());
+    }
+
+    #[test]
+    fn test_peek_ret_type() {
+        for ret_type in [0, -1, -400, 100] {
+            let resp = crate::generated::get_global_state::Response {
+                ret_type,
+                ret_msg: Some("x".to_string()),
+                err_code: None,
+                s2c: None,
+            };
+            assert_eq!(peek_ret_type(&resp.encode_to_vec()), Some(ret_type));
+        }
+        assert_eq!(peek_ret_type(b""), None);
+        assert_eq!(peek_ret_type(b"\x0a\x00"), None);
+    }
+
+    #[test]
+    fn test_dump_appends_to_file() {
+        let path = std::env::temp_dir().join(format!("futu_trace_{}.log", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let trace = MessageTrace::new(4, Some(path.clone()));
+        trace.record(TraceDirection::Send, 3001, 7, b"req");
+        trace.dump("test");
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(content.contains("# trace dump: test"));
+        assert!(content.contains("SEND proto_id=3001 serial_no=7 body_len=3"));
+    }
+}
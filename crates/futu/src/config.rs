@@ -1,4 +1,14 @@
 use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::client::cache::ResponseCacheConfig;
+use crate::client::clock::{system_clock, SharedClock};
+use crate::client::flow_control::RetryPolicy;
+use crate::client::rate_limit::RateLimitConfig;
+use crate::protocol::{DecodeMode, ProtoFmt};
+use crate::protocol::encryption::AesPadding;
+#[cfg(feature = "trade")]
+use crate::trade::throttle::OrderThrottleConfig;
 
 /// Configuration for connecting to Futu OpenD gateway.
 #[derive(Debug, Clone)]
@@ -11,14 +21,82 @@ pub struct FutuConfig {
     pub client_id: String,
     /// Client version string
     pub client_ver: i32,
-    /// Path to RSA private key file (optional, for encrypted connections)
+    /// PEM private key configured in FutuOpenD. The InitConnect handshake
+    /// is then RSA-encrypted and AES encryption enabled, as OpenD requires
+    /// (see `protocol::encryption::RsaCipher`)
     pub rsa_key_path: Option<PathBuf>,
     /// Enable AES encryption (requires RSA keys configured in FutuOpenD)
     pub enable_encryption: bool,
+    /// Padding of AES bodies. The default asks for Futu's FTAES_ECB and
+    /// decrypts either padding, as OpenD versions differ in the one they use
+    pub aes_padding: AesPadding,
     /// Reconnect on disconnect
     pub reconnect: bool,
-    /// Reconnect interval in seconds
+    /// Delay before the first reconnect attempt in seconds; doubled after
+    /// every failed attempt
     pub reconnect_interval_secs: u64,
+    /// Longest delay between reconnect attempts in seconds
+    pub reconnect_max_interval_secs: u64,
+    /// Failed reconnect attempts after which the client gives up (None
+    /// retries forever)
+    pub reconnect_max_retries: Option<u32>,
+    /// Time allowed for the TCP connect to each endpoint (None leaves it to
+    /// the OS, which can take minutes for an unreachable host)
+    pub connect_timeout: Option<Duration>,
+    /// Idle time before TCP keep-alive probes are sent (None disables them)
+    pub tcp_keepalive: Option<Duration>,
+    /// Longest wait for the next message from OpenD before the connection
+    /// counts as lost. OpenD answers keepalives, so it should exceed the
+    /// keepalive interval (None waits forever)
+    pub read_timeout: Option<Duration>,
+    /// Send small messages at once instead of coalescing them (TCP_NODELAY)
+    pub nodelay: bool,
+    /// Keepalive heartbeats left unanswered in a row after which the
+    /// connection counts as stale and is dropped like one whose keepalive
+    /// fails (None only reports them, see `client::keepalive`)
+    pub keepalive_max_missed: Option<u32>,
+    /// Fallback OpenD endpoints (host, port), tried in order after the primary
+    pub fallback_endpoints: Vec<(String, u16)>,
+    /// Number of recent message summaries kept for error dumps (0 disables)
+    pub trace_capacity: usize,
+    /// File that trace dumps are appended to, in addition to the log
+    pub trace_dump_path: Option<PathBuf>,
+    /// File every packet is captured to, wire and decrypted forms (see
+    /// `client::capture`); None disables capture
+    pub capture_path: Option<PathBuf>,
+    /// OpenD's log directory, tailed to report rejected requests with the
+    /// gateway's log lines (see `client::gateway_log`); None disables it
+    pub gateway_log_dir: Option<PathBuf>,
+    /// Refuse place/modify order calls while another login of the account
+    /// has preempted this session
+    pub pause_trading_on_preempt: bool,
+    /// Drop pushes a subscriber already received, e.g. repeated by OpenD
+    /// when a security is subscribed again (see `client::dedup`)
+    pub dedup_pushes: bool,
+    /// Quote subscription quota checked locally before subscribing (see
+    /// `quote::sub_manager`); None leaves the check to OpenD
+    pub sub_quota: Option<u32>,
+    /// How responses and pushes lacking `required` fields are decoded
+    pub decode_mode: DecodeMode,
+    /// Format InitConnect asks OpenD to write pushes in. JSON pushes are
+    /// decoded with the `json` feature (see `protocol::json`)
+    pub push_proto_fmt: ProtoFmt,
+    /// Client-side budgets for place, modify and cancel order requests
+    #[cfg(feature = "trade")]
+    pub order_throttle: OrderThrottleConfig,
+    /// Resending of requests OpenD answers as busy or over its frequency limit
+    pub retry_policy: RetryPolicy,
+    /// Allow the cash flow and funds protocols of `trade::funds`
+    pub enable_funds_protocols: bool,
+    /// Caching of reference data responses per protocol (off by default)
+    pub response_cache: ResponseCacheConfig,
+    /// Client-side request rate limits per protocol (off by default)
+    pub rate_limit: RateLimitConfig,
+    /// Requests still unanswered after this long are logged as warnings
+    /// with their serial number, and again once answered (None disables it)
+    pub slow_request_threshold: Option<Duration>,
+    /// Time source of timers, rate limits and caches; replaced in tests
+    pub clock: SharedClock,
 }
 
 impl Default for FutuConfig {
@@ -30,12 +108,62 @@ impl Default for FutuConfig {
             client_ver: 100,
             rsa_key_path: None,
             enable_encryption: false,
+            aes_padding: AesPadding::Compatible,
             reconnect: true,
             reconnect_interval_secs: 5,
+            reconnect_max_interval_secs: 60,
+            reconnect_max_retries: None,
+            connect_timeout: Some(Duration::from_secs(10)),
+            tcp_keepalive: None,
+            read_timeout: None,
+            nodelay: true,
+            keepalive_max_missed: Some(3),
+            fallback_endpoints: Vec::new(),
+            trace_capacity: 64,
+            trace_dump_path: None,
+            capture_path: None,
+            gateway_log_dir: None,
+            pause_trading_on_preempt: false,
+            dedup_pushes: false,
+            sub_quota: None,
+            decode_mode: DecodeMode::Lenient,
+            push_proto_fmt: ProtoFmt::Protobuf,
+            #[cfg(feature = "trade")]
+            order_throttle: OrderThrottleConfig::default(),
+            retry_policy: RetryPolicy::default(),
+            enable_funds_protocols: false,
+            response_cache: ResponseCacheConfig::default(),
+            rate_limit: RateLimitConfig::default(),
+            slow_request_threshold: None,
+            clock: system_clock(),
         }
     }
 }
 
+impl FutuConfig {
+    /// Whether InitConnect asks for AES encryption: on request, and always
+    /// with an RSA key, as OpenD encrypts every message then.
+    pub fn encryption_enabled(&self) -> bool {
+        self.enable_encryption || self.rsa_key_path.is_some()
+    }
+
+    /// Ordered list of endpoints to try: the primary host/port followed by fallbacks.
+    /// Hosts are normalized so bracketed IPv6 literals (`[::1]`) become bare addresses.
+    pub fn endpoints(&self) -> Vec<(String, u16)> {
+        std::iter::once((self.host.as_str(), self.port))
+            .chain(self.fallback_endpoints.iter().map(|(h, p)| (h.as_str(), *p)))
+            .map(|(host, port)| (normalize_host(host).to_string(), port))
+            .collect()
+    }
+}
+
+/// Strip surrounding brackets from an IPv6 literal (`[::1]` -> `::1`).
+pub fn normalize_host(host: &str) -> &str {
+    host.strip_prefix('[')
+        .and_then(|h| h.strip_suffix(']'))
+        .unwrap_or(host)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -49,8 +177,32 @@ mod tests {
         assert_eq!(config.client_ver, 100);
         assert!(config.rsa_key_path.is_none());
         assert!(!config.enable_encryption);
+        assert_eq!(config.aes_padding, AesPadding::Compatible);
         assert!(config.reconnect);
         assert_eq!(config.reconnect_interval_secs, 5);
+        assert_eq!(config.reconnect_max_interval_secs, 60);
+        assert_eq!(config.reconnect_max_retries, None);
+        assert!(!config.pause_trading_on_preempt);
+        assert_eq!(config.decode_mode, DecodeMode::Lenient);
+        #[cfg(feature = "trade")]
+        assert_eq!(config.order_throttle, OrderThrottleConfig::default());
+        assert_eq!(config.retry_policy.max_retries, 2);
+        assert!(!config.enable_funds_protocols);
+        assert!(!config.dedup_pushes);
+        assert_eq!(config.sub_quota, None);
+        assert!(!config.response_cache.enabled);
+        assert!(!config.rate_limit.enabled);
+        assert_eq!(config.connect_timeout, Some(Duration::from_secs(10)));
+        assert!(config.tcp_keepalive.is_none());
+        assert!(config.read_timeout.is_none());
+        assert!(config.nodelay);
+        assert_eq!(config.keepalive_max_missed, Some(3));
+        assert!(config.fallback_endpoints.is_empty());
+        assert_eq!(config.trace_capacity, 64);
+        assert!(config.trace_dump_path.is_none());
+        assert!(config.capture_path.is_none());
+        assert!(config.gateway_log_dir.is_none());
+        assert!(config.slow_request_threshold.is_none());
     }
 
     #[test]
@@ -62,8 +214,34 @@ mod tests {
             client_ver: 200,
             rsa_key_path: Some(PathBuf::from("/tmp/rsa.key")),
             enable_encryption: true,
+            aes_padding: AesPadding::Futu,
             reconnect: false,
             reconnect_interval_secs: 10,
+            reconnect_max_interval_secs: 120,
+            reconnect_max_retries: Some(8),
+            connect_timeout: None,
+            tcp_keepalive: Some(Duration::from_secs(30)),
+            read_timeout: Some(Duration::from_secs(60)),
+            nodelay: false,
+            keepalive_max_missed: None,
+            fallback_endpoints: vec![("192.168.1.101".to_string(), 11111)],
+            trace_capacity: 0,
+            trace_dump_path: None,
+            capture_path: Some(PathBuf::from("/tmp/futu.cap")),
+            gateway_log_dir: Some(PathBuf::from("/tmp/opend/log")),
+            pause_trading_on_preempt: true,
+            dedup_pushes: true,
+            sub_quota: Some(300),
+            decode_mode: DecodeMode::Strict,
+            push_proto_fmt: ProtoFmt::Json,
+            #[cfg(feature = "trade")]
+            order_throttle: OrderThrottleConfig { entry_limit: Some(15), ..Default::default() },
+            retry_policy: RetryPolicy { max_retries: 0, ..Default::default() },
+            enable_funds_protocols: true,
+            response_cache: ResponseCacheConfig { enabled: true, ..Default::default() },
+            rate_limit: RateLimitConfig { enabled: true, ..Default::default() },
+            slow_request_threshold: Some(Duration::from_secs(5)),
+            clock: system_clock(),
         };
         assert_eq!(config.host, "192.168.1.100");
         assert_eq!(config.port, 22222);
@@ -71,8 +249,25 @@ mod tests {
         assert_eq!(config.client_ver, 200);
         assert_eq!(config.rsa_key_path.unwrap(), PathBuf::from("/tmp/rsa.key"));
         assert!(config.enable_encryption);
+        assert_eq!(config.aes_padding, AesPadding::Futu);
+        assert_eq!(config.push_proto_fmt, ProtoFmt::Json);
         assert!(!config.reconnect);
         assert_eq!(config.reconnect_interval_secs, 10);
+        assert_eq!(config.reconnect_max_retries, Some(8));
+        assert_eq!(config.tcp_keepalive, Some(Duration::from_secs(30)));
+        assert!(!config.nodelay);
+        assert_eq!(config.keepalive_max_missed, None);
+        assert_eq!(config.fallback_endpoints.len(), 1);
+        assert_eq!(config.gateway_log_dir, Some(PathBuf::from("/tmp/opend/log")));
+        assert!(config.pause_trading_on_preempt);
+        assert!(config.dedup_pushes);
+        assert_eq!(config.sub_quota, Some(300));
+        assert_eq!(config.decode_mode, DecodeMode::Strict);
+        assert!(config.enable_funds_protocols);
+        assert!(config.response_cache.enabled);
+        assert!(config.rate_limit.enabled);
+        #[cfg(feature = "trade")]
+        assert_eq!(config.order_throttle.entry_limit, Some(15));
     }
 
     #[test]
@@ -88,4 +283,33 @@ mod tests {
         assert_eq!(cloned.client_id, config.client_id);
         assert_eq!(cloned.enable_encryption, config.enable_encryption);
     }
+
+    #[test]
+    fn test_endpoints_order_and_ipv6() {
+        let config = FutuConfig {
+            host: "[::1]".to_string(),
+            port: 11111,
+            fallback_endpoints: vec![
+                ("fe80::1".to_string(), 11112),
+                ("10.0.0.2".to_string(), 11113),
+            ],
+            ..FutuConfig::default()
+        };
+        assert_eq!(
+            config.endpoints(),
+            vec![
+                ("::1".to_string(), 11111),
+                ("fe80::1".to_string(), 11112),
+                ("10.0.0.2".to_string(), 11113),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_normalize_host() {
+        assert_eq!(normalize_host("[::1]"), "::1");
+        assert_eq!(normalize_host("::1"), "::1");
+        assert_eq!(normalize_host("127.0.0.1"), "127.0.0.1");
+        assert_eq!(normalize_host("[bad"), "[bad");
+    }
 }
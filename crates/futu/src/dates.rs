@@ -0,0 +1,51 @@
+//! Calendar date arithmetic shared by the quote and trade modules.
+
+/// Add `delta` days to a `YYYY-MM-DD` date.
+#[cfg(feature = "quote")]
+pub(crate) fn add_days(date: &str, delta: i64) -> Option<String> {
+    let d = date.get(..10).unwrap_or(date);
+    let mut parts = d.split('-');
+    let y: i64 = parts.next()?.parse().ok()?;
+    let m: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+    let (y, m, day) = civil_from_days(days_from_civil(y, m, day) + delta);
+    Some(format!("{:04}-{:02}-{:02}", y, m, day))
+}
+
+// Howard Hinnant's civil date algorithms (proleptic Gregorian calendar).
+pub(crate) fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (m + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+pub(crate) fn civil_from_days(z: i64) -> (i64, i64, i64) {
+    let z = z + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    (y, m, d)
+}
+
+#[cfg(all(test, feature = "quote"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_days() {
+        assert_eq!(add_days("2024-02-28", 1).as_deref(), Some("2024-02-29"));
+        assert_eq!(add_days("2023-02-28", 1).as_deref(), Some("2023-03-01"));
+        assert_eq!(add_days("2024-12-31", 1).as_deref(), Some("2025-01-01"));
+        assert_eq!(add_days("2024-01-01 09:30:00", -1).as_deref(), Some("2023-12-31"));
+        assert_eq!(add_days("bad", 1), None);
+    }
+}
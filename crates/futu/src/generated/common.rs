@@ -1,15 +1,18 @@
 // This file is @generated by prost-build.
 /// 包的唯一标识，用于回放攻击的识别和保护
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct PacketId {
     /// 当前TCP连接的连接ID，一条连接的唯一标识，InitConnect协议会返回
     #[prost(uint64, required, tag = "1")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub conn_id: u64,
     /// 自增序列号
     #[prost(uint32, required, tag = "2")]
     pub serial_no: u32,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct ProgramStatus {
     /// 当前状态
     #[prost(enumeration = "ProgramStatusType", required, tag = "1")]
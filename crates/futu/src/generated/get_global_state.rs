@@ -3,12 +3,15 @@
 // https://github.com/FutunnOpen/py-futu-api/blob/master/futu/common/pb/GetGlobalState.proto
 
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct C2s {
     #[prost(uint64, required, tag = "1")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub user_id: u64,
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     #[prost(int32, required, tag = "1")]
     pub market_hk: i32,
@@ -29,6 +32,7 @@ pub struct S2c {
     #[prost(int32, required, tag = "9")]
     pub server_build_no: i32,
     #[prost(int64, required, tag = "10")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub time: i64,
     #[prost(double, optional, tag = "11")]
     pub local_time: ::core::option::Option<f64>,
@@ -45,12 +49,14 @@ pub struct S2c {
 }
 
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Request {
     #[prost(message, required, tag = "1")]
     pub c2s: C2s,
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     #[prost(int32, required, tag = "1", default = "-400")]
     pub ret_type: i32,
@@ -0,0 +1,92 @@
+// Hand-written prost structs for GetUserInfo (proto 1005).
+// Field tags match official Futu OpenD proto definition:
+// https://github.com/FutunnOpen/py-futu-api/blob/master/futu/common/pb/GetUserInfo.proto
+
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct C2s {
+    /// UserInfoField bits to return; all fields if unset
+    #[prost(int32, optional, tag = "2")]
+    pub flag: ::core::option::Option<i32>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct S2c {
+    #[prost(string, optional, tag = "1")]
+    pub nick_name: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "2")]
+    pub avatar_url: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "3")]
+    pub api_level: ::core::option::Option<::prost::alloc::string::String>,
+    /// Qot_Common.QotRight
+    #[prost(int32, optional, tag = "4")]
+    pub hk_qot_right: ::core::option::Option<i32>,
+    #[prost(int32, optional, tag = "5")]
+    pub us_qot_right: ::core::option::Option<i32>,
+    #[prost(int32, optional, tag = "6")]
+    pub cn_qot_right: ::core::option::Option<i32>,
+    #[prost(bool, optional, tag = "7")]
+    pub is_need_agree_disclaimer: ::core::option::Option<bool>,
+    #[prost(int64, optional, tag = "8")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::optional_int64"))]
+    pub user_id: ::core::option::Option<i64>,
+    /// UpdateType
+    #[prost(int32, optional, tag = "9")]
+    pub update_type: ::core::option::Option<i32>,
+    // tag 10: webKey (string) — skipped
+    #[prost(int32, optional, tag = "11")]
+    pub hk_option_qot_right: ::core::option::Option<i32>,
+    #[prost(bool, optional, tag = "12")]
+    pub has_us_option_qot_right: ::core::option::Option<bool>,
+    #[prost(int32, optional, tag = "13")]
+    pub hk_future_qot_right: ::core::option::Option<i32>,
+    #[prost(int32, optional, tag = "14")]
+    pub sub_quota: ::core::option::Option<i32>,
+    #[prost(int32, optional, tag = "15")]
+    pub history_kl_quota: ::core::option::Option<i32>,
+    #[prost(int32, optional, tag = "16")]
+    pub us_future_qot_right: ::core::option::Option<i32>,
+    #[prost(int32, optional, tag = "17")]
+    pub us_option_qot_right: ::core::option::Option<i32>,
+    // tag 18: webJumpUrlHead (string) — skipped
+    /// Common.UserAttribution
+    #[prost(int32, optional, tag = "19")]
+    pub user_attribution: ::core::option::Option<i32>,
+    #[prost(string, optional, tag = "20")]
+    pub update_whats_new: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(int32, optional, tag = "21")]
+    pub us_index_qot_right: ::core::option::Option<i32>,
+    #[prost(int32, optional, tag = "22")]
+    pub us_otc_qot_right: ::core::option::Option<i32>,
+    // tags 23-27: per-exchange US future rights — skipped
+    #[prost(int32, optional, tag = "28")]
+    pub sg_future_qot_right: ::core::option::Option<i32>,
+    #[prost(int32, optional, tag = "29")]
+    pub jp_future_qot_right: ::core::option::Option<i32>,
+    // tag 30: isAppNNOrMM (bool) — skipped
+    #[prost(int32, optional, tag = "31")]
+    pub sh_qot_right: ::core::option::Option<i32>,
+    #[prost(int32, optional, tag = "32")]
+    pub sz_qot_right: ::core::option::Option<i32>,
+}
+
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct Request {
+    #[prost(message, required, tag = "1")]
+    pub c2s: C2s,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct Response {
+    #[prost(int32, required, tag = "1", default = "-400")]
+    pub ret_type: i32,
+    #[prost(string, optional, tag = "2")]
+    pub ret_msg: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(int32, optional, tag = "3")]
+    pub err_code: ::core::option::Option<i32>,
+    #[prost(message, optional, tag = "4")]
+    pub s2c: ::core::option::Option<S2c>,
+}
@@ -1,5 +1,6 @@
 // This file is @generated by prost-build.
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct C2s {
     /// 客户端版本号，clientVer = "."以前的数 * 100 + "."以后的，举例：1.1版本的clientVer为1 * 100 + 1 = 101，2.21版本为2 * 100 + 21 = 221
     #[prost(int32, required, tag = "1")]
@@ -24,15 +25,18 @@ pub struct C2s {
     pub programming_language: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     /// FutuOpenD的版本号
     #[prost(int32, required, tag = "1")]
     pub server_ver: i32,
     /// FutuOpenD登陆的牛牛用户ID
     #[prost(uint64, required, tag = "2")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub login_user_id: u64,
     /// 此连接的连接ID，连接的唯一标识
     #[prost(uint64, required, tag = "3")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub conn_id: u64,
     /// 此连接后续AES加密通信的Key，固定为16字节长字符串
     #[prost(string, required, tag = "4")]
@@ -48,11 +52,13 @@ pub struct S2c {
     pub user_attribution: ::core::option::Option<i32>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Request {
     #[prost(message, required, tag = "1")]
     pub c2s: C2s,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     /// 返回结果，参见Common.RetType的枚举定义
     #[prost(int32, required, tag = "1", default = "-400")]
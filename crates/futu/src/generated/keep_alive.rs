@@ -1,22 +1,28 @@
 // This file is @generated by prost-build.
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct C2s {
     /// 客户端发包时的格林威治时间戳，单位秒
     #[prost(int64, required, tag = "1")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub time: i64,
 }
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     /// 服务器回包时的格林威治时间戳，单位秒
     #[prost(int64, required, tag = "1")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub time: i64,
 }
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Request {
     #[prost(message, required, tag = "1")]
     pub c2s: C2s,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     /// RetType,返回结果
     #[prost(int32, required, tag = "1", default = "-400")]
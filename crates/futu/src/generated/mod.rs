@@ -1,5 +1,9 @@
 // Generated protobuf code - files created by prost-build in build.rs
 // These modules correspond to .rs files generated from proto/*.proto
+// Qot_* protos are built with the `quote` feature and Trd_* protos with
+// `trade`; Qot_Common is shared. Instrument records carry
+// Trd_GetMarginRatio data, so it and Trd_Common are also built for
+// quote-only builds.
 
 #[allow(clippy::all)]
 pub mod init_connect;
@@ -9,107 +13,186 @@ pub mod keep_alive;
 pub mod common;
 #[allow(clippy::all)]
 pub mod qot_common;
+#[cfg(any(feature = "quote", feature = "trade"))]
 #[allow(clippy::all)]
 pub mod trd_common;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_sub;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_reg_qot_push;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_basic_qot;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_kl;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_order_book;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_ticker;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_update_basic_qot;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_update_kl;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_update_order_book;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_update_ticker;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_static_info;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_security_snapshot;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_history_kl;
+#[cfg(feature = "trade")]
 #[allow(clippy::all)]
 pub mod trd_get_acc_list;
+#[cfg(feature = "trade")]
 #[allow(clippy::all)]
 pub mod trd_unlock_trade;
+#[cfg(feature = "trade")]
 #[allow(clippy::all)]
 pub mod trd_place_order;
+#[cfg(feature = "trade")]
 #[allow(clippy::all)]
 pub mod trd_modify_order;
+#[cfg(feature = "trade")]
 #[allow(clippy::all)]
 pub mod trd_get_order_list;
+#[cfg(feature = "trade")]
 #[allow(clippy::all)]
 pub mod trd_get_order_fill_list;
+#[cfg(feature = "trade")]
 #[allow(clippy::all)]
 pub mod trd_get_position_list;
+#[cfg(feature = "trade")]
 #[allow(clippy::all)]
 pub mod trd_get_funds;
+#[cfg(feature = "trade")]
 #[allow(clippy::all)]
 pub mod trd_update_order;
+#[cfg(feature = "trade")]
 #[allow(clippy::all)]
 pub mod trd_update_order_fill;
+#[cfg(feature = "trade")]
 #[allow(clippy::all)]
 pub mod trd_sub_acc_push;
 #[allow(clippy::all)]
 pub mod get_global_state;
 #[allow(clippy::all)]
+pub mod get_user_info;
+#[cfg(feature = "quote")]
+#[allow(clippy::all)]
 pub mod qot_stock_filter;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_plate_security;
+#[cfg(feature = "trade")]
 #[allow(clippy::all)]
 pub mod trd_get_history_order_list;
+#[cfg(feature = "trade")]
 #[allow(clippy::all)]
 pub mod trd_get_history_order_fill_list;
+#[cfg(feature = "trade")]
 #[allow(clippy::all)]
 pub mod trd_get_max_trd_qtys;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_plate_set;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_owner_plate;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_rt;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_capital_flow;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_capital_distribution;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_broker;
+#[cfg(feature = "quote")]
+#[allow(clippy::all)]
+pub mod qot_get_order_detail;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_option_chain;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_option_expiration_date;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_warrant;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_future_info;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_reference;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_ipo_list;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_request_trade_date;
+#[cfg(feature = "quote")]
+#[allow(clippy::all)]
+pub mod qot_request_history_kl_quota;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_code_change;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_rehab;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_suspend;
+#[cfg(feature = "quote")]
+#[allow(clippy::all)]
+pub mod qot_get_market_state;
+#[cfg(any(feature = "quote", feature = "trade"))]
 #[allow(clippy::all)]
 pub mod trd_get_margin_ratio;
+#[cfg(feature = "trade")]
 #[allow(clippy::all)]
 pub mod trd_get_order_fee;
+#[cfg(feature = "trade")]
+#[allow(clippy::all)]
+pub mod trd_flow_summary;
+#[cfg(feature = "trade")]
+#[allow(clippy::all)]
+pub mod trd_reconfirm_order;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_user_security;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_modify_user_security;
+#[cfg(feature = "quote")]
 #[allow(clippy::all)]
 pub mod qot_get_sub_info;
+#[cfg(feature = "quote")]
+#[allow(clippy::all)]
+pub mod qot_set_price_reminder;
+#[cfg(feature = "quote")]
+#[allow(clippy::all)]
+pub mod qot_get_price_reminder;
+#[cfg(feature = "quote")]
+#[allow(clippy::all)]
+pub mod qot_update_price_reminder;
+#[allow(clippy::all)]
+pub mod notify;
@@ -0,0 +1,114 @@
+// This file is @generated by prost-build.
+/// 网关事件
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct GtwEvent {
+    /// GtwEventType,事件类型
+    #[prost(int32, required, tag = "1")]
+    pub event_type: i32,
+    /// 事件描述
+    #[prost(string, required, tag = "2")]
+    pub desc: ::prost::alloc::string::String,
+}
+/// 连接状态
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct ConnectStatus {
+    /// 是否登录行情服务器
+    #[prost(bool, required, tag = "1")]
+    pub qot_logined: bool,
+    /// 是否登录交易服务器
+    #[prost(bool, required, tag = "2")]
+    pub trd_logined: bool,
+}
+/// 行情权限
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct QotRight {
+    /// 港股行情权限, Qot_Common.QotRight
+    #[prost(int32, required, tag = "4")]
+    pub hk_qot_right: i32,
+    /// 美股行情权限, Qot_Common.QotRight
+    #[prost(int32, required, tag = "5")]
+    pub us_qot_right: i32,
+    /// A股行情权限, Qot_Common.QotRight
+    #[prost(int32, required, tag = "6")]
+    pub cn_qot_right: i32,
+    /// 港股期权行情权限, Qot_Common.QotRight
+    #[prost(int32, optional, tag = "7")]
+    pub hk_option_qot_right: ::core::option::Option<i32>,
+    /// 港股期货行情权限, Qot_Common.QotRight
+    #[prost(int32, optional, tag = "9")]
+    pub hk_future_qot_right: ::core::option::Option<i32>,
+}
+/// API用户等级
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct ApiLevel {
+    /// api用户等级描述
+    #[prost(string, required, tag = "3")]
+    pub api_level: ::prost::alloc::string::String,
+}
+/// API额度
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct ApiQuota {
+    /// 订阅额度
+    #[prost(int32, required, tag = "1")]
+    pub sub_quota: i32,
+    /// 历史K线额度
+    #[prost(int32, required, tag = "2")]
+    pub history_kl_quota: i32,
+}
+/// 已使用额度
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct UsedQuota {
+    /// 已使用订阅额度
+    #[prost(int32, optional, tag = "1")]
+    pub used_sub_quota: ::core::option::Option<i32>,
+    /// 已使用历史K线额度
+    #[prost(int32, optional, tag = "2")]
+    pub used_k_line_quota: ::core::option::Option<i32>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct S2c {
+    /// NotifyType,通知类型
+    #[prost(int32, required, tag = "1")]
+    pub r#type: i32,
+    /// 事件通知
+    #[prost(message, optional, tag = "2")]
+    pub event: ::core::option::Option<GtwEvent>,
+    /// 程序状态
+    #[prost(message, optional, tag = "3")]
+    pub program_status: ::core::option::Option<super::common::ProgramStatus>,
+    /// 连接状态
+    #[prost(message, optional, tag = "4")]
+    pub connect_status: ::core::option::Option<ConnectStatus>,
+    /// 行情权限
+    #[prost(message, optional, tag = "5")]
+    pub qot_right: ::core::option::Option<QotRight>,
+    /// 用户等级,已过期
+    #[prost(message, optional, tag = "6")]
+    pub api_level: ::core::option::Option<ApiLevel>,
+    /// API额度
+    #[prost(message, optional, tag = "7")]
+    pub api_quota: ::core::option::Option<ApiQuota>,
+    /// 已使用额度
+    #[prost(message, optional, tag = "8")]
+    pub used_quota: ::core::option::Option<UsedQuota>,
+}
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct Response {
+    /// RetType,返回结果
+    #[prost(int32, required, tag = "1", default = "-400")]
+    pub ret_type: i32,
+    #[prost(string, optional, tag = "2")]
+    pub ret_msg: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(int32, optional, tag = "3")]
+    pub err_code: ::core::option::Option<i32>,
+    #[prost(message, optional, tag = "4")]
+    pub s2c: ::core::option::Option<S2c>,
+}
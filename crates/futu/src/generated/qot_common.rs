@@ -1,6 +1,7 @@
 // This file is @generated by prost-build.
 /// 两个字段确定一支股票
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Security {
     /// QotMarket,股票市场
     #[prost(int32, required, tag = "1")]
@@ -10,6 +11,7 @@ pub struct Security {
     pub code: ::prost::alloc::string::String,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct KLine {
     /// 时间戳字符串
     #[prost(string, required, tag = "1")]
@@ -34,6 +36,7 @@ pub struct KLine {
     pub last_close_price: ::core::option::Option<f64>,
     /// 成交量
     #[prost(int64, optional, tag = "8")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::optional_int64"))]
     pub volume: ::core::option::Option<i64>,
     /// 成交额
     #[prost(double, optional, tag = "9")]
@@ -52,6 +55,7 @@ pub struct KLine {
     pub timestamp: ::core::option::Option<f64>,
 }
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct OptionBasicQotExData {
     /// 行权价
     #[prost(double, required, tag = "1")]
@@ -111,6 +115,7 @@ pub struct OptionBasicQotExData {
 /// 美股支持盘前盘后数据
 /// 科创板仅支持盘后数据：成交量，成交额
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct PreAfterMarketData {
     /// 盘前或盘后 - 价格
     #[prost(double, optional, tag = "1")]
@@ -123,6 +128,7 @@ pub struct PreAfterMarketData {
     pub low_price: ::core::option::Option<f64>,
     /// 盘前或盘后 - 成交量
     #[prost(int64, optional, tag = "4")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::optional_int64"))]
     pub volume: ::core::option::Option<i64>,
     /// 盘前或盘后 - 成交额
     #[prost(double, optional, tag = "5")]
@@ -138,6 +144,7 @@ pub struct PreAfterMarketData {
     pub amplitude: ::core::option::Option<f64>,
 }
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct FutureBasicQotExData {
     /// 昨结
     #[prost(double, required, tag = "1")]
@@ -153,6 +160,7 @@ pub struct FutureBasicQotExData {
     pub expiry_date_distance: ::core::option::Option<i32>,
 }
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct WarrantBasicQotExData {
     /// 对冲值,仅认购认沽支持该字段
     #[prost(double, optional, tag = "1")]
@@ -165,6 +173,7 @@ pub struct WarrantBasicQotExData {
     pub premium: f64,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct BasicQot {
     /// 股票
     #[prost(message, required, tag = "1")]
@@ -201,6 +210,7 @@ pub struct BasicQot {
     pub last_close_price: f64,
     /// 成交量
     #[prost(int64, required, tag = "11")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub volume: i64,
     /// 成交额
     #[prost(double, required, tag = "12")]
@@ -243,6 +253,7 @@ pub struct BasicQot {
     pub overnight: ::core::option::Option<PreAfterMarketData>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct TimeShare {
     /// 时间字符串
     #[prost(string, required, tag = "1")]
@@ -264,6 +275,7 @@ pub struct TimeShare {
     pub avg_price: ::core::option::Option<f64>,
     /// 成交量
     #[prost(int64, optional, tag = "7")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::optional_int64"))]
     pub volume: ::core::option::Option<i64>,
     /// 成交额
     #[prost(double, optional, tag = "8")]
@@ -273,12 +285,14 @@ pub struct TimeShare {
     pub timestamp: ::core::option::Option<f64>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct SecurityStaticBasic {
     /// 股票
     #[prost(message, required, tag = "1")]
     pub security: Security,
     /// 股票ID
     #[prost(int64, required, tag = "2")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub id: i64,
     /// 每手数量,期权以及期货类型表示合约乘数
     #[prost(int32, required, tag = "3")]
@@ -303,6 +317,7 @@ pub struct SecurityStaticBasic {
     pub exch_type: ::core::option::Option<i32>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct WarrantStaticExData {
     /// Qot_Common.WarrantType,窝轮类型
     #[prost(int32, required, tag = "1")]
@@ -312,6 +327,7 @@ pub struct WarrantStaticExData {
     pub owner: Security,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct OptionStaticExData {
     /// Qot_Common.OptionType,期权
     #[prost(int32, required, tag = "1")]
@@ -348,6 +364,7 @@ pub struct OptionStaticExData {
     pub option_settlement_mode: ::core::option::Option<i32>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct FutureStaticExData {
     /// 最后交易日，只有非主连期货合约才有该字段
     #[prost(string, required, tag = "1")]
@@ -360,6 +377,7 @@ pub struct FutureStaticExData {
     pub is_main_contract: bool,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct SecurityStaticInfo {
     /// 基本股票静态信息
     #[prost(message, required, tag = "1")]
@@ -375,9 +393,11 @@ pub struct SecurityStaticInfo {
     pub future_ex_data: ::core::option::Option<FutureStaticExData>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Broker {
     /// 经纪ID
     #[prost(int64, required, tag = "1")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub id: i64,
     /// 经纪名称
     #[prost(string, required, tag = "2")]
@@ -389,18 +409,22 @@ pub struct Broker {
     ///
     /// 交易所订单ID，与交易接口返回的订单ID并不一样
     #[prost(int64, optional, tag = "4")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::optional_int64"))]
     pub order_id: ::core::option::Option<i64>,
     /// 订单股数
     #[prost(int64, optional, tag = "5")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::optional_int64"))]
     pub volume: ::core::option::Option<i64>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Ticker {
     /// 时间字符串
     #[prost(string, required, tag = "1")]
     pub time: ::prost::alloc::string::String,
     /// 唯一标识
     #[prost(int64, required, tag = "2")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub sequence: i64,
     /// TickerDirection, 买卖方向
     #[prost(int32, required, tag = "3")]
@@ -410,6 +434,7 @@ pub struct Ticker {
     pub price: f64,
     /// 成交量
     #[prost(int64, required, tag = "5")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub volume: i64,
     /// 成交额
     #[prost(double, required, tag = "6")]
@@ -431,21 +456,26 @@ pub struct Ticker {
     pub timestamp: ::core::option::Option<f64>,
 }
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct OrderBookDetail {
     /// 交易所订单ID，与交易接口返回的订单ID并不一样
     #[prost(int64, required, tag = "1")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub order_id: i64,
     /// 订单股数
     #[prost(int64, required, tag = "2")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub volume: i64,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct OrderBook {
     /// 委托价格
     #[prost(double, required, tag = "1")]
     pub price: f64,
     /// 委托数量
     #[prost(int64, required, tag = "2")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub volume: i64,
     /// 委托订单个数
     #[prost(int32, required, tag = "3")]
@@ -454,8 +484,20 @@ pub struct OrderBook {
     #[prost(message, repeated, tag = "4")]
     pub detail_list: ::prost::alloc::vec::Vec<OrderBookDetail>,
 }
+/// 委托明细
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct OrderDetail {
+    /// 委托订单个数
+    #[prost(int32, required, tag = "1")]
+    pub order_count: i32,
+    /// 每笔委托的委托量，注意：当前只会返回最多前50笔委托的委托数量
+    #[prost(double, repeated, packed = "false", tag = "2")]
+    pub order_vol: ::prost::alloc::vec::Vec<f64>,
+}
 /// 持股变动
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct ShareHoldingChange {
     /// 持有者名称（机构名称 或 基金名称 或 高管姓名）
     #[prost(string, required, tag = "1")]
@@ -480,6 +522,7 @@ pub struct ShareHoldingChange {
     pub timestamp: ::core::option::Option<f64>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct SubInfo {
     /// Qot_Common.SubType,订阅类型
     #[prost(int32, required, tag = "1")]
@@ -489,6 +532,7 @@ pub struct SubInfo {
     pub security_list: ::prost::alloc::vec::Vec<Security>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct ConnSubInfo {
     /// 该连接订阅信息
     #[prost(message, repeated, tag = "1")]
@@ -501,6 +545,7 @@ pub struct ConnSubInfo {
     pub is_own_conn_data: bool,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct PlateInfo {
     /// 板块
     #[prost(message, required, tag = "1")]
@@ -513,12 +558,14 @@ pub struct PlateInfo {
     pub plate_type: ::core::option::Option<i32>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Rehab {
     /// 时间字符串
     #[prost(string, required, tag = "1")]
     pub time: ::prost::alloc::string::String,
     /// 公司行动(CompanyAct)组合标志位,指定某些字段值是否有效
     #[prost(int64, required, tag = "2")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub company_act_flag: i64,
     /// 前复权因子A
     #[prost(double, required, tag = "3")]
@@ -1352,6 +1399,8 @@ pub enum SubType {
     KlYear = 16,
     /// 3分K
     Kl3min = 17,
+    /// 委托明细
+    OrderDetail = 18,
 }
 impl SubType {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -1377,6 +1426,7 @@ impl SubType {
             Self::KlQurater => "SubType_KL_Qurater",
             Self::KlYear => "SubType_KL_Year",
             Self::Kl3min => "SubType_KL_3Min",
+            Self::OrderDetail => "SubType_OrderDetail",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -1399,6 +1449,7 @@ impl SubType {
             "SubType_KL_Qurater" => Some(Self::KlQurater),
             "SubType_KL_Year" => Some(Self::KlYear),
             "SubType_KL_3Min" => Some(Self::Kl3min),
+            "SubType_OrderDetail" => Some(Self::OrderDetail),
             _ => None,
         }
     }
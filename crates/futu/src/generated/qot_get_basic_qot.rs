@@ -1,22 +1,26 @@
 // This file is @generated by prost-build.
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct C2s {
     /// 股票
     #[prost(message, repeated, tag = "1")]
     pub security_list: ::prost::alloc::vec::Vec<super::qot_common::Security>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     /// 股票基本行情
     #[prost(message, repeated, tag = "1")]
     pub basic_qot_list: ::prost::alloc::vec::Vec<super::qot_common::BasicQot>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Request {
     #[prost(message, required, tag = "1")]
     pub c2s: C2s,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     /// RetType,返回结果
     #[prost(int32, required, tag = "1", default = "-400")]
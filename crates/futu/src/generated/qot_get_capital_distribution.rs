@@ -2,12 +2,14 @@
 // Tags match official Futu proto: Qot_GetCapitalDistribution.proto
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct C2s {
     #[prost(message, required, tag = "1")]
     pub security: super::qot_common::Security,
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     #[prost(double, required, tag = "1")]
     pub capital_in_big: f64,
@@ -32,12 +34,14 @@ pub struct S2c {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Request {
     #[prost(message, required, tag = "1")]
     pub c2s: C2s,
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     #[prost(int32, required, tag = "1", default = "-400")]
     pub ret_type: i32,
@@ -2,6 +2,7 @@
 // Tags match official Futu proto: Qot_GetCapitalFlow.proto
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct CapitalFlowItem {
     #[prost(double, required, tag = "1")]
     pub in_flow: f64,
@@ -22,6 +23,7 @@ pub struct CapitalFlowItem {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct C2s {
     #[prost(message, required, tag = "1")]
     pub security: super::qot_common::Security,
@@ -34,6 +36,7 @@ pub struct C2s {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     #[prost(message, repeated, tag = "1")]
     pub flow_item_list: ::prost::alloc::vec::Vec<CapitalFlowItem>,
@@ -44,12 +47,14 @@ pub struct S2c {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Request {
     #[prost(message, required, tag = "1")]
     pub c2s: C2s,
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     #[prost(int32, required, tag = "1", default = "-400")]
     pub ret_type: i32,
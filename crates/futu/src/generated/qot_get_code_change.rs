@@ -2,6 +2,7 @@
 // Tags match official Futu proto: Qot_GetCodeChange.proto
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct CodeChangeInfo {
     #[prost(int32, required, tag = "1")]
     pub r#type: i32,
@@ -24,6 +25,7 @@ pub struct CodeChangeInfo {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct TimeFilter {
     #[prost(int32, required, tag = "1")]
     pub r#type: i32,
@@ -34,6 +36,7 @@ pub struct TimeFilter {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct C2s {
     #[prost(int32, optional, tag = "1")]
     pub place_holder: ::core::option::Option<i32>,
@@ -46,18 +49,21 @@ pub struct C2s {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     #[prost(message, repeated, tag = "1")]
     pub code_change_list: ::prost::alloc::vec::Vec<CodeChangeInfo>,
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Request {
     #[prost(message, required, tag = "1")]
     pub c2s: C2s,
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     #[prost(int32, required, tag = "1", default = "-400")]
     pub ret_type: i32,
@@ -2,6 +2,7 @@
 // Tags match official Futu proto: Qot_GetFutureInfo.proto
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct TradeTime {
     #[prost(double, optional, tag = "1")]
     pub begin: ::core::option::Option<f64>,
@@ -10,6 +11,7 @@ pub struct TradeTime {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct FutureInfo {
     #[prost(string, required, tag = "1")]
     pub name: ::prost::alloc::string::String,
@@ -50,24 +52,28 @@ pub struct FutureInfo {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct C2s {
     #[prost(message, repeated, tag = "1")]
     pub security_list: ::prost::alloc::vec::Vec<super::qot_common::Security>,
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     #[prost(message, repeated, tag = "1")]
     pub future_info_list: ::prost::alloc::vec::Vec<FutureInfo>,
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Request {
     #[prost(message, required, tag = "1")]
     pub c2s: C2s,
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     #[prost(int32, required, tag = "1", default = "-400")]
     pub ret_type: i32,
@@ -1,5 +1,6 @@
 // This file is @generated by prost-build.
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct C2s {
     /// Qot_Common.RehabType,复权类型
     #[prost(int32, required, tag = "1")]
@@ -21,13 +22,16 @@ pub struct C2s {
     pub max_ack_kl_num: ::core::option::Option<i32>,
     /// 指定返回K线结构体特定某几项数据，KLFields枚举值或组合，如果未指定返回全部字段
     #[prost(int64, optional, tag = "7")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::optional_int64"))]
     pub need_kl_fields_flag: ::core::option::Option<i64>,
     /// 分页请求的key。如果请求时服务器因为数据量太大只返回了部分数据，那么s2c会返回nextReqKey，
     /// 此时用该key再次请求后面的数据
     #[prost(bytes = "vec", optional, tag = "8")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::optional_bytes"))]
     pub next_req_key: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     #[prost(message, required, tag = "1")]
     pub security: super::qot_common::Security,
@@ -36,14 +40,17 @@ pub struct S2c {
     pub kl_list: ::prost::alloc::vec::Vec<super::qot_common::KLine>,
     /// 分页请求的key，用于翻页的凭证
     #[prost(bytes = "vec", optional, tag = "3")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::optional_bytes"))]
     pub next_req_key: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Request {
     #[prost(message, required, tag = "1")]
     pub c2s: C2s,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     /// RetType,返回结果
     #[prost(int32, required, tag = "1", default = "-400")]
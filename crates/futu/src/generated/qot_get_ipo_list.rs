@@ -2,6 +2,7 @@
 // Tags match official Futu proto: Qot_GetIpoList.proto
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct BasicIpoData {
     #[prost(message, required, tag = "1")]
     pub security: super::qot_common::Security,
@@ -14,6 +15,7 @@ pub struct BasicIpoData {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct WinningNumData {
     #[prost(string, required, tag = "1")]
     pub winning_name: ::prost::alloc::string::String,
@@ -22,16 +24,21 @@ pub struct WinningNumData {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct CnIpoExData {
     #[prost(string, required, tag = "1")]
     pub apply_code: ::prost::alloc::string::String,
     #[prost(int64, required, tag = "2")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub issue_size: i64,
     #[prost(int64, required, tag = "3")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub online_issue_size: i64,
     #[prost(int64, required, tag = "4")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub apply_upper_limit: i64,
     #[prost(int64, required, tag = "5")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub apply_limit_market_value: i64,
     #[prost(bool, required, tag = "6")]
     pub is_estimate_ipo_price: bool,
@@ -60,6 +67,7 @@ pub struct CnIpoExData {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct HkIpoExData {
     #[prost(double, required, tag = "1")]
     pub ipo_price_min: f64,
@@ -80,16 +88,19 @@ pub struct HkIpoExData {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct UsIpoExData {
     #[prost(double, required, tag = "1")]
     pub ipo_price_min: f64,
     #[prost(double, required, tag = "2")]
     pub ipo_price_max: f64,
     #[prost(int64, required, tag = "3")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub issue_size: i64,
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct IpoData {
     #[prost(message, required, tag = "1")]
     pub basic: BasicIpoData,
@@ -102,24 +113,28 @@ pub struct IpoData {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct C2s {
     #[prost(int32, required, tag = "1")]
     pub market: i32,
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     #[prost(message, repeated, tag = "1")]
     pub ipo_list: ::prost::alloc::vec::Vec<IpoData>,
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Request {
     #[prost(message, required, tag = "1")]
     pub c2s: C2s,
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     #[prost(int32, required, tag = "1", default = "-400")]
     pub ret_type: i32,
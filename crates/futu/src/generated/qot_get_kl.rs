@@ -1,5 +1,6 @@
 // This file is @generated by prost-build.
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct C2s {
     /// Qot_Common.RehabType,复权类型
     #[prost(int32, required, tag = "1")]
@@ -15,6 +16,7 @@ pub struct C2s {
     pub req_num: i32,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     /// 股票
     #[prost(message, required, tag = "1")]
@@ -27,11 +29,13 @@ pub struct S2c {
     pub kl_list: ::prost::alloc::vec::Vec<super::qot_common::KLine>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Request {
     #[prost(message, required, tag = "1")]
     pub c2s: C2s,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     /// RetType,返回结果
     #[prost(int32, required, tag = "1", default = "-400")]
@@ -0,0 +1,48 @@
+// Hand-written prost structs for Qot_GetMarketState (proto_id 3223).
+// Tags match official Futu proto: Qot_GetMarketState.proto
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct C2s {
+    #[prost(message, repeated, tag = "1")]
+    pub security_list: ::prost::alloc::vec::Vec<super::qot_common::Security>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct MarketInfo {
+    #[prost(message, required, tag = "1")]
+    pub security: super::qot_common::Security,
+    #[prost(string, required, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+    /// Qot_Common.QotMarketState
+    #[prost(int32, required, tag = "3")]
+    pub market_state: i32,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct S2c {
+    #[prost(message, repeated, tag = "1")]
+    pub market_info_list: ::prost::alloc::vec::Vec<MarketInfo>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct Request {
+    #[prost(message, required, tag = "1")]
+    pub c2s: C2s,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct Response {
+    #[prost(int32, required, tag = "1", default = "-400")]
+    pub ret_type: i32,
+    #[prost(string, optional, tag = "2")]
+    pub ret_msg: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(int32, optional, tag = "3")]
+    pub err_code: ::core::option::Option<i32>,
+    #[prost(message, optional, tag = "4")]
+    pub s2c: ::core::option::Option<S2c>,
+}
@@ -2,6 +2,7 @@
 // Tags match official Futu proto: Qot_GetOptionChain.proto
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct DataFilter {
     #[prost(double, optional, tag = "1")]
     pub implied_volatility_min: ::core::option::Option<f64>,
@@ -42,6 +43,7 @@ pub struct DataFilter {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct OptionItem {
     #[prost(message, optional, tag = "1")]
     pub call: ::core::option::Option<super::qot_common::SecurityStaticInfo>,
@@ -50,6 +52,7 @@ pub struct OptionItem {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct OptionChain {
     #[prost(string, required, tag = "1")]
     pub strike_time: ::prost::alloc::string::String,
@@ -60,6 +63,7 @@ pub struct OptionChain {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct C2s {
     #[prost(message, required, tag = "1")]
     pub owner: super::qot_common::Security,
@@ -78,18 +82,21 @@ pub struct C2s {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     #[prost(message, repeated, tag = "1")]
     pub option_chain: ::prost::alloc::vec::Vec<OptionChain>,
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Request {
     #[prost(message, required, tag = "1")]
     pub c2s: C2s,
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     #[prost(int32, required, tag = "1", default = "-400")]
     pub ret_type: i32,
@@ -2,6 +2,7 @@
 // Tags match official Futu proto: Qot_GetOptionExpirationDate.proto
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct OptionExpirationDate {
     #[prost(string, optional, tag = "1")]
     pub strike_time: ::core::option::Option<::prost::alloc::string::String>,
@@ -14,6 +15,7 @@ pub struct OptionExpirationDate {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct C2s {
     #[prost(message, required, tag = "1")]
     pub owner: super::qot_common::Security,
@@ -22,18 +24,21 @@ pub struct C2s {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     #[prost(message, repeated, tag = "1")]
     pub date_list: ::prost::alloc::vec::Vec<OptionExpirationDate>,
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Request {
     #[prost(message, required, tag = "1")]
     pub c2s: C2s,
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     #[prost(int32, required, tag = "1", default = "-400")]
     pub ret_type: i32,
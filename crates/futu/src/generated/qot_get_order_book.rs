@@ -1,5 +1,6 @@
 // This file is @generated by prost-build.
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct C2s {
     /// 股票
     #[prost(message, required, tag = "1")]
@@ -9,6 +10,7 @@ pub struct C2s {
     pub num: i32,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     /// 股票
     #[prost(message, required, tag = "1")]
@@ -36,11 +38,13 @@ pub struct S2c {
     pub svr_recv_time_ask_timestamp: ::core::option::Option<f64>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Request {
     #[prost(message, required, tag = "1")]
     pub c2s: C2s,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     /// RetType,返回结果
     #[prost(int32, required, tag = "1", default = "-400")]
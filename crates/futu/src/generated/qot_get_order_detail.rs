@@ -0,0 +1,42 @@
+// Hand-written prost structs for Qot_GetOrderDetail (proto_id 3016).
+// Tags match official Futu proto: Qot_GetOrderDetail.proto
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct C2s {
+    #[prost(message, required, tag = "1")]
+    pub security: super::qot_common::Security,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct S2c {
+    #[prost(message, required, tag = "1")]
+    pub security: super::qot_common::Security,
+    #[prost(message, required, tag = "2")]
+    pub order_detail_ask: super::qot_common::OrderDetail,
+    #[prost(message, required, tag = "3")]
+    pub order_detail_bid: super::qot_common::OrderDetail,
+    #[prost(string, optional, tag = "4")]
+    pub name: ::core::option::Option<::prost::alloc::string::String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct Request {
+    #[prost(message, required, tag = "1")]
+    pub c2s: C2s,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct Response {
+    #[prost(int32, required, tag = "1", default = "-400")]
+    pub ret_type: i32,
+    #[prost(string, optional, tag = "2")]
+    pub ret_msg: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(int32, optional, tag = "3")]
+    pub err_code: ::core::option::Option<i32>,
+    #[prost(message, optional, tag = "4")]
+    pub s2c: ::core::option::Option<S2c>,
+}
@@ -0,0 +1,74 @@
+// Hand-written prost structs for Qot_GetPriceReminder.
+// Tags match official Futu proto: Qot_GetPriceReminder.proto
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct PriceReminderItem {
+    #[prost(int64, required, tag = "1")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
+    pub key: i64,
+    /// Qot_Common.PriceReminderType
+    #[prost(int32, required, tag = "2")]
+    pub r#type: i32,
+    #[prost(double, required, tag = "3")]
+    pub value: f64,
+    #[prost(string, required, tag = "4")]
+    pub note: ::prost::alloc::string::String,
+    /// Qot_Common.PriceReminderFreq
+    #[prost(int32, required, tag = "5")]
+    pub freq: i32,
+    #[prost(bool, required, tag = "6")]
+    pub is_enable: bool,
+    /// Qot_Common.PriceReminderMarketStatus
+    #[prost(int32, repeated, packed = "false", tag = "7")]
+    pub reminder_session_list: ::prost::alloc::vec::Vec<i32>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct PriceReminder {
+    #[prost(message, required, tag = "1")]
+    pub security: super::qot_common::Security,
+    #[prost(message, repeated, tag = "2")]
+    pub item_list: ::prost::alloc::vec::Vec<PriceReminderItem>,
+    #[prost(string, optional, tag = "3")]
+    pub name: ::core::option::Option<::prost::alloc::string::String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct C2s {
+    /// Reminders of one security; takes precedence over `market`
+    #[prost(message, optional, tag = "1")]
+    pub security: ::core::option::Option<super::qot_common::Security>,
+    /// Qot_Common.QotMarket: reminders of every security of the market
+    #[prost(int32, optional, tag = "2")]
+    pub market: ::core::option::Option<i32>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct S2c {
+    #[prost(message, repeated, tag = "1")]
+    pub price_reminder_list: ::prost::alloc::vec::Vec<PriceReminder>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct Request {
+    #[prost(message, required, tag = "1")]
+    pub c2s: C2s,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct Response {
+    #[prost(int32, required, tag = "1", default = "-400")]
+    pub ret_type: i32,
+    #[prost(string, optional, tag = "2")]
+    pub ret_msg: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(int32, optional, tag = "3")]
+    pub err_code: ::core::option::Option<i32>,
+    #[prost(message, optional, tag = "4")]
+    pub s2c: ::core::option::Option<S2c>,
+}
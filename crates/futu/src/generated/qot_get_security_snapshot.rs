@@ -1,5 +1,6 @@
 // This file is @generated by prost-build.
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct C2s {
     /// 股票
     #[prost(message, repeated, tag = "1")]
@@ -7,9 +8,11 @@ pub struct C2s {
 }
 /// 正股类型额外数据
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct EquitySnapshotExData {
     /// 发行股本,即总股本
     #[prost(int64, required, tag = "1")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub issued_shares: i64,
     /// 总市值 =总股本*当前价格（单位：元）
     #[prost(double, required, tag = "2")]
@@ -25,6 +28,7 @@ pub struct EquitySnapshotExData {
     pub earnings_pershare: f64,
     /// 流通股本
     #[prost(int64, required, tag = "6")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub outstanding_shares: i64,
     /// 流通市值 =流通股本*当前价格（单位：元）
     #[prost(double, required, tag = "7")]
@@ -59,6 +63,7 @@ pub struct EquitySnapshotExData {
 }
 /// 窝轮类型额外数据
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct WarrantSnapshotExData {
     /// 换股比率
     #[prost(double, required, tag = "1")]
@@ -83,9 +88,11 @@ pub struct WarrantSnapshotExData {
     pub recovery_price: f64,
     /// 街货量
     #[prost(int64, required, tag = "8")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub street_volumn: i64,
     /// 发行量
     #[prost(int64, required, tag = "9")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub issue_volumn: i64,
     /// 街货占比（该字段为百分比字段，默认不展示%，如20实际对应20%）
     #[prost(double, required, tag = "10")]
@@ -138,6 +145,7 @@ pub struct WarrantSnapshotExData {
 }
 /// 期权类型额外数据
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct OptionSnapshotExData {
     /// Qot_Common.OptionType,期权
     #[prost(int32, required, tag = "1")]
@@ -208,6 +216,7 @@ pub struct OptionSnapshotExData {
 }
 /// 指数类型额外数据
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct IndexSnapshotExData {
     /// 上涨支数
     #[prost(int32, required, tag = "1")]
@@ -221,6 +230,7 @@ pub struct IndexSnapshotExData {
 }
 /// 板块类型额外数据
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct PlateSnapshotExData {
     /// 上涨支数
     #[prost(int32, required, tag = "1")]
@@ -234,6 +244,7 @@ pub struct PlateSnapshotExData {
 }
 /// 期货类型额外数据
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct FutureSnapshotExData {
     /// 昨结
     #[prost(double, required, tag = "1")]
@@ -256,6 +267,7 @@ pub struct FutureSnapshotExData {
 }
 /// 基金类型额外数据
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct TrustSnapshotExData {
     /// 股息率（该字段为百分比字段，默认不展示%，如20实际对应20%）
     #[prost(double, required, tag = "1")]
@@ -265,6 +277,7 @@ pub struct TrustSnapshotExData {
     pub aum: f64,
     /// 总发行量
     #[prost(int64, required, tag = "3")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub outstanding_units: i64,
     /// 单位净值
     #[prost(double, required, tag = "4")]
@@ -278,6 +291,7 @@ pub struct TrustSnapshotExData {
 }
 /// 基本快照数据
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct SnapshotBasicData {
     /// 股票
     #[prost(message, required, tag = "1")]
@@ -320,6 +334,7 @@ pub struct SnapshotBasicData {
     pub cur_price: f64,
     /// 成交量
     #[prost(int64, required, tag = "13")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub volume: i64,
     /// 成交额
     #[prost(double, required, tag = "14")]
@@ -341,9 +356,11 @@ pub struct SnapshotBasicData {
     pub bid_price: ::core::option::Option<f64>,
     /// 卖量
     #[prost(int64, optional, tag = "20")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::optional_int64"))]
     pub ask_vol: ::core::option::Option<i64>,
     /// 买量
     #[prost(int64, optional, tag = "21")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::optional_int64"))]
     pub bid_vol: ::core::option::Option<i64>,
     /// 是否可融资，如果为true，后两个字段才有意义
     #[prost(bool, optional, tag = "22")]
@@ -362,6 +379,7 @@ pub struct SnapshotBasicData {
     pub short_sell_rate: ::core::option::Option<f64>,
     /// 剩余可卖空数量（股）
     #[prost(int64, optional, tag = "27")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::optional_int64"))]
     pub short_available_volume: ::core::option::Option<i64>,
     /// 卖空（融券）初始保证金率（该字段为百分比字段，默认不展示%，如20实际对应20%）
     #[prost(double, optional, tag = "28")]
@@ -407,6 +425,7 @@ pub struct SnapshotBasicData {
     pub overnight: ::core::option::Option<super::qot_common::PreAfterMarketData>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Snapshot {
     /// 快照基本数据
     #[prost(message, required, tag = "1")]
@@ -434,17 +453,20 @@ pub struct Snapshot {
     pub trust_ex_data: ::core::option::Option<TrustSnapshotExData>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     /// 股票快照
     #[prost(message, repeated, tag = "1")]
     pub snapshot_list: ::prost::alloc::vec::Vec<Snapshot>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Request {
     #[prost(message, required, tag = "1")]
     pub c2s: C2s,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     /// RetType,返回结果
     #[prost(int32, required, tag = "1", default = "-400")]
@@ -2,6 +2,7 @@
 // Tags match official Futu proto: Qot_GetWarrant.proto
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct WarrantData {
     #[prost(message, required, tag = "1")]
     pub stock: super::qot_common::Security,
@@ -48,10 +49,13 @@ pub struct WarrantData {
     #[prost(double, required, tag = "22")]
     pub ask_price: f64,
     #[prost(int64, required, tag = "23")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub bid_vol: i64,
     #[prost(int64, required, tag = "24")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub ask_vol: i64,
     #[prost(int64, required, tag = "25")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub volume: i64,
     #[prost(double, required, tag = "26")]
     pub turnover: f64,
@@ -72,10 +76,12 @@ pub struct WarrantData {
     #[prost(double, required, tag = "34")]
     pub street_rate: f64,
     #[prost(int64, required, tag = "35")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub street_vol: i64,
     #[prost(double, required, tag = "36")]
     pub amplitude: f64,
     #[prost(int64, required, tag = "37")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub issue_size: i64,
     // NOTE: There is no tag=38
     #[prost(double, required, tag = "39")]
@@ -97,6 +103,7 @@ pub struct WarrantData {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct C2s {
     #[prost(int32, required, tag = "1")]
     pub begin: i32,
@@ -139,8 +146,10 @@ pub struct C2s {
     #[prost(double, optional, tag = "20")]
     pub conversion_max: ::core::option::Option<f64>,
     #[prost(uint64, optional, tag = "21")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::optional_int64"))]
     pub vol_min: ::core::option::Option<u64>,
     #[prost(uint64, optional, tag = "22")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::optional_int64"))]
     pub vol_max: ::core::option::Option<u64>,
     #[prost(double, optional, tag = "23")]
     pub premium_min: ::core::option::Option<f64>,
@@ -169,6 +178,7 @@ pub struct C2s {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     #[prost(bool, required, tag = "1")]
     pub last_page: bool,
@@ -179,12 +189,14 @@ pub struct S2c {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Request {
     #[prost(message, required, tag = "1")]
     pub c2s: C2s,
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     #[prost(int32, required, tag = "1", default = "-400")]
     pub ret_type: i32,
@@ -0,0 +1,54 @@
+// Hand-written prost structs for Qot_RequestHistoryKLQuota (proto_id 3104).
+// Tags match official Futu proto: Qot_RequestHistoryKLQuota.proto
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct DetailItem {
+    #[prost(message, required, tag = "1")]
+    pub security: super::qot_common::Security,
+    #[prost(string, required, tag = "2")]
+    pub request_time: ::prost::alloc::string::String,
+    #[prost(int64, optional, tag = "3")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::optional_int64"))]
+    pub request_time_stamp: ::core::option::Option<i64>,
+    #[prost(string, optional, tag = "4")]
+    pub name: ::core::option::Option<::prost::alloc::string::String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct C2s {
+    #[prost(bool, optional, tag = "2")]
+    pub b_get_detail: ::core::option::Option<bool>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct S2c {
+    #[prost(int32, required, tag = "1")]
+    pub used_quota: i32,
+    #[prost(int32, required, tag = "2")]
+    pub remain_quota: i32,
+    #[prost(message, repeated, tag = "3")]
+    pub detail_list: ::prost::alloc::vec::Vec<DetailItem>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct Request {
+    #[prost(message, required, tag = "1")]
+    pub c2s: C2s,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct Response {
+    #[prost(int32, required, tag = "1", default = "-400")]
+    pub ret_type: i32,
+    #[prost(string, optional, tag = "2")]
+    pub ret_msg: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(int32, optional, tag = "3")]
+    pub err_code: ::core::option::Option<i32>,
+    #[prost(message, optional, tag = "4")]
+    pub s2c: ::core::option::Option<S2c>,
+}
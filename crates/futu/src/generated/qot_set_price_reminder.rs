@@ -0,0 +1,57 @@
+// Hand-written prost structs for Qot_SetPriceReminder.
+// Tags match official Futu proto: Qot_SetPriceReminder.proto
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct C2s {
+    #[prost(message, required, tag = "1")]
+    pub security: super::qot_common::Security,
+    /// SetPriceReminderOp
+    #[prost(int32, required, tag = "2")]
+    pub op: i32,
+    /// Key of the reminder; required by every op but Add and DelAll
+    #[prost(int64, optional, tag = "3")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::optional_int64"))]
+    pub key: ::core::option::Option<i64>,
+    /// Qot_Common.PriceReminderType
+    #[prost(int32, optional, tag = "4")]
+    pub r#type: ::core::option::Option<i32>,
+    #[prost(double, optional, tag = "5")]
+    pub value: ::core::option::Option<f64>,
+    #[prost(string, optional, tag = "6")]
+    pub note: ::core::option::Option<::prost::alloc::string::String>,
+    /// Qot_Common.PriceReminderFreq
+    #[prost(int32, optional, tag = "7")]
+    pub freq: ::core::option::Option<i32>,
+    /// Qot_Common.PriceReminderMarketStatus
+    #[prost(int32, repeated, packed = "false", tag = "8")]
+    pub reminder_session_list: ::prost::alloc::vec::Vec<i32>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct S2c {
+    #[prost(int64, required, tag = "1")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
+    pub key: i64,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct Request {
+    #[prost(message, required, tag = "1")]
+    pub c2s: C2s,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct Response {
+    #[prost(int32, required, tag = "1", default = "-400")]
+    pub ret_type: i32,
+    #[prost(string, optional, tag = "2")]
+    pub ret_msg: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(int32, optional, tag = "3")]
+    pub err_code: ::core::option::Option<i32>,
+    #[prost(message, optional, tag = "4")]
+    pub s2c: ::core::option::Option<S2c>,
+}
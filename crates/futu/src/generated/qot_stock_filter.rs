@@ -3,6 +3,7 @@
 
 /// Base attribute filter condition.
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct BaseFilter {
     /// StockField, attribute field
     #[prost(int32, required, tag = "1")]
@@ -23,6 +24,7 @@ pub struct BaseFilter {
 
 /// Accumulate attribute filter condition.
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct AccumulateFilter {
     /// AccumulateField, attribute field
     #[prost(int32, required, tag = "1")]
@@ -42,6 +44,7 @@ pub struct AccumulateFilter {
 
 /// Financial attribute filter condition.
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct FinancialFilter {
     /// FinancialField, attribute field
     #[prost(int32, required, tag = "1")]
@@ -61,6 +64,7 @@ pub struct FinancialFilter {
 
 /// Base data item in response.
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct BaseData {
     #[prost(int32, required, tag = "1")]
     pub field_name: i32,
@@ -70,6 +74,7 @@ pub struct BaseData {
 
 /// Accumulate data item in response.
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct AccumulateData {
     #[prost(int32, required, tag = "1")]
     pub field_name: i32,
@@ -81,6 +86,7 @@ pub struct AccumulateData {
 
 /// Financial data item in response.
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct FinancialData {
     #[prost(int32, required, tag = "1")]
     pub field_name: i32,
@@ -92,6 +98,7 @@ pub struct FinancialData {
 
 /// Single stock result in response.
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct StockData {
     #[prost(message, required, tag = "1")]
     pub security: super::qot_common::Security,
@@ -107,6 +114,7 @@ pub struct StockData {
 
 /// C2S request.
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct C2s {
     /// Data starting offset (0-based)
     #[prost(int32, required, tag = "1")]
@@ -130,6 +138,7 @@ pub struct C2s {
 
 /// S2C response.
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     /// True if this is the last page
     #[prost(bool, required, tag = "1")]
@@ -142,12 +151,14 @@ pub struct S2c {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Request {
     #[prost(message, required, tag = "1")]
     pub c2s: C2s,
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     #[prost(int32, required, tag = "1", default = "-400")]
     pub ret_type: i32,
@@ -1,5 +1,6 @@
 // This file is @generated by prost-build.
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     /// Qot_Common.RehabType,复权类型
     #[prost(int32, required, tag = "1")]
@@ -18,6 +19,7 @@ pub struct S2c {
     pub kl_list: ::prost::alloc::vec::Vec<super::qot_common::KLine>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     /// RetType,返回结果
     #[prost(int32, required, tag = "1", default = "-400")]
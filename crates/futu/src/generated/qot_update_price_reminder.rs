@@ -0,0 +1,48 @@
+// Hand-written prost structs for Qot_UpdatePriceReminder.
+// Tags match official Futu proto: Qot_UpdatePriceReminder.proto
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct S2c {
+    #[prost(message, required, tag = "1")]
+    pub security: super::qot_common::Security,
+    #[prost(double, required, tag = "2")]
+    pub price: f64,
+    #[prost(double, required, tag = "3")]
+    pub change_rate: f64,
+    /// Qot_Common.PriceReminderMarketStatus
+    #[prost(int32, required, tag = "4")]
+    pub market_status: i32,
+    /// Text of the reminder
+    #[prost(string, required, tag = "5")]
+    pub content: ::prost::alloc::string::String,
+    #[prost(string, required, tag = "6")]
+    pub note: ::prost::alloc::string::String,
+    #[prost(int64, optional, tag = "7")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::optional_int64"))]
+    pub key: ::core::option::Option<i64>,
+    /// Qot_Common.PriceReminderType
+    #[prost(int32, optional, tag = "8")]
+    pub r#type: ::core::option::Option<i32>,
+    /// Value the reminder was set to
+    #[prost(double, optional, tag = "9")]
+    pub set_value: ::core::option::Option<f64>,
+    /// Value that triggered the reminder
+    #[prost(double, optional, tag = "10")]
+    pub cur_value: ::core::option::Option<f64>,
+    #[prost(string, optional, tag = "11")]
+    pub name: ::core::option::Option<::prost::alloc::string::String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct Response {
+    #[prost(int32, required, tag = "1", default = "-400")]
+    pub ret_type: i32,
+    #[prost(string, optional, tag = "2")]
+    pub ret_msg: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(int32, optional, tag = "3")]
+    pub err_code: ::core::option::Option<i32>,
+    #[prost(message, optional, tag = "4")]
+    pub s2c: ::core::option::Option<S2c>,
+}
@@ -1,6 +1,7 @@
 // This file is @generated by prost-build.
 /// 账户现金信息，目前仅用于期货账户
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct AccCashInfo {
     /// 货币类型，取值参考 Currency
     #[prost(int32, optional, tag = "1")]
@@ -17,6 +18,7 @@ pub struct AccCashInfo {
 }
 /// 分市场资产信息
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct AccMarketInfo {
     /// 交易市场, 参见TrdMarket的枚举定义
     #[prost(int32, optional, tag = "1")]
@@ -27,12 +29,14 @@ pub struct AccMarketInfo {
 }
 /// 交易协议公共参数头
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct TrdHeader {
     /// 交易环境, 参见TrdEnv的枚举定义
     #[prost(int32, required, tag = "1")]
     pub trd_env: i32,
     /// 业务账号, 业务账号与交易环境、市场权限需要匹配，否则会返回错误
     #[prost(uint64, required, tag = "2")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub acc_id: u64,
     /// 交易市场, 参见TrdMarket的枚举定义
     #[prost(int32, required, tag = "3")]
@@ -40,12 +44,14 @@ pub struct TrdHeader {
 }
 /// 交易业务账户结构
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct TrdAcc {
     /// 交易环境，参见TrdEnv的枚举定义
     #[prost(int32, required, tag = "1")]
     pub trd_env: i32,
     /// 业务账号
     #[prost(uint64, required, tag = "2")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub acc_id: u64,
     /// 业务账户支持的交易市场权限，即此账户能交易那些市场, 可拥有多个交易市场权限，目前仅单个，取值参见TrdMarket的枚举定义
     #[prost(int32, repeated, packed = "false", tag = "3")]
@@ -71,6 +77,7 @@ pub struct TrdAcc {
 }
 /// 账户资金结构
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Funds {
     /// 最大购买力（做多），3位精度，下同。
     #[prost(double, required, tag = "1")]
@@ -174,9 +181,11 @@ pub struct Funds {
 }
 /// 账户持仓结构
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Position {
     /// 持仓ID，一条持仓的唯一标识
     #[prost(uint64, required, tag = "1")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub position_id: u64,
     /// 持仓方向，参见PositionSide的枚举定义
     #[prost(int32, required, tag = "2")]
@@ -255,6 +264,7 @@ pub struct Position {
 }
 /// 订单结构
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Order {
     /// 交易方向, 参见TrdSide的枚举定义
     #[prost(int32, required, tag = "1")]
@@ -267,6 +277,7 @@ pub struct Order {
     pub order_status: i32,
     /// 订单号
     #[prost(uint64, required, tag = "4")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub order_id: u64,
     /// 扩展订单号(仅查问题时备用)
     #[prost(string, required, tag = "5")]
@@ -339,6 +350,7 @@ pub struct Order {
     pub session: ::core::option::Option<i32>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct OrderFeeItem {
     /// 费用名字
     #[prost(string, optional, tag = "1")]
@@ -348,6 +360,7 @@ pub struct OrderFeeItem {
     pub value: ::core::option::Option<f64>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct OrderFee {
     /// 扩展订单号
     #[prost(string, required, tag = "1")]
@@ -361,18 +374,21 @@ pub struct OrderFee {
 }
 /// 成交结构
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct OrderFill {
     /// 交易方向, 参见TrdSide的枚举定义
     #[prost(int32, required, tag = "1")]
     pub trd_side: i32,
     /// 成交号
     #[prost(uint64, required, tag = "2")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub fill_id: u64,
     /// 扩展成交号(仅查问题时备用)
     #[prost(string, required, tag = "3")]
     pub fill_id_ex: ::prost::alloc::string::String,
     /// 订单号
     #[prost(uint64, optional, tag = "4")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::optional_int64"))]
     pub order_id: ::core::option::Option<u64>,
     /// 扩展订单号(仅查问题时备用)
     #[prost(string, optional, tag = "5")]
@@ -416,6 +432,7 @@ pub struct OrderFill {
 }
 /// 最大可交易数量
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct MaxTrdQtys {
     /// 因目前服务器实现的问题，卖空需要先卖掉持仓才能再卖空，是分开两步卖的，买回来同样是逆向两步；而看多的买是可以现金加融资一起一步买的，请注意这个差异
     ///
@@ -443,12 +460,14 @@ pub struct MaxTrdQtys {
 }
 /// 过滤条件，条件组合是"与"不是"或"，用于获取订单、成交、持仓等时二次过滤
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct TrdFilterConditions {
     /// 代码过滤，只返回包含这些代码的数据，没传不过滤
     #[prost(string, repeated, tag = "1")]
     pub code_list: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
     /// ID主键过滤，只返回包含这些ID的数据，没传不过滤，订单是orderID、成交是fillID、持仓是positionID
     #[prost(uint64, repeated, packed = "false", tag = "2")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::repeated_int64"))]
     pub id_list: ::prost::alloc::vec::Vec<u64>,
     /// 开始时间，严格按YYYY-MM-DD HH:MM:SS或YYYY-MM-DD HH:MM:SS.MS格式传，对持仓无效，拉历史数据必须填
     #[prost(string, optional, tag = "3")]
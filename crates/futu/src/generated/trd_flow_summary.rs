@@ -0,0 +1,64 @@
+// Hand-written prost structs for Trd_FlowSummary (proto_id 2226).
+// Tags match official Futu proto: Trd_FlowSummary.proto
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct FlowSummaryInfo {
+    #[prost(string, optional, tag = "1")]
+    pub clearing_date: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(string, optional, tag = "2")]
+    pub settlement_date: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(int32, optional, tag = "3")]
+    pub currency: ::core::option::Option<i32>,
+    #[prost(string, optional, tag = "4")]
+    pub cash_flow_type: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(int32, optional, tag = "5")]
+    pub cash_flow_direction: ::core::option::Option<i32>,
+    #[prost(double, optional, tag = "6")]
+    pub cash_flow_amount: ::core::option::Option<f64>,
+    #[prost(string, optional, tag = "7")]
+    pub cash_flow_remark: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(uint64, optional, tag = "8")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::optional_int64"))]
+    pub cash_flow_id: ::core::option::Option<u64>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct C2s {
+    #[prost(message, required, tag = "1")]
+    pub header: super::trd_common::TrdHeader,
+    #[prost(string, required, tag = "2")]
+    pub clearing_date: ::prost::alloc::string::String,
+    #[prost(int32, optional, tag = "3")]
+    pub cash_flow_direction: ::core::option::Option<i32>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct S2c {
+    #[prost(message, required, tag = "1")]
+    pub header: super::trd_common::TrdHeader,
+    #[prost(message, repeated, tag = "2")]
+    pub flow_summary_info_list: ::prost::alloc::vec::Vec<FlowSummaryInfo>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct Request {
+    #[prost(message, required, tag = "1")]
+    pub c2s: C2s,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct Response {
+    #[prost(int32, required, tag = "1", default = "-400")]
+    pub ret_type: i32,
+    #[prost(string, optional, tag = "2")]
+    pub ret_msg: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(int32, optional, tag = "3")]
+    pub err_code: ::core::option::Option<i32>,
+    #[prost(message, optional, tag = "4")]
+    pub s2c: ::core::option::Option<S2c>,
+}
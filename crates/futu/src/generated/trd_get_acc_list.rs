@@ -1,8 +1,10 @@
 // This file is @generated by prost-build.
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct C2s {
     /// 历史原因，目前已废弃，填0即可
     #[prost(uint64, required, tag = "1")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub user_id: u64,
     /// 交易品类，参考 Trd_Common.TrdCategory
     #[prost(int32, optional, tag = "2")]
@@ -12,17 +14,20 @@ pub struct C2s {
     pub need_general_sec_account: ::core::option::Option<bool>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     /// 交易业务账户列表
     #[prost(message, repeated, tag = "1")]
     pub acc_list: ::prost::alloc::vec::Vec<super::trd_common::TrdAcc>,
 }
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Request {
     #[prost(message, required, tag = "1")]
     pub c2s: C2s,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     /// 以下3个字段每条协议都有，注释说明在InitConnect.proto中
     #[prost(int32, required, tag = "1", default = "-400")]
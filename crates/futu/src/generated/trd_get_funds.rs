@@ -1,5 +1,6 @@
 // This file is @generated by prost-build.
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct C2s {
     /// 交易公共参数头
     #[prost(message, required, tag = "1")]
@@ -15,6 +16,7 @@ pub struct C2s {
     pub currency: ::core::option::Option<i32>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     /// 交易公共参数头
     #[prost(message, required, tag = "1")]
@@ -24,11 +26,13 @@ pub struct S2c {
     pub funds: ::core::option::Option<super::trd_common::Funds>,
 }
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Request {
     #[prost(message, required, tag = "1")]
     pub c2s: C2s,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     /// 以下3个字段每条协议都有，注释说明在InitConnect.proto中
     #[prost(int32, required, tag = "1", default = "-400")]
@@ -2,6 +2,7 @@
 // Tags match official Futu proto: Trd_GetMaxTrdQtys.proto
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct C2s {
     #[prost(message, required, tag = "1")]
     pub header: super::trd_common::TrdHeader,
@@ -12,6 +13,7 @@ pub struct C2s {
     #[prost(double, required, tag = "4")]
     pub price: f64,
     #[prost(uint64, optional, tag = "5")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::optional_int64"))]
     pub order_id: ::core::option::Option<u64>,
     #[prost(bool, optional, tag = "6")]
     pub adjust_price: ::core::option::Option<bool>,
@@ -24,6 +26,7 @@ pub struct C2s {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     #[prost(message, required, tag = "1")]
     pub header: super::trd_common::TrdHeader,
@@ -32,12 +35,14 @@ pub struct S2c {
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Request {
     #[prost(message, required, tag = "1")]
     pub c2s: C2s,
 }
 
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     #[prost(int32, required, tag = "1", default = "-400")]
     pub ret_type: i32,
@@ -1,5 +1,6 @@
 // This file is @generated by prost-build.
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct C2s {
     /// 交易公共参数头
     #[prost(message, required, tag = "1")]
@@ -14,6 +15,7 @@ pub struct C2s {
     pub refresh_cache: ::core::option::Option<bool>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     /// 交易公共参数头
     #[prost(message, required, tag = "1")]
@@ -23,11 +25,13 @@ pub struct S2c {
     pub order_fill_list: ::prost::alloc::vec::Vec<super::trd_common::OrderFill>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Request {
     #[prost(message, required, tag = "1")]
     pub c2s: C2s,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     /// 以下3个字段每条协议都有，注释说明在InitConnect.proto中
     #[prost(int32, required, tag = "1", default = "-400")]
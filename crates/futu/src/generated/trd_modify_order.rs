@@ -1,5 +1,6 @@
 // This file is @generated by prost-build.
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct C2s {
     /// 交易写操作防重放攻击
     #[prost(message, required, tag = "1")]
@@ -9,6 +10,7 @@ pub struct C2s {
     pub header: super::trd_common::TrdHeader,
     /// 订单号，forAll为true时，传0
     #[prost(uint64, required, tag = "3")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub order_id: u64,
     /// 修改操作类型，参见Trd_Common.ModifyOrderOp的枚举定义
     #[prost(int32, required, tag = "4")]
@@ -52,23 +54,27 @@ pub struct C2s {
     pub order_id_ex: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct S2c {
     /// 交易公共参数头
     #[prost(message, required, tag = "1")]
     pub header: super::trd_common::TrdHeader,
     /// 订单号
     #[prost(uint64, required, tag = "2")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
     pub order_id: u64,
     /// 服务器订单id
     #[prost(string, optional, tag = "3")]
     pub order_id_ex: ::core::option::Option<::prost::alloc::string::String>,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Request {
     #[prost(message, required, tag = "1")]
     pub c2s: C2s,
 }
 #[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
 pub struct Response {
     /// 以下3个字段每条协议都有，注释说明在InitConnect.proto中
     #[prost(int32, required, tag = "1", default = "-400")]
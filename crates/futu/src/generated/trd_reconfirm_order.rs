@@ -0,0 +1,52 @@
+// Hand-written prost structs for Trd_ReconfirmOrder (proto_id 2206).
+// Tags match official Futu proto: Trd_ReconfirmOrder.proto
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct C2s {
+    #[prost(message, required, tag = "1")]
+    pub packet_id: super::common::PacketId,
+    #[prost(message, required, tag = "2")]
+    pub header: super::trd_common::TrdHeader,
+    #[prost(uint64, required, tag = "3")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
+    pub order_id: u64,
+    /// ReconfirmOrderReason: the warning being confirmed
+    #[prost(int32, required, tag = "4")]
+    pub reconfirm_reason: i32,
+    /// Server order id, used instead of order_id if set
+    #[prost(string, optional, tag = "5")]
+    pub order_id_ex: ::core::option::Option<::prost::alloc::string::String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct S2c {
+    #[prost(message, required, tag = "1")]
+    pub header: super::trd_common::TrdHeader,
+    #[prost(uint64, required, tag = "2")]
+    #[cfg_attr(feature = "json", serde(deserialize_with = "crate::protocol::json::int64"))]
+    pub order_id: u64,
+    #[prost(string, optional, tag = "3")]
+    pub order_id_ex: ::core::option::Option<::prost::alloc::string::String>,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct Request {
+    #[prost(message, required, tag = "1")]
+    pub c2s: C2s,
+}
+
+#[derive(Clone, PartialEq, ::prost::Message)]
+#[cfg_attr(feature = "json", derive(serde::Deserialize), serde(default))]
+pub struct Response {
+    #[prost(int32, required, tag = "1", default = "-400")]
+    pub ret_type: i32,
+    #[prost(string, optional, tag = "2")]
+    pub ret_msg: ::core::option::Option<::prost::alloc::string::String>,
+    #[prost(int32, optional, tag = "3")]
+    pub err_code: ::core::option::Option<i32>,
+    #[prost(message, optional, tag = "4")]
+    pub s2c: ::core::option::Option<S2c>,
+}
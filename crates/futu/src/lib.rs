@@ -1,18 +1,54 @@
 pub mod config;
 pub mod protocol;
 pub mod client;
+#[cfg(feature = "quote")]
 pub mod quote;
+#[cfg(feature = "trade")]
 pub mod trade;
+#[cfg(feature = "python")]
 pub mod python;
+#[cfg(any(feature = "quote", feature = "trade"))]
+mod dates;
 
 // Re-export generated protobuf types
 pub mod generated;
+pub mod types;
 
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
 
 /// The Futu OpenD adapter Python module.
+#[cfg(feature = "python")]
 #[pymodule]
 fn _rust(m: &Bound<'_, PyModule>) -> PyResult<()> {
     m.add_class::<python::client::PyFutuClient>()?;
+    m.add_class::<python::pool::PyFutuClientPool>()?;
+    m.add_class::<python::models::BasicQot>()?;
+    m.add_class::<python::models::Kline>()?;
+    m.add_class::<python::models::Order>()?;
+    m.add_class::<python::models::Position>()?;
+    m.add_function(wrap_pyfunction!(python::logging::init_logging, m)?)?;
+    m.add_function(wrap_pyfunction!(python::logging::set_log_filter, m)?)?;
+    m.add("FutuError", m.py().get_type_bound::<python::errors::FutuError>())?;
+    m.add("FutuConnectionError", m.py().get_type_bound::<python::errors::FutuConnectionError>())?;
+    m.add("FutuServerError", m.py().get_type_bound::<python::errors::FutuServerError>())?;
+    m.add("FutuDecodeError", m.py().get_type_bound::<python::errors::FutuDecodeError>())?;
+    m.add("FutuTimeoutError", m.py().get_type_bound::<python::errors::FutuTimeoutError>())?;
+    m.add(
+        "SecurityFirmMismatchError",
+        m.py().get_type_bound::<python::errors::SecurityFirmMismatchError>(),
+    )?;
+    m.add(
+        "SessionPreemptedError",
+        m.py().get_type_bound::<python::errors::SessionPreemptedError>(),
+    )?;
+    m.add(
+        "SubscriptionRejectedError",
+        m.py().get_type_bound::<python::errors::SubscriptionRejectedError>(),
+    )?;
+    m.add(
+        "ThrottledError",
+        m.py().get_type_bound::<python::errors::ThrottledError>(),
+    )?;
     Ok(())
 }
@@ -1,14 +1,20 @@
-use bytes::BytesMut;
+use bytes::{Buf, Bytes, BytesMut};
 use tokio_util::codec::{Decoder, Encoder};
 
 use super::header::{HeaderError, PacketHeader, HEADER_SIZE};
+use super::json::ProtoFmt;
 
 /// A framed message consisting of header + body.
+///
+/// The body is reference-counted: cloning a message for each push subscriber
+/// shares one buffer instead of copying it.
 #[derive(Debug, Clone)]
 pub struct FutuMessage {
     pub proto_id: u32,
     pub serial_no: u32,
-    pub body: Vec<u8>,
+    pub body: Bytes,
+    /// Format of the body, from the header's proto_fmt_type.
+    pub proto_fmt: ProtoFmt,
 }
 
 /// Maximum allowed body size (100 MB) to prevent OOM from malicious/corrupted data.
@@ -27,20 +33,16 @@ impl Decoder for FutuCodec {
             return Ok(None);
         }
 
-        // Peek at the header to get body length without consuming
-        let body_len = {
-            let mut peek = src.clone();
-            match PacketHeader::decode(&mut peek) {
-                Ok(header) => {
-                    if header.body_len > MAX_BODY_SIZE {
-                        return Err(CodecError::BodyTooLarge(header.body_len));
-                    }
-                    header.body_len as usize
-                }
-                Err(HeaderError::InsufficientData) => return Ok(None),
-                Err(e) => return Err(CodecError::Header(e)),
-            }
+        // Peek at the header without consuming it
+        let header = match PacketHeader::parse(src) {
+            Ok(header) => header,
+            Err(HeaderError::InsufficientData) => return Ok(None),
+            Err(e) => return Err(CodecError::Header(e)),
         };
+        if header.body_len > MAX_BODY_SIZE {
+            return Err(CodecError::BodyTooLarge(header.body_len));
+        }
+        let body_len = header.body_len as usize;
 
         // Check if we have the full packet
         let total_len = HEADER_SIZE + body_len;
@@ -50,11 +52,11 @@ impl Decoder for FutuCodec {
             return Ok(None);
         }
 
-        // Now consume the header
-        let header = PacketHeader::decode(src).map_err(CodecError::Header)?;
-
-        // Extract body
-        let body = src.split_to(body_len).to_vec();
+        // Split the body off the read buffer without copying. Once every
+        // clone of it is dropped, the read buffer reclaims the space on its
+        // next reserve instead of allocating.
+        src.advance(HEADER_SIZE);
+        let body = src.split_to(body_len).freeze();
 
         // Verify body SHA1 checksum
         if !header.verify_body(&body) {
@@ -68,6 +70,7 @@ impl Decoder for FutuCodec {
             proto_id: header.proto_id,
             serial_no: header.serial_no,
             body,
+            proto_fmt: ProtoFmt::from_header(header.proto_fmt_type),
         }))
     }
 }
@@ -76,7 +79,8 @@ impl Encoder<FutuMessage> for FutuCodec {
     type Error = CodecError;
 
     fn encode(&mut self, item: FutuMessage, dst: &mut BytesMut) -> Result<(), Self::Error> {
-        let header = PacketHeader::new(item.proto_id, item.serial_no, &item.body);
+        let mut header = PacketHeader::new(item.proto_id, item.serial_no, &item.body);
+        header.proto_fmt_type = item.proto_fmt.as_i32() as u8;
         dst.reserve(HEADER_SIZE + item.body.len());
         header.encode(dst);
         dst.extend_from_slice(&item.body);
@@ -107,7 +111,8 @@ mod tests {
         let msg = FutuMessage {
             proto_id: 1001,
             serial_no: 42,
-            body: b"test body data".to_vec(),
+            body: Bytes::from_static(b"test body data"),
+            proto_fmt: ProtoFmt::Protobuf,
         };
 
         let mut buf = BytesMut::new();
@@ -116,7 +121,41 @@ mod tests {
         let decoded = codec.decode(&mut buf).unwrap().unwrap();
         assert_eq!(decoded.proto_id, 1001);
         assert_eq!(decoded.serial_no, 42);
-        assert_eq!(decoded.body, b"test body data");
+        assert_eq!(decoded.body, &b"test body data"[..]);
+    }
+
+    #[test]
+    fn test_codec_carries_proto_fmt() {
+        let mut codec = FutuCodec;
+        let msg = FutuMessage {
+            proto_id: 3011,
+            serial_no: 0,
+            body: Bytes::from_static(b"{\"retType\":0}"),
+            proto_fmt: ProtoFmt::Json,
+        };
+
+        let mut buf = BytesMut::new();
+        codec.encode(msg, &mut buf).unwrap();
+        assert_eq!(buf[6], 1);
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap().proto_fmt, ProtoFmt::Json);
+    }
+
+    #[test]
+    fn test_codec_body_shares_read_buffer() {
+        let mut codec = FutuCodec;
+        let msg = FutuMessage {
+            proto_id: 3011,
+            serial_no: 7,
+            body: Bytes::from_static(b"ticker push"),
+            proto_fmt: ProtoFmt::Protobuf,
+        };
+        let mut buf = BytesMut::new();
+        codec.encode(msg, &mut buf).unwrap();
+        let body_ptr = buf[HEADER_SIZE..].as_ptr();
+
+        let decoded = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(decoded.body.as_ptr(), body_ptr);
+        assert!(buf.is_empty());
     }
 
     #[test]
@@ -132,7 +171,8 @@ mod tests {
         let msg = FutuMessage {
             proto_id: 1001,
             serial_no: 1,
-            body: b"hello".to_vec(),
+            body: Bytes::from_static(b"hello"),
+            proto_fmt: ProtoFmt::Protobuf,
         };
 
         let mut full_buf = BytesMut::new();
@@ -150,12 +190,14 @@ mod tests {
         let msg1 = FutuMessage {
             proto_id: 1001,
             serial_no: 1,
-            body: b"first".to_vec(),
+            body: Bytes::from_static(b"first"),
+            proto_fmt: ProtoFmt::Protobuf,
         };
         let msg2 = FutuMessage {
             proto_id: 3001,
             serial_no: 2,
-            body: b"second".to_vec(),
+            body: Bytes::from_static(b"second"),
+            proto_fmt: ProtoFmt::Protobuf,
         };
 
         let mut buf = BytesMut::new();
@@ -165,12 +207,12 @@ mod tests {
         let d1 = codec.decode(&mut buf).unwrap().unwrap();
         assert_eq!(d1.proto_id, 1001);
         assert_eq!(d1.serial_no, 1);
-        assert_eq!(d1.body, b"first");
+        assert_eq!(d1.body, &b"first"[..]);
 
         let d2 = codec.decode(&mut buf).unwrap().unwrap();
         assert_eq!(d2.proto_id, 3001);
         assert_eq!(d2.serial_no, 2);
-        assert_eq!(d2.body, b"second");
+        assert_eq!(d2.body, &b"second"[..]);
     }
 
     #[test]
@@ -179,7 +221,8 @@ mod tests {
         let msg = FutuMessage {
             proto_id: 1004,
             serial_no: 10,
-            body: vec![],
+            body: Bytes::new(),
+            proto_fmt: ProtoFmt::Protobuf,
         };
         let mut buf = BytesMut::new();
         codec.encode(msg, &mut buf).unwrap();
@@ -197,7 +240,8 @@ mod tests {
         let msg = FutuMessage {
             proto_id: 3103,
             serial_no: 99,
-            body: body.clone(),
+            body: Bytes::from(body.clone()),
+            proto_fmt: ProtoFmt::Protobuf,
         };
         let mut buf = BytesMut::new();
         codec.encode(msg, &mut buf).unwrap();
@@ -215,7 +259,8 @@ mod tests {
         let msg = FutuMessage {
             proto_id: 1001,
             serial_no: 42,
-            body: b"original".to_vec(),
+            body: Bytes::from_static(b"original"),
+            proto_fmt: ProtoFmt::Protobuf,
         };
         let mut buf = BytesMut::new();
         codec.encode(msg, &mut buf).unwrap();
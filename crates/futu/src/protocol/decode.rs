@@ -0,0 +1,262 @@
+//! Protobuf body decoding with a configurable strictness for missing
+//! `required` fields.
+//!
+//! prost does not enforce proto2 `required`: a field OpenD leaves out
+//! decodes silently as its default (e.g. an `acc_id` of 0). Missing required
+//! fields are found by re-encoding the decoded message — prost always writes
+//! required fields, so any tag the re-encoded bytes have and the original
+//! lacks was missing. Fields are reported as dotted tag paths, e.g. `4.1.2`
+//! for field 2 of field 1 of the `s2c` (tag 4) message. The check costs an
+//! encode per decode, so it runs in Strict mode, and in Lenient mode only
+//! while warnings are logged.
+
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+use parking_lot::Mutex;
+use prost::Message;
+
+use super::json::{JsonMessage, ProtoFmt};
+
+/// How to treat bodies that lack `required` fields.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DecodeMode {
+    /// Decode missing fields as defaults and log a warning, once per message
+    /// type and set of missing fields. Tolerates older and newer OpenD
+    /// versions whose messages differ from the bundled definitions.
+    #[default]
+    Lenient,
+    /// Reject the body with a decode error.
+    Strict,
+}
+
+impl DecodeMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DecodeMode::Lenient => "lenient",
+            DecodeMode::Strict => "strict",
+        }
+    }
+}
+
+impl std::str::FromStr for DecodeMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lenient" => Ok(DecodeMode::Lenient),
+            "strict" => Ok(DecodeMode::Strict),
+            other => Err(format!("Unknown decode mode '{}', expected 'lenient' or 'strict'", other)),
+        }
+    }
+}
+
+/// Decode a message in `proto_fmt`, the format its header gives, checking
+/// for missing required fields according to `mode`. JSON bodies are decoded
+/// by `json::JsonMessage`; JSON has no required fields to check.
+pub fn decode_message<M: Message + Default + JsonMessage>(
+    body: &[u8],
+    proto_fmt: ProtoFmt,
+    mode: DecodeMode,
+) -> Result<M, String> {
+    if proto_fmt == ProtoFmt::Json {
+        return M::decode_json(body);
+    }
+    let message = M::decode(body).map_err(|e| e.to_string())?;
+    if !checks_required_fields(mode) {
+        return Ok(message);
+    }
+    let missing = missing_required_fields(body, &message.encode_to_vec());
+    if missing.is_empty() {
+        return Ok(message);
+    }
+
+    let name = message_name::<M>();
+    let fields = missing.join(", ");
+    match mode {
+        DecodeMode::Strict => Err(format!("{} is missing required field(s) {}", name, fields)),
+        DecodeMode::Lenient => {
+            static WARNED: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
+            let key = format!("{} {}", name, fields);
+            if WARNED.get_or_init(Default::default).lock().insert(key) {
+                tracing::warn!("{} is missing required field(s) {}, decoded as defaults", name, fields);
+            }
+            Ok(message)
+        }
+    }
+}
+
+/// Whether decoding in `mode` looks for missing required fields. Finding
+/// them re-encodes the message, so Lenient mode only does it when a tracing
+/// subscriber would record the warning.
+fn checks_required_fields(mode: DecodeMode) -> bool {
+    match mode {
+        DecodeMode::Strict => true,
+        DecodeMode::Lenient => tracing::enabled!(tracing::Level::WARN),
+    }
+}
+
+/// Tag paths present in `reencoded` but not in `original`.
+pub fn missing_required_fields(original: &[u8], reencoded: &[u8]) -> Vec<String> {
+    let mut missing = Vec::new();
+    collect_missing(original, reencoded, "", &mut missing);
+    missing
+}
+
+fn collect_missing<'a>(original: &'a [u8], reencoded: &'a [u8], prefix: &str, missing: &mut Vec<String>) {
+    if original == reencoded {
+        return;
+    }
+    let (Some(original), Some(reencoded)) = (parse_fields(original), parse_fields(reencoded)) else {
+        return;
+    };
+
+    let mut tags: Vec<u32> = reencoded.iter().map(|f| f.tag).collect();
+    tags.dedup();
+    for tag in tags {
+        if !original.iter().any(|f| f.tag == tag) {
+            missing.push(format!("{}{}", prefix, tag));
+            continue;
+        }
+        // Pair up length-delimited occurrences to descend into sub-messages.
+        // Strings and bytes re-encode unchanged, so they never report anything.
+        let payloads = |fields: &[WireField<'a>]| -> Vec<&'a [u8]> {
+            fields.iter().filter(|f| f.tag == tag).filter_map(|f| f.payload).collect()
+        };
+        let in_original = payloads(&original);
+        let in_reencoded = payloads(&reencoded);
+        if in_original.len() == in_reencoded.len() {
+            let nested = format!("{}{}.", prefix, tag);
+            for (o, r) in in_original.iter().zip(&in_reencoded) {
+                collect_missing(o, r, &nested, missing);
+            }
+        }
+    }
+    missing.dedup();
+}
+
+struct WireField<'a> {
+    tag: u32,
+    /// Contents of a length-delimited field.
+    payload: Option<&'a [u8]>,
+}
+
+/// Split a buffer into top-level fields. None if it is not a valid
+/// protobuf message (groups are not supported).
+fn parse_fields(mut buf: &[u8]) -> Option<Vec<WireField<'_>>> {
+    let mut fields = Vec::new();
+    while !buf.is_empty() {
+        let key = read_varint(&mut buf)?;
+        let tag = u32::try_from(key >> 3).ok().filter(|t| *t > 0)?;
+        let payload = match key & 0x7 {
+            0 => {
+                read_varint(&mut buf)?;
+                None
+            }
+            1 => {
+                buf = buf.get(8..)?;
+                None
+            }
+            2 => {
+                let len = usize::try_from(read_varint(&mut buf)?).ok()?;
+                let payload = buf.get(..len)?;
+                buf = &buf[len..];
+                Some(payload)
+            }
+            5 => {
+                buf = buf.get(4..)?;
+                None
+            }
+            _ => return None,
+        };
+        fields.push(WireField { tag, payload });
+    }
+    Some(fields)
+}
+
+fn read_varint(buf: &mut &[u8]) -> Option<u64> {
+    let mut value = 0u64;
+    for (i, byte) in buf.iter().enumerate().take(10) {
+        value |= u64::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            *buf = &buf[i + 1..];
+            return Some(value);
+        }
+    }
+    None
+}
+
+/// `qot_get_basic_qot::Response` rather than the full type path.
+fn message_name<M>() -> &'static str {
+    let name = std::any::type_name::<M>();
+    name.strip_prefix("nautilus_futu::generated::").unwrap_or(name)
+}
+
+#[cfg(all(test, feature = "trade"))]
+mod tests {
+    use super::*;
+    use crate::generated::trd_place_order::Response;
+
+    /// Place-order response whose s2c header lacks acc_id (tag 2).
+    fn response_without_acc_id() -> Vec<u8> {
+        let header = [0x08, 0x01, 0x18, 0x02]; // trd_env 1, trd_market 2
+        let mut s2c = vec![0x0a, header.len() as u8];
+        s2c.extend_from_slice(&header);
+        s2c.extend_from_slice(&[0x10, 42]); // order_id 42
+        let mut body = vec![0x08, 0x00, 0x22, s2c.len() as u8];
+        body.extend_from_slice(&s2c);
+        body
+    }
+
+    #[test]
+    fn test_complete_message_has_nothing_missing() {
+        let body = Response { ret_type: 0, ret_msg: Some("ok".to_string()), ..Default::default() }.encode_to_vec();
+        assert!(missing_required_fields(&body, &body).is_empty());
+        let decoded: Response = decode_message(&body, ProtoFmt::Protobuf, DecodeMode::Strict).unwrap();
+        assert_eq!(decoded.ret_msg.as_deref(), Some("ok"));
+    }
+
+    #[test]
+    fn test_missing_nested_required_field() {
+        let body = response_without_acc_id();
+        let reencoded = Response::decode(body.as_slice()).unwrap().encode_to_vec();
+        assert_eq!(missing_required_fields(&body, &reencoded), vec!["4.1.2".to_string()]);
+
+        let lenient: Response = decode_message(&body, ProtoFmt::Protobuf, DecodeMode::Lenient).unwrap();
+        let s2c = lenient.s2c.unwrap();
+        assert_eq!(s2c.header.acc_id, 0);
+        assert_eq!(s2c.header.trd_market, 2);
+        assert_eq!(s2c.order_id, Some(42));
+
+        let err = decode_message::<Response>(&body, ProtoFmt::Protobuf, DecodeMode::Strict).unwrap_err();
+        assert!(err.contains("trd_place_order::Response"), "{}", err);
+        assert!(err.contains("4.1.2"), "{}", err);
+    }
+
+    #[test]
+    fn test_missing_top_level_field_and_malformed_body() {
+        // Empty body: ret_type (tag 1) is required
+        let err = decode_message::<Response>(&[], ProtoFmt::Protobuf, DecodeMode::Strict).unwrap_err();
+        assert!(err.ends_with("field(s) 1"), "{}", err);
+        let lenient: Response = decode_message(&[], ProtoFmt::Protobuf, DecodeMode::Lenient).unwrap();
+        assert_eq!(lenient.ret_type, -400);
+
+        // Wire-type errors fail in both modes
+        assert!(decode_message::<Response>(&[0x0a, 0x01, 0xff], ProtoFmt::Protobuf, DecodeMode::Lenient).is_err());
+    }
+
+    #[test]
+    fn test_lenient_skips_check_without_warning_logs() {
+        // No tracing subscriber is installed in unit tests
+        assert!(checks_required_fields(DecodeMode::Strict));
+        assert!(!checks_required_fields(DecodeMode::Lenient));
+    }
+
+    #[test]
+    fn test_mode_from_str() {
+        assert_eq!("strict".parse::<DecodeMode>(), Ok(DecodeMode::Strict));
+        assert_eq!("lenient".parse::<DecodeMode>(), Ok(DecodeMode::Lenient));
+        assert!("loose".parse::<DecodeMode>().is_err());
+        assert_eq!(DecodeMode::default().as_str(), "lenient");
+    }
+}
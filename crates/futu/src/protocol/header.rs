@@ -44,6 +44,13 @@ impl PacketHeader {
     }
 
     pub fn decode(buf: &mut BytesMut) -> Result<Self, HeaderError> {
+        let header = Self::parse(buf)?;
+        buf.advance(HEADER_SIZE);
+        Ok(header)
+    }
+
+    /// Parse a header from the start of `buf` without consuming it.
+    pub fn parse(buf: &[u8]) -> Result<Self, HeaderError> {
         if buf.len() < HEADER_SIZE {
             return Err(HeaderError::InsufficientData);
         }
@@ -62,8 +69,6 @@ impl PacketHeader {
         body_sha1.copy_from_slice(&buf[16..36]);
         // Skip reserved bytes 36..44
 
-        buf.advance(HEADER_SIZE);
-
         Ok(Self {
             proto_id,
             proto_fmt_type,
@@ -0,0 +1,305 @@
+//! JSON bodies, OpenD's alternative to protobuf.
+//!
+//! OpenD writes pushes in the format InitConnect asks for with
+//! `push_proto_fmt`, and answers a request in the format of the request.
+//! Each packet header gives the format of its body (`FutuMessage::proto_fmt`).
+//! `decode::decode_message` hands JSON bodies to `JsonMessage`, so JSON
+//! pushes decode into the same prost structs as protobuf ones.
+//!
+//! With the `json` feature, the structs of `generated` derive
+//! `serde::Deserialize`. OpenD prints fields under their proto names
+//! (`connAESKey`) while the structs use prost's snake_case names
+//! (`conn_aes_key`), so keys are converted the way prost converts them
+//! before deserializing. 64-bit integers may be written as strings and
+//! bytes as base64, as the proto3 JSON mapping has them. Fields declared
+//! as protobuf enums are read as numbers only. `decode_value` reads a body
+//! without a struct, e.g. for protos without a prost module. Without the
+//! feature, JSON bodies fail to decode.
+
+/// Body format of a packet (the header's proto_fmt_type).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ProtoFmt {
+    #[default]
+    Protobuf,
+    Json,
+}
+
+impl ProtoFmt {
+    /// The value of header proto_fmt_type and InitConnect push_proto_fmt.
+    pub fn as_i32(self) -> i32 {
+        match self {
+            ProtoFmt::Protobuf => 0,
+            ProtoFmt::Json => 1,
+        }
+    }
+
+    /// The format of a header proto_fmt_type. Values other than JSON's
+    /// are read as protobuf, the format OpenD defaults to.
+    pub fn from_header(proto_fmt_type: u8) -> Self {
+        match proto_fmt_type {
+            1 => ProtoFmt::Json,
+            _ => ProtoFmt::Protobuf,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProtoFmt::Protobuf => "protobuf",
+            ProtoFmt::Json => "json",
+        }
+    }
+}
+
+impl std::str::FromStr for ProtoFmt {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "protobuf" => Ok(ProtoFmt::Protobuf),
+            "json" => Ok(ProtoFmt::Json),
+            other => Err(format!("Unknown proto format '{}', expected 'protobuf' or 'json'", other)),
+        }
+    }
+}
+
+/// Messages that can be decoded from a JSON body.
+pub trait JsonMessage: Sized {
+    fn decode_json(body: &[u8]) -> Result<Self, String>;
+}
+
+#[cfg(feature = "json")]
+impl<M: serde::de::DeserializeOwned> JsonMessage for M {
+    fn decode_json(body: &[u8]) -> Result<Self, String> {
+        serde_json::from_value(decode_value(body)?).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(not(feature = "json"))]
+impl<M> JsonMessage for M {
+    fn decode_json(_body: &[u8]) -> Result<Self, String> {
+        Err("JSON body, but built without the json feature".to_string())
+    }
+}
+
+/// A JSON body as a value, its keys converted to snake_case.
+#[cfg(feature = "json")]
+pub fn decode_value(body: &[u8]) -> Result<serde_json::Value, String> {
+    let value = serde_json::from_slice(body).map_err(|e| e.to_string())?;
+    Ok(snake_case_keys(value))
+}
+
+#[cfg(feature = "json")]
+fn snake_case_keys(value: serde_json::Value) -> serde_json::Value {
+    use serde_json::Value;
+    match value {
+        Value::Object(map) => Value::Object(map.into_iter().map(|(k, v)| (snake_case(&k), snake_case_keys(v))).collect()),
+        Value::Array(items) => Value::Array(items.into_iter().map(snake_case_keys).collect()),
+        other => other,
+    }
+}
+
+/// A proto field name as prost names its struct field: words start at a
+/// capital following a lowercase letter, and at the last capital of a run
+/// followed by a lowercase letter, so `connAESKey` becomes `conn_aes_key`.
+/// Digits belong to the word they follow.
+pub fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+            if prev.is_lowercase() || prev.is_ascii_digit() || (prev.is_uppercase() && next_lower) {
+                out.push('_');
+            }
+        }
+        out.extend(c.to_lowercase());
+    }
+    out
+}
+
+#[cfg(feature = "json")]
+pub use self::serde_fields::*;
+
+/// `deserialize_with` functions of the generated structs.
+#[cfg(feature = "json")]
+mod serde_fields {
+    use serde::{Deserialize, Deserializer};
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString<T> {
+        Number(T),
+        String(String),
+    }
+
+    impl<T: std::str::FromStr> NumberOrString<T> {
+        fn into_number<E: serde::de::Error>(self) -> Result<T, E> {
+            match self {
+                NumberOrString::Number(n) => Ok(n),
+                NumberOrString::String(s) => s.parse().map_err(|_| E::custom(format!("invalid integer '{}'", s))),
+            }
+        }
+    }
+
+    /// A 64-bit integer, as a number or a string.
+    pub fn int64<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de> + std::str::FromStr,
+    {
+        NumberOrString::<T>::deserialize(deserializer)?.into_number()
+    }
+
+    pub fn optional_int64<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de> + std::str::FromStr,
+    {
+        Option::<NumberOrString<T>>::deserialize(deserializer)?.map(NumberOrString::into_number).transpose()
+    }
+
+    pub fn repeated_int64<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: Deserialize<'de> + std::str::FromStr,
+    {
+        Vec::<NumberOrString<T>>::deserialize(deserializer)?.into_iter().map(NumberOrString::into_number).collect()
+    }
+
+    /// Bytes as base64.
+    pub fn optional_bytes<'de, D>(deserializer: D) -> Result<Option<Vec<u8>>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<String>::deserialize(deserializer)?
+            .map(|s| super::base64_decode(&s).ok_or_else(|| serde::de::Error::custom("invalid base64")))
+            .transpose()
+    }
+}
+
+/// Decode standard or URL-safe base64, padded or not.
+#[cfg(feature = "json")]
+fn base64_decode(s: &str) -> Option<Vec<u8>> {
+    fn sextet(c: u8) -> Option<u32> {
+        match c {
+            b'A'..=b'Z' => Some((c - b'A') as u32),
+            b'a'..=b'z' => Some((c - b'a' + 26) as u32),
+            b'0'..=b'9' => Some((c - b'0' + 52) as u32),
+            b'+' | b'-' => Some(62),
+            b'/' | b'_' => Some(63),
+            _ => None,
+        }
+    }
+    let s = s.trim_end_matches('=').as_bytes();
+    let mut out = Vec::with_capacity(s.len() * 3 / 4);
+    for chunk in s.chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+        let mut acc = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            acc |= sextet(c)? << (18 - 6 * i);
+        }
+        out.extend_from_slice(&acc.to_be_bytes()[1..chunk.len()]);
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snake_case_matches_prost() {
+        assert_eq!(snake_case("retType"), "ret_type");
+        assert_eq!(snake_case("connAESKey"), "conn_aes_key");
+        assert_eq!(snake_case("aesCBCiv"), "aes_cb_civ");
+        assert_eq!(snake_case("orderIDEx"), "order_id_ex");
+        assert_eq!(snake_case("s2c"), "s2c");
+        assert_eq!(snake_case("already_snake"), "already_snake");
+    }
+
+    #[test]
+    fn test_proto_fmt() {
+        assert_eq!(ProtoFmt::from_header(1), ProtoFmt::Json);
+        assert_eq!(ProtoFmt::from_header(0), ProtoFmt::Protobuf);
+        assert_eq!(ProtoFmt::from_header(ProtoFmt::Json.as_i32() as u8), ProtoFmt::Json);
+        assert_eq!("json".parse::<ProtoFmt>(), Ok(ProtoFmt::Json));
+        assert!("xml".parse::<ProtoFmt>().is_err());
+    }
+
+    #[cfg(all(feature = "json", feature = "quote"))]
+    #[test]
+    fn test_decode_into_prost_structs() {
+        use crate::generated::{init_connect, qot_get_history_kl};
+
+        let body = br#"{"retType":0,"s2c":{"serverVer":904,"loginUserID":"12345678901","connID":"7","connAESKey":"0123456789abcdef","keepAliveInterval":10}}"#;
+        let response = init_connect::Response::decode_json(body).unwrap();
+        assert_eq!(response.ret_type, 0);
+        let s2c = response.s2c.unwrap();
+        assert_eq!((s2c.server_ver, s2c.login_user_id, s2c.conn_id), (904, 12345678901, 7));
+        assert_eq!(s2c.conn_aes_key, "0123456789abcdef");
+        // Fields left out decode as their defaults, as in protobuf
+        assert_eq!(s2c.aes_cb_civ, None);
+
+        let body = br#"{"retType":0,"s2c":{"security":{"market":1,"code":"00700"},"klList":[],"nextReqKey":"AQID"}}"#;
+        let response = qot_get_history_kl::Response::decode_json(body).unwrap();
+        assert_eq!(response.s2c.unwrap().next_req_key, Some(vec![1, 2, 3]));
+
+        assert!(init_connect::Response::decode_json(br#"{"retType":"zero"}"#).is_err());
+    }
+
+    #[test]
+    fn test_decode_message_routes_on_proto_fmt() {
+        use crate::generated::init_connect;
+        use crate::protocol::decode::{decode_message, DecodeMode};
+
+        let body = br#"{"retType":-1,"retMsg":"no quota"}"#;
+        let decoded = decode_message::<init_connect::Response>(body, ProtoFmt::Json, DecodeMode::Strict);
+        #[cfg(feature = "json")]
+        assert_eq!(decoded.unwrap().ret_msg.as_deref(), Some("no quota"));
+        #[cfg(not(feature = "json"))]
+        assert!(decoded.unwrap_err().contains("json feature"));
+
+        // The header says protobuf, so the body is not read as JSON
+        assert!(decode_message::<init_connect::Response>(body, ProtoFmt::Protobuf, DecodeMode::Lenient).is_err());
+    }
+
+    #[cfg(all(feature = "json", feature = "quote"))]
+    #[tokio::test]
+    async fn test_json_pushes_against_mock() {
+        use crate::client::mock::MockOpenD;
+        use crate::client::FutuClient;
+        use crate::config::FutuConfig;
+        use crate::generated::qot_update_ticker;
+
+        let mock = MockOpenD::start().await.unwrap();
+        let config = FutuConfig {
+            port: mock.port(),
+            enable_encryption: false,
+            push_proto_fmt: ProtoFmt::Json,
+            ..FutuConfig::default()
+        };
+        let mut client = FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+        let mut rx = client.subscribe_push(3011).await;
+
+        let body = r#"{"retType":0,"s2c":{"security":{"market":1,"code":"00700"},"tickerList":[{"time":"2024-01-02 09:30:00","sequence":"88000000000001","dir":1,"price":300.2,"volume":"500","turnover":150100.0}]}}"#;
+        mock.push_fmt(3011, ProtoFmt::Json, body.as_bytes().to_vec());
+        let push = rx.recv().await.unwrap();
+        let response: qot_update_ticker::Response = client.decode(&push).unwrap();
+        let ticker = &response.s2c.unwrap().ticker_list[0];
+        assert_eq!((ticker.sequence, ticker.volume), (88000000000001, 500));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64_decode("aGVsbG8="), Some(b"hello".to_vec()));
+        assert_eq!(base64_decode("aGVsbG8h"), Some(b"hello!".to_vec()));
+        assert_eq!(base64_decode(""), Some(Vec::new()));
+        assert_eq!(base64_decode("a"), None);
+        assert_eq!(base64_decode("a*=="), None);
+    }
+}
@@ -1,7 +1,11 @@
 pub mod codec;
+pub mod decode;
 pub mod encryption;
 pub mod header;
+pub mod json;
 
 pub use codec::{CodecError, FutuCodec, FutuMessage};
+pub use decode::DecodeMode;
 pub use encryption::AesEcbCipher;
 pub use header::{PacketHeader, HEADER_SIZE};
+pub use json::ProtoFmt;
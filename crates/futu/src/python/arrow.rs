@@ -0,0 +1,90 @@
+//! History K-lines as Arrow record batches.
+//!
+//! Building a dict per K-line and a DataFrame from the dicts dominates the
+//! cost of loading long histories in Python. With the `arrow` feature the
+//! K-lines are written straight into Arrow columns, and the record batch is
+//! handed to pyarrow through the C data interface, so Polars or Pandas can
+//! load it without copying the columns again.
+
+use std::sync::Arc;
+
+use arrow::array::{ArrayRef, BooleanArray, Float64Array, Int64Array, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::error::ArrowError;
+use arrow::record_batch::RecordBatch;
+
+use crate::generated::qot_common::KLine;
+
+/// Columns of a K-line batch: the fields of `get_history_kl()` dicts plus
+/// turnover_rate, pe and change_rate. Missing values are nulls.
+pub fn kl_schema() -> Schema {
+    let price = |name| Field::new(name, DataType::Float64, true);
+    Schema::new(vec![
+        Field::new("time", DataType::Utf8, false),
+        price("timestamp"),
+        Field::new("is_blank", DataType::Boolean, false),
+        price("open_price"),
+        price("high_price"),
+        price("low_price"),
+        price("close_price"),
+        price("last_close_price"),
+        Field::new("volume", DataType::Int64, true),
+        price("turnover"),
+        price("turnover_rate"),
+        price("pe"),
+        price("change_rate"),
+    ])
+}
+
+/// One record batch of `kl_list`, a row per K-line.
+pub fn kl_record_batch(kl_list: &[KLine]) -> Result<RecordBatch, ArrowError> {
+    let float = |get: fn(&KLine) -> Option<f64>| -> ArrayRef { Arc::new(kl_list.iter().map(get).collect::<Float64Array>()) };
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(kl_list.iter().map(|kl| Some(kl.time.as_str())).collect::<StringArray>()),
+        float(|kl| kl.timestamp),
+        Arc::new(kl_list.iter().map(|kl| Some(kl.is_blank)).collect::<BooleanArray>()),
+        float(|kl| kl.open_price),
+        float(|kl| kl.high_price),
+        float(|kl| kl.low_price),
+        float(|kl| kl.close_price),
+        float(|kl| kl.last_close_price),
+        Arc::new(kl_list.iter().map(|kl| kl.volume).collect::<Int64Array>()),
+        float(|kl| kl.turnover),
+        float(|kl| kl.turnover_rate),
+        float(|kl| kl.pe),
+        float(|kl| kl.change_rate),
+    ];
+    RecordBatch::try_new(Arc::new(kl_schema()), columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Array;
+
+    #[test]
+    fn test_kl_record_batch() {
+        let kl_list = vec![
+            KLine {
+                time: "2024-01-02 00:00:00".to_string(),
+                open_price: Some(300.0),
+                close_price: Some(305.5),
+                volume: Some(1200),
+                ..Default::default()
+            },
+            KLine { time: "2024-01-03 00:00:00".to_string(), is_blank: true, ..Default::default() },
+        ];
+        let batch = kl_record_batch(&kl_list).unwrap();
+        assert_eq!((batch.num_rows(), batch.num_columns()), (2, 13));
+
+        let close = batch.column_by_name("close_price").unwrap().as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(close.value(0), 305.5);
+        assert!(close.is_null(1));
+        let volume = batch.column_by_name("volume").unwrap().as_any().downcast_ref::<Int64Array>().unwrap();
+        assert_eq!((volume.value(0), volume.null_count()), (1200, 1));
+        let time = batch.column_by_name("time").unwrap().as_any().downcast_ref::<StringArray>().unwrap();
+        assert_eq!(time.value(1), "2024-01-03 00:00:00");
+
+        assert_eq!(kl_record_batch(&[]).unwrap().num_rows(), 0);
+    }
+}
@@ -1,18 +1,141 @@
 #![allow(clippy::useless_conversion)]
 
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
 use pyo3::prelude::*;
-use pyo3::exceptions::PyRuntimeError;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
 use parking_lot::Mutex as SyncMutex;
 use tokio::runtime::Runtime;
 use tokio::sync::{mpsc, Mutex};
 
 use crate::config::FutuConfig;
+use crate::protocol::DecodeMode;
+use crate::protocol::encryption::AesPadding;
 use crate::client::FutuClient;
+use crate::client::cache::ResponseCacheConfig;
+use crate::client::clock::SharedClock;
+use crate::client::connection::format_endpoint;
+use crate::client::flow_control::RetryPolicy;
+use crate::client::metrics::{render_prometheus, Family};
+use crate::client::rate_limit::{RateLimit, RateLimitConfig, RateLimitMode};
+use crate::client::mock::MockOpenD;
+use crate::client::session::SessionEvent;
+use crate::client::supervisor::{RestartPolicy, TaskSupervisor};
+use crate::quote::bars::{AggregatedBar, BarAggregator, BarSpec, PartialBarPolicy};
+use crate::quote::profiles::{ProfileRegistry, SubscriptionProfile};
+use crate::quote::instrument::InstrumentCache;
+use crate::quote::ipo_watch::IpoEvent;
+use crate::quote::warrant_watch::{WarrantScreen, WarrantScreenEvent};
+use crate::quote::listing_watch::ListingEvent;
+use crate::quote::market_session::{MarketPhase, MarketSession, MarketSessionEvent};
+use crate::quote::kl_check::{KlIssue, KlReport};
+use crate::quote::names::NameTable;
+use crate::quote::orderbook::Level;
+use crate::quote::brokers::BrokerTable;
+use crate::quote::push_stats::PushStat;
+use crate::quote::symbols::SymbolMap;
+use crate::quote::sub_manager::SubscriptionExpired;
+use crate::quote::suspend::SuspensionCache;
+use crate::trade::autolock::UnlockCredentials;
+use crate::trade::funds::{CashFlow, CashFlowDirection, FundsOperation};
+use crate::trade::history::Amendment;
+use crate::trade::portfolio::{LivePortfolio, LivePortfolioOptions, LivePosition};
+use crate::trade::sequence::{
+    TradeEvent, TradeSequencer, DEFAULT_MAX_HOLD, PROTO_TRD_UPDATE_ORDER, PROTO_TRD_UPDATE_ORDER_FILL,
+};
+use crate::trade::throttle::OrderThrottleConfig;
+use crate::trade::tracker::{OrderEvent, OrderEventStream};
+use super::errors::{FutuConnectionError, IntoPyErr, SubscriptionRejectedError};
+use super::fields::FieldFilter;
+use super::gil::GilCheckpoint;
+use super::models::{BasicQot, Kline, Order, Position};
+use super::push_callback::{CallbackOptions, PushCallback};
+use super::push_decode::{decode_push, decoded_to_py, order_book_levels_to_py, DecodedPush, PushPayload};
+
+type PushMessage = (u32, PushPayload);
+/// Push channels are lock-free queues: forwarders send from the runtime and
+/// polls receive on the Python thread without entering the runtime.
+type PushSender = crossbeam_channel::Sender<PushMessage>;
+type PushReceiver = crossbeam_channel::Receiver<PushMessage>;
+/// Orders the trade pushes of a channel; shared by its forwarders.
+type PushSequencer = Arc<SyncMutex<TradeSequencer<PushMessage>>>;
+/// Quote securities a push channel lets through; None lets all through.
+type SecurityFilter = Arc<SyncMutex<Option<Arc<HashSet<(i32, String)>>>>>;
+type IpoEventReceiver = Arc<Mutex<mpsc::UnboundedReceiver<IpoEvent>>>;
+type WarrantEventReceiver = Arc<Mutex<mpsc::UnboundedReceiver<WarrantScreenEvent>>>;
+type ListingEventReceiver = Arc<Mutex<mpsc::UnboundedReceiver<ListingEvent>>>;
+type StaleAlarmReceiver = Arc<Mutex<mpsc::UnboundedReceiver<PushStat>>>;
+type SessionEventReceiver = Arc<Mutex<mpsc::UnboundedReceiver<SessionEvent>>>;
+type OrderEventReceiver = Arc<Mutex<OrderEventStream>>;
+type SubExpiryReceiver = Arc<Mutex<mpsc::UnboundedReceiver<SubscriptionExpired>>>;
+type MarketEventReceiver = Arc<Mutex<mpsc::UnboundedReceiver<MarketSessionEvent>>>;
+type BarReceiver = Arc<Mutex<mpsc::UnboundedReceiver<AggregatedBar>>>;
+/// (trd_env, acc_id, trd_market)
+type AccountDefaults = (i32, u64, i32);
+
+/// Selectable fields of `get_basic_qot()` rows.
+const BASIC_QOT_FIELDS: [&str; 12] = [
+    "alias", "name", "cur_price", "price_spread", "open_price", "high_price", "low_price",
+    "last_close_price", "volume", "turnover", "turnover_rate", "update_timestamp",
+];
+
+/// Selectable fields of `get_security_snapshot()` rows.
+const SNAPSHOT_FIELDS: [&str; 49] = [
+    "alias", "name", "type", "is_suspend", "lot_size", "cur_price", "open_price", "high_price",
+    "low_price", "last_close_price", "volume", "turnover", "update_time", "update_timestamp",
+    "ask_price", "bid_price", "ask_vol", "bid_vol", "price_spread", "list_time", "list_timestamp",
+    "turnover_rate", "amplitude", "avg_price", "bid_ask_ratio", "volume_ratio",
+    "highest52_weeks_price", "lowest52_weeks_price", "highest_history_price", "lowest_history_price",
+    "close_price5_minute", "sec_status", "enable_margin", "mortgage_ratio", "long_margin_initial_ratio",
+    "enable_short_sell", "short_sell_rate", "short_available_volume", "short_margin_initial_ratio",
+    "pre_market", "after_market", "overnight", "equity_ex_data", "warrant_ex_data", "option_ex_data",
+    "index_ex_data", "plate_ex_data", "future_ex_data", "trust_ex_data",
+];
+
+/// A security given either as a (market, code) tuple or as a registered alias.
+#[derive(FromPyObject)]
+enum SecurityArg {
+    Pair((i32, String)),
+    Alias(String),
+}
+
+/// A `start_push()` or `start_qot_poller()` channel. The filter fields
+/// only apply to `start_push()` channels.
+struct PushChannel {
+    tx: PushSender,
+    rx: PushReceiver,
+    /// Proto_ids with a running forwarder.
+    proto_ids: BTreeSet<u32>,
+    decode_on_worker: bool,
+    /// Keep the per-order detail of order book levels.
+    include_detail: bool,
+    /// Shared with the forwarders, which read it for every message.
+    securities: SecurityFilter,
+    /// Holds fills back until the order update of their order has passed.
+    sequencer: PushSequencer,
+}
+
+impl PushChannel {
+    fn new(tx: PushSender, rx: PushReceiver, decode_on_worker: bool, include_detail: bool) -> Self {
+        Self {
+            tx,
+            rx,
+            proto_ids: BTreeSet::new(),
+            decode_on_worker,
+            include_detail,
+            securities: Arc::new(SyncMutex::new(None)),
+            sequencer: Arc::new(SyncMutex::new(TradeSequencer::default())),
+        }
+    }
+}
 
-type PushMessage = (u32, Vec<u8>);
-type PushSender = mpsc::UnboundedSender<PushMessage>;
-type PushReceiver = Arc<Mutex<mpsc::UnboundedReceiver<PushMessage>>>;
+/// A `start_bar_push()` channel: the aggregator shared with its task, and
+/// the bar channel. Removing a spec sends its partial bar on `tx`.
+struct BarChannel {
+    aggregator: Arc<SyncMutex<BarAggregator>>,
+    tx: mpsc::UnboundedSender<AggregatedBar>,
+    rx: BarReceiver,
+}
 
 /// Python-facing Futu client.
 ///
@@ -22,11 +145,89 @@ type PushReceiver = Arc<Mutex<mpsc::UnboundedReceiver<PushMessage>>>;
 #[pyclass]
 pub struct PyFutuClient {
     runtime: Runtime,
-    client: SyncMutex<Option<Arc<FutuClient>>>,
+    client: Arc<SyncMutex<Option<Arc<FutuClient>>>>,
     /// Each `start_push()` call creates its own channel pair so data and
     /// execution clients don't compete for the same receiver.
-    push_channels: SyncMutex<Vec<(PushSender, PushReceiver)>>,
-    push_handles: SyncMutex<Vec<tokio::task::JoinHandle<()>>>,
+    push_channels: Arc<SyncMutex<Vec<PushChannel>>>,
+    /// Push forwarders and pollers; aborted on disconnect.
+    tasks: TaskSupervisor,
+    /// Event receivers of `watch_ipo()` calls, indexed by watcher_id.
+    ipo_watchers: SyncMutex<Vec<IpoEventReceiver>>,
+    /// Event receivers of `watch_warrant_screen()` calls, indexed by watcher_id.
+    warrant_watchers: SyncMutex<Vec<WarrantEventReceiver>>,
+    /// Event receivers of `watch_listings()` calls, indexed by watcher_id.
+    listing_watchers: SyncMutex<Vec<ListingEventReceiver>>,
+    /// Alarm receivers of `start_staleness_monitor()` calls, indexed by monitor_id.
+    stale_monitors: SyncMutex<Vec<StaleAlarmReceiver>>,
+    /// Bar aggregators of `start_bar_push()` calls, indexed by channel_id.
+    bar_channels: SyncMutex<Vec<BarChannel>>,
+    /// Delivery threads of push channels with a `set_push_callback()`
+    /// callback, by channel_id.
+    push_callbacks: SyncMutex<HashMap<usize, PushCallback>>,
+    /// Defaults used by trade methods when trd_env/acc_id/trd_market are None.
+    default_account: SyncMutex<Option<AccountDefaults>>,
+    /// Named subscription profiles; active ones are replayed on connect.
+    profiles: SyncMutex<ProfileRegistry>,
+    /// Lazily refreshed suspension calendar for `is_suspended_on`/`next_resume`.
+    suspensions: SuspensionCache,
+    /// Consolidated instrument records returned by `instrument()`; shared
+    /// with listing watchers, which keep them up to date.
+    instruments: Arc<InstrumentCache>,
+    /// Preferred language and local security name translations.
+    /// Replaced wholesale on change so readers can hold a snapshot; shared
+    /// with push callback threads.
+    names: Arc<SyncMutex<Arc<NameTable>>>,
+    /// Symbol aliases accepted in security lists and added to outputs.
+    /// Replaced wholesale on change, like `names`.
+    aliases: Arc<SyncMutex<Arc<SymbolMap>>>,
+    /// Broker seat names and groups for broker queues; learns the names
+    /// `get_broker()` returns.
+    brokers: SyncMutex<BrokerTable>,
+    /// Applied to every new connection; see `set_pause_trading_on_preempt()`.
+    pause_trading_on_preempt: std::sync::atomic::AtomicBool,
+    /// Applied to every new connection; see `set_funds_protocols()`.
+    funds_protocols: std::sync::atomic::AtomicBool,
+    /// Applied to every new connection; see `set_dedup_pushes()`.
+    dedup_pushes: std::sync::atomic::AtomicBool,
+    /// Applied to every new connection; see `set_sub_quota()`.
+    sub_quota: SyncMutex<Option<u32>>,
+    /// Round place_order qty and price; see `set_auto_round_orders()`.
+    auto_round_orders: std::sync::atomic::AtomicBool,
+    /// Password `place_order_autolock()` unlocks with; see
+    /// `set_unlock_credentials()`.
+    unlock_credentials: SyncMutex<Option<UnlockCredentials>>,
+    /// Applied to every new connection; see `set_order_throttle()`.
+    order_throttle: SyncMutex<OrderThrottleConfig>,
+    /// Applied to every new connection; see `set_retry_policy()`.
+    retry_policy: SyncMutex<RetryPolicy>,
+    /// Applied to every new connection; see `set_response_cache()`.
+    response_cache: SyncMutex<ResponseCacheConfig>,
+    /// Applied to every new connection; see `set_rate_limit()`.
+    rate_limit: SyncMutex<RateLimitConfig>,
+    /// Applied to every new connection; see `set_socket_options()`.
+    socket_options: SyncMutex<SocketOptions>,
+    /// Applied to every new connection; see `set_slow_request_threshold()`.
+    slow_request_threshold: SyncMutex<Option<std::time::Duration>>,
+    /// Session events of the current connection, subscribed on connect.
+    session_events: SyncMutex<Option<SessionEventReceiver>>,
+    /// Order lifecycle events of the current connection, tracked from connect.
+    order_events: SyncMutex<Option<OrderEventReceiver>>,
+    /// Events of the expiry watcher started by the first `subscribe()` with
+    /// a TTL on the current connection.
+    sub_expiry: SyncMutex<Option<SubExpiryReceiver>>,
+    /// Latest market states; kept across connections.
+    market_session: Arc<MarketSession>,
+    /// Events of the poller started by `start_market_session()` on the
+    /// current connection.
+    market_events: SyncMutex<Option<MarketEventReceiver>>,
+    /// Positions followed by `start_live_portfolio()` on the current
+    /// connection.
+    live_portfolio: SyncMutex<Option<Arc<LivePortfolio>>>,
+    /// Successful `connect()` calls, reconnects included.
+    connects: Arc<std::sync::atomic::AtomicU64>,
+    /// In-process gateway of `connect(mock=True)`; kept across reconnects
+    /// so its canned responses stay registered.
+    mock: SyncMutex<Option<Arc<MockOpenD>>>,
 }
 
 impl PyFutuClient {
@@ -38,8 +239,1020 @@ impl PyFutuClient {
             .lock()
             .as_ref()
             .cloned()
-            .ok_or_else(|| PyRuntimeError::new_err("Not connected"))
+            .ok_or_else(|| FutuConnectionError::new_err("Not connected"))
+    }
+
+    /// Start the subscription expiry watcher of the current connection
+    /// unless it runs.
+    fn ensure_sub_expiry(&self, client: &Arc<FutuClient>) {
+        let mut sub_expiry = self.sub_expiry.lock();
+        if sub_expiry.is_some() {
+            return;
+        }
+        let (tx, rx) = mpsc::unbounded_channel();
+        *sub_expiry = Some(Arc::new(Mutex::new(rx)));
+        let client = Arc::clone(client);
+        let policy = RestartPolicy::OnPanic {
+            max_restarts: 3,
+            backoff: std::time::Duration::from_secs(1),
+        };
+        let _guard = self.runtime.enter();
+        self.tasks.spawn("subscription_expiry", policy, move || {
+            crate::quote::sub_manager::run_expiry_watcher(Arc::clone(&client), std::time::Duration::from_secs(1), tx.clone())
+        });
+    }
+
+    /// The mock gateway, started on first use.
+    fn mock_gateway(&self, py: Python<'_>) -> PyResult<Arc<MockOpenD>> {
+        if let Some(mock) = self.mock.lock().as_ref() {
+            return Ok(Arc::clone(mock));
+        }
+        let mock = py.allow_threads(|| self.runtime.block_on(MockOpenD::start()))
+            .map_err(|e| PyRuntimeError::new_err(format!("Mock OpenD failed to start: {}", e)))?;
+        Ok(Arc::clone(self.mock.lock().get_or_insert(Arc::new(mock))))
+    }
+
+    /// The mock gateway of a `connect(mock=True)` client.
+    fn connected_mock(&self) -> PyResult<Arc<MockOpenD>> {
+        self.mock.lock().as_ref().cloned().ok_or_else(|| PyRuntimeError::new_err("Not connected to a mock OpenD"))
+    }
+
+    /// State read by `metrics()`, shareable with the metrics server.
+    fn metrics_source(&self) -> MetricsSource {
+        MetricsSource {
+            client: Arc::clone(&self.client),
+            push_channels: Arc::clone(&self.push_channels),
+            connects: Arc::clone(&self.connects),
+        }
+    }
+
+    /// Receiver of push channel `channel_id`, if it exists.
+    fn push_receiver(&self, channel_id: usize) -> Option<PushReceiver> {
+        self.push_channels.lock().get(channel_id).map(|channel| channel.rx.clone())
+    }
+
+    /// Proto_ids forwarded to push channel `channel_id`.
+    fn forwarded_protos(&self, channel_id: usize) -> BTreeSet<u32> {
+        self.push_channels.lock().get(channel_id).map(|channel| channel.proto_ids.clone()).unwrap_or_default()
+    }
+
+    /// Register dispatcher handlers for `proto_ids` and spawn forwarders
+    /// from them to push channel `channel_id`. Proto_ids that are already
+    /// forwarded are skipped.
+    fn start_forwarders(&self, py: Python<'_>, client: &FutuClient, channel_id: usize, proto_ids: Vec<u32>) -> PyResult<()> {
+        let mode = client.connection().config().decode_mode;
+        let (tx, decode_on_worker, include_detail, securities, sequencer) = {
+            let channels = self.push_channels.lock();
+            let channel = channels
+                .get(channel_id)
+                .ok_or_else(|| PyValueError::new_err(format!("Unknown push channel: {}", channel_id)))?;
+            (
+                channel.tx.clone(),
+                channel.decode_on_worker,
+                channel.include_detail,
+                Arc::clone(&channel.securities),
+                Arc::clone(&channel.sequencer),
+            )
+        };
+        let clock = Arc::clone(client.clock());
+
+        for proto_id in proto_ids {
+            let added = self.push_channels.lock()
+                .get_mut(channel_id)
+                .is_some_and(|channel| channel.proto_ids.insert(proto_id));
+            if !added {
+                continue;
+            }
+            let mut push_rx = py.allow_threads(|| {
+                self.runtime.block_on(async {
+                    client.subscribe_push(proto_id).await
+                })
+            });
+
+            let tx = tx.clone();
+            let securities = Arc::clone(&securities);
+            let (sequencer, clock) = (Arc::clone(&sequencer), Arc::clone(&clock));
+            let _guard = self.runtime.enter();
+            if is_trade_push(proto_id) {
+                self.spawn_sequencer_flush(channel_id, tx.clone(), Arc::clone(&sequencer), Arc::clone(&clock));
+            }
+            self.tasks.spawn_once(&forwarder_name(channel_id, proto_id), async move {
+                while let Some(msg) = push_rx.recv().await {
+                    let event = TradeEvent::of_push(msg.proto_id, &msg.body);
+                    let filter = securities.lock().clone();
+                    let payload = match filter {
+                        Some(filter) => match decode_push(mode, msg.proto_fmt, msg.proto_id, &msg.body) {
+                            Ok(decoded) => match decoded.retain_securities(&filter) {
+                                Some(decoded) => PushPayload::Decoded(Ok(Box::new(decoded))),
+                                None => continue,
+                            },
+                            Err(e) => PushPayload::Decoded(Err(e)),
+                        },
+                        None if decode_on_worker => {
+                            PushPayload::Decoded(decode_push(mode, msg.proto_fmt, msg.proto_id, &msg.body).map(Box::new))
+                        }
+                        None => PushPayload::Raw(msg.body, msg.proto_fmt),
+                    };
+                    let payload = if include_detail { payload } else { payload.without_order_book_detail(mode, msg.proto_id) };
+                    let released = match event {
+                        None => vec![(msg.proto_id, payload)],
+                        Some(_) => sequencer.lock().push(event, (msg.proto_id, payload), clock.unix_time()),
+                    };
+                    if released.into_iter().any(|item| tx.send(item).is_err()) {
+                        break;
+                    }
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Periodically release the fills the sequencer of push channel
+    /// `channel_id` has held too long. Started with its first trade forwarder.
+    fn spawn_sequencer_flush(&self, channel_id: usize, tx: PushSender, sequencer: PushSequencer, clock: SharedClock) {
+        let period = std::time::Duration::from_secs_f64(DEFAULT_MAX_HOLD / 4.0);
+        self.tasks.spawn_once(&format!("push_sequencer:{}", channel_id), async move {
+            loop {
+                clock.sleep(period).await;
+                let expired = sequencer.lock().flush_expired(clock.unix_time());
+                if expired.into_iter().any(|item| tx.send(item).is_err()) {
+                    break;
+                }
+            }
+        });
+    }
+
+    /// Abort the forwarders of `proto_ids` to push channel `channel_id` and
+    /// unregister their dispatcher handlers. Returns the proto_ids stopped.
+    fn stop_forwarders(&self, py: Python<'_>, client: &FutuClient, channel_id: usize, proto_ids: Vec<u32>) -> PyResult<Vec<u32>> {
+        let stopping: Vec<u32> = {
+            let mut channels = self.push_channels.lock();
+            let channel = channels
+                .get_mut(channel_id)
+                .ok_or_else(|| PyValueError::new_err(format!("Unknown push channel: {}", channel_id)))?;
+            proto_ids.into_iter().filter(|proto_id| channel.proto_ids.remove(proto_id)).collect()
+        };
+        py.allow_threads(|| {
+            self.runtime.block_on(async {
+                for &proto_id in &stopping {
+                    // Dropping the forwarder closes its handler's receiver
+                    self.tasks.abort(&forwarder_name(channel_id, proto_id)).await;
+                    client.prune_push(proto_id).await;
+                }
+            })
+        });
+        Ok(stopping)
+    }
+
+    /// Aggregator of bar channel `channel_id`.
+    fn bar_aggregator(&self, channel_id: usize) -> PyResult<Arc<SyncMutex<BarAggregator>>> {
+        self.bar_channels
+            .lock()
+            .get(channel_id)
+            .map(|c| Arc::clone(&c.aggregator))
+            .ok_or_else(|| PyValueError::new_err(format!("Unknown bar channel: {}", channel_id)))
+    }
+
+    /// Convert queued push messages to `poll_push()` dicts.
+    fn push_messages_to_dicts(&self, py: Python<'_>, messages: Vec<PushMessage>) -> PyResult<Vec<PyObject>> {
+        let names = self.names();
+        let aliases = self.aliases();
+        let mode = self.decode_mode();
+        let mut checkpoint = GilCheckpoint::new(py);
+        let mut result = Vec::with_capacity(messages.len());
+        for (proto_id, payload) in messages {
+            checkpoint.tick()?;
+            result.push(push_message_to_dict(py, &names, &aliases, mode, proto_id, payload)?);
+        }
+        Ok(result)
+    }
+
+    /// Snapshot of the name table, safe to use across `py.allow_threads()`.
+    fn names(&self) -> Arc<NameTable> {
+        Arc::clone(&self.names.lock())
+    }
+
+    /// Decode mode of the current connection, or the default when disconnected.
+    fn decode_mode(&self) -> DecodeMode {
+        self.client
+            .lock()
+            .as_ref()
+            .map(|c| c.connection().config().decode_mode)
+            .unwrap_or_default()
+    }
+
+    /// Snapshot of the symbol aliases, safe to use across `py.allow_threads()`.
+    fn aliases(&self) -> Arc<SymbolMap> {
+        Arc::clone(&self.aliases.lock())
+    }
+
+    /// Resolve aliases in a security list to (market, code) pairs.
+    fn resolve_securities(&self, securities: Vec<SecurityArg>) -> PyResult<Vec<(i32, String)>> {
+        let aliases = self.aliases();
+        securities
+            .into_iter()
+            .map(|sec| match sec {
+                SecurityArg::Pair(pair) => Ok(pair),
+                SecurityArg::Alias(alias) => aliases
+                    .resolve(&alias)
+                    .map(|(market, code)| (market, code.to_string()))
+                    .ok_or_else(|| PyValueError::new_err(format!("Unknown symbol alias: {}", alias))),
+            })
+            .collect()
+    }
+
+    /// Resolve an order's security to its TrdSecMarket and code. A pair or
+    /// a known alias gives both; any other string is taken as a bare code
+    /// in `sec_market`, as before aliases existed.
+    fn resolve_order_security(
+        &self,
+        security: SecurityArg,
+        sec_market: Option<i32>,
+    ) -> PyResult<(Option<i32>, String)> {
+        let (market, code) = match security {
+            SecurityArg::Pair(pair) => pair,
+            SecurityArg::Alias(alias) => match self.aliases().resolve(&alias) {
+                Some((market, code)) => (market, code.to_string()),
+                None => return Ok((sec_market, alias)),
+            },
+        };
+        let trd_sec_market = crate::quote::names::qot_market_to_trd_sec_market(market)
+            .ok_or_else(|| PyValueError::new_err(format!("No trade market for QotMarket {}", market)))?;
+        Ok((Some(trd_sec_market), code))
+    }
+
+    /// Codes of the securities in a trade filter, which matches on code
+    /// only. Strings that are not aliases are taken as codes.
+    fn resolve_codes(&self, securities: Vec<SecurityArg>) -> Vec<String> {
+        let aliases = self.aliases();
+        securities
+            .into_iter()
+            .map(|sec| match sec {
+                SecurityArg::Pair((_, code)) => code,
+                SecurityArg::Alias(alias) => match aliases.resolve(&alias) {
+                    Some((_, code)) => code.to_string(),
+                    None => alias,
+                },
+            })
+            .collect()
+    }
+
+    /// Fill in any missing trade account parameter from the stored defaults.
+    fn resolve_account(
+        &self,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
+    ) -> PyResult<AccountDefaults> {
+        resolve_account(*self.default_account.lock(), trd_env, acc_id, trd_market)
+            .map_err(pyo3::exceptions::PyValueError::new_err)
+    }
+
+    /// Round an order's qty to whole lots and its price onto the tick table
+    /// for `set_auto_round_orders`. Orders whose security market is not
+    /// given are passed through unchanged.
+    #[allow(clippy::too_many_arguments)]
+    fn round_order(
+        &self,
+        py: Python<'_>,
+        client: &FutuClient,
+        sec_market: Option<i32>,
+        code: &str,
+        trd_side: i32,
+        qty: f64,
+        price: Option<f64>,
+    ) -> PyResult<(f64, Option<f64>)> {
+        let Some(market) = sec_market.and_then(crate::quote::names::trd_sec_market_to_qot_market) else {
+            tracing::debug!("Not rounding order for {}: security market unknown", code);
+            return Ok((qty, price));
+        };
+        let cache = &self.instruments;
+        let instrument = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                cache.get(client, (market, code.to_string()), None, false).await
+            })
+        }).map_err(|e| e.into_py_err("Place order failed"))?;
+
+        if instrument.delisted {
+            return Err(PyValueError::new_err(format!("{} is delisted", code)));
+        }
+        let rounded_qty = instrument.round_qty(qty);
+        if rounded_qty <= 0.0 {
+            return Err(PyValueError::new_err(format!(
+                "qty {} of {} is less than one lot of {}", qty, code, instrument.lot_size,
+            )));
+        }
+        let rounded_price = price.map(|p| instrument.round_price(p, trd_side).unwrap_or(p));
+        if rounded_qty != qty || rounded_price != price {
+            tracing::info!(
+                "Rounded order for {}: qty {} -> {}, price {:?} -> {:?}",
+                code, qty, rounded_qty, price, rounded_price,
+            );
+        }
+        Ok((rounded_qty, rounded_price))
+    }
+}
+
+/// Snapshot dict in the `get_security_snapshot()` layout.
+fn snapshot_to_dict(
+    py: Python<'_>,
+    filter: &FieldFilter,
+    names: &NameTable,
+    aliases: &SymbolMap,
+    snapshot: &crate::generated::qot_get_security_snapshot::Snapshot,
+) -> PyResult<PyObject> {
+    let dict = pyo3::types::PyDict::new_bound(py);
+    let basic = &snapshot.basic;
+    let sec = &basic.security;
+    dict.set_item("market", sec.market)?;
+    dict.set_item("code", &sec.code)?;
+    filter.set(&dict, "alias", aliases.alias(sec.market, &sec.code))?;
+    filter.set(&dict, "name", names.localize_opt(sec.market, &sec.code, basic.name.as_deref()))?;
+    filter.set(&dict, "type", basic.r#type)?;
+    filter.set(&dict, "is_suspend", basic.is_suspend)?;
+    filter.set(&dict, "lot_size", basic.lot_size)?;
+    filter.set(&dict, "cur_price", basic.cur_price)?;
+    filter.set(&dict, "open_price", basic.open_price)?;
+    filter.set(&dict, "high_price", basic.high_price)?;
+    filter.set(&dict, "low_price", basic.low_price)?;
+    filter.set(&dict, "last_close_price", basic.last_close_price)?;
+    filter.set(&dict, "volume", basic.volume)?;
+    filter.set(&dict, "turnover", basic.turnover)?;
+    filter.set(&dict, "update_time", &basic.update_time)?;
+    filter.set(&dict, "update_timestamp", basic.update_timestamp)?;
+    filter.set(&dict, "ask_price", basic.ask_price)?;
+    filter.set(&dict, "bid_price", basic.bid_price)?;
+    filter.set(&dict, "ask_vol", basic.ask_vol)?;
+    filter.set(&dict, "bid_vol", basic.bid_vol)?;
+    filter.set(&dict, "price_spread", basic.price_spread)?;
+    filter.set(&dict, "list_time", &basic.list_time)?;
+    filter.set(&dict, "list_timestamp", basic.list_timestamp)?;
+    filter.set(&dict, "turnover_rate", basic.turnover_rate)?;
+    filter.set(&dict, "amplitude", basic.amplitude)?;
+    filter.set(&dict, "avg_price", basic.avg_price)?;
+    filter.set(&dict, "bid_ask_ratio", basic.bid_ask_ratio)?;
+    filter.set(&dict, "volume_ratio", basic.volume_ratio)?;
+    filter.set(&dict, "highest52_weeks_price", basic.highest52_weeks_price)?;
+    filter.set(&dict, "lowest52_weeks_price", basic.lowest52_weeks_price)?;
+    filter.set(&dict, "highest_history_price", basic.highest_history_price)?;
+    filter.set(&dict, "lowest_history_price", basic.lowest_history_price)?;
+    filter.set(&dict, "close_price5_minute", basic.close_price5_minute)?;
+    filter.set(&dict, "sec_status", basic.sec_status)?;
+    filter.set(&dict, "enable_margin", basic.enable_margin)?;
+    filter.set(&dict, "mortgage_ratio", basic.mortgage_ratio)?;
+    filter.set(&dict, "long_margin_initial_ratio", basic.long_margin_initial_ratio)?;
+    filter.set(&dict, "enable_short_sell", basic.enable_short_sell)?;
+    filter.set(&dict, "short_sell_rate", basic.short_sell_rate)?;
+    filter.set(&dict, "short_available_volume", basic.short_available_volume)?;
+    filter.set(&dict, "short_margin_initial_ratio", basic.short_margin_initial_ratio)?;
+    for (field, session) in [("pre_market", &basic.pre_market), ("after_market", &basic.after_market), ("overnight", &basic.overnight)] {
+        if filter.includes(field) {
+            let value = session.as_ref().map(|data| pre_after_market_to_dict(py, data)).transpose()?;
+            dict.set_item(field, value)?;
+        }
+    }
+
+    // Type-specific data; only the section matching the security type is sent
+    if let (Some(ex), true) = (&snapshot.equity_ex_data, filter.includes("equity_ex_data")) {
+        let d = pyo3::types::PyDict::new_bound(py);
+        d.set_item("issued_shares", ex.issued_shares)?;
+        d.set_item("issued_market_val", ex.issued_market_val)?;
+        d.set_item("net_asset", ex.net_asset)?;
+        d.set_item("net_profit", ex.net_profit)?;
+        d.set_item("earnings_pershare", ex.earnings_pershare)?;
+        d.set_item("outstanding_shares", ex.outstanding_shares)?;
+        d.set_item("outstanding_market_val", ex.outstanding_market_val)?;
+        d.set_item("net_asset_pershare", ex.net_asset_pershare)?;
+        d.set_item("ey_rate", ex.ey_rate)?;
+        d.set_item("pe_rate", ex.pe_rate)?;
+        d.set_item("pb_rate", ex.pb_rate)?;
+        d.set_item("pe_ttm_rate", ex.pe_ttm_rate)?;
+        d.set_item("dividend_ttm", ex.dividend_ttm)?;
+        d.set_item("dividend_ratio_ttm", ex.dividend_ratio_ttm)?;
+        d.set_item("dividend_lfy", ex.dividend_lfy)?;
+        d.set_item("dividend_lfy_ratio", ex.dividend_lfy_ratio)?;
+        dict.set_item("equity_ex_data", d)?;
+    }
+    if let (Some(ex), true) = (&snapshot.warrant_ex_data, filter.includes("warrant_ex_data")) {
+        let d = pyo3::types::PyDict::new_bound(py);
+        d.set_item("conversion_rate", ex.conversion_rate)?;
+        d.set_item("warrant_type", ex.warrant_type)?;
+        d.set_item("strike_price", ex.strike_price)?;
+        d.set_item("maturity_time", &ex.maturity_time)?;
+        d.set_item("end_trade_time", &ex.end_trade_time)?;
+        d.set_item("owner_market", ex.owner.market)?;
+        d.set_item("owner_code", &ex.owner.code)?;
+        d.set_item("recovery_price", ex.recovery_price)?;
+        d.set_item("street_volume", ex.street_volumn)?;
+        d.set_item("issue_volume", ex.issue_volumn)?;
+        d.set_item("street_rate", ex.street_rate)?;
+        d.set_item("delta", ex.delta)?;
+        d.set_item("implied_volatility", ex.implied_volatility)?;
+        d.set_item("premium", ex.premium)?;
+        d.set_item("maturity_timestamp", ex.maturity_timestamp)?;
+        d.set_item("end_trade_timestamp", ex.end_trade_timestamp)?;
+        d.set_item("leverage", ex.leverage)?;
+        d.set_item("ipop", ex.ipop)?;
+        d.set_item("break_even_point", ex.break_even_point)?;
+        d.set_item("conversion_price", ex.conversion_price)?;
+        d.set_item("price_recovery_ratio", ex.price_recovery_ratio)?;
+        d.set_item("score", ex.score)?;
+        d.set_item("upper_strike_price", ex.upper_strike_price)?;
+        d.set_item("lower_strike_price", ex.lower_strike_price)?;
+        d.set_item("in_line_price_status", ex.in_line_price_status)?;
+        d.set_item("issuer_code", ex.issuer_code.as_deref())?;
+        dict.set_item("warrant_ex_data", d)?;
+    }
+    if let (Some(ex), true) = (&snapshot.option_ex_data, filter.includes("option_ex_data")) {
+        let d = pyo3::types::PyDict::new_bound(py);
+        d.set_item("type", ex.r#type)?;
+        d.set_item("owner_market", ex.owner.market)?;
+        d.set_item("owner_code", &ex.owner.code)?;
+        d.set_item("strike_time", &ex.strike_time)?;
+        d.set_item("strike_timestamp", ex.strike_timestamp)?;
+        d.set_item("strike_price", ex.strike_price)?;
+        d.set_item("contract_size", ex.contract_size)?;
+        d.set_item("contract_size_float", ex.contract_size_float)?;
+        d.set_item("open_interest", ex.open_interest)?;
+        d.set_item("net_open_interest", ex.net_open_interest)?;
+        d.set_item("implied_volatility", ex.implied_volatility)?;
+        d.set_item("premium", ex.premium)?;
+        d.set_item("delta", ex.delta)?;
+        d.set_item("gamma", ex.gamma)?;
+        d.set_item("vega", ex.vega)?;
+        d.set_item("theta", ex.theta)?;
+        d.set_item("rho", ex.rho)?;
+        d.set_item("index_option_type", ex.index_option_type)?;
+        d.set_item("expiry_date_distance", ex.expiry_date_distance)?;
+        d.set_item("contract_nominal_value", ex.contract_nominal_value)?;
+        d.set_item("owner_lot_multiplier", ex.owner_lot_multiplier)?;
+        d.set_item("option_area_type", ex.option_area_type)?;
+        d.set_item("contract_multiplier", ex.contract_multiplier)?;
+        dict.set_item("option_ex_data", d)?;
+    }
+    for (field, counts) in [
+        ("index_ex_data", snapshot.index_ex_data.as_ref().map(|ex| (ex.raise_count, ex.fall_count, ex.equal_count))),
+        ("plate_ex_data", snapshot.plate_ex_data.as_ref().map(|ex| (ex.raise_count, ex.fall_count, ex.equal_count))),
+    ] {
+        if let (Some((raise_count, fall_count, equal_count)), true) = (counts, filter.includes(field)) {
+            let d = pyo3::types::PyDict::new_bound(py);
+            d.set_item("raise_count", raise_count)?;
+            d.set_item("fall_count", fall_count)?;
+            d.set_item("equal_count", equal_count)?;
+            dict.set_item(field, d)?;
+        }
+    }
+    if let (Some(ex), true) = (&snapshot.future_ex_data, filter.includes("future_ex_data")) {
+        let d = pyo3::types::PyDict::new_bound(py);
+        d.set_item("last_settle_price", ex.last_settle_price)?;
+        d.set_item("position", ex.position)?;
+        d.set_item("position_change", ex.position_change)?;
+        d.set_item("last_trade_time", &ex.last_trade_time)?;
+        d.set_item("last_trade_timestamp", ex.last_trade_timestamp)?;
+        d.set_item("is_main_contract", ex.is_main_contract)?;
+        dict.set_item("future_ex_data", d)?;
+    }
+    if let (Some(ex), true) = (&snapshot.trust_ex_data, filter.includes("trust_ex_data")) {
+        let d = pyo3::types::PyDict::new_bound(py);
+        d.set_item("dividend_yield", ex.dividend_yield)?;
+        d.set_item("aum", ex.aum)?;
+        d.set_item("outstanding_units", ex.outstanding_units)?;
+        d.set_item("net_asset_value", ex.net_asset_value)?;
+        d.set_item("premium", ex.premium)?;
+        d.set_item("asset_class", ex.asset_class)?;
+        dict.set_item("trust_ex_data", d)?;
+    }
+    Ok(dict.into_any().unbind())
+}
+
+/// Pre-market, after-hours or overnight trading of a snapshot.
+fn pre_after_market_to_dict<'py>(
+    py: Python<'py>,
+    data: &crate::generated::qot_common::PreAfterMarketData,
+) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+    let d = pyo3::types::PyDict::new_bound(py);
+    d.set_item("price", data.price)?;
+    d.set_item("high_price", data.high_price)?;
+    d.set_item("low_price", data.low_price)?;
+    d.set_item("volume", data.volume)?;
+    d.set_item("turnover", data.turnover)?;
+    d.set_item("change_val", data.change_val)?;
+    d.set_item("change_rate", data.change_rate)?;
+    d.set_item("amplitude", data.amplitude)?;
+    Ok(d)
+}
+
+/// TCP settings of `set_socket_options()`.
+#[derive(Debug, Clone, Copy)]
+struct SocketOptions {
+    connect_timeout: Option<std::time::Duration>,
+    tcp_keepalive: Option<std::time::Duration>,
+    read_timeout: Option<std::time::Duration>,
+    nodelay: bool,
+}
+
+impl Default for SocketOptions {
+    fn default() -> Self {
+        let config = FutuConfig::default();
+        Self {
+            connect_timeout: config.connect_timeout,
+            tcp_keepalive: config.tcp_keepalive,
+            read_timeout: config.read_timeout,
+            nodelay: config.nodelay,
+        }
+    }
+}
+
+/// Per-field override of the default account; errors name the first missing field.
+fn resolve_account(
+    defaults: Option<AccountDefaults>,
+    trd_env: Option<i32>,
+    acc_id: Option<u64>,
+    trd_market: Option<i32>,
+) -> Result<AccountDefaults, String> {
+    let missing = |field: &str| format!("{} not given and no default account set", field);
+    Ok((
+        trd_env.or(defaults.map(|d| d.0)).ok_or_else(|| missing("trd_env"))?,
+        acc_id.or(defaults.map(|d| d.1)).ok_or_else(|| missing("acc_id"))?,
+        trd_market.or(defaults.map(|d| d.2)).ok_or_else(|| missing("trd_market"))?,
+    ))
+}
+
+/// Warrant fields shared by `get_warrant()` and `poll_warrant_event()`.
+fn warrant_to_dict<'py>(
+    py: Python<'py>,
+    names: &NameTable,
+    w: &crate::generated::qot_get_warrant::WarrantData,
+) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+    let d = pyo3::types::PyDict::new_bound(py);
+    d.set_item("stock_market", w.stock.market)?;
+    d.set_item("stock_code", &w.stock.code)?;
+    d.set_item("owner_market", w.owner.market)?;
+    d.set_item("owner_code", &w.owner.code)?;
+    d.set_item("type", w.r#type)?;
+    d.set_item("issuer", w.issuer)?;
+    d.set_item("name", names.localize(w.stock.market, &w.stock.code, &w.name))?;
+    d.set_item("maturity_time", &w.maturity_time)?;
+    d.set_item("maturity_timestamp", w.maturity_timestamp)?;
+    d.set_item("list_time", &w.list_time)?;
+    d.set_item("list_timestamp", w.list_timestamp)?;
+    d.set_item("last_trade_time", &w.last_trade_time)?;
+    d.set_item("last_trade_timestamp", w.last_trade_timestamp)?;
+    d.set_item("recovery_price", w.recovery_price)?;
+    d.set_item("strike_price", w.strike_price)?;
+    d.set_item("cur_price", w.cur_price)?;
+    d.set_item("last_close_price", w.last_close_price)?;
+    d.set_item("price_change_val", w.price_change_val)?;
+    d.set_item("change_rate", w.change_rate)?;
+    d.set_item("volume", w.volume)?;
+    d.set_item("turnover", w.turnover)?;
+    d.set_item("premium", w.premium)?;
+    d.set_item("break_even_point", w.break_even_point)?;
+    d.set_item("conversion_ratio", w.conversion_ratio)?;
+    d.set_item("conversion_price", w.conversion_price)?;
+    d.set_item("lot_size", w.lot_size)?;
+    d.set_item("leverage", w.leverage)?;
+    d.set_item("ipop", w.ipop)?;
+    d.set_item("effective_leverage", w.effective_leverage)?;
+    d.set_item("score", w.score)?;
+    d.set_item("status", w.status)?;
+    d.set_item("bid_price", w.bid_price)?;
+    d.set_item("ask_price", w.ask_price)?;
+    d.set_item("bid_vol", w.bid_vol)?;
+    d.set_item("ask_vol", w.ask_vol)?;
+    d.set_item("high_price", w.high_price)?;
+    d.set_item("low_price", w.low_price)?;
+    d.set_item("implied_volatility", w.implied_volatility)?;
+    d.set_item("delta", w.delta)?;
+    d.set_item("street_rate", w.street_rate)?;
+    d.set_item("street_vol", w.street_vol)?;
+    d.set_item("amplitude", w.amplitude)?;
+    d.set_item("issue_size", w.issue_size)?;
+    d.set_item("upper_strike_price", w.upper_strike_price)?;
+    d.set_item("lower_strike_price", w.lower_strike_price)?;
+    d.set_item("in_line_price_status", w.in_line_price_status)?;
+    d.set_item("price_recovery_ratio", w.price_recovery_ratio)?;
+    Ok(d)
+}
+
+/// Saved warrant filter from the `filter` dict of `watch_warrant_screen()`.
+fn warrant_screen_from_dict(filter: &Bound<'_, pyo3::types::PyDict>) -> PyResult<WarrantScreen> {
+    const KEYS: [&str; 7] = ["sort_field", "ascend", "owner", "type_list", "issuer_list", "max_results", "page_interval_ms"];
+    for key in filter.keys() {
+        let key: String = key.extract()?;
+        if !KEYS.contains(&key.as_str()) {
+            return Err(PyValueError::new_err(format!("Unknown warrant filter key: {}", key)));
+        }
+    }
+    let get = |key: &str| filter.get_item(key).ok().flatten().filter(|v| !v.is_none());
+    let sort_field: i32 = get("sort_field")
+        .ok_or_else(|| PyValueError::new_err("Warrant filter needs sort_field"))?
+        .extract()?;
+    let mut screen = WarrantScreen { sort_field, ..Default::default() };
+    if let Some(v) = get("ascend") {
+        screen.ascend = v.extract()?;
+    }
+    if let Some(v) = get("owner") {
+        screen.owner = Some(v.extract()?);
+    }
+    if let Some(v) = get("type_list") {
+        screen.type_list = v.extract()?;
+    }
+    if let Some(v) = get("issuer_list") {
+        screen.issuer_list = v.extract()?;
+    }
+    if let Some(v) = get("max_results") {
+        screen.max_results = v.extract::<usize>()?.max(1);
+    }
+    if let Some(v) = get("page_interval_ms") {
+        screen.page_interval = std::time::Duration::from_millis(v.extract()?);
+    }
+    Ok(screen)
+}
+
+/// Qot_GetOptionChain DataFilter from `(key, value)` pairs, where keys are
+/// the DataFilter field names, e.g. `delta_min` or `open_interest_max`.
+fn option_data_filter(
+    bounds: &[(String, f64)],
+) -> Result<crate::generated::qot_get_option_chain::DataFilter, String> {
+    let mut filter = crate::generated::qot_get_option_chain::DataFilter::default();
+    for (key, value) in bounds {
+        let field = match key.as_str() {
+            "implied_volatility_min" => &mut filter.implied_volatility_min,
+            "implied_volatility_max" => &mut filter.implied_volatility_max,
+            "delta_min" => &mut filter.delta_min,
+            "delta_max" => &mut filter.delta_max,
+            "gamma_min" => &mut filter.gamma_min,
+            "gamma_max" => &mut filter.gamma_max,
+            "vega_min" => &mut filter.vega_min,
+            "vega_max" => &mut filter.vega_max,
+            "theta_min" => &mut filter.theta_min,
+            "theta_max" => &mut filter.theta_max,
+            "rho_min" => &mut filter.rho_min,
+            "rho_max" => &mut filter.rho_max,
+            "net_open_interest_min" => &mut filter.net_open_interest_min,
+            "net_open_interest_max" => &mut filter.net_open_interest_max,
+            "open_interest_min" => &mut filter.open_interest_min,
+            "open_interest_max" => &mut filter.open_interest_max,
+            "vol_min" => &mut filter.vol_min,
+            "vol_max" => &mut filter.vol_max,
+            _ => return Err(format!("Unknown option data filter key: {}", key)),
+        };
+        *field = Some(*value);
+    }
+    let ranges = [
+        ("implied_volatility", filter.implied_volatility_min, filter.implied_volatility_max),
+        ("delta", filter.delta_min, filter.delta_max),
+        ("gamma", filter.gamma_min, filter.gamma_max),
+        ("vega", filter.vega_min, filter.vega_max),
+        ("theta", filter.theta_min, filter.theta_max),
+        ("rho", filter.rho_min, filter.rho_max),
+        ("net_open_interest", filter.net_open_interest_min, filter.net_open_interest_max),
+        ("open_interest", filter.open_interest_min, filter.open_interest_max),
+        ("vol", filter.vol_min, filter.vol_max),
+    ];
+    for (name, min, max) in ranges {
+        if let (Some(min), Some(max)) = (min, max) {
+            if min > max {
+                return Err(format!("Option data filter {}_min {} is above {}_max {}", name, min, name, max));
+            }
+        }
+    }
+    Ok(filter)
+}
+
+/// Position fields of `get_live_portfolio()`.
+fn live_position_to_dict<'py>(
+    py: Python<'py>,
+    names: &NameTable,
+    aliases: &SymbolMap,
+    position: &LivePosition,
+) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+    let dict = pyo3::types::PyDict::new_bound(py);
+    dict.set_item("code", &position.code)?;
+    dict.set_item("alias", aliases.alias_trd(position.sec_market, &position.code))?;
+    dict.set_item("name", names.localize_trd(position.sec_market, &position.code, &position.name))?;
+    dict.set_item("sec_market", position.sec_market)?;
+    dict.set_item("qty", position.qty)?;
+    dict.set_item("cost_price", position.cost_price)?;
+    dict.set_item("last_price", position.last_price)?;
+    dict.set_item("price_time", position.price_time)?;
+    dict.set_item("market_val", position.market_val())?;
+    dict.set_item("unrealized_pl", position.unrealized_pl())?;
+    dict.set_item("realized_pl", position.realized_pl)?;
+    dict.set_item("currency", position.currency)?;
+    Ok(dict)
+}
+
+/// IPO fields shared by `get_ipo_list()` and `poll_ipo_event()`.
+fn ipo_to_dict<'py>(
+    py: Python<'py>,
+    names: &NameTable,
+    aliases: &SymbolMap,
+    ipo: &crate::generated::qot_get_ipo_list::IpoData,
+) -> PyResult<Bound<'py, pyo3::types::PyDict>> {
+    let dict = pyo3::types::PyDict::new_bound(py);
+    dict.set_item("market", ipo.basic.security.market)?;
+    dict.set_item("code", &ipo.basic.security.code)?;
+    dict.set_item("alias", aliases.alias(ipo.basic.security.market, &ipo.basic.security.code))?;
+    let sec = &ipo.basic.security;
+    dict.set_item("name", names.localize(sec.market, &sec.code, &ipo.basic.name))?;
+    dict.set_item("list_time", ipo.basic.list_time.as_deref())?;
+    dict.set_item("list_timestamp", ipo.basic.list_timestamp)?;
+
+    if let Some(ref hk) = ipo.hk_ex_data {
+        dict.set_item("ipo_price_min", hk.ipo_price_min)?;
+        dict.set_item("ipo_price_max", hk.ipo_price_max)?;
+        dict.set_item("list_price", hk.list_price)?;
+        dict.set_item("lot_size", hk.lot_size)?;
+        dict.set_item("entrance_price", hk.entrance_price)?;
+        dict.set_item("is_subscribe_status", hk.is_subscribe_status)?;
+    }
+    if let Some(ref us) = ipo.us_ex_data {
+        dict.set_item("ipo_price_min", us.ipo_price_min)?;
+        dict.set_item("ipo_price_max", us.ipo_price_max)?;
+        dict.set_item("issue_size", us.issue_size)?;
+    }
+    if let Some(ref cn) = ipo.cn_ex_data {
+        dict.set_item("apply_code", &cn.apply_code)?;
+        dict.set_item("issue_size", cn.issue_size)?;
+        dict.set_item("ipo_price", cn.ipo_price)?;
+        dict.set_item("winning_ratio", cn.winning_ratio)?;
+    }
+    Ok(dict)
+}
+
+fn push_stat_to_dict(py: Python<'_>, aliases: &SymbolMap, stat: &PushStat) -> PyResult<PyObject> {
+    let dict = pyo3::types::PyDict::new_bound(py);
+    dict.set_item("market", stat.security.0)?;
+    dict.set_item("code", &stat.security.1)?;
+    dict.set_item("alias", aliases.alias(stat.security.0, &stat.security.1))?;
+    dict.set_item("sub_type", stat.sub_type)?;
+    dict.set_item("count", stat.count)?;
+    dict.set_item("last_update", stat.last_update)?;
+    dict.set_item("tracked_since", stat.tracked_since)?;
+    dict.set_item("subscribed", stat.subscribed)?;
+    Ok(dict.into_any().unbind())
+}
+
+fn bar_to_dict(py: Python<'_>, aliases: &SymbolMap, bar: &AggregatedBar) -> PyResult<PyObject> {
+    let dict = pyo3::types::PyDict::new_bound(py);
+    dict.set_item("market", bar.market)?;
+    dict.set_item("code", &bar.code)?;
+    dict.set_item("alias", aliases.alias(bar.market, &bar.code))?;
+    dict.set_item("bar_spec", bar.spec.to_string())?;
+    dict.set_item("open", bar.open)?;
+    dict.set_item("high", bar.high)?;
+    dict.set_item("low", bar.low)?;
+    dict.set_item("close", bar.close)?;
+    dict.set_item("volume", bar.volume)?;
+    dict.set_item("tick_count", bar.tick_count)?;
+    dict.set_item("ts_open", bar.ts_open)?;
+    dict.set_item("ts_close", bar.ts_close)?;
+    dict.set_item("partial", bar.partial)?;
+    Ok(dict.into_any().unbind())
+}
+
+/// Levels of a local order book, in the layout of `get_order_book()`.
+fn book_levels_to_py<'py>(py: Python<'py>, levels: &[Level]) -> PyResult<Bound<'py, pyo3::types::PyList>> {
+    let list = pyo3::types::PyList::empty_bound(py);
+    for level in levels {
+        let d = pyo3::types::PyDict::new_bound(py);
+        d.set_item("price", level.price)?;
+        d.set_item("volume", level.volume)?;
+        d.set_item("order_count", level.order_count)?;
+        list.append(d)?;
+    }
+    Ok(list)
+}
+
+/// Decode a push message into `{"proto_id": ..., "data": ...}`.
+pub(super) fn push_message_to_dict(
+    py: Python<'_>,
+    names: &NameTable,
+    aliases: &SymbolMap,
+    mode: DecodeMode,
+    proto_id: u32,
+    payload: PushPayload,
+) -> PyResult<PyObject> {
+    let decoded = payload.decode(mode, proto_id).map_err(PyValueError::new_err)?;
+    let data = decoded_to_py(py, names, aliases, &decoded)?;
+    let dict = pyo3::types::PyDict::new_bound(py);
+    dict.set_item("proto_id", proto_id)?;
+    dict.set_item("data", data)?;
+    Ok(dict.into_any().unbind())
+}
+
+/// A K-line in the `get_history_kl()` layout.
+fn kline_to_dict(py: Python<'_>, kl: &crate::generated::qot_common::KLine) -> PyResult<PyObject> {
+    let dict = pyo3::types::PyDict::new_bound(py);
+    dict.set_item("time", &kl.time)?;
+    dict.set_item("is_blank", kl.is_blank)?;
+    dict.set_item("open_price", kl.open_price)?;
+    dict.set_item("high_price", kl.high_price)?;
+    dict.set_item("low_price", kl.low_price)?;
+    dict.set_item("close_price", kl.close_price)?;
+    dict.set_item("last_close_price", kl.last_close_price)?;
+    dict.set_item("volume", kl.volume)?;
+    dict.set_item("turnover", kl.turnover)?;
+    dict.set_item("timestamp", kl.timestamp)?;
+    Ok(dict.into_any().unbind())
+}
+
+/// A K-line as a `Kline` if `typed`, else in the `get_history_kl()` layout.
+fn kline_to_py(py: Python<'_>, kl: &crate::generated::qot_common::KLine, typed: bool) -> PyResult<PyObject> {
+    if typed {
+        return Ok(Py::new(py, Kline::from(kl))?.into_any());
+    }
+    kline_to_dict(py, kl)
+}
+
+/// A `KlQuota` with the consumed symbols as (market, code, time) tuples.
+fn kl_quota_to_dict(py: Python<'_>, quota: &crate::quote::kl_quota::KlQuota) -> PyResult<PyObject> {
+    let dict = pyo3::types::PyDict::new_bound(py);
+    dict.set_item("used", quota.used)?;
+    dict.set_item("remaining", quota.remaining)?;
+    let consumed: Vec<(i32, &str, i64)> = quota.consumed.iter().map(|((m, c), t)| (*m, c.as_str(), *t)).collect();
+    dict.set_item("consumed", consumed)?;
+    Ok(dict.into_any().unbind())
+}
+
+/// A `KlReport` with issue counts by kind and one dict per issue.
+fn kl_report_to_dict(py: Python<'_>, report: &KlReport) -> PyResult<PyObject> {
+    let dict = pyo3::types::PyDict::new_bound(py);
+    dict.set_item("bars", report.bars)?;
+    dict.set_item("missing_bars", report.missing_bars)?;
+    dict.set_item("exchange_calendar", report.exchange_calendar)?;
+    dict.set_item("is_clean", report.is_clean())?;
+    for kind in ["gap", "duplicate", "out_of_order", "inconsistent", "bad_time"] {
+        dict.set_item(format!("{}_count", kind), report.count(kind))?;
+    }
+    let issues = pyo3::types::PyList::empty_bound(py);
+    for issue in &report.issues {
+        let item = pyo3::types::PyDict::new_bound(py);
+        item.set_item("kind", issue.kind())?;
+        match issue {
+            KlIssue::Gap { after, before, missing } => {
+                item.set_item("after", after)?;
+                item.set_item("before", before)?;
+                item.set_item("missing", missing)?;
+            }
+            KlIssue::Duplicate { time } | KlIssue::OutOfOrder { time } | KlIssue::BadTime { time } => {
+                item.set_item("time", time)?;
+            }
+            KlIssue::Inconsistent { time, reason } => {
+                item.set_item("time", time)?;
+                item.set_item("reason", *reason)?;
+            }
+        }
+        issues.append(item)?;
+    }
+    dict.set_item("issues", issues)?;
+    Ok(dict.into_any().unbind())
+}
+
+/// Supervisor task name of the forwarder of `proto_id` to a push channel.
+/// Whether `proto_id` is an order or fill push, which channels sequence.
+fn is_trade_push(proto_id: u32) -> bool {
+    matches!(proto_id, PROTO_TRD_UPDATE_ORDER | PROTO_TRD_UPDATE_ORDER_FILL)
+}
+
+fn forwarder_name(channel_id: usize, proto_id: u32) -> String {
+    format!("push_forwarder:{}:{}", channel_id, proto_id)
+}
+
+/// What the Prometheus metrics are rendered from.
+#[derive(Clone)]
+struct MetricsSource {
+    client: Arc<SyncMutex<Option<Arc<FutuClient>>>>,
+    push_channels: Arc<SyncMutex<Vec<PushChannel>>>,
+    connects: Arc<std::sync::atomic::AtomicU64>,
+}
+
+impl MetricsSource {
+    /// Client metrics of the current connection, followed by connects and
+    /// push channel backlogs.
+    async fn render(&self) -> String {
+        let client = self.client.lock().clone();
+        let (mut out, endpoint) = match client {
+            Some(client) => {
+                let (host, port) = client.connection().endpoint();
+                (render_prometheus(&client).await, Some(format_endpoint(&host, port)))
+            }
+            None => (String::new(), None),
+        };
+        let mut labels = Vec::new();
+        if let Some(endpoint) = &endpoint {
+            labels.push(("endpoint", endpoint.as_str()));
+        }
+
+        let connects = self.connects.load(std::sync::atomic::Ordering::Relaxed);
+        Family::new(&mut out, "futu_connects_total", "counter", "Successful connects, reconnects included.")
+            .sample("", &labels, connects as f64);
+
+        let depths: Vec<(String, usize)> = self.push_channels.lock().iter().enumerate()
+            .map(|(id, channel)| (id.to_string(), channel.rx.len()))
+            .collect();
+        let mut family = Family::new(&mut out, "futu_push_queue_depth", "gauge", "Push messages waiting in a poll_push() channel.");
+        for (channel, depth) in &depths {
+            let mut channel_labels = labels.clone();
+            channel_labels.push(("channel", channel));
+            family.sample("", &channel_labels, *depth as f64);
+        }
+        out
+    }
+}
+
+fn cash_flow_to_dict(py: Python<'_>, flow: &CashFlow) -> PyResult<PyObject> {
+    let dict = pyo3::types::PyDict::new_bound(py);
+    dict.set_item("cash_flow_id", flow.cash_flow_id)?;
+    dict.set_item("clearing_date", &flow.clearing_date)?;
+    dict.set_item("settlement_date", &flow.settlement_date)?;
+    dict.set_item("currency", flow.currency)?;
+    dict.set_item("cash_flow_type", &flow.cash_flow_type)?;
+    dict.set_item("direction", flow.direction.map(|d| d.as_i32()))?;
+    dict.set_item("amount", flow.amount)?;
+    dict.set_item("remark", &flow.remark)?;
+    Ok(dict.into_any().unbind())
+}
+
+fn fill_to_dict(
+    py: Python<'_>,
+    names: &NameTable,
+    aliases: &SymbolMap,
+    fill: &crate::generated::trd_common::OrderFill,
+) -> PyResult<PyObject> {
+    let dict = pyo3::types::PyDict::new_bound(py);
+    dict.set_item("trd_side", fill.trd_side)?;
+    dict.set_item("fill_id", fill.fill_id)?;
+    dict.set_item("fill_id_ex", &fill.fill_id_ex)?;
+    dict.set_item("order_id", fill.order_id)?;
+    dict.set_item("order_id_ex", fill.order_id_ex.as_deref())?;
+    dict.set_item("code", &fill.code)?;
+    dict.set_item("alias", aliases.alias_trd(fill.sec_market, &fill.code))?;
+    dict.set_item("name", names.localize_trd(fill.sec_market, &fill.code, &fill.name))?;
+    dict.set_item("qty", fill.qty)?;
+    dict.set_item("price", fill.price)?;
+    dict.set_item("create_time", &fill.create_time)?;
+    dict.set_item("create_timestamp", fill.create_timestamp)?;
+    dict.set_item("update_timestamp", fill.update_timestamp)?;
+    dict.set_item("sec_market", fill.sec_market)?;
+    dict.set_item("status", fill.status)?;
+    Ok(dict.into_any().unbind())
+}
+
+fn session_event_to_dict(py: Python<'_>, event: &SessionEvent) -> PyResult<PyObject> {
+    let dict = pyo3::types::PyDict::new_bound(py);
+    match event {
+        SessionEvent::Preempted(p) => {
+            dict.set_item("event", "preempted")?;
+            dict.set_item("reason", p.reason.as_str())?;
+            dict.set_item("desc", &p.desc)?;
+            dict.set_item("timestamp", p.timestamp)?;
+        }
+        SessionEvent::Resolved { timestamp } => {
+            dict.set_item("event", "resolved")?;
+            dict.set_item("timestamp", timestamp)?;
+        }
     }
+    Ok(dict.into_any().unbind())
+}
+
+fn market_session_event_to_dict(py: Python<'_>, event: &MarketSessionEvent) -> PyResult<PyObject> {
+    let dict = pyo3::types::PyDict::new_bound(py);
+    dict.set_item("market", event.market.as_str())?;
+    dict.set_item("prev_state", event.prev_state)?;
+    dict.set_item("state", event.state)?;
+    dict.set_item("prev_phase", event.prev_phase().map(|p| p.as_str()))?;
+    dict.set_item("phase", event.phase().as_str())?;
+    dict.set_item("timestamp", event.timestamp)?;
+    Ok(dict.into_any().unbind())
+}
+
+fn order_event_to_dict(py: Python<'_>, names: &NameTable, aliases: &SymbolMap, event: &OrderEvent) -> PyResult<PyObject> {
+    let dict = pyo3::types::PyDict::new_bound(py);
+    dict.set_item("acc_id", event.acc_id)?;
+    dict.set_item("order_id", event.order_id)?;
+    dict.set_item("code", &event.code)?;
+    dict.set_item("prev_state", event.prev_state.map(|s| s.as_str()))?;
+    dict.set_item("state", event.state.as_str())?;
+    dict.set_item("qty", event.qty)?;
+    dict.set_item("filled_qty", event.filled_qty)?;
+    dict.set_item("fill_avg_price", event.fill_avg_price)?;
+    let fill = event.fill.as_ref().map(|f| fill_to_dict(py, names, aliases, f)).transpose()?;
+    dict.set_item("fill", fill)?;
+    dict.set_item("timestamp", event.timestamp)?;
+    Ok(dict.into_any().unbind())
+}
+
+fn amendment_to_dict(py: Python<'_>, amendment: &Amendment) -> PyResult<PyObject> {
+    let dict = pyo3::types::PyDict::new_bound(py);
+    dict.set_item("kind", amendment.kind.as_str())?;
+    dict.set_item("timestamp", amendment.timestamp)?;
+    dict.set_item("qty", amendment.qty)?;
+    dict.set_item("price", amendment.price)?;
+    dict.set_item("order_status", amendment.order_status)?;
+    Ok(dict.into_any().unbind())
 }
 
 #[pymethods]
@@ -50,13 +1263,63 @@ impl PyFutuClient {
             .map_err(|e| PyRuntimeError::new_err(format!("Failed to create runtime: {}", e)))?;
         Ok(Self {
             runtime,
-            client: SyncMutex::new(None),
-            push_channels: SyncMutex::new(Vec::new()),
-            push_handles: SyncMutex::new(Vec::new()),
+            client: Arc::new(SyncMutex::new(None)),
+            push_channels: Arc::new(SyncMutex::new(Vec::new())),
+            tasks: TaskSupervisor::new(),
+            ipo_watchers: SyncMutex::new(Vec::new()),
+            warrant_watchers: SyncMutex::new(Vec::new()),
+            listing_watchers: SyncMutex::new(Vec::new()),
+            bar_channels: SyncMutex::new(Vec::new()),
+            push_callbacks: SyncMutex::new(HashMap::new()),
+            stale_monitors: SyncMutex::new(Vec::new()),
+            default_account: SyncMutex::new(None),
+            profiles: SyncMutex::new(ProfileRegistry::new()),
+            suspensions: SuspensionCache::default(),
+            instruments: Arc::new(InstrumentCache::default()),
+            names: Arc::new(SyncMutex::new(Arc::new(NameTable::new()))),
+            aliases: Arc::new(SyncMutex::new(Arc::new(SymbolMap::new()))),
+            brokers: SyncMutex::new(BrokerTable::new()),
+            pause_trading_on_preempt: std::sync::atomic::AtomicBool::new(false),
+            funds_protocols: std::sync::atomic::AtomicBool::new(false),
+            dedup_pushes: std::sync::atomic::AtomicBool::new(false),
+            sub_quota: SyncMutex::new(None),
+            auto_round_orders: std::sync::atomic::AtomicBool::new(false),
+            unlock_credentials: SyncMutex::new(None),
+            order_throttle: SyncMutex::new(OrderThrottleConfig::default()),
+            retry_policy: SyncMutex::new(RetryPolicy::default()),
+            response_cache: SyncMutex::new(ResponseCacheConfig::default()),
+            rate_limit: SyncMutex::new(RateLimitConfig::default()),
+            socket_options: SyncMutex::new(SocketOptions::default()),
+            slow_request_threshold: SyncMutex::new(None),
+            session_events: SyncMutex::new(None),
+            order_events: SyncMutex::new(None),
+            sub_expiry: SyncMutex::new(None),
+            market_session: Arc::new(MarketSession::new()),
+            market_events: SyncMutex::new(None),
+            live_portfolio: SyncMutex::new(None),
+            connects: Arc::new(std::sync::atomic::AtomicU64::new(0)),
+            mock: SyncMutex::new(None),
         })
     }
 
     /// Connect to Futu OpenD gateway.
+    /// fallback_endpoints: optional list of (host, port) tried in order if the
+    /// primary endpoint is unreachable. IPv6 hosts may be bare or bracketed.
+    /// trace_dump_path: optional file that message trace dumps are appended to.
+    /// decode_mode: "lenient" (default) decodes messages lacking required
+    /// fields with defaults and logs a warning; "strict" rejects them.
+    /// mock: connect to an in-process mock OpenD instead of host and port,
+    /// also enabled by the environment variable FUTU_MOCK=1. Requests succeed
+    /// without data unless `mock_response()` registered one; `mock_push()`
+    /// sends pushes.
+    /// gateway_log_dir: OpenD's log directory; rejected requests are then
+    /// reported by `gateway_rejections()` with the gateway's log lines.
+    /// rsa_key_path: PEM private key configured in OpenD; the handshake is
+    /// then RSA-encrypted and later messages AES-encrypted.
+    /// aes_padding: "compatible" (default) decrypts either padding OpenD
+    /// uses, "futu" and "pkcs7" only FTAES_ECB or standard AES_ECB bodies.
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (host, port, client_id, client_ver, fallback_endpoints=None, trace_dump_path=None, decode_mode=None, mock=false, gateway_log_dir=None, rsa_key_path=None, aes_padding=None))]
     fn connect(
         &self,
         py: Python<'_>,
@@ -64,12 +1327,58 @@ impl PyFutuClient {
         port: u16,
         client_id: &str,
         client_ver: i32,
+        fallback_endpoints: Option<Vec<(String, u16)>>,
+        trace_dump_path: Option<std::path::PathBuf>,
+        decode_mode: Option<&str>,
+        mock: bool,
+        gateway_log_dir: Option<std::path::PathBuf>,
+        rsa_key_path: Option<std::path::PathBuf>,
+        aes_padding: Option<&str>,
     ) -> PyResult<()> {
+        let mock = mock || std::env::var("FUTU_MOCK").is_ok_and(|v| v == "1");
+        let (host, port, fallback_endpoints) = if mock {
+            let mock = self.mock_gateway(py)?;
+            ("127.0.0.1", mock.port(), None)
+        } else {
+            (host, port, fallback_endpoints)
+        };
+        let decode_mode = decode_mode
+            .map(str::parse::<DecodeMode>)
+            .transpose()
+            .map_err(PyValueError::new_err)?
+            .unwrap_or_default();
+        let aes_padding = aes_padding
+            .map(str::parse::<AesPadding>)
+            .transpose()
+            .map_err(PyValueError::new_err)?
+            .unwrap_or_default();
+        let socket_options = *self.socket_options.lock();
         let config = FutuConfig {
             host: host.to_string(),
             port,
             client_id: client_id.to_string(),
             client_ver,
+            fallback_endpoints: fallback_endpoints.unwrap_or_default(),
+            // The data and execution clients reconnect and resubscribe themselves
+            reconnect: false,
+            trace_dump_path,
+            gateway_log_dir,
+            rsa_key_path,
+            aes_padding,
+            pause_trading_on_preempt: self.pause_trading_on_preempt.load(std::sync::atomic::Ordering::Relaxed),
+            dedup_pushes: self.dedup_pushes.load(std::sync::atomic::Ordering::Relaxed),
+            sub_quota: *self.sub_quota.lock(),
+            decode_mode,
+            order_throttle: self.order_throttle.lock().clone(),
+            retry_policy: self.retry_policy.lock().clone(),
+            enable_funds_protocols: self.funds_protocols.load(std::sync::atomic::Ordering::Relaxed),
+            response_cache: self.response_cache.lock().clone(),
+            rate_limit: self.rate_limit.lock().clone(),
+            slow_request_threshold: *self.slow_request_threshold.lock(),
+            connect_timeout: socket_options.connect_timeout,
+            tcp_keepalive: socket_options.tcp_keepalive,
+            read_timeout: socket_options.read_timeout,
+            nodelay: socket_options.nodelay,
             ..Default::default()
         };
 
@@ -78,35 +1387,86 @@ impl PyFutuClient {
         let client = py.allow_threads(|| {
             let mut client = self.runtime.block_on(async {
                 FutuClient::connect(config).await
-            }).map_err(|e| e.to_string())?;
+            })?;
 
             self.runtime.block_on(async {
                 client.init().await
-            }).map_err(|e| e.to_string())?;
+            })?;
 
-            Ok::<_, String>(client)
-        }).map_err(|e| PyRuntimeError::new_err(format!("Connection failed: {}", e)))?;
+            Ok::<_, crate::client::init::InitError>(client)
+        }).map_err(|e| e.into_py_err("Connection failed"))?;
 
         // Brief lock to store the connected client
-        *self.client.lock() = Some(Arc::new(client));
+        let client = Arc::new(client);
+        *self.session_events.lock() = Some(Arc::new(Mutex::new(client.session().subscribe())));
+        let order_events = py.allow_threads(|| self.runtime.block_on(client.order_events()));
+        *self.order_events.lock() = Some(Arc::new(Mutex::new(order_events)));
+        // The expiry watcher of a previous connection stops with its receiver
+        self.sub_expiry.lock().take();
+        *self.client.lock() = Some(Arc::clone(&client));
+        self.connects.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+
+        // Replay active subscription profiles (e.g. after a reconnect)
+        let active = self.profiles.lock().active_profiles();
+        if !active.is_empty() {
+            py.allow_threads(|| {
+                self.runtime.block_on(async {
+                    for (name, profile) in &active {
+                        if let Err(e) = crate::quote::profiles::activate_profile(&client, profile).await {
+                            tracing::warn!("Failed to replay subscription profile '{}': {}", name, e);
+                        }
+                    }
+                });
+            });
+        }
         Ok(())
     }
 
-    /// Disconnect from Futu OpenD.
-    fn disconnect(&self, py: Python<'_>) -> PyResult<()> {
-        // Abort push forwarder tasks
-        for handle in self.push_handles.lock().drain(..) {
-            handle.abort();
-        }
-        self.push_channels.lock().clear();
+    /// Answer requests of `proto_id` with `body`, an encoded Response
+    /// message, on the mock OpenD of `connect(mock=True)`.
+    fn mock_response(&self, proto_id: u32, body: Vec<u8>) -> PyResult<()> {
+        self.connected_mock()?.respond(proto_id, body);
+        Ok(())
+    }
+
+    /// Send a push of `proto_id` with `body`, an encoded Response message,
+    /// from the mock OpenD of `connect(mock=True)`. Returns the number of
+    /// connections it reached.
+    fn mock_push(&self, proto_id: u32, body: Vec<u8>) -> PyResult<usize> {
+        Ok(self.connected_mock()?.push(proto_id, body))
+    }
 
-        // Clear pending requests so callers don't hang forever
+    /// Disconnect from Futu OpenD. Requests in flight, e.g. order
+    /// placements, are given up to grace_period seconds to be answered
+    /// before the connection is closed; new ones fail at once. Quote
+    /// subscriptions are dropped at OpenD.
+    #[pyo3(signature = (grace_period=5.0))]
+    fn disconnect(&self, py: Python<'_>, grace_period: f64) -> PyResult<()> {
+        let grace_period = std::time::Duration::try_from_secs_f64(grace_period)
+            .map_err(|_| PyValueError::new_err("grace_period must be a non-negative number of seconds"))?;
+        // Abort push forwarder and poller tasks
+        self.tasks.abort_all();
+        self.push_channels.lock().clear();
+        self.ipo_watchers.lock().clear();
+        self.warrant_watchers.lock().clear();
+        self.listing_watchers.lock().clear();
+        self.stale_monitors.lock().clear();
+        self.bar_channels.lock().clear();
+        self.session_events.lock().take();
+        self.order_events.lock().take();
+        self.sub_expiry.lock().take();
+        self.market_events.lock().take();
+        self.live_portfolio.lock().take();
+        let callbacks: Vec<PushCallback> = self.push_callbacks.lock().drain().map(|(_, callback)| callback).collect();
+        py.allow_threads(|| callbacks.into_iter().for_each(PushCallback::stop));
+
+        // Let pending requests finish, then fail later ones so callers
+        // don't hang forever
         if let Some(client) = self.client.lock().as_ref().cloned() {
-            py.allow_threads(|| {
-                self.runtime.block_on(async {
-                    client.clear_pending().await;
-                });
-            });
+            let report = py.allow_threads(|| self.runtime.block_on(client.shutdown(grace_period)));
+            if report.abandoned > 0 {
+                tracing::warn!("Disconnected with {} requests unanswered", report.abandoned);
+            }
         }
 
         // Take the Arc out — when the last Arc reference is dropped,
@@ -117,52 +1477,263 @@ impl PyFutuClient {
     }
 
     /// Subscribe to quote data.
-    /// securities: list of (market, code) tuples
+    /// securities: list of (market, code) tuples or symbol aliases
     /// sub_types: list of SubType integers
     /// is_sub: True to subscribe, False to unsubscribe
+    /// Securities OpenD rejects are isolated and the others still applied.
+    /// Returns a list of dicts with market, code, alias, ok and error (OpenD's
+    /// reason, None if ok), in input order. If any security was rejected,
+    /// raises SubscriptionRejectedError with args (message, results) unless
+    /// raise_on_reject is False.
+    /// order_book_detail: subscribe order books with per-order detail, SF
+    /// quotes only. Qot_Sub has no depth parameter: order book pushes carry
+    /// as many levels as the quote right of the market allows, reported as
+    /// "depth" in each push, and lack detail without the SF right.
+    /// ttl_secs: unsubscribe the subscribed pairs automatically after this
+    /// many seconds unless renewed with `renew_subscriptions()`; expiries
+    /// are reported by `poll_subscription_expiry()`. Subscribing a pair
+    /// again without ttl_secs keeps it subscribed.
+    /// reg_push: register push for the pairs (default); False subscribes
+    /// without pushes, e.g. for polling with get_basic_qot()
+    /// first_push: push the data OpenD already holds once after
+    /// registering (None = OpenD default, True)
+    /// push_rehab_types: RehabType values of K-line pushes (None = OpenD
+    /// default, forward)
+    /// extended_time: include US pre-market and after-hours data
+    /// (superseded by session)
+    /// session: Session of US quotes, e.g. 3 for all sessions
+    /// unsub_all: with is_sub False, drop every subscription of the
+    /// connection in one request; securities and sub_types are ignored
+    /// except for shaping the results
+    /// Only order_book_detail is kept when subscriptions are replayed after
+    /// a reconnect.
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (
+        securities, sub_types, is_sub, raise_on_reject=true, order_book_detail=false, ttl_secs=None,
+        reg_push=true, first_push=None, push_rehab_types=None, extended_time=None, session=None, unsub_all=false,
+    ))]
     fn subscribe(
         &self,
         py: Python<'_>,
-        securities: Vec<(i32, String)>,
+        securities: Vec<SecurityArg>,
         sub_types: Vec<i32>,
         is_sub: bool,
-    ) -> PyResult<()> {
+        raise_on_reject: bool,
+        order_book_detail: bool,
+        ttl_secs: Option<f64>,
+        reg_push: bool,
+        first_push: Option<bool>,
+        push_rehab_types: Option<Vec<i32>>,
+        extended_time: Option<bool>,
+        session: Option<i32>,
+        unsub_all: bool,
+    ) -> PyResult<Vec<PyObject>> {
+        let securities = self.resolve_securities(securities)?;
+        let ttl = ttl_secs
+            .filter(|_| is_sub)
+            .map(|secs| std::time::Duration::try_from_secs_f64(secs).map_err(|e| PyValueError::new_err(format!("Invalid ttl_secs: {}", e))))
+            .transpose()?;
         let client = self.get_client()?;
+        if ttl.is_some() {
+            self.ensure_sub_expiry(&client);
+        }
         let client = &*client;
+        let options = crate::quote::subscribe::SubOptions {
+            order_book_detail,
+            reg_push: Some(reg_push),
+            first_push,
+            push_rehab_types: push_rehab_types.unwrap_or_default(),
+            extended_time,
+            session,
+            unsub_all,
+        };
+
+        let results = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                match ttl {
+                    Some(ttl) => crate::quote::sub_manager::subscribe_with_ttl(client, securities, sub_types, ttl, &options).await,
+                    None => crate::quote::subscribe::subscribe_with_options(client, securities, sub_types, is_sub, &options).await,
+                }
+            })
+        }).map_err(|e| e.into_py_err("Subscribe failed"))?;
+
+        let aliases = self.aliases();
+        let dicts = results
+            .iter()
+            .map(|r| {
+                let d = pyo3::types::PyDict::new_bound(py);
+                d.set_item("market", r.security.0)?;
+                d.set_item("code", &r.security.1)?;
+                d.set_item("alias", aliases.alias(r.security.0, &r.security.1))?;
+                d.set_item("ok", r.is_ok())?;
+                d.set_item("error", r.error.as_deref())?;
+                Ok(d.into_any().unbind())
+            })
+            .collect::<PyResult<Vec<PyObject>>>()?;
+        if raise_on_reject {
+            if let Err(e) = crate::quote::subscribe::check_sub_results(results) {
+                return Err(SubscriptionRejectedError::new_err((format!("Subscribe failed: {}", e), dicts)));
+            }
+        }
+        Ok(dicts)
+    }
 
+    /// Drop every quote subscription of the connection in one request, also
+    /// ones made outside `subscribe()`, and clear the local record of them,
+    /// including TTLs and push statistics. Returns the number of (security,
+    /// sub type) pairs that were recorded. OpenD frees the quota of a
+    /// subscription no sooner than a minute after it was made.
+    fn unsubscribe_all(&self, py: Python<'_>) -> PyResult<u32> {
+        let client = self.get_client()?;
+        let client = &*client;
         py.allow_threads(|| {
             self.runtime.block_on(async {
-                crate::quote::subscribe::subscribe(client, securities, sub_types, is_sub).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Subscribe failed: {}", e)))
+                crate::quote::subscribe::unsubscribe_all(client).await
+            })
+        }).map_err(|e| e.into_py_err("Unsubscribe all failed"))
     }
 
-    /// Get static info for securities.
-    /// securities: list of (market, code) tuples
-    /// Returns list of dicts with static info.
-    fn get_static_info(
+    /// Register or unregister push for subscribed securities without changing
+    /// the subscriptions.
+    /// securities: list of (market, code) tuples or symbol aliases
+    /// sub_types: list of SubType integers
+    /// rehab_type: RehabType for K-line pushes (None = OpenD default, forward)
+    /// is_first_push: push data already held by OpenD once after registering
+    /// (None = OpenD default, True)
+    #[pyo3(signature = (securities, sub_types, is_reg=true, rehab_type=None, is_first_push=None))]
+    fn reg_qot_push(
         &self,
         py: Python<'_>,
-        securities: Vec<(i32, String)>,
-    ) -> PyResult<Vec<PyObject>> {
+        securities: Vec<SecurityArg>,
+        sub_types: Vec<i32>,
+        is_reg: bool,
+        rehab_type: Option<i32>,
+        is_first_push: Option<bool>,
+    ) -> PyResult<()> {
+        let securities = self.resolve_securities(securities)?;
         let client = self.get_client()?;
         let client = &*client;
 
-        let response = py.allow_threads(|| {
+        py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::quote::subscribe::reg_qot_push(
+                    client, securities, sub_types, rehab_type, is_reg, is_first_push,
+                ).await
+            })
+        }).map_err(|e| e.into_py_err("Reg qot push failed"))
+    }
+
+    /// Define (or replace) a named subscription profile.
+    /// securities: list of (market, code) tuples or symbol aliases
+    /// sub_types: list of SubType integers
+    fn define_profile(
+        &self,
+        name: &str,
+        securities: Vec<SecurityArg>,
+        sub_types: Vec<i32>,
+    ) -> PyResult<()> {
+        let securities = self.resolve_securities(securities)?;
+        self.profiles.lock().define(name, SubscriptionProfile { securities, sub_types });
+        Ok(())
+    }
+
+    /// Remove a profile definition.
+    /// Returns False if the profile is unknown or still active.
+    fn remove_profile(&self, name: &str) -> bool {
+        self.profiles.lock().remove(name).is_some()
+    }
+
+    /// Subscribe all securities/sub types of a profile and mark it active.
+    /// Active profiles are re-subscribed automatically on (re)connect.
+    fn activate_profile(&self, py: Python<'_>, name: &str) -> PyResult<()> {
+        let client = self.get_client()?;
+        let client = &*client;
+        let profile = self.profiles.lock().get(name).cloned()
+            .ok_or_else(|| pyo3::exceptions::PyKeyError::new_err(format!("Unknown profile: {}", name)))?;
+
+        py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::quote::profiles::activate_profile(client, &profile).await
+            })
+        }).map_err(|e| e.into_py_err("Activate profile failed"))?;
+
+        self.profiles.lock().mark_active(name);
+        Ok(())
+    }
+
+    /// Unsubscribe a profile's subscriptions that no other active profile
+    /// still needs, and mark it inactive.
+    fn deactivate_profile(&self, py: Python<'_>, name: &str) -> PyResult<()> {
+        let client = self.get_client()?;
+        let client = &*client;
+        let pairs = {
+            let profiles = self.profiles.lock();
+            if !profiles.is_active(name) {
+                return Ok(());
+            }
+            profiles.exclusive_pairs(name)
+        };
+
+        py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::quote::profiles::deactivate_pairs(client, pairs).await
+            })
+        }).map_err(|e| e.into_py_err("Deactivate profile failed"))?;
+
+        self.profiles.lock().mark_inactive(name);
+        Ok(())
+    }
+
+    /// List subscription profiles.
+    /// Returns list of dicts with name, securities, sub_types and active.
+    fn list_profiles(&self, py: Python<'_>) -> PyResult<Vec<PyObject>> {
+        let profiles = self.profiles.lock();
+        let mut result = Vec::new();
+        for name in profiles.names() {
+            if let Some(profile) = profiles.get(&name) {
+                let dict = pyo3::types::PyDict::new_bound(py);
+                dict.set_item("name", &name)?;
+                dict.set_item("securities", profile.securities.clone())?;
+                dict.set_item("sub_types", profile.sub_types.clone())?;
+                dict.set_item("active", profiles.is_active(&name))?;
+                result.push(dict.into_any().unbind());
+            }
+        }
+        Ok(result)
+    }
+
+    /// Get static info for securities.
+    /// securities: list of (market, code) tuples or symbol aliases
+    /// Returns list of dicts with static info.
+    fn get_static_info(
+        &self,
+        py: Python<'_>,
+        securities: Vec<SecurityArg>,
+    ) -> PyResult<Vec<PyObject>> {
+        let securities = self.resolve_securities(securities)?;
+        let client = self.get_client()?;
+        let client = &*client;
+        let aliases = self.aliases();
+
+        let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::quote::snapshot::get_static_info(client, securities).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get static info failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get static info failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
+            let names = self.names();
+            let mut checkpoint = GilCheckpoint::new(py);
             for info in s2c.static_info_list {
+                checkpoint.tick()?;
                 let dict = pyo3::types::PyDict::new_bound(py);
                 let basic = &info.basic;
                 let sec = &basic.security;
                 dict.set_item("market", sec.market)?;
                 dict.set_item("code", &sec.code)?;
-                dict.set_item("name", &basic.name)?;
+                dict.set_item("alias", aliases.alias(sec.market, &sec.code))?;
+                dict.set_item("name", names.localize(sec.market, &sec.code, &basic.name))?;
                 dict.set_item("lot_size", basic.lot_size)?;
                 dict.set_item("sec_type", basic.sec_type)?;
                 dict.set_item("list_time", &basic.list_time)?;
@@ -200,71 +1771,108 @@ impl PyFutuClient {
     }
 
     /// Get order book for a single security.
-    /// Returns a dict with asks and bids lists.
-    #[pyo3(signature = (market, code, num=10))]
+    /// Returns a dict with asks and bids lists and depth, the levels
+    /// returned. Depth is below num when the quote right of the market does
+    /// not cover num levels.
+    /// include_detail: add the per-order "details" (order_id, volume) of
+    /// each level, which only SF quotes carry.
+    #[pyo3(signature = (security, num=10, include_detail=false))]
     fn get_order_book(
         &self,
         py: Python<'_>,
-        market: i32,
-        code: String,
+        security: SecurityArg,
         num: i32,
+        include_detail: bool,
     ) -> PyResult<PyObject> {
+        let (market, code) = self.resolve_securities(vec![security])?.remove(0);
         let client = self.get_client()?;
         let client = &*client;
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::quote::snapshot::get_order_book(client, market, code, num).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get order book failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get order book failed"))?;
 
         let dict = pyo3::types::PyDict::new_bound(py);
         if let Some(s2c) = response.s2c {
-            let asks = pyo3::types::PyList::empty_bound(py);
-            for ob in &s2c.order_book_ask_list {
-                let d = pyo3::types::PyDict::new_bound(py);
-                d.set_item("price", ob.price)?;
-                d.set_item("volume", ob.volume)?;
-                d.set_item("order_count", ob.order_count)?;
-                asks.append(d)?;
-            }
-            dict.set_item("asks", asks)?;
-
-            let bids = pyo3::types::PyList::empty_bound(py);
-            for ob in &s2c.order_book_bid_list {
-                let d = pyo3::types::PyDict::new_bound(py);
-                d.set_item("price", ob.price)?;
-                d.set_item("volume", ob.volume)?;
-                d.set_item("order_count", ob.order_count)?;
-                bids.append(d)?;
-            }
-            dict.set_item("bids", bids)?;
+            dict.set_item("asks", order_book_levels_to_py(py, &s2c.order_book_ask_list, include_detail)?)?;
+            dict.set_item("bids", order_book_levels_to_py(py, &s2c.order_book_bid_list, include_detail)?)?;
+            dict.set_item("depth", crate::quote::snapshot::book_depth(&s2c.order_book_ask_list, &s2c.order_book_bid_list))?;
         }
         Ok(dict.into_any().unbind())
     }
 
+    /// Order book of a security kept from its order book pushes, without
+    /// a request to OpenD. Returns a dict with asks and bids (best first,
+    /// cut to depth levels if given), bid_time and ask_time (server receive
+    /// times), updated_at, sequence (pushes applied), gaps (pushes after a
+    /// gap in server time) and dropped (out-of-order pushes); None before
+    /// the first push.
+    #[pyo3(signature = (security, depth=None))]
+    fn local_order_book(&self, py: Python<'_>, security: SecurityArg, depth: Option<usize>) -> PyResult<Option<PyObject>> {
+        let security = self.resolve_securities(vec![security])?.remove(0);
+        let client = self.get_client()?;
+        let book = match depth {
+            Some(levels) => client.order_books().depth(&security, levels),
+            None => client.order_books().book(&security),
+        };
+        let Some(book) = book else {
+            return Ok(None);
+        };
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("asks", book_levels_to_py(py, &book.asks)?)?;
+        dict.set_item("bids", book_levels_to_py(py, &book.bids)?)?;
+        dict.set_item("bid_time", book.bid_time)?;
+        dict.set_item("ask_time", book.ask_time)?;
+        dict.set_item("updated_at", book.updated_at)?;
+        dict.set_item("sequence", book.sequence)?;
+        dict.set_item("gaps", book.gaps)?;
+        dict.set_item("dropped", book.dropped)?;
+        Ok(Some(dict.into_any().unbind()))
+    }
+
+    /// Best bid and ask of a security's local order book (see
+    /// `local_order_book()`). Returns a dict with bid, bid_volume, ask and
+    /// ask_volume (None for an empty side), or None before the first push.
+    fn best_bid_ask(&self, py: Python<'_>, security: SecurityArg) -> PyResult<Option<PyObject>> {
+        let security = self.resolve_securities(vec![security])?.remove(0);
+        let client = self.get_client()?;
+        let Some((bid, ask)) = client.order_books().best_bid_ask(&security) else {
+            return Ok(None);
+        };
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("bid", bid.map(|l| l.price))?;
+        dict.set_item("bid_volume", bid.map(|l| l.volume))?;
+        dict.set_item("ask", ask.map(|l| l.price))?;
+        dict.set_item("ask_volume", ask.map(|l| l.volume))?;
+        Ok(Some(dict.into_any().unbind()))
+    }
+
     /// Get ticker (trade ticks) for a single security.
     /// Returns a list of ticker dicts.
-    #[pyo3(signature = (market, code, max_ret_num=100))]
+    #[pyo3(signature = (security, max_ret_num=100))]
     fn get_ticker(
         &self,
         py: Python<'_>,
-        market: i32,
-        code: String,
+        security: SecurityArg,
         max_ret_num: i32,
     ) -> PyResult<Vec<PyObject>> {
+        let (market, code) = self.resolve_securities(vec![security])?.remove(0);
         let client = self.get_client()?;
         let client = &*client;
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::quote::snapshot::get_ticker(client, market, code, max_ret_num).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get ticker failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get ticker failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
+            let mut checkpoint = GilCheckpoint::new(py);
             for t in &s2c.ticker_list {
+                checkpoint.tick()?;
                 let dict = pyo3::types::PyDict::new_bound(py);
                 dict.set_item("price", t.price)?;
                 dict.set_item("volume", t.volume)?;
@@ -281,38 +1889,55 @@ impl PyFutuClient {
     }
 
     /// Get basic quote data.
+    /// fields: names of the fields to include (market and code are always
+    /// included); None includes all. Unknown names raise ValueError.
+    /// typed: return `BasicQot` objects with every field instead of dicts
+    #[pyo3(signature = (securities, fields=None, typed=false))]
     fn get_basic_qot(
         &self,
         py: Python<'_>,
-        securities: Vec<(i32, String)>,
+        securities: Vec<SecurityArg>,
+        fields: Option<Vec<String>>,
+        typed: bool,
     ) -> PyResult<Vec<PyObject>> {
+        let securities = self.resolve_securities(securities)?;
+        let filter = FieldFilter::from_py(fields, &BASIC_QOT_FIELDS)?;
         let client = self.get_client()?;
         let client = &*client;
+        let aliases = self.aliases();
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::quote::snapshot::get_basic_qot(client, securities).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get basic qot failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get basic qot failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
+            let names = self.names();
+            let mut checkpoint = GilCheckpoint::new(py);
             for qot in s2c.basic_qot_list {
+                checkpoint.tick()?;
+                if typed {
+                    result.push(Py::new(py, BasicQot::from_proto(&names, &aliases, &qot))?.into_any());
+                    continue;
+                }
                 let dict = pyo3::types::PyDict::new_bound(py);
                 let sec = &qot.security;
                 dict.set_item("market", sec.market)?;
                 dict.set_item("code", &sec.code)?;
-                dict.set_item("name", &qot.name)?;
-                dict.set_item("cur_price", qot.cur_price)?;
-                dict.set_item("price_spread", qot.price_spread)?;
-                dict.set_item("open_price", qot.open_price)?;
-                dict.set_item("high_price", qot.high_price)?;
-                dict.set_item("low_price", qot.low_price)?;
-                dict.set_item("last_close_price", qot.last_close_price)?;
-                dict.set_item("volume", qot.volume)?;
-                dict.set_item("turnover", qot.turnover)?;
-                dict.set_item("turnover_rate", qot.turnover_rate)?;
-                dict.set_item("update_timestamp", qot.update_timestamp)?;
+                filter.set(&dict, "alias", aliases.alias(sec.market, &sec.code))?;
+                filter.set(&dict, "name", names.localize_opt(sec.market, &sec.code, qot.name.as_deref()))?;
+                filter.set(&dict, "cur_price", qot.cur_price)?;
+                filter.set(&dict, "price_spread", qot.price_spread)?;
+                filter.set(&dict, "open_price", qot.open_price)?;
+                filter.set(&dict, "high_price", qot.high_price)?;
+                filter.set(&dict, "low_price", qot.low_price)?;
+                filter.set(&dict, "last_close_price", qot.last_close_price)?;
+                filter.set(&dict, "volume", qot.volume)?;
+                filter.set(&dict, "turnover", qot.turnover)?;
+                filter.set(&dict, "turnover_rate", qot.turnover_rate)?;
+                filter.set(&dict, "update_timestamp", qot.update_timestamp)?;
                 result.push(dict.into_any().unbind());
             }
         }
@@ -320,19 +1945,21 @@ impl PyFutuClient {
     }
 
     /// Get historical K-line data.
+    /// typed: return `Kline` objects instead of dicts
     #[allow(clippy::too_many_arguments)]
-    #[pyo3(signature = (market, code, rehab_type, kl_type, begin_time, end_time, max_count=None))]
+    #[pyo3(signature = (security, rehab_type, kl_type, begin_time, end_time, max_count=None, typed=false))]
     fn get_history_kl(
         &self,
         py: Python<'_>,
-        market: i32,
-        code: String,
+        security: SecurityArg,
         rehab_type: i32,
         kl_type: i32,
         begin_time: String,
         end_time: String,
         max_count: Option<i32>,
+        typed: bool,
     ) -> PyResult<Vec<PyObject>> {
+        let (market, code) = self.resolve_securities(vec![security])?.remove(0);
         let client = self.get_client()?;
         let client = &*client;
 
@@ -342,29 +1969,262 @@ impl PyFutuClient {
                     client, market, code, rehab_type, kl_type,
                     begin_time, end_time, max_count,
                 ).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get history KL failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get history KL failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
-            for kl in s2c.kl_list {
-                let dict = pyo3::types::PyDict::new_bound(py);
-                dict.set_item("time", &kl.time)?;
-                dict.set_item("is_blank", kl.is_blank)?;
-                dict.set_item("open_price", kl.open_price)?;
-                dict.set_item("high_price", kl.high_price)?;
-                dict.set_item("low_price", kl.low_price)?;
-                dict.set_item("close_price", kl.close_price)?;
-                dict.set_item("last_close_price", kl.last_close_price)?;
-                dict.set_item("volume", kl.volume)?;
-                dict.set_item("turnover", kl.turnover)?;
-                dict.set_item("timestamp", kl.timestamp)?;
-                result.push(dict.into_any().unbind());
+            let mut checkpoint = GilCheckpoint::new(py);
+            for kl in &s2c.kl_list {
+                checkpoint.tick()?;
+                result.push(kline_to_py(py, kl, typed)?);
             }
         }
         Ok(result)
     }
 
+    /// Get every historical K-line of a range, following OpenD's pagination
+    /// until it is exhausted.
+    /// max_count: K-lines per request; None lets OpenD choose
+    /// max_pages: stop after this many requests; None follows every page
+    /// typed: return `Kline` objects instead of dicts
+    /// Returns list of dicts as `get_history_kl()`. Any failed request
+    /// fails the call; see `download_history_kl()` to keep partial data.
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (security, rehab_type, kl_type, begin_time, end_time, max_count=None, max_pages=None, typed=false))]
+    fn get_history_kl_all(
+        &self,
+        py: Python<'_>,
+        security: SecurityArg,
+        rehab_type: i32,
+        kl_type: i32,
+        begin_time: String,
+        end_time: String,
+        max_count: Option<i32>,
+        max_pages: Option<usize>,
+        typed: bool,
+    ) -> PyResult<Vec<PyObject>> {
+        let (market, code) = self.resolve_securities(vec![security])?.remove(0);
+        let client = self.get_client()?;
+        let client = &*client;
+
+        let kl_list = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::quote::history::get_history_kl_all(
+                    client, market, code, rehab_type, kl_type,
+                    begin_time, end_time, max_count, max_pages,
+                ).await
+            })
+        }).map_err(|e| e.into_py_err("Get history KL failed"))?;
+
+        let mut result = Vec::with_capacity(kl_list.len());
+        let mut checkpoint = GilCheckpoint::new(py);
+        for kl in &kl_list {
+            checkpoint.tick()?;
+            result.push(kline_to_py(py, kl, typed)?);
+        }
+        Ok(result)
+    }
+
+    /// `get_history_kl_all()` as a pyarrow RecordBatch, one row per K-line,
+    /// with the columns time, timestamp, is_blank, open_price, high_price,
+    /// low_price, close_price, last_close_price, volume, turnover,
+    /// turnover_rate, pe and change_rate. The columns are shared with
+    /// pyarrow, not copied, so e.g. `polars.from_arrow()` loads them
+    /// directly. Needs the `arrow` feature and pyarrow.
+    #[cfg(feature = "arrow")]
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (security, rehab_type, kl_type, begin_time, end_time, max_count=None, max_pages=None))]
+    fn get_history_kl_arrow(
+        &self,
+        py: Python<'_>,
+        security: SecurityArg,
+        rehab_type: i32,
+        kl_type: i32,
+        begin_time: String,
+        end_time: String,
+        max_count: Option<i32>,
+        max_pages: Option<usize>,
+    ) -> PyResult<PyObject> {
+        let (market, code) = self.resolve_securities(vec![security])?.remove(0);
+        use arrow::pyarrow::ToPyArrow;
+
+        let client = self.get_client()?;
+        let client = &*client;
+
+        let kl_list = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::quote::history::get_history_kl_all(
+                    client, market, code, rehab_type, kl_type,
+                    begin_time, end_time, max_count, max_pages,
+                ).await
+            })
+        }).map_err(|e| e.into_py_err("Get history KL failed"))?;
+        let batch = py.allow_threads(|| super::arrow::kl_record_batch(&kl_list))
+            .map_err(|e| PyRuntimeError::new_err(format!("Get history KL failed: {}", e)))?;
+
+        batch.to_pyarrow(py)
+    }
+
+    /// Download all historical K-lines of a range, following OpenD's
+    /// pagination, and optionally check them for gaps against the trading
+    /// calendar, duplicate times and inconsistent OHLC values.
+    /// page_size: K-lines per request; None lets OpenD choose
+    /// max_pages: stop after this many requests
+    /// validate: add an integrity report
+    /// resume_key: the `resume_key` of an earlier, incomplete download to
+    /// continue it instead of starting at begin_time
+    /// Returns a dict with `kl_list` (in the `get_history_kl()` layout),
+    /// `pages`, `is_complete`, `error` (of the page that stopped the
+    /// download, or None), `resume_key` (bytes, or None once complete) and
+    /// `report` (None unless validated). Only a failed first request raises.
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (security, rehab_type, kl_type, begin_time, end_time, page_size=None, max_pages=1000, validate=false, resume_key=None))]
+    fn download_history_kl(
+        &self,
+        py: Python<'_>,
+        security: SecurityArg,
+        rehab_type: i32,
+        kl_type: i32,
+        begin_time: String,
+        end_time: String,
+        page_size: Option<i32>,
+        max_pages: usize,
+        validate: bool,
+        resume_key: Option<Vec<u8>>,
+    ) -> PyResult<PyObject> {
+        let (market, code) = self.resolve_securities(vec![security])?.remove(0);
+        let client = self.get_client()?;
+        let client = &*client;
+        let options = crate::quote::history::KlDownloadOptions { page_size, max_pages, validate, resume_key };
+
+        let download = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::quote::history::download_history_kl(
+                    client, market, code, rehab_type, kl_type,
+                    begin_time, end_time, &options,
+                ).await
+            })
+        }).map_err(|e| e.into_py_err("Download history KL failed"))?;
+
+        let mut kl_list = Vec::with_capacity(download.kl_list.len());
+        let mut checkpoint = GilCheckpoint::new(py);
+        for kl in &download.kl_list {
+            checkpoint.tick()?;
+            kl_list.push(kline_to_dict(py, kl)?);
+        }
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("kl_list", kl_list)?;
+        dict.set_item("pages", download.pages)?;
+        dict.set_item("is_complete", download.is_complete)?;
+        dict.set_item("error", download.error.as_deref())?;
+        let resume_key = download.resume_key.as_deref().map(|key| pyo3::types::PyBytes::new_bound(py, key));
+        dict.set_item("resume_key", resume_key)?;
+        let report = download.report.as_ref().map(|r| kl_report_to_dict(py, r)).transpose()?;
+        dict.set_item("report", report)?;
+        Ok(dict.into_any().unbind())
+    }
+
+    /// History K-line quota as a dict with `used`, `remaining` and
+    /// `consumed`, a list of (market, code, time) of the symbols that used
+    /// quota in the last 30 days, time in Unix seconds.
+    fn get_history_kl_quota(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let client = self.get_client()?;
+        let client = &*client;
+
+        let quota = py.allow_threads(|| {
+            self.runtime.block_on(crate::quote::kl_quota::get_kl_quota(client))
+        }).map_err(|e| e.into_py_err("Get history KL quota failed"))?;
+        kl_quota_to_dict(py, &quota)
+    }
+
+    /// Download historical K-lines of several securities within the history
+    /// K-line quota. Securities already paid for in the last 30 days are
+    /// downloaded first; a batch needing more new symbols than the quota
+    /// has left raises unless allow_over_quota. Symbols throttled by OpenD
+    /// are retried after a backoff.
+    /// ledger_path: file of the symbols that used quota, read before and
+    /// updated after the batch
+    /// page_size, max_pages, validate: as for `download_history_kl()`
+    /// Returns a dict with `paid` and `new` (the plan, as (market, code)
+    /// lists), `downloads` (a list of dicts with `market`, `code` and the
+    /// `download_history_kl()` fields), `failed` (a list of (market, code,
+    /// error)) and `quota` (the quota after the batch, or None).
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (securities, rehab_type, kl_type, begin_time, end_time, ledger_path=None, allow_over_quota=false, page_size=None, max_pages=1000, validate=false))]
+    fn download_history_kl_batch(
+        &self,
+        py: Python<'_>,
+        securities: Vec<SecurityArg>,
+        rehab_type: i32,
+        kl_type: i32,
+        begin_time: String,
+        end_time: String,
+        ledger_path: Option<String>,
+        allow_over_quota: bool,
+        page_size: Option<i32>,
+        max_pages: usize,
+        validate: bool,
+    ) -> PyResult<PyObject> {
+        let securities = self.resolve_securities(securities)?;
+        let client = self.get_client()?;
+        let client = &*client;
+        let mut ledger = match &ledger_path {
+            Some(path) => crate::quote::kl_quota::KlQuotaLedger::open(path)
+                .map_err(|e| PyValueError::new_err(format!("Cannot read {}: {}", path, e)))?,
+            None => crate::quote::kl_quota::KlQuotaLedger::new(),
+        };
+        let options = crate::quote::kl_quota::KlBatchOptions {
+            download: crate::quote::history::KlDownloadOptions { page_size, max_pages, validate, resume_key: None },
+            allow_over_quota,
+            ..Default::default()
+        };
+
+        let batch = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::quote::kl_quota::download_history_kl_batch(
+                    client, &securities, rehab_type, kl_type,
+                    begin_time, end_time, &mut ledger, &options,
+                ).await
+            })
+        }).map_err(|e| e.into_py_err("Download history KL batch failed"))?;
+
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("paid", &batch.plan.paid)?;
+        dict.set_item("new", &batch.plan.new)?;
+        let mut downloads = Vec::with_capacity(batch.downloads.len());
+        let mut checkpoint = GilCheckpoint::new(py);
+        for ((market, code), download) in &batch.downloads {
+            let entry = pyo3::types::PyDict::new_bound(py);
+            entry.set_item("market", market)?;
+            entry.set_item("code", code)?;
+            let mut kl_list = Vec::with_capacity(download.kl_list.len());
+            for kl in &download.kl_list {
+                checkpoint.tick()?;
+                kl_list.push(kline_to_dict(py, kl)?);
+            }
+            entry.set_item("kl_list", kl_list)?;
+            entry.set_item("pages", download.pages)?;
+            entry.set_item("is_complete", download.is_complete)?;
+            entry.set_item("error", download.error.as_deref())?;
+            let resume_key = download.resume_key.as_deref().map(|key| pyo3::types::PyBytes::new_bound(py, key));
+            entry.set_item("resume_key", resume_key)?;
+            let report = download.report.as_ref().map(|r| kl_report_to_dict(py, r)).transpose()?;
+            entry.set_item("report", report)?;
+            downloads.push(entry);
+        }
+        dict.set_item("downloads", downloads)?;
+        let failed: Vec<(i32, String, String)> = batch
+            .failed
+            .iter()
+            .map(|((market, code), e)| (*market, code.clone(), e.to_string()))
+            .collect();
+        dict.set_item("failed", failed)?;
+        let quota = batch.quota_after.as_ref().map(|q| kl_quota_to_dict(py, q)).transpose()?;
+        dict.set_item("quota", quota)?;
+        Ok(dict.into_any().unbind())
+    }
+
     /// Get account list.
     #[pyo3(signature = (trd_category=None, need_general_sec_account=None))]
     fn get_acc_list(
@@ -383,8 +2243,8 @@ impl PyFutuClient {
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::trade::account::get_acc_list(client, user_id, trd_category, need_general_sec_account).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get acc list failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get acc list failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
@@ -406,44 +2266,61 @@ impl PyFutuClient {
     }
 
     /// Unlock trading.
-    /// security_firm: 1=FutuSecurities, 2=FutuInc, 3=FutuSG, etc.
-    #[pyo3(signature = (unlock, pwd_md5, security_firm=1))]
+    /// security_firm: 1=FutuSecurities, 2=FutuInc, 3=FutuSG, etc. If None, it is
+    /// detected from the account; an explicit value must match the account's
+    /// firm or SecurityFirmMismatchError is raised.
+    /// acc_id: account whose firm is used; defaults to the default account.
+    /// Without either, security_firm must be the firm of one of the real
+    /// accounts, and ValueError is raised if they are of several firms and
+    /// security_firm is None. Returns the security firm used (None if unknown).
+    #[pyo3(signature = (unlock, pwd_md5, security_firm=None, acc_id=None))]
     fn unlock_trade(
         &self,
         py: Python<'_>,
         unlock: bool,
         pwd_md5: String,
-        security_firm: i32,
-    ) -> PyResult<()> {
+        security_firm: Option<i32>,
+        acc_id: Option<u64>,
+    ) -> PyResult<Option<i32>> {
         let client = self.get_client()?;
         let client = &*client;
+        let acc_id = acc_id.or_else(|| self.default_account.lock().map(|d| d.1));
 
         py.allow_threads(|| {
             self.runtime.block_on(async {
-                crate::trade::account::unlock_trade(client, unlock, pwd_md5, Some(security_firm)).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Unlock trade failed: {}", e)))
+                crate::trade::account::unlock_trade_for_account(client, unlock, pwd_md5, acc_id, security_firm).await
+            })
+        }).map_err(|e| e.into_py_err("Unlock trade failed"))
     }
 
     /// Place an order.
-    /// sec_market: 1=HK, 2=US, 3=CN_SH, 4=CN_SZ, etc.
+    /// security: (market, code) tuple or symbol alias, which also sets the
+    /// security market; any other string is a bare code in sec_market
+    /// (1=HK, 2=US, 31=CN_SH, 32=CN_SZ, etc.)
     #[allow(clippy::too_many_arguments)]
-    #[pyo3(signature = (trd_env, acc_id, trd_market, trd_side, order_type, code, qty, price=None, sec_market=None))]
+    #[pyo3(signature = (trd_side, order_type, security, qty, price=None, sec_market=None, *, trd_env=None, acc_id=None, trd_market=None))]
     fn place_order(
         &self,
         py: Python<'_>,
-        trd_env: i32,
-        acc_id: u64,
-        trd_market: i32,
         trd_side: i32,
         order_type: i32,
-        code: String,
+        security: SecurityArg,
         qty: f64,
         price: Option<f64>,
         sec_market: Option<i32>,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
     ) -> PyResult<PyObject> {
+        let (sec_market, code) = self.resolve_order_security(security, sec_market)?;
         let client = self.get_client()?;
         let client = &*client;
+        let (trd_env, acc_id, trd_market) = self.resolve_account(trd_env, acc_id, trd_market)?;
+        let (qty, price) = if self.auto_round_orders.load(std::sync::atomic::Ordering::Relaxed) {
+            self.round_order(py, client, sec_market, &code, trd_side, qty, price)?
+        } else {
+            (qty, price)
+        };
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
@@ -452,8 +2329,57 @@ impl PyFutuClient {
                     trd_side, order_type, code, qty, price,
                     None, sec_market, None, None, None, None, None, None, None,
                 ).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Place order failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Place order failed"))?;
+
+        let dict = pyo3::types::PyDict::new_bound(py);
+        if let Some(s2c) = response.s2c {
+            dict.set_item("order_id", s2c.order_id)?;
+            dict.set_item("order_id_ex", s2c.order_id_ex)?;
+        }
+        Ok(dict.into_any().unbind())
+    }
+
+    /// Place an order unless the same order (account, code, side, qty and
+    /// price) was placed by this client within the last minute, in which case
+    /// the earlier order_id is returned. Safe to call again after a timeout or
+    /// connection error: the retry sends the PacketID of the failed attempt,
+    /// so OpenD does not take it for a second order.
+    /// Takes the arguments of place_order().
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (trd_side, order_type, security, qty, price=None, sec_market=None, *, trd_env=None, acc_id=None, trd_market=None))]
+    fn place_order_idempotent(
+        &self,
+        py: Python<'_>,
+        trd_side: i32,
+        order_type: i32,
+        security: SecurityArg,
+        qty: f64,
+        price: Option<f64>,
+        sec_market: Option<i32>,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
+    ) -> PyResult<PyObject> {
+        let (sec_market, code) = self.resolve_order_security(security, sec_market)?;
+        let client = self.get_client()?;
+        let client = &*client;
+        let (trd_env, acc_id, trd_market) = self.resolve_account(trd_env, acc_id, trd_market)?;
+        let (qty, price) = if self.auto_round_orders.load(std::sync::atomic::Ordering::Relaxed) {
+            self.round_order(py, client, sec_market, &code, trd_side, qty, price)?
+        } else {
+            (qty, price)
+        };
+
+        let response = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::trade::order::place_order_idempotent(
+                    client, trd_env, acc_id, trd_market,
+                    trd_side, order_type, code, qty, price,
+                    None, sec_market, None, None, None, None, None, None, None,
+                ).await
+            })
+        }).map_err(|e| e.into_py_err("Place order failed"))?;
 
         let dict = pyo3::types::PyDict::new_bound(py);
         if let Some(s2c) = response.s2c {
@@ -463,22 +2389,88 @@ impl PyFutuClient {
         Ok(dict.into_any().unbind())
     }
 
+    /// Place an order, unlocking trading and retrying once if OpenD answers
+    /// that trading is locked. Unlocking needs allow_unlock=True and
+    /// credentials stored with set_unlock_credentials(); otherwise the locked
+    /// error is returned like any other.
+    /// Never raises for a rejected order: returns a dict with ok, order_id,
+    /// order_id_ex, error (None if ok), unlocked, and steps, a list of dicts
+    /// with step ("place", "unlock" or "retry") and error (None if it
+    /// succeeded) in the order taken.
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (trd_side, order_type, security, qty, price=None, sec_market=None, allow_unlock=false, *, trd_env=None, acc_id=None, trd_market=None))]
+    fn place_order_autolock(
+        &self,
+        py: Python<'_>,
+        trd_side: i32,
+        order_type: i32,
+        security: SecurityArg,
+        qty: f64,
+        price: Option<f64>,
+        sec_market: Option<i32>,
+        allow_unlock: bool,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
+    ) -> PyResult<PyObject> {
+        let (sec_market, code) = self.resolve_order_security(security, sec_market)?;
+        let client = self.get_client()?;
+        let client = &*client;
+        let (trd_env, acc_id, trd_market) = self.resolve_account(trd_env, acc_id, trd_market)?;
+        let (qty, price) = if self.auto_round_orders.load(std::sync::atomic::Ordering::Relaxed) {
+            self.round_order(py, client, sec_market, &code, trd_side, qty, price)?
+        } else {
+            (qty, price)
+        };
+        let credentials = if allow_unlock { self.unlock_credentials.lock().clone() } else { None };
+
+        let outcome = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::trade::autolock::place_order_autolock(client, acc_id, credentials.as_ref(), || {
+                    crate::trade::order::place_order(
+                        client, trd_env, acc_id, trd_market,
+                        trd_side, order_type, code.clone(), qty, price,
+                        None, sec_market, None, None, None, None, None, None, None,
+                    )
+                }).await
+            })
+        });
+
+        let dict = pyo3::types::PyDict::new_bound(py);
+        let s2c = outcome.result.as_ref().ok().and_then(|r| r.s2c.as_ref());
+        dict.set_item("ok", outcome.result.is_ok())?;
+        dict.set_item("order_id", s2c.and_then(|s| s.order_id))?;
+        dict.set_item("order_id_ex", s2c.and_then(|s| s.order_id_ex.clone()))?;
+        dict.set_item("error", outcome.result.as_ref().err().map(|e| e.to_string()))?;
+        dict.set_item("unlocked", outcome.unlocked())?;
+        let steps = pyo3::types::PyList::empty_bound(py);
+        for attempt in &outcome.steps {
+            let d = pyo3::types::PyDict::new_bound(py);
+            d.set_item("step", attempt.step.as_str())?;
+            d.set_item("error", attempt.error.as_deref())?;
+            steps.append(d)?;
+        }
+        dict.set_item("steps", steps)?;
+        Ok(dict.into_any().unbind())
+    }
+
     /// Modify an order.
     #[allow(clippy::too_many_arguments)]
-    #[pyo3(signature = (trd_env, acc_id, trd_market, order_id, modify_op, qty=None, price=None))]
+    #[pyo3(signature = (order_id, modify_op, qty=None, price=None, *, trd_env=None, acc_id=None, trd_market=None))]
     fn modify_order(
         &self,
         py: Python<'_>,
-        trd_env: i32,
-        acc_id: u64,
-        trd_market: i32,
         order_id: u64,
         modify_op: i32,
         qty: Option<f64>,
         price: Option<f64>,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
     ) -> PyResult<()> {
         let client = self.get_client()?;
         let client = &*client;
+        let (trd_env, acc_id, trd_market) = self.resolve_account(trd_env, acc_id, trd_market)?;
 
         py.allow_threads(|| {
             self.runtime.block_on(async {
@@ -486,51 +2478,168 @@ impl PyFutuClient {
                     client, trd_env, acc_id, trd_market,
                     order_id, modify_op, qty, price, None,
                 ).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Modify order failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Modify order failed"))?;
 
         Ok(())
     }
 
-    /// Get order list.
-    /// Returns list of dicts with order details.
-    fn get_order_list(
+    /// Confirm an order OpenD held back with a warning, e.g. a US order in
+    /// the pre- or post-market session. reconfirm_reason is the warning's
+    /// ReconfirmOrderReason, as given by the err_code of the place_order
+    /// error. Returns the order ID.
+    #[pyo3(signature = (order_id, reconfirm_reason, *, trd_env=None, acc_id=None, trd_market=None))]
+    fn reconfirm_order(
         &self,
         py: Python<'_>,
-        trd_env: i32,
-        acc_id: u64,
-        trd_market: i32,
-    ) -> PyResult<Vec<PyObject>> {
+        order_id: u64,
+        reconfirm_reason: i32,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
+    ) -> PyResult<u64> {
         let client = self.get_client()?;
         let client = &*client;
+        let (trd_env, acc_id, trd_market) = self.resolve_account(trd_env, acc_id, trd_market)?;
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
-                crate::trade::query::get_order_list(client, trd_env, acc_id, trd_market, None).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get order list failed: {}", e)))?;
+                crate::trade::order::reconfirm_order(
+                    client, trd_env, acc_id, trd_market, order_id, reconfirm_reason,
+                ).await
+            })
+        }).map_err(|e| e.into_py_err("Reconfirm order failed"))?;
 
-        let mut result = Vec::new();
-        if let Some(s2c) = response.s2c {
-            for order in s2c.order_list {
-                let dict = pyo3::types::PyDict::new_bound(py);
-                dict.set_item("trd_side", order.trd_side)?;
-                dict.set_item("order_type", order.order_type)?;
-                dict.set_item("order_status", order.order_status)?;
-                dict.set_item("order_id", order.order_id)?;
-                dict.set_item("order_id_ex", &order.order_id_ex)?;
-                dict.set_item("code", &order.code)?;
-                dict.set_item("name", &order.name)?;
-                dict.set_item("qty", order.qty)?;
-                dict.set_item("price", order.price)?;
-                dict.set_item("create_time", &order.create_time)?;
-                dict.set_item("update_time", &order.update_time)?;
-                dict.set_item("fill_qty", order.fill_qty)?;
-                dict.set_item("fill_avg_price", order.fill_avg_price)?;
-                dict.set_item("sec_market", order.sec_market)?;
-                dict.set_item("create_timestamp", order.create_timestamp)?;
-                dict.set_item("update_timestamp", order.update_timestamp)?;
-                dict.set_item("time_in_force", order.time_in_force)?;
+        Ok(response.s2c.map_or(order_id, |s| s.order_id))
+    }
+
+    /// Cancel the open orders of an account. Without `codes` or
+    /// `order_ids` OpenD cancels them all in one request; otherwise the
+    /// matching open orders are cancelled concurrently. `codes` takes codes,
+    /// (market, code) tuples or symbol aliases. Returns a dict with
+    /// for_all (True for the single request), cancelled (order IDs) and
+    /// failed (list of (order_id, error) tuples).
+    #[pyo3(signature = (trd_env=None, acc_id=None, trd_market=None, codes=None, order_ids=None))]
+    fn cancel_all_orders(
+        &self,
+        py: Python<'_>,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
+        codes: Option<Vec<SecurityArg>>,
+        order_ids: Option<Vec<u64>>,
+    ) -> PyResult<PyObject> {
+        let codes = codes.map(|codes| self.resolve_codes(codes));
+        let client = self.get_client()?;
+        let client = &*client;
+        let (trd_env, acc_id, trd_market) = self.resolve_account(trd_env, acc_id, trd_market)?;
+        let filter = (codes.is_some() || order_ids.is_some()).then(|| crate::generated::trd_common::TrdFilterConditions {
+            code_list: codes.unwrap_or_default(),
+            id_list: order_ids.unwrap_or_default(),
+            ..Default::default()
+        });
+
+        let result = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::trade::order::cancel_all_orders(client, trd_env, acc_id, trd_market, filter).await
+            })
+        }).map_err(|e| e.into_py_err("Cancel all orders failed"))?;
+
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("for_all", result.for_all)?;
+        dict.set_item("cancelled", result.cancelled)?;
+        let failed: Vec<(u64, String)> = result.failed.into_iter().map(|(id, e)| (id, e.to_string())).collect();
+        dict.set_item("failed", failed)?;
+        Ok(dict.into_any().unbind())
+    }
+
+    /// Recent requests OpenD rejected (non-zero retType), oldest first, as
+    /// dicts with conn_id, proto_id, serial_no, ret_type, timestamp and
+    /// gateway_log (the matching lines of OpenD's log). Empty unless
+    /// connected with `gateway_log_dir`.
+    fn gateway_rejections(&self, py: Python<'_>) -> PyResult<Vec<PyObject>> {
+        let client = self.get_client()?;
+        let Some(gateway_log) = client.gateway_log() else {
+            return Ok(Vec::new());
+        };
+        let reports = py.allow_threads(|| gateway_log.rejections());
+        reports
+            .iter()
+            .map(|report| {
+                let r = &report.rejection;
+                let dict = pyo3::types::PyDict::new_bound(py);
+                dict.set_item("conn_id", r.conn_id)?;
+                dict.set_item("proto_id", r.proto_id)?;
+                dict.set_item("serial_no", r.serial_no)?;
+                dict.set_item("ret_type", r.ret_type)?;
+                dict.set_item("timestamp", r.timestamp)?;
+                dict.set_item("gateway_log", &report.gateway_lines)?;
+                Ok(dict.into_any().unbind())
+            })
+            .collect()
+    }
+
+    /// Amendment chain of an order placed, modified or updated on this
+    /// connection, oldest first. Returns list of dicts with kind (placed,
+    /// modified, cancelled, disabled, enabled, deleted, status_changed),
+    /// timestamp, qty, price and order_status (None until OpenD reports it).
+    /// Chains of finished orders are dropped an hour after they finished.
+    fn order_history(&self, py: Python<'_>, order_id: u64) -> PyResult<Vec<PyObject>> {
+        let client = self.get_client()?;
+        client.order_history().chain(order_id).iter().map(|a| amendment_to_dict(py, a)).collect()
+    }
+
+    /// Get order list.
+    /// Returns list of dicts with order details, or `Order` objects if typed.
+    #[pyo3(signature = (trd_env=None, acc_id=None, trd_market=None, typed=false))]
+    fn get_order_list(
+        &self,
+        py: Python<'_>,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
+        typed: bool,
+    ) -> PyResult<Vec<PyObject>> {
+        let client = self.get_client()?;
+        let client = &*client;
+        let aliases = self.aliases();
+        let (trd_env, acc_id, trd_market) = self.resolve_account(trd_env, acc_id, trd_market)?;
+
+        let response = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::trade::query::get_order_list(client, trd_env, acc_id, trd_market, None).await
+            })
+        }).map_err(|e| e.into_py_err("Get order list failed"))?;
+
+        let mut result = Vec::new();
+        if let Some(s2c) = response.s2c {
+            let names = self.names();
+            let mut checkpoint = GilCheckpoint::new(py);
+            for order in s2c.order_list {
+                checkpoint.tick()?;
+                if typed {
+                    result.push(Py::new(py, Order::from_proto(&names, &aliases, &order))?.into_any());
+                    continue;
+                }
+                let dict = pyo3::types::PyDict::new_bound(py);
+                dict.set_item("trd_side", order.trd_side)?;
+                dict.set_item("order_type", order.order_type)?;
+                dict.set_item("order_status", order.order_status)?;
+                dict.set_item("order_id", order.order_id)?;
+                dict.set_item("order_id_ex", &order.order_id_ex)?;
+                dict.set_item("code", &order.code)?;
+                dict.set_item("alias", aliases.alias_trd(order.sec_market, &order.code))?;
+                dict.set_item("name", names.localize_trd(order.sec_market, &order.code, &order.name))?;
+                dict.set_item("qty", order.qty)?;
+                dict.set_item("price", order.price)?;
+                dict.set_item("create_time", &order.create_time)?;
+                dict.set_item("update_time", &order.update_time)?;
+                dict.set_item("fill_qty", order.fill_qty)?;
+                dict.set_item("fill_avg_price", order.fill_avg_price)?;
+                dict.set_item("sec_market", order.sec_market)?;
+                dict.set_item("create_timestamp", order.create_timestamp)?;
+                dict.set_item("update_timestamp", order.update_timestamp)?;
+                dict.set_item("time_in_force", order.time_in_force)?;
                 dict.set_item("remark", &order.remark)?;
                 dict.set_item("last_err_msg", &order.last_err_msg)?;
                 result.push(dict.into_any().unbind());
@@ -541,72 +2650,76 @@ impl PyFutuClient {
 
     /// Get order fill list.
     /// Returns list of dicts with fill details.
+    #[pyo3(signature = (trd_env=None, acc_id=None, trd_market=None))]
     fn get_order_fill_list(
         &self,
         py: Python<'_>,
-        trd_env: i32,
-        acc_id: u64,
-        trd_market: i32,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
     ) -> PyResult<Vec<PyObject>> {
         let client = self.get_client()?;
         let client = &*client;
+        let aliases = self.aliases();
+        let (trd_env, acc_id, trd_market) = self.resolve_account(trd_env, acc_id, trd_market)?;
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::trade::query::get_order_fill_list(client, trd_env, acc_id, trd_market, None).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get order fill list failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get order fill list failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
-            for fill in s2c.order_fill_list {
-                let dict = pyo3::types::PyDict::new_bound(py);
-                dict.set_item("trd_side", fill.trd_side)?;
-                dict.set_item("fill_id", fill.fill_id)?;
-                dict.set_item("fill_id_ex", &fill.fill_id_ex)?;
-                dict.set_item("order_id", fill.order_id)?;
-                dict.set_item("order_id_ex", fill.order_id_ex.as_deref())?;
-                dict.set_item("code", &fill.code)?;
-                dict.set_item("name", &fill.name)?;
-                dict.set_item("qty", fill.qty)?;
-                dict.set_item("price", fill.price)?;
-                dict.set_item("create_time", &fill.create_time)?;
-                dict.set_item("create_timestamp", fill.create_timestamp)?;
-                dict.set_item("update_timestamp", fill.update_timestamp)?;
-                dict.set_item("sec_market", fill.sec_market)?;
-                dict.set_item("status", fill.status)?;
-                result.push(dict.into_any().unbind());
+            let names = self.names();
+            let mut checkpoint = GilCheckpoint::new(py);
+            for fill in &s2c.order_fill_list {
+                checkpoint.tick()?;
+                result.push(fill_to_dict(py, &names, &aliases, fill)?);
             }
         }
         Ok(result)
     }
 
     /// Get position list.
-    /// Returns list of dicts with position details.
+    /// Returns list of dicts with position details, or `Position` objects
+    /// if typed.
+    #[pyo3(signature = (trd_env=None, acc_id=None, trd_market=None, typed=false))]
     fn get_position_list(
         &self,
         py: Python<'_>,
-        trd_env: i32,
-        acc_id: u64,
-        trd_market: i32,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
+        typed: bool,
     ) -> PyResult<Vec<PyObject>> {
         let client = self.get_client()?;
         let client = &*client;
+        let aliases = self.aliases();
+        let (trd_env, acc_id, trd_market) = self.resolve_account(trd_env, acc_id, trd_market)?;
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::trade::query::get_position_list(client, trd_env, acc_id, trd_market, None).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get position list failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get position list failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
+            let names = self.names();
+            let mut checkpoint = GilCheckpoint::new(py);
             for pos in s2c.position_list {
+                checkpoint.tick()?;
+                if typed {
+                    result.push(Py::new(py, Position::from_proto(&names, &aliases, &pos))?.into_any());
+                    continue;
+                }
                 let dict = pyo3::types::PyDict::new_bound(py);
                 dict.set_item("position_id", pos.position_id)?;
                 dict.set_item("position_side", pos.position_side)?;
                 dict.set_item("code", &pos.code)?;
-                dict.set_item("name", &pos.name)?;
+                dict.set_item("alias", aliases.alias_trd(pos.sec_market, &pos.code))?;
+                dict.set_item("name", names.localize_trd(pos.sec_market, &pos.code, &pos.name))?;
                 dict.set_item("qty", pos.qty)?;
                 dict.set_item("can_sell_qty", pos.can_sell_qty)?;
                 dict.set_item("price", pos.price)?;
@@ -621,187 +2734,1714 @@ impl PyFutuClient {
                 result.push(dict.into_any().unbind());
             }
         }
-        Ok(result)
+        Ok(result)
+    }
+
+    /// Follow the positions of an account from fill and quote pushes,
+    /// reconciling with `get_position_list()` every `interval_secs`; read
+    /// them with `get_live_portfolio()`. With `subscribe_quotes`, basic
+    /// quotes of held securities are subscribed so they are marked to
+    /// market. Replaces a portfolio already followed.
+    #[pyo3(signature = (trd_env=None, acc_id=None, trd_market=None, interval_secs=30.0, subscribe_quotes=true))]
+    fn start_live_portfolio(
+        &self,
+        py: Python<'_>,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
+        interval_secs: f64,
+        subscribe_quotes: bool,
+    ) -> PyResult<()> {
+        let client = self.get_client()?;
+        let account = self.resolve_account(trd_env, acc_id, trd_market)?;
+        let reconcile_interval = std::time::Duration::try_from_secs_f64(interval_secs)
+            .ok()
+            .filter(|d| !d.is_zero())
+            .ok_or_else(|| PyValueError::new_err("interval_secs must be a positive number of seconds"))?;
+        let options = LivePortfolioOptions { reconcile_interval, subscribe_quotes };
+
+        py.allow_threads(|| self.runtime.block_on(self.tasks.abort("live_portfolio")));
+        let portfolio = Arc::new(LivePortfolio::new(account));
+        *self.live_portfolio.lock() = Some(Arc::clone(&portfolio));
+        let policy = RestartPolicy::OnPanic {
+            max_restarts: 3,
+            backoff: std::time::Duration::from_secs(1),
+        };
+        let _guard = self.runtime.enter();
+        self.tasks.spawn("live_portfolio", policy, move || {
+            crate::trade::portfolio::run_live_portfolio(Arc::clone(&client), Arc::clone(&portfolio), options.clone())
+        });
+        Ok(())
+    }
+
+    /// Open positions of `start_live_portfolio()`, marked to the latest
+    /// pushed price, without a request to OpenD. Returns a list of dicts
+    /// with code, alias, name, sec_market, qty (negative when short),
+    /// cost_price, last_price, price_time, market_val, unrealized_pl,
+    /// realized_pl, currency and reconciled_at (time of the latest
+    /// position list poll, None before the first).
+    fn get_live_portfolio(&self, py: Python<'_>) -> PyResult<Vec<PyObject>> {
+        let portfolio = self.live_portfolio.lock().as_ref().cloned()
+            .ok_or_else(|| pyo3::exceptions::PyRuntimeError::new_err("Live portfolio not started"))?;
+        let names = self.names();
+        let aliases = self.aliases();
+        let reconciled_at = portfolio.reconciled_at();
+        portfolio
+            .positions()
+            .iter()
+            .map(|position| {
+                let dict = live_position_to_dict(py, &names, &aliases, position)?;
+                dict.set_item("reconciled_at", reconciled_at)?;
+                Ok(dict.into_any().unbind())
+            })
+            .collect()
+    }
+
+    /// Get account funds.
+    /// Returns a dict with fund details.
+    #[pyo3(signature = (trd_env=None, acc_id=None, trd_market=None, currency=None))]
+    fn get_funds(
+        &self,
+        py: Python<'_>,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
+        currency: Option<i32>,
+    ) -> PyResult<PyObject> {
+        let client = self.get_client()?;
+        let client = &*client;
+        let (trd_env, acc_id, trd_market) = self.resolve_account(trd_env, acc_id, trd_market)?;
+
+        let response = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::trade::query::get_funds(client, trd_env, acc_id, trd_market, currency).await
+            })
+        }).map_err(|e| e.into_py_err("Get funds failed"))?;
+
+        let dict = pyo3::types::PyDict::new_bound(py);
+        if let Some(s2c) = response.s2c {
+            if let Some(funds) = s2c.funds {
+                dict.set_item("power", funds.power)?;
+                dict.set_item("total_assets", funds.total_assets)?;
+                dict.set_item("cash", funds.cash)?;
+                dict.set_item("market_val", funds.market_val)?;
+                dict.set_item("frozen_cash", funds.frozen_cash)?;
+                dict.set_item("debt_cash", funds.debt_cash)?;
+                dict.set_item("avl_withdrawal_cash", funds.avl_withdrawal_cash)?;
+                dict.set_item("currency", funds.currency)?;
+                dict.set_item("available_funds", funds.available_funds)?;
+                dict.set_item("unrealized_pl", funds.unrealized_pl)?;
+                dict.set_item("realized_pl", funds.realized_pl)?;
+                dict.set_item("risk_level", funds.risk_level)?;
+                dict.set_item("initial_margin", funds.initial_margin)?;
+                dict.set_item("maintenance_margin", funds.maintenance_margin)?;
+                dict.set_item("max_withdrawal", funds.max_withdrawal)?;
+            }
+        }
+        Ok(dict.into_any().unbind())
+    }
+
+    /// Get security snapshot.
+    /// securities: list of (market, code) tuples or symbol aliases
+    /// Returns list of dicts with snapshot data. The data of the security's
+    /// type is a dict under equity_ex_data (PE, PB, market cap, shares),
+    /// warrant_ex_data, option_ex_data (greeks, open interest),
+    /// index_ex_data, plate_ex_data, future_ex_data or trust_ex_data; the
+    /// keys of other types are absent.
+    /// fields: names of the fields to include (market and code are always
+    /// included); None includes all. Unknown names raise ValueError.
+    #[pyo3(signature = (securities, fields=None))]
+    fn get_security_snapshot(
+        &self,
+        py: Python<'_>,
+        securities: Vec<SecurityArg>,
+        fields: Option<Vec<String>>,
+    ) -> PyResult<Vec<PyObject>> {
+        let securities = self.resolve_securities(securities)?;
+        let filter = FieldFilter::from_py(fields, &SNAPSHOT_FIELDS)?;
+        let client = self.get_client()?;
+        let client = &*client;
+        let names = self.names();
+        let aliases = self.aliases();
+
+        let response = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::quote::snapshot::get_security_snapshot(client, securities).await
+            })
+        }).map_err(|e| e.into_py_err("Get snapshot failed"))?;
+
+        let mut result = Vec::new();
+        if let Some(s2c) = response.s2c {
+            let mut checkpoint = GilCheckpoint::new(py);
+            for snapshot in &s2c.snapshot_list {
+                checkpoint.tick()?;
+                result.push(snapshot_to_dict(py, &filter, &names, &aliases, snapshot)?);
+            }
+        }
+        Ok(result)
+    }
+
+    /// Snapshots of any number of securities, in input order, requested 400
+    /// at a time with up to `parallelism` requests in flight. Unlike
+    /// `get_security_snapshot_bulk()`, any failed request fails the call.
+    /// Returns list of dicts as `get_security_snapshot()`.
+    #[pyo3(signature = (securities, fields=None, parallelism=4))]
+    fn get_security_snapshot_all(
+        &self,
+        py: Python<'_>,
+        securities: Vec<SecurityArg>,
+        fields: Option<Vec<String>>,
+        parallelism: usize,
+    ) -> PyResult<Vec<PyObject>> {
+        let securities = self.resolve_securities(securities)?;
+        let filter = FieldFilter::from_py(fields, &SNAPSHOT_FIELDS)?;
+        let client = self.get_client()?;
+        let client = &*client;
+        let names = self.names();
+        let aliases = self.aliases();
+
+        let snapshots = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::quote::snapshot::get_security_snapshot_all(client, securities, parallelism).await
+            })
+        }).map_err(|e| e.into_py_err("Get snapshot failed"))?;
+
+        let mut result = Vec::with_capacity(snapshots.len());
+        let mut checkpoint = GilCheckpoint::new(py);
+        for snapshot in &snapshots {
+            checkpoint.tick()?;
+            result.push(snapshot_to_dict(py, &filter, &names, &aliases, snapshot)?);
+        }
+        Ok(result)
+    }
+
+    /// Snapshots of any number of securities, requested chunk_size (at
+    /// most 400) at a time. A failed chunk does not fail the call.
+    /// Returns a dict with `snapshots` (in the `get_security_snapshot()`
+    /// layout), `errors` (dicts of the failed chunks' securities and error)
+    /// and `resume` (the failed securities, to pass back to retry them).
+    #[pyo3(signature = (securities, fields=None, chunk_size=400))]
+    fn get_security_snapshot_bulk(
+        &self,
+        py: Python<'_>,
+        securities: Vec<SecurityArg>,
+        fields: Option<Vec<String>>,
+        chunk_size: usize,
+    ) -> PyResult<PyObject> {
+        let securities = self.resolve_securities(securities)?;
+        let filter = FieldFilter::from_py(fields, &SNAPSHOT_FIELDS)?;
+        let client = self.get_client()?;
+        let client = &*client;
+        let names = self.names();
+        let aliases = self.aliases();
+
+        let bulk = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::quote::snapshot::get_security_snapshot_bulk(client, securities, chunk_size).await
+            })
+        });
+
+        let mut snapshots = Vec::with_capacity(bulk.snapshots.len());
+        let mut checkpoint = GilCheckpoint::new(py);
+        for snapshot in &bulk.snapshots {
+            checkpoint.tick()?;
+            snapshots.push(snapshot_to_dict(py, &filter, &names, &aliases, snapshot)?);
+        }
+        let mut errors = Vec::with_capacity(bulk.errors.len());
+        for e in &bulk.errors {
+            let d = pyo3::types::PyDict::new_bound(py);
+            d.set_item("securities", &e.securities)?;
+            d.set_item("error", &e.error)?;
+            errors.push(d.into_any().unbind());
+        }
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("snapshots", snapshots)?;
+        dict.set_item("errors", errors)?;
+        dict.set_item("resume", bulk.resume())?;
+        Ok(dict.into_any().unbind())
+    }
+
+    /// Subscribe to trade account push notifications.
+    /// acc_ids: list of account IDs to subscribe
+    /// replace: subscribe exactly acc_ids instead of adding them to the
+    /// accounts already subscribed
+    /// Returns the accounts subscribed afterwards.
+    #[pyo3(signature = (acc_ids, replace=false))]
+    fn sub_acc_push(
+        &self,
+        py: Python<'_>,
+        acc_ids: Vec<u64>,
+        replace: bool,
+    ) -> PyResult<Vec<u64>> {
+        let client = self.get_client()?;
+        let client = &*client;
+        let mode = if replace {
+            crate::trade::push::AccPushMode::Replace
+        } else {
+            crate::trade::push::AccPushMode::Add
+        };
+
+        py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::trade::push::sub_acc_push_with_mode(client, acc_ids, mode).await
+            })
+        }).map_err(|e| e.into_py_err("Sub acc push failed"))
+    }
+
+    /// Stop trade account push notifications of the given account IDs.
+    /// Returns the accounts still subscribed.
+    fn unsub_acc_push(
+        &self,
+        py: Python<'_>,
+        acc_ids: Vec<u64>,
+    ) -> PyResult<Vec<u64>> {
+        let client = self.get_client()?;
+        let client = &*client;
+
+        py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::trade::push::unsub_acc_push(client, acc_ids).await
+            })
+        }).map_err(|e| e.into_py_err("Unsub acc push failed"))
+    }
+
+    /// Subscribe the recorded trade push accounts again.
+    /// Returns them.
+    fn refresh_acc_push(&self, py: Python<'_>) -> PyResult<Vec<u64>> {
+        let client = self.get_client()?;
+        let client = &*client;
+
+        py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::trade::push::refresh_acc_push(client).await
+            })
+        }).map_err(|e| e.into_py_err("Refresh acc push failed"))
+    }
+
+    /// Accounts currently subscribed to trade pushes, ascending.
+    fn get_acc_push_accounts(&self) -> PyResult<Vec<u64>> {
+        Ok(self.get_client()?.acc_push().accounts())
+    }
+
+    /// Set the default trade account used when trd_env, acc_id or trd_market
+    /// is omitted (or None) in trade methods. Per-call values still override.
+    fn set_default_account(&self, trd_env: i32, acc_id: u64, trd_market: i32) {
+        *self.default_account.lock() = Some((trd_env, acc_id, trd_market));
+    }
+
+    /// Get the default trade account as (trd_env, acc_id, trd_market), or None.
+    fn get_default_account(&self) -> Option<(i32, u64, i32)> {
+        *self.default_account.lock()
+    }
+
+    /// Set the preferred language for security names, or None for the names
+    /// returned by OpenD. OpenD has no per-connection locale, so names are
+    /// translated locally from tables added with `add_security_names`.
+    #[pyo3(signature = (language=None))]
+    fn set_language(&self, language: Option<String>) {
+        let mut names = self.names.lock();
+        let mut table = (**names).clone();
+        table.set_language(language);
+        *names = Arc::new(table);
+    }
+
+    /// Get the preferred language for security names, or None.
+    fn get_language(&self) -> Option<String> {
+        self.names().language().map(str::to_string)
+    }
+
+    /// Add security names for `language` as (market, code, name) tuples.
+    /// Returns the number of names known for the language.
+    fn add_security_names(&self, language: String, names: Vec<(i32, String, String)>) -> usize {
+        let mut guard = self.names.lock();
+        let mut table = (**guard).clone();
+        table.add_names(&language, names);
+        let count = table.len(&language);
+        *guard = Arc::new(table);
+        count
+    }
+
+    /// Add broker seats as (id, name, group) tuples; a group of None groups
+    /// the seat by its name. Returns the number of known seats.
+    fn add_brokers(&self, brokers: Vec<(i64, String, Option<String>)>) -> usize {
+        let mut table = self.brokers.lock();
+        table.add(brokers);
+        table.len()
+    }
+
+    /// Load a broker reference table from a CSV file of `id,name[,group]`
+    /// lines. Returns the number of known seats.
+    fn load_broker_table(&self, path: String) -> PyResult<usize> {
+        let text = std::fs::read_to_string(&path)
+            .map_err(|e| PyValueError::new_err(format!("Cannot read {}: {}", path, e)))?;
+        let entries = crate::quote::brokers::parse_broker_csv(&text)
+            .map_err(|e| PyValueError::new_err(format!("{}: {}", path, e)))?;
+        Ok(self.add_brokers(entries))
+    }
+
+    /// Name and group of a broker seat as a dict with "id", "name" and
+    /// "group", or None if the seat is unknown.
+    fn broker_info(&self, py: Python<'_>, broker_id: i64) -> PyResult<Option<PyObject>> {
+        let table = self.brokers.lock();
+        let Some(info) = table.get(broker_id) else {
+            return Ok(None);
+        };
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("id", broker_id)?;
+        dict.set_item("name", &info.name)?;
+        dict.set_item("group", info.group())?;
+        Ok(Some(dict.into_any().unbind()))
+    }
+
+    /// IDs of the known seats of broker group `group`, ascending.
+    fn broker_seats(&self, group: String) -> Vec<i64> {
+        self.brokers.lock().seats(&group)
+    }
+
+    /// Register symbol aliases as (alias, market, code) tuples. Aliases are
+    /// accepted wherever a securities list is taken, and outputs carrying a
+    /// market and code (pushes included) get an "alias" key (None if unmapped).
+    /// Re-registering an alias or a security replaces its old mapping.
+    /// Returns the number of registered aliases.
+    fn add_symbol_aliases(&self, aliases: Vec<(String, i32, String)>) -> usize {
+        let mut guard = self.aliases.lock();
+        let mut map = (**guard).clone();
+        for (alias, market, code) in aliases {
+            map.insert(alias, market, code);
+        }
+        let count = map.len();
+        *guard = Arc::new(map);
+        count
+    }
+
+    /// Remove a symbol alias. Returns False if it was unknown.
+    fn remove_symbol_alias(&self, alias: &str) -> bool {
+        let mut guard = self.aliases.lock();
+        let mut map = (**guard).clone();
+        let removed = map.remove(alias);
+        *guard = Arc::new(map);
+        removed
+    }
+
+    /// (market, code) of a symbol alias, or None.
+    fn resolve_symbol(&self, alias: &str) -> Option<(i32, String)> {
+        self.aliases().resolve(alias).map(|(market, code)| (market, code.to_string()))
+    }
+
+    /// Alias registered for (market, code), or None.
+    fn symbol_alias(&self, market: i32, code: &str) -> Option<String> {
+        self.aliases().alias(market, code).map(str::to_string)
+    }
+
+    /// Clear the default trade account.
+    fn clear_default_account(&self) {
+        *self.default_account.lock() = None;
+    }
+
+    /// Check if the client is connected to Futu OpenD.
+    fn is_connected(&self) -> bool {
+        self.client.lock().is_some()
+    }
+
+    /// Get the recent message trace (oldest first).
+    /// Returns list of dicts with direction, proto_id, serial_no, body_len,
+    /// timestamp and ret_type.
+    fn get_message_trace(&self, py: Python<'_>) -> PyResult<Vec<PyObject>> {
+        let client = self.get_client()?;
+
+        let mut result = Vec::new();
+        for entry in client.trace_snapshot() {
+            let dict = pyo3::types::PyDict::new_bound(py);
+            dict.set_item("direction", entry.direction.as_str())?;
+            dict.set_item("proto_id", entry.proto_id)?;
+            dict.set_item("serial_no", entry.serial_no)?;
+            dict.set_item("body_len", entry.body_len)?;
+            dict.set_item("timestamp", entry.timestamp)?;
+            dict.set_item("ret_type", entry.ret_type)?;
+            result.push(dict.into_any().unbind());
+        }
+        Ok(result)
+    }
+
+    /// Start capturing every packet to `path` (see the `futu_capture` tool).
+    /// include_wire: also record the encrypted form of each body.
+    /// Credential-bearing bodies (InitConnect, UnlockTrade) are never written.
+    #[pyo3(signature = (path, include_wire=true))]
+    fn start_capture(&self, path: std::path::PathBuf, include_wire: bool) -> PyResult<()> {
+        let client = self.get_client()?;
+        client
+            .connection()
+            .start_capture(&path, include_wire)
+            .map_err(|e| PyRuntimeError::new_err(format!("start_capture failed: {}", e)))
+    }
+
+    /// Stop the running packet capture. Returns the number of packets
+    /// written, or None if no capture was running.
+    fn stop_capture(&self) -> PyResult<Option<u64>> {
+        let client = self.get_client()?;
+        Ok(client.connection().stop_capture())
+    }
+
+    /// Health report of background tasks: the client's keepalive and recv
+    /// loops (when connected) followed by push forwarders and pollers.
+    /// Returns list of dicts with name, state, restarts, last_error and started_at.
+    fn health(&self, py: Python<'_>) -> PyResult<Vec<PyObject>> {
+        let mut tasks = match self.client.lock().as_ref() {
+            Some(client) => client.health(),
+            None => Vec::new(),
+        };
+        tasks.extend(self.tasks.health());
+
+        let mut result = Vec::new();
+        for task in tasks {
+            let dict = pyo3::types::PyDict::new_bound(py);
+            dict.set_item("name", &task.name)?;
+            dict.set_item("state", task.state.as_str())?;
+            dict.set_item("restarts", task.restarts)?;
+            dict.set_item("last_error", &task.last_error)?;
+            dict.set_item("started_at", task.started_at)?;
+            result.push(dict.into_any().unbind());
+        }
+        Ok(result)
+    }
+
+    /// Connection-level counters as a dict: endpoint, uptime_secs,
+    /// total_requests, avg_latency_ms, push_rate (pushes per second since
+    /// connecting), bytes_sent, bytes_received, reconnects, key_rotations,
+    /// pending_requests, requests ({proto_id: {count, failures, throttled,
+    /// retries, avg_latency_ms}}) and pushes ({proto_id: {received, dropped,
+    /// per_sec}}). Latencies are None before any response.
+    fn get_stats(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let stats = self.get_client()?.stats();
+        let ms = |latency: Option<std::time::Duration>| latency.map(|d| d.as_secs_f64() * 1000.0);
+
+        let requests = pyo3::types::PyDict::new_bound(py);
+        for (proto_id, request) in &stats.requests {
+            let dict = pyo3::types::PyDict::new_bound(py);
+            dict.set_item("count", request.count)?;
+            dict.set_item("failures", request.failures)?;
+            dict.set_item("throttled", request.throttled)?;
+            dict.set_item("retries", request.retries)?;
+            dict.set_item("avg_latency_ms", ms(request.avg_latency))?;
+            requests.set_item(proto_id, dict)?;
+        }
+        let pushes = pyo3::types::PyDict::new_bound(py);
+        for (proto_id, push) in &stats.pushes {
+            let dict = pyo3::types::PyDict::new_bound(py);
+            dict.set_item("received", push.received)?;
+            dict.set_item("dropped", push.dropped)?;
+            dict.set_item("per_sec", push.per_sec)?;
+            pushes.set_item(proto_id, dict)?;
+        }
+
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("endpoint", &stats.endpoint)?;
+        dict.set_item("uptime_secs", stats.uptime.as_secs_f64())?;
+        dict.set_item("total_requests", stats.total_requests())?;
+        dict.set_item("avg_latency_ms", ms(stats.avg_latency()))?;
+        dict.set_item("push_rate", stats.push_rate())?;
+        dict.set_item("bytes_sent", stats.bytes_sent)?;
+        dict.set_item("bytes_received", stats.bytes_received)?;
+        dict.set_item("reconnects", stats.reconnects)?;
+        dict.set_item("key_rotations", stats.key_rotations)?;
+        dict.set_item("pending_requests", stats.pending_requests)?;
+        dict.set_item("requests", requests)?;
+        dict.set_item("pushes", pushes)?;
+        Ok(dict.into_any().unbind())
+    }
+
+    /// Keepalive health as a dict: last_sent, last_reply and last_received
+    /// (Unix timestamps, None before the first), heartbeats_sent, replies,
+    /// missed (heartbeats since the last reply), stale, stale_count and
+    /// rtt_last_ms, rtt_min_ms, rtt_max_ms, rtt_avg_ms (None before the
+    /// first reply).
+    fn connection_health(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let health = self.get_client()?.connection_health();
+        let ms = |rtt: Option<std::time::Duration>| rtt.map(|d| d.as_secs_f64() * 1000.0);
+
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("last_sent", health.last_sent)?;
+        dict.set_item("last_reply", health.last_reply)?;
+        dict.set_item("last_received", health.last_received)?;
+        dict.set_item("heartbeats_sent", health.heartbeats_sent)?;
+        dict.set_item("replies", health.replies)?;
+        dict.set_item("missed", health.missed)?;
+        dict.set_item("stale", health.stale)?;
+        dict.set_item("stale_count", health.stale_count)?;
+        dict.set_item("rtt_last_ms", ms(health.rtt_last))?;
+        dict.set_item("rtt_min_ms", ms(health.rtt_min))?;
+        dict.set_item("rtt_max_ms", ms(health.rtt_max))?;
+        dict.set_item("rtt_avg_ms", ms(health.rtt_avg))?;
+        Ok(dict.into_any().unbind())
+    }
+
+    /// Client metrics in the Prometheus text format: request latencies, push
+    /// counts and drops, quote pushes per security, pending requests, task
+    /// restarts, session preemption, connects and push channel backlogs.
+    fn metrics(&self, py: Python<'_>) -> String {
+        let source = self.metrics_source();
+        py.allow_threads(|| self.runtime.block_on(source.render()))
+    }
+
+    /// Serve `metrics()` on http://host:port/metrics for Prometheus to
+    /// scrape, across reconnects, until `stop_metrics_server()` or
+    /// `disconnect()`. Starting it again replaces the running server. Port 0
+    /// picks a free port. Returns the bound port. Only available when built
+    /// with the `server` feature.
+    #[cfg(feature = "server")]
+    #[pyo3(signature = (host="127.0.0.1", port=9464))]
+    fn start_metrics_server(&self, py: Python<'_>, host: &str, port: u16) -> PyResult<u16> {
+        let listener = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                self.tasks.abort("metrics_server").await;
+                tokio::net::TcpListener::bind((host, port)).await
+            })
+        }).map_err(|e| PyRuntimeError::new_err(format!("Failed to bind metrics server: {}", e)))?;
+        let bound = listener.local_addr()
+            .map_err(|e| PyRuntimeError::new_err(e.to_string()))?
+            .port();
+
+        let source = self.metrics_source();
+        let render = move || {
+            let source = source.clone();
+            async move { source.render().await }
+        };
+        let _guard = self.runtime.enter();
+        self.tasks.spawn_once("metrics_server", async move {
+            let read_timeout = crate::client::metrics::METRICS_READ_TIMEOUT;
+            if let Err(e) = crate::client::metrics::serve_metrics(listener, read_timeout, render).await {
+                tracing::error!("Metrics server stopped: {}", e);
+            }
+        });
+        Ok(bound)
+    }
+
+    /// Stop the server started by `start_metrics_server()`, closing the
+    /// port. Returns False if none was running.
+    #[cfg(feature = "server")]
+    fn stop_metrics_server(&self, py: Python<'_>) -> bool {
+        py.allow_threads(|| self.runtime.block_on(self.tasks.abort("metrics_server")))
+    }
+
+    /// Start receiving push notifications for the given proto_ids.
+    /// Each call creates a **new** channel pair and returns its index.
+    /// Data and execution clients should each call this once and store
+    /// their own `channel_id` for use with `poll_push()`.
+    /// decode_on_worker: parse pushes on the runtime as they arrive, so
+    /// polling only builds the Python objects. Keeps poll latency flat
+    /// under bursts at the cost of decoding messages that are never polled.
+    /// include_detail: keep the per-order "details" of order book levels
+    /// (order_id, volume) that books subscribed with order_book_detail
+    /// carry; without it they are dropped before reaching the queue.
+    #[pyo3(signature = (proto_ids, decode_on_worker=false, include_detail=false))]
+    fn start_push(
+        &self,
+        py: Python<'_>,
+        proto_ids: Vec<u32>,
+        decode_on_worker: bool,
+        include_detail: bool,
+    ) -> PyResult<usize> {
+        let client = self.get_client()?;
+
+        // Always create a new channel pair for this caller
+        let (tx, rx) = crossbeam_channel::unbounded::<PushMessage>();
+        let channel_id = {
+            let mut channels = self.push_channels.lock();
+            let id = channels.len();
+            channels.push(PushChannel::new(tx, rx, decode_on_worker, include_detail));
+            id
+        };
+
+        self.start_forwarders(py, &client, channel_id, proto_ids)?;
+        Ok(channel_id)
+    }
+
+    /// Stop forwarding the given proto_ids to a `start_push()` channel.
+    /// Their dispatcher handlers are unregistered and forwarder tasks
+    /// aborted; messages already queued stay on the channel. Returns the
+    /// proto_ids that were stopped.
+    fn stop_push(&self, py: Python<'_>, channel_id: usize, proto_ids: Vec<u32>) -> PyResult<Vec<u32>> {
+        let client = self.get_client()?;
+        self.stop_forwarders(py, &client, channel_id, proto_ids)
+    }
+
+    /// Change what a `start_push()` channel receives without reconnecting.
+    /// proto_ids: the complete proto set; forwarders are started and
+    ///     stopped to match. None keeps the current set.
+    /// securities: only quote pushes of these securities are delivered;
+    ///     basic quote lists are trimmed to them. Trade pushes are never
+    ///     filtered. None keeps the current filter.
+    /// clear_securities: remove the security filter.
+    /// Returns a dict with the resulting `proto_ids` and `securities`
+    /// (None when unfiltered).
+    #[pyo3(signature = (channel_id, proto_ids=None, securities=None, clear_securities=false))]
+    fn update_push_filter(
+        &self,
+        py: Python<'_>,
+        channel_id: usize,
+        proto_ids: Option<Vec<u32>>,
+        securities: Option<Vec<SecurityArg>>,
+        clear_securities: bool,
+    ) -> PyResult<PyObject> {
+        if clear_securities && securities.is_some() {
+            return Err(PyValueError::new_err("securities and clear_securities are exclusive"));
+        }
+        let securities = securities.map(|s| self.resolve_securities(s)).transpose()?;
+        let client = self.get_client()?;
+        let filter = {
+            let channels = self.push_channels.lock();
+            let channel = channels
+                .get(channel_id)
+                .ok_or_else(|| PyValueError::new_err(format!("Unknown push channel: {}", channel_id)))?;
+            Arc::clone(&channel.securities)
+        };
+
+        // Filter first, so newly started forwarders never deliver unwanted quotes
+        if let Some(securities) = securities {
+            *filter.lock() = Some(Arc::new(securities.into_iter().collect()));
+        } else if clear_securities {
+            filter.lock().take();
+        }
+        if let Some(wanted) = proto_ids {
+            let wanted: BTreeSet<u32> = wanted.into_iter().collect();
+            let current = self.forwarded_protos(channel_id);
+            self.stop_forwarders(py, &client, channel_id, current.difference(&wanted).copied().collect())?;
+            self.start_forwarders(py, &client, channel_id, wanted.difference(&current).copied().collect())?;
+        }
+
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("proto_ids", self.forwarded_protos(channel_id).into_iter().collect::<Vec<_>>())?;
+        let securities = filter.lock().clone().map(|set| {
+            let mut list: Vec<(i32, String)> = set.iter().cloned().collect();
+            list.sort();
+            list
+        });
+        dict.set_item("securities", securities)?;
+        Ok(dict.into_any().unbind())
+    }
+
+    /// Start polling basic quotes for securities that are not subscribed.
+    /// Changed quotes (by update time) are delivered on a new push channel as
+    /// Qot_UpdateBasicQot (proto 3005) messages, so `poll_push()` handles them
+    /// like regular basic quote pushes. Returns the channel_id.
+    /// interval_ms: time between polling passes
+    /// chunk_size: securities per snapshot request (max 400)
+    #[pyo3(signature = (securities, interval_ms=3000, chunk_size=400))]
+    fn start_qot_poller(
+        &self,
+        securities: Vec<SecurityArg>,
+        interval_ms: u64,
+        chunk_size: usize,
+    ) -> PyResult<usize> {
+        let securities = self.resolve_securities(securities)?;
+        let client = self.get_client()?;
+
+        let (tx, rx) = crossbeam_channel::unbounded::<PushMessage>();
+        let channel_id = {
+            let mut channels = self.push_channels.lock();
+            let id = channels.len();
+            channels.push(PushChannel::new(tx.clone(), rx, false, false));
+            id
+        };
+
+        let config = crate::quote::poller::QotPollerConfig {
+            securities,
+            interval: std::time::Duration::from_millis(interval_ms),
+            chunk_size,
+            ..Default::default()
+        };
+        let _guard = self.runtime.enter();
+        let (qot_tx, mut qot_rx) = mpsc::unbounded_channel();
+        let policy = RestartPolicy::OnPanic {
+            max_restarts: 3,
+            backoff: std::time::Duration::from_secs(1),
+        };
+        self.tasks.spawn(&format!("qot_poller:{}", channel_id), policy, move || {
+            crate::quote::poller::run_basic_qot_poller(Arc::clone(&client), config.clone(), qot_tx.clone())
+        });
+        self.tasks.spawn_once(&format!("qot_poller_forwarder:{}", channel_id), async move {
+            while let Some(basic_qot_list) = qot_rx.recv().await {
+                let s2c = crate::generated::qot_update_basic_qot::S2c { basic_qot_list };
+                let payload = PushPayload::Decoded(Ok(Box::new(DecodedPush::BasicQot(s2c))));
+                let msg = (super::push_decode::PROTO_QOT_UPDATE_BASIC_QOT, payload);
+                if tx.send(msg).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(channel_id)
+    }
+
+    /// Deliver the messages of a push channel to `callback` instead of
+    /// polling them. A thread of the channel waits for messages and calls
+    /// `callback(message)` with the GIL held, one `poll_push()` dict per
+    /// call; the channel must not be polled meanwhile. Exceptions raised by
+    /// the callback are reported through `sys.unraisablehook` and do not
+    /// stop delivery. Replaces an earlier callback of the channel; a
+    /// callback of None stops delivery, leaving later messages queued.
+    /// queue_size: messages that may wait while the callback is busy; the
+    ///     oldest quote pushes beyond it are dropped. Order and fill pushes
+    ///     are never dropped. None queues without limit.
+    /// max_batch: messages delivered per GIL acquisition
+    /// batch: call `callback(messages)` once per batch with a list instead
+    #[pyo3(signature = (channel_id, callback=None, queue_size=None, max_batch=256, batch=false))]
+    fn set_push_callback(
+        &self,
+        py: Python<'_>,
+        channel_id: usize,
+        callback: Option<PyObject>,
+        queue_size: Option<usize>,
+        max_batch: usize,
+        batch: bool,
+    ) -> PyResult<()> {
+        if callback.as_ref().is_some_and(|c| !c.bind(py).is_callable()) {
+            return Err(PyValueError::new_err("callback must be callable"));
+        }
+        if queue_size == Some(0) || max_batch == 0 {
+            return Err(PyValueError::new_err("queue_size and max_batch must be positive"));
+        }
+        let Some(rx) = self.push_receiver(channel_id) else {
+            return Err(PyValueError::new_err(format!("Unknown push channel: {}", channel_id)));
+        };
+        if let Some(previous) = self.push_callbacks.lock().remove(&channel_id) {
+            py.allow_threads(|| previous.stop());
+        }
+        let Some(callback) = callback else {
+            return Ok(());
+        };
+
+        let (names, aliases, mode) = (Arc::clone(&self.names), Arc::clone(&self.aliases), self.decode_mode());
+        let convert = move |py: Python<'_>, (proto_id, payload): PushMessage| {
+            let (names, aliases) = (Arc::clone(&names.lock()), Arc::clone(&aliases.lock()));
+            push_message_to_dict(py, &names, &aliases, mode, proto_id, payload)
+        };
+        let options = CallbackOptions { queue_size, max_batch, as_list: batch };
+        let droppable = |(proto_id, _): &PushMessage| !is_trade_push(*proto_id);
+        let delivery = PushCallback::start(rx, callback, options, droppable, convert)
+            .map_err(|e| PyRuntimeError::new_err(format!("Failed to start push callback: {}", e)))?;
+        self.push_callbacks.lock().insert(channel_id, delivery);
+        Ok(())
+    }
+
+    /// Counters of the callback of a push channel as a dict with
+    /// `delivered`, `dropped` (by queue_size), `errors` (failed conversions
+    /// and raised callbacks) and `queued`, or None without a callback.
+    fn push_callback_stats(&self, py: Python<'_>, channel_id: usize) -> PyResult<Option<PyObject>> {
+        let callbacks = self.push_callbacks.lock();
+        let Some(callback) = callbacks.get(&channel_id) else {
+            return Ok(None);
+        };
+        let stats = callback.stats();
+        let ordering = std::sync::atomic::Ordering::Relaxed;
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("delivered", stats.delivered.load(ordering))?;
+        dict.set_item("dropped", stats.dropped.load(ordering))?;
+        dict.set_item("errors", stats.errors.load(ordering))?;
+        dict.set_item("queued", self.push_receiver(channel_id).map_or(0, |rx| rx.len()))?;
+        Ok(Some(dict.into_any().unbind()))
+    }
+
+    /// Poll for the next push message on a specific channel.
+    /// channel_id: index returned by `start_push()`
+    /// timeout_ms: how long to wait for a message (in milliseconds)
+    #[pyo3(signature = (channel_id, timeout_ms=100))]
+    fn poll_push(
+        &self,
+        py: Python<'_>,
+        channel_id: usize,
+        timeout_ms: u64,
+    ) -> PyResult<Option<PyObject>> {
+        let Some(rx) = self.push_receiver(channel_id) else {
+            return Ok(None);
+        };
+
+        let timeout = std::time::Duration::from_millis(timeout_ms);
+
+        match py.allow_threads(|| rx.recv_timeout(timeout)) {
+            Ok((proto_id, payload)) => {
+                push_message_to_dict(py, &self.names(), &self.aliases(), self.decode_mode(), proto_id, payload).map(Some)
+            }
+            // Timeout or channel closed
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Poll up to `max_items` push messages on a channel in one call.
+    /// Waits up to timeout_ms for the first message, then takes whatever else
+    /// is already queued without waiting. Returns a list of dicts in the
+    /// `poll_push()` layout; empty on timeout, closed or unknown channel.
+    #[pyo3(signature = (channel_id, max_items=256, timeout_ms=100))]
+    fn poll_push_batch(
+        &self,
+        py: Python<'_>,
+        channel_id: usize,
+        max_items: usize,
+        timeout_ms: u64,
+    ) -> PyResult<Vec<PyObject>> {
+        let Some(rx) = self.push_receiver(channel_id) else {
+            return Ok(Vec::new());
+        };
+        if max_items == 0 {
+            return Ok(Vec::new());
+        }
+
+        let timeout = std::time::Duration::from_millis(timeout_ms);
+
+        let messages = py.allow_threads(|| {
+            let mut messages = Vec::new();
+            if let Ok(first) = rx.recv_timeout(timeout) {
+                messages.push(first);
+                messages.extend(rx.try_iter().take(max_items - 1));
+            }
+            messages
+        });
+
+        self.push_messages_to_dicts(py, messages)
+    }
+
+    /// Take every push message currently queued on a channel without waiting.
+    /// Same dict layout as `poll_push()`.
+    fn drain_push(&self, py: Python<'_>, channel_id: usize) -> PyResult<Vec<PyObject>> {
+        let Some(rx) = self.push_receiver(channel_id) else {
+            return Ok(Vec::new());
+        };
+
+        let messages = rx.try_iter().collect();
+        self.push_messages_to_dicts(py, messages)
+    }
+
+    /// Start a bar aggregation channel. Bars of the specs added with
+    /// `add_bar_spec()` are built from ticker pushes (LAST) and order book
+    /// pushes (BID, ASK, MID); read them with `poll_bars()`. The securities
+    /// need the matching TICKER or ORDER_BOOK subscription. Returns the channel_id.
+    /// partial_bars: "skip" (default) drops bars that do not cover their whole
+    /// interval; "emit" delivers them with partial=True.
+    #[pyo3(signature = (partial_bars="skip"))]
+    fn start_bar_push(&self, partial_bars: &str) -> PyResult<usize> {
+        let policy = partial_bars.parse::<PartialBarPolicy>().map_err(PyValueError::new_err)?;
+        let client = self.get_client()?;
+
+        let aggregator = Arc::new(SyncMutex::new(BarAggregator::new(policy)));
+        let (tx, rx) = mpsc::unbounded_channel();
+        let channel_id = {
+            let mut channels = self.bar_channels.lock();
+            channels.push(BarChannel {
+                aggregator: Arc::clone(&aggregator),
+                tx: tx.clone(),
+                rx: Arc::new(Mutex::new(rx)),
+            });
+            channels.len() - 1
+        };
+
+        let restart = RestartPolicy::OnPanic {
+            max_restarts: 3,
+            backoff: std::time::Duration::from_secs(1),
+        };
+        let _guard = self.runtime.enter();
+        self.tasks.spawn(&format!("bar_aggregator:{}", channel_id), restart, move || {
+            crate::quote::bars::run_bar_aggregator(Arc::clone(&client), Arc::clone(&aggregator), tx.clone())
+        });
+
+        Ok(channel_id)
+    }
+
+    /// Build `bar_spec` bars (e.g. "1-MINUTE-LAST", "5-MINUTE-MID") for a
+    /// security on a bar channel. Supported aggregations are SECOND, MINUTE
+    /// and HOUR; price types BID, ASK, MID and LAST.
+    /// Returns False if the spec was already added.
+    fn add_bar_spec(&self, channel_id: usize, security: SecurityArg, bar_spec: &str) -> PyResult<bool> {
+        let spec = bar_spec.parse::<BarSpec>().map_err(PyValueError::new_err)?;
+        let (market, code) = self.resolve_securities(vec![security])?.remove(0);
+        let aggregator = self.bar_aggregator(channel_id)?;
+        let added = aggregator.lock().add(market, &code, spec, crate::quote::push_stats::now());
+        Ok(added)
+    }
+
+    /// Stop building `bar_spec` bars for a security. Its open bar is delivered
+    /// if the channel emits partial bars. Returns False if it was not added.
+    fn remove_bar_spec(&self, channel_id: usize, security: SecurityArg, bar_spec: &str) -> PyResult<bool> {
+        let spec = bar_spec.parse::<BarSpec>().map_err(PyValueError::new_err)?;
+        let (market, code) = self.resolve_securities(vec![security])?.remove(0);
+        let (aggregator, tx) = {
+            let channels = self.bar_channels.lock();
+            let channel = channels
+                .get(channel_id)
+                .ok_or_else(|| PyValueError::new_err(format!("Unknown bar channel: {}", channel_id)))?;
+            (Arc::clone(&channel.aggregator), channel.tx.clone())
+        };
+        let removed = aggregator.lock().remove(market, &code, spec, crate::quote::push_stats::now());
+        match removed {
+            Some(bars) => {
+                for bar in bars {
+                    let _ = tx.send(bar);
+                }
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Poll up to `max_items` bars of a bar channel, waiting up to timeout_ms
+    /// for the first. Returns a list of dicts with market, code, alias,
+    /// bar_spec, open, high, low, close, volume, tick_count, ts_open,
+    /// ts_close (the bar's event time) and partial; empty on timeout.
+    #[pyo3(signature = (channel_id, max_items=256, timeout_ms=100))]
+    fn poll_bars(
+        &self,
+        py: Python<'_>,
+        channel_id: usize,
+        max_items: usize,
+        timeout_ms: u64,
+    ) -> PyResult<Vec<PyObject>> {
+        let Some(rx) = self.bar_channels.lock().get(channel_id).map(|c| Arc::clone(&c.rx)) else {
+            return Ok(Vec::new());
+        };
+        if max_items == 0 {
+            return Ok(Vec::new());
+        }
+
+        let timeout = std::time::Duration::from_millis(timeout_ms);
+        let bars = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                let mut guard = rx.lock().await;
+                let mut bars = Vec::new();
+                if let Ok(Some(first)) = tokio::time::timeout(timeout, guard.recv()).await {
+                    bars.push(first);
+                    while bars.len() < max_items {
+                        match guard.try_recv() {
+                            Ok(bar) => bars.push(bar),
+                            Err(_) => break,
+                        }
+                    }
+                }
+                bars
+            })
+        });
+
+        let aliases = self.aliases();
+        bars.iter().map(|bar| bar_to_dict(py, &aliases, bar)).collect()
+    }
+
+    /// Watch the IPO lists of `markets`, polling every `interval_secs`.
+    /// Emits events for newly announced IPOs, pricing updates and listing
+    /// days; read them with `poll_ipo_event()`. Returns the watcher_id.
+    #[pyo3(signature = (markets, interval_secs=3600))]
+    fn watch_ipo(&self, markets: Vec<i32>, interval_secs: u64) -> PyResult<usize> {
+        let client = self.get_client()?;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let watcher_id = {
+            let mut watchers = self.ipo_watchers.lock();
+            watchers.push(Arc::new(Mutex::new(rx)));
+            watchers.len() - 1
+        };
+
+        let interval = std::time::Duration::from_secs(interval_secs.max(1));
+        let policy = RestartPolicy::OnPanic {
+            max_restarts: 3,
+            backoff: std::time::Duration::from_secs(1),
+        };
+        let _guard = self.runtime.enter();
+        self.tasks.spawn(&format!("ipo_watcher:{}", watcher_id), policy, move || {
+            crate::quote::ipo_watch::run_ipo_watcher(Arc::clone(&client), markets.clone(), interval, tx.clone())
+        });
+
+        Ok(watcher_id)
+    }
+
+    /// Poll for the next IPO event of a watcher.
+    /// Returns a dict with `event` ("announced", "pricing_updated" or
+    /// "listing_day") plus the IPO fields of `get_ipo_list()`, or None on timeout.
+    #[pyo3(signature = (watcher_id, timeout_ms=100))]
+    fn poll_ipo_event(
+        &self,
+        py: Python<'_>,
+        watcher_id: usize,
+        timeout_ms: u64,
+    ) -> PyResult<Option<PyObject>> {
+        let rx = match self.ipo_watchers.lock().get(watcher_id) {
+            Some(rx) => Arc::clone(rx),
+            None => return Ok(None),
+        };
+
+        let timeout = std::time::Duration::from_millis(timeout_ms);
+        let result = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                let mut guard = rx.lock().await;
+                tokio::time::timeout(timeout, guard.recv()).await
+            })
+        });
+
+        match result {
+            Ok(Some(event)) => {
+                let dict = ipo_to_dict(py, &self.names(), &self.aliases(), &event.ipo)?;
+                dict.set_item("event", event.kind.as_str())?;
+                Ok(Some(dict.into_any().unbind()))
+            }
+            // Channel closed or timeout
+            _ => Ok(None),
+        }
+    }
+
+    /// Re-run a saved warrant filter every `interval_secs` and emit ranking
+    /// changes; read them with `poll_warrant_event()`. `filter` takes the
+    /// `get_warrant()` arguments sort_field, ascend, owner, type_list and
+    /// issuer_list, plus max_results (ranking length watched, default 200)
+    /// and page_interval_ms (pause between page requests, default 500).
+    /// The first run reports the whole ranking as added. Returns the watcher_id.
+    #[pyo3(signature = (filter, interval_secs=60))]
+    fn watch_warrant_screen(&self, filter: &Bound<'_, pyo3::types::PyDict>, interval_secs: u64) -> PyResult<usize> {
+        let screen = warrant_screen_from_dict(filter)?;
+        let client = self.get_client()?;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let watcher_id = {
+            let mut watchers = self.warrant_watchers.lock();
+            watchers.push(Arc::new(Mutex::new(rx)));
+            watchers.len() - 1
+        };
+
+        let interval = std::time::Duration::from_secs(interval_secs.max(1));
+        let policy = RestartPolicy::OnPanic {
+            max_restarts: 3,
+            backoff: std::time::Duration::from_secs(1),
+        };
+        let _guard = self.runtime.enter();
+        self.tasks.spawn(&format!("warrant_watcher:{}", watcher_id), policy, move || {
+            crate::quote::warrant_watch::run_warrant_watcher(Arc::clone(&client), screen.clone(), interval, tx.clone())
+        });
+
+        Ok(watcher_id)
+    }
+
+    /// Poll for the next ranking change of a warrant watcher.
+    /// Returns a dict with `event` ("added", "removed" or "rank_changed"),
+    /// `rank` and `prev_rank` (1-based, None where not ranked) plus the
+    /// warrant fields of `get_warrant()`, or None on timeout.
+    #[pyo3(signature = (watcher_id, timeout_ms=100))]
+    fn poll_warrant_event(
+        &self,
+        py: Python<'_>,
+        watcher_id: usize,
+        timeout_ms: u64,
+    ) -> PyResult<Option<PyObject>> {
+        let rx = match self.warrant_watchers.lock().get(watcher_id) {
+            Some(rx) => Arc::clone(rx),
+            None => return Ok(None),
+        };
+
+        let timeout = std::time::Duration::from_millis(timeout_ms);
+        let result = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                let mut guard = rx.lock().await;
+                tokio::time::timeout(timeout, guard.recv()).await
+            })
+        });
+
+        match result {
+            Ok(Some(event)) => {
+                let dict = warrant_to_dict(py, &self.names(), &event.warrant)?;
+                dict.set_item("event", event.kind.as_str())?;
+                dict.set_item("rank", event.rank)?;
+                dict.set_item("prev_rank", event.prev_rank)?;
+                Ok(Some(dict.into_any().unbind()))
+            }
+            // Channel closed or timeout
+            _ => Ok(None),
+        }
+    }
+
+    /// Scan the securities of the instrument cache (those `instrument()` or
+    /// order rounding looked up) plus `securities` every `interval_secs`
+    /// (daily by default) for suspensions, delistings and code changes,
+    /// and update the cached instruments; read the changes with
+    /// `poll_listing_event()`. The first scan reports the securities that
+    /// are already suspended or delisted. Returns the watcher_id.
+    #[pyo3(signature = (securities=None, interval_secs=86400))]
+    fn watch_listings(&self, securities: Option<Vec<SecurityArg>>, interval_secs: u64) -> PyResult<usize> {
+        let extra = self.resolve_securities(securities.unwrap_or_default())?;
+        let client = self.get_client()?;
+        let cache = Arc::clone(&self.instruments);
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let watcher_id = {
+            let mut watchers = self.listing_watchers.lock();
+            watchers.push(Arc::new(Mutex::new(rx)));
+            watchers.len() - 1
+        };
+
+        let interval = std::time::Duration::from_secs(interval_secs.max(1));
+        let policy = RestartPolicy::OnPanic {
+            max_restarts: 3,
+            backoff: std::time::Duration::from_secs(1),
+        };
+        let _guard = self.runtime.enter();
+        self.tasks.spawn(&format!("listing_watcher:{}", watcher_id), policy, move || {
+            crate::quote::listing_watch::run_listing_watcher(
+                Arc::clone(&client), Arc::clone(&cache), extra.clone(), interval, tx.clone(),
+            )
+        });
+
+        Ok(watcher_id)
+    }
+
+    /// Poll for the next listing change of a listing watcher.
+    /// Returns a dict with `event` ("suspended", "resumed", "delisted" or
+    /// "code_changed"), market, code and alias, or None on timeout. Code
+    /// changes add type, related_market, related_code, effective_time and
+    /// end_time.
+    #[pyo3(signature = (watcher_id, timeout_ms=100))]
+    fn poll_listing_event(
+        &self,
+        py: Python<'_>,
+        watcher_id: usize,
+        timeout_ms: u64,
+    ) -> PyResult<Option<PyObject>> {
+        let rx = match self.listing_watchers.lock().get(watcher_id) {
+            Some(rx) => Arc::clone(rx),
+            None => return Ok(None),
+        };
+
+        let timeout = std::time::Duration::from_millis(timeout_ms);
+        let result = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                let mut guard = rx.lock().await;
+                tokio::time::timeout(timeout, guard.recv()).await
+            })
+        });
+
+        match result {
+            Ok(Some(event)) => {
+                let (market, code) = &event.security;
+                let dict = pyo3::types::PyDict::new_bound(py);
+                dict.set_item("event", event.kind.as_str())?;
+                dict.set_item("market", market)?;
+                dict.set_item("code", code)?;
+                dict.set_item("alias", self.aliases().alias(*market, code))?;
+                if let Some(change) = &event.code_change {
+                    dict.set_item("type", change.r#type)?;
+                    dict.set_item("related_market", change.related_security.market)?;
+                    dict.set_item("related_code", &change.related_security.code)?;
+                    dict.set_item("effective_time", change.effective_time.as_deref())?;
+                    dict.set_item("end_time", change.end_time.as_deref())?;
+                }
+                Ok(Some(dict.into_any().unbind()))
+            }
+            // Channel closed or timeout
+            _ => Ok(None),
+        }
+    }
+
+    /// Quote push statistics per security and sub type.
+    /// Returns list of dicts with market, code, sub_type, count, last_update,
+    /// tracked_since and subscribed.
+    fn push_stats(&self, py: Python<'_>) -> PyResult<Vec<PyObject>> {
+        let client = self.get_client()?;
+        let aliases = self.aliases();
+        client.push_stats().snapshot().iter().map(|s| push_stat_to_dict(py, &aliases, s)).collect()
+    }
+
+    /// Drop pushes a push channel already received, such as the latest
+    /// quote OpenD pushes again when a subscribed security is subscribed
+    /// by another profile. Applies to the current and future connections.
+    /// Off by default.
+    fn set_dedup_pushes(&self, enabled: bool) {
+        self.dedup_pushes.store(enabled, std::sync::atomic::Ordering::Relaxed);
+        if let Some(client) = self.client.lock().as_ref() {
+            client.set_dedup_pushes(enabled);
+        }
+    }
+
+    /// Extend subscriptions made with ttl_secs to expire `ttl_secs` from
+    /// now. Pairs subscribed without a TTL are left alone. Returns the
+    /// number of (security, sub type) pairs renewed.
+    fn renew_subscriptions(&self, securities: Vec<SecurityArg>, sub_types: Vec<i32>, ttl_secs: f64) -> PyResult<usize> {
+        let securities = self.resolve_securities(securities)?;
+        let ttl = std::time::Duration::try_from_secs_f64(ttl_secs)
+            .map_err(|e| PyValueError::new_err(format!("Invalid ttl_secs: {}", e)))?;
+        let client = self.get_client()?;
+        Ok(client.subscriptions().renew(&securities, &sub_types, client.clock().now() + ttl))
+    }
+
+    /// Poll for the next expired subscription. Returns a dict with market,
+    /// code, alias, sub_type and error (OpenD's reason if unsubscribing
+    /// failed, retried every second; None once unsubscribed), or None on
+    /// timeout or if nothing was subscribed with a TTL.
+    #[pyo3(signature = (timeout_ms=100))]
+    fn poll_subscription_expiry(&self, py: Python<'_>, timeout_ms: u64) -> PyResult<Option<PyObject>> {
+        let Some(rx) = self.sub_expiry.lock().as_ref().cloned() else {
+            return Ok(None);
+        };
+
+        let timeout = std::time::Duration::from_millis(timeout_ms);
+        let result = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                let mut guard = rx.lock().await;
+                tokio::time::timeout(timeout, guard.recv()).await
+            })
+        });
+
+        match result {
+            Ok(Some(event)) => {
+                let (market, code) = &event.security;
+                let dict = pyo3::types::PyDict::new_bound(py);
+                dict.set_item("market", market)?;
+                dict.set_item("code", code)?;
+                dict.set_item("alias", self.aliases().alias(*market, code))?;
+                dict.set_item("sub_type", event.sub_type)?;
+                dict.set_item("error", event.error)?;
+                Ok(Some(dict.into_any().unbind()))
+            }
+            // Channel closed or timeout
+            _ => Ok(None),
+        }
+    }
+
+    /// Current quote subscriptions made through `subscribe()`, recorded
+    /// locally without asking OpenD. Returns list of dicts with market, code,
+    /// alias, sub_types and order_book_detail.
+    fn current_subscriptions(&self, py: Python<'_>) -> PyResult<Vec<PyObject>> {
+        let client = self.get_client()?;
+        let aliases = self.aliases();
+        client.subscriptions().current_subscriptions().iter().map(|s| {
+            let dict = pyo3::types::PyDict::new_bound(py);
+            dict.set_item("market", s.security.0)?;
+            dict.set_item("code", &s.security.1)?;
+            dict.set_item("alias", aliases.alias(s.security.0, &s.security.1))?;
+            dict.set_item("sub_types", &s.sub_types)?;
+            dict.set_item("order_book_detail", s.order_book_detail)?;
+            Ok(dict.into_any().unbind())
+        }).collect()
+    }
+
+    /// Refuse `subscribe()` calls that would hold more than `quota`
+    /// (security, sub type) pairs, before sending them; None leaves the
+    /// check to OpenD. Applies to the current and future connections.
+    #[pyo3(signature = (quota=None))]
+    fn set_sub_quota(&self, quota: Option<u32>) {
+        *self.sub_quota.lock() = quota;
+        if let Some(client) = self.client.lock().as_ref() {
+            client.subscriptions().set_quota(quota);
+        }
+    }
+
+    /// Take the subscription quota of the current connection from OpenD
+    /// (Qot_GetSubInfo) and check it locally from now on. Returns the quota.
+    fn sync_sub_quota(&self, py: Python<'_>) -> PyResult<u32> {
+        let client = self.get_client()?;
+        let client = &*client;
+        py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::quote::sub_manager::sync_quota(client).await
+            })
+        }).map_err(|e| e.into_py_err("Sync sub quota failed"))
+    }
+
+    /// Subscription quota in use and left, a dict with used, quota and
+    /// remaining (None without a quota).
+    fn sub_quota(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let client = self.get_client()?;
+        let subscriptions = client.subscriptions();
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("used", subscriptions.used_quota())?;
+        dict.set_item("quota", subscriptions.quota())?;
+        dict.set_item("remaining", subscriptions.remaining_quota())?;
+        Ok(dict.into_any().unbind())
+    }
+
+    /// Pushes suppressed as duplicates since connecting, a dict keyed by
+    /// proto_id. A push suppressed for two channels counts twice.
+    fn suppressed_duplicates(&self) -> PyResult<std::collections::BTreeMap<u32, u64>> {
+        let client = self.get_client()?;
+        Ok(client.suppressed_duplicates())
+    }
+
+    /// Subscribed securities without a push for more than `threshold_secs`,
+    /// regardless of market hours. Same dict layout as `push_stats()`.
+    fn stale_securities(&self, py: Python<'_>, threshold_secs: f64) -> PyResult<Vec<PyObject>> {
+        let client = self.get_client()?;
+        let aliases = self.aliases();
+        let now = crate::quote::push_stats::now();
+        client.push_stats().stale(threshold_secs, now).iter().map(|s| push_stat_to_dict(py, &aliases, s)).collect()
+    }
+
+    /// Start checking every `check_interval_secs` for subscribed securities
+    /// that stop pushing for more than `threshold_secs` while their market is
+    /// in session. Read alarms with `poll_stale_alarm()`. Returns the monitor_id.
+    #[pyo3(signature = (threshold_secs=60.0, check_interval_secs=10.0))]
+    fn start_staleness_monitor(&self, threshold_secs: f64, check_interval_secs: f64) -> PyResult<usize> {
+        let client = self.get_client()?;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let monitor_id = {
+            let mut monitors = self.stale_monitors.lock();
+            monitors.push(Arc::new(Mutex::new(rx)));
+            monitors.len() - 1
+        };
+
+        let threshold = std::time::Duration::from_secs_f64(threshold_secs.max(0.0));
+        let check_interval = std::time::Duration::from_secs_f64(check_interval_secs.max(1.0));
+        let policy = RestartPolicy::OnPanic {
+            max_restarts: 3,
+            backoff: std::time::Duration::from_secs(1),
+        };
+        let _guard = self.runtime.enter();
+        self.tasks.spawn(&format!("staleness_monitor:{}", monitor_id), policy, move || {
+            crate::quote::push_stats::run_staleness_monitor(Arc::clone(&client), threshold, check_interval, tx.clone())
+        });
+
+        Ok(monitor_id)
+    }
+
+    /// Poll for the next staleness alarm of a monitor.
+    /// Returns a dict in the `push_stats()` layout, or None on timeout.
+    #[pyo3(signature = (monitor_id, timeout_ms=100))]
+    fn poll_stale_alarm(
+        &self,
+        py: Python<'_>,
+        monitor_id: usize,
+        timeout_ms: u64,
+    ) -> PyResult<Option<PyObject>> {
+        let rx = match self.stale_monitors.lock().get(monitor_id) {
+            Some(rx) => Arc::clone(rx),
+            None => return Ok(None),
+        };
+
+        let timeout = std::time::Duration::from_millis(timeout_ms);
+        let result = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                let mut guard = rx.lock().await;
+                tokio::time::timeout(timeout, guard.recv()).await
+            })
+        });
+
+        match result {
+            Ok(Some(stat)) => Ok(Some(push_stat_to_dict(py, &self.aliases(), &stat)?)),
+            // Channel closed or timeout
+            _ => Ok(None),
+        }
+    }
+
+    /// Refuse place_order/modify_order with SessionPreemptedError while
+    /// another login of the account has preempted the session (OpenD kicked
+    /// out, or quote rights taken over). Applies to the current and future
+    /// connections. Off by default.
+    fn set_pause_trading_on_preempt(&self, enabled: bool) {
+        self.pause_trading_on_preempt.store(enabled, std::sync::atomic::Ordering::Relaxed);
+        if let Some(client) = self.client.lock().as_ref() {
+            client.session().set_pause_trading(enabled);
+        }
+    }
+
+    /// Round place_order qty down to whole lots and price onto the
+    /// security's tick table (buys down, sells up) instead of letting OpenD
+    /// reject them. Needs sec_market to find the security. Off by default.
+    fn set_auto_round_orders(&self, enabled: bool) {
+        self.auto_round_orders.store(enabled, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// Store the trading password (MD5) place_order_autolock() unlocks with,
+    /// or clear it with None. It is kept in memory only.
+    /// security_firm: as for unlock_trade(); None detects it from the account.
+    #[pyo3(signature = (pwd_md5=None, security_firm=None))]
+    fn set_unlock_credentials(&self, pwd_md5: Option<String>, security_firm: Option<i32>) {
+        *self.unlock_credentials.lock() = pwd_md5.map(|pwd_md5| UnlockCredentials { pwd_md5, security_firm });
+    }
+
+    /// Client-side budgets for order requests, per account. place_order
+    /// draws from entry_limit; modify_order from cancel_limit, of which
+    /// cancel_reserve requests are kept for cancels (modify_op 2 or 5).
+    /// Requests over budget wait until it refills over window_secs. A limit
+    /// of None leaves those requests unthrottled. Applies to the current and
+    /// future connections. Off by default.
+    #[pyo3(signature = (entry_limit=None, cancel_limit=None, cancel_reserve=0, window_secs=30.0))]
+    fn set_order_throttle(
+        &self,
+        entry_limit: Option<u32>,
+        cancel_limit: Option<u32>,
+        cancel_reserve: u32,
+        window_secs: f64,
+    ) -> PyResult<()> {
+        let window = std::time::Duration::try_from_secs_f64(window_secs)
+            .map_err(|e| PyValueError::new_err(format!("Invalid window_secs: {}", e)))?;
+        let config = OrderThrottleConfig { entry_limit, cancel_limit, cancel_reserve, window };
+        config.validate().map_err(PyValueError::new_err)?;
+        *self.order_throttle.lock() = config.clone();
+        if let Some(client) = self.client.lock().as_ref() {
+            client.order_throttle().set_config(config);
+        }
+        Ok(())
+    }
+
+    /// Resending of requests OpenD answers as busy or over its frequency
+    /// limit. A throttled request is resent up to max_retries times when
+    /// the suggested wait is at most max_delay_secs; otherwise the error is
+    /// returned (ThrottledError from place_order/modify_order, which are
+    /// never resent). Applies to the current and future connections.
+    #[pyo3(signature = (max_retries=2, max_delay_secs=2.0))]
+    fn set_retry_policy(&self, max_retries: u32, max_delay_secs: f64) -> PyResult<()> {
+        let max_delay = std::time::Duration::try_from_secs_f64(max_delay_secs)
+            .map_err(|e| PyValueError::new_err(format!("Invalid max_delay_secs: {}", e)))?;
+        let policy = RetryPolicy { max_retries, max_delay };
+        *self.retry_policy.lock() = policy.clone();
+        if let Some(client) = self.client.lock().as_ref() {
+            client.set_retry_policy(policy);
+        }
+        Ok(())
     }
 
-    /// Get account funds.
-    /// Returns a dict with fund details.
-    #[pyo3(signature = (trd_env, acc_id, trd_market, currency=None))]
-    fn get_funds(
+    /// TCP settings of later connections, in seconds: connect_timeout per
+    /// endpoint (connect() raises FutuTimeoutError when it passes),
+    /// tcp_keepalive idle time before keep-alive probes, read_timeout after
+    /// which a silent OpenD counts as disconnected, and nodelay for
+    /// TCP_NODELAY. None disables a timeout.
+    #[pyo3(signature = (connect_timeout=Some(10.0), tcp_keepalive=None, read_timeout=None, nodelay=true))]
+    fn set_socket_options(
         &self,
-        py: Python<'_>,
-        trd_env: i32,
-        acc_id: u64,
-        trd_market: i32,
-        currency: Option<i32>,
-    ) -> PyResult<PyObject> {
-        let client = self.get_client()?;
-        let client = &*client;
+        connect_timeout: Option<f64>,
+        tcp_keepalive: Option<f64>,
+        read_timeout: Option<f64>,
+        nodelay: bool,
+    ) -> PyResult<()> {
+        let secs = |name: &str, value: Option<f64>| {
+            value
+                .map(std::time::Duration::try_from_secs_f64)
+                .transpose()
+                .map_err(|e| PyValueError::new_err(format!("Invalid {}: {}", name, e)))
+        };
+        *self.socket_options.lock() = SocketOptions {
+            connect_timeout: secs("connect_timeout", connect_timeout)?,
+            tcp_keepalive: secs("tcp_keepalive", tcp_keepalive)?,
+            read_timeout: secs("read_timeout", read_timeout)?,
+            nodelay,
+        };
+        Ok(())
+    }
 
-        let response = py.allow_threads(|| {
-            self.runtime.block_on(async {
-                crate::trade::query::get_funds(client, trd_env, acc_id, trd_market, currency).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get funds failed: {}", e)))?;
+    /// Log a warning, with proto_id and serial_no, for every request still
+    /// unanswered after `secs` seconds, and another when it is answered.
+    /// None turns the warnings off. Applies to the current and future
+    /// connections. Off by default.
+    #[pyo3(signature = (secs=None))]
+    fn set_slow_request_threshold(&self, secs: Option<f64>) -> PyResult<()> {
+        let threshold = secs
+            .map(std::time::Duration::try_from_secs_f64)
+            .transpose()
+            .map_err(|e| PyValueError::new_err(format!("Invalid slow request threshold: {}", e)))?;
+        *self.slow_request_threshold.lock() = threshold;
+        if let Some(client) = self.client.lock().as_ref() {
+            client.set_slow_request_threshold(threshold);
+        }
+        Ok(())
+    }
+
+    /// Cache responses of reference data requests. `ttls` maps proto_id to
+    /// seconds and is merged into the current TTLs; 0 stops caching a
+    /// protocol. By default static info, future info and trading calendars
+    /// are kept a day, plate sets, plate members, references, owner plates,
+    /// code changes and option expirations an hour; snapshots and other
+    /// quotes are never cached. Applies to the current and future
+    /// connections. Off by default.
+    #[pyo3(signature = (enabled, ttls=None, max_entries=None))]
+    fn set_response_cache(
+        &self,
+        enabled: bool,
+        ttls: Option<std::collections::HashMap<u32, f64>>,
+        max_entries: Option<usize>,
+    ) -> PyResult<()> {
+        let mut config = self.response_cache.lock().clone();
+        config.enabled = enabled;
+        for (proto_id, secs) in ttls.unwrap_or_default() {
+            let ttl = std::time::Duration::try_from_secs_f64(secs)
+                .map_err(|e| PyValueError::new_err(format!("Invalid TTL for proto {}: {}", proto_id, e)))?;
+            config.ttls.insert(proto_id, ttl);
+        }
+        if let Some(max_entries) = max_entries {
+            config.max_entries = max_entries;
+        }
+        *self.response_cache.lock() = config.clone();
+        if let Some(client) = self.client.lock().as_ref() {
+            client.response_cache().set_config(config);
+        }
+        Ok(())
+    }
 
+    /// Response cache counters: a dict with hits, misses, evictions,
+    /// invalidations, entries and per_proto (proto_id -> dict of hits,
+    /// misses and entries), plus enabled and ttls (proto_id -> seconds).
+    fn response_cache_stats(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let client = self.get_client()?;
+        let cache = client.response_cache();
+        let stats = cache.stats();
+        let config = cache.config();
         let dict = pyo3::types::PyDict::new_bound(py);
-        if let Some(s2c) = response.s2c {
-            if let Some(funds) = s2c.funds {
-                dict.set_item("power", funds.power)?;
-                dict.set_item("total_assets", funds.total_assets)?;
-                dict.set_item("cash", funds.cash)?;
-                dict.set_item("market_val", funds.market_val)?;
-                dict.set_item("frozen_cash", funds.frozen_cash)?;
-                dict.set_item("debt_cash", funds.debt_cash)?;
-                dict.set_item("avl_withdrawal_cash", funds.avl_withdrawal_cash)?;
-                dict.set_item("currency", funds.currency)?;
-                dict.set_item("available_funds", funds.available_funds)?;
-                dict.set_item("unrealized_pl", funds.unrealized_pl)?;
-                dict.set_item("realized_pl", funds.realized_pl)?;
-                dict.set_item("risk_level", funds.risk_level)?;
-                dict.set_item("initial_margin", funds.initial_margin)?;
-                dict.set_item("maintenance_margin", funds.maintenance_margin)?;
-                dict.set_item("max_withdrawal", funds.max_withdrawal)?;
-            }
+        dict.set_item("enabled", config.enabled)?;
+        dict.set_item("hits", stats.hits)?;
+        dict.set_item("misses", stats.misses)?;
+        dict.set_item("evictions", stats.evictions)?;
+        dict.set_item("invalidations", stats.invalidations)?;
+        dict.set_item("entries", stats.entries)?;
+        let per_proto = pyo3::types::PyDict::new_bound(py);
+        for (proto_id, p) in &stats.per_proto {
+            let item = pyo3::types::PyDict::new_bound(py);
+            item.set_item("hits", p.hits)?;
+            item.set_item("misses", p.misses)?;
+            item.set_item("entries", p.entries)?;
+            per_proto.set_item(proto_id, item)?;
         }
+        dict.set_item("per_proto", per_proto)?;
+        let ttls = pyo3::types::PyDict::new_bound(py);
+        for (proto_id, ttl) in &config.ttls {
+            ttls.set_item(proto_id, ttl.as_secs_f64())?;
+        }
+        dict.set_item("ttls", ttls)?;
         Ok(dict.into_any().unbind())
     }
 
-    /// Get security snapshot.
-    /// securities: list of (market, code) tuples
-    /// Returns list of dicts with snapshot data.
-    fn get_security_snapshot(
-        &self,
-        py: Python<'_>,
-        securities: Vec<(i32, String)>,
-    ) -> PyResult<Vec<PyObject>> {
+    /// Drop cached responses of `proto_id`, or all of them if None.
+    /// Returns the number dropped.
+    #[pyo3(signature = (proto_id=None))]
+    fn invalidate_response_cache(&self, proto_id: Option<u32>) -> PyResult<usize> {
         let client = self.get_client()?;
-        let client = &*client;
-
-        let response = py.allow_threads(|| {
-            self.runtime.block_on(async {
-                crate::quote::snapshot::get_security_snapshot(client, securities).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get snapshot failed: {}", e)))?;
-
-        let mut result = Vec::new();
-        if let Some(s2c) = response.s2c {
-            for snapshot in s2c.snapshot_list {
-                let dict = pyo3::types::PyDict::new_bound(py);
-                let basic = &snapshot.basic;
-                let sec = &basic.security;
-                dict.set_item("market", sec.market)?;
-                dict.set_item("code", &sec.code)?;
-                dict.set_item("type", basic.r#type)?;
-                dict.set_item("is_suspend", basic.is_suspend)?;
-                dict.set_item("lot_size", basic.lot_size)?;
-                dict.set_item("cur_price", basic.cur_price)?;
-                dict.set_item("open_price", basic.open_price)?;
-                dict.set_item("high_price", basic.high_price)?;
-                dict.set_item("low_price", basic.low_price)?;
-                dict.set_item("last_close_price", basic.last_close_price)?;
-                dict.set_item("volume", basic.volume)?;
-                dict.set_item("turnover", basic.turnover)?;
-                dict.set_item("update_time", &basic.update_time)?;
-                dict.set_item("update_timestamp", basic.update_timestamp)?;
-                dict.set_item("ask_price", basic.ask_price)?;
-                dict.set_item("bid_price", basic.bid_price)?;
-                dict.set_item("ask_vol", basic.ask_vol)?;
-                dict.set_item("bid_vol", basic.bid_vol)?;
-                dict.set_item("price_spread", basic.price_spread)?;
-                result.push(dict.into_any().unbind());
-            }
-        }
-        Ok(result)
+        Ok(client.response_cache().invalidate(proto_id))
     }
 
-    /// Subscribe to trade account push notifications.
-    /// acc_ids: list of account IDs to subscribe
-    fn sub_acc_push(
+    /// Client-side request rate limits per protocol. `limits` maps
+    /// proto_id to (requests, window_secs) and is merged into the current
+    /// limits; by default OpenD's documented limits of snapshots, history
+    /// K-lines, plates, option chains, warrants, capital flows, stock
+    /// filters and trade history queries are used. In mode "delay" a
+    /// request over its limit waits for the budget; in mode "fail_fast" it
+    /// raises ThrottledError. Applies to the current and future
+    /// connections. Off by default.
+    #[pyo3(signature = (enabled, mode="delay", limits=None))]
+    fn set_rate_limit(
         &self,
-        py: Python<'_>,
-        acc_ids: Vec<u64>,
+        enabled: bool,
+        mode: &str,
+        limits: Option<HashMap<u32, (u32, f64)>>,
     ) -> PyResult<()> {
+        let mut config = self.rate_limit.lock().clone();
+        config.enabled = enabled;
+        config.mode = match mode {
+            "delay" => RateLimitMode::Delay,
+            "fail_fast" => RateLimitMode::FailFast,
+            other => return Err(PyValueError::new_err(format!("Unknown rate limit mode: {}", other))),
+        };
+        for (proto_id, (requests, window_secs)) in limits.unwrap_or_default() {
+            let window = std::time::Duration::try_from_secs_f64(window_secs)
+                .map_err(|e| PyValueError::new_err(format!("Invalid window for proto {}: {}", proto_id, e)))?;
+            config.limits.insert(proto_id, RateLimit { requests, window });
+        }
+        config.validate().map_err(PyValueError::new_err)?;
+        *self.rate_limit.lock() = config.clone();
+        if let Some(client) = self.client.lock().as_ref() {
+            client.rate_limiter().set_config(config);
+        }
+        Ok(())
+    }
+
+    /// Rate limiter state: a dict with enabled, mode, limits (proto_id ->
+    /// (requests, window_secs)) and limited (proto_id -> requests delayed
+    /// or rejected).
+    fn rate_limit_stats(&self, py: Python<'_>) -> PyResult<PyObject> {
         let client = self.get_client()?;
-        let client = &*client;
+        let limiter = client.rate_limiter();
+        let config = limiter.config();
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("enabled", config.enabled)?;
+        let mode = match config.mode {
+            RateLimitMode::Delay => "delay",
+            RateLimitMode::FailFast => "fail_fast",
+        };
+        dict.set_item("mode", mode)?;
+        let limits = pyo3::types::PyDict::new_bound(py);
+        for (proto_id, limit) in &config.limits {
+            limits.set_item(proto_id, (limit.requests, limit.window.as_secs_f64()))?;
+        }
+        dict.set_item("limits", limits)?;
+        dict.set_item("limited", limiter.limited())?;
+        Ok(dict.into_any().unbind())
+    }
 
-        py.allow_threads(|| {
-            self.runtime.block_on(async {
-                crate::trade::push::sub_acc_push(client, acc_ids).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Sub acc push failed: {}", e)))
+    /// Allow the cash flow and funds protocols (`get_cash_flow()`), which
+    /// are refused until enabled. Applies to the current and future
+    /// connections. Off by default.
+    fn set_funds_protocols(&self, enabled: bool) {
+        self.funds_protocols.store(enabled, std::sync::atomic::Ordering::Relaxed);
+        if let Some(client) = self.client.lock().as_ref() {
+            client.set_funds_protocols(enabled);
+        }
     }
 
-    /// Check if the client is connected to Futu OpenD.
-    fn is_connected(&self) -> bool {
-        self.client.lock().is_some()
+    /// Funds operations and whether they can be used: a dict keyed by
+    /// operation ("cash_flow_summary", "funds_transfer", "cash_sweep",
+    /// "currency_exchange") of dicts with proto_id (None when OpenAPI has
+    /// no protocol for it), supported and enabled.
+    fn funds_capabilities(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let enabled = self.funds_protocols.load(std::sync::atomic::Ordering::Relaxed);
+        let dict = pyo3::types::PyDict::new_bound(py);
+        for op in FundsOperation::ALL {
+            let item = pyo3::types::PyDict::new_bound(py);
+            item.set_item("proto_id", op.proto_id())?;
+            item.set_item("supported", op.proto_id().is_some())?;
+            item.set_item("enabled", enabled && op.proto_id().is_some())?;
+            dict.set_item(op.as_str(), item)?;
+        }
+        Ok(dict.into_any().unbind())
     }
 
-    /// Start receiving push notifications for the given proto_ids.
-    /// Each call creates a **new** channel pair and returns its index.
-    /// Data and execution clients should each call this once and store
-    /// their own `channel_id` for use with `poll_push()`.
-    fn start_push(
-        &self,
-        py: Python<'_>,
-        proto_ids: Vec<u32>,
-    ) -> PyResult<usize> {
+    /// Current preemption as a dict with reason ("kicked_out" or
+    /// "quote_right_lost"), desc and timestamp, or None.
+    fn session_preempted(&self, py: Python<'_>) -> PyResult<Option<PyObject>> {
         let client = self.get_client()?;
-        let client = &*client;
-
-        // Always create a new channel pair for this caller
-        let (tx, rx) = mpsc::unbounded_channel::<PushMessage>();
-        let rx = Arc::new(Mutex::new(rx));
+        client
+            .session()
+            .preempted()
+            .map(|p| session_event_to_dict(py, &SessionEvent::Preempted(p)))
+            .transpose()
+    }
 
-        let channel_id = {
-            let mut channels = self.push_channels.lock();
-            let id = channels.len();
-            channels.push((tx.clone(), rx));
-            id
-        };
+    /// Clear a preemption manually, e.g. after logging OpenD back in.
+    fn resume_trading(&self) -> PyResult<()> {
+        let client = self.get_client()?;
+        client.session().resume(crate::quote::push_stats::now());
+        Ok(())
+    }
 
-        // For each proto_id, register a push handler and spawn a forwarder task
-        for proto_id in proto_ids {
-            let mut push_rx = py.allow_threads(|| {
-                self.runtime.block_on(async {
-                    client.subscribe_push(proto_id).await
-                })
-            });
+    /// Rotate the connection's AES key by repeating the InitConnect
+    /// handshake on the open connection; subscriptions are kept. Returns a
+    /// dict with conn_id, server_ver, keep_alive_interval and key_rotations
+    /// (rotations so far, also exported as futu_aes_key_rotations_total).
+    fn rekey(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let client = self.get_client()?;
+        let client = &*client;
 
-            let tx_clone = tx.clone();
-            let handle = self.runtime.spawn(async move {
-                while let Some(msg) = push_rx.recv().await {
-                    if tx_clone.send((msg.proto_id, msg.body)).is_err() {
-                        break;
-                    }
-                }
-            });
-            self.push_handles.lock().push(handle);
-        }
+        let resp = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                client.rekey().await
+            })
+        }).map_err(|e| e.into_py_err("Rekey failed"))?;
 
-        Ok(channel_id)
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("conn_id", resp.conn_id)?;
+        dict.set_item("server_ver", resp.server_ver)?;
+        dict.set_item("keep_alive_interval", resp.keep_alive_interval)?;
+        dict.set_item("key_rotations", client.connection().key_rotations())?;
+        Ok(dict.into_any().unbind())
     }
 
-    /// Poll for the next push message on a specific channel.
-    /// channel_id: index returned by `start_push()`
-    /// timeout_ms: how long to wait for a message (in milliseconds)
-    #[pyo3(signature = (channel_id, timeout_ms=100))]
-    fn poll_push(
-        &self,
-        py: Python<'_>,
-        channel_id: usize,
-        timeout_ms: u64,
-    ) -> PyResult<Option<PyObject>> {
-        let rx = {
-            let channels = self.push_channels.lock();
-            match channels.get(channel_id) {
-                Some((_, rx)) => Arc::clone(rx),
-                None => return Ok(None),
-            }
+    /// Poll for the next session event of the current connection.
+    /// Returns a dict with event ("preempted" or "resolved") and timestamp,
+    /// plus reason and desc for preemptions, or None on timeout.
+    #[pyo3(signature = (timeout_ms=100))]
+    fn poll_session_event(&self, py: Python<'_>, timeout_ms: u64) -> PyResult<Option<PyObject>> {
+        let Some(rx) = self.session_events.lock().as_ref().map(Arc::clone) else {
+            return Ok(None);
         };
 
         let timeout = std::time::Duration::from_millis(timeout_ms);
-
         let result = py.allow_threads(|| {
             self.runtime.block_on(async {
                 let mut guard = rx.lock().await;
@@ -810,22 +4450,51 @@ impl PyFutuClient {
         });
 
         match result {
-            Ok(Some((proto_id, body))) => {
-                let data = super::push_decode::decode_push_message(py, proto_id, &body)?;
-                let dict = pyo3::types::PyDict::new_bound(py);
-                dict.set_item("proto_id", proto_id)?;
-                dict.set_item("data", data)?;
-                Ok(Some(dict.into_any().unbind()))
-            }
-            Ok(None) => {
-                // Channel closed
-                Ok(None)
-            }
-            Err(_) => {
-                // Timeout — no message available
-                Ok(None)
-            }
+            Ok(Some(event)) => Ok(Some(session_event_to_dict(py, &event)?)),
+            // Channel closed or timeout
+            _ => Ok(None),
+        }
+    }
+
+    /// Poll up to `max_events` order lifecycle events of the current
+    /// connection, synthesized from order and fill pushes of the accounts
+    /// subscribed with `sub_acc_push()`. Waits up to timeout_ms for the
+    /// first event, then takes whatever else is queued. Each event is a dict
+    /// with acc_id, order_id, code, prev_state (None for an order's first
+    /// event), state ("pending_submit", "submitted", "partially_filled",
+    /// "pending_cancel", "disabled", "filled", "cancelled", "rejected",
+    /// "deleted" or "unknown"), qty, filled_qty, fill_avg_price, fill (the
+    /// `get_order_fill_list()` dict of the fill behind the event, or None)
+    /// and timestamp. A new fill is reported even if the state stays the same.
+    #[pyo3(signature = (timeout_ms=100, max_events=256))]
+    fn poll_order_events(&self, py: Python<'_>, timeout_ms: u64, max_events: usize) -> PyResult<Vec<PyObject>> {
+        let Some(stream) = self.order_events.lock().as_ref().map(Arc::clone) else {
+            return Ok(Vec::new());
+        };
+        if max_events == 0 {
+            return Ok(Vec::new());
         }
+
+        let timeout = std::time::Duration::from_millis(timeout_ms);
+        let events = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                let mut guard = stream.lock().await;
+                let mut events = Vec::new();
+                if let Ok(Some(first)) = tokio::time::timeout(timeout, guard.recv()).await {
+                    events.push(first);
+                    while events.len() < max_events {
+                        match futures::FutureExt::now_or_never(guard.recv()) {
+                            Some(Some(event)) => events.push(event),
+                            _ => break,
+                        }
+                    }
+                }
+                events
+            })
+        });
+
+        let (names, aliases) = (self.names(), self.aliases());
+        events.iter().map(|event| order_event_to_dict(py, &names, &aliases, event)).collect()
     }
 
     /// Filter stocks by conditions (Qot_StockFilter, proto 3215).
@@ -846,6 +4515,7 @@ impl PyFutuClient {
     ) -> PyResult<PyObject> {
         let client = self.get_client()?;
         let client = &*client;
+        let aliases = self.aliases();
 
         let base = base_filters.unwrap_or_default().into_iter().map(|(field, min, max, sort)| {
             crate::generated::qot_stock_filter::BaseFilter {
@@ -884,8 +4554,8 @@ impl PyFutuClient {
                 crate::quote::snapshot::stock_filter(
                     client, begin, num, market, None, base, accumulate, financial,
                 ).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Stock filter failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Stock filter failed"))?;
 
         let result = pyo3::types::PyDict::new_bound(py);
         if let Some(s2c) = response.s2c {
@@ -893,11 +4563,15 @@ impl PyFutuClient {
             result.set_item("all_count", s2c.all_count)?;
 
             let data_list = pyo3::types::PyList::empty_bound(py);
+            let names = self.names();
+            let mut checkpoint = GilCheckpoint::new(py);
             for stock in &s2c.data_list {
+                checkpoint.tick()?;
                 let dict = pyo3::types::PyDict::new_bound(py);
                 dict.set_item("market", stock.security.market)?;
                 dict.set_item("code", &stock.security.code)?;
-                dict.set_item("name", &stock.name)?;
+                dict.set_item("alias", aliases.alias(stock.security.market, &stock.security.code))?;
+                dict.set_item("name", names.localize(stock.security.market, &stock.security.code, &stock.name))?;
 
                 let base_data = pyo3::types::PyList::empty_bound(py);
                 for bd in &stock.base_data_list {
@@ -937,35 +4611,40 @@ impl PyFutuClient {
 
     /// Get securities in a plate/sector (Qot_GetPlateSecurity, proto 3205).
     /// Returns a list of static info dicts (same format as get_static_info).
-    #[pyo3(signature = (plate_market, plate_code, sort_field=None, ascend=None))]
+    #[pyo3(signature = (plate, sort_field=None, ascend=None))]
     fn get_plate_security(
         &self,
         py: Python<'_>,
-        plate_market: i32,
-        plate_code: String,
+        plate: SecurityArg,
         sort_field: Option<i32>,
         ascend: Option<bool>,
     ) -> PyResult<Vec<PyObject>> {
+        let (plate_market, plate_code) = self.resolve_securities(vec![plate])?.remove(0);
         let client = self.get_client()?;
         let client = &*client;
+        let aliases = self.aliases();
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::quote::snapshot::get_plate_security(
                     client, plate_market, plate_code, sort_field, ascend,
                 ).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get plate security failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get plate security failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
+            let names = self.names();
+            let mut checkpoint = GilCheckpoint::new(py);
             for info in s2c.static_info_list {
+                checkpoint.tick()?;
                 let dict = pyo3::types::PyDict::new_bound(py);
                 let basic = &info.basic;
                 let sec = &basic.security;
                 dict.set_item("market", sec.market)?;
                 dict.set_item("code", &sec.code)?;
-                dict.set_item("name", &basic.name)?;
+                dict.set_item("alias", aliases.alias(sec.market, &sec.code))?;
+                dict.set_item("name", names.localize(sec.market, &sec.code, &basic.name))?;
                 dict.set_item("lot_size", basic.lot_size)?;
                 dict.set_item("sec_type", basic.sec_type)?;
                 dict.set_item("list_time", &basic.list_time)?;
@@ -1001,18 +4680,21 @@ impl PyFutuClient {
 
     // ── Trade: get_history_order_list ──────────────────────────────────
     /// Get historical order list.
-    /// Returns list of dicts with order details.
-    #[pyo3(signature = (trd_env, acc_id, trd_market, filter_status_list=None))]
+    /// Returns list of dicts with order details, or `Order` objects if typed.
+    #[pyo3(signature = (trd_env=None, acc_id=None, trd_market=None, filter_status_list=None, typed=false))]
     fn get_history_order_list(
         &self,
         py: Python<'_>,
-        trd_env: i32,
-        acc_id: u64,
-        trd_market: i32,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
         filter_status_list: Option<Vec<i32>>,
+        typed: bool,
     ) -> PyResult<Vec<PyObject>> {
         let client = self.get_client()?;
         let client = &*client;
+        let aliases = self.aliases();
+        let (trd_env, acc_id, trd_market) = self.resolve_account(trd_env, acc_id, trd_market)?;
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
@@ -1020,12 +4702,19 @@ impl PyFutuClient {
                     client, trd_env, acc_id, trd_market, None,
                     filter_status_list.unwrap_or_default(),
                 ).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get history order list failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get history order list failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
+            let names = self.names();
+            let mut checkpoint = GilCheckpoint::new(py);
             for order in s2c.order_list {
+                checkpoint.tick()?;
+                if typed {
+                    result.push(Py::new(py, Order::from_proto(&names, &aliases, &order))?.into_any());
+                    continue;
+                }
                 let dict = pyo3::types::PyDict::new_bound(py);
                 dict.set_item("trd_side", order.trd_side)?;
                 dict.set_item("order_type", order.order_type)?;
@@ -1033,7 +4722,8 @@ impl PyFutuClient {
                 dict.set_item("order_id", order.order_id)?;
                 dict.set_item("order_id_ex", &order.order_id_ex)?;
                 dict.set_item("code", &order.code)?;
-                dict.set_item("name", &order.name)?;
+                dict.set_item("alias", aliases.alias_trd(order.sec_market, &order.code))?;
+                dict.set_item("name", names.localize_trd(order.sec_market, &order.code, &order.name))?;
                 dict.set_item("qty", order.qty)?;
                 dict.set_item("price", order.price)?;
                 dict.set_item("create_time", &order.create_time)?;
@@ -1054,66 +4744,193 @@ impl PyFutuClient {
     // ── Trade: get_history_order_fill_list ───────────────────────────────
     /// Get historical order fill list.
     /// Returns list of dicts with fill details.
+    #[pyo3(signature = (trd_env=None, acc_id=None, trd_market=None))]
     fn get_history_order_fill_list(
         &self,
         py: Python<'_>,
-        trd_env: i32,
-        acc_id: u64,
-        trd_market: i32,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
     ) -> PyResult<Vec<PyObject>> {
         let client = self.get_client()?;
         let client = &*client;
+        let aliases = self.aliases();
+        let (trd_env, acc_id, trd_market) = self.resolve_account(trd_env, acc_id, trd_market)?;
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::trade::query::get_history_order_fill_list(
                     client, trd_env, acc_id, trd_market, None,
                 ).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get history order fill list failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get history order fill list failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
-            for fill in s2c.order_fill_list {
-                let dict = pyo3::types::PyDict::new_bound(py);
-                dict.set_item("trd_side", fill.trd_side)?;
-                dict.set_item("fill_id", fill.fill_id)?;
-                dict.set_item("fill_id_ex", &fill.fill_id_ex)?;
-                dict.set_item("order_id", fill.order_id)?;
-                dict.set_item("order_id_ex", fill.order_id_ex.as_deref())?;
-                dict.set_item("code", &fill.code)?;
-                dict.set_item("name", &fill.name)?;
-                dict.set_item("qty", fill.qty)?;
-                dict.set_item("price", fill.price)?;
-                dict.set_item("create_time", &fill.create_time)?;
-                dict.set_item("create_timestamp", fill.create_timestamp)?;
-                dict.set_item("update_timestamp", fill.update_timestamp)?;
-                dict.set_item("sec_market", fill.sec_market)?;
-                dict.set_item("status", fill.status)?;
-                result.push(dict.into_any().unbind());
+            let names = self.names();
+            let mut checkpoint = GilCheckpoint::new(py);
+            for fill in &s2c.order_fill_list {
+                checkpoint.tick()?;
+                result.push(fill_to_dict(py, &names, &aliases, fill)?);
             }
         }
         Ok(result)
     }
 
+    // ── Trade: get_history_fills ────────────────────────────────────────
+    /// Historical fills between begin_time and end_time ("YYYY-MM-DD
+    /// HH:MM:SS" or "YYYY-MM-DD", inclusive), fetched in windows of
+    /// window_days, concurrency windows at a time. Windows returning
+    /// page_limit fills are split until they fit. Fills are deduplicated by
+    /// fill_id_ex and ordered by create_timestamp. code_list takes codes,
+    /// (market, code) tuples or symbol aliases.
+    /// Returns a dict with "fills" (same keys as get_history_order_fill_list),
+    /// "is_complete" (False if a one-minute window was still full, so fills
+    /// may be missing) and "requests".
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (begin_time, end_time, code_list=None, trd_env=None, acc_id=None, trd_market=None, window_days=7.0, concurrency=2, page_limit=1000))]
+    fn get_history_fills(
+        &self,
+        py: Python<'_>,
+        begin_time: &str,
+        end_time: &str,
+        code_list: Option<Vec<SecurityArg>>,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
+        window_days: f64,
+        concurrency: usize,
+        page_limit: usize,
+    ) -> PyResult<PyObject> {
+        if window_days.is_nan() || window_days <= 0.0 || concurrency == 0 || page_limit == 0 {
+            return Err(PyValueError::new_err("window_days, concurrency and page_limit must be positive"));
+        }
+        let client = self.get_client()?;
+        let client = &*client;
+        let aliases = self.aliases();
+        let code_list = code_list.map(|codes| self.resolve_codes(codes));
+        let (trd_env, acc_id, trd_market) = self.resolve_account(trd_env, acc_id, trd_market)?;
+        let options = crate::trade::fills::FillHistoryOptions {
+            window_secs: ((window_days * 86_400.0) as i64).max(1),
+            concurrency,
+            page_limit,
+            ..Default::default()
+        };
+
+        let history = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::trade::fills::get_history_fills(
+                    client, trd_env, acc_id, trd_market, begin_time, end_time,
+                    code_list.unwrap_or_default(), &options,
+                ).await
+            })
+        }).map_err(|e| e.into_py_err("Get history fills failed"))?;
+
+        let names = self.names();
+        let mut checkpoint = GilCheckpoint::new(py);
+        let mut fills = Vec::with_capacity(history.fills.len());
+        for fill in &history.fills {
+            checkpoint.tick()?;
+            fills.push(fill_to_dict(py, &names, &aliases, fill)?);
+        }
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("fills", fills)?;
+        dict.set_item("is_complete", history.is_complete)?;
+        dict.set_item("requests", history.requests)?;
+        Ok(dict.into_any().unbind())
+    }
+
+    // ── Trade: execution_report ─────────────────────────────────────────
+    /// Execution-quality report for one day ("YYYY-MM-DD"): each fill is
+    /// compared with the mid-price of the 1-minute bar it executed in.
+    /// Returns a dict with "fills" (fill_id, order_id, code, sec_market,
+    /// trd_side, qty, price, fill_time, fill_timestamp, benchmark, slippage,
+    /// slippage_bps), "orders" (order_id, code, trd_side, fill_count,
+    /// filled_qty, avg_price, arrival_price, shortfall, shortfall_bps) and
+    /// "csv" (the fill rows as CSV). Positive slippage and shortfall are costs.
+    #[pyo3(signature = (day, trd_env=None, acc_id=None, trd_market=None))]
+    fn execution_report(
+        &self,
+        py: Python<'_>,
+        day: String,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
+    ) -> PyResult<PyObject> {
+        let client = self.get_client()?;
+        let client = &*client;
+        let aliases = self.aliases();
+        let (trd_env, acc_id, trd_market) = self.resolve_account(trd_env, acc_id, trd_market)?;
+
+        let report = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::trade::report::execution_report(client, trd_env, acc_id, trd_market, &day).await
+            })
+        }).map_err(|e| e.into_py_err("Execution report failed"))?;
+
+        let mut checkpoint = GilCheckpoint::new(py);
+        let fills = pyo3::types::PyList::empty_bound(py);
+        for f in &report.fills {
+            checkpoint.tick()?;
+            let dict = pyo3::types::PyDict::new_bound(py);
+            dict.set_item("fill_id", f.fill_id)?;
+            dict.set_item("order_id", f.order_id)?;
+            dict.set_item("code", &f.code)?;
+            dict.set_item("alias", aliases.alias_trd(f.sec_market, &f.code))?;
+            dict.set_item("sec_market", f.sec_market)?;
+            dict.set_item("trd_side", f.trd_side)?;
+            dict.set_item("qty", f.qty)?;
+            dict.set_item("price", f.price)?;
+            dict.set_item("fill_time", &f.fill_time)?;
+            dict.set_item("fill_timestamp", f.fill_timestamp)?;
+            dict.set_item("benchmark", f.benchmark)?;
+            dict.set_item("slippage", f.slippage)?;
+            dict.set_item("slippage_bps", f.slippage_bps)?;
+            fills.append(dict)?;
+        }
+        let orders = pyo3::types::PyList::empty_bound(py);
+        for o in &report.orders {
+            checkpoint.tick()?;
+            let dict = pyo3::types::PyDict::new_bound(py);
+            dict.set_item("order_id", o.order_id)?;
+            dict.set_item("code", &o.code)?;
+            dict.set_item("trd_side", o.trd_side)?;
+            dict.set_item("fill_count", o.fill_count)?;
+            dict.set_item("filled_qty", o.filled_qty)?;
+            dict.set_item("avg_price", o.avg_price)?;
+            dict.set_item("arrival_price", o.arrival_price)?;
+            dict.set_item("shortfall", o.shortfall)?;
+            dict.set_item("shortfall_bps", o.shortfall_bps)?;
+            orders.append(dict)?;
+        }
+
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("fills", fills)?;
+        dict.set_item("orders", orders)?;
+        dict.set_item("csv", report.to_csv())?;
+        Ok(dict.into_any().unbind())
+    }
+
     // ── Trade: get_max_trd_qtys ─────────────────────────────────────────
     /// Get maximum tradeable quantities.
     /// Returns a dict with max qty fields.
     #[allow(clippy::too_many_arguments)]
-    #[pyo3(signature = (trd_env, acc_id, trd_market, order_type, code, price, sec_market=None))]
+    #[pyo3(signature = (order_type, security, price, sec_market=None, *, trd_env=None, acc_id=None, trd_market=None))]
     fn get_max_trd_qtys(
         &self,
         py: Python<'_>,
-        trd_env: i32,
-        acc_id: u64,
-        trd_market: i32,
         order_type: i32,
-        code: String,
+        security: SecurityArg,
         price: f64,
         sec_market: Option<i32>,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
     ) -> PyResult<PyObject> {
+        let (sec_market, code) = self.resolve_order_security(security, sec_market)?;
         let client = self.get_client()?;
         let client = &*client;
+        let (trd_env, acc_id, trd_market) = self.resolve_account(trd_env, acc_id, trd_market)?;
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
@@ -1121,8 +4938,8 @@ impl PyFutuClient {
                     client, trd_env, acc_id, trd_market,
                     order_type, code, price, sec_market,
                 ).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get max trd qtys failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get max trd qtys failed"))?;
 
         let dict = pyo3::types::PyDict::new_bound(py);
         if let Some(s2c) = response.s2c {
@@ -1142,31 +4959,38 @@ impl PyFutuClient {
     // ── Trade: get_margin_ratio ─────────────────────────────────────────
     /// Get margin ratio for securities.
     /// Returns list of dicts with margin ratio info.
+    #[pyo3(signature = (securities, *, trd_env=None, acc_id=None, trd_market=None))]
     fn get_margin_ratio(
         &self,
         py: Python<'_>,
-        trd_env: i32,
-        acc_id: u64,
-        trd_market: i32,
-        securities: Vec<(i32, String)>,
+        securities: Vec<SecurityArg>,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
     ) -> PyResult<Vec<PyObject>> {
+        let securities = self.resolve_securities(securities)?;
         let client = self.get_client()?;
         let client = &*client;
+        let aliases = self.aliases();
+        let (trd_env, acc_id, trd_market) = self.resolve_account(trd_env, acc_id, trd_market)?;
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::trade::query::get_margin_ratio(
                     client, trd_env, acc_id, trd_market, securities,
                 ).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get margin ratio failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get margin ratio failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
+            let mut checkpoint = GilCheckpoint::new(py);
             for info in s2c.margin_ratio_info_list {
+                checkpoint.tick()?;
                 let dict = pyo3::types::PyDict::new_bound(py);
                 dict.set_item("market", info.security.market)?;
                 dict.set_item("code", &info.security.code)?;
+                dict.set_item("alias", aliases.alias(info.security.market, &info.security.code))?;
                 dict.set_item("is_long_permit", info.is_long_permit)?;
                 dict.set_item("is_short_permit", info.is_short_permit)?;
                 dict.set_item("short_pool_remain", info.short_pool_remain)?;
@@ -1184,24 +5008,26 @@ impl PyFutuClient {
     // ── Trade: get_order_fee ────────────────────────────────────────────
     /// Get order fee details.
     /// Returns list of dicts with fee info.
+    #[pyo3(signature = (order_id_ex_list, *, trd_env=None, acc_id=None, trd_market=None))]
     fn get_order_fee(
         &self,
         py: Python<'_>,
-        trd_env: i32,
-        acc_id: u64,
-        trd_market: i32,
         order_id_ex_list: Vec<String>,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
     ) -> PyResult<Vec<PyObject>> {
         let client = self.get_client()?;
         let client = &*client;
+        let (trd_env, acc_id, trd_market) = self.resolve_account(trd_env, acc_id, trd_market)?;
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::trade::query::get_order_fee(
                     client, trd_env, acc_id, trd_market, order_id_ex_list,
                 ).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get order fee failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get order fee failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
@@ -1225,6 +5051,124 @@ impl PyFutuClient {
         Ok(result)
     }
 
+    // ── Trade: get_cash_flow ────────────────────────────────────────────
+    /// Cash flow entries of an account on a clearing date ("YYYY-MM-DD").
+    /// direction: 1 = in, 2 = out; None returns both.
+    /// Needs `set_funds_protocols(True)`.
+    /// Returns list of dicts with cash flow info.
+    #[pyo3(signature = (clearing_date, direction=None, trd_env=None, acc_id=None, trd_market=None))]
+    fn get_cash_flow(
+        &self,
+        py: Python<'_>,
+        clearing_date: String,
+        direction: Option<i32>,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
+    ) -> PyResult<Vec<PyObject>> {
+        let direction = direction
+            .map(|d| CashFlowDirection::from_i32(d).ok_or_else(|| PyValueError::new_err(format!("Invalid cash flow direction: {}", d))))
+            .transpose()?;
+        let client = self.get_client()?;
+        let client = &*client;
+        let (trd_env, acc_id, trd_market) = self.resolve_account(trd_env, acc_id, trd_market)?;
+
+        let flows = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::trade::funds::get_cash_flow(client, trd_env, acc_id, trd_market, clearing_date, direction).await
+            })
+        }).map_err(|e| e.into_py_err("Get cash flow failed"))?;
+
+        flows.iter().map(|flow| cash_flow_to_dict(py, flow)).collect()
+    }
+
+    // ── Trade: get_flow_summary ─────────────────────────────────────────
+    /// Cash flow entries of an account with a clearing date from begin_date
+    /// to end_date ("YYYY-MM-DD", both included; end_date defaults to
+    /// begin_date), oldest first. One request per day, at most 366 days.
+    /// OpenD allows 20 Trd_FlowSummary requests per 30 seconds, so longer
+    /// ranges are paced to that limit: 60 days take about a minute and a
+    /// year about 9 minutes.
+    /// direction: 1 = in, 2 = out; None returns both.
+    /// Needs `set_funds_protocols(True)`.
+    /// Returns list of dicts with cash flow info, as `get_cash_flow()`.
+    #[pyo3(signature = (begin_date, end_date=None, direction=None, trd_env=None, acc_id=None, trd_market=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn get_flow_summary(
+        &self,
+        py: Python<'_>,
+        begin_date: String,
+        end_date: Option<String>,
+        direction: Option<i32>,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
+    ) -> PyResult<Vec<PyObject>> {
+        let direction = direction
+            .map(|d| CashFlowDirection::from_i32(d).ok_or_else(|| PyValueError::new_err(format!("Invalid cash flow direction: {}", d))))
+            .transpose()?;
+        let end_date = end_date.unwrap_or_else(|| begin_date.clone());
+        let client = self.get_client()?;
+        let client = &*client;
+        let (trd_env, acc_id, trd_market) = self.resolve_account(trd_env, acc_id, trd_market)?;
+
+        let flows = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::trade::query::get_flow_summary(client, trd_env, acc_id, trd_market, &begin_date, &end_date, direction).await
+            })
+        }).map_err(|e| e.into_py_err("Get flow summary failed"))?;
+
+        flows.iter().map(|flow| cash_flow_to_dict(py, flow)).collect()
+    }
+
+    // ── Trade: preview_order ────────────────────────────────────────────
+    /// Preview an order without submitting it.
+    /// Combines max tradeable quantities, margin ratio and funds.
+    /// security: (market, code) tuple or symbol alias, with the QotMarket
+    /// (used for the margin ratio lookup).
+    /// Returns a dict with feasibility and estimated buying-power impact.
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (trd_side, order_type, security, qty, price, *, trd_env=None, acc_id=None, trd_market=None))]
+    fn preview_order(
+        &self,
+        py: Python<'_>,
+        trd_side: i32,
+        order_type: i32,
+        security: SecurityArg,
+        qty: f64,
+        price: f64,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
+    ) -> PyResult<PyObject> {
+        let (market, code) = self.resolve_securities(vec![security])?.remove(0);
+        let client = self.get_client()?;
+        let client = &*client;
+        let (trd_env, acc_id, trd_market) = self.resolve_account(trd_env, acc_id, trd_market)?;
+
+        let preview = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::trade::preview::preview_order(
+                    client, trd_env, acc_id, trd_market, trd_side,
+                    order_type, market, code, qty, price,
+                ).await
+            })
+        }).map_err(|e| e.into_py_err("Preview order failed"))?;
+
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("feasible", preview.feasible)?;
+        dict.set_item("reason", preview.reason)?;
+        dict.set_item("max_qty", preview.max_qty)?;
+        dict.set_item("order_value", preview.order_value)?;
+        dict.set_item("initial_margin_ratio", preview.initial_margin_ratio)?;
+        dict.set_item("buying_power_used", preview.buying_power_used)?;
+        dict.set_item("buying_power", preview.buying_power)?;
+        dict.set_item("buying_power_after", preview.buying_power_after)?;
+        dict.set_item("initial_margin", preview.initial_margin)?;
+        dict.set_item("initial_margin_after", preview.initial_margin_after)?;
+        Ok(dict.into_any().unbind())
+    }
+
     // ── Quote: get_sub_info ─────────────────────────────────────────────
     /// Get subscription info.
     /// Returns a dict with quota and subscription details.
@@ -1236,12 +5180,13 @@ impl PyFutuClient {
     ) -> PyResult<PyObject> {
         let client = self.get_client()?;
         let client = &*client;
+        let aliases = self.aliases();
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::quote::snapshot::get_sub_info(client, is_req_all_conn).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get sub info failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get sub info failed"))?;
 
         let dict = pyo3::types::PyDict::new_bound(py);
         if let Some(s2c) = response.s2c {
@@ -1263,6 +5208,7 @@ impl PyFutuClient {
                         let sec_d = pyo3::types::PyDict::new_bound(py);
                         sec_d.set_item("market", sec.market)?;
                         sec_d.set_item("code", &sec.code)?;
+                        sec_d.set_item("alias", aliases.alias(sec.market, &sec.code))?;
                         sec_list.append(sec_d)?;
                     }
                     sd.set_item("security_list", sec_list)?;
@@ -1282,26 +5228,30 @@ impl PyFutuClient {
     fn get_rt(
         &self,
         py: Python<'_>,
-        market: i32,
-        code: String,
+        security: SecurityArg,
     ) -> PyResult<PyObject> {
+        let (market, code) = self.resolve_securities(vec![security])?.remove(0);
         let client = self.get_client()?;
         let client = &*client;
+        let aliases = self.aliases();
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::quote::snapshot::get_rt(client, market, code).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get RT failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get RT failed"))?;
 
         let dict = pyo3::types::PyDict::new_bound(py);
         if let Some(s2c) = response.s2c {
             dict.set_item("market", s2c.security.market)?;
             dict.set_item("code", &s2c.security.code)?;
+            dict.set_item("alias", aliases.alias(s2c.security.market, &s2c.security.code))?;
             dict.set_item("name", s2c.name.as_deref())?;
 
             let rt_list = pyo3::types::PyList::empty_bound(py);
+            let mut checkpoint = GilCheckpoint::new(py);
             for rt in &s2c.rt_list {
+                checkpoint.tick()?;
                 let d = pyo3::types::PyDict::new_bound(py);
                 d.set_item("time", &rt.time)?;
                 d.set_item("minute", rt.minute)?;
@@ -1321,43 +5271,75 @@ impl PyFutuClient {
 
     // ── Quote: get_broker ───────────────────────────────────────────────
     /// Get broker queue for a single security.
-    /// Returns a dict with broker_ask_list and broker_bid_list.
+    /// Returns a dict with broker_ask_list and broker_bid_list. Entries carry
+    /// the seat's "group" (None if unknown) and take their name from the
+    /// broker table when one was loaded.
     fn get_broker(
         &self,
         py: Python<'_>,
-        market: i32,
-        code: String,
+        security: SecurityArg,
     ) -> PyResult<PyObject> {
+        let (market, code) = self.resolve_securities(vec![security])?.remove(0);
         let client = self.get_client()?;
         let client = &*client;
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::quote::snapshot::get_broker(client, market, code).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get broker failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get broker failed"))?;
 
         let dict = pyo3::types::PyDict::new_bound(py);
         if let Some(s2c) = response.s2c {
-            let ask_list = pyo3::types::PyList::empty_bound(py);
-            for b in &s2c.broker_ask_list {
-                let d = pyo3::types::PyDict::new_bound(py);
-                d.set_item("id", b.id)?;
-                d.set_item("name", &b.name)?;
-                d.set_item("pos", b.pos)?;
-                ask_list.append(d)?;
+            let mut brokers = self.brokers.lock();
+            for b in s2c.broker_ask_list.iter().chain(&s2c.broker_bid_list) {
+                brokers.learn(b.id, &b.name);
+            }
+            for (key, list) in [("broker_ask_list", &s2c.broker_ask_list), ("broker_bid_list", &s2c.broker_bid_list)] {
+                let py_list = pyo3::types::PyList::empty_bound(py);
+                for b in list {
+                    let info = brokers.get(b.id);
+                    let d = pyo3::types::PyDict::new_bound(py);
+                    d.set_item("id", b.id)?;
+                    d.set_item("name", info.map_or(b.name.as_str(), |i| i.name.as_str()))?;
+                    d.set_item("group", info.map(|i| i.group()))?;
+                    d.set_item("pos", b.pos)?;
+                    py_list.append(d)?;
+                }
+                dict.set_item(key, py_list)?;
             }
-            dict.set_item("broker_ask_list", ask_list)?;
+        }
+        Ok(dict.into_any().unbind())
+    }
+
+    /// Get the order queue detail of the best ask and bid of a security
+    /// (level 2 quote rights and an ORDER_DETAIL subscription needed).
+    /// Returns a dict with `order_detail_ask` and `order_detail_bid`, each
+    /// a dict with `order_count` and `order_vol`, the volumes of the queued
+    /// orders in queue order (at most 50).
+    fn get_order_detail(
+        &self,
+        py: Python<'_>,
+        security: SecurityArg,
+    ) -> PyResult<PyObject> {
+        let (market, code) = self.resolve_securities(vec![security])?.remove(0);
+        let client = self.get_client()?;
+        let client = &*client;
+
+        let response = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::quote::snapshot::get_order_detail(client, market, code).await
+            })
+        }).map_err(|e| e.into_py_err("Get order detail failed"))?;
 
-            let bid_list = pyo3::types::PyList::empty_bound(py);
-            for b in &s2c.broker_bid_list {
+        let dict = pyo3::types::PyDict::new_bound(py);
+        if let Some(s2c) = response.s2c {
+            for (key, detail) in [("order_detail_ask", &s2c.order_detail_ask), ("order_detail_bid", &s2c.order_detail_bid)] {
                 let d = pyo3::types::PyDict::new_bound(py);
-                d.set_item("id", b.id)?;
-                d.set_item("name", &b.name)?;
-                d.set_item("pos", b.pos)?;
-                bid_list.append(d)?;
+                d.set_item("order_count", detail.order_count)?;
+                d.set_item("order_vol", &detail.order_vol)?;
+                dict.set_item(key, d)?;
             }
-            dict.set_item("broker_bid_list", bid_list)?;
         }
         Ok(dict.into_any().unbind())
     }
@@ -1368,23 +5350,28 @@ impl PyFutuClient {
     fn get_rehab(
         &self,
         py: Python<'_>,
-        securities: Vec<(i32, String)>,
+        securities: Vec<SecurityArg>,
     ) -> PyResult<Vec<PyObject>> {
+        let securities = self.resolve_securities(securities)?;
         let client = self.get_client()?;
         let client = &*client;
+        let aliases = self.aliases();
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::quote::snapshot::get_rehab(client, securities).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get rehab failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get rehab failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
+            let mut checkpoint = GilCheckpoint::new(py);
             for sec_rehab in s2c.security_rehab_list {
+                checkpoint.tick()?;
                 let dict = pyo3::types::PyDict::new_bound(py);
                 dict.set_item("market", sec_rehab.security.market)?;
                 dict.set_item("code", &sec_rehab.security.code)?;
+                dict.set_item("alias", aliases.alias(sec_rehab.security.market, &sec_rehab.security.code))?;
 
                 let rehab_list = pyo3::types::PyList::empty_bound(py);
                 for r in &sec_rehab.rehab_list {
@@ -1415,25 +5402,30 @@ impl PyFutuClient {
     fn get_suspend(
         &self,
         py: Python<'_>,
-        securities: Vec<(i32, String)>,
+        securities: Vec<SecurityArg>,
         begin_time: String,
         end_time: String,
     ) -> PyResult<Vec<PyObject>> {
+        let securities = self.resolve_securities(securities)?;
         let client = self.get_client()?;
         let client = &*client;
+        let aliases = self.aliases();
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::quote::snapshot::get_suspend(client, securities, begin_time, end_time).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get suspend failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get suspend failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
+            let mut checkpoint = GilCheckpoint::new(py);
             for sec_suspend in s2c.security_suspend_list {
+                checkpoint.tick()?;
                 let dict = pyo3::types::PyDict::new_bound(py);
                 dict.set_item("market", sec_suspend.security.market)?;
                 dict.set_item("code", &sec_suspend.security.code)?;
+                dict.set_item("alias", aliases.alias(sec_suspend.security.market, &sec_suspend.security.code))?;
 
                 let suspend_list = pyo3::types::PyList::empty_bound(py);
                 for s in &sec_suspend.suspend_list {
@@ -1450,6 +5442,193 @@ impl PyFutuClient {
         Ok(result)
     }
 
+    // ── Quote: get_market_state ─────────────────────────────────────────
+    /// Get the market state of each security; securities of one market can
+    /// be in different sessions.
+    /// Returns list of dicts with market, code, alias, name and
+    /// market_state (QotMarketState).
+    fn get_market_state(&self, py: Python<'_>, securities: Vec<SecurityArg>) -> PyResult<Vec<PyObject>> {
+        let securities = self.resolve_securities(securities)?;
+        let client = self.get_client()?;
+        let client = &*client;
+        let names = self.names();
+        let aliases = self.aliases();
+
+        let response = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::quote::snapshot::get_market_state(client, securities).await
+            })
+        }).map_err(|e| e.into_py_err("Get market state failed"))?;
+
+        let mut result = Vec::new();
+        if let Some(s2c) = response.s2c {
+            for info in s2c.market_info_list {
+                let sec = &info.security;
+                let dict = pyo3::types::PyDict::new_bound(py);
+                dict.set_item("market", sec.market)?;
+                dict.set_item("code", &sec.code)?;
+                dict.set_item("alias", aliases.alias(sec.market, &sec.code))?;
+                dict.set_item("name", names.localize(sec.market, &sec.code, &info.name))?;
+                dict.set_item("market_state", info.market_state)?;
+                result.push(dict.into_any().unbind());
+            }
+        }
+        Ok(result)
+    }
+
+    // ── Quote: suspension calendar ──────────────────────────────────────
+    /// Whether a security is suspended on `date` (YYYY-MM-DD).
+    /// Suspension data is fetched on demand and cached per security.
+    fn is_suspended_on(
+        &self,
+        py: Python<'_>,
+        security: SecurityArg,
+        date: String,
+    ) -> PyResult<bool> {
+        let (market, code) = self.resolve_securities(vec![security])?.remove(0);
+        let client = self.get_client()?;
+        let client = &*client;
+        let cache = &self.suspensions;
+
+        py.allow_threads(|| {
+            self.runtime.block_on(async {
+                cache.is_suspended_on(client, (market, code), &date).await
+            })
+        }).map_err(|e| e.into_py_err("Is suspended on failed"))
+    }
+
+    /// Date (YYYY-MM-DD) trading resumes after the suspension covering `date`.
+    /// Returns None if the security is not suspended on `date` or no resume
+    /// date is known yet.
+    fn next_resume(
+        &self,
+        py: Python<'_>,
+        security: SecurityArg,
+        date: String,
+    ) -> PyResult<Option<String>> {
+        let (market, code) = self.resolve_securities(vec![security])?.remove(0);
+        let client = self.get_client()?;
+        let client = &*client;
+        let cache = &self.suspensions;
+
+        py.allow_threads(|| {
+            self.runtime.block_on(async {
+                cache.next_resume(client, (market, code), &date).await
+            })
+        }).map_err(|e| e.into_py_err("Next resume failed"))
+    }
+
+    // ── Quote: instrument ───────────────────────────────────────────────
+    /// Consolidated record of a security for onboarding a symbol: static
+    /// info, tick table, margin and short-sell flags, trading sessions and
+    /// currency. With an account (given or the default one) the account's
+    /// margin ratios are added under "margin". Records are cached for an
+    /// hour; `refresh=True` fetches them again.
+    #[pyo3(signature = (security, trd_env=None, acc_id=None, trd_market=None, refresh=false))]
+    fn instrument(
+        &self,
+        py: Python<'_>,
+        security: SecurityArg,
+        trd_env: Option<i32>,
+        acc_id: Option<u64>,
+        trd_market: Option<i32>,
+        refresh: bool,
+    ) -> PyResult<PyObject> {
+        let security = self.resolve_securities(vec![security])?.remove(0);
+        let account = if trd_env.is_none() && acc_id.is_none() && trd_market.is_none() {
+            *self.default_account.lock()
+        } else {
+            Some(self.resolve_account(trd_env, acc_id, trd_market)?)
+        };
+        let client = self.get_client()?;
+        let client = &*client;
+        let cache = &self.instruments;
+
+        let instrument = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                cache.get(client, security, account, refresh).await
+            })
+        }).map_err(|e| e.into_py_err("Instrument failed"))?;
+
+        let names = self.names();
+        let aliases = self.aliases();
+        let (market, code) = &instrument.security;
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("market", market)?;
+        dict.set_item("code", code)?;
+        dict.set_item("alias", aliases.alias(*market, code))?;
+        dict.set_item("name", names.localize(*market, code, &instrument.name))?;
+        dict.set_item("sec_type", instrument.sec_type)?;
+        dict.set_item("exch_type", instrument.exch_type)?;
+        dict.set_item("lot_size", instrument.lot_size)?;
+        dict.set_item("list_time", &instrument.list_time)?;
+        dict.set_item("delisted", instrument.delisted)?;
+        dict.set_item("currency", instrument.currency)?;
+        dict.set_item("timezone", instrument.timezone)?;
+        let sessions: Vec<(&str, &str)> = instrument.trading_sessions.iter().map(|s| (s.open, s.close)).collect();
+        dict.set_item("trading_sessions", sessions)?;
+        dict.set_item("price_spread", instrument.price_spread)?;
+        let tick_table: Vec<(f64, f64)> = instrument.tick_table.iter().map(|b| (b.from, b.tick)).collect();
+        dict.set_item("tick_table", tick_table)?;
+        dict.set_item("is_suspended", instrument.is_suspended)?;
+        dict.set_item("enable_margin", instrument.enable_margin)?;
+        dict.set_item("long_margin_initial_ratio", instrument.long_margin_initial_ratio)?;
+        dict.set_item("short_margin_initial_ratio", instrument.short_margin_initial_ratio)?;
+        dict.set_item("shortable", instrument.shortable())?;
+        dict.set_item("short_sell_rate", instrument.short_sell_rate)?;
+        dict.set_item("short_available_volume", instrument.short_available_volume)?;
+        match &instrument.margin {
+            Some(m) => {
+                let margin = pyo3::types::PyDict::new_bound(py);
+                margin.set_item("is_long_permit", m.is_long_permit)?;
+                margin.set_item("is_short_permit", m.is_short_permit)?;
+                margin.set_item("short_pool_remain", m.short_pool_remain)?;
+                margin.set_item("short_fee_rate", m.short_fee_rate)?;
+                margin.set_item("alert_long_ratio", m.alert_long_ratio)?;
+                margin.set_item("alert_short_ratio", m.alert_short_ratio)?;
+                margin.set_item("im_long_ratio", m.im_long_ratio)?;
+                margin.set_item("im_short_ratio", m.im_short_ratio)?;
+                margin.set_item("mcm_long_ratio", m.mcm_long_ratio)?;
+                margin.set_item("mcm_short_ratio", m.mcm_short_ratio)?;
+                margin.set_item("mm_long_ratio", m.mm_long_ratio)?;
+                margin.set_item("mm_short_ratio", m.mm_short_ratio)?;
+                dict.set_item("margin", margin)?;
+            }
+            None => dict.set_item("margin", py.None())?,
+        }
+        Ok(dict.into_any().unbind())
+    }
+
+    /// `qty` of `security` rounded down to a whole number of lots.
+    fn round_qty(&self, py: Python<'_>, security: SecurityArg, qty: f64) -> PyResult<f64> {
+        let security = self.resolve_securities(vec![security])?.remove(0);
+        let client = self.get_client()?;
+        let client = &*client;
+        let cache = &self.instruments;
+
+        py.allow_threads(|| {
+            self.runtime.block_on(async {
+                cache.round_qty(client, security, qty).await
+            })
+        }).map_err(|e| e.into_py_err("Round qty failed"))
+    }
+
+    /// `price` of `security` rounded onto its tick table for an order of
+    /// `trd_side`: buys (1, 4) round down, sells (2, 3) round up, other
+    /// sides to the nearest tick. None if the tick table is unknown.
+    fn round_price(&self, py: Python<'_>, security: SecurityArg, price: f64, trd_side: i32) -> PyResult<Option<f64>> {
+        let security = self.resolve_securities(vec![security])?.remove(0);
+        let client = self.get_client()?;
+        let client = &*client;
+        let cache = &self.instruments;
+
+        py.allow_threads(|| {
+            self.runtime.block_on(async {
+                cache.round_price(client, security, price, trd_side).await
+            })
+        }).map_err(|e| e.into_py_err("Round price failed"))
+    }
+
     // ── Quote: get_plate_set ────────────────────────────────────────────
     /// Get plate set (sector list) for a market.
     /// Returns list of dicts with plate info.
@@ -1465,12 +5644,14 @@ impl PyFutuClient {
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::quote::snapshot::get_plate_set(client, market, plate_set_type).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get plate set failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get plate set failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
+            let mut checkpoint = GilCheckpoint::new(py);
             for plate in s2c.plate_info_list {
+                checkpoint.tick()?;
                 let dict = pyo3::types::PyDict::new_bound(py);
                 dict.set_item("plate_market", plate.plate.market)?;
                 dict.set_item("plate_code", &plate.plate.code)?;
@@ -1482,34 +5663,87 @@ impl PyFutuClient {
         Ok(result)
     }
 
+    /// Every listed security of a market, gathered from the securities of
+    /// its plates (see `quote::universe`). Takes one Qot_GetPlateSecurity
+    /// request per plate, which OpenD limits to 10 per 30 seconds; enable
+    /// the rate limit to pace them. Returns a list of dicts sorted by
+    /// security with market, code, alias, name, lot_size, sec_type,
+    /// list_time, exch_type, delisted and plates (plate codes).
+    #[pyo3(signature = (market, plate_set_type=0, sec_types=None, include_delisted=false))]
+    fn get_all_securities(
+        &self,
+        py: Python<'_>,
+        market: i32,
+        plate_set_type: i32,
+        sec_types: Option<Vec<i32>>,
+        include_delisted: bool,
+    ) -> PyResult<Vec<PyObject>> {
+        let client = self.get_client()?;
+        let client = &*client;
+        let aliases = self.aliases();
+        let options = crate::quote::universe::UniverseOptions { plate_set_type, sec_types, include_delisted };
+
+        let securities = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::quote::universe::get_all_securities(client, market, &options).await
+            })
+        }).map_err(|e| e.into_py_err("Get all securities failed"))?;
+
+        let names = self.names();
+        let mut checkpoint = GilCheckpoint::new(py);
+        let mut result = Vec::with_capacity(securities.len());
+        for listed in securities {
+            checkpoint.tick()?;
+            let (market, code) = &listed.security;
+            let dict = pyo3::types::PyDict::new_bound(py);
+            dict.set_item("market", market)?;
+            dict.set_item("code", code)?;
+            dict.set_item("alias", aliases.alias(*market, code))?;
+            dict.set_item("name", names.localize(*market, code, &listed.name))?;
+            dict.set_item("lot_size", listed.lot_size)?;
+            dict.set_item("sec_type", listed.sec_type)?;
+            dict.set_item("list_time", &listed.list_time)?;
+            dict.set_item("exch_type", listed.exch_type)?;
+            dict.set_item("delisted", listed.delisted)?;
+            dict.set_item("plates", &listed.plates)?;
+            result.push(dict.into_any().unbind());
+        }
+        Ok(result)
+    }
+
     // ── Quote: get_reference ────────────────────────────────────────────
     /// Get reference data (related securities) for a single security.
     /// Returns list of static info dicts.
     fn get_reference(
         &self,
         py: Python<'_>,
-        market: i32,
-        code: String,
+        security: SecurityArg,
         reference_type: i32,
     ) -> PyResult<Vec<PyObject>> {
+        let (market, code) = self.resolve_securities(vec![security])?.remove(0);
         let client = self.get_client()?;
         let client = &*client;
+        let aliases = self.aliases();
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::quote::snapshot::get_reference(client, market, code, reference_type).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get reference failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get reference failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
+            let names = self.names();
+            let mut checkpoint = GilCheckpoint::new(py);
             for info in s2c.static_info_list {
+                checkpoint.tick()?;
                 let dict = pyo3::types::PyDict::new_bound(py);
                 let basic = &info.basic;
                 let sec = &basic.security;
                 dict.set_item("market", sec.market)?;
                 dict.set_item("code", &sec.code)?;
-                dict.set_item("name", &basic.name)?;
+                dict.set_item("alias", aliases.alias(sec.market, &sec.code))?;
+                dict.set_item("name", names.localize(sec.market, &sec.code, &basic.name))?;
                 dict.set_item("lot_size", basic.lot_size)?;
                 dict.set_item("sec_type", basic.sec_type)?;
                 dict.set_item("list_time", &basic.list_time)?;
@@ -1545,16 +5779,18 @@ impl PyFutuClient {
     fn get_owner_plate(
         &self,
         py: Python<'_>,
-        securities: Vec<(i32, String)>,
+        securities: Vec<SecurityArg>,
     ) -> PyResult<Vec<PyObject>> {
+        let securities = self.resolve_securities(securities)?;
         let client = self.get_client()?;
         let client = &*client;
+        let aliases = self.aliases();
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::quote::snapshot::get_owner_plate(client, securities).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get owner plate failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get owner plate failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
@@ -1562,6 +5798,7 @@ impl PyFutuClient {
                 let dict = pyo3::types::PyDict::new_bound(py);
                 dict.set_item("market", owner.security.market)?;
                 dict.set_item("code", &owner.security.code)?;
+                dict.set_item("alias", aliases.alias(owner.security.market, &owner.security.code))?;
                 dict.set_item("name", owner.name.as_deref())?;
 
                 let plates = pyo3::types::PyList::empty_bound(py);
@@ -1583,36 +5820,52 @@ impl PyFutuClient {
 
     // ── Quote: get_option_chain ─────────────────────────────────────────
     /// Get option chain for an underlying security.
+    /// `data_filter` narrows the chain by greeks and market data: a dict of
+    /// bounds keyed `<field>_min`/`<field>_max`, where field is one of
+    /// implied_volatility (percent), delta, gamma, vega, theta, rho,
+    /// net_open_interest, open_interest and vol; None values are ignored.
     /// Returns list of dicts with strike_time and option items.
     #[allow(clippy::too_many_arguments)]
-    #[pyo3(signature = (owner_market, owner_code, begin_time, end_time, option_type=None, condition=None, index_option_type=None))]
+    #[pyo3(signature = (owner, begin_time, end_time, option_type=None, condition=None, index_option_type=None, data_filter=None))]
     fn get_option_chain(
         &self,
         py: Python<'_>,
-        owner_market: i32,
-        owner_code: String,
+        owner: SecurityArg,
         begin_time: String,
         end_time: String,
         option_type: Option<i32>,
         condition: Option<i32>,
         index_option_type: Option<i32>,
+        data_filter: Option<std::collections::HashMap<String, Option<f64>>>,
     ) -> PyResult<Vec<PyObject>> {
+        let (owner_market, owner_code) = self.resolve_securities(vec![owner])?.remove(0);
+        let data_filter = match data_filter {
+            Some(bounds) => {
+                let bounds: Vec<(String, f64)> = bounds.into_iter().filter_map(|(k, v)| Some((k, v?))).collect();
+                Some(option_data_filter(&bounds).map_err(PyValueError::new_err)?)
+            }
+            None => None,
+        };
         let client = self.get_client()?;
         let client = &*client;
+        let aliases = self.aliases();
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::quote::snapshot::get_option_chain(
                     client, owner_market, owner_code,
                     begin_time, end_time,
-                    option_type, condition, index_option_type, None,
+                    option_type, condition, index_option_type, data_filter,
                 ).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get option chain failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get option chain failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
+            let names = self.names();
+            let mut checkpoint = GilCheckpoint::new(py);
             for chain in s2c.option_chain {
+                checkpoint.tick()?;
                 let dict = pyo3::types::PyDict::new_bound(py);
                 dict.set_item("strike_time", &chain.strike_time)?;
                 dict.set_item("strike_timestamp", chain.strike_timestamp)?;
@@ -1624,7 +5877,8 @@ impl PyFutuClient {
                         let cd = pyo3::types::PyDict::new_bound(py);
                         cd.set_item("market", call.basic.security.market)?;
                         cd.set_item("code", &call.basic.security.code)?;
-                        cd.set_item("name", &call.basic.name)?;
+                        cd.set_item("alias", aliases.alias(call.basic.security.market, &call.basic.security.code))?;
+                        cd.set_item("name", names.localize(call.basic.security.market, &call.basic.security.code, &call.basic.name))?;
                         cd.set_item("lot_size", call.basic.lot_size)?;
                         cd.set_item("sec_type", call.basic.sec_type)?;
                         if let Some(ref opt) = call.option_ex_data {
@@ -1638,7 +5892,8 @@ impl PyFutuClient {
                         let pd = pyo3::types::PyDict::new_bound(py);
                         pd.set_item("market", put.basic.security.market)?;
                         pd.set_item("code", &put.basic.security.code)?;
-                        pd.set_item("name", &put.basic.name)?;
+                        pd.set_item("alias", aliases.alias(put.basic.security.market, &put.basic.security.code))?;
+                        pd.set_item("name", names.localize(put.basic.security.market, &put.basic.security.code, &put.basic.name))?;
                         pd.set_item("lot_size", put.basic.lot_size)?;
                         pd.set_item("sec_type", put.basic.sec_type)?;
                         if let Some(ref opt) = put.option_ex_data {
@@ -1670,10 +5925,11 @@ impl PyFutuClient {
         num: i32,
         sort_field: i32,
         ascend: bool,
-        owner: Option<(i32, String)>,
+        owner: Option<SecurityArg>,
         type_list: Option<Vec<i32>>,
         issuer_list: Option<Vec<i32>>,
     ) -> PyResult<PyObject> {
+        let owner = owner.map(|o| self.resolve_securities(vec![o])).transpose()?.map(|mut o| o.remove(0));
         let client = self.get_client()?;
         let client = &*client;
 
@@ -1683,8 +5939,8 @@ impl PyFutuClient {
                     client, begin, num, sort_field, ascend,
                     owner, type_list.unwrap_or_default(), issuer_list.unwrap_or_default(),
                 ).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get warrant failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get warrant failed"))?;
 
         let result = pyo3::types::PyDict::new_bound(py);
         if let Some(s2c) = response.s2c {
@@ -1692,56 +5948,11 @@ impl PyFutuClient {
             result.set_item("all_count", s2c.all_count)?;
 
             let data_list = pyo3::types::PyList::empty_bound(py);
+            let names = self.names();
+            let mut checkpoint = GilCheckpoint::new(py);
             for w in &s2c.warrant_data_list {
-                let d = pyo3::types::PyDict::new_bound(py);
-                d.set_item("stock_market", w.stock.market)?;
-                d.set_item("stock_code", &w.stock.code)?;
-                d.set_item("owner_market", w.owner.market)?;
-                d.set_item("owner_code", &w.owner.code)?;
-                d.set_item("type", w.r#type)?;
-                d.set_item("issuer", w.issuer)?;
-                d.set_item("name", &w.name)?;
-                d.set_item("maturity_time", &w.maturity_time)?;
-                d.set_item("maturity_timestamp", w.maturity_timestamp)?;
-                d.set_item("list_time", &w.list_time)?;
-                d.set_item("list_timestamp", w.list_timestamp)?;
-                d.set_item("last_trade_time", &w.last_trade_time)?;
-                d.set_item("last_trade_timestamp", w.last_trade_timestamp)?;
-                d.set_item("recovery_price", w.recovery_price)?;
-                d.set_item("strike_price", w.strike_price)?;
-                d.set_item("cur_price", w.cur_price)?;
-                d.set_item("last_close_price", w.last_close_price)?;
-                d.set_item("price_change_val", w.price_change_val)?;
-                d.set_item("change_rate", w.change_rate)?;
-                d.set_item("volume", w.volume)?;
-                d.set_item("turnover", w.turnover)?;
-                d.set_item("premium", w.premium)?;
-                d.set_item("break_even_point", w.break_even_point)?;
-                d.set_item("conversion_ratio", w.conversion_ratio)?;
-                d.set_item("conversion_price", w.conversion_price)?;
-                d.set_item("lot_size", w.lot_size)?;
-                d.set_item("leverage", w.leverage)?;
-                d.set_item("ipop", w.ipop)?;
-                d.set_item("effective_leverage", w.effective_leverage)?;
-                d.set_item("score", w.score)?;
-                d.set_item("status", w.status)?;
-                d.set_item("bid_price", w.bid_price)?;
-                d.set_item("ask_price", w.ask_price)?;
-                d.set_item("bid_vol", w.bid_vol)?;
-                d.set_item("ask_vol", w.ask_vol)?;
-                d.set_item("high_price", w.high_price)?;
-                d.set_item("low_price", w.low_price)?;
-                d.set_item("implied_volatility", w.implied_volatility)?;
-                d.set_item("delta", w.delta)?;
-                d.set_item("street_rate", w.street_rate)?;
-                d.set_item("street_vol", w.street_vol)?;
-                d.set_item("amplitude", w.amplitude)?;
-                d.set_item("issue_size", w.issue_size)?;
-                d.set_item("upper_strike_price", w.upper_strike_price)?;
-                d.set_item("lower_strike_price", w.lower_strike_price)?;
-                d.set_item("in_line_price_status", w.in_line_price_status)?;
-                d.set_item("price_recovery_ratio", w.price_recovery_ratio)?;
-                data_list.append(d)?;
+                checkpoint.tick()?;
+                data_list.append(warrant_to_dict(py, &names, w)?)?;
             }
             result.set_item("data", data_list)?;
         }
@@ -1751,22 +5962,22 @@ impl PyFutuClient {
     // ── Quote: get_capital_flow ──────────────────────────────────────────
     /// Get capital flow for a single security.
     /// Returns a dict with flow_item_list.
-    #[pyo3(signature = (market, code, period_type=None))]
+    #[pyo3(signature = (security, period_type=None))]
     fn get_capital_flow(
         &self,
         py: Python<'_>,
-        market: i32,
-        code: String,
+        security: SecurityArg,
         period_type: Option<i32>,
     ) -> PyResult<PyObject> {
+        let (market, code) = self.resolve_securities(vec![security])?.remove(0);
         let client = self.get_client()?;
         let client = &*client;
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::quote::snapshot::get_capital_flow(client, market, code, period_type).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get capital flow failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get capital flow failed"))?;
 
         let dict = pyo3::types::PyDict::new_bound(py);
         if let Some(s2c) = response.s2c {
@@ -1774,7 +5985,9 @@ impl PyFutuClient {
             dict.set_item("last_valid_timestamp", s2c.last_valid_timestamp)?;
 
             let flow_list = pyo3::types::PyList::empty_bound(py);
+            let mut checkpoint = GilCheckpoint::new(py);
             for item in &s2c.flow_item_list {
+                checkpoint.tick()?;
                 let d = pyo3::types::PyDict::new_bound(py);
                 d.set_item("in_flow", item.in_flow)?;
                 d.set_item("time", item.time.as_deref())?;
@@ -1791,23 +6004,97 @@ impl PyFutuClient {
         Ok(dict.into_any().unbind())
     }
 
+    /// Download daily (period_type=2), weekly (3) or monthly (4) capital
+    /// flow of several securities between begin_time and end_time
+    /// ("YYYY-MM-DD"), in chunks of chunk_days with request_interval seconds
+    /// between requests. Returns a dict with rows (one merged table, by
+    /// security then time), failed (securities whose download failed, with
+    /// error and the begin_time to download them again from) and requests.
+    #[pyo3(signature = (securities, begin_time, end_time, period_type=2, chunk_days=365, request_interval=1.0))]
+    #[allow(clippy::too_many_arguments)]
+    fn download_capital_flow(
+        &self,
+        py: Python<'_>,
+        securities: Vec<SecurityArg>,
+        begin_time: String,
+        end_time: String,
+        period_type: i32,
+        chunk_days: i64,
+        request_interval: f64,
+    ) -> PyResult<PyObject> {
+        let securities = self.resolve_securities(securities)?;
+        if !request_interval.is_finite() || request_interval < 0.0 {
+            return Err(PyValueError::new_err("request_interval must be a non-negative number of seconds"));
+        }
+        let options = crate::quote::capital_flow::CapitalFlowDownloadOptions {
+            chunk_days,
+            request_interval: std::time::Duration::from_secs_f64(request_interval),
+        };
+        let client = self.get_client()?;
+        let client = &*client;
+
+        let download = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::quote::capital_flow::download_capital_flow(
+                    client, &securities, period_type, &begin_time, &end_time, &options,
+                ).await
+            })
+        });
+
+        let aliases = self.aliases();
+        let mut rows = Vec::with_capacity(download.rows.len());
+        let mut checkpoint = GilCheckpoint::new(py);
+        for row in &download.rows {
+            checkpoint.tick()?;
+            let d = pyo3::types::PyDict::new_bound(py);
+            d.set_item("market", row.market)?;
+            d.set_item("code", &row.code)?;
+            d.set_item("alias", aliases.alias(row.market, &row.code))?;
+            d.set_item("time", &row.time)?;
+            d.set_item("timestamp", row.timestamp)?;
+            d.set_item("in_flow", row.in_flow)?;
+            d.set_item("main_in_flow", row.main_in_flow)?;
+            d.set_item("super_in_flow", row.super_in_flow)?;
+            d.set_item("big_in_flow", row.big_in_flow)?;
+            d.set_item("mid_in_flow", row.mid_in_flow)?;
+            d.set_item("sml_in_flow", row.sml_in_flow)?;
+            rows.push(d.into_any().unbind());
+        }
+        let mut failed = Vec::with_capacity(download.failed.len());
+        for failure in &download.failed {
+            let (market, code) = &failure.security;
+            let d = pyo3::types::PyDict::new_bound(py);
+            d.set_item("market", market)?;
+            d.set_item("code", code)?;
+            d.set_item("alias", aliases.alias(*market, code))?;
+            d.set_item("begin_time", &failure.begin_time)?;
+            d.set_item("error", &failure.error)?;
+            failed.push(d.into_any().unbind());
+        }
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("rows", rows)?;
+        dict.set_item("failed", failed)?;
+        dict.set_item("requests", download.requests)?;
+        Ok(dict.into_any().unbind())
+    }
+
     // ── Quote: get_capital_distribution ──────────────────────────────────
     /// Get capital distribution for a single security.
     /// Returns a dict with capital in/out fields.
     fn get_capital_distribution(
         &self,
         py: Python<'_>,
-        market: i32,
-        code: String,
+        security: SecurityArg,
     ) -> PyResult<PyObject> {
+        let (market, code) = self.resolve_securities(vec![security])?.remove(0);
         let client = self.get_client()?;
         let client = &*client;
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::quote::snapshot::get_capital_distribution(client, market, code).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get capital distribution failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get capital distribution failed"))?;
 
         let dict = pyo3::types::PyDict::new_bound(py);
         if let Some(s2c) = response.s2c {
@@ -1835,22 +6122,27 @@ impl PyFutuClient {
     ) -> PyResult<Vec<PyObject>> {
         let client = self.get_client()?;
         let client = &*client;
+        let aliases = self.aliases();
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::quote::snapshot::get_user_security(client, group_name).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get user security failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get user security failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
+            let names = self.names();
+            let mut checkpoint = GilCheckpoint::new(py);
             for info in s2c.static_info_list {
+                checkpoint.tick()?;
                 let dict = pyo3::types::PyDict::new_bound(py);
                 let basic = &info.basic;
                 let sec = &basic.security;
                 dict.set_item("market", sec.market)?;
                 dict.set_item("code", &sec.code)?;
-                dict.set_item("name", &basic.name)?;
+                dict.set_item("alias", aliases.alias(sec.market, &sec.code))?;
+                dict.set_item("name", names.localize(sec.market, &sec.code, &basic.name))?;
                 dict.set_item("lot_size", basic.lot_size)?;
                 dict.set_item("sec_type", basic.sec_type)?;
                 dict.set_item("list_time", &basic.list_time)?;
@@ -1871,21 +6163,118 @@ impl PyFutuClient {
         py: Python<'_>,
         group_name: String,
         op: i32,
-        securities: Vec<(i32, String)>,
+        securities: Vec<SecurityArg>,
     ) -> PyResult<PyObject> {
+        let securities = self.resolve_securities(securities)?;
         let client = self.get_client()?;
         let client = &*client;
 
         py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::quote::snapshot::modify_user_security(client, group_name, op, securities).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Modify user security failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Modify user security failed"))?;
 
         let dict = pyo3::types::PyDict::new_bound(py);
         Ok(dict.into_any().unbind())
     }
 
+    // ── Quote: price reminders ──────────────────────────────────────────
+    /// Add, modify, enable, disable or delete a price reminder kept by OpenD.
+    /// op: 1=add, 2=delete, 3=enable, 4=disable, 5=modify, 6=delete all of
+    /// the security. key is required by every op but add and delete all.
+    /// reminder_type and value are required by add and modify; values of
+    /// percentage types are in percent (20 for 20%).
+    /// freq: PriceReminderFreq; sessions: PriceReminderMarketStatus values.
+    /// Returns the key of the reminder. Triggered reminders arrive as pushes
+    /// of proto 3019 on `start_push()` channels.
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (security, op, key=None, reminder_type=None, value=None, freq=None, note=None, sessions=None))]
+    fn set_price_reminder(
+        &self,
+        py: Python<'_>,
+        security: SecurityArg,
+        op: i32,
+        key: Option<i64>,
+        reminder_type: Option<i32>,
+        value: Option<f64>,
+        freq: Option<i32>,
+        note: Option<String>,
+        sessions: Option<Vec<i32>>,
+    ) -> PyResult<i64> {
+        use crate::quote::reminder::{ReminderSpec, OP_ADD, OP_DEL_ALL, OP_MODIFY};
+
+        let security = self.resolve_securities(vec![security])?.remove(0);
+        let spec = match (reminder_type, value) {
+            (Some(reminder_type), Some(value)) => {
+                Some(ReminderSpec { reminder_type, value, freq, note, sessions: sessions.unwrap_or_default() })
+            }
+            _ if op == OP_ADD || op == OP_MODIFY => {
+                return Err(PyValueError::new_err("reminder_type and value are required to add or modify a reminder"));
+            }
+            _ => None,
+        };
+        if key.is_none() && op != OP_ADD && op != OP_DEL_ALL {
+            return Err(PyValueError::new_err(format!("key is required by op {}", op)));
+        }
+        let client = self.get_client()?;
+        let client = &*client;
+
+        py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::quote::reminder::set_price_reminder(client, security, op, key, spec).await
+            })
+        }).map_err(|e| e.into_py_err("Set price reminder failed"))
+    }
+
+    /// Price reminders of a security, or of every security of market
+    /// (QotMarket) when no security is given. Returns a list of dicts with
+    /// market, code, alias, name, key, reminder_type, value, note, freq,
+    /// is_enable and sessions, one per reminder.
+    #[pyo3(signature = (security=None, market=None))]
+    fn get_price_reminder(
+        &self,
+        py: Python<'_>,
+        security: Option<SecurityArg>,
+        market: Option<i32>,
+    ) -> PyResult<Vec<PyObject>> {
+        let security = security.map(|s| self.resolve_securities(vec![s])).transpose()?.map(|mut v| v.remove(0));
+        if security.is_none() && market.is_none() {
+            return Err(PyValueError::new_err("Either security or market is required"));
+        }
+        let client = self.get_client()?;
+        let client = &*client;
+        let aliases = self.aliases();
+        let names = self.names();
+
+        let reminders = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::quote::reminder::get_price_reminder(client, security, market).await
+            })
+        }).map_err(|e| e.into_py_err("Get price reminder failed"))?;
+
+        let mut result = Vec::new();
+        for reminder in &reminders {
+            let sec = &reminder.security;
+            for item in &reminder.item_list {
+                let dict = pyo3::types::PyDict::new_bound(py);
+                dict.set_item("market", sec.market)?;
+                dict.set_item("code", &sec.code)?;
+                dict.set_item("alias", aliases.alias(sec.market, &sec.code))?;
+                dict.set_item("name", names.localize_opt(sec.market, &sec.code, reminder.name.as_deref()))?;
+                dict.set_item("key", item.key)?;
+                dict.set_item("reminder_type", item.r#type)?;
+                dict.set_item("value", item.value)?;
+                dict.set_item("note", &item.note)?;
+                dict.set_item("freq", item.freq)?;
+                dict.set_item("is_enable", item.is_enable)?;
+                dict.set_item("sessions", &item.reminder_session_list)?;
+                result.push(dict.into_any().unbind());
+            }
+        }
+        Ok(result)
+    }
+
     // ── Quote: get_code_change ──────────────────────────────────────────
     /// Get code change info for securities.
     /// Returns list of dicts with code change details.
@@ -1893,27 +6282,32 @@ impl PyFutuClient {
     fn get_code_change(
         &self,
         py: Python<'_>,
-        securities: Vec<(i32, String)>,
+        securities: Vec<SecurityArg>,
         type_list: Option<Vec<i32>>,
     ) -> PyResult<Vec<PyObject>> {
+        let securities = self.resolve_securities(securities)?;
         let client = self.get_client()?;
         let client = &*client;
+        let aliases = self.aliases();
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::quote::snapshot::get_code_change(
                     client, securities, type_list.unwrap_or_default(),
                 ).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get code change failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get code change failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
+            let mut checkpoint = GilCheckpoint::new(py);
             for info in s2c.code_change_list {
+                checkpoint.tick()?;
                 let dict = pyo3::types::PyDict::new_bound(py);
                 dict.set_item("type", info.r#type)?;
                 dict.set_item("market", info.security.market)?;
                 dict.set_item("code", &info.security.code)?;
+                dict.set_item("alias", aliases.alias(info.security.market, &info.security.code))?;
                 dict.set_item("related_market", info.related_security.market)?;
                 dict.set_item("related_code", &info.related_security.code)?;
                 dict.set_item("public_time", info.public_time.as_deref())?;
@@ -1942,40 +6336,15 @@ impl PyFutuClient {
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::quote::snapshot::get_ipo_list(client, market).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get IPO list failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get IPO list failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
-            for ipo in s2c.ipo_list {
-                let dict = pyo3::types::PyDict::new_bound(py);
-                dict.set_item("market", ipo.basic.security.market)?;
-                dict.set_item("code", &ipo.basic.security.code)?;
-                dict.set_item("name", &ipo.basic.name)?;
-                dict.set_item("list_time", ipo.basic.list_time.as_deref())?;
-                dict.set_item("list_timestamp", ipo.basic.list_timestamp)?;
-
-                if let Some(ref hk) = ipo.hk_ex_data {
-                    dict.set_item("ipo_price_min", hk.ipo_price_min)?;
-                    dict.set_item("ipo_price_max", hk.ipo_price_max)?;
-                    dict.set_item("list_price", hk.list_price)?;
-                    dict.set_item("lot_size", hk.lot_size)?;
-                    dict.set_item("entrance_price", hk.entrance_price)?;
-                    dict.set_item("is_subscribe_status", hk.is_subscribe_status)?;
-                }
-                if let Some(ref us) = ipo.us_ex_data {
-                    dict.set_item("ipo_price_min", us.ipo_price_min)?;
-                    dict.set_item("ipo_price_max", us.ipo_price_max)?;
-                    dict.set_item("issue_size", us.issue_size)?;
-                }
-                if let Some(ref cn) = ipo.cn_ex_data {
-                    dict.set_item("apply_code", &cn.apply_code)?;
-                    dict.set_item("issue_size", cn.issue_size)?;
-                    dict.set_item("ipo_price", cn.ipo_price)?;
-                    dict.set_item("winning_ratio", cn.winning_ratio)?;
-                }
-
-                result.push(dict.into_any().unbind());
+            let mut checkpoint = GilCheckpoint::new(py);
+            for ipo in &s2c.ipo_list {
+                checkpoint.tick()?;
+                result.push(ipo_to_dict(py, &self.names(), &self.aliases(), ipo)?.into_any().unbind());
             }
         }
         Ok(result)
@@ -1987,24 +6356,30 @@ impl PyFutuClient {
     fn get_future_info(
         &self,
         py: Python<'_>,
-        securities: Vec<(i32, String)>,
+        securities: Vec<SecurityArg>,
     ) -> PyResult<Vec<PyObject>> {
+        let securities = self.resolve_securities(securities)?;
         let client = self.get_client()?;
         let client = &*client;
+        let aliases = self.aliases();
 
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::quote::snapshot::get_future_info(client, securities).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get future info failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get future info failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
+            let names = self.names();
+            let mut checkpoint = GilCheckpoint::new(py);
             for info in s2c.future_info_list {
+                checkpoint.tick()?;
                 let dict = pyo3::types::PyDict::new_bound(py);
-                dict.set_item("name", &info.name)?;
+                dict.set_item("name", names.localize(info.security.market, &info.security.code, &info.name))?;
                 dict.set_item("market", info.security.market)?;
                 dict.set_item("code", &info.security.code)?;
+                dict.set_item("alias", aliases.alias(info.security.market, &info.security.code))?;
                 dict.set_item("last_trade_time", &info.last_trade_time)?;
                 dict.set_item("last_trade_timestamp", info.last_trade_timestamp)?;
                 if let Some(ref owner) = info.owner {
@@ -2051,8 +6426,9 @@ impl PyFutuClient {
         market: i32,
         begin_time: String,
         end_time: String,
-        security: Option<(i32, String)>,
+        security: Option<SecurityArg>,
     ) -> PyResult<Vec<PyObject>> {
+        let security = security.map(|s| self.resolve_securities(vec![s])).transpose()?.map(|mut s| s.remove(0));
         let client = self.get_client()?;
         let client = &*client;
 
@@ -2061,12 +6437,14 @@ impl PyFutuClient {
                 crate::quote::snapshot::request_trade_date(
                     client, market, begin_time, end_time, security,
                 ).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Request trade date failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Request trade date failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
+            let mut checkpoint = GilCheckpoint::new(py);
             for td in s2c.trade_date_list {
+                checkpoint.tick()?;
                 let dict = pyo3::types::PyDict::new_bound(py);
                 dict.set_item("time", &td.time)?;
                 dict.set_item("timestamp", td.timestamp)?;
@@ -2077,17 +6455,42 @@ impl PyFutuClient {
         Ok(result)
     }
 
+    /// Sessions of QotMarket `market` on `date` ("YYYY-MM-DD"), with
+    /// half days from the exchange calendar where OpenD has one. Returns a
+    /// dict with date, trade_date_type ("whole", "morning", "afternoon" or
+    /// None on a non-trading day), timezone, sessions (list of (open, close)
+    /// "HH:MM" tuples in exchange local time) and exchange_calendar.
+    fn session_times(&self, py: Python<'_>, market: i32, date: String) -> PyResult<PyObject> {
+        let client = self.get_client()?;
+        let client = &*client;
+
+        let day = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::quote::sessions::session_times(client, market, &date).await
+            })
+        }).map_err(|e| e.into_py_err("Session times failed"))?;
+
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("date", &day.date)?;
+        dict.set_item("trade_date_type", day.trade_date_type.map(|t| t.as_str()))?;
+        dict.set_item("timezone", day.timezone)?;
+        let sessions: Vec<(&str, &str)> = day.sessions.iter().map(|s| (s.open, s.close)).collect();
+        dict.set_item("sessions", sessions)?;
+        dict.set_item("exchange_calendar", day.exchange_calendar)?;
+        Ok(dict.into_any().unbind())
+    }
+
     // ── Quote: get_option_expiration_date ────────────────────────────────
     /// Get option expiration dates for an underlying security.
     /// Returns list of dicts with expiration date info.
-    #[pyo3(signature = (owner_market, owner_code, index_option_type=None))]
+    #[pyo3(signature = (owner, index_option_type=None))]
     fn get_option_expiration_date(
         &self,
         py: Python<'_>,
-        owner_market: i32,
-        owner_code: String,
+        owner: SecurityArg,
         index_option_type: Option<i32>,
     ) -> PyResult<Vec<PyObject>> {
+        let (owner_market, owner_code) = self.resolve_securities(vec![owner])?.remove(0);
         let client = self.get_client()?;
         let client = &*client;
 
@@ -2096,12 +6499,14 @@ impl PyFutuClient {
                 crate::quote::snapshot::get_option_expiration_date(
                     client, owner_market, owner_code, index_option_type,
                 ).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get option expiration date failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get option expiration date failed"))?;
 
         let mut result = Vec::new();
         if let Some(s2c) = response.s2c {
+            let mut checkpoint = GilCheckpoint::new(py);
             for date in s2c.date_list {
+                checkpoint.tick()?;
                 let dict = pyo3::types::PyDict::new_bound(py);
                 dict.set_item("strike_time", date.strike_time.as_deref())?;
                 dict.set_item("strike_timestamp", date.strike_timestamp)?;
@@ -2126,8 +6531,8 @@ impl PyFutuClient {
         let response = py.allow_threads(|| {
             self.runtime.block_on(async {
                 crate::client::init::get_global_state(client, user_id).await
-            }).map_err(|e| e.to_string())
-        }).map_err(|e| PyRuntimeError::new_err(format!("Get global state failed: {}", e)))?;
+            })
+        }).map_err(|e| e.into_py_err("Get global state failed"))?;
 
         let dict = pyo3::types::PyDict::new_bound(py);
         if let Some(s2c) = response.s2c {
@@ -2148,4 +6553,173 @@ impl PyFutuClient {
         }
         Ok(dict.into_any().unbind())
     }
+
+    /// Poll GetGlobalState every interval_secs on the current connection
+    /// and report market state changes to `poll_market_events()`. Does
+    /// nothing if the poller already runs; it stops on disconnect.
+    #[pyo3(signature = (interval_secs=30.0))]
+    fn start_market_session(&self, interval_secs: f64) -> PyResult<()> {
+        let client = self.get_client()?;
+        let mut market_events = self.market_events.lock();
+        if market_events.is_some() {
+            return Ok(());
+        }
+        *market_events = Some(Arc::new(Mutex::new(self.market_session.subscribe())));
+
+        let session = Arc::clone(&self.market_session);
+        let interval = std::time::Duration::from_secs_f64(interval_secs.max(1.0));
+        let policy = RestartPolicy::OnPanic {
+            max_restarts: 3,
+            backoff: std::time::Duration::from_secs(1),
+        };
+        let _guard = self.runtime.enter();
+        self.tasks.spawn("market_session", policy, move || {
+            crate::quote::market_session::run_market_session(Arc::clone(&client), Arc::clone(&session), interval)
+        });
+        Ok(())
+    }
+
+    /// Query GetGlobalState now and apply it to the market states.
+    /// Returns the changes, in the `poll_market_events()` layout; they are
+    /// also reported to `poll_market_events()`.
+    fn refresh_market_session(&self, py: Python<'_>) -> PyResult<Vec<PyObject>> {
+        let client = self.get_client()?;
+        let client = &*client;
+        let session = &*self.market_session;
+
+        let events = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::quote::market_session::refresh(client, session).await
+            })
+        }).map_err(|e| e.into_py_err("Refresh market session failed"))?;
+        events.iter().map(|event| market_session_event_to_dict(py, event)).collect()
+    }
+
+    /// Latest market states, keyed by market ("hk", "us", "sh", "sz",
+    /// "hk_future", "us_future", "sg_future", "jp_future"). Each value is a
+    /// dict with state (Qot_Common.QotMarketState) and phase ("pre_open",
+    /// "open", "break", "closing", "extended" or "closed"). Empty until the
+    /// first refresh.
+    fn market_states(&self, py: Python<'_>) -> PyResult<PyObject> {
+        let dict = pyo3::types::PyDict::new_bound(py);
+        for (market, state) in self.market_session.states() {
+            let d = pyo3::types::PyDict::new_bound(py);
+            d.set_item("state", state)?;
+            d.set_item("phase", MarketPhase::from_state(state).as_str())?;
+            dict.set_item(market.as_str(), d)?;
+        }
+        Ok(dict.into_any().unbind())
+    }
+
+    /// Poll up to `max_events` market state changes of the poller started
+    /// by `start_market_session()`. Waits up to timeout_ms for the first
+    /// event, then takes whatever else is queued. Each event is a dict with
+    /// market, prev_state and prev_phase (None for the first answer), state,
+    /// phase and timestamp.
+    #[pyo3(signature = (timeout_ms=100, max_events=256))]
+    fn poll_market_events(&self, py: Python<'_>, timeout_ms: u64, max_events: usize) -> PyResult<Vec<PyObject>> {
+        let Some(rx) = self.market_events.lock().as_ref().map(Arc::clone) else {
+            return Ok(Vec::new());
+        };
+        if max_events == 0 {
+            return Ok(Vec::new());
+        }
+
+        let timeout = std::time::Duration::from_millis(timeout_ms);
+        let events = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                let mut guard = rx.lock().await;
+                let mut events = Vec::new();
+                if let Ok(Some(first)) = tokio::time::timeout(timeout, guard.recv()).await {
+                    events.push(first);
+                    while events.len() < max_events {
+                        match guard.try_recv() {
+                            Ok(event) => events.push(event),
+                            Err(_) => break,
+                        }
+                    }
+                }
+                events
+            })
+        });
+
+        events.iter().map(|event| market_session_event_to_dict(py, event)).collect()
+    }
+
+    /// Get the logged-in user's info from Futu OpenD (proto 1005).
+    /// Returns a dict with the API level, quote rights per market
+    /// (Qot_Common.QotRight values) and subscription/history K-line quotas.
+    #[pyo3(signature = (flag=None))]
+    fn get_user_info(&self, py: Python<'_>, flag: Option<i32>) -> PyResult<PyObject> {
+        let client = self.get_client()?;
+        let client = &*client;
+
+        let s2c = py.allow_threads(|| {
+            self.runtime.block_on(async {
+                crate::client::init::get_user_info(client, flag).await
+            })
+        }).map_err(|e| e.into_py_err("Get user info failed"))?;
+
+        let dict = pyo3::types::PyDict::new_bound(py);
+        dict.set_item("user_id", s2c.user_id)?;
+        dict.set_item("nick_name", s2c.nick_name)?;
+        dict.set_item("api_level", s2c.api_level)?;
+        dict.set_item("hk_qot_right", s2c.hk_qot_right)?;
+        dict.set_item("us_qot_right", s2c.us_qot_right)?;
+        dict.set_item("cn_qot_right", s2c.cn_qot_right)?;
+        dict.set_item("sh_qot_right", s2c.sh_qot_right)?;
+        dict.set_item("sz_qot_right", s2c.sz_qot_right)?;
+        dict.set_item("hk_option_qot_right", s2c.hk_option_qot_right)?;
+        dict.set_item("hk_future_qot_right", s2c.hk_future_qot_right)?;
+        dict.set_item("us_option_qot_right", s2c.us_option_qot_right)?;
+        dict.set_item("has_us_option_qot_right", s2c.has_us_option_qot_right)?;
+        dict.set_item("us_future_qot_right", s2c.us_future_qot_right)?;
+        dict.set_item("us_index_qot_right", s2c.us_index_qot_right)?;
+        dict.set_item("us_otc_qot_right", s2c.us_otc_qot_right)?;
+        dict.set_item("sg_future_qot_right", s2c.sg_future_qot_right)?;
+        dict.set_item("jp_future_qot_right", s2c.jp_future_qot_right)?;
+        dict.set_item("sub_quota", s2c.sub_quota)?;
+        dict.set_item("history_kl_quota", s2c.history_kl_quota)?;
+        dict.set_item("is_need_agree_disclaimer", s2c.is_need_agree_disclaimer)?;
+        dict.set_item("user_attribution", s2c.user_attribution)?;
+        Ok(dict.into_any().unbind())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_account_uses_defaults() {
+        let defaults = Some((0, 123, 1));
+        assert_eq!(resolve_account(defaults, None, None, None), Ok((0, 123, 1)));
+    }
+
+    #[test]
+    fn test_resolve_account_per_field_override() {
+        let defaults = Some((0, 123, 1));
+        assert_eq!(resolve_account(defaults, Some(1), None, Some(2)), Ok((1, 123, 2)));
+        assert_eq!(resolve_account(None, Some(1), Some(9), Some(2)), Ok((1, 9, 2)));
+    }
+
+    #[test]
+    fn test_option_data_filter() {
+        let bounds = |pairs: &[(&str, f64)]| pairs.iter().map(|(k, v)| (k.to_string(), *v)).collect::<Vec<_>>();
+        let filter = option_data_filter(&bounds(&[("delta_min", 0.3), ("delta_max", 0.7), ("open_interest_min", 100.0)])).unwrap();
+        assert_eq!((filter.delta_min, filter.delta_max), (Some(0.3), Some(0.7)));
+        assert_eq!(filter.open_interest_min, Some(100.0));
+        assert_eq!(filter.implied_volatility_max, None);
+
+        let err = option_data_filter(&bounds(&[("delta", 0.5)])).unwrap_err();
+        assert_eq!(err, "Unknown option data filter key: delta");
+        let err = option_data_filter(&bounds(&[("vega_min", 0.2), ("vega_max", 0.1)])).unwrap_err();
+        assert_eq!(err, "Option data filter vega_min 0.2 is above vega_max 0.1");
+    }
+
+    #[test]
+    fn test_resolve_account_missing_field() {
+        let err = resolve_account(None, Some(1), None, Some(2)).unwrap_err();
+        assert_eq!(err, "acc_id not given and no default account set");
+    }
 }
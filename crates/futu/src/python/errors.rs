@@ -0,0 +1,158 @@
+//! Python exception types raised by the client.
+//!
+//! Failed calls raise a subclass of `FutuError` (itself a `RuntimeError`)
+//! chosen by `IntoPyErr` from the kind of the Rust error, so callers can
+//! tell a lost connection from a rejected request.
+
+// pyo3 0.22's create_exception! checks the `gil-refs` feature in the calling crate
+#![allow(unexpected_cfgs)]
+
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::client::connection::ConnectionError;
+use crate::client::init::InitError;
+use crate::client::pool::PoolError;
+use crate::quote::instrument::InstrumentError;
+use crate::quote::subscribe::QuoteError;
+use crate::trade::account::TradeError;
+
+pyo3::create_exception!(_rust, FutuError, PyRuntimeError, "Base class of the errors of failed client calls.");
+
+pyo3::create_exception!(
+    _rust,
+    FutuConnectionError,
+    FutuError,
+    "The connection to OpenD failed or was closed."
+);
+
+pyo3::create_exception!(
+    _rust,
+    FutuServerError,
+    FutuError,
+    "OpenD answered with a failure. Attributes: ret_type, err_code (None if OpenD gave none)."
+);
+
+pyo3::create_exception!(_rust, FutuDecodeError, FutuError, "A response or push could not be decrypted or decoded.");
+
+pyo3::create_exception!(_rust, FutuTimeoutError, FutuError, "Connecting to or talking with OpenD timed out.");
+
+pyo3::create_exception!(
+    _rust,
+    SecurityFirmMismatchError,
+    PyValueError,
+    "Explicit security_firm does not match the account's security firm."
+);
+
+pyo3::create_exception!(
+    _rust,
+    SessionPreemptedError,
+    PyRuntimeError,
+    "Trading is paused because another login of the account preempted the session."
+);
+
+pyo3::create_exception!(
+    _rust,
+    SubscriptionRejectedError,
+    PyRuntimeError,
+    "OpenD rejected some securities of a subscribe call; the others were applied. args: (message, results)."
+);
+
+pyo3::create_exception!(
+    _rust,
+    ThrottledError,
+    PyRuntimeError,
+    "OpenD was busy or over a request frequency limit. args: (message, retry_after_secs)."
+);
+
+/// Conversion of a client error into the Python exception of its kind, with
+/// `context` (e.g. "Place order failed") prefixed to the message.
+pub trait IntoPyErr {
+    fn into_py_err(self, context: &str) -> PyErr;
+}
+
+impl IntoPyErr for ConnectionError {
+    fn into_py_err(self, context: &str) -> PyErr {
+        let msg = format!("{}: {}", context, self);
+        match self {
+            ConnectionError::ConnectTimeout { .. } | ConnectionError::ReadTimeout(_) => FutuTimeoutError::new_err(msg),
+            ConnectionError::Io(ref e) if e.kind() == std::io::ErrorKind::TimedOut => FutuTimeoutError::new_err(msg),
+            ConnectionError::Decryption(_) => FutuDecodeError::new_err(msg),
+            ConnectionError::RateLimited { retry_after, .. } => ThrottledError::new_err((msg, retry_after.as_secs_f64())),
+            _ => FutuConnectionError::new_err(msg),
+        }
+    }
+}
+
+impl IntoPyErr for QuoteError {
+    fn into_py_err(self, context: &str) -> PyErr {
+        let msg = format!("{}: {}", context, self);
+        match self {
+            QuoteError::Connection(e) => e.into_py_err(context),
+            QuoteError::Decode(_) => FutuDecodeError::new_err(msg),
+            QuoteError::Server { ret_type, err_code, .. } => server_err(msg, ret_type, err_code),
+            QuoteError::Throttled { retry_after, .. } => ThrottledError::new_err((msg, retry_after.as_secs_f64())),
+            QuoteError::UnsupportedKlType(_) => PyValueError::new_err(msg),
+            _ => FutuError::new_err(msg),
+        }
+    }
+}
+
+impl IntoPyErr for TradeError {
+    fn into_py_err(self, context: &str) -> PyErr {
+        let msg = format!("{}: {}", context, self);
+        match self {
+            TradeError::Connection(e) => e.into_py_err(context),
+            TradeError::Decode(_) => FutuDecodeError::new_err(msg),
+            TradeError::Server { ret_type, err_code, .. } => server_err(msg, ret_type, err_code),
+            TradeError::Throttled { retry_after, .. } => ThrottledError::new_err((msg, retry_after.as_secs_f64())),
+            TradeError::SessionPreempted(_) => SessionPreemptedError::new_err(msg),
+            TradeError::SecurityFirmMismatch { .. } => SecurityFirmMismatchError::new_err(msg),
+            TradeError::InvalidRange(_) | TradeError::AmbiguousSecurityFirm(_) => PyValueError::new_err(msg),
+            _ => FutuError::new_err(msg),
+        }
+    }
+}
+
+impl IntoPyErr for InitError {
+    fn into_py_err(self, context: &str) -> PyErr {
+        let msg = format!("{}: {}", context, self);
+        match self {
+            InitError::Connection(e) => e.into_py_err(context),
+            InitError::Decode(_) | InitError::UnexpectedProto(_) | InitError::MissingS2C => FutuDecodeError::new_err(msg),
+            InitError::ServerError { ret_type, err_code, .. } => server_err(msg, ret_type, err_code),
+        }
+    }
+}
+
+impl IntoPyErr for PoolError {
+    fn into_py_err(self, context: &str) -> PyErr {
+        match self {
+            PoolError::Member { index, source } => source.into_py_err(&format!("{} (connection {})", context, index)),
+            PoolError::Empty => PyValueError::new_err(format!("{}: {}", context, self)),
+        }
+    }
+}
+
+impl IntoPyErr for InstrumentError {
+    fn into_py_err(self, context: &str) -> PyErr {
+        match self {
+            InstrumentError::Quote(e) => e.into_py_err(context),
+            InstrumentError::Trade(e) => e.into_py_err(context),
+            e => FutuError::new_err(format!("{}: {}", context, e)),
+        }
+    }
+}
+
+/// FutuServerError with its ret_type and err_code attributes set.
+fn server_err(msg: String, ret_type: i32, err_code: Option<i32>) -> PyErr {
+    Python::with_gil(|py| {
+        let err = FutuServerError::new_err(msg);
+        let value = err.value_bound(py);
+        match value.setattr("ret_type", ret_type).and_then(|_| value.setattr("err_code", err_code)) {
+            Ok(()) => err,
+            Err(e) => e,
+        }
+    })
+}
+
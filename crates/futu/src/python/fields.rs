@@ -0,0 +1,94 @@
+//! Output field selection for wide quote dicts.
+//!
+//! Converting every field of thousands of snapshot rows into Python objects
+//! dominates the cost of a bulk snapshot. A `FieldFilter` skips the fields a
+//! caller did not ask for; `market` and `code` are always kept so rows stay
+//! identifiable.
+
+use std::collections::HashSet;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+/// Fields kept regardless of the selection.
+const ALWAYS: [&str; 2] = ["market", "code"];
+
+/// Which optional fields go into the output dicts.
+pub struct FieldFilter {
+    /// None keeps every field.
+    selected: Option<HashSet<&'static str>>,
+}
+
+impl FieldFilter {
+    /// Build a filter from the requested field names. `known` lists the
+    /// fields the method can produce; other names are rejected so typos
+    /// don't silently drop data.
+    pub fn new(fields: Option<Vec<String>>, known: &[&'static str]) -> Result<Self, String> {
+        let Some(fields) = fields else {
+            return Ok(Self { selected: None });
+        };
+        let mut selected = HashSet::with_capacity(fields.len());
+        for field in &fields {
+            match known.iter().chain(ALWAYS.iter()).find(|k| **k == field.as_str()) {
+                Some(k) => {
+                    selected.insert(*k);
+                }
+                None => return Err(format!("unknown field '{}', expected one of: {}", field, known.join(", "))),
+            }
+        }
+        Ok(Self { selected: Some(selected) })
+    }
+
+    /// `new` with the error raised as ValueError.
+    pub fn from_py(fields: Option<Vec<String>>, known: &[&'static str]) -> PyResult<Self> {
+        Self::new(fields, known).map_err(PyValueError::new_err)
+    }
+
+    pub fn includes(&self, field: &str) -> bool {
+        match &self.selected {
+            None => true,
+            Some(selected) => ALWAYS.contains(&field) || selected.contains(field),
+        }
+    }
+
+    /// Set `field` on `dict` if it is selected.
+    pub fn set<V: ToPyObject>(&self, dict: &Bound<'_, PyDict>, field: &str, value: V) -> PyResult<()> {
+        if self.includes(field) {
+            dict.set_item(field, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KNOWN: [&str; 3] = ["cur_price", "volume", "name"];
+
+    fn fields(names: &[&str]) -> Option<Vec<String>> {
+        Some(names.iter().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn test_all_fields_by_default() {
+        let filter = FieldFilter::new(None, &KNOWN).unwrap();
+        assert!(filter.includes("volume"));
+        assert!(filter.includes("anything"));
+    }
+
+    #[test]
+    fn test_selection_keeps_identity_fields() {
+        let filter = FieldFilter::new(fields(&["cur_price"]), &KNOWN).unwrap();
+        assert!(filter.includes("cur_price"));
+        assert!(filter.includes("market"));
+        assert!(filter.includes("code"));
+        assert!(!filter.includes("volume"));
+    }
+
+    #[test]
+    fn test_unknown_field_rejected() {
+        let err = FieldFilter::new(fields(&["cur_prise"]), &KNOWN).err().unwrap();
+        assert!(err.contains("cur_prise"));
+    }
+}
@@ -0,0 +1,56 @@
+//! GIL checkpoints for long Rust-to-Python conversions.
+//!
+//! Responses are decoded with the GIL released, but building the Python
+//! objects needs it. For large responses (thousands of snapshot or K-line
+//! rows) a checkpoint every few hundred rows handles pending signals
+//! (e.g. Ctrl+C) and briefly releases the GIL so other threads can run.
+
+use pyo3::prelude::*;
+
+/// Rows converted between checkpoints.
+pub const CHECKPOINT_ROWS: usize = 256;
+
+/// Counts converted rows and yields the GIL every `every` rows.
+pub struct GilCheckpoint<'py> {
+    py: Python<'py>,
+    every: usize,
+    count: usize,
+}
+
+impl<'py> GilCheckpoint<'py> {
+    pub fn new(py: Python<'py>) -> Self {
+        Self::with_interval(py, CHECKPOINT_ROWS)
+    }
+
+    pub fn with_interval(py: Python<'py>, every: usize) -> Self {
+        Self { py, every: every.max(1), count: 0 }
+    }
+
+    /// Call once per converted row. Raises if a signal handler raised
+    /// (e.g. KeyboardInterrupt), aborting the conversion.
+    pub fn tick(&mut self) -> PyResult<()> {
+        if due(&mut self.count, self.every) {
+            self.py.check_signals()?;
+            self.py.allow_threads(std::thread::yield_now);
+        }
+        Ok(())
+    }
+}
+
+/// Advance `count` and report whether a checkpoint is due.
+fn due(count: &mut usize, every: usize) -> bool {
+    *count += 1;
+    (*count).is_multiple_of(every)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_due_every_n() {
+        let mut count = 0;
+        let hits: Vec<bool> = (0..6).map(|_| due(&mut count, 3)).collect();
+        assert_eq!(hits, vec![false, false, true, false, false, true]);
+    }
+}
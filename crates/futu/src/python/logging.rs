@@ -0,0 +1,271 @@
+//! Rust `tracing` output for Python users.
+//!
+//! The crate logs through `tracing`, which prints nothing until a
+//! subscriber is installed. `init_logging()` installs one that hands every
+//! event to Python's `logging` module, to a file, or both. Events go to the
+//! logger named after their module path, with `::` turned into `.`, e.g.
+//! `nautilus_futu.client.keepalive`, so Python handlers and levels apply to
+//! them as to any other logger. The records are passed to Python by a
+//! thread of their own, which takes the GIL once per batch; runtime threads
+//! never wait for the GIL to log. Which events are recorded at all is an
+//! `EnvFilter` of a default level and per-module levels, which
+//! `set_log_filter()` replaces at runtime.
+
+#![allow(clippy::useless_conversion)]
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::fs::OpenOptions;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+use crossbeam_channel::{Receiver, Sender};
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::prelude::*;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, EnvFilter, Layer, Registry};
+
+/// Records handed to Python per GIL acquisition.
+const MAX_BATCH: usize = 256;
+
+/// Filter of the installed subscriber; set once by the first `init_logging()`.
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// An event on its way to Python `logging`.
+#[derive(Debug, Clone, PartialEq)]
+struct LogRecord {
+    logger: String,
+    level: Level,
+    message: String,
+}
+
+/// Sends events to the thread that logs them in Python.
+struct PythonLayer {
+    tx: Sender<LogRecord>,
+}
+
+impl<S: Subscriber> Layer<S> for PythonLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let metadata = event.metadata();
+        let _ = self.tx.send(LogRecord {
+            logger: logger_name(metadata.target()),
+            level: *metadata.level(),
+            message: visitor.finish(),
+        });
+    }
+}
+
+/// The message of an event followed by its other fields as `name=value`.
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl MessageVisitor {
+    fn finish(self) -> String {
+        match (self.message.is_empty(), self.fields.is_empty()) {
+            (_, true) => self.message,
+            (true, false) => self.fields,
+            (false, false) => format!("{} {}", self.message, self.fields),
+        }
+    }
+
+    fn push_field(&mut self, field: &Field, value: std::fmt::Arguments<'_>) {
+        if !self.fields.is_empty() {
+            self.fields.push(' ');
+        }
+        let _ = write!(self.fields, "{}={}", field.name(), value);
+    }
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.push_field(field, format_args!("{}", value));
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            self.push_field(field, format_args!("{:?}", value));
+        }
+    }
+}
+
+/// Python logger of a tracing target.
+fn logger_name(target: &str) -> String {
+    target.replace("::", ".")
+}
+
+/// Python `logging` level of a tracing level; TRACE maps below DEBUG.
+fn python_level(level: Level) -> i32 {
+    match level {
+        Level::ERROR => 40,
+        Level::WARN => 30,
+        Level::INFO => 20,
+        Level::DEBUG => 10,
+        Level::TRACE => 5,
+    }
+}
+
+/// `EnvFilter` directives of a default level and per-module levels.
+/// Modules may be given as Rust paths or Python logger names.
+fn filter_directives(level: &str, filters: Option<&HashMap<String, String>>) -> String {
+    let mut directives = vec![level.to_string()];
+    if let Some(filters) = filters {
+        let mut modules: Vec<_> = filters.iter().collect();
+        modules.sort();
+        directives.extend(modules.into_iter().map(|(module, level)| format!("{}={}", module.replace('.', "::"), level)));
+    }
+    directives.join(",")
+}
+
+fn build_filter(level: &str, filters: Option<&HashMap<String, String>>) -> PyResult<EnvFilter> {
+    let directives = filter_directives(level, filters);
+    EnvFilter::try_new(&directives)
+        .map_err(|e| PyValueError::new_err(format!("Invalid log filter {:?}: {}", directives, e)))
+}
+
+/// Log the records of `rx` in Python until every sender is gone.
+fn forward_to_python(rx: Receiver<LogRecord>) {
+    while let Ok(first) = rx.recv() {
+        let batch: Vec<LogRecord> = std::iter::once(first).chain(rx.try_iter().take(MAX_BATCH - 1)).collect();
+        Python::with_gil(|py| {
+            let Ok(get_logger) = py.import_bound("logging").and_then(|m| m.getattr("getLogger")) else {
+                return;
+            };
+            for record in batch {
+                let result = get_logger
+                    .call1((record.logger.as_str(),))
+                    .and_then(|logger| logger.call_method1("log", (python_level(record.level), record.message)));
+                if let Err(e) = result {
+                    e.print(py);
+                }
+            }
+        });
+    }
+}
+
+/// Install the subscriber that makes the crate's Rust logs visible.
+/// level: default level ("error", "warn", "info", "debug" or "trace")
+/// to_python: hand events to Python `logging`, to the logger named after
+/// the module, e.g. "nautilus_futu.client.keepalive"
+/// file: also append events as text lines to this file
+/// filters: per-module levels, e.g. {"nautilus_futu.client": "debug"}
+/// Returns True if installed. If `init_logging()` ran before, only the
+/// filter is replaced and False is returned. Raises RuntimeError if
+/// another tracing subscriber was installed in the process.
+#[pyfunction]
+#[pyo3(signature = (level="info", to_python=true, file=None, filters=None))]
+pub fn init_logging(
+    level: &str,
+    to_python: bool,
+    file: Option<PathBuf>,
+    filters: Option<HashMap<String, String>>,
+) -> PyResult<bool> {
+    let filter = build_filter(level, filters.as_ref())?;
+    if let Some(handle) = FILTER.get() {
+        handle.reload(filter).map_err(|e| PyRuntimeError::new_err(format!("Log filter update failed: {}", e)))?;
+        return Ok(false);
+    }
+
+    let file_layer = match file {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .map_err(|e| PyValueError::new_err(format!("Cannot open log file {}: {}", path.display(), e)))?;
+            Some(fmt::layer().with_ansi(false).with_writer(Mutex::new(file)))
+        }
+        None => None,
+    };
+    let (python_layer, python_rx) = if to_python {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        (Some(PythonLayer { tx }), Some(rx))
+    } else {
+        (None, None)
+    };
+
+    let (filter, handle) = reload::Layer::new(filter);
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(python_layer)
+        .with(file_layer)
+        .try_init()
+        .map_err(|e| PyRuntimeError::new_err(format!("Logging already initialized: {}", e)))?;
+    let _ = FILTER.set(handle);
+    if let Some(rx) = python_rx {
+        std::thread::Builder::new()
+            .name("futu-logging".to_string())
+            .spawn(move || forward_to_python(rx))
+            .map_err(|e| PyRuntimeError::new_err(format!("Logging thread failed to start: {}", e)))?;
+    }
+    Ok(true)
+}
+
+/// Replace the level filter of `init_logging()`, e.g. to turn on debug logs
+/// of one module while running. Takes the same level and filters.
+#[pyfunction]
+#[pyo3(signature = (level, filters=None))]
+pub fn set_log_filter(level: &str, filters: Option<HashMap<String, String>>) -> PyResult<()> {
+    let handle = FILTER.get().ok_or_else(|| PyRuntimeError::new_err("Logging not initialized; call init_logging() first"))?;
+    let filter = build_filter(level, filters.as_ref())?;
+    handle.reload(filter).map_err(|e| PyRuntimeError::new_err(format!("Log filter update failed: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_logger_name() {
+        assert_eq!(logger_name("nautilus_futu::client::keepalive"), "nautilus_futu.client.keepalive");
+        assert_eq!(logger_name("tokio"), "tokio");
+    }
+
+    #[test]
+    fn test_python_level() {
+        assert_eq!(python_level(Level::WARN), 30);
+        assert!(python_level(Level::TRACE) < python_level(Level::DEBUG));
+    }
+
+    #[test]
+    fn test_filter_directives() {
+        assert_eq!(filter_directives("info", None), "info");
+        let filters = HashMap::from([
+            ("nautilus_futu.client".to_string(), "debug".to_string()),
+            ("nautilus_futu::quote".to_string(), "warn".to_string()),
+        ]);
+        assert_eq!(
+            filter_directives("warn", Some(&filters)),
+            "warn,nautilus_futu::client=debug,nautilus_futu::quote=warn",
+        );
+        assert!(EnvFilter::try_new(filter_directives("warn", Some(&filters))).is_ok());
+        let bad = HashMap::from([("nautilus_futu.client".to_string(), "[bad".to_string())]);
+        assert!(EnvFilter::try_new(filter_directives("info", Some(&bad))).is_err());
+    }
+
+    #[test]
+    fn test_python_layer_records_message_and_fields() {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        let subscriber = tracing_subscriber::registry().with(PythonLayer { tx });
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::warn!(target: "nautilus_futu::client", conn_id = 7, "Keepalive missed {}", 2);
+        });
+        let record = rx.try_recv().unwrap();
+        assert_eq!(record.logger, "nautilus_futu.client");
+        assert_eq!(record.level, Level::WARN);
+        assert_eq!(record.message, "Keepalive missed 2 conn_id=7");
+    }
+}
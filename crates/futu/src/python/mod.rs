@@ -1,2 +1,11 @@
+#[cfg(feature = "arrow")]
+pub mod arrow;
 pub mod client;
+pub mod errors;
+pub mod fields;
+pub mod gil;
+pub mod logging;
+pub mod models;
+pub mod pool;
+pub mod push_callback;
 pub mod push_decode;
@@ -0,0 +1,212 @@
+//! Typed result classes returned by `typed=True` calls.
+//!
+//! Each class holds the fields of the dict the call returns otherwise,
+//! under the same names, as read-only attributes. `to_dict()` gives that
+//! dict back, so code written against the dicts can take either.
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::quote::names::NameTable;
+use crate::quote::symbols::SymbolMap;
+
+/// Declare a frozen pyclass with one getter per field, `to_dict()` and a
+/// `Name(field=value, ...)` repr.
+macro_rules! model {
+    ($(#[$meta:meta])* $name:ident { $($field:ident: $ty:ty),* $(,)? }) => {
+        $(#[$meta])*
+        #[pyclass(get_all, frozen, eq, module = "nautilus_futu._rust")]
+        #[derive(Debug, Clone, PartialEq)]
+        pub struct $name {
+            $(pub $field: $ty),*
+        }
+
+        #[pymethods]
+        impl $name {
+            /// The fields as a dict, in the layout of the untyped result.
+            fn to_dict(&self, py: Python<'_>) -> PyResult<PyObject> {
+                let dict = PyDict::new_bound(py);
+                $(dict.set_item(stringify!($field), &self.$field)?;)*
+                Ok(dict.into_any().unbind())
+            }
+
+            fn __repr__(&self) -> String {
+                let fields: Vec<String> = vec![$(format!("{}={:?}", stringify!($field), self.$field)),*];
+                format!("{}({})", stringify!($name), fields.join(", "))
+            }
+        }
+    };
+}
+
+model! {
+    /// A row of `get_basic_qot(typed=True)`.
+    BasicQot {
+        market: i32,
+        code: String,
+        alias: Option<String>,
+        name: Option<String>,
+        cur_price: f64,
+        price_spread: f64,
+        open_price: f64,
+        high_price: f64,
+        low_price: f64,
+        last_close_price: f64,
+        volume: i64,
+        turnover: f64,
+        turnover_rate: f64,
+        update_timestamp: Option<f64>,
+    }
+}
+
+impl BasicQot {
+    pub fn from_proto(names: &NameTable, aliases: &SymbolMap, qot: &crate::generated::qot_common::BasicQot) -> Self {
+        let sec = &qot.security;
+        Self {
+            market: sec.market,
+            code: sec.code.clone(),
+            alias: aliases.alias(sec.market, &sec.code).map(str::to_string),
+            name: names.localize_opt(sec.market, &sec.code, qot.name.as_deref()).map(str::to_string),
+            cur_price: qot.cur_price,
+            price_spread: qot.price_spread,
+            open_price: qot.open_price,
+            high_price: qot.high_price,
+            low_price: qot.low_price,
+            last_close_price: qot.last_close_price,
+            volume: qot.volume,
+            turnover: qot.turnover,
+            turnover_rate: qot.turnover_rate,
+            update_timestamp: qot.update_timestamp,
+        }
+    }
+}
+
+model! {
+    /// A K-line of `get_history_kl(typed=True)`.
+    Kline {
+        time: String,
+        is_blank: bool,
+        open_price: Option<f64>,
+        high_price: Option<f64>,
+        low_price: Option<f64>,
+        close_price: Option<f64>,
+        last_close_price: Option<f64>,
+        volume: Option<i64>,
+        turnover: Option<f64>,
+        timestamp: Option<f64>,
+    }
+}
+
+impl From<&crate::generated::qot_common::KLine> for Kline {
+    fn from(kl: &crate::generated::qot_common::KLine) -> Self {
+        Self {
+            time: kl.time.clone(),
+            is_blank: kl.is_blank,
+            open_price: kl.open_price,
+            high_price: kl.high_price,
+            low_price: kl.low_price,
+            close_price: kl.close_price,
+            last_close_price: kl.last_close_price,
+            volume: kl.volume,
+            turnover: kl.turnover,
+            timestamp: kl.timestamp,
+        }
+    }
+}
+
+model! {
+    /// An order of `get_order_list(typed=True)`.
+    Order {
+        trd_side: i32,
+        order_type: i32,
+        order_status: i32,
+        order_id: u64,
+        order_id_ex: String,
+        code: String,
+        alias: Option<String>,
+        name: String,
+        qty: f64,
+        price: Option<f64>,
+        create_time: String,
+        update_time: String,
+        fill_qty: Option<f64>,
+        fill_avg_price: Option<f64>,
+        sec_market: Option<i32>,
+        create_timestamp: Option<f64>,
+        update_timestamp: Option<f64>,
+        time_in_force: Option<i32>,
+        remark: Option<String>,
+        last_err_msg: Option<String>,
+    }
+}
+
+impl Order {
+    pub fn from_proto(names: &NameTable, aliases: &SymbolMap, order: &crate::generated::trd_common::Order) -> Self {
+        Self {
+            trd_side: order.trd_side,
+            order_type: order.order_type,
+            order_status: order.order_status,
+            order_id: order.order_id,
+            order_id_ex: order.order_id_ex.clone(),
+            code: order.code.clone(),
+            alias: aliases.alias_trd(order.sec_market, &order.code).map(str::to_string),
+            name: names.localize_trd(order.sec_market, &order.code, &order.name).to_string(),
+            qty: order.qty,
+            price: order.price,
+            create_time: order.create_time.clone(),
+            update_time: order.update_time.clone(),
+            fill_qty: order.fill_qty,
+            fill_avg_price: order.fill_avg_price,
+            sec_market: order.sec_market,
+            create_timestamp: order.create_timestamp,
+            update_timestamp: order.update_timestamp,
+            time_in_force: order.time_in_force,
+            remark: order.remark.clone(),
+            last_err_msg: order.last_err_msg.clone(),
+        }
+    }
+}
+
+model! {
+    /// A position of `get_position_list(typed=True)`.
+    Position {
+        position_id: u64,
+        position_side: i32,
+        code: String,
+        alias: Option<String>,
+        name: String,
+        qty: f64,
+        can_sell_qty: f64,
+        price: f64,
+        cost_price: Option<f64>,
+        val: f64,
+        pl_val: f64,
+        pl_ratio: Option<f64>,
+        sec_market: Option<i32>,
+        unrealized_pl: Option<f64>,
+        realized_pl: Option<f64>,
+        currency: Option<i32>,
+    }
+}
+
+impl Position {
+    pub fn from_proto(names: &NameTable, aliases: &SymbolMap, pos: &crate::generated::trd_common::Position) -> Self {
+        Self {
+            position_id: pos.position_id,
+            position_side: pos.position_side,
+            code: pos.code.clone(),
+            alias: aliases.alias_trd(pos.sec_market, &pos.code).map(str::to_string),
+            name: names.localize_trd(pos.sec_market, &pos.code, &pos.name).to_string(),
+            qty: pos.qty,
+            can_sell_qty: pos.can_sell_qty,
+            price: pos.price,
+            cost_price: pos.cost_price,
+            val: pos.val,
+            pl_val: pos.pl_val,
+            pl_ratio: pos.pl_ratio,
+            sec_market: pos.sec_market,
+            unrealized_pl: pos.unrealized_pl,
+            realized_pl: pos.realized_pl,
+            currency: pos.currency,
+        }
+    }
+}
@@ -0,0 +1,263 @@
+//! Python-facing pool of OpenD connections; see `client::pool`.
+
+#![allow(clippy::useless_conversion)]
+
+use std::sync::Arc;
+use pyo3::prelude::*;
+use pyo3::exceptions::{PyRuntimeError, PyValueError};
+use pyo3::types::PyDict;
+use parking_lot::Mutex as SyncMutex;
+use tokio::runtime::Runtime;
+
+use crate::client::mock::MockOpenD;
+use crate::client::pool::{strategy_from_name, FutuClientPool};
+use crate::config::FutuConfig;
+use crate::protocol::DecodeMode;
+use crate::quote::names::NameTable;
+use crate::quote::subscribe::SubOptions;
+use crate::quote::symbols::SymbolMap;
+use super::client::push_message_to_dict;
+use super::errors::{FutuConnectionError, IntoPyErr};
+use super::push_decode::PushPayload;
+
+type PushMessage = (u32, PushPayload);
+type PushChannel = (crossbeam_channel::Sender<PushMessage>, crossbeam_channel::Receiver<PushMessage>);
+
+/// Several Futu OpenD connections with quote subscriptions spread across
+/// them by a routing strategy.
+#[pyclass]
+pub struct PyFutuClientPool {
+    runtime: Runtime,
+    pool: SyncMutex<Option<Arc<FutuClientPool>>>,
+    /// Merged pushes of `start_push()`, and the tasks forwarding them.
+    pushes: SyncMutex<Option<PushChannel>>,
+    forwarders: SyncMutex<Vec<tokio::task::JoinHandle<()>>>,
+    /// In-process gateway of `connect(mock=True)`, shared by every member.
+    mock: SyncMutex<Option<Arc<MockOpenD>>>,
+}
+
+impl PyFutuClientPool {
+    fn get_pool(&self) -> PyResult<Arc<FutuClientPool>> {
+        self.pool
+            .lock()
+            .as_ref()
+            .cloned()
+            .ok_or_else(|| FutuConnectionError::new_err("Not connected"))
+    }
+}
+
+#[pymethods]
+impl PyFutuClientPool {
+    #[new]
+    fn new() -> PyResult<Self> {
+        let runtime = Runtime::new()
+            .map_err(|e| PyRuntimeError::new_err(format!("Failed to create runtime: {}", e)))?;
+        Ok(Self {
+            runtime,
+            pool: SyncMutex::new(None),
+            pushes: SyncMutex::new(None),
+            forwarders: SyncMutex::new(Vec::new()),
+            mock: SyncMutex::new(None),
+        })
+    }
+
+    /// Connect one client per (host, port) endpoint. strategy routes
+    /// securities to connections: "least_loaded" (most quota left, then
+    /// fewest subscriptions), "round_robin" or "hash" (by market and code).
+    /// sub_quota is checked locally per connection. With mock=True every
+    /// connection goes to one in-process mock OpenD.
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (endpoints, client_id="nautilus_futu", client_ver=100, strategy="least_loaded", sub_quota=None, mock=false))]
+    fn connect(
+        &self,
+        py: Python<'_>,
+        endpoints: Vec<(String, u16)>,
+        client_id: &str,
+        client_ver: i32,
+        strategy: &str,
+        sub_quota: Option<u32>,
+        mock: bool,
+    ) -> PyResult<()> {
+        let strategy = strategy_from_name(strategy).map_err(PyValueError::new_err)?;
+        let mock_port = if mock {
+            let mock = py.allow_threads(|| self.runtime.block_on(MockOpenD::start()))
+                .map_err(|e| PyRuntimeError::new_err(format!("Mock OpenD failed to start: {}", e)))?;
+            let port = mock.port();
+            *self.mock.lock() = Some(Arc::new(mock));
+            Some(port)
+        } else {
+            None
+        };
+        let configs = endpoints
+            .into_iter()
+            .map(|(host, port)| {
+                let (host, port) = match mock_port {
+                    Some(port) => ("127.0.0.1".to_string(), port),
+                    None => (host, port),
+                };
+                FutuConfig { host, port, client_id: client_id.to_string(), client_ver, sub_quota, ..Default::default() }
+            })
+            .collect();
+
+        let pool = py.allow_threads(|| self.runtime.block_on(FutuClientPool::connect(configs, strategy)))
+            .map_err(|e| e.into_py_err("Pool connection failed"))?;
+        *self.pool.lock() = Some(Arc::new(pool));
+        Ok(())
+    }
+
+    fn is_connected(&self) -> bool {
+        self.pool.lock().is_some()
+    }
+
+    fn __len__(&self) -> usize {
+        self.pool.lock().as_ref().map_or(0, |pool| pool.len())
+    }
+
+    /// Subscribe (or unsubscribe) securities, each on the connection the
+    /// pool routes it to. Returns one dict per security with market, code,
+    /// ok, error and connection (index of the connection serving it, None
+    /// once it is no longer subscribed). reg_push, first_push,
+    /// push_rehab_types and session as in `PyFutuClient.subscribe()`.
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (
+        securities, sub_types, is_sub=true, order_book_detail=false,
+        reg_push=true, first_push=None, push_rehab_types=None, session=None,
+    ))]
+    fn subscribe(
+        &self,
+        py: Python<'_>,
+        securities: Vec<(i32, String)>,
+        sub_types: Vec<i32>,
+        is_sub: bool,
+        order_book_detail: bool,
+        reg_push: bool,
+        first_push: Option<bool>,
+        push_rehab_types: Option<Vec<i32>>,
+        session: Option<i32>,
+    ) -> PyResult<Vec<PyObject>> {
+        let pool = self.get_pool()?;
+        let options = SubOptions {
+            order_book_detail,
+            reg_push: Some(reg_push),
+            first_push,
+            push_rehab_types: push_rehab_types.unwrap_or_default(),
+            session,
+            ..Default::default()
+        };
+        let results = py.allow_threads(|| {
+            self.runtime.block_on(pool.subscribe(securities, sub_types, is_sub, &options))
+        }).map_err(|e| e.into_py_err("Subscribe failed"))?;
+
+        results
+            .iter()
+            .map(|r| {
+                let d = PyDict::new_bound(py);
+                d.set_item("market", r.security.0)?;
+                d.set_item("code", &r.security.1)?;
+                d.set_item("ok", r.is_ok())?;
+                d.set_item("error", r.error.as_deref())?;
+                d.set_item("connection", pool.route_of(&r.security))?;
+                Ok(d.into_any().unbind())
+            })
+            .collect()
+    }
+
+    /// Drop every quote subscription of every connection and forget the
+    /// routes. Returns the pairs released per connection.
+    fn unsubscribe_all(&self, py: Python<'_>) -> PyResult<Vec<u32>> {
+        let pool = self.get_pool()?;
+        py.allow_threads(|| self.runtime.block_on(pool.unsubscribe_all()))
+            .map_err(|e| e.into_py_err("Unsubscribe all failed"))
+    }
+
+    /// Index of the connection serving (market, code), or None if the
+    /// pool does not hold a subscription of it.
+    fn route_of(&self, market: i32, code: String) -> PyResult<Option<usize>> {
+        Ok(self.get_pool()?.route_of(&(market, code)))
+    }
+
+    /// Load per connection: list of dicts with index, subscriptions,
+    /// remaining_quota and pending_requests.
+    fn loads(&self, py: Python<'_>) -> PyResult<Vec<PyObject>> {
+        self.get_pool()?
+            .loads()
+            .into_iter()
+            .map(|load| {
+                let d = PyDict::new_bound(py);
+                d.set_item("index", load.index)?;
+                d.set_item("subscriptions", load.subscriptions)?;
+                d.set_item("remaining_quota", load.remaining_quota)?;
+                d.set_item("pending_requests", load.pending_requests)?;
+                Ok(d.into_any().unbind())
+            })
+            .collect()
+    }
+
+    /// Forward pushes of `proto_ids` from every connection to one queue
+    /// read by `poll_push()`. Calling it again adds proto_ids to the queue.
+    fn start_push(&self, py: Python<'_>, proto_ids: Vec<u32>) -> PyResult<()> {
+        let pool = self.get_pool()?;
+        let tx = self.pushes.lock().get_or_insert_with(crossbeam_channel::unbounded).0.clone();
+        for proto_id in proto_ids {
+            let mut rx = py.allow_threads(|| self.runtime.block_on(pool.subscribe_push(proto_id)));
+            let tx = tx.clone();
+            let handle = self.runtime.spawn(async move {
+                while let Some(msg) = rx.recv().await {
+                    if tx.send((msg.proto_id, PushPayload::Raw(msg.body, msg.proto_fmt))).is_err() {
+                        break;
+                    }
+                }
+            });
+            self.forwarders.lock().push(handle);
+        }
+        Ok(())
+    }
+
+    /// Next push of any connection as a dict with proto_id and data, in the
+    /// layout of `PyFutuClient.poll_push()`; None on timeout.
+    #[pyo3(signature = (timeout_ms=100))]
+    fn poll_push(&self, py: Python<'_>, timeout_ms: u64) -> PyResult<Option<PyObject>> {
+        let Some(rx) = self.pushes.lock().as_ref().map(|(_, rx)| rx.clone()) else {
+            return Ok(None);
+        };
+        let timeout = std::time::Duration::from_millis(timeout_ms);
+        match py.allow_threads(|| rx.recv_timeout(timeout)) {
+            Ok((proto_id, payload)) => {
+                push_message_to_dict(py, &NameTable::new(), &SymbolMap::new(), DecodeMode::Lenient, proto_id, payload).map(Some)
+            }
+            // Timeout or channel closed
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Send a push of `proto_id` with `body` from the mock OpenD of
+    /// `connect(mock=True)`. Returns the number of connections it reached.
+    fn mock_push(&self, proto_id: u32, body: Vec<u8>) -> PyResult<usize> {
+        let mock = self.mock.lock().clone().ok_or_else(|| PyRuntimeError::new_err("Not connected to a mock OpenD"))?;
+        Ok(mock.push(proto_id, body))
+    }
+
+    /// Disconnect every connection of the pool.
+    fn disconnect(&self, py: Python<'_>) -> PyResult<()> {
+        for handle in self.forwarders.lock().drain(..) {
+            handle.abort();
+        }
+        *self.pushes.lock() = None;
+        if let Some(pool) = self.pool.lock().take() {
+            py.allow_threads(|| {
+                self.runtime.block_on(async {
+                    match Arc::try_unwrap(pool) {
+                        Ok(pool) => pool.disconnect().await,
+                        Err(pool) => {
+                            for client in pool.clients() {
+                                client.close_requests().await;
+                            }
+                        }
+                    }
+                })
+            });
+        }
+        *self.mock.lock() = None;
+        Ok(())
+    }
+}
@@ -0,0 +1,200 @@
+//! Push channels delivered to a Python callable.
+//!
+//! `poll_push()` needs a polling loop in Python. A channel with a callback
+//! is drained by a thread of its own instead: it waits for messages with
+//! the GIL released, takes the GIL once per batch and calls the callable
+//! with each message, or with the batch as a list. When the callable falls
+//! behind, messages queue up on the channel; with a queue size, the oldest
+//! quote pushes beyond it are dropped and counted, so a slow callable sees
+//! recent quotes instead of an ever-growing backlog. Order and fill pushes
+//! are never dropped.
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use crossbeam_channel::{Receiver, RecvTimeoutError};
+use pyo3::prelude::*;
+
+/// How often the thread checks whether it was stopped.
+const STOP_CHECK: Duration = Duration::from_millis(100);
+
+/// How a callback channel is delivered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CallbackOptions {
+    /// Messages queued before the oldest quote pushes are dropped; None
+    /// queues without limit.
+    pub queue_size: Option<usize>,
+    /// Messages delivered per GIL acquisition.
+    pub max_batch: usize,
+    /// Call the callable once per batch with a list instead of once per
+    /// message.
+    pub as_list: bool,
+}
+
+/// Counters of a callback channel.
+#[derive(Debug, Default)]
+pub struct CallbackStats {
+    pub delivered: AtomicU64,
+    pub dropped: AtomicU64,
+    /// Messages that failed to convert and calls that raised.
+    pub errors: AtomicU64,
+}
+
+/// The delivery thread of a callback channel.
+pub struct PushCallback {
+    stop: Arc<AtomicBool>,
+    stats: Arc<CallbackStats>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PushCallback {
+    /// Start delivering `rx` to `callback`. `convert` builds the Python
+    /// object of a message; `droppable` tells which messages the queue size
+    /// may drop.
+    pub fn start<T, C>(
+        rx: Receiver<T>,
+        callback: PyObject,
+        options: CallbackOptions,
+        droppable: fn(&T) -> bool,
+        convert: C,
+    ) -> std::io::Result<Self>
+    where
+        T: Send + 'static,
+        C: Fn(Python<'_>, T) -> PyResult<PyObject> + Send + 'static,
+    {
+        let stop = Arc::new(AtomicBool::new(false));
+        let stats = Arc::new(CallbackStats::default());
+        let (thread_stop, thread_stats) = (Arc::clone(&stop), Arc::clone(&stats));
+        let thread = std::thread::Builder::new()
+            .name("futu-push-callback".to_string())
+            .spawn(move || deliver(rx, callback, options, droppable, convert, &thread_stop, &thread_stats))?;
+        Ok(Self { stop, stats, thread: Some(thread) })
+    }
+
+    pub fn stats(&self) -> &CallbackStats {
+        &self.stats
+    }
+
+    /// Stop the thread and wait for it. The thread may be waiting for the
+    /// GIL, so this must be called with the GIL released.
+    pub fn stop(mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                tracing::error!("Push callback thread panicked");
+            }
+        }
+    }
+}
+
+fn deliver<T, C>(
+    rx: Receiver<T>,
+    callback: PyObject,
+    options: CallbackOptions,
+    droppable: fn(&T) -> bool,
+    convert: C,
+    stop: &AtomicBool,
+    stats: &CallbackStats,
+) where
+    C: Fn(Python<'_>, T) -> PyResult<PyObject>,
+{
+    let max_batch = options.max_batch.max(1);
+    while !stop.load(Ordering::Relaxed) {
+        let first = match rx.recv_timeout(STOP_CHECK) {
+            Ok(first) => first,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        let mut messages = vec![first];
+        messages.extend(rx.try_iter().take(max_batch - 1));
+        if let Some(limit) = options.queue_size {
+            let dropped = trim_backlog(&mut messages, &rx, limit, droppable);
+            stats.dropped.fetch_add(dropped, Ordering::Relaxed);
+        }
+        if messages.is_empty() {
+            continue;
+        }
+
+        Python::with_gil(|py| {
+            let mut objects = Vec::with_capacity(messages.len());
+            for message in messages {
+                match convert(py, message) {
+                    Ok(object) => objects.push(object),
+                    Err(e) => {
+                        stats.errors.fetch_add(1, Ordering::Relaxed);
+                        e.write_unraisable_bound(py, None);
+                    }
+                }
+            }
+            if objects.is_empty() {
+                return;
+            }
+            let delivered = objects.len() as u64;
+            let calls: Vec<PyResult<PyObject>> = if options.as_list {
+                vec![callback.call1(py, (objects,))]
+            } else {
+                objects.into_iter().map(|object| callback.call1(py, (object,))).collect()
+            };
+            for result in calls {
+                if let Err(e) = result {
+                    stats.errors.fetch_add(1, Ordering::Relaxed);
+                    e.write_unraisable_bound(py, Some(callback.bind(py)));
+                }
+            }
+            stats.delivered.fetch_add(delivered, Ordering::Relaxed);
+        });
+    }
+}
+
+/// Drop the oldest droppable messages of `batch`, then of `rx`, until at
+/// most `limit` messages are left in both, keeping the messages that may
+/// not be dropped in order. Returns the number dropped.
+fn trim_backlog<T>(batch: &mut Vec<T>, rx: &Receiver<T>, limit: usize, droppable: fn(&T) -> bool) -> u64 {
+    let backlog = batch.len() + rx.len();
+    let mut excess = backlog.saturating_sub(limit.max(1));
+    let mut dropped = 0;
+    batch.retain(|message| {
+        if excess > 0 && droppable(message) {
+            excess -= 1;
+            dropped += 1;
+            return false;
+        }
+        true
+    });
+    while excess > 0 {
+        match rx.try_recv() {
+            Ok(message) if droppable(&message) => {
+                excess -= 1;
+                dropped += 1;
+            }
+            Ok(message) => batch.push(message),
+            Err(_) => break,
+        }
+    }
+    dropped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trim_backlog_keeps_undroppable() {
+        let (tx, rx) = crossbeam_channel::unbounded();
+        for message in 3..10 {
+            tx.send(message).unwrap();
+        }
+        // Even messages stand in for order pushes, which are never dropped
+        let mut batch = vec![0, 1, 2];
+        let dropped = trim_backlog(&mut batch, &rx, 6, |m: &u32| m % 2 == 1);
+        assert_eq!(dropped, 4);
+        assert_eq!(batch, vec![0, 2, 4, 6]);
+        assert_eq!(rx.try_iter().collect::<Vec<_>>(), vec![8, 9]);
+
+        let mut batch = vec![10];
+        assert_eq!(trim_backlog(&mut batch, &rx, 4, |_| true), 0);
+        assert_eq!(batch, vec![10]);
+    }
+}
@@ -1,10 +1,27 @@
 //! Decode Futu push messages into Python dicts.
+//!
+//! Decoding is split in two: `decode_push` parses the body into a
+//! `DecodedPush` without the GIL, so it can run on a runtime worker, and
+//! `decoded_to_py` builds the Python objects from it.
 
-use prost::Message;
+use std::collections::HashSet;
+use bytes::Bytes;
 use pyo3::prelude::*;
 use pyo3::exceptions::PyValueError;
 use pyo3::types::{PyDict, PyList};
 
+use crate::generated::{
+    notify, qot_update_basic_qot, qot_update_kl, qot_update_order_book, qot_update_price_reminder, qot_update_ticker,
+    trd_update_order, trd_update_order_fill,
+};
+use crate::protocol::decode::{decode_message, DecodeMode};
+use crate::protocol::json::{JsonMessage, ProtoFmt};
+use crate::quote::names::NameTable;
+pub use crate::client::notify::PROTO_NOTIFY;
+pub use crate::quote::reminder::PROTO_QOT_UPDATE_PRICE_REMINDER;
+use crate::quote::snapshot::book_depth;
+use crate::quote::symbols::SymbolMap;
+
 // Proto IDs for push notifications
 pub const PROTO_QOT_UPDATE_BASIC_QOT: u32 = 3005;
 pub const PROTO_QOT_UPDATE_TICKER: u32 = 3011;
@@ -13,32 +30,157 @@ pub const PROTO_QOT_UPDATE_KL: u32 = 3007;
 pub const PROTO_TRD_UPDATE_ORDER: u32 = 2208;
 pub const PROTO_TRD_UPDATE_ORDER_FILL: u32 = 2218;
 
-/// Decode a push message body into a Python object based on proto_id.
-pub fn decode_push_message(py: Python<'_>, proto_id: u32, body: &[u8]) -> PyResult<PyObject> {
+/// A push message parsed into its s2c, ready for conversion to Python.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodedPush {
+    BasicQot(qot_update_basic_qot::S2c),
+    Ticker(qot_update_ticker::S2c),
+    OrderBook(qot_update_order_book::S2c),
+    Kl(qot_update_kl::S2c),
+    PriceReminder(qot_update_price_reminder::S2c),
+    Order(trd_update_order::S2c),
+    Fill(trd_update_order_fill::S2c),
+    Notify(notify::S2c),
+}
+
+impl DecodedPush {
+    /// Keep only the quotes of `securities` (market, code), or None if none
+    /// are left. Trade and Notify pushes are always kept.
+    pub fn retain_securities(self, securities: &HashSet<(i32, String)>) -> Option<DecodedPush> {
+        let wanted = |s: &crate::generated::qot_common::Security| securities.contains(&(s.market, s.code.clone()));
+        match self {
+            DecodedPush::BasicQot(mut s2c) => {
+                s2c.basic_qot_list.retain(|q| wanted(&q.security));
+                (!s2c.basic_qot_list.is_empty()).then_some(DecodedPush::BasicQot(s2c))
+            }
+            DecodedPush::Ticker(s2c) => wanted(&s2c.security).then_some(DecodedPush::Ticker(s2c)),
+            DecodedPush::OrderBook(s2c) => wanted(&s2c.security).then_some(DecodedPush::OrderBook(s2c)),
+            DecodedPush::Kl(s2c) => wanted(&s2c.security).then_some(DecodedPush::Kl(s2c)),
+            DecodedPush::PriceReminder(s2c) => wanted(&s2c.security).then_some(DecodedPush::PriceReminder(s2c)),
+            other @ (DecodedPush::Order(_) | DecodedPush::Fill(_) | DecodedPush::Notify(_)) => Some(other),
+        }
+    }
+}
+
+/// Body of a queued push message: the raw bytes in their format, or the result of decoding
+/// them on a runtime worker. Decode errors are kept so that polling raises
+/// them exactly as it would for a raw body. Decoded messages are boxed to
+/// keep queue slots small.
+#[derive(Debug, Clone)]
+pub enum PushPayload {
+    Raw(Bytes, ProtoFmt),
+    Decoded(Result<Box<DecodedPush>, String>),
+}
+
+impl PushPayload {
+    /// Decode the body on the current thread unless already decoded.
+    pub fn decode(self, mode: DecodeMode, proto_id: u32) -> Result<DecodedPush, String> {
+        match self {
+            PushPayload::Raw(body, proto_fmt) => decode_push(mode, proto_fmt, proto_id, &body),
+            PushPayload::Decoded(decoded) => decoded.map(|d| *d),
+        }
+    }
+
+    /// Drop the per-order detail of an order book push, decoding it if
+    /// needed. Other pushes are returned unchanged.
+    pub fn without_order_book_detail(self, mode: DecodeMode, proto_id: u32) -> PushPayload {
+        if proto_id != PROTO_QOT_UPDATE_ORDER_BOOK {
+            return self;
+        }
+        let decoded = self.decode(mode, proto_id).map(|decoded| match decoded {
+            DecodedPush::OrderBook(mut s2c) => {
+                for level in s2c.order_book_ask_list.iter_mut().chain(s2c.order_book_bid_list.iter_mut()) {
+                    level.detail_list.clear();
+                }
+                Box::new(DecodedPush::OrderBook(s2c))
+            }
+            other => Box::new(other),
+        });
+        PushPayload::Decoded(decoded)
+    }
+}
+
+/// Parse a push message body in `proto_fmt` based on proto_id. Does not need the GIL.
+pub fn decode_push(mode: DecodeMode, proto_fmt: ProtoFmt, proto_id: u32, body: &[u8]) -> Result<DecodedPush, String> {
+    fn s2c<M: prost::Message + Default + JsonMessage, S>(
+        body: &[u8],
+        proto_fmt: ProtoFmt,
+        mode: DecodeMode,
+        what: &str,
+        s2c: impl FnOnce(M) -> Option<S>,
+    ) -> Result<S, String> {
+        let resp = decode_message::<M>(body, proto_fmt, mode).map_err(|e| format!("Decode error: {}", e))?;
+        s2c(resp).ok_or_else(|| format!("Missing s2c in {} push", what))
+    }
+
     match proto_id {
-        PROTO_QOT_UPDATE_BASIC_QOT => decode_basic_qot(py, body),
-        PROTO_QOT_UPDATE_TICKER => decode_ticker(py, body),
-        PROTO_QOT_UPDATE_ORDER_BOOK => decode_order_book(py, body),
-        PROTO_QOT_UPDATE_KL => decode_kl(py, body),
-        PROTO_TRD_UPDATE_ORDER => decode_trd_order(py, body),
-        PROTO_TRD_UPDATE_ORDER_FILL => decode_trd_fill(py, body),
-        _ => Err(PyValueError::new_err(format!("Unknown push proto_id: {}", proto_id))),
+        PROTO_QOT_UPDATE_BASIC_QOT => {
+            s2c(body, proto_fmt, mode, "basic qot", |r: qot_update_basic_qot::Response| r.s2c).map(DecodedPush::BasicQot)
+        }
+        PROTO_QOT_UPDATE_TICKER => {
+            s2c(body, proto_fmt, mode, "ticker", |r: qot_update_ticker::Response| r.s2c).map(DecodedPush::Ticker)
+        }
+        PROTO_QOT_UPDATE_ORDER_BOOK => {
+            s2c(body, proto_fmt, mode, "order book", |r: qot_update_order_book::Response| r.s2c).map(DecodedPush::OrderBook)
+        }
+        PROTO_QOT_UPDATE_KL => s2c(body, proto_fmt, mode, "KL", |r: qot_update_kl::Response| r.s2c).map(DecodedPush::Kl),
+        PROTO_QOT_UPDATE_PRICE_REMINDER => {
+            s2c(body, proto_fmt, mode, "price reminder", |r: qot_update_price_reminder::Response| r.s2c).map(DecodedPush::PriceReminder)
+        }
+        PROTO_TRD_UPDATE_ORDER => {
+            s2c(body, proto_fmt, mode, "order", |r: trd_update_order::Response| r.s2c).map(DecodedPush::Order)
+        }
+        PROTO_TRD_UPDATE_ORDER_FILL => {
+            s2c(body, proto_fmt, mode, "fill", |r: trd_update_order_fill::Response| r.s2c).map(DecodedPush::Fill)
+        }
+        PROTO_NOTIFY => s2c(body, proto_fmt, mode, "notify", |r: notify::Response| r.s2c).map(DecodedPush::Notify),
+        _ => Err(format!("Unknown push proto_id: {}", proto_id)),
     }
 }
 
-fn decode_basic_qot(py: Python<'_>, body: &[u8]) -> PyResult<PyObject> {
-    let resp = crate::generated::qot_update_basic_qot::Response::decode(body)
-        .map_err(|e| PyValueError::new_err(format!("Decode error: {}", e)))?;
+/// Decode a push message body into a Python object based on proto_id.
+/// Security names are localized with `names`; `aliases` adds an "alias" key.
+pub fn decode_push_message(
+    py: Python<'_>,
+    names: &NameTable,
+    aliases: &SymbolMap,
+    mode: DecodeMode,
+    proto_fmt: ProtoFmt,
+    proto_id: u32,
+    body: &[u8],
+) -> PyResult<PyObject> {
+    let decoded = decode_push(mode, proto_fmt, proto_id, body).map_err(PyValueError::new_err)?;
+    decoded_to_py(py, names, aliases, &decoded)
+}
 
-    let s2c = resp.s2c
-        .ok_or_else(|| PyValueError::new_err("Missing s2c in basic qot push"))?;
+/// Convert a decoded push message into the Python object of its proto_id.
+pub fn decoded_to_py(
+    py: Python<'_>,
+    names: &NameTable,
+    aliases: &SymbolMap,
+    decoded: &DecodedPush,
+) -> PyResult<PyObject> {
+    match decoded {
+        DecodedPush::BasicQot(s2c) => basic_qot_to_py(py, names, aliases, s2c),
+        DecodedPush::Ticker(s2c) => ticker_to_py(py, aliases, s2c),
+        DecodedPush::OrderBook(s2c) => order_book_to_py(py, aliases, s2c),
+        DecodedPush::Kl(s2c) => kl_to_py(py, aliases, s2c),
+        DecodedPush::PriceReminder(s2c) => price_reminder_to_py(py, names, aliases, s2c),
+        DecodedPush::Order(s2c) => trd_order_to_py(py, names, aliases, s2c),
+        DecodedPush::Fill(s2c) => trd_fill_to_py(py, names, aliases, s2c),
+        DecodedPush::Notify(s2c) => notify_to_py(py, s2c),
+    }
+}
 
+fn basic_qot_to_py(py: Python<'_>, names: &NameTable, aliases: &SymbolMap, s2c: &qot_update_basic_qot::S2c) -> PyResult<PyObject> {
     let list = PyList::empty_bound(py);
     for qot in &s2c.basic_qot_list {
         let dict = PyDict::new_bound(py);
         dict.set_item("market", qot.security.market)?;
         dict.set_item("code", &qot.security.code)?;
-        dict.set_item("name", &qot.name)?;
+        dict.set_item("alias", aliases.alias(qot.security.market, &qot.security.code))?;
+        let sec = &qot.security;
+        dict.set_item("name", names.localize_opt(sec.market, &sec.code, qot.name.as_deref()))?;
         dict.set_item("is_suspended", qot.is_suspended)?;
         dict.set_item("cur_price", qot.cur_price)?;
         dict.set_item("price_spread", qot.price_spread)?;
@@ -56,16 +198,11 @@ fn decode_basic_qot(py: Python<'_>, body: &[u8]) -> PyResult<PyObject> {
     Ok(list.into_any().unbind())
 }
 
-fn decode_ticker(py: Python<'_>, body: &[u8]) -> PyResult<PyObject> {
-    let resp = crate::generated::qot_update_ticker::Response::decode(body)
-        .map_err(|e| PyValueError::new_err(format!("Decode error: {}", e)))?;
-
-    let s2c = resp.s2c
-        .ok_or_else(|| PyValueError::new_err("Missing s2c in ticker push"))?;
-
+fn ticker_to_py(py: Python<'_>, aliases: &SymbolMap, s2c: &qot_update_ticker::S2c) -> PyResult<PyObject> {
     let dict = PyDict::new_bound(py);
     dict.set_item("market", s2c.security.market)?;
     dict.set_item("code", &s2c.security.code)?;
+    dict.set_item("alias", aliases.alias(s2c.security.market, &s2c.security.code))?;
 
     let tickers = PyList::empty_bound(py);
     for t in &s2c.ticker_list {
@@ -82,49 +219,52 @@ fn decode_ticker(py: Python<'_>, body: &[u8]) -> PyResult<PyObject> {
     Ok(dict.into_any().unbind())
 }
 
-fn decode_order_book(py: Python<'_>, body: &[u8]) -> PyResult<PyObject> {
-    let resp = crate::generated::qot_update_order_book::Response::decode(body)
-        .map_err(|e| PyValueError::new_err(format!("Decode error: {}", e)))?;
-
-    let s2c = resp.s2c
-        .ok_or_else(|| PyValueError::new_err("Missing s2c in order book push"))?;
-
+fn order_book_to_py(py: Python<'_>, aliases: &SymbolMap, s2c: &qot_update_order_book::S2c) -> PyResult<PyObject> {
     let dict = PyDict::new_bound(py);
     dict.set_item("market", s2c.security.market)?;
     dict.set_item("code", &s2c.security.code)?;
+    dict.set_item("alias", aliases.alias(s2c.security.market, &s2c.security.code))?;
 
-    let asks = PyList::empty_bound(py);
-    for ob in &s2c.order_book_ask_list {
-        let d = PyDict::new_bound(py);
-        d.set_item("price", ob.price)?;
-        d.set_item("volume", ob.volume)?;
-        d.set_item("order_count", ob.order_count)?;
-        asks.append(d)?;
-    }
-    dict.set_item("asks", asks)?;
+    dict.set_item("asks", order_book_levels_to_py(py, &s2c.order_book_ask_list, true)?)?;
+    dict.set_item("bids", order_book_levels_to_py(py, &s2c.order_book_bid_list, true)?)?;
+    dict.set_item("depth", book_depth(&s2c.order_book_ask_list, &s2c.order_book_bid_list))?;
+    Ok(dict.into_any().unbind())
+}
 
-    let bids = PyList::empty_bound(py);
-    for ob in &s2c.order_book_bid_list {
+/// Order book levels as dicts of price, volume and order_count, plus the
+/// per-order "details" (order_id, volume) that SF quotes carry if
+/// `include_detail`.
+pub(crate) fn order_book_levels_to_py<'py>(
+    py: Python<'py>,
+    levels: &[crate::generated::qot_common::OrderBook],
+    include_detail: bool,
+) -> PyResult<Bound<'py, PyList>> {
+    let list = PyList::empty_bound(py);
+    for ob in levels {
         let d = PyDict::new_bound(py);
         d.set_item("price", ob.price)?;
         d.set_item("volume", ob.volume)?;
         d.set_item("order_count", ob.order_count)?;
-        bids.append(d)?;
+        if include_detail && !ob.detail_list.is_empty() {
+            let details = PyList::empty_bound(py);
+            for detail in &ob.detail_list {
+                let dd = PyDict::new_bound(py);
+                dd.set_item("order_id", detail.order_id)?;
+                dd.set_item("volume", detail.volume)?;
+                details.append(dd)?;
+            }
+            d.set_item("details", details)?;
+        }
+        list.append(d)?;
     }
-    dict.set_item("bids", bids)?;
-    Ok(dict.into_any().unbind())
+    Ok(list)
 }
 
-fn decode_kl(py: Python<'_>, body: &[u8]) -> PyResult<PyObject> {
-    let resp = crate::generated::qot_update_kl::Response::decode(body)
-        .map_err(|e| PyValueError::new_err(format!("Decode error: {}", e)))?;
-
-    let s2c = resp.s2c
-        .ok_or_else(|| PyValueError::new_err("Missing s2c in KL push"))?;
-
+fn kl_to_py(py: Python<'_>, aliases: &SymbolMap, s2c: &qot_update_kl::S2c) -> PyResult<PyObject> {
     let dict = PyDict::new_bound(py);
     dict.set_item("market", s2c.security.market)?;
     dict.set_item("code", &s2c.security.code)?;
+    dict.set_item("alias", aliases.alias(s2c.security.market, &s2c.security.code))?;
     dict.set_item("kl_type", s2c.kl_type)?;
     dict.set_item("rehab_type", s2c.rehab_type)?;
 
@@ -147,13 +287,31 @@ fn decode_kl(py: Python<'_>, body: &[u8]) -> PyResult<PyObject> {
     Ok(dict.into_any().unbind())
 }
 
-fn decode_trd_order(py: Python<'_>, body: &[u8]) -> PyResult<PyObject> {
-    let resp = crate::generated::trd_update_order::Response::decode(body)
-        .map_err(|e| PyValueError::new_err(format!("Decode error: {}", e)))?;
-
-    let s2c = resp.s2c
-        .ok_or_else(|| PyValueError::new_err("Missing s2c in order push"))?;
+fn price_reminder_to_py(
+    py: Python<'_>,
+    names: &NameTable,
+    aliases: &SymbolMap,
+    s2c: &qot_update_price_reminder::S2c,
+) -> PyResult<PyObject> {
+    let dict = PyDict::new_bound(py);
+    let sec = &s2c.security;
+    dict.set_item("market", sec.market)?;
+    dict.set_item("code", &sec.code)?;
+    dict.set_item("alias", aliases.alias(sec.market, &sec.code))?;
+    dict.set_item("name", names.localize_opt(sec.market, &sec.code, s2c.name.as_deref()))?;
+    dict.set_item("key", s2c.key)?;
+    dict.set_item("reminder_type", s2c.r#type)?;
+    dict.set_item("set_value", s2c.set_value)?;
+    dict.set_item("cur_value", s2c.cur_value)?;
+    dict.set_item("price", s2c.price)?;
+    dict.set_item("change_rate", s2c.change_rate)?;
+    dict.set_item("market_status", s2c.market_status)?;
+    dict.set_item("content", &s2c.content)?;
+    dict.set_item("note", &s2c.note)?;
+    Ok(dict.into_any().unbind())
+}
 
+fn trd_order_to_py(py: Python<'_>, names: &NameTable, aliases: &SymbolMap, s2c: &trd_update_order::S2c) -> PyResult<PyObject> {
     let dict = PyDict::new_bound(py);
     dict.set_item("trd_env", s2c.header.trd_env)?;
     dict.set_item("acc_id", s2c.header.acc_id)?;
@@ -166,7 +324,8 @@ fn decode_trd_order(py: Python<'_>, body: &[u8]) -> PyResult<PyObject> {
     order_dict.set_item("order_id", o.order_id)?;
     order_dict.set_item("order_id_ex", &o.order_id_ex)?;
     order_dict.set_item("code", &o.code)?;
-    order_dict.set_item("name", &o.name)?;
+    order_dict.set_item("alias", aliases.alias_trd(o.sec_market, &o.code))?;
+    order_dict.set_item("name", names.localize_trd(o.sec_market, &o.code, &o.name))?;
     order_dict.set_item("qty", o.qty)?;
     order_dict.set_item("price", o.price)?;
     order_dict.set_item("fill_qty", o.fill_qty)?;
@@ -181,13 +340,7 @@ fn decode_trd_order(py: Python<'_>, body: &[u8]) -> PyResult<PyObject> {
     Ok(dict.into_any().unbind())
 }
 
-fn decode_trd_fill(py: Python<'_>, body: &[u8]) -> PyResult<PyObject> {
-    let resp = crate::generated::trd_update_order_fill::Response::decode(body)
-        .map_err(|e| PyValueError::new_err(format!("Decode error: {}", e)))?;
-
-    let s2c = resp.s2c
-        .ok_or_else(|| PyValueError::new_err("Missing s2c in fill push"))?;
-
+fn trd_fill_to_py(py: Python<'_>, names: &NameTable, aliases: &SymbolMap, s2c: &trd_update_order_fill::S2c) -> PyResult<PyObject> {
     let dict = PyDict::new_bound(py);
     dict.set_item("trd_env", s2c.header.trd_env)?;
     dict.set_item("acc_id", s2c.header.acc_id)?;
@@ -200,7 +353,8 @@ fn decode_trd_fill(py: Python<'_>, body: &[u8]) -> PyResult<PyObject> {
     fill_dict.set_item("order_id", f.order_id)?;
     fill_dict.set_item("order_id_ex", &f.order_id_ex)?;
     fill_dict.set_item("code", &f.code)?;
-    fill_dict.set_item("name", &f.name)?;
+    fill_dict.set_item("alias", aliases.alias_trd(f.sec_market, &f.code))?;
+    fill_dict.set_item("name", names.localize_trd(f.sec_market, &f.code, &f.name))?;
     fill_dict.set_item("qty", f.qty)?;
     fill_dict.set_item("price", f.price)?;
     fill_dict.set_item("sec_market", f.sec_market)?;
@@ -213,19 +367,59 @@ fn decode_trd_fill(py: Python<'_>, body: &[u8]) -> PyResult<PyObject> {
     Ok(dict.into_any().unbind())
 }
 
+/// Flat dict of a Notify push: "type" (NotifyType) and the fields of the
+/// message it carries.
+fn notify_to_py(py: Python<'_>, s2c: &notify::S2c) -> PyResult<PyObject> {
+    let dict = PyDict::new_bound(py);
+    dict.set_item("type", s2c.r#type)?;
+    if let Some(e) = &s2c.event {
+        dict.set_item("event_type", e.event_type)?;
+        dict.set_item("desc", &e.desc)?;
+    }
+    if let Some(p) = &s2c.program_status {
+        dict.set_item("program_status", p.r#type)?;
+        dict.set_item("desc", &p.str_ext_desc)?;
+    }
+    if let Some(c) = &s2c.connect_status {
+        dict.set_item("qot_logined", c.qot_logined)?;
+        dict.set_item("trd_logined", c.trd_logined)?;
+    }
+    if let Some(r) = &s2c.qot_right {
+        dict.set_item("hk_qot_right", r.hk_qot_right)?;
+        dict.set_item("us_qot_right", r.us_qot_right)?;
+        dict.set_item("cn_qot_right", r.cn_qot_right)?;
+        dict.set_item("hk_option_qot_right", r.hk_option_qot_right)?;
+        dict.set_item("hk_future_qot_right", r.hk_future_qot_right)?;
+    }
+    if let Some(l) = &s2c.api_level {
+        dict.set_item("api_level", &l.api_level)?;
+    }
+    if let Some(q) = &s2c.api_quota {
+        dict.set_item("sub_quota", q.sub_quota)?;
+        dict.set_item("history_kl_quota", q.history_kl_quota)?;
+    }
+    if let Some(q) = &s2c.used_quota {
+        dict.set_item("used_sub_quota", q.used_sub_quota)?;
+        dict.set_item("used_kl_quota", q.used_k_line_quota)?;
+    }
+    Ok(dict.into_any().unbind())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use prost::Message;
-
+    
     #[test]
     fn test_proto_id_constants() {
         assert_eq!(PROTO_QOT_UPDATE_BASIC_QOT, 3005);
         assert_eq!(PROTO_QOT_UPDATE_TICKER, 3011);
         assert_eq!(PROTO_QOT_UPDATE_ORDER_BOOK, 3013);
         assert_eq!(PROTO_QOT_UPDATE_KL, 3007);
+        assert_eq!(PROTO_QOT_UPDATE_PRICE_REMINDER, 3019);
         assert_eq!(PROTO_TRD_UPDATE_ORDER, 2208);
         assert_eq!(PROTO_TRD_UPDATE_ORDER_FILL, 2218);
+        assert_eq!(PROTO_NOTIFY, 1003);
     }
 
     #[test]
@@ -464,6 +658,124 @@ mod tests {
         assert_eq!(s2c.order_fill.update_timestamp, Some(1704067210.0));
     }
 
+    #[test]
+    fn test_decode_push_without_gil() {
+        let s2c = crate::generated::qot_update_ticker::S2c {
+            security: crate::generated::qot_common::Security { market: 11, code: "AAPL".to_string() },
+            ticker_list: vec![crate::generated::qot_common::Ticker { price: 195.5, ..Default::default() }],
+            ..Default::default()
+        };
+        let resp = crate::generated::qot_update_ticker::Response { s2c: Some(s2c.clone()), ..Default::default() };
+        let body = bytes::Bytes::from(resp.encode_to_vec());
+
+        let decoded = decode_push(DecodeMode::Lenient, ProtoFmt::Protobuf, PROTO_QOT_UPDATE_TICKER, &body).unwrap();
+        assert_eq!(decoded, DecodedPush::Ticker(s2c));
+        // Raw and worker-decoded payloads end up the same
+        let raw = PushPayload::Raw(body, ProtoFmt::Protobuf).decode(DecodeMode::Lenient, PROTO_QOT_UPDATE_TICKER).unwrap();
+        let worker = PushPayload::Decoded(Ok(Box::new(decoded.clone()))).decode(DecodeMode::Lenient, PROTO_QOT_UPDATE_TICKER);
+        assert_eq!(raw, decoded);
+        assert_eq!(worker.unwrap(), decoded);
+    }
+
+    #[test]
+    fn test_order_book_detail_dropped() {
+        use crate::generated::qot_common::{OrderBook, OrderBookDetail, Security};
+        let level = |price| OrderBook {
+            price,
+            volume: 300,
+            order_count: 2,
+            detail_list: vec![OrderBookDetail { order_id: 1, volume: 100 }, OrderBookDetail { order_id: 2, volume: 200 }],
+        };
+        let s2c = qot_update_order_book::S2c {
+            security: Security { market: 1, code: "00700".to_string() },
+            order_book_ask_list: vec![level(346.0)],
+            order_book_bid_list: vec![level(345.0)],
+            ..Default::default()
+        };
+        let resp = qot_update_order_book::Response { s2c: Some(s2c.clone()), ..Default::default() };
+        let body = bytes::Bytes::from(resp.encode_to_vec());
+
+        let stripped = PushPayload::Raw(body.clone(), ProtoFmt::Protobuf)
+            .without_order_book_detail(DecodeMode::Lenient, PROTO_QOT_UPDATE_ORDER_BOOK)
+            .decode(DecodeMode::Lenient, PROTO_QOT_UPDATE_ORDER_BOOK)
+            .unwrap();
+        let DecodedPush::OrderBook(stripped) = stripped else { panic!("not an order book") };
+        assert!(stripped.order_book_ask_list.iter().chain(&stripped.order_book_bid_list).all(|l| l.detail_list.is_empty()));
+        assert_eq!(stripped.order_book_ask_list[0].volume, 300);
+        assert_eq!(stripped.order_book_bid_list[0].order_count, 2);
+
+        // Other pushes are passed through undecoded
+        let ticker = PushPayload::Raw(body, ProtoFmt::Protobuf).without_order_book_detail(DecodeMode::Lenient, PROTO_QOT_UPDATE_TICKER);
+        assert!(matches!(ticker, PushPayload::Raw(..)));
+    }
+
+    #[test]
+    fn test_price_reminder_push() {
+        let s2c = qot_update_price_reminder::S2c {
+            security: crate::generated::qot_common::Security { market: 1, code: "00700".to_string() },
+            price: 401.2,
+            change_rate: 2.5,
+            market_status: 1,
+            content: "Price rose to 400.000".to_string(),
+            key: Some(77),
+            r#type: Some(1),
+            set_value: Some(400.0),
+            cur_value: Some(401.2),
+            ..Default::default()
+        };
+        let resp = qot_update_price_reminder::Response { s2c: Some(s2c.clone()), ..Default::default() };
+        let decoded = decode_push(DecodeMode::Lenient, ProtoFmt::Protobuf, PROTO_QOT_UPDATE_PRICE_REMINDER, &resp.encode_to_vec()).unwrap();
+        assert_eq!(decoded, DecodedPush::PriceReminder(s2c));
+
+        let wanted: HashSet<(i32, String)> = [(11, "AAPL".to_string())].into_iter().collect();
+        assert!(decoded.retain_securities(&wanted).is_none());
+    }
+
+    #[test]
+    fn test_notify_push() {
+        let s2c = notify::S2c {
+            r#type: 6,
+            api_quota: Some(notify::ApiQuota { sub_quota: 300, history_kl_quota: 100 }),
+            ..Default::default()
+        };
+        let resp = notify::Response { s2c: Some(s2c.clone()), ..Default::default() };
+        let decoded = decode_push(DecodeMode::Lenient, ProtoFmt::Protobuf, PROTO_NOTIFY, &resp.encode_to_vec()).unwrap();
+        assert_eq!(decoded, DecodedPush::Notify(s2c));
+        assert!(decoded.retain_securities(&HashSet::new()).is_some());
+    }
+
+    #[test]
+    fn test_retain_securities() {
+        let security = |market: i32, code: &str| crate::generated::qot_common::Security { market, code: code.to_string() };
+        let qot = |market: i32, code: &str| crate::generated::qot_common::BasicQot { security: security(market, code), ..Default::default() };
+        let wanted: HashSet<(i32, String)> = [(1, "00700".to_string())].into_iter().collect();
+
+        let basic = DecodedPush::BasicQot(qot_update_basic_qot::S2c { basic_qot_list: vec![qot(1, "00700"), qot(11, "AAPL")] });
+        match basic.retain_securities(&wanted) {
+            Some(DecodedPush::BasicQot(s2c)) => assert_eq!(s2c.basic_qot_list, vec![qot(1, "00700")]),
+            other => panic!("unexpected {:?}", other),
+        }
+        let other_only = DecodedPush::BasicQot(qot_update_basic_qot::S2c { basic_qot_list: vec![qot(11, "AAPL")] });
+        assert!(other_only.retain_securities(&wanted).is_none());
+
+        let ticker = |market, code| DecodedPush::Ticker(qot_update_ticker::S2c { security: security(market, code), ..Default::default() });
+        assert!(ticker(1, "00700").retain_securities(&wanted).is_some());
+        assert!(ticker(11, "AAPL").retain_securities(&wanted).is_none());
+        let order = DecodedPush::Order(trd_update_order::S2c::default());
+        assert!(order.retain_securities(&HashSet::new()).is_some());
+    }
+
+    #[test]
+    fn test_decode_push_errors() {
+        let err = decode_push(DecodeMode::Lenient, ProtoFmt::Protobuf, 9999, b"").unwrap_err();
+        assert_eq!(err, "Unknown push proto_id: 9999");
+        let err = decode_push(DecodeMode::Lenient, ProtoFmt::Protobuf, PROTO_TRD_UPDATE_ORDER, b"not protobuf").unwrap_err();
+        assert!(err.starts_with("Decode error: "), "{}", err);
+        let empty = crate::generated::qot_update_kl::Response::default().encode_to_vec();
+        let err = decode_push(DecodeMode::Lenient, ProtoFmt::Protobuf, PROTO_QOT_UPDATE_KL, &empty).unwrap_err();
+        assert_eq!(err, "Missing s2c in KL push");
+    }
+
     #[test]
     fn test_invalid_body_errors() {
         let bad_body = b"this is not protobuf";
@@ -0,0 +1,651 @@
+//! Time bars aggregated from ticker and order book pushes.
+//!
+//! Futu K-line pushes only cover fixed periods of last prices. The aggregator
+//! builds bars of any SECOND, MINUTE or HOUR step, priced from trades (LAST)
+//! or the top of book (BID, ASK, MID), keyed by the `{step}-{aggregation}-{price}`
+//! bar specifications Nautilus uses, e.g. `1-MINUTE-LAST` or `5-MINUTE-MID`.
+//!
+//! Like Nautilus time bars, intervals are aligned from the start of the UTC
+//! day, bars are stamped with their close time and intervals without updates
+//! produce no bar. Updates are bucketed by local receipt time, matching the
+//! timestamps the adapter gives ticks.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use prost::Message;
+use tokio::sync::mpsc;
+
+use crate::client::FutuClient;
+use crate::types::{KlType, SubType};
+
+const PROTO_QOT_UPDATE_TICKER: u32 = 3011;
+const PROTO_QOT_UPDATE_ORDER_BOOK: u32 = 3013;
+
+/// How often the runner closes bars whose interval has ended.
+const TIMER_INTERVAL: Duration = Duration::from_millis(200);
+
+const SECS_PER_DAY: f64 = 86_400.0;
+
+/// Time unit of a bar specification. The aggregator builds SECOND, MINUTE
+/// and HOUR bars; DAY, WEEK and MONTH bars only come from Futu K-lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BarAggregation {
+    Second,
+    Minute,
+    Hour,
+    Day,
+    Week,
+    Month,
+}
+
+impl BarAggregation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BarAggregation::Second => "SECOND",
+            BarAggregation::Minute => "MINUTE",
+            BarAggregation::Hour => "HOUR",
+            BarAggregation::Day => "DAY",
+            BarAggregation::Week => "WEEK",
+            BarAggregation::Month => "MONTH",
+        }
+    }
+
+    /// The aggregator builds bars of this unit.
+    pub fn is_intraday(&self) -> bool {
+        matches!(self, BarAggregation::Second | BarAggregation::Minute | BarAggregation::Hour)
+    }
+
+    /// Length in seconds; a month counts as 31 days.
+    fn secs(&self) -> f64 {
+        match self {
+            BarAggregation::Second => 1.0,
+            BarAggregation::Minute => 60.0,
+            BarAggregation::Hour => 3_600.0,
+            BarAggregation::Day => SECS_PER_DAY,
+            BarAggregation::Week => 7.0 * SECS_PER_DAY,
+            BarAggregation::Month => 31.0 * SECS_PER_DAY,
+        }
+    }
+}
+
+/// Price a bar is built from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PriceType {
+    Bid,
+    Ask,
+    Mid,
+    Last,
+}
+
+impl PriceType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PriceType::Bid => "BID",
+            PriceType::Ask => "ASK",
+            PriceType::Mid => "MID",
+            PriceType::Last => "LAST",
+        }
+    }
+}
+
+/// A bar specification such as `5-MINUTE-MID`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BarSpec {
+    pub step: u32,
+    pub aggregation: BarAggregation,
+    pub price_type: PriceType,
+}
+
+impl BarSpec {
+    /// Bar length in seconds.
+    pub fn interval(&self) -> f64 {
+        f64::from(self.step) * self.aggregation.secs()
+    }
+
+    /// Sub type of the Futu K-line push carrying these bars. Futu K-lines
+    /// are priced from trades, so only LAST specs have one.
+    pub fn sub_type(&self) -> Option<SubType> {
+        if self.price_type != PriceType::Last {
+            return None;
+        }
+        Some(match (self.aggregation, self.step) {
+            (BarAggregation::Minute, 1) => SubType::Kl1min,
+            (BarAggregation::Minute, 5) => SubType::Kl5min,
+            (BarAggregation::Minute, 15) => SubType::Kl15min,
+            (BarAggregation::Minute, 30) => SubType::Kl30min,
+            (BarAggregation::Minute, 60) | (BarAggregation::Hour, 1) => SubType::Kl60min,
+            (BarAggregation::Day, 1) => SubType::KlDay,
+            (BarAggregation::Week, 1) => SubType::KlWeek,
+            (BarAggregation::Month, 1) => SubType::KlMonth,
+            _ => return None,
+        })
+    }
+
+    /// K-line type of historical requests for these bars, if Futu has one.
+    pub fn kl_type(&self) -> Option<KlType> {
+        if self.price_type != PriceType::Last {
+            return None;
+        }
+        Some(match (self.aggregation, self.step) {
+            (BarAggregation::Minute, 1) => KlType::KlType1min,
+            (BarAggregation::Minute, 5) => KlType::KlType5min,
+            (BarAggregation::Minute, 15) => KlType::KlType15min,
+            (BarAggregation::Minute, 30) => KlType::KlType30min,
+            (BarAggregation::Minute, 60) | (BarAggregation::Hour, 1) => KlType::KlType60min,
+            (BarAggregation::Day, 1) => KlType::Day,
+            (BarAggregation::Week, 1) => KlType::Week,
+            (BarAggregation::Month, 1) => KlType::Month,
+            _ => return None,
+        })
+    }
+
+    /// Start of the interval containing `ts`, counted from the UTC day start.
+    fn interval_start(&self, ts: f64) -> f64 {
+        let day_start = (ts / SECS_PER_DAY).floor() * SECS_PER_DAY;
+        day_start + ((ts - day_start) / self.interval()).floor() * self.interval()
+    }
+}
+
+impl fmt::Display for BarSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}-{}", self.step, self.aggregation.as_str(), self.price_type.as_str())
+    }
+}
+
+impl FromStr for BarSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid bar specification '{}', expected e.g. '1-MINUTE-LAST'", s);
+        let mut parts = s.split('-');
+        let (Some(step), Some(aggregation), Some(price_type), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(invalid());
+        };
+        let step = step.parse::<u32>().ok().filter(|s| *s > 0).ok_or_else(invalid)?;
+        let aggregation = match aggregation {
+            "SECOND" => BarAggregation::Second,
+            "MINUTE" => BarAggregation::Minute,
+            "HOUR" => BarAggregation::Hour,
+            other => return Err(format!("Unsupported bar aggregation '{}' in '{}'", other, s)),
+        };
+        let price_type = match price_type {
+            "BID" => PriceType::Bid,
+            "ASK" => PriceType::Ask,
+            "MID" => PriceType::Mid,
+            "LAST" => PriceType::Last,
+            other => return Err(format!("Unsupported price type '{}' in '{}'", other, s)),
+        };
+        let spec = BarSpec { step, aggregation, price_type };
+        if spec.interval() > SECS_PER_DAY {
+            return Err(format!("Bar interval of '{}' exceeds one day", s));
+        }
+        Ok(spec)
+    }
+}
+
+/// What to do with bars that do not cover their whole interval: the first
+/// bar after a subscription starts mid-interval, and the open bar when a
+/// subscription is removed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PartialBarPolicy {
+    /// Drop partial bars, so every emitted bar covers a full interval.
+    #[default]
+    Skip,
+    /// Emit partial bars with `partial` set.
+    Emit,
+}
+
+impl FromStr for PartialBarPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(PartialBarPolicy::Skip),
+            "emit" => Ok(PartialBarPolicy::Emit),
+            other => Err(format!("Unknown partial bar policy '{}', expected 'skip' or 'emit'", other)),
+        }
+    }
+}
+
+/// A completed (or partial) bar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggregatedBar {
+    pub market: i32,
+    pub code: String,
+    pub spec: BarSpec,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+    /// Updates aggregated into the bar.
+    pub tick_count: u64,
+    /// Unix timestamp of the interval start.
+    pub ts_open: f64,
+    /// Unix timestamp of the interval end; the bar's event time.
+    pub ts_close: f64,
+    /// The bar does not cover its whole interval.
+    pub partial: bool,
+}
+
+#[derive(Debug, Clone)]
+struct OpenBar {
+    start: f64,
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: f64,
+    tick_count: u64,
+}
+
+#[derive(Debug)]
+struct BarBuilder {
+    spec: BarSpec,
+    subscribed_at: f64,
+    current: Option<OpenBar>,
+}
+
+impl BarBuilder {
+    fn update(&mut self, price: f64, size: f64, now: f64) -> Option<(OpenBar, bool)> {
+        let start = self.spec.interval_start(now);
+        let closed = self.current.take_if(|bar| bar.start < start).map(|bar| self.finish(bar, now));
+        match self.current.as_mut() {
+            Some(bar) => {
+                bar.high = bar.high.max(price);
+                bar.low = bar.low.min(price);
+                bar.close = price;
+                bar.volume += size;
+                bar.tick_count += 1;
+            }
+            None => {
+                self.current = Some(OpenBar {
+                    start,
+                    open: price,
+                    high: price,
+                    low: price,
+                    close: price,
+                    volume: size,
+                    tick_count: 1,
+                });
+            }
+        }
+        closed
+    }
+
+    /// Close the open bar if its interval has ended by `now`.
+    fn close_due(&mut self, now: f64) -> Option<(OpenBar, bool)> {
+        let interval = self.spec.interval();
+        let bar = self.current.take_if(|bar| bar.start + interval <= now)?;
+        Some(self.finish(bar, now))
+    }
+
+    /// The bar with whether it is partial.
+    fn finish(&self, bar: OpenBar, now: f64) -> (OpenBar, bool) {
+        let partial = bar.start < self.subscribed_at || now < bar.start + self.spec.interval();
+        (bar, partial)
+    }
+}
+
+/// Builds bars for (security, spec) subscriptions from quote pushes.
+#[derive(Debug, Default)]
+pub struct BarAggregator {
+    policy: PartialBarPolicy,
+    builders: HashMap<(i32, String), Vec<BarBuilder>>,
+}
+
+impl BarAggregator {
+    pub fn new(policy: PartialBarPolicy) -> Self {
+        Self { policy, builders: HashMap::new() }
+    }
+
+    pub fn policy(&self) -> PartialBarPolicy {
+        self.policy
+    }
+
+    /// Start building `spec` bars for a security. Returns false if already
+    /// added or `spec` is not intraday.
+    pub fn add(&mut self, market: i32, code: &str, spec: BarSpec, now: f64) -> bool {
+        if !spec.aggregation.is_intraday() {
+            return false;
+        }
+        let builders = self.builders.entry((market, code.to_string())).or_default();
+        if builders.iter().any(|b| b.spec == spec) {
+            return false;
+        }
+        builders.push(BarBuilder { spec, subscribed_at: now, current: None });
+        true
+    }
+
+    /// Stop building `spec` bars for a security. Returns the open bar if the
+    /// partial bar policy emits it, or None if the spec was not added.
+    pub fn remove(&mut self, market: i32, code: &str, spec: BarSpec, now: f64) -> Option<Vec<AggregatedBar>> {
+        let key = (market, code.to_string());
+        let builders = self.builders.get_mut(&key)?;
+        let pos = builders.iter().position(|b| b.spec == spec)?;
+        let mut builder = builders.remove(pos);
+        if builders.is_empty() {
+            self.builders.remove(&key);
+        }
+        let mut out = Vec::new();
+        if let Some(bar) = builder.current.take() {
+            emit(self.policy, &key, spec, builder.finish(bar, now), &mut out);
+        }
+        Some(out)
+    }
+
+    /// (market, code, spec) of every subscription.
+    pub fn subscriptions(&self) -> Vec<(i32, String, BarSpec)> {
+        self.builders
+            .iter()
+            .flat_map(|((market, code), builders)| builders.iter().map(|b| (*market, code.clone(), b.spec)))
+            .collect()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.builders.is_empty()
+    }
+
+    /// Apply a trade to LAST bars.
+    pub fn on_trade(&mut self, market: i32, code: &str, price: f64, volume: f64, now: f64) -> Vec<AggregatedBar> {
+        self.update(market, code, now, |price_type| (price_type == PriceType::Last).then_some((price, volume)))
+    }
+
+    /// Apply a top-of-book change, as (price, size) per side, to BID, ASK and
+    /// MID bars. MID bars need both sides and use the average size.
+    pub fn on_quote(
+        &mut self,
+        market: i32,
+        code: &str,
+        bid: Option<(f64, f64)>,
+        ask: Option<(f64, f64)>,
+        now: f64,
+    ) -> Vec<AggregatedBar> {
+        self.update(market, code, now, |price_type| match price_type {
+            PriceType::Bid => bid,
+            PriceType::Ask => ask,
+            PriceType::Mid => bid.zip(ask).map(|((bp, bs), (ap, az))| ((bp + ap) / 2.0, (bs + az) / 2.0)),
+            PriceType::Last => None,
+        })
+    }
+
+    /// Close every bar whose interval has ended by `now`.
+    pub fn on_timer(&mut self, now: f64) -> Vec<AggregatedBar> {
+        let mut out = Vec::new();
+        let policy = self.policy;
+        for (key, builders) in &mut self.builders {
+            for builder in builders.iter_mut() {
+                if let Some(closed) = builder.close_due(now) {
+                    emit(policy, key, builder.spec, closed, &mut out);
+                }
+            }
+        }
+        out
+    }
+
+    /// Apply a ticker (3011) or order book (3013) push. Other proto ids and
+    /// undecodable bodies produce nothing.
+    pub fn record_push(&mut self, proto_id: u32, body: &[u8], now: f64) -> Vec<AggregatedBar> {
+        match proto_id {
+            PROTO_QOT_UPDATE_TICKER => {
+                let Some(s2c) = crate::generated::qot_update_ticker::Response::decode(body).ok().and_then(|r| r.s2c)
+                else {
+                    return Vec::new();
+                };
+                let security = &s2c.security;
+                let mut out = Vec::new();
+                for ticker in &s2c.ticker_list {
+                    out.extend(self.on_trade(security.market, &security.code, ticker.price, ticker.volume as f64, now));
+                }
+                out
+            }
+            PROTO_QOT_UPDATE_ORDER_BOOK => {
+                let Some(s2c) =
+                    crate::generated::qot_update_order_book::Response::decode(body).ok().and_then(|r| r.s2c)
+                else {
+                    return Vec::new();
+                };
+                let top = |levels: &[crate::generated::qot_common::OrderBook]| {
+                    levels.first().map(|l| (l.price, l.volume as f64))
+                };
+                let (bid, ask) = (top(&s2c.order_book_bid_list), top(&s2c.order_book_ask_list));
+                self.on_quote(s2c.security.market, &s2c.security.code, bid, ask, now)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn update(
+        &mut self,
+        market: i32,
+        code: &str,
+        now: f64,
+        price_of: impl Fn(PriceType) -> Option<(f64, f64)>,
+    ) -> Vec<AggregatedBar> {
+        let key = (market, code.to_string());
+        let mut out = Vec::new();
+        let Some(builders) = self.builders.get_mut(&key) else {
+            return out;
+        };
+        for builder in builders.iter_mut() {
+            if let Some((price, size)) = price_of(builder.spec.price_type) {
+                if let Some(closed) = builder.update(price, size, now) {
+                    emit(self.policy, &key, builder.spec, closed, &mut out);
+                }
+            }
+        }
+        out
+    }
+}
+
+fn emit(
+    policy: PartialBarPolicy,
+    (market, code): &(i32, String),
+    spec: BarSpec,
+    (bar, partial): (OpenBar, bool),
+    out: &mut Vec<AggregatedBar>,
+) {
+    if partial && policy == PartialBarPolicy::Skip {
+        return;
+    }
+    out.push(AggregatedBar {
+        market: *market,
+        code: code.clone(),
+        spec,
+        open: bar.open,
+        high: bar.high,
+        low: bar.low,
+        close: bar.close,
+        volume: bar.volume,
+        tick_count: bar.tick_count,
+        ts_open: bar.start,
+        ts_close: bar.start + spec.interval(),
+        partial,
+    });
+}
+
+/// Feed ticker and order book pushes into `aggregator` and send the bars it
+/// produces until `tx` is closed.
+///
+/// Only pushes OpenD already sends are aggregated: securities need a TICKER
+/// subscription for LAST bars and an ORDER_BOOK one for BID, ASK and MID bars.
+pub async fn run_bar_aggregator(
+    client: Arc<FutuClient>,
+    aggregator: Arc<Mutex<BarAggregator>>,
+    tx: mpsc::UnboundedSender<AggregatedBar>,
+) {
+    let mut tickers = client.subscribe_push(PROTO_QOT_UPDATE_TICKER).await;
+    let mut books = client.subscribe_push(PROTO_QOT_UPDATE_ORDER_BOOK).await;
+    let clock = Arc::clone(client.clock());
+    // A deadline rather than a fresh sleep per iteration, so a steady stream
+    // of pushes cannot hold the timer off
+    let mut next_timer = clock.now() + TIMER_INTERVAL;
+
+    loop {
+        let bars = tokio::select! {
+            Some(msg) = tickers.recv() => aggregator.lock().record_push(msg.proto_id, &msg.body, clock.unix_time()),
+            Some(msg) = books.recv() => aggregator.lock().record_push(msg.proto_id, &msg.body, clock.unix_time()),
+            _ = clock.sleep_until(next_timer) => {
+                next_timer = clock.now() + TIMER_INTERVAL;
+                aggregator.lock().on_timer(clock.unix_time())
+            }
+            else => break,
+        };
+        for bar in bars {
+            if tx.send(bar).is_err() {
+                tracing::debug!("Bar aggregator stopped");
+                return;
+            }
+        }
+        if tx.is_closed() {
+            break;
+        }
+    }
+    tracing::debug!("Bar aggregator stopped");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: f64 = 1_704_153_600.0; // 2024-01-02 00:00:00 UTC
+
+    fn spec(s: &str) -> BarSpec {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_bar_spec() {
+        let s = spec("5-MINUTE-MID");
+        assert_eq!(s.step, 5);
+        assert_eq!(s.aggregation, BarAggregation::Minute);
+        assert_eq!(s.price_type, PriceType::Mid);
+        assert_eq!(s.interval(), 300.0);
+        assert_eq!(s.to_string(), "5-MINUTE-MID");
+
+        assert!("1-DAY-LAST".parse::<BarSpec>().is_err());
+        assert!("0-MINUTE-LAST".parse::<BarSpec>().is_err());
+        assert!("1-MINUTE-LAST-EXTERNAL".parse::<BarSpec>().is_err());
+        assert!("25-HOUR-LAST".parse::<BarSpec>().is_err());
+        assert_eq!("emit".parse::<PartialBarPolicy>(), Ok(PartialBarPolicy::Emit));
+    }
+
+    #[test]
+    fn test_kline_mapping() {
+        let day = BarSpec { step: 1, aggregation: BarAggregation::Day, price_type: PriceType::Last };
+        assert_eq!(day.sub_type(), Some(SubType::KlDay));
+        assert_eq!(BarSpec { step: 2, ..day }.sub_type(), None);
+        assert_eq!(BarSpec { aggregation: BarAggregation::Week, ..day }.kl_type(), Some(KlType::Week));
+        assert_eq!(spec("5-MINUTE-LAST").sub_type(), Some(SubType::Kl5min));
+        assert_eq!(spec("1-HOUR-LAST").sub_type(), Some(SubType::Kl60min));
+        assert_eq!(spec("60-MINUTE-LAST").kl_type(), Some(KlType::KlType60min));
+        assert_eq!(spec("3-MINUTE-LAST").kl_type(), None);
+        assert_eq!(spec("1-MINUTE-MID").sub_type(), None);
+
+        // Bars of a day or longer are not aggregated
+        let mut agg = BarAggregator::new(PartialBarPolicy::Skip);
+        assert!(!agg.add(1, "00700", day, DAY));
+        assert!(agg.is_empty());
+    }
+
+    #[test]
+    fn test_interval_alignment_from_day_start() {
+        let s = spec("7-MINUTE-LAST");
+        // 7 minutes does not divide a day; intervals restart at midnight
+        assert_eq!(s.interval_start(DAY + 1.0), DAY);
+        assert_eq!(s.interval_start(DAY + 7.0 * 60.0 + 1.0), DAY + 420.0);
+        assert_eq!(s.interval_start(DAY - 1.0), DAY - 60.0 * (1440 % 7) as f64);
+    }
+
+    #[test]
+    fn test_last_bars_skip_first_partial() {
+        let mut agg = BarAggregator::new(PartialBarPolicy::Skip);
+        assert!(agg.add(1, "00700", spec("1-MINUTE-LAST"), DAY + 30.0));
+        assert!(!agg.add(1, "00700", spec("1-MINUTE-LAST"), DAY + 30.0));
+
+        // First interval started before the subscription: partial, skipped
+        assert!(agg.on_trade(1, "00700", 100.0, 10.0, DAY + 40.0).is_empty());
+        assert!(agg.on_trade(1, "00700", 101.0, 5.0, DAY + 61.0).is_empty());
+        assert!(agg.on_trade(1, "00700", 99.0, 5.0, DAY + 90.0).is_empty());
+        assert!(agg.on_trade(1, "00700", 100.5, 5.0, DAY + 100.0).is_empty());
+        // Quotes do not touch LAST bars
+        assert!(agg.on_quote(1, "00700", Some((1.0, 1.0)), Some((2.0, 1.0)), DAY + 110.0).is_empty());
+
+        let bars = agg.on_timer(DAY + 120.0);
+        assert_eq!(bars.len(), 1);
+        let bar = &bars[0];
+        assert_eq!((bar.open, bar.high, bar.low, bar.close), (101.0, 101.0, 99.0, 100.5));
+        assert_eq!(bar.volume, 15.0);
+        assert_eq!(bar.tick_count, 3);
+        assert_eq!((bar.ts_open, bar.ts_close), (DAY + 60.0, DAY + 120.0));
+        assert!(!bar.partial);
+
+        // No updates: no bar
+        assert!(agg.on_timer(DAY + 300.0).is_empty());
+    }
+
+    #[test]
+    fn test_quote_bars_and_partial_emission() {
+        let mut agg = BarAggregator::new(PartialBarPolicy::Emit);
+        agg.add(1, "00700", spec("10-SECOND-MID"), DAY + 5.0);
+        agg.add(1, "00700", spec("10-SECOND-BID"), DAY + 5.0);
+
+        assert!(agg.on_quote(1, "00700", Some((100.0, 10.0)), Some((100.2, 30.0)), DAY + 6.0).is_empty());
+        // Ask only: BID bars are unaffected, MID needs both sides
+        assert!(agg.on_quote(1, "00700", None, Some((100.4, 1.0)), DAY + 7.0).is_empty());
+
+        let mut bars = agg.on_quote(1, "00700", Some((100.2, 10.0)), Some((100.4, 10.0)), DAY + 12.0);
+        bars.sort_by_key(|b| b.spec.price_type.as_str());
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].spec.price_type, PriceType::Bid);
+        assert_eq!(bars[0].close, 100.0);
+        assert_eq!(bars[1].spec.price_type, PriceType::Mid);
+        assert!((bars[1].close - 100.1).abs() < 1e-9);
+        assert_eq!(bars[1].volume, 20.0);
+        assert!(bars.iter().all(|b| b.partial));
+
+        // Removing mid-interval emits the open bar as partial
+        let removed = agg.remove(1, "00700", spec("10-SECOND-MID"), DAY + 15.0).unwrap();
+        assert_eq!(removed.len(), 1);
+        assert!(removed[0].partial);
+        assert_eq!(removed[0].ts_close, DAY + 20.0);
+        assert_eq!(agg.subscriptions().len(), 1);
+        assert!(agg.remove(1, "00700", spec("10-SECOND-MID"), DAY + 16.0).is_none());
+    }
+
+    #[test]
+    fn test_record_ticker_push() {
+        use crate::generated::qot_common::{Security, Ticker};
+        use crate::generated::qot_update_ticker::{Response, S2c};
+
+        let mut agg = BarAggregator::new(PartialBarPolicy::Emit);
+        agg.add(1, "00700", spec("1-SECOND-LAST"), DAY);
+        let body = Response {
+            ret_type: 0,
+            s2c: Some(S2c {
+                security: Security { market: 1, code: "00700".to_string() },
+                ticker_list: vec![
+                    Ticker { price: 10.0, volume: 100, ..Default::default() },
+                    Ticker { price: 11.0, volume: 200, ..Default::default() },
+                ],
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+        .encode_to_vec();
+        assert!(agg.record_push(PROTO_QOT_UPDATE_TICKER, &body, DAY + 0.5).is_empty());
+        assert!(agg.record_push(3005, &body, DAY + 0.6).is_empty());
+
+        let bars = agg.on_timer(DAY + 1.0);
+        assert_eq!(bars.len(), 1);
+        assert_eq!((bars[0].open, bars[0].close, bars[0].volume), (10.0, 11.0, 300.0));
+        assert!(!bars[0].partial);
+    }
+}
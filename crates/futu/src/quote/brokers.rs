@@ -0,0 +1,151 @@
+//! Broker ID reference table for broker queues.
+//!
+//! Qot_GetBroker lists brokers by seat ID. A firm trades through many
+//! seats, each with its own ID, and OpenD neither exposes its broker list
+//! nor says which seats belong together. This table maps seat IDs to names
+//! and to a group, the firm behind the seat. It learns names from broker
+//! queue responses and loads reference tables from CSV; a seat without an
+//! explicit group is grouped by its name, as OpenD names every seat of a
+//! firm alike.
+
+use std::collections::HashMap;
+
+/// Name and group of a broker seat.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrokerInfo {
+    pub name: String,
+    /// Explicit group, or None to group by name.
+    pub group: Option<String>,
+}
+
+impl BrokerInfo {
+    /// Group of the seat; its name unless a group was given.
+    pub fn group(&self) -> &str {
+        self.group.as_deref().unwrap_or(&self.name)
+    }
+}
+
+/// Broker seats by ID.
+#[derive(Debug, Clone, Default)]
+pub struct BrokerTable {
+    brokers: HashMap<i64, BrokerInfo>,
+}
+
+impl BrokerTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add or replace seats. Entries are (id, name, group).
+    pub fn add(&mut self, entries: impl IntoIterator<Item = (i64, String, Option<String>)>) {
+        for (id, name, group) in entries {
+            self.brokers.insert(id, BrokerInfo { name, group });
+        }
+    }
+
+    /// Record the name OpenD returned for a seat. Names of known seats are
+    /// kept, so a loaded table wins over the gateway language.
+    pub fn learn(&mut self, id: i64, name: &str) {
+        if name.is_empty() {
+            return;
+        }
+        let info = self.brokers.entry(id).or_insert_with(|| BrokerInfo { name: String::new(), group: None });
+        if info.name.is_empty() {
+            info.name = name.to_string();
+        }
+    }
+
+    pub fn get(&self, id: i64) -> Option<&BrokerInfo> {
+        self.brokers.get(&id).filter(|info| !info.name.is_empty())
+    }
+
+    pub fn name(&self, id: i64) -> Option<&str> {
+        self.get(id).map(|info| info.name.as_str())
+    }
+
+    pub fn group(&self, id: i64) -> Option<&str> {
+        self.get(id).map(BrokerInfo::group)
+    }
+
+    /// IDs of the seats in `group`, ascending.
+    pub fn seats(&self, group: &str) -> Vec<i64> {
+        let mut ids: Vec<i64> = self
+            .brokers
+            .iter()
+            .filter(|(_, info)| !info.name.is_empty() && info.group() == group)
+            .map(|(id, _)| *id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    pub fn len(&self) -> usize {
+        self.brokers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.brokers.is_empty()
+    }
+}
+
+/// Parse a reference table of `id,name[,group]` lines. Blank lines, lines
+/// starting with `#` and a leading header line are skipped.
+pub fn parse_broker_csv(text: &str) -> Result<Vec<(i64, String, Option<String>)>, String> {
+    let mut entries = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut fields = line.splitn(3, ',').map(str::trim);
+        let id = fields.next().unwrap_or_default();
+        let id = match id.parse::<i64>() {
+            Ok(id) => id,
+            Err(_) if entries.is_empty() && id.eq_ignore_ascii_case("id") => continue,
+            Err(_) => return Err(format!("line {}: invalid broker id {:?}", n + 1, id)),
+        };
+        let name = fields.next().filter(|name| !name.is_empty())
+            .ok_or_else(|| format!("line {}: missing broker name", n + 1))?;
+        let group = fields.next().filter(|group| !group.is_empty()).map(str::to_string);
+        entries.push((id, name.to_string(), group));
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_groups_by_name_unless_given() {
+        let mut table = BrokerTable::new();
+        for id in [6996, 6997, 6998] {
+            table.learn(id, "中银国际");
+        }
+        table.learn(8463, "Morgan Stanley");
+        table.add(vec![(4488, "MS Asia Ltd".to_string(), Some("Morgan Stanley".to_string()))]);
+        assert_eq!(table.seats("中银国际"), vec![6996, 6997, 6998]);
+        assert_eq!(table.seats("Morgan Stanley"), vec![4488, 8463]);
+        assert_eq!(table.group(4488), Some("Morgan Stanley"));
+        assert_eq!(table.name(4488), Some("MS Asia Ltd"));
+        assert_eq!(table.name(1), None);
+
+        // Loaded names are not overwritten by the gateway's
+        table.learn(4488, "摩根士丹利");
+        assert_eq!(table.name(4488), Some("MS Asia Ltd"));
+        table.learn(2, "");
+        assert_eq!(table.len(), 5);
+    }
+
+    #[test]
+    fn test_parse_broker_csv() {
+        let text = "id,name,group\n# seats\n6996, 中银国际 \n\n4488,MS Asia Ltd,Morgan Stanley\n";
+        let entries = parse_broker_csv(text).unwrap();
+        assert_eq!(entries, vec![
+            (6996, "中银国际".to_string(), None),
+            (4488, "MS Asia Ltd".to_string(), Some("Morgan Stanley".to_string())),
+        ]);
+        assert!(parse_broker_csv("6996,中银国际\nx,y").unwrap_err().starts_with("line 2"));
+        assert!(parse_broker_csv("6996").unwrap_err().contains("missing broker name"));
+    }
+}
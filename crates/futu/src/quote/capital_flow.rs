@@ -0,0 +1,238 @@
+//! Bulk download of historical capital flow across securities.
+//!
+//! Qot_GetCapitalFlow returns the flow of one security per request and
+//! limits how far a single request reaches back, so `download_capital_flow`
+//! splits the date range into chunks, requests every (security, chunk) pair
+//! one at a time with a pause between requests to stay under the
+//! protocol's frequency limit, and merges the items into one table of
+//! `CapitalFlowRow`. A security whose request fails is reported in
+//! `failed`, with the chunk to resume from, and the download moves on to
+//! the next one.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+use prost::Message;
+
+use crate::client::FutuClient;
+use crate::generated::qot_get_capital_flow::CapitalFlowItem;
+use super::subscribe::QuoteError;
+use crate::dates::add_days;
+
+const PROTO_QOT_GET_CAPITAL_FLOW: u32 = 3211;
+
+// Qot_Common.PeriodType values
+pub const PERIOD_INTRADAY: i32 = 1;
+pub const PERIOD_DAY: i32 = 2;
+pub const PERIOD_WEEK: i32 = 3;
+pub const PERIOD_MONTH: i32 = 4;
+
+/// How `download_capital_flow` splits and paces its requests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapitalFlowDownloadOptions {
+    /// Days of the range covered by one request.
+    pub chunk_days: i64,
+    /// Pause between requests.
+    pub request_interval: Duration,
+}
+
+impl Default for CapitalFlowDownloadOptions {
+    fn default() -> Self {
+        Self { chunk_days: 365, request_interval: Duration::from_secs(1) }
+    }
+}
+
+/// One capital flow item of one security.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapitalFlowRow {
+    pub market: i32,
+    pub code: String,
+    pub time: String,
+    pub timestamp: Option<f64>,
+    pub in_flow: f64,
+    pub main_in_flow: Option<f64>,
+    pub super_in_flow: Option<f64>,
+    pub big_in_flow: Option<f64>,
+    pub mid_in_flow: Option<f64>,
+    pub sml_in_flow: Option<f64>,
+}
+
+impl CapitalFlowRow {
+    fn new(security: &(i32, String), item: CapitalFlowItem) -> Self {
+        Self {
+            market: security.0,
+            code: security.1.clone(),
+            time: item.time.unwrap_or_default(),
+            timestamp: item.timestamp,
+            in_flow: item.in_flow,
+            main_in_flow: item.main_in_flow,
+            super_in_flow: item.super_in_flow,
+            big_in_flow: item.big_in_flow,
+            mid_in_flow: item.mid_in_flow,
+            sml_in_flow: item.sml_in_flow,
+        }
+    }
+}
+
+/// A security whose download stopped at a failed chunk.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapitalFlowFailure {
+    pub security: (i32, String),
+    /// Start of the failed chunk; downloading the security again from here
+    /// completes it.
+    pub begin_time: String,
+    pub error: String,
+}
+
+/// Merged rows of a download, by security (in request order) then time.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CapitalFlowDownload {
+    pub rows: Vec<CapitalFlowRow>,
+    /// Securities whose download failed. Their rows from chunks fetched
+    /// before the failure are kept.
+    pub failed: Vec<CapitalFlowFailure>,
+    pub requests: usize,
+}
+
+/// Split `begin..=end` (`YYYY-MM-DD`) into consecutive ranges of at most
+/// `chunk_days` days. An unparsable date gives the range unsplit.
+pub fn date_chunks(begin: &str, end: &str, chunk_days: i64) -> Vec<(String, String)> {
+    let chunk_days = chunk_days.max(1);
+    let mut chunks = Vec::new();
+    let mut start = begin.get(..10).unwrap_or(begin).to_string();
+    let end = end.get(..10).unwrap_or(end);
+    if add_days(&start, 0).is_none() || add_days(end, 0).is_none() {
+        return vec![(begin.to_string(), end.to_string())];
+    }
+    while start.as_str() <= end {
+        let Some(chunk_end) = add_days(&start, chunk_days - 1) else { break };
+        let chunk_end = if chunk_end.as_str() < end { chunk_end } else { end.to_string() };
+        let Some(next) = add_days(&chunk_end, 1) else { break };
+        chunks.push((start, chunk_end));
+        start = next;
+    }
+    chunks
+}
+
+/// Capital flow of one security over `begin_time..=end_time`.
+pub async fn get_capital_flow_range(
+    client: &FutuClient,
+    market: i32,
+    code: String,
+    period_type: i32,
+    begin_time: String,
+    end_time: String,
+) -> Result<crate::generated::qot_get_capital_flow::Response, QuoteError> {
+    let security = crate::generated::qot_common::Security { market, code };
+    let c2s = crate::generated::qot_get_capital_flow::C2s {
+        security,
+        period_type: Some(period_type),
+        begin_time: Some(begin_time),
+        end_time: Some(end_time),
+    };
+    let request = crate::generated::qot_get_capital_flow::Request { c2s };
+    let body = request.encode_to_vec();
+
+    let resp = client.request(PROTO_QOT_GET_CAPITAL_FLOW, &body).await
+        .map_err(QuoteError::Connection)?;
+
+    let response = client.decode::<crate::generated::qot_get_capital_flow::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
+
+    if response.ret_type != 0 {
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
+    }
+
+    Ok(response)
+}
+
+/// Download the capital flow of `securities` between `begin_time` and
+/// `end_time` (`YYYY-MM-DD`). Intraday flow has no history, so
+/// `PERIOD_INTRADAY` makes one request per security for the latest day.
+pub async fn download_capital_flow(
+    client: &FutuClient,
+    securities: &[(i32, String)],
+    period_type: i32,
+    begin_time: &str,
+    end_time: &str,
+    options: &CapitalFlowDownloadOptions,
+) -> CapitalFlowDownload {
+    let chunks = if period_type == PERIOD_INTRADAY {
+        vec![(begin_time.to_string(), end_time.to_string())]
+    } else {
+        date_chunks(begin_time, end_time, options.chunk_days)
+    };
+    let mut download = CapitalFlowDownload::default();
+
+    for security in securities {
+        let mut rows: BTreeMap<String, CapitalFlowRow> = BTreeMap::new();
+        for (begin, end) in &chunks {
+            if download.requests > 0 {
+                client.clock().sleep(options.request_interval).await;
+            }
+            download.requests += 1;
+            let result = get_capital_flow_range(
+                client, security.0, security.1.clone(), period_type, begin.clone(), end.clone(),
+            ).await;
+            match result {
+                Ok(resp) => {
+                    let items = resp.s2c.map(|s| s.flow_item_list).unwrap_or_default();
+                    for item in items {
+                        let row = CapitalFlowRow::new(security, item);
+                        // Chunks share no days, but keep the latest item if OpenD overlaps them
+                        rows.insert(row.time.clone(), row);
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Capital flow of {}.{} failed for {}..{}: {}", security.0, security.1, begin, end, e);
+                    download.failed.push(CapitalFlowFailure {
+                        security: security.clone(),
+                        begin_time: begin.clone(),
+                        error: e.to_string(),
+                    });
+                    break;
+                }
+            }
+        }
+        download.rows.extend(rows.into_values());
+    }
+    download
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(begin: &str, end: &str) -> (String, String) {
+        (begin.to_string(), end.to_string())
+    }
+
+    #[test]
+    fn test_date_chunks() {
+        assert_eq!(
+            date_chunks("2023-01-01", "2024-03-01", 365),
+            vec![chunk("2023-01-01", "2023-12-31"), chunk("2024-01-01", "2024-03-01")],
+        );
+        // Leap day and a single-day range
+        assert_eq!(
+            date_chunks("2024-02-28", "2024-03-02", 2),
+            vec![chunk("2024-02-28", "2024-02-29"), chunk("2024-03-01", "2024-03-02")],
+        );
+        assert_eq!(date_chunks("2024-05-06 00:00:00", "2024-05-06", 30), vec![chunk("2024-05-06", "2024-05-06")]);
+        assert!(date_chunks("2024-05-07", "2024-05-06", 30).is_empty());
+        assert_eq!(date_chunks("bad", "2024-05-06", 30), vec![chunk("bad", "2024-05-06")]);
+    }
+
+    #[test]
+    fn test_row_from_item() {
+        let item = CapitalFlowItem {
+            in_flow: 1.5e8,
+            time: Some("2024-05-06".to_string()),
+            main_in_flow: Some(1.0e8),
+            ..Default::default()
+        };
+        let row = CapitalFlowRow::new(&(1, "00700".to_string()), item);
+        assert_eq!((row.market, row.code.as_str(), row.time.as_str()), (1, "00700", "2024-05-06"));
+        assert_eq!(row.main_in_flow, Some(1.0e8));
+        assert_eq!(row.sml_in_flow, None);
+    }
+}
@@ -1,5 +1,8 @@
 use prost::Message;
 use crate::client::FutuClient;
+use crate::generated::qot_common::KLine;
+use super::kl_check::{validate_kl, KlCalendar, KlReport};
+use super::sessions::trade_date_market;
 use super::subscribe::QuoteError;
 
 const PROTO_QOT_GET_KL: u32 = 3006;
@@ -8,12 +11,15 @@ const PROTO_QOT_GET_HISTORY_KL: u32 = 3103;
 /// Get K-line (candlestick) data for a subscribed security.
 pub async fn get_kl(
     client: &FutuClient,
-    market: i32,
+    market: impl Into<i32>,
     code: String,
-    rehab_type: i32,
-    kl_type: i32,
+    rehab_type: impl Into<i32>,
+    kl_type: impl Into<i32>,
     req_count: i32,
 ) -> Result<crate::generated::qot_get_kl::Response, QuoteError> {
+    let market = market.into();
+    let rehab_type = rehab_type.into();
+    let kl_type = kl_type.into();
     let security = crate::generated::qot_common::Security { market, code };
     let c2s = crate::generated::qot_get_kl::C2s {
         rehab_type,
@@ -27,14 +33,11 @@ pub async fn get_kl(
     let resp = client.request(PROTO_QOT_GET_KL, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_kl::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_kl::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -43,6 +46,68 @@ pub async fn get_kl(
 /// Get historical K-line data.
 #[allow(clippy::too_many_arguments)]
 pub async fn get_history_kl(
+    client: &FutuClient,
+    market: impl Into<i32>,
+    code: String,
+    rehab_type: impl Into<i32>,
+    kl_type: impl Into<i32>,
+    begin_time: String,
+    end_time: String,
+    max_count: Option<i32>,
+) -> Result<crate::generated::qot_get_history_kl::Response, QuoteError> {
+    let market = market.into();
+    let rehab_type = rehab_type.into();
+    let kl_type = kl_type.into();
+    get_history_kl_page(client, market, code, rehab_type, kl_type, begin_time, end_time, max_count, None).await
+}
+
+/// Every K-line between `begin_time` and `end_time`, requesting pages of
+/// `max_count` K-lines and following `next_req_key` until OpenD returns no
+/// key. With `max_pages`, stops after that many pages even if more remain.
+/// Unlike `download_history_kl`, a failed page fails the call.
+#[allow(clippy::too_many_arguments)]
+pub async fn get_history_kl_all(
+    client: &FutuClient,
+    market: impl Into<i32>,
+    code: String,
+    rehab_type: impl Into<i32>,
+    kl_type: impl Into<i32>,
+    begin_time: String,
+    end_time: String,
+    max_count: Option<i32>,
+    max_pages: Option<usize>,
+) -> Result<Vec<KLine>, QuoteError> {
+    let market = market.into();
+    let rehab_type = rehab_type.into();
+    let kl_type = kl_type.into();
+    let mut kl_list = Vec::new();
+    let mut next_req_key = None;
+    let mut pages = 0;
+    loop {
+        let response = get_history_kl_page(
+            client, market, code.clone(), rehab_type, kl_type,
+            begin_time.clone(), end_time.clone(), max_count, next_req_key.take(),
+        ).await?;
+        pages += 1;
+        let Some(s2c) = response.s2c else {
+            break;
+        };
+        kl_list.extend(s2c.kl_list);
+        next_req_key = s2c.next_req_key.filter(|key| !key.is_empty());
+        if next_req_key.is_none() {
+            break;
+        }
+        if max_pages.is_some_and(|max| pages >= max) {
+            tracing::warn!("History KL of {}.{} stopped after {} pages, more remain", market, code, pages);
+            break;
+        }
+    }
+    Ok(kl_list)
+}
+
+/// One page of historical K-lines, continuing from `next_req_key`.
+#[allow(clippy::too_many_arguments)]
+async fn get_history_kl_page(
     client: &FutuClient,
     market: i32,
     code: String,
@@ -51,6 +116,7 @@ pub async fn get_history_kl(
     begin_time: String,
     end_time: String,
     max_count: Option<i32>,
+    next_req_key: Option<Vec<u8>>,
 ) -> Result<crate::generated::qot_get_history_kl::Response, QuoteError> {
     let security = crate::generated::qot_common::Security { market, code };
     let c2s = crate::generated::qot_get_history_kl::C2s {
@@ -60,6 +126,7 @@ pub async fn get_history_kl(
         begin_time,
         end_time,
         max_ack_kl_num: max_count,
+        next_req_key,
         ..Default::default()
     };
     let request = crate::generated::qot_get_history_kl::Request { c2s };
@@ -68,19 +135,130 @@ pub async fn get_history_kl(
     let resp = client.request(PROTO_QOT_GET_HISTORY_KL, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_history_kl::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_history_kl::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
 }
 
+/// How `download_history_kl` pages through a range.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KlDownloadOptions {
+    /// K-lines per request; None lets OpenD choose.
+    pub page_size: Option<i32>,
+    /// Stop after this many pages even if more remain.
+    pub max_pages: usize,
+    /// Check the series with `validate_kl` once downloaded.
+    pub validate: bool,
+    /// Continue an earlier download from its `resume_key` instead of
+    /// starting at `begin_time`.
+    pub resume_key: Option<Vec<u8>>,
+}
+
+impl Default for KlDownloadOptions {
+    fn default() -> Self {
+        Self { page_size: None, max_pages: 1000, validate: false, resume_key: None }
+    }
+}
+
+/// K-lines of a range with how they were fetched.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KlDownload {
+    pub kl_list: Vec<KLine>,
+    pub pages: usize,
+    /// False if `max_pages` was reached or a page failed before the last page.
+    pub is_complete: bool,
+    /// Error of the page that stopped the download; the K-lines of the
+    /// pages before it are kept.
+    pub error: Option<String>,
+    /// Pagination key of the first page not downloaded, for
+    /// `KlDownloadOptions::resume_key`. None once complete.
+    pub resume_key: Option<Vec<u8>>,
+    /// Integrity report, when validation was requested.
+    pub report: Option<KlReport>,
+}
+
+/// Download every K-line between `begin_time` and `end_time`, following
+/// the pagination key, and optionally validate the series against the
+/// market's trading calendar. If the calendar cannot be fetched, gaps are
+/// counted against weekdays instead. Only a failure of the first request
+/// is an error; a later failed page ends the download with what was
+/// fetched, the error and a key to resume from.
+#[allow(clippy::too_many_arguments)]
+pub async fn download_history_kl(
+    client: &FutuClient,
+    market: impl Into<i32>,
+    code: String,
+    rehab_type: impl Into<i32>,
+    kl_type: impl Into<i32>,
+    begin_time: String,
+    end_time: String,
+    options: &KlDownloadOptions,
+) -> Result<KlDownload, QuoteError> {
+    let market = market.into();
+    let rehab_type = rehab_type.into();
+    let kl_type = kl_type.into();
+    let mut download = KlDownload::default();
+    let mut next_req_key = options.resume_key.clone();
+    loop {
+        let result = get_history_kl_page(
+            client, market, code.clone(), rehab_type, kl_type,
+            begin_time.clone(), end_time.clone(), options.page_size, next_req_key.clone(),
+        ).await;
+        let response = match result {
+            Ok(response) => response,
+            Err(e) if download.pages == 0 => return Err(e),
+            Err(e) => {
+                tracing::warn!("History KL of {}.{} stopped after {} pages: {}", market, code, download.pages, e);
+                download.error = Some(e.to_string());
+                download.resume_key = next_req_key;
+                break;
+            }
+        };
+        download.pages += 1;
+        let Some(s2c) = response.s2c else {
+            download.is_complete = true;
+            break;
+        };
+        download.kl_list.extend(s2c.kl_list);
+        next_req_key = s2c.next_req_key.filter(|key| !key.is_empty());
+        if next_req_key.is_none() {
+            download.is_complete = true;
+            break;
+        }
+        if download.pages >= options.max_pages {
+            tracing::warn!("History KL of {}.{} stopped after {} pages", market, code, download.pages);
+            download.resume_key = next_req_key;
+            break;
+        }
+    }
+
+    if options.validate {
+        let mut calendar = KlCalendar::for_market(market);
+        if let Some(trade_date_market) = trade_date_market(market) {
+            let range = (date_part(&begin_time), date_part(&end_time));
+            let security = Some((market, code.clone()));
+            match super::snapshot::request_trade_date(client, trade_date_market, range.0, range.1, security).await {
+                Ok(resp) => {
+                    let dates = resp.s2c.map(|s| s.trade_date_list).unwrap_or_default();
+                    calendar = calendar.with_trade_dates(&dates);
+                }
+                Err(e) => tracing::warn!("Trade dates unavailable, validating {}.{} against weekdays: {}", market, code, e),
+            }
+        }
+        download.report = Some(validate_kl(&download.kl_list, kl_type, &calendar));
+    }
+    Ok(download)
+}
+
+fn date_part(time: &str) -> String {
+    time.split_whitespace().next().unwrap_or_default().to_string()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -91,6 +269,15 @@ mod tests {
         assert_eq!(PROTO_QOT_GET_HISTORY_KL, 3103);
     }
 
+    #[test]
+    fn test_trade_date_range() {
+        assert_eq!(trade_date_market(1), Some(1));
+        assert_eq!(trade_date_market(22), Some(3));
+        assert_eq!(trade_date_market(41), None);
+        assert_eq!(date_part("2024-01-05 09:30:00"), "2024-01-05");
+        assert_eq!(date_part("2024-01-05"), "2024-01-05");
+    }
+
     #[test]
     fn test_kl_request_encode_decode() {
         let security = crate::generated::qot_common::Security {
@@ -210,4 +397,38 @@ mod tests {
         assert_eq!(decoded.ret_msg, Some("not subscribed".to_string()));
         assert!(decoded.s2c.is_none());
     }
+
+    #[tokio::test]
+    async fn test_history_kl_all_follows_next_req_key() {
+        use crate::client::mock::MockOpenD;
+        use crate::generated::qot_common::Security;
+        use crate::generated::qot_get_history_kl::{Response, S2c};
+        use prost::Message;
+
+        let mock = MockOpenD::start().await.unwrap();
+        let config = crate::config::FutuConfig { port: mock.port(), enable_encryption: false, ..Default::default() };
+        let mut client = FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+
+        let page = |next_req_key: Option<Vec<u8>>| Response {
+            ret_type: 0,
+            s2c: Some(S2c {
+                security: Security { market: 1, code: "00700".to_string() },
+                kl_list: vec![KLine { time: "2024-01-02 00:00:00".to_string(), ..Default::default() }; 2],
+                next_req_key,
+            }),
+            ..Default::default()
+        };
+        let all = |max_pages| get_history_kl_all(&client, 1, "00700".to_string(), 1, 2, "2024-01-01".to_string(), "2024-12-31".to_string(), Some(2), max_pages);
+
+        // The mock repeats the same page, so a key is never exhausted
+        mock.respond(PROTO_QOT_GET_HISTORY_KL, page(Some(vec![1])).encode_to_vec());
+        assert_eq!(all(Some(3)).await.unwrap().len(), 6);
+        mock.respond(PROTO_QOT_GET_HISTORY_KL, page(Some(Vec::new())).encode_to_vec());
+        assert_eq!(all(None).await.unwrap().len(), 2);
+        let failed = Response { ret_type: -1, ret_msg: Some("no such stock".to_string()), ..Default::default() };
+        mock.respond(PROTO_QOT_GET_HISTORY_KL, failed.encode_to_vec());
+        assert!(all(None).await.is_err());
+    }
 }
+
@@ -0,0 +1,465 @@
+//! Consolidated instrument records for onboarding a symbol.
+//!
+//! `InstrumentCache::get` joins Qot_GetStaticInfo (name, type, lot size),
+//! Qot_GetSecuritySnapshot (current tick, margin and short-sell flags) and,
+//! when an account is given, Trd_GetMarginRatio (the broker's ratios and
+//! short pool) into one `Instrument`, and keeps it until the TTL expires.
+//! Currency, tick tables and regular trading sessions come from static
+//! per-market tables, so they are only known for the markets listed here.
+//! Without the `trade` feature no margin ratios are fetched.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use parking_lot::Mutex;
+
+use crate::client::FutuClient;
+use crate::generated::qot_common::SecurityStaticBasic;
+use crate::generated::qot_get_security_snapshot::SnapshotBasicData;
+use crate::generated::trd_get_margin_ratio::MarginRatioInfo;
+#[cfg(feature = "trade")]
+use crate::trade::account::TradeError;
+use super::subscribe::QuoteError;
+
+type SecurityKey = (i32, String);
+/// (trd_env, acc_id, trd_market)
+type Account = (i32, u64, i32);
+/// Security and the acc_id whose margin ratios the record holds.
+type CacheKey = (SecurityKey, Option<u64>);
+
+// SecurityType values
+const SEC_TYPE_BWRT: i32 = 2;
+const SEC_TYPE_EQTY: i32 = 3;
+const SEC_TYPE_TRUST: i32 = 4;
+const SEC_TYPE_WARRANT: i32 = 5;
+const SEC_TYPE_FUTURE: i32 = 10;
+
+/// Tick size for prices from `from` up to the next band.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TickBand {
+    pub from: f64,
+    pub tick: f64,
+}
+
+const fn band(from: f64, tick: f64) -> TickBand {
+    TickBand { from, tick }
+}
+
+/// HKEX spread table (Part A), used by stocks, ETFs and warrants.
+const HK_TICK_TABLE: [TickBand; 11] = [
+    band(0.0, 0.001),
+    band(0.25, 0.005),
+    band(0.5, 0.01),
+    band(10.0, 0.02),
+    band(20.0, 0.05),
+    band(100.0, 0.1),
+    band(200.0, 0.2),
+    band(500.0, 0.5),
+    band(1000.0, 1.0),
+    band(2000.0, 2.0),
+    band(5000.0, 5.0),
+];
+
+/// Reg NMS sub-penny rule.
+const US_TICK_TABLE: [TickBand; 2] = [band(0.0, 0.0001), band(1.0, 0.01)];
+
+/// Regular session in exchange local time ("HH:MM").
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TradingSession {
+    pub open: &'static str,
+    pub close: &'static str,
+}
+
+const fn session(open: &'static str, close: &'static str) -> TradingSession {
+    TradingSession { open, close }
+}
+
+const HK_SESSIONS: [TradingSession; 2] = [session("09:30", "12:00"), session("13:00", "16:00")];
+const US_SESSIONS: [TradingSession; 1] = [session("09:30", "16:00")];
+const CN_SESSIONS: [TradingSession; 2] = [session("09:30", "11:30"), session("13:00", "15:00")];
+const SG_SESSIONS: [TradingSession; 2] = [session("09:00", "12:00"), session("13:00", "17:00")];
+const JP_SESSIONS: [TradingSession; 2] = [session("09:00", "11:30"), session("12:30", "15:30")];
+const AU_SESSIONS: [TradingSession; 1] = [session("10:00", "16:00")];
+const MY_SESSIONS: [TradingSession; 2] = [session("09:00", "12:30"), session("14:30", "17:00")];
+
+/// Static, quote and (optionally) account data of one security.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Instrument {
+    pub security: SecurityKey,
+    pub name: String,
+    /// Qot_Common.SecurityType
+    pub sec_type: i32,
+    /// Qot_Common.ExchType
+    pub exch_type: Option<i32>,
+    /// Shares per lot; the contract multiplier for options and futures.
+    pub lot_size: i32,
+    pub list_time: String,
+    pub delisted: bool,
+    pub currency: Option<&'static str>,
+    /// IANA time zone of `trading_sessions`.
+    pub timezone: Option<&'static str>,
+    /// Regular sessions of the market; empty for futures and unknown markets.
+    pub trading_sessions: Vec<TradingSession>,
+    /// Tick at the current price, from the snapshot.
+    pub price_spread: Option<f64>,
+    /// Tick bands ordered by price; a single band of `price_spread` when the
+    /// market's table is not known.
+    pub tick_table: Vec<TickBand>,
+    pub is_suspended: Option<bool>,
+    pub enable_margin: Option<bool>,
+    pub long_margin_initial_ratio: Option<f64>,
+    pub short_margin_initial_ratio: Option<f64>,
+    pub enable_short_sell: Option<bool>,
+    pub short_sell_rate: Option<f64>,
+    pub short_available_volume: Option<i64>,
+    /// Account-specific ratios; None unless an account was given.
+    pub margin: Option<MarginRatioInfo>,
+}
+
+impl Instrument {
+    /// Join the per-protocol records of one security.
+    pub fn join(
+        info: &SecurityStaticBasic,
+        snapshot: Option<&SnapshotBasicData>,
+        margin: Option<MarginRatioInfo>,
+    ) -> Self {
+        let market = info.security.market;
+        let price_spread = snapshot.map(|s| s.price_spread).filter(|s| *s > 0.0);
+        let (timezone, sessions) = market_sessions(market);
+        Self {
+            security: (market, info.security.code.clone()),
+            name: info.name.clone(),
+            sec_type: info.sec_type,
+            exch_type: info.exch_type,
+            lot_size: info.lot_size,
+            list_time: info.list_time.clone(),
+            delisted: info.delisting.unwrap_or(false),
+            currency: market_currency(market),
+            timezone,
+            trading_sessions: if info.sec_type == SEC_TYPE_FUTURE { Vec::new() } else { sessions.to_vec() },
+            price_spread,
+            tick_table: tick_table(market, info.sec_type, price_spread),
+            is_suspended: snapshot.map(|s| s.is_suspend),
+            enable_margin: snapshot.and_then(|s| s.enable_margin),
+            long_margin_initial_ratio: snapshot.and_then(|s| s.long_margin_initial_ratio),
+            short_margin_initial_ratio: snapshot.and_then(|s| s.short_margin_initial_ratio),
+            enable_short_sell: snapshot.and_then(|s| s.enable_short_sell),
+            short_sell_rate: snapshot.and_then(|s| s.short_sell_rate),
+            short_available_volume: snapshot.and_then(|s| s.short_available_volume),
+            margin,
+        }
+    }
+
+    /// Tick size at `price`, if the tick table is known.
+    pub fn tick_size(&self, price: f64) -> Option<f64> {
+        self.tick_table.iter().rev().find(|b| price >= b.from).map(|b| b.tick)
+    }
+
+    /// `qty` rounded down to a whole number of lots. Unchanged when the lot
+    /// size is unknown.
+    pub fn round_qty(&self, qty: f64) -> f64 {
+        if self.lot_size <= 0 {
+            return qty;
+        }
+        let lot = self.lot_size as f64;
+        (qty / lot + ROUNDING_EPSILON).floor() * lot
+    }
+
+    /// `price` rounded onto the tick table, or None if the table is unknown.
+    /// Buy and buy-back orders (TrdSide 1 and 4) round down and sell and
+    /// short-sell orders (2 and 3) round up, so rounding never makes an
+    /// order more aggressive; other sides round to the nearest tick.
+    pub fn round_price(&self, price: f64, trd_side: i32) -> Option<f64> {
+        let i = self.tick_table.iter().rposition(|b| price >= b.from)?;
+        let TickBand { from, tick } = self.tick_table[i];
+        let steps = (price - from) / tick;
+        let steps = match trd_side {
+            1 | 4 => (steps + ROUNDING_EPSILON).floor(),
+            2 | 3 => (steps - ROUNDING_EPSILON).ceil(),
+            _ => steps.round(),
+        };
+        let mut rounded = round_to_tick(from + steps * tick, tick);
+        // Rounding up may pass the band's end, which is itself a valid price
+        if let Some(next) = self.tick_table.get(i + 1) {
+            rounded = rounded.min(next.from);
+        }
+        Some(rounded)
+    }
+
+    /// Whether the security can be sold short: the account's permission when
+    /// margin ratios were fetched, otherwise the market-wide snapshot flag.
+    pub fn shortable(&self) -> Option<bool> {
+        self.margin.as_ref().and_then(|m| m.is_short_permit).or(self.enable_short_sell)
+    }
+}
+
+/// Currency of a QotMarket.
+pub fn market_currency(market: i32) -> Option<&'static str> {
+    match market {
+        1 | 2 => Some("HKD"),
+        11 => Some("USD"),
+        21 | 22 => Some("CNY"),
+        31 => Some("SGD"),
+        41 => Some("JPY"),
+        51 => Some("AUD"),
+        61 => Some("MYR"),
+        _ => None,
+    }
+}
+
+/// Time zone and regular sessions of a QotMarket.
+pub(crate) fn market_sessions(market: i32) -> (Option<&'static str>, &'static [TradingSession]) {
+    match market {
+        1 | 2 => (Some("Asia/Hong_Kong"), &HK_SESSIONS),
+        11 => (Some("America/New_York"), &US_SESSIONS),
+        21 | 22 => (Some("Asia/Shanghai"), &CN_SESSIONS),
+        31 => (Some("Asia/Singapore"), &SG_SESSIONS),
+        41 => (Some("Asia/Tokyo"), &JP_SESSIONS),
+        51 => (Some("Australia/Sydney"), &AU_SESSIONS),
+        61 => (Some("Asia/Kuala_Lumpur"), &MY_SESSIONS),
+        _ => (None, &[]),
+    }
+}
+
+/// Tolerance for quantities and prices that are a float error off a lot or tick.
+const ROUNDING_EPSILON: f64 = 1e-9;
+
+/// Strip float noise by rounding to the decimals of `tick`.
+fn round_to_tick(price: f64, tick: f64) -> f64 {
+    let decimals = (-tick.log10()).ceil().max(0.0) as i32;
+    let scale = 10f64.powi(decimals);
+    (price * scale).round() / scale
+}
+
+fn tick_table(market: i32, sec_type: i32, price_spread: Option<f64>) -> Vec<TickBand> {
+    match (market, sec_type) {
+        (1, SEC_TYPE_BWRT | SEC_TYPE_EQTY | SEC_TYPE_TRUST | SEC_TYPE_WARRANT) => HK_TICK_TABLE.to_vec(),
+        (11, SEC_TYPE_EQTY | SEC_TYPE_TRUST) => US_TICK_TABLE.to_vec(),
+        (21 | 22, SEC_TYPE_EQTY) => vec![band(0.0, 0.01)],
+        (21 | 22, SEC_TYPE_TRUST) => vec![band(0.0, 0.001)],
+        _ => price_spread.map(|tick| vec![band(0.0, tick)]).unwrap_or_default(),
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum InstrumentError {
+    #[error(transparent)]
+    Quote(#[from] QuoteError),
+    #[cfg(feature = "trade")]
+    #[error(transparent)]
+    Trade(#[from] TradeError),
+    #[error("no static info for security {0}.{1}")]
+    NotFound(i32, String),
+}
+
+/// Instrument records keyed by security and account, fetched on demand.
+pub struct InstrumentCache {
+    entries: Mutex<HashMap<CacheKey, (Instant, Instrument)>>,
+    /// Age after which a record is fetched again.
+    ttl: Duration,
+}
+
+impl Default for InstrumentCache {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(3600))
+    }
+}
+
+impl InstrumentCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self { entries: Mutex::new(HashMap::new()), ttl }
+    }
+
+    /// The instrument record of `security`, with the margin ratios of
+    /// `account` if given. Cached records younger than the TTL are returned
+    /// without a request unless `refresh` is set.
+    pub async fn get(
+        &self,
+        client: &FutuClient,
+        security: SecurityKey,
+        account: Option<Account>,
+        refresh: bool,
+    ) -> Result<Instrument, InstrumentError> {
+        let key = (security, account.map(|a| a.1));
+        if !refresh {
+            if let Some((fetched_at, instrument)) = self.entries.lock().get(&key) {
+                if client.clock().now().saturating_duration_since(*fetched_at) < self.ttl {
+                    return Ok(instrument.clone());
+                }
+            }
+        }
+        let instrument = fetch(client, &key.0, account).await?;
+        self.entries.lock().insert(key, (client.clock().now(), instrument.clone()));
+        Ok(instrument)
+    }
+
+    /// `qty` of `security` rounded down to whole lots; see `Instrument::round_qty`.
+    pub async fn round_qty(&self, client: &FutuClient, security: SecurityKey, qty: f64) -> Result<f64, InstrumentError> {
+        Ok(self.get(client, security, None, false).await?.round_qty(qty))
+    }
+
+    /// `price` of `security` rounded onto its tick table for an order of
+    /// `trd_side`; see `Instrument::round_price`. None if the table is unknown.
+    pub async fn round_price(
+        &self,
+        client: &FutuClient,
+        security: SecurityKey,
+        price: f64,
+        trd_side: i32,
+    ) -> Result<Option<f64>, InstrumentError> {
+        Ok(self.get(client, security, None, false).await?.round_price(price, trd_side))
+    }
+
+    /// Drop the cached records of `security`, for every account.
+    pub fn invalidate(&self, security: &SecurityKey) {
+        self.entries.lock().retain(|(sec, _), _| sec != security);
+    }
+
+    /// Securities with a cached record, sorted.
+    pub fn securities(&self) -> Vec<SecurityKey> {
+        let mut securities: Vec<SecurityKey> = self.entries.lock().keys().map(|(sec, _)| sec.clone()).collect();
+        securities.sort();
+        securities.dedup();
+        securities
+    }
+
+    /// Apply `f` to the cached records of `security`, for every account,
+    /// without changing their age.
+    pub fn update(&self, security: &SecurityKey, mut f: impl FnMut(&mut Instrument)) {
+        for ((sec, _), (_, instrument)) in self.entries.lock().iter_mut() {
+            if sec == security {
+                f(instrument);
+            }
+        }
+    }
+}
+
+async fn fetch(
+    client: &FutuClient,
+    security: &SecurityKey,
+    account: Option<Account>,
+) -> Result<Instrument, InstrumentError> {
+    let securities = vec![security.clone()];
+    #[cfg(feature = "trade")]
+    let margin = async {
+        match account {
+            Some((trd_env, acc_id, trd_market)) => {
+                let resp = crate::trade::query::get_margin_ratio(client, trd_env, acc_id, trd_market, securities.clone()).await?;
+                Ok::<_, TradeError>(resp.s2c.and_then(|s| s.margin_ratio_info_list.into_iter().next()))
+            }
+            None => Ok(None),
+        }
+    };
+    #[cfg(not(feature = "trade"))]
+    let margin = async {
+        let _ = account;
+        Ok::<Option<MarginRatioInfo>, InstrumentError>(None)
+    };
+    let (info, snapshot, margin) = tokio::join!(
+        super::snapshot::get_static_info(client, securities.clone()),
+        super::snapshot::get_security_snapshot(client, securities.clone()),
+        margin,
+    );
+
+    let info = info?
+        .s2c
+        .and_then(|s| s.static_info_list.into_iter().next())
+        .ok_or_else(|| InstrumentError::NotFound(security.0, security.1.clone()))?;
+    let snapshot = snapshot?.s2c.and_then(|s| s.snapshot_list.into_iter().next());
+    Ok(Instrument::join(&info.basic, snapshot.as_ref().map(|s| &s.basic), margin?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generated::qot_common::Security;
+
+    fn static_info(market: i32, code: &str, sec_type: i32) -> SecurityStaticBasic {
+        SecurityStaticBasic {
+            security: Security { market, code: code.to_string() },
+            lot_size: 100,
+            sec_type,
+            name: "Test".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn snapshot(price_spread: f64) -> SnapshotBasicData {
+        SnapshotBasicData {
+            price_spread,
+            enable_short_sell: Some(true),
+            enable_margin: Some(true),
+            long_margin_initial_ratio: Some(0.5),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_join_hk_equity() {
+        let instrument = Instrument::join(&static_info(1, "00700", SEC_TYPE_EQTY), Some(&snapshot(0.2)), None);
+        assert_eq!(instrument.currency, Some("HKD"));
+        assert_eq!(instrument.timezone, Some("Asia/Hong_Kong"));
+        assert_eq!(instrument.trading_sessions.len(), 2);
+        assert_eq!(instrument.price_spread, Some(0.2));
+        assert_eq!(instrument.long_margin_initial_ratio, Some(0.5));
+        // HKEX spread table agrees with the snapshot tick at 300 HKD
+        assert_eq!(instrument.tick_size(300.0), Some(0.2));
+        assert_eq!(instrument.tick_size(0.3), Some(0.005));
+        assert_eq!(instrument.tick_size(15.0), Some(0.02));
+    }
+
+    #[test]
+    fn test_tick_tables() {
+        let us = Instrument::join(&static_info(11, "AAPL", SEC_TYPE_EQTY), None, None);
+        assert_eq!(us.tick_size(0.5), Some(0.0001));
+        assert_eq!(us.tick_size(150.0), Some(0.01));
+        assert_eq!(us.is_suspended, None);
+
+        // Unknown table: the snapshot tick, or nothing without a snapshot
+        let future = Instrument::join(&static_info(1, "HSImain", SEC_TYPE_FUTURE), Some(&snapshot(1.0)), None);
+        assert_eq!(future.tick_table, vec![band(0.0, 1.0)]);
+        assert!(future.trading_sessions.is_empty());
+        let unknown = Instrument::join(&static_info(99, "X", SEC_TYPE_EQTY), None, None);
+        assert_eq!(unknown.tick_size(10.0), None);
+        assert_eq!(unknown.currency, None);
+    }
+
+    #[test]
+    fn test_round_qty() {
+        let hk = Instrument::join(&static_info(1, "00700", SEC_TYPE_EQTY), None, None);
+        assert_eq!(hk.round_qty(250.0), 200.0);
+        assert_eq!(hk.round_qty(300.0), 300.0);
+        assert_eq!(hk.round_qty(99.0), 0.0);
+        let mut unknown = hk.clone();
+        unknown.lot_size = 0;
+        assert_eq!(unknown.round_qty(250.0), 250.0);
+    }
+
+    #[test]
+    fn test_round_price_by_side() {
+        let hk = Instrument::join(&static_info(1, "00700", SEC_TYPE_EQTY), None, None);
+        assert_eq!(hk.round_price(300.13, 1), Some(300.0));
+        assert_eq!(hk.round_price(300.13, 2), Some(300.2));
+        assert_eq!(hk.round_price(300.13, 0), Some(300.2));
+        assert_eq!(hk.round_price(300.2, 2), Some(300.2));
+        // Float noise does not move a price already on a tick
+        assert_eq!(hk.round_price(0.1 + 0.2, 1), Some(0.3));
+        // Rounding up stops at the next band
+        assert_eq!(hk.round_price(9.995, 3), Some(10.0));
+        assert_eq!(hk.round_price(10.01, 1), Some(10.0));
+
+        let us = Instrument::join(&static_info(11, "AAPL", SEC_TYPE_EQTY), None, None);
+        assert_eq!(us.round_price(0.12345, 4), Some(0.1234));
+        assert_eq!(us.round_price(150.005, 2), Some(150.01));
+        let unknown = Instrument::join(&static_info(99, "X", SEC_TYPE_EQTY), None, None);
+        assert_eq!(unknown.round_price(10.0, 1), None);
+    }
+
+    #[test]
+    fn test_shortable_prefers_account_permission() {
+        let info = static_info(11, "TSLA", SEC_TYPE_EQTY);
+        assert_eq!(Instrument::join(&info, Some(&snapshot(0.01)), None).shortable(), Some(true));
+        let margin = MarginRatioInfo { is_short_permit: Some(false), ..Default::default() };
+        assert_eq!(Instrument::join(&info, Some(&snapshot(0.01)), Some(margin)).shortable(), Some(false));
+        assert_eq!(Instrument::join(&info, None, None).shortable(), None);
+    }
+}
@@ -0,0 +1,203 @@
+//! Periodic IPO list polling that emits events for changes.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+
+use crate::client::FutuClient;
+use crate::generated::qot_get_ipo_list::IpoData;
+
+/// Seconds in a listing day, measured from `list_timestamp`.
+const LISTING_DAY_SECS: f64 = 86_400.0;
+
+/// Kind of change detected between two IPO list polls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IpoEventKind {
+    /// An IPO appeared that was not in the previous list.
+    Announced,
+    /// Price range or final price of a known IPO changed.
+    PricingUpdated,
+    /// The IPO lists today. Emitted once per IPO.
+    ListingDay,
+}
+
+impl IpoEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IpoEventKind::Announced => "announced",
+            IpoEventKind::PricingUpdated => "pricing_updated",
+            IpoEventKind::ListingDay => "listing_day",
+        }
+    }
+}
+
+/// A detected IPO change, carrying the latest IPO data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IpoEvent {
+    pub kind: IpoEventKind,
+    pub ipo: IpoData,
+}
+
+/// Pricing fields compared between polls: (price_min, price_max, final price).
+/// CN IPOs have a single issue price, reported as both min and max.
+pub fn ipo_pricing(ipo: &IpoData) -> (f64, f64, f64) {
+    if let Some(ref hk) = ipo.hk_ex_data {
+        (hk.ipo_price_min, hk.ipo_price_max, hk.list_price)
+    } else if let Some(ref us) = ipo.us_ex_data {
+        (us.ipo_price_min, us.ipo_price_max, 0.0)
+    } else if let Some(ref cn) = ipo.cn_ex_data {
+        (cn.ipo_price, cn.ipo_price, cn.ipo_price)
+    } else {
+        (0.0, 0.0, 0.0)
+    }
+}
+
+/// Diffs successive IPO lists per market.
+///
+/// The first list seen for a market is a baseline and produces no
+/// `Announced` events, so a restart does not replay the whole calendar.
+#[derive(Debug, Default)]
+pub struct IpoTracker {
+    known: HashMap<(i32, String), IpoData>,
+    seeded_markets: HashSet<i32>,
+    reminded: HashSet<(i32, String)>,
+}
+
+impl IpoTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compare `ipo_list` for `market` with the previous one. `now` is a Unix timestamp.
+    pub fn diff(&mut self, market: i32, ipo_list: &[IpoData], now: f64) -> Vec<IpoEvent> {
+        let baseline = self.seeded_markets.insert(market);
+        let mut events = Vec::new();
+
+        for ipo in ipo_list {
+            let key = (ipo.basic.security.market, ipo.basic.security.code.clone());
+            match self.known.get(&key) {
+                None if !baseline => events.push(IpoEvent { kind: IpoEventKind::Announced, ipo: ipo.clone() }),
+                Some(prev) if ipo_pricing(prev) != ipo_pricing(ipo) => {
+                    events.push(IpoEvent { kind: IpoEventKind::PricingUpdated, ipo: ipo.clone() });
+                }
+                _ => {}
+            }
+            let listing_today = ipo
+                .basic
+                .list_timestamp
+                .is_some_and(|ts| ts <= now && now < ts + LISTING_DAY_SECS);
+            if listing_today && self.reminded.insert(key.clone()) {
+                events.push(IpoEvent { kind: IpoEventKind::ListingDay, ipo: ipo.clone() });
+            }
+            self.known.insert(key, ipo.clone());
+        }
+        events
+    }
+}
+
+/// Start watching the IPO lists of `markets`, polling every `interval`.
+/// The task stops when the receiver is dropped or the handle is aborted.
+pub fn watch_ipo(
+    client: Arc<FutuClient>,
+    markets: Vec<i32>,
+    interval: Duration,
+) -> (tokio::task::JoinHandle<()>, mpsc::UnboundedReceiver<IpoEvent>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let handle = tokio::spawn(run_ipo_watcher(client, markets, interval, tx));
+    (handle, rx)
+}
+
+/// Polling loop behind `watch_ipo`, for callers that spawn it themselves.
+/// Returns when `tx` is closed.
+pub async fn run_ipo_watcher(
+    client: Arc<FutuClient>,
+    markets: Vec<i32>,
+    interval: Duration,
+    tx: mpsc::UnboundedSender<IpoEvent>,
+) {
+    let mut tracker = IpoTracker::new();
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        ticker.tick().await;
+        for &market in &markets {
+            match super::snapshot::get_ipo_list(&client, market).await {
+                Ok(resp) => {
+                    let ipo_list = resp.s2c.map(|s| s.ipo_list).unwrap_or_default();
+                    let now = SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap_or_default()
+                        .as_secs_f64();
+                    for event in tracker.diff(market, &ipo_list, now) {
+                        if tx.send(event).is_err() {
+                            tracing::debug!("IPO watcher stopped");
+                            return;
+                        }
+                    }
+                }
+                Err(e) => tracing::warn!("IPO list poll failed for market {}: {}", market, e),
+            }
+        }
+        if tx.is_closed() {
+            break;
+        }
+    }
+    tracing::debug!("IPO watcher stopped");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generated::qot_common::Security;
+    use crate::generated::qot_get_ipo_list::{BasicIpoData, HkIpoExData};
+
+    fn ipo(code: &str, price_max: f64, list_timestamp: Option<f64>) -> IpoData {
+        IpoData {
+            basic: BasicIpoData {
+                security: Security { market: 1, code: code.to_string() },
+                name: code.to_string(),
+                list_time: None,
+                list_timestamp,
+            },
+            hk_ex_data: Some(HkIpoExData {
+                ipo_price_min: 10.0,
+                ipo_price_max: price_max,
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn kinds(events: &[IpoEvent]) -> Vec<(IpoEventKind, String)> {
+        events.iter().map(|e| (e.kind, e.ipo.basic.security.code.clone())).collect()
+    }
+
+    #[test]
+    fn test_baseline_then_announced() {
+        let mut tracker = IpoTracker::new();
+        assert!(tracker.diff(1, &[ipo("02001", 12.0, None)], 0.0).is_empty());
+        let events = tracker.diff(1, &[ipo("02001", 12.0, None), ipo("02002", 5.0, None)], 0.0);
+        assert_eq!(kinds(&events), vec![(IpoEventKind::Announced, "02002".to_string())]);
+    }
+
+    #[test]
+    fn test_pricing_updated() {
+        let mut tracker = IpoTracker::new();
+        tracker.diff(1, &[ipo("02001", 12.0, None)], 0.0);
+        assert!(tracker.diff(1, &[ipo("02001", 12.0, None)], 0.0).is_empty());
+        let events = tracker.diff(1, &[ipo("02001", 11.5, None)], 0.0);
+        assert_eq!(kinds(&events), vec![(IpoEventKind::PricingUpdated, "02001".to_string())]);
+    }
+
+    #[test]
+    fn test_listing_day_reminded_once() {
+        let mut tracker = IpoTracker::new();
+        let list_ts = 1_700_000_000.0;
+        assert!(tracker.diff(1, &[ipo("02001", 12.0, Some(list_ts))], list_ts - 1.0).is_empty());
+        let events = tracker.diff(1, &[ipo("02001", 12.0, Some(list_ts))], list_ts + 3600.0);
+        assert_eq!(kinds(&events), vec![(IpoEventKind::ListingDay, "02001".to_string())]);
+        assert!(tracker.diff(1, &[ipo("02001", 12.0, Some(list_ts))], list_ts + 7200.0).is_empty());
+    }
+}
@@ -0,0 +1,378 @@
+//! Integrity checks for downloaded K-line series.
+//!
+//! `validate_kl` walks a series in the order it was returned and reports
+//! duplicate and out-of-order times, bars whose prices contradict each other,
+//! and gaps between consecutive bars. Gaps are counted against a `KlCalendar`:
+//! minute bars are expected in every slot of the day's sessions, labelled by
+//! their end time as OpenD returns them, and daily bars on every trading
+//! day. Half days hold only the sessions `sessions::day_sessions` gives them. Bars missing before the first or after the last bar of the
+//! series are not reported, and neither are gaps in weekly or longer series.
+
+
+use crate::generated::qot_common::KLine;
+use crate::generated::qot_request_trade_date::TradeDate;
+use super::sessions::{day_sessions, SessionCalendar, TradeDateType};
+use crate::dates::days_from_civil;
+
+const KL_TYPE_DAY: i32 = 2;
+
+/// Minutes per bar of the intraday KLTypes.
+fn period_minutes(kl_type: i32) -> Option<i64> {
+    match kl_type {
+        1 => Some(1),
+        6 => Some(5),
+        7 => Some(15),
+        8 => Some(30),
+        9 => Some(60),
+        _ => None,
+    }
+}
+
+/// Trading days and sessions used to count missing bars.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KlCalendar {
+    calendar: SessionCalendar,
+}
+
+impl KlCalendar {
+    /// Weekday calendar with the regular sessions of a QotMarket.
+    pub fn for_market(market: i32) -> Self {
+        Self { calendar: SessionCalendar::for_market(market) }
+    }
+
+    /// Use the trading days of a Qot_RequestTradeDate response instead of
+    /// assuming weekdays.
+    pub fn with_trade_dates(self, dates: &[TradeDate]) -> Self {
+        Self { calendar: self.calendar.with_trade_dates(dates) }
+    }
+
+    /// Whether trading days come from the exchange rather than weekdays.
+    pub fn has_trade_dates(&self) -> bool {
+        self.calendar.has_trade_dates()
+    }
+
+    fn is_trading_day(&self, day: i64) -> bool {
+        self.calendar.trade_date_type_on(day).is_some()
+    }
+
+    /// (open, close) in minutes after midnight of the sessions bars on `day`
+    /// are slotted into. A day with bars that the calendar does not trade on
+    /// is slotted like a whole day.
+    fn sessions(&self, day: i64) -> Vec<(i64, i64)> {
+        let date_type = self.calendar.trade_date_type_on(day).unwrap_or(TradeDateType::Whole);
+        day_sessions(self.calendar.market(), date_type)
+            .iter()
+            .filter_map(|s| Some((parse_hhmm(s.open)?, parse_hhmm(s.close)?)))
+            .collect()
+    }
+
+    /// Bar slots of `day`; none if it is not a trading day.
+    fn slots_on(&self, day: i64, period: i64) -> i64 {
+        if !self.is_trading_day(day) {
+            return 0;
+        }
+        self.sessions(day).iter().map(|(open, close)| (close - open + period - 1) / period).sum()
+    }
+
+    /// Slot of a bar ending at `minute` of `day`, counting from 1 at the
+    /// first bar of the day. Times outside the sessions are clamped to the
+    /// nearest slot.
+    fn slot(&self, day: i64, minute: i64, period: i64) -> i64 {
+        let mut offset = 0;
+        for (open, close) in self.sessions(day) {
+            let slots = (close - open + period - 1) / period;
+            if minute <= close {
+                let into = (minute - open).max(0);
+                return offset + (into + period - 1) / period;
+            }
+            offset += slots;
+        }
+        offset
+    }
+}
+
+/// Something wrong with a K-line series.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KlIssue {
+    /// Bars missing between two consecutive bars.
+    Gap { after: String, before: String, missing: u64 },
+    /// A bar with the same time as the bar before it.
+    Duplicate { time: String },
+    /// A bar earlier than the bar before it.
+    OutOfOrder { time: String },
+    /// A bar whose prices or volume contradict each other.
+    Inconsistent { time: String, reason: &'static str },
+    /// A bar whose time cannot be parsed.
+    BadTime { time: String },
+}
+
+impl KlIssue {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            KlIssue::Gap { .. } => "gap",
+            KlIssue::Duplicate { .. } => "duplicate",
+            KlIssue::OutOfOrder { .. } => "out_of_order",
+            KlIssue::Inconsistent { .. } => "inconsistent",
+            KlIssue::BadTime { .. } => "bad_time",
+        }
+    }
+}
+
+/// Result of `validate_kl`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KlReport {
+    /// Bars checked.
+    pub bars: usize,
+    /// Bars missing across all gaps.
+    pub missing_bars: u64,
+    /// Whether gaps were counted against exchange trading days.
+    pub exchange_calendar: bool,
+    /// Issues in series order.
+    pub issues: Vec<KlIssue>,
+}
+
+impl KlReport {
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Number of issues of a `KlIssue::kind`.
+    pub fn count(&self, kind: &str) -> usize {
+        self.issues.iter().filter(|i| i.kind() == kind).count()
+    }
+}
+
+/// Check a K-line series of `kl_type` against `calendar`.
+pub fn validate_kl(kl_list: &[KLine], kl_type: i32, calendar: &KlCalendar) -> KlReport {
+    let mut report = KlReport {
+        bars: kl_list.len(),
+        exchange_calendar: calendar.has_trade_dates(),
+        ..Default::default()
+    };
+    let mut prev: Option<(&KLine, i64, i64)> = None;
+
+    for kl in kl_list {
+        if let Some(reason) = ohlc_problem(kl) {
+            report.issues.push(KlIssue::Inconsistent { time: kl.time.clone(), reason });
+        }
+        let Some((day, secs)) = parse_kl_time(&kl.time) else {
+            report.issues.push(KlIssue::BadTime { time: kl.time.clone() });
+            continue;
+        };
+
+        if let Some((prev_kl, prev_day, prev_secs)) = prev {
+            match (day, secs).cmp(&(prev_day, prev_secs)) {
+                std::cmp::Ordering::Equal => {
+                    report.issues.push(KlIssue::Duplicate { time: kl.time.clone() });
+                    continue;
+                }
+                std::cmp::Ordering::Less => {
+                    report.issues.push(KlIssue::OutOfOrder { time: kl.time.clone() });
+                    continue;
+                }
+                std::cmp::Ordering::Greater => {}
+            }
+            let missing = missing_between(calendar, kl_type, (prev_day, prev_secs), (day, secs));
+            if missing > 0 {
+                report.missing_bars += missing;
+                report.issues.push(KlIssue::Gap {
+                    after: prev_kl.time.clone(),
+                    before: kl.time.clone(),
+                    missing,
+                });
+            }
+        }
+        prev = Some((kl, day, secs));
+    }
+    report
+}
+
+/// Bars expected strictly between two bars, given as (day, seconds of day).
+fn missing_between(calendar: &KlCalendar, kl_type: i32, from: (i64, i64), to: (i64, i64)) -> u64 {
+    if kl_type == KL_TYPE_DAY {
+        return (from.0 + 1..to.0).filter(|day| calendar.is_trading_day(*day)).count() as u64;
+    }
+    let Some(period) = period_minutes(kl_type) else {
+        return 0;
+    };
+    let from_slot = calendar.slot(from.0, from.1 / 60, period);
+    let to_slot = calendar.slot(to.0, to.1 / 60, period);
+    if from.0 == to.0 {
+        return (to_slot - from_slot - 1).max(0) as u64;
+    }
+
+    let tail = calendar.slots_on(from.0, period) - from_slot;
+    let head = if calendar.is_trading_day(to.0) { to_slot - 1 } else { 0 };
+    let between: i64 = (from.0 + 1..to.0).map(|day| calendar.slots_on(day, period)).sum();
+    (tail.max(0) + head.max(0) + between) as u64
+}
+
+/// First contradiction in a bar's prices or volume. Blank bars carry only
+/// their time and are not checked.
+fn ohlc_problem(kl: &KLine) -> Option<&'static str> {
+    if kl.is_blank {
+        return None;
+    }
+    let prices = [kl.open_price, kl.high_price, kl.low_price, kl.close_price];
+    if prices.iter().flatten().any(|p| !p.is_finite() || *p <= 0.0) {
+        return Some("non-positive price");
+    }
+    if kl.volume.is_some_and(|v| v < 0) {
+        return Some("negative volume");
+    }
+    let (Some(high), Some(low)) = (kl.high_price, kl.low_price) else {
+        return None;
+    };
+    let tolerance = high.abs() * 1e-9;
+    if high + tolerance < low {
+        return Some("high below low");
+    }
+    let outside = |p: Option<f64>| p.is_some_and(|p| p > high + tolerance || p < low - tolerance);
+    if outside(kl.open_price) {
+        return Some("open outside high-low range");
+    }
+    if outside(kl.close_price) {
+        return Some("close outside high-low range");
+    }
+    None
+}
+
+/// "YYYY-MM-DD[ HH:MM[:SS]]" as (days since 1970-01-01, seconds of day).
+fn parse_kl_time(s: &str) -> Option<(i64, i64)> {
+    let s = s.trim();
+    let (date, time) = match s.split_once(' ') {
+        Some((date, time)) => (date, Some(time)),
+        None => (s, None),
+    };
+    let mut parts = date.split('-').map(|p| p.parse::<i64>().ok());
+    let (y, m, d) = (parts.next()??, parts.next()??, parts.next()??);
+    if parts.next().is_some() || !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return None;
+    }
+    let secs = match time {
+        Some(time) => {
+            let mut parts = time.split('.').next()?.split(':').map(|p| p.parse::<i64>().ok());
+            let (h, min) = (parts.next()??, parts.next()??);
+            let sec = parts.next().flatten().unwrap_or(0);
+            if h > 23 || min > 59 || sec > 59 {
+                return None;
+            }
+            h * 3600 + min * 60 + sec
+        }
+        None => 0,
+    };
+    Some((days_from_civil(y, m, d), secs))
+}
+
+fn parse_hhmm(s: &str) -> Option<i64> {
+    let (h, m) = s.split_once(':')?;
+    Some(h.parse::<i64>().ok()? * 60 + m.parse::<i64>().ok()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bar(time: &str, open: f64, high: f64, low: f64, close: f64) -> KLine {
+        KLine {
+            time: time.to_string(),
+            open_price: Some(open),
+            high_price: Some(high),
+            low_price: Some(low),
+            close_price: Some(close),
+            volume: Some(100),
+            ..Default::default()
+        }
+    }
+
+    fn flat(time: &str) -> KLine {
+        bar(time, 10.0, 10.0, 10.0, 10.0)
+    }
+
+    #[test]
+    fn test_intraday_gaps_across_lunch_and_days() {
+        // HK: 2024-01-05 is a Friday, 2024-01-08 a Monday
+        let calendar = KlCalendar::for_market(1);
+        let bars = vec![
+            flat("2024-01-05 11:59:00"),
+            flat("2024-01-05 12:00:00"),
+            flat("2024-01-05 13:01:00"),
+            flat("2024-01-05 13:04:00"),
+            flat("2024-01-05 16:00:00"),
+            flat("2024-01-08 09:31:00"),
+        ];
+        let report = validate_kl(&bars, 1, &calendar);
+        assert!(!report.exchange_calendar);
+        // 13:02 and 13:03, then 13:05 to 15:59
+        assert_eq!(report.count("gap"), 2);
+        assert_eq!(report.missing_bars, 2 + 175);
+
+        // The weekend is skipped, the missing Monday open is not
+        let bars = vec![flat("2024-01-05 16:00:00"), flat("2024-01-08 09:35:00")];
+        assert_eq!(validate_kl(&bars, 1, &calendar).missing_bars, 4);
+    }
+
+    #[test]
+    fn test_gaps_use_trade_dates() {
+        let calendar = KlCalendar::for_market(1).with_trade_dates(&[
+            TradeDate { time: "2024-02-08".to_string(), timestamp: None, trade_date_type: Some(0) },
+            TradeDate { time: "2024-02-09".to_string(), timestamp: None, trade_date_type: Some(1) },
+            TradeDate { time: "2024-02-14".to_string(), timestamp: None, trade_date_type: Some(0) },
+            TradeDate { time: "2024-02-15".to_string(), timestamp: None, trade_date_type: Some(0) },
+            TradeDate { time: "2024-02-16".to_string(), timestamp: None, trade_date_type: Some(0) },
+        ]);
+        // Lunar new year holiday between the 9th and the 14th
+        let daily = vec![flat("2024-02-08 00:00:00"), flat("2024-02-09 00:00:00"), flat("2024-02-14 00:00:00"), flat("2024-02-16 00:00:00")];
+        let report = validate_kl(&daily, 2, &calendar);
+        assert!(report.exchange_calendar);
+        assert_eq!(report.issues, vec![KlIssue::Gap {
+            after: "2024-02-14 00:00:00".to_string(),
+            before: "2024-02-16 00:00:00".to_string(),
+            missing: 1,
+        }]);
+
+        // A half day ending at 12:00 leaves no gap
+        let minutes = vec![flat("2024-02-09 12:00:00"), flat("2024-02-14 09:31:00")];
+        assert!(validate_kl(&minutes, 1, &calendar).is_clean());
+        // Its missing bars are counted from its morning session only
+        let minutes = vec![flat("2024-02-08 16:00:00"), flat("2024-02-09 11:00:00"), flat("2024-02-14 09:32:00")];
+        let report = validate_kl(&minutes, 1, &calendar);
+        assert_eq!(report.missing_bars, 89 + 60 + 1);
+    }
+
+    #[test]
+    fn test_hourly_slots() {
+        // HK hourly bars: 10:30 11:30 12:00 14:00 15:00 16:00
+        let calendar = KlCalendar::for_market(1);
+        let bars: Vec<KLine> = ["10:30", "11:30", "12:00", "14:00", "16:00"]
+            .iter()
+            .map(|t| flat(&format!("2024-01-05 {}:00", t)))
+            .collect();
+        let report = validate_kl(&bars, 9, &calendar);
+        assert_eq!(report.missing_bars, 1);
+        // 2024-01-05 is a Friday
+        assert_eq!(calendar.slots_on(days_from_civil(2024, 1, 5), 60), 6);
+    }
+
+    #[test]
+    fn test_duplicates_order_and_ohlc() {
+        let calendar = KlCalendar::for_market(11);
+        let bars = vec![
+            flat("2024-01-05 09:31:00"),
+            flat("2024-01-05 09:31:00"),
+            bar("2024-01-05 09:32:00", 10.0, 9.0, 9.5, 9.5),
+            bar("2024-01-05 09:33:00", 11.0, 10.5, 9.5, 10.0),
+            flat("2024-01-05 09:30:00"),
+            flat("not a time"),
+            KLine { time: "2024-01-05 09:34:00".to_string(), is_blank: true, ..Default::default() },
+        ];
+        let report = validate_kl(&bars, 1, &calendar);
+        let kinds: Vec<&str> = report.issues.iter().map(|i| i.kind()).collect();
+        assert_eq!(kinds, vec!["duplicate", "inconsistent", "inconsistent", "out_of_order", "bad_time"]);
+        assert_eq!(report.issues[1], KlIssue::Inconsistent {
+            time: "2024-01-05 09:32:00".to_string(),
+            reason: "high below low",
+        });
+        assert_eq!(report.missing_bars, 0);
+    }
+}
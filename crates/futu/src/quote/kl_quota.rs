@@ -0,0 +1,454 @@
+//! History K-line quota and quota-aware batch downloads.
+//!
+//! OpenD charges history K-line requests against a per-account quota of
+//! symbols: the first download of a symbol uses one unit, and downloading
+//! it again within 30 days is free. `download_history_kl_batch` asks OpenD
+//! for the quota (Qot_RequestHistoryKLQuota), splits a batch into symbols
+//! already paid for and symbols that would use quota, refuses batches that
+//! need more than remains unless told otherwise, downloads the paid-for
+//! symbols first and waits out frequency limits. A `KlQuotaLedger` keeps the
+//! symbols that used quota in a file, so batches can be planned across
+//! sessions even when OpenD leaves out the detail list.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use prost::Message;
+
+use crate::client::FutuClient;
+use crate::dates::days_from_civil;
+use super::history::{download_history_kl, KlDownload, KlDownloadOptions};
+use super::subscribe::QuoteError;
+
+const PROTO_QOT_REQUEST_HISTORY_KL_QUOTA: u32 = 3104;
+
+/// Days a symbol stays paid for after its first download.
+pub const QUOTA_WINDOW_DAYS: i64 = 30;
+const QUOTA_WINDOW_SECS: i64 = QUOTA_WINDOW_DAYS * 24 * 3600;
+
+/// Longest wait between attempts of a throttled symbol.
+const MAX_THROTTLE_WAIT: Duration = Duration::from_secs(60);
+
+/// History K-line quota of the account.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KlQuota {
+    pub used: u32,
+    pub remaining: u32,
+    /// Symbols that used quota in the last 30 days, with the Unix time of
+    /// their first download. Empty unless details were requested.
+    pub consumed: Vec<((i32, String), i64)>,
+}
+
+/// Get the history K-line quota, with the symbols that used it if
+/// `get_detail`.
+pub async fn request_history_kl_quota(
+    client: &FutuClient,
+    get_detail: bool,
+) -> Result<crate::generated::qot_request_history_kl_quota::Response, QuoteError> {
+    let c2s = crate::generated::qot_request_history_kl_quota::C2s { b_get_detail: Some(get_detail) };
+    let request = crate::generated::qot_request_history_kl_quota::Request { c2s };
+    let body = request.encode_to_vec();
+
+    let resp = client.request(PROTO_QOT_REQUEST_HISTORY_KL_QUOTA, &body).await
+        .map_err(QuoteError::Connection)?;
+
+    let response = client.decode::<crate::generated::qot_request_history_kl_quota::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
+
+    if response.ret_type != 0 {
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
+    }
+
+    Ok(response)
+}
+
+/// The history K-line quota with the symbols that used it.
+pub async fn get_kl_quota(client: &FutuClient) -> Result<KlQuota, QuoteError> {
+    let response = request_history_kl_quota(client, true).await?;
+    let Some(s2c) = response.s2c else {
+        return Err(QuoteError::Decode("no s2c in history KL quota response".to_string()));
+    };
+    let consumed = s2c
+        .detail_list
+        .into_iter()
+        .map(|item| {
+            let time = item.request_time_stamp.unwrap_or_else(|| parse_time(&item.request_time).unwrap_or(0));
+            ((item.security.market, item.security.code), time)
+        })
+        .collect();
+    Ok(KlQuota {
+        used: s2c.used_quota.max(0) as u32,
+        remaining: s2c.remain_quota.max(0) as u32,
+        consumed,
+    })
+}
+
+/// Symbols that used history K-line quota, by the Unix time of their first
+/// download, optionally saved to a file of `market,code,time` lines.
+#[derive(Debug, Clone, Default)]
+pub struct KlQuotaLedger {
+    path: Option<PathBuf>,
+    entries: BTreeMap<(i32, String), i64>,
+}
+
+impl KlQuotaLedger {
+    /// An empty ledger that is not saved.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the ledger saved at `path`, or start an empty one there if the
+    /// file does not exist.
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let mut ledger = Self { path: None, entries: BTreeMap::new() };
+        match std::fs::read_to_string(&path) {
+            Ok(text) => ledger.entries = parse_ledger(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        ledger.path = Some(path);
+        Ok(ledger)
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Record that `security` used quota at `time`. An earlier time within
+    /// the window is kept, as the window starts at the first download.
+    pub fn record(&mut self, security: (i32, String), time: i64) {
+        let entry = self.entries.entry(security).or_insert(time);
+        if time - *entry >= QUOTA_WINDOW_SECS || time < *entry {
+            *entry = time;
+        }
+    }
+
+    /// Record the symbols OpenD reports in `quota`.
+    pub fn merge(&mut self, quota: &KlQuota) {
+        for (security, time) in &quota.consumed {
+            self.record(security.clone(), *time);
+        }
+    }
+
+    /// Whether `security` is paid for at `now`.
+    pub fn is_paid(&self, security: &(i32, String), now: i64) -> bool {
+        self.entries.get(security).is_some_and(|&time| now - time < QUOTA_WINDOW_SECS)
+    }
+
+    /// Symbols paid for at `now`, sorted.
+    pub fn paid(&self, now: i64) -> Vec<(i32, String)> {
+        self.entries.iter().filter(|(_, &time)| now - time < QUOTA_WINDOW_SECS).map(|(s, _)| s.clone()).collect()
+    }
+
+    /// Forget symbols whose window ended before `now`.
+    pub fn prune(&mut self, now: i64) {
+        self.entries.retain(|_, time| now - *time < QUOTA_WINDOW_SECS);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Write the ledger to its file; a ledger without a file is not saved.
+    pub fn save(&self) -> io::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let mut text = String::from("# market,code,first download (Unix time)\n");
+        for ((market, code), time) in &self.entries {
+            text.push_str(&format!("{},{},{}\n", market, code, time));
+        }
+        std::fs::write(path, text)
+    }
+}
+
+fn parse_ledger(text: &str) -> Result<BTreeMap<(i32, String), i64>, String> {
+    let mut entries = BTreeMap::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let [market, code, time] = fields[..] else {
+            return Err(format!("line {}: expected market,code,time", i + 1));
+        };
+        let market = market.parse().map_err(|_| format!("line {}: invalid market {:?}", i + 1, market))?;
+        let time = time.parse().map_err(|_| format!("line {}: invalid time {:?}", i + 1, time))?;
+        entries.insert((market, code.to_string()), time);
+    }
+    Ok(entries)
+}
+
+/// Unix time of a `yyyy-MM-dd[ HH:mm:ss]` time, read as UTC; a day off at
+/// most, which is fine for a 30-day window.
+fn parse_time(time: &str) -> Option<i64> {
+    let mut parts = time.split_whitespace();
+    let date: Vec<i64> = parts.next()?.split('-').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+    let [year, month, day] = date[..] else {
+        return None;
+    };
+    let secs = match parts.next() {
+        Some(clock) => {
+            let hms: Vec<i64> = clock.split(':').map(|p| p.parse().ok()).collect::<Option<_>>()?;
+            hms.iter().zip([3600, 60, 1]).map(|(v, unit)| v * unit).sum()
+        }
+        None => 0,
+    };
+    Some(days_from_civil(year, month, day) * 86400 + secs)
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs() as i64)
+}
+
+/// How a batch splits against the quota.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KlBatchPlan {
+    /// Symbols already paid for, downloaded first.
+    pub paid: Vec<(i32, String)>,
+    /// Symbols that would use quota, in batch order.
+    pub new: Vec<(i32, String)>,
+    pub remaining: u32,
+}
+
+impl KlBatchPlan {
+    /// Split `securities` into paid and new symbols. Duplicates are dropped.
+    pub fn new(securities: &[(i32, String)], quota: &KlQuota, ledger: &KlQuotaLedger, now: i64) -> Self {
+        let mut plan = Self { remaining: quota.remaining, ..Self::default() };
+        for security in securities {
+            if plan.paid.contains(security) || plan.new.contains(security) {
+                continue;
+            }
+            let reported = quota.consumed.iter().any(|(s, time)| s == security && now - time < QUOTA_WINDOW_SECS);
+            if reported || ledger.is_paid(security, now) {
+                plan.paid.push(security.clone());
+            } else {
+                plan.new.push(security.clone());
+            }
+        }
+        plan
+    }
+
+    /// Whether the new symbols need more quota than remains.
+    pub fn exceeds_quota(&self) -> bool {
+        self.new.len() > self.remaining as usize
+    }
+
+    /// Symbols in download order: paid ones first.
+    pub fn order(&self) -> impl Iterator<Item = &(i32, String)> {
+        self.paid.iter().chain(&self.new)
+    }
+}
+
+/// How `download_history_kl_batch` downloads a batch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KlBatchOptions {
+    /// Paging and validation of each symbol.
+    pub download: KlDownloadOptions,
+    /// Download a batch that needs more quota than remains instead of
+    /// refusing it; OpenD then rejects the symbols beyond the quota.
+    pub allow_over_quota: bool,
+    /// Attempts per symbol that hit a frequency limit, waiting between
+    /// them with a doubling backoff.
+    pub max_throttle_retries: u32,
+}
+
+impl Default for KlBatchOptions {
+    fn default() -> Self {
+        Self { download: KlDownloadOptions::default(), allow_over_quota: false, max_throttle_retries: 3 }
+    }
+}
+
+/// Downloads of a batch.
+#[derive(Debug, Default)]
+pub struct KlBatchDownload {
+    pub plan: KlBatchPlan,
+    /// Downloads by symbol, in download order.
+    pub downloads: Vec<((i32, String), KlDownload)>,
+    /// Symbols whose first request failed, with the error.
+    pub failed: Vec<((i32, String), QuoteError)>,
+    /// Quota after the batch, if it could be fetched.
+    pub quota_after: Option<KlQuota>,
+}
+
+/// Download history K-lines of `securities` within the history quota.
+/// Symbols already paid for (reported by OpenD or in `ledger`) are
+/// downloaded first. A batch whose new symbols need more quota than
+/// remains is refused with `HistoryQuotaExceeded` unless
+/// `options.allow_over_quota`, which downloads it anyway with a warning.
+/// Throttled symbols are retried after the wait OpenD asks for. New symbols
+/// downloaded are recorded in `ledger`, which is saved afterwards.
+#[allow(clippy::too_many_arguments)]
+pub async fn download_history_kl_batch(
+    client: &FutuClient,
+    securities: &[(i32, String)],
+    rehab_type: impl Into<i32>,
+    kl_type: impl Into<i32>,
+    begin_time: String,
+    end_time: String,
+    ledger: &mut KlQuotaLedger,
+    options: &KlBatchOptions,
+) -> Result<KlBatchDownload, QuoteError> {
+    let rehab_type = rehab_type.into();
+    let kl_type = kl_type.into();
+    let quota = get_kl_quota(client).await?;
+    let now = unix_now();
+    ledger.merge(&quota);
+    ledger.prune(now);
+
+    let plan = KlBatchPlan::new(securities, &quota, ledger, now);
+    if plan.exceeds_quota() {
+        if !options.allow_over_quota {
+            return Err(QuoteError::HistoryQuotaExceeded { needed: plan.new.len() as u32, remaining: plan.remaining });
+        }
+        tracing::warn!(
+            "History KL batch needs quota for {} symbols but {} remain; downloading anyway",
+            plan.new.len(), plan.remaining,
+        );
+    }
+
+    let mut batch = KlBatchDownload { plan: plan.clone(), ..KlBatchDownload::default() };
+    for security in plan.order() {
+        let mut wait = Duration::ZERO;
+        let mut attempt = 0;
+        let result = loop {
+            let result = download_history_kl(
+                client, security.0, security.1.clone(), rehab_type, kl_type,
+                begin_time.clone(), end_time.clone(), &options.download,
+            ).await;
+            match result {
+                Err(QuoteError::Throttled { retry_after, .. }) if attempt < options.max_throttle_retries => {
+                    attempt += 1;
+                    wait = (wait * 2).max(retry_after).min(MAX_THROTTLE_WAIT);
+                    tracing::info!("History KL of {}.{} throttled, retrying in {:?}", security.0, security.1, wait);
+                    tokio::time::sleep(wait).await;
+                }
+                result => break result,
+            }
+        };
+        match result {
+            Ok(download) => {
+                ledger.record(security.clone(), unix_now());
+                batch.downloads.push((security.clone(), download));
+            }
+            Err(e) => {
+                tracing::warn!("History KL of {}.{} failed: {}", security.0, security.1, e);
+                batch.failed.push((security.clone(), e));
+            }
+        }
+    }
+
+    if let Err(e) = ledger.save() {
+        tracing::warn!("Cannot save history KL quota ledger: {}", e);
+    }
+    match get_kl_quota(client).await {
+        Ok(quota) => batch.quota_after = Some(quota),
+        Err(e) => tracing::warn!("History KL quota unavailable after batch: {}", e),
+    }
+    Ok(batch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::mock::MockOpenD;
+    use crate::config::FutuConfig;
+    use crate::generated::qot_common::Security;
+    use crate::generated::qot_request_history_kl_quota::{DetailItem, Response, S2c};
+
+    fn security(code: &str) -> (i32, String) {
+        (1, code.to_string())
+    }
+
+    #[test]
+    fn test_parse_time() {
+        assert_eq!(parse_time("1970-01-02"), Some(86400));
+        assert_eq!(parse_time("2024-03-01 00:00:01"), Some(1709251201));
+        assert_eq!(parse_time("yesterday"), None);
+    }
+
+    #[test]
+    fn test_plan_and_ledger() {
+        let now = 100 * 86400;
+        let quota = KlQuota { used: 1, remaining: 1, consumed: vec![(security("00700"), now - 86400)] };
+        let mut ledger = KlQuotaLedger::new();
+        ledger.record(security("09988"), now - 40 * 86400);
+        ledger.record(security("00005"), now - 2 * 86400);
+
+        let batch = [security("00700"), security("09988"), security("00005"), security("00388"), security("00700")];
+        let plan = KlBatchPlan::new(&batch, &quota, &ledger, now);
+        assert_eq!(plan.paid, vec![security("00700"), security("00005")]);
+        // 09988 used quota more than 30 days ago and has to pay again
+        assert_eq!(plan.new, vec![security("09988"), security("00388")]);
+        assert!(plan.exceeds_quota());
+        assert_eq!(plan.order().next(), Some(&security("00700")));
+
+        ledger.prune(now);
+        assert_eq!(ledger.paid(now), vec![security("00005")]);
+        // A later download within the window keeps the first time
+        ledger.record(security("00005"), now);
+        assert!(!ledger.is_paid(&security("00005"), now - 2 * 86400 + QUOTA_WINDOW_SECS));
+    }
+
+    #[test]
+    fn test_ledger_file() {
+        let path = std::env::temp_dir().join(format!("kl_quota_ledger_{}.csv", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mut ledger = KlQuotaLedger::open(&path).unwrap();
+        assert!(ledger.is_empty());
+        ledger.record(security("00700"), 1_700_000_000);
+        ledger.save().unwrap();
+
+        let reloaded = KlQuotaLedger::open(&path).unwrap();
+        assert!(reloaded.is_paid(&security("00700"), 1_700_000_100));
+        std::fs::write(&path, "1,00700\n").unwrap();
+        assert_eq!(KlQuotaLedger::open(&path).unwrap_err().kind(), io::ErrorKind::InvalidData);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_batch_against_mock() {
+        let mock = MockOpenD::start().await.unwrap();
+        let config = FutuConfig { port: mock.port(), enable_encryption: false, ..FutuConfig::default() };
+        let mut client = FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+
+        let detail = DetailItem {
+            security: Security { market: 1, code: "00700".to_string() },
+            request_time: String::new(),
+            request_time_stamp: Some(unix_now() - 3600),
+            name: None,
+        };
+        let quota = Response {
+            ret_type: 0,
+            s2c: Some(S2c { used_quota: 1, remain_quota: 1, detail_list: vec![detail] }),
+            ..Default::default()
+        };
+        mock.respond(PROTO_QOT_REQUEST_HISTORY_KL_QUOTA, quota.encode_to_vec());
+
+        let mut ledger = KlQuotaLedger::new();
+        let batch = [security("00700"), security("09988"), security("00005")];
+        let options = KlBatchOptions::default();
+        let err = download_history_kl_batch(&client, &batch, 1, 2, "2024-01-02".into(), "2024-01-05".into(), &mut ledger, &options)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, QuoteError::HistoryQuotaExceeded { needed: 2, remaining: 1 }));
+
+        let options = KlBatchOptions { allow_over_quota: true, ..KlBatchOptions::default() };
+        let result = download_history_kl_batch(&client, &batch, 1, 2, "2024-01-02".into(), "2024-01-05".into(), &mut ledger, &options)
+            .await
+            .unwrap();
+        let order: Vec<&str> = result.downloads.iter().map(|(s, _)| s.1.as_str()).collect();
+        assert_eq!(order, vec!["00700", "09988", "00005"]);
+        assert!(ledger.is_paid(&security("09988"), unix_now()));
+        assert_eq!(result.quota_after.unwrap().remaining, 1);
+    }
+}
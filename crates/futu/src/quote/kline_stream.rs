@@ -0,0 +1,344 @@
+//! Candles of one security assembled from K-line pushes.
+//!
+//! Qot_UpdateKL pushes the bar in progress every time it changes, and on
+//! subscription the latest bars OpenD holds, so the same bar arrives many
+//! times. `CandleAssembler` turns these pushes into `Candle`s: each bar is
+//! emitted once, complete, when the first push of a later bar arrives, and
+//! optionally on every update before that. Bars OpenD marks `is_blank` (no
+//! trades in the period) come without prices; they are filled with the
+//! previous close and no volume. Pushes of bars older than the one in
+//! progress are ignored.
+//!
+//! `KlineStream` subscribes a security's K-line type and runs an assembler
+//! over its pushes.
+
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+use crate::client::dispatcher::PushFilter;
+use crate::client::FutuClient;
+use crate::generated::qot_common::KLine;
+use crate::protocol::FutuMessage;
+use super::push_stats::{kl_type_to_sub_type, PROTO_QOT_UPDATE_KL};
+use super::subscribe::{check_sub_results, QuoteError};
+
+/// RehabType OpenD pushes K-lines in unless told otherwise.
+const REHAB_FORWARD: i32 = 1;
+
+/// One bar of a K-line series.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candle {
+    /// (market, code)
+    pub security: (i32, String),
+    pub kl_type: i32,
+    /// Bar time as OpenD formats it, e.g. "2024-01-02 09:31:00".
+    pub time: String,
+    pub timestamp: Option<f64>,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: i64,
+    pub turnover: f64,
+    /// Bar without trades, priced at the previous close.
+    pub is_blank: bool,
+    /// Whether the bar has ended; false for updates of the bar in progress.
+    pub complete: bool,
+}
+
+/// What `KlineStream` emits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KlineStreamOptions {
+    /// Also emit the bar in progress on every push, with `complete` false.
+    pub emit_updates: bool,
+    /// Subscribe the K-line type; turn off when it is subscribed elsewhere.
+    pub subscribe: bool,
+}
+
+impl Default for KlineStreamOptions {
+    fn default() -> Self {
+        Self { emit_updates: false, subscribe: true }
+    }
+}
+
+/// Assembles the K-line pushes of one security and KLType; see the module docs.
+#[derive(Debug)]
+pub struct CandleAssembler {
+    security: (i32, String),
+    kl_type: i32,
+    emit_updates: bool,
+    /// Latest push of the bar in progress.
+    current: Option<KLine>,
+    /// Close of the last complete bar, used to fill blank bars.
+    last_close: Option<f64>,
+}
+
+impl CandleAssembler {
+    pub fn new(security: (i32, String), kl_type: i32, emit_updates: bool) -> Self {
+        Self { security, kl_type, emit_updates, current: None, last_close: None }
+    }
+
+    /// Apply the bars of one push, oldest first.
+    pub fn push(&mut self, kl_list: &[KLine]) -> Vec<Candle> {
+        let mut candles = Vec::new();
+        for kl in kl_list {
+            match &self.current {
+                Some(current) if kl.time < current.time => continue,
+                Some(current) if kl.time > current.time => {
+                    let done = self.current.take().expect("current bar");
+                    if let Some(candle) = self.candle(&done, true) {
+                        self.last_close = Some(candle.close);
+                        candles.push(candle);
+                    }
+                }
+                _ => {}
+            }
+            self.current = Some(kl.clone());
+            if self.emit_updates {
+                candles.extend(self.candle(kl, false));
+            }
+        }
+        candles
+    }
+
+    /// Candle of a pushed bar, or None for a blank bar before any price is known.
+    fn candle(&self, kl: &KLine, complete: bool) -> Option<Candle> {
+        let (open, high, low, close, volume, turnover) = match kl.close_price {
+            Some(close) if !kl.is_blank => (
+                kl.open_price.unwrap_or(close),
+                kl.high_price.unwrap_or(close),
+                kl.low_price.unwrap_or(close),
+                close,
+                kl.volume.unwrap_or_default(),
+                kl.turnover.unwrap_or_default(),
+            ),
+            _ => {
+                let price = self.last_close.or(kl.last_close_price)?;
+                (price, price, price, price, 0, 0.0)
+            }
+        };
+        Some(Candle {
+            security: self.security.clone(),
+            kl_type: self.kl_type,
+            time: kl.time.clone(),
+            timestamp: kl.timestamp,
+            open,
+            high,
+            low,
+            close,
+            volume,
+            turnover,
+            is_blank: kl.is_blank,
+            complete,
+        })
+    }
+}
+
+/// Candles of one security and KLType. Dropping the stream stops it; the
+/// subscription is left in place for other consumers.
+pub struct KlineStream {
+    rx: mpsc::UnboundedReceiver<Candle>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl KlineStream {
+    /// Subscribe `security` to the K-line type (unless turned off in
+    /// `options`) and start assembling its pushes. Fails with
+    /// `SubscriptionRejected` if OpenD rejects the subscription.
+    pub async fn start(
+        client: Arc<FutuClient>,
+        security: (i32, String),
+        kl_type: i32,
+        options: KlineStreamOptions,
+    ) -> Result<Self, QuoteError> {
+        let sub_type = kl_type_to_sub_type(kl_type).ok_or(QuoteError::UnsupportedKlType(kl_type))?;
+        // Registered first so the bars pushed on subscription are not missed
+        let filter = PushFilter::security(security.0, security.1.clone());
+        let pushes = client.subscribe_push_filtered(PROTO_QOT_UPDATE_KL, filter).await;
+        if options.subscribe {
+            check_sub_results(super::subscribe::subscribe(&client, vec![security.clone()], [sub_type], true).await?)?;
+        }
+        let assembler = CandleAssembler::new(security, kl_type, options.emit_updates);
+        let (tx, rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run_kline_stream(client, assembler, pushes, tx));
+        Ok(Self { rx, task })
+    }
+
+    /// The next candle, or None once the stream has stopped.
+    pub async fn recv(&mut self) -> Option<Candle> {
+        self.rx.recv().await
+    }
+}
+
+impl futures::Stream for KlineStream {
+    type Item = Candle;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Candle>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+impl Drop for KlineStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn run_kline_stream(
+    client: Arc<FutuClient>,
+    mut assembler: CandleAssembler,
+    mut pushes: mpsc::UnboundedReceiver<FutuMessage>,
+    tx: mpsc::UnboundedSender<Candle>,
+) {
+    loop {
+        let msg = tokio::select! {
+            msg = pushes.recv() => match msg {
+                Some(msg) => msg,
+                None => break,
+            },
+            _ = tx.closed() => break,
+        };
+        let Some(s2c) = client.decode::<crate::generated::qot_update_kl::Response>(&msg).ok().and_then(|r| r.s2c) else {
+            continue;
+        };
+        // Pushes of other securities are filtered by the dispatcher
+        if s2c.kl_type != assembler.kl_type || s2c.rehab_type != REHAB_FORWARD {
+            continue;
+        }
+        for candle in assembler.push(&s2c.kl_list) {
+            if tx.send(candle).is_err() {
+                return;
+            }
+        }
+    }
+    tracing::debug!("K-line stream of {}.{} stopped", assembler.security.0, assembler.security.1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+    use crate::client::mock::MockOpenD;
+    use crate::config::FutuConfig;
+    use crate::generated::qot_common::Security;
+
+    fn bar(time: &str, close: f64, volume: i64) -> KLine {
+        KLine {
+            time: time.to_string(),
+            open_price: Some(close - 1.0),
+            high_price: Some(close + 1.0),
+            low_price: Some(close - 2.0),
+            close_price: Some(close),
+            volume: Some(volume),
+            ..Default::default()
+        }
+    }
+
+    fn blank(time: &str) -> KLine {
+        KLine { time: time.to_string(), is_blank: true, ..Default::default() }
+    }
+
+    fn assembler(emit_updates: bool) -> CandleAssembler {
+        CandleAssembler::new((1, "00700".to_string()), 1, emit_updates)
+    }
+
+    #[test]
+    fn test_bars_complete_on_next_bar() {
+        let mut a = assembler(false);
+        assert!(a.push(&[bar("2024-01-02 09:31:00", 350.0, 100)]).is_empty());
+        assert!(a.push(&[bar("2024-01-02 09:31:00", 351.0, 300)]).is_empty());
+        let candles = a.push(&[bar("2024-01-02 09:32:00", 352.0, 50)]);
+        assert_eq!(candles.len(), 1);
+        assert_eq!((candles[0].time.as_str(), candles[0].close, candles[0].volume), ("2024-01-02 09:31:00", 351.0, 300));
+        assert!(candles[0].complete);
+        // Stale bars are ignored
+        assert!(a.push(&[bar("2024-01-02 09:31:00", 1.0, 1)]).is_empty());
+    }
+
+    #[test]
+    fn test_blank_bars_filled_with_previous_close() {
+        let mut a = assembler(false);
+        // A blank bar before any price is dropped once complete
+        a.push(&[blank("2024-01-02 09:30:00")]);
+        assert!(a.push(&[bar("2024-01-02 09:31:00", 350.0, 100)]).is_empty());
+        let candles = a.push(&[blank("2024-01-02 09:32:00"), blank("2024-01-02 09:33:00"), bar("2024-01-02 09:34:00", 353.0, 10)]);
+        let closes: Vec<_> = candles.iter().map(|c| (c.time.as_str(), c.open, c.close, c.volume, c.is_blank)).collect();
+        assert_eq!(
+            closes,
+            vec![
+                ("2024-01-02 09:31:00", 349.0, 350.0, 100, false),
+                ("2024-01-02 09:32:00", 350.0, 350.0, 0, true),
+                ("2024-01-02 09:33:00", 350.0, 350.0, 0, true),
+            ]
+        );
+
+        // The previous day's close fills a blank first bar
+        let mut a = assembler(false);
+        a.push(&[KLine { last_close_price: Some(340.0), ..blank("2024-01-02 09:31:00") }]);
+        let candles = a.push(&[bar("2024-01-02 09:32:00", 341.0, 1)]);
+        assert_eq!((candles[0].close, candles[0].is_blank), (340.0, true));
+    }
+
+    #[test]
+    fn test_updates_emitted_incomplete() {
+        let mut a = assembler(true);
+        let candles = a.push(&[bar("2024-01-02 09:31:00", 350.0, 100)]);
+        assert_eq!(candles.len(), 1);
+        assert!(!candles[0].complete);
+        let candles = a.push(&[bar("2024-01-02 09:32:00", 351.0, 5)]);
+        let states: Vec<_> = candles.iter().map(|c| (c.time.as_str(), c.complete)).collect();
+        assert_eq!(states, vec![("2024-01-02 09:31:00", true), ("2024-01-02 09:32:00", false)]);
+    }
+
+    #[tokio::test]
+    async fn test_stream_against_mock() {
+        let mock = MockOpenD::start().await.unwrap();
+        let config = FutuConfig { port: mock.port(), enable_encryption: false, ..FutuConfig::default() };
+        let mut client = FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+        let client = Arc::new(client);
+
+        assert!(matches!(
+            client.kline_stream((1, "00700".to_string()), 99, KlineStreamOptions::default()).await,
+            Err(QuoteError::UnsupportedKlType(99))
+        ));
+        // A rejected subscription fails the stream instead of leaving it silent
+        let rejected = crate::generated::qot_sub::Response {
+            ret_type: -1,
+            ret_msg: Some("unknown stock".to_string()),
+            ..Default::default()
+        };
+        mock.respond(crate::quote::subscribe::PROTO_QOT_SUB, rejected.encode_to_vec());
+        assert!(matches!(
+            client.kline_stream((1, "BAD".to_string()), 1, KlineStreamOptions::default()).await,
+            Err(QuoteError::SubscriptionRejected { total: 1, .. })
+        ));
+        mock.respond(crate::quote::subscribe::PROTO_QOT_SUB, vec![0x08, 0x00]);
+        let mut stream = client.kline_stream((1, "00700".to_string()), 1, KlineStreamOptions::default()).await.unwrap();
+
+        let push = |security: Security, kl_list: Vec<KLine>| {
+            crate::generated::qot_update_kl::Response {
+                ret_type: 0,
+                s2c: Some(crate::generated::qot_update_kl::S2c {
+                    rehab_type: REHAB_FORWARD,
+                    kl_type: 1,
+                    security,
+                    name: None,
+                    kl_list,
+                }),
+                ..Default::default()
+            }
+            .encode_to_vec()
+        };
+        let tencent = Security { market: 1, code: "00700".to_string() };
+        let other = Security { market: 1, code: "09988".to_string() };
+        mock.push(PROTO_QOT_UPDATE_KL, push(tencent.clone(), vec![bar("2024-01-02 09:31:00", 350.0, 100)]));
+        mock.push(PROTO_QOT_UPDATE_KL, push(other, vec![bar("2024-01-02 09:31:00", 80.0, 1), bar("2024-01-02 09:32:00", 81.0, 1)]));
+        mock.push(PROTO_QOT_UPDATE_KL, push(tencent, vec![bar("2024-01-02 09:32:00", 351.0, 5)]));
+
+        let candle = stream.recv().await.unwrap();
+        assert_eq!((candle.security.1.as_str(), candle.close, candle.complete), ("00700", 350.0, true));
+    }
+}
@@ -0,0 +1,286 @@
+//! Daily scan of the instrument universe for code changes, suspensions and
+//! delistings.
+//!
+//! `scan_listings` reads the delisting flag from Qot_GetStaticInfo, the
+//! suspension state from Qot_GetSecuritySnapshot and the code changes of
+//! Qot_GetCodeChange for a set of securities. The watcher scans the
+//! securities of an `InstrumentCache` on every run, diffs the result into
+//! events and applies them to the cache, so order rounding sees a delisted
+//! or suspended instrument without waiting for its record to expire.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+
+use crate::client::FutuClient;
+use crate::dates::civil_from_days;
+use crate::generated::qot_get_code_change::CodeChangeInfo;
+use super::instrument::InstrumentCache;
+use super::subscribe::QuoteError;
+
+type SecurityKey = (i32, String);
+
+/// Securities per Qot_GetStaticInfo, Qot_GetSecuritySnapshot and
+/// Qot_GetCodeChange request.
+const SCAN_CHUNK: usize = super::poller::SNAPSHOT_MAX_SECURITIES;
+
+/// Listing state of one security.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListingState {
+    pub delisted: bool,
+    /// None if the snapshot of the security failed.
+    pub suspended: Option<bool>,
+}
+
+/// Result of one scan.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListingScan {
+    /// States of the securities OpenD returned static info for.
+    pub states: HashMap<SecurityKey, ListingState>,
+    pub code_changes: Vec<CodeChangeInfo>,
+}
+
+/// Scan the listing state and code changes of `securities`. Fails if a
+/// static info or code change request fails; a failed snapshot leaves the
+/// suspension state of its securities unknown.
+pub async fn scan_listings(client: &FutuClient, securities: &[SecurityKey]) -> Result<ListingScan, QuoteError> {
+    let mut scan = ListingScan::default();
+    for chunk in securities.chunks(SCAN_CHUNK) {
+        let info = super::snapshot::get_static_info(client, chunk.to_vec()).await?;
+        for info in info.s2c.map(|s| s.static_info_list).unwrap_or_default() {
+            let key = (info.basic.security.market, info.basic.security.code.clone());
+            let delisted = info.basic.delisting.unwrap_or(false);
+            scan.states.insert(key, ListingState { delisted, suspended: None });
+        }
+        let changes = super::snapshot::get_code_change(client, chunk.to_vec(), Vec::new()).await?;
+        scan.code_changes.extend(changes.s2c.map(|s| s.code_change_list).unwrap_or_default());
+    }
+    let bulk = super::snapshot::get_security_snapshot_bulk(client, securities.to_vec(), SCAN_CHUNK).await;
+    for snapshot in bulk.snapshots {
+        let key = (snapshot.basic.security.market, snapshot.basic.security.code.clone());
+        if let Some(state) = scan.states.get_mut(&key) {
+            state.suspended = Some(snapshot.basic.is_suspend);
+        }
+    }
+    Ok(scan)
+}
+
+/// Kind of listing change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListingEventKind {
+    /// A code change of the security is in effect or upcoming.
+    CodeChanged,
+    /// Trading of the security is suspended.
+    Suspended,
+    /// A suspended security trades again.
+    Resumed,
+    /// The security is delisted. Emitted once per security.
+    Delisted,
+}
+
+impl ListingEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ListingEventKind::CodeChanged => "code_changed",
+            ListingEventKind::Suspended => "suspended",
+            ListingEventKind::Resumed => "resumed",
+            ListingEventKind::Delisted => "delisted",
+        }
+    }
+}
+
+/// A listing change of a security; code changes carry their details.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListingEvent {
+    pub kind: ListingEventKind,
+    pub security: SecurityKey,
+    pub code_change: Option<CodeChangeInfo>,
+}
+
+/// Diffs successive scans.
+///
+/// The first scan reports the securities that are already suspended or
+/// delisted, so a watcher starts with every dead symbol of its universe.
+/// Code changes are reported once, and only while they are in effect or
+/// upcoming: a change that ended, or took effect without an end date
+/// before `today`, is already part of the static info.
+#[derive(Debug, Default)]
+pub struct ListingTracker {
+    states: HashMap<SecurityKey, ListingState>,
+    reported_changes: HashSet<(i32, SecurityKey, SecurityKey, Option<String>)>,
+}
+
+impl ListingTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compare `scan` with the previous one. `today` is `YYYY-MM-DD`.
+    pub fn diff(&mut self, scan: &ListingScan, today: &str) -> Vec<ListingEvent> {
+        let mut events = Vec::new();
+        let mut securities: Vec<&SecurityKey> = scan.states.keys().collect();
+        securities.sort();
+
+        for security in securities {
+            let mut state = scan.states[security];
+            let prev = self.states.get(security).copied();
+            let event = |kind| ListingEvent { kind, security: security.clone(), code_change: None };
+            if state.delisted && !prev.is_some_and(|p| p.delisted) {
+                events.push(event(ListingEventKind::Delisted));
+            }
+            match (prev.and_then(|p| p.suspended), state.suspended) {
+                (Some(false) | None, Some(true)) => events.push(event(ListingEventKind::Suspended)),
+                (Some(true), Some(false)) => events.push(event(ListingEventKind::Resumed)),
+                // An unknown state keeps the last known one
+                (last, None) => state.suspended = last,
+                _ => {}
+            }
+            self.states.insert(security.clone(), state);
+        }
+
+        for change in &scan.code_changes {
+            let last_day = change.end_time.as_deref().or(change.effective_time.as_deref()).unwrap_or_default();
+            if last_day.get(..10).unwrap_or(last_day) < today {
+                continue;
+            }
+            let key = (
+                change.r#type,
+                (change.security.market, change.security.code.clone()),
+                (change.related_security.market, change.related_security.code.clone()),
+                change.effective_time.clone(),
+            );
+            if self.reported_changes.insert(key) {
+                events.push(ListingEvent {
+                    kind: ListingEventKind::CodeChanged,
+                    security: (change.security.market, change.security.code.clone()),
+                    code_change: Some(change.clone()),
+                });
+            }
+        }
+        events
+    }
+}
+
+/// Bring the cached records of `event`'s security up to date. Records of a
+/// security with a code change are dropped, so they are fetched again.
+pub fn apply_event(cache: &InstrumentCache, event: &ListingEvent) {
+    match event.kind {
+        ListingEventKind::CodeChanged => cache.invalidate(&event.security),
+        ListingEventKind::Suspended => cache.update(&event.security, |i| i.is_suspended = Some(true)),
+        ListingEventKind::Resumed => cache.update(&event.security, |i| i.is_suspended = Some(false)),
+        ListingEventKind::Delisted => cache.update(&event.security, |i| i.delisted = true),
+    }
+}
+
+/// Today's date (UTC) as `YYYY-MM-DD`.
+fn today() -> String {
+    let secs = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let (y, m, d) = civil_from_days((secs / 86_400) as i64);
+    format!("{:04}-{:02}-{:02}", y, m, d)
+}
+
+/// Scan the securities of `cache` plus `extra` every `interval` (daily for
+/// a daily job), apply the changes to `cache` and send them. Returns when
+/// `tx` is closed.
+pub async fn run_listing_watcher(
+    client: Arc<FutuClient>,
+    cache: Arc<InstrumentCache>,
+    extra: Vec<SecurityKey>,
+    interval: Duration,
+    tx: mpsc::UnboundedSender<ListingEvent>,
+) {
+    let mut tracker = ListingTracker::new();
+    let clock = Arc::clone(client.clock());
+    let mut next_run = clock.now();
+
+    loop {
+        clock.sleep_until(next_run).await;
+        next_run = clock.now() + interval;
+        let mut securities = cache.securities();
+        for security in &extra {
+            if !securities.contains(security) {
+                securities.push(security.clone());
+            }
+        }
+        if securities.is_empty() {
+            continue;
+        }
+        match scan_listings(&client, &securities).await {
+            Ok(scan) => {
+                for event in tracker.diff(&scan, &today()) {
+                    apply_event(&cache, &event);
+                    if tx.send(event).is_err() {
+                        tracing::debug!("Listing watcher stopped");
+                        return;
+                    }
+                }
+            }
+            // A failed run keeps the previous states, so nothing is reported as resumed
+            Err(e) => tracing::warn!("Listing scan of {} securities failed: {}", securities.len(), e),
+        }
+        if tx.is_closed() {
+            break;
+        }
+    }
+    tracing::debug!("Listing watcher stopped");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generated::qot_common::Security;
+
+    fn scan(states: &[(&str, bool, Option<bool>)], code_changes: Vec<CodeChangeInfo>) -> ListingScan {
+        ListingScan {
+            states: states
+                .iter()
+                .map(|(code, delisted, suspended)| ((1, code.to_string()), ListingState { delisted: *delisted, suspended: *suspended }))
+                .collect(),
+            code_changes,
+        }
+    }
+
+    fn summary(events: &[ListingEvent]) -> Vec<(&'static str, &str)> {
+        events.iter().map(|e| (e.kind.as_str(), e.security.1.as_str())).collect()
+    }
+
+    fn code_change(code: &str, effective: &str, end: Option<&str>) -> CodeChangeInfo {
+        CodeChangeInfo {
+            r#type: 1,
+            security: Security { market: 1, code: code.to_string() },
+            related_security: Security { market: 1, code: format!("{}0", code) },
+            effective_time: Some(effective.to_string()),
+            end_time: end.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_suspension_and_delisting_transitions() {
+        let mut tracker = ListingTracker::new();
+        let first = tracker.diff(&scan(&[("A", false, Some(false)), ("B", false, Some(true)), ("C", true, Some(true))], vec![]), "2024-06-03");
+        assert_eq!(summary(&first), vec![("suspended", "B"), ("delisted", "C"), ("suspended", "C")]);
+
+        let events = tracker.diff(&scan(&[("A", false, Some(true)), ("B", false, Some(false)), ("C", true, Some(true))], vec![]), "2024-06-04");
+        assert_eq!(summary(&events), vec![("suspended", "A"), ("resumed", "B")]);
+
+        // A failed snapshot keeps A suspended rather than resuming it later
+        assert!(tracker.diff(&scan(&[("A", false, None)], vec![]), "2024-06-05").is_empty());
+        assert!(tracker.diff(&scan(&[("A", false, Some(true))], vec![]), "2024-06-06").is_empty());
+    }
+
+    #[test]
+    fn test_code_changes_reported_once_while_current() {
+        let mut tracker = ListingTracker::new();
+        let changes = vec![
+            code_change("A", "2020-01-02", None),
+            code_change("B", "2024-06-10", None),
+            code_change("C", "2024-05-01", Some("2024-06-20 00:00:00")),
+        ];
+        let events = tracker.diff(&scan(&[], changes.clone()), "2024-06-03");
+        assert_eq!(summary(&events), vec![("code_changed", "B"), ("code_changed", "C")]);
+        assert_eq!(events[0].code_change.as_ref().unwrap().related_security.code, "B0");
+        assert!(tracker.diff(&scan(&[], changes), "2024-06-04").is_empty());
+    }
+}
@@ -0,0 +1,347 @@
+//! Market sessions from GetGlobalState, polled on a timer or on demand.
+//!
+//! GetGlobalState reports one QotMarketState per market group: HK, US, the
+//! two A-share exchanges and the futures markets. OpenD has no push for
+//! these, so `run_market_session` asks again every interval and
+//! `MarketSession` compares each answer with the one before. Every change of
+//! a market's state is sent to the subscribers as a `MarketSessionEvent`;
+//! the first answer reports every market with no previous state. The states
+//! fold into a `MarketPhase` (pre-open, open, break, closing auction,
+//! extended hours, closed), which is what order timing usually gates on.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use parking_lot::Mutex;
+use tokio::sync::mpsc;
+
+use crate::client::init::InitError;
+use crate::client::FutuClient;
+use crate::generated::get_global_state::S2c;
+use crate::types::QotMarketState;
+
+/// Market group of a GetGlobalState market state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SessionMarket {
+    Hk,
+    Us,
+    Sh,
+    Sz,
+    HkFuture,
+    UsFuture,
+    SgFuture,
+    JpFuture,
+}
+
+impl SessionMarket {
+    pub const ALL: [SessionMarket; 8] = [
+        SessionMarket::Hk,
+        SessionMarket::Us,
+        SessionMarket::Sh,
+        SessionMarket::Sz,
+        SessionMarket::HkFuture,
+        SessionMarket::UsFuture,
+        SessionMarket::SgFuture,
+        SessionMarket::JpFuture,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionMarket::Hk => "hk",
+            SessionMarket::Us => "us",
+            SessionMarket::Sh => "sh",
+            SessionMarket::Sz => "sz",
+            SessionMarket::HkFuture => "hk_future",
+            SessionMarket::UsFuture => "us_future",
+            SessionMarket::SgFuture => "sg_future",
+            SessionMarket::JpFuture => "jp_future",
+        }
+    }
+
+    pub fn from_str_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|m| m.as_str() == name)
+    }
+
+    /// Market group of a QotMarket, e.g. 21 (SH) to `Sh`.
+    pub fn from_qot_market(market: i32) -> Option<Self> {
+        match market {
+            1 => Some(SessionMarket::Hk),
+            2 => Some(SessionMarket::HkFuture),
+            11 => Some(SessionMarket::Us),
+            21 => Some(SessionMarket::Sh),
+            22 => Some(SessionMarket::Sz),
+            31 => Some(SessionMarket::SgFuture),
+            41 => Some(SessionMarket::JpFuture),
+            _ => None,
+        }
+    }
+
+    /// QotMarketState of the market in `state`; `None` for the futures
+    /// markets an older OpenD does not report.
+    pub fn state_in(&self, state: &S2c) -> Option<i32> {
+        match self {
+            SessionMarket::Hk => Some(state.market_hk),
+            SessionMarket::Us => Some(state.market_us),
+            SessionMarket::Sh => Some(state.market_sh),
+            SessionMarket::Sz => Some(state.market_sz),
+            SessionMarket::HkFuture => Some(state.market_hk_future),
+            SessionMarket::UsFuture => state.market_us_future,
+            SessionMarket::SgFuture => state.market_sg_future,
+            SessionMarket::JpFuture => state.market_jp_future,
+        }
+    }
+}
+
+/// Part of the trading day a QotMarketState falls in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarketPhase {
+    /// Opening auction or waiting for the open.
+    PreOpen,
+    /// Continuous trading, day or night.
+    Open,
+    /// Lunch or futures mid-session break.
+    Break,
+    /// Closing auction or trade-at-last.
+    Closing,
+    /// Pre-market, after-hours or overnight trading.
+    Extended,
+    Closed,
+}
+
+impl MarketPhase {
+    pub fn from_state(state: i32) -> Self {
+        match QotMarketState::try_from(state) {
+            Ok(
+                QotMarketState::Auction
+                | QotMarketState::WaitingOpen
+                | QotMarketState::PreMarketEnd
+                | QotMarketState::FutureDayWaitForOpen
+                | QotMarketState::FutureNightWait,
+            ) => MarketPhase::PreOpen,
+            Ok(
+                QotMarketState::Morning
+                | QotMarketState::Afternoon
+                | QotMarketState::NightOpen
+                | QotMarketState::FutureDayOpen
+                | QotMarketState::FutureAfternoon
+                | QotMarketState::FutureOpen
+                | QotMarketState::FutureBreakOver
+                | QotMarketState::Night,
+            ) => MarketPhase::Open,
+            Ok(QotMarketState::Rest | QotMarketState::FutureDayBreak | QotMarketState::FutureBreak) => {
+                MarketPhase::Break
+            }
+            Ok(
+                QotMarketState::HkCas
+                | QotMarketState::CloseAuction
+                | QotMarketState::TradeAtLast
+                | QotMarketState::TradeAuction,
+            ) => MarketPhase::Closing,
+            Ok(
+                QotMarketState::PreMarketBegin
+                | QotMarketState::AfterHoursBegin
+                | QotMarketState::StibAfterHoursWait
+                | QotMarketState::StibAfterHoursBegin
+                | QotMarketState::OvernightBegin
+                | QotMarketState::Overnight,
+            ) => MarketPhase::Extended,
+            _ => MarketPhase::Closed,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MarketPhase::PreOpen => "pre_open",
+            MarketPhase::Open => "open",
+            MarketPhase::Break => "break",
+            MarketPhase::Closing => "closing",
+            MarketPhase::Extended => "extended",
+            MarketPhase::Closed => "closed",
+        }
+    }
+}
+
+/// Change of a market's state between two GetGlobalState answers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarketSessionEvent {
+    pub market: SessionMarket,
+    /// QotMarketState before; `None` for the first answer.
+    pub prev_state: Option<i32>,
+    /// QotMarketState now.
+    pub state: i32,
+    /// Unix timestamp (seconds) of the answer.
+    pub timestamp: f64,
+}
+
+impl MarketSessionEvent {
+    pub fn prev_phase(&self) -> Option<MarketPhase> {
+        self.prev_state.map(MarketPhase::from_state)
+    }
+
+    pub fn phase(&self) -> MarketPhase {
+        MarketPhase::from_state(self.state)
+    }
+}
+
+#[derive(Debug, Default)]
+struct SessionState {
+    states: BTreeMap<SessionMarket, i32>,
+    /// Unix timestamp of the latest answer.
+    updated_at: Option<f64>,
+    subscribers: Vec<mpsc::UnboundedSender<MarketSessionEvent>>,
+}
+
+/// Latest market states and the subscribers to their changes.
+#[derive(Debug, Default)]
+pub struct MarketSession {
+    state: Mutex<SessionState>,
+}
+
+impl MarketSession {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Receive state changes from now on.
+    pub fn subscribe(&self) -> mpsc::UnboundedReceiver<MarketSessionEvent> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.state.lock().subscribers.push(tx);
+        rx
+    }
+
+    /// Latest QotMarketState of `market`, if reported.
+    pub fn state(&self, market: SessionMarket) -> Option<i32> {
+        self.state.lock().states.get(&market).copied()
+    }
+
+    /// Latest phase of `market`, if reported.
+    pub fn phase(&self, market: SessionMarket) -> Option<MarketPhase> {
+        self.state(market).map(MarketPhase::from_state)
+    }
+
+    /// Whether `market` is in continuous trading.
+    pub fn is_open(&self, market: SessionMarket) -> bool {
+        self.phase(market) == Some(MarketPhase::Open)
+    }
+
+    /// Latest QotMarketState of every reported market.
+    pub fn states(&self) -> BTreeMap<SessionMarket, i32> {
+        self.state.lock().states.clone()
+    }
+
+    /// Unix timestamp of the latest answer, if any.
+    pub fn updated_at(&self) -> Option<f64> {
+        self.state.lock().updated_at
+    }
+
+    /// Record a GetGlobalState answer received at `now`, send the changes
+    /// to the subscribers and return them.
+    pub fn apply(&self, s2c: &S2c, now: f64) -> Vec<MarketSessionEvent> {
+        let mut state = self.state.lock();
+        let mut events = Vec::new();
+        for market in SessionMarket::ALL {
+            let Some(current) = market.state_in(s2c) else {
+                continue;
+            };
+            let prev_state = state.states.insert(market, current);
+            if prev_state != Some(current) {
+                events.push(MarketSessionEvent { market, prev_state, state: current, timestamp: now });
+            }
+        }
+        state.updated_at = Some(now);
+        state.subscribers.retain(|tx| events.iter().all(|e| tx.send(e.clone()).is_ok()));
+        events
+    }
+}
+
+/// Ask OpenD for the market states once and apply them to `session`.
+/// Returns the changes.
+pub async fn refresh(client: &FutuClient, session: &MarketSession) -> Result<Vec<MarketSessionEvent>, InitError> {
+    let user_id = client.init_response().map(|r| r.login_user_id).unwrap_or(0);
+    let resp = crate::client::init::get_global_state(client, user_id).await?;
+    Ok(match resp.s2c {
+        Some(s2c) => session.apply(&s2c, client.clock().unix_time()),
+        None => Vec::new(),
+    })
+}
+
+/// Refresh `session` every `interval` until the task is aborted. Failed
+/// requests are logged and retried at the next interval.
+pub async fn run_market_session(client: Arc<FutuClient>, session: Arc<MarketSession>, interval: Duration) {
+    let clock = Arc::clone(client.clock());
+    let mut next_poll = clock.now();
+    loop {
+        clock.sleep_until(next_poll).await;
+        next_poll = clock.now() + interval;
+        match refresh(&client, &session).await {
+            Ok(events) => {
+                for e in events {
+                    tracing::info!(
+                        "Market {} state {:?} -> {} ({})",
+                        e.market.as_str(), e.prev_state, e.state, e.phase().as_str(),
+                    );
+                }
+            }
+            Err(e) => tracing::warn!("Market session refresh failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn s2c(hk: i32, us: i32) -> S2c {
+        S2c { market_hk: hk, market_us: us, market_sg_future: Some(23), ..Default::default() }
+    }
+
+    #[test]
+    fn test_phase_from_state() {
+        assert_eq!(MarketPhase::from_state(1), MarketPhase::PreOpen);
+        assert_eq!(MarketPhase::from_state(3), MarketPhase::Open);
+        assert_eq!(MarketPhase::from_state(4), MarketPhase::Break);
+        assert_eq!(MarketPhase::from_state(19), MarketPhase::Closing);
+        assert_eq!(MarketPhase::from_state(8), MarketPhase::Extended);
+        assert_eq!(MarketPhase::from_state(6), MarketPhase::Closed);
+        assert_eq!(MarketPhase::from_state(999), MarketPhase::Closed);
+    }
+
+    #[test]
+    fn test_first_answer_reports_every_market() {
+        let session = MarketSession::new();
+        let events = session.apply(&s2c(2, 6), 1.0);
+        // hk, us, sh, sz, hk_future and sg_future; us/jp futures unreported
+        assert_eq!(events.len(), 6);
+        assert!(events.iter().all(|e| e.prev_state.is_none()));
+        assert_eq!(session.state(SessionMarket::SgFuture), Some(23));
+        assert_eq!(session.state(SessionMarket::JpFuture), None);
+        assert!(session.is_open(SessionMarket::SgFuture));
+    }
+
+    #[test]
+    fn test_transitions_sent_to_subscribers() {
+        let session = MarketSession::new();
+        session.apply(&s2c(2, 6), 1.0);
+        let mut rx = session.subscribe();
+
+        assert!(session.apply(&s2c(2, 6), 2.0).is_empty());
+        let events = session.apply(&s2c(3, 6), 3.0);
+        assert_eq!(events.len(), 1);
+
+        let event = rx.try_recv().unwrap();
+        assert_eq!(event.market, SessionMarket::Hk);
+        assert_eq!(event.prev_phase(), Some(MarketPhase::PreOpen));
+        assert_eq!(event.phase(), MarketPhase::Open);
+        assert_eq!(event.timestamp, 3.0);
+        assert!(rx.try_recv().is_err());
+        assert_eq!(session.updated_at(), Some(3.0));
+    }
+
+    #[test]
+    fn test_closed_subscriber_dropped() {
+        let session = MarketSession::new();
+        drop(session.subscribe());
+        session.apply(&s2c(2, 6), 1.0);
+        assert!(session.state.lock().subscribers.is_empty());
+    }
+}
@@ -1,5 +1,27 @@
 pub mod subscribe;
 pub mod snapshot;
 pub mod history;
+pub mod kl_quota;
+pub mod capital_flow;
+pub mod poller;
+pub mod profiles;
+pub mod suspend;
+pub mod ipo_watch;
+pub mod push_stats;
+pub mod names;
+pub mod symbols;
+pub mod bars;
+pub mod instrument;
+pub mod kl_check;
+pub mod kline_stream;
+pub mod sessions;
+pub mod sub_manager;
+pub mod warrant_watch;
+pub mod listing_watch;
+pub mod brokers;
+pub mod reminder;
+pub mod orderbook;
+pub mod universe;
+pub mod market_session;
 
 pub use subscribe::QuoteError;
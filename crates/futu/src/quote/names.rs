@@ -0,0 +1,149 @@
+//! Local security name translation.
+//!
+//! OpenD returns names in the language configured on the gateway; neither
+//! InitConnect nor the quote protos carry a locale. This table maps
+//! (market, code) to names per language so clients can present names in a
+//! preferred language regardless of the gateway setting.
+
+use std::collections::HashMap;
+
+/// Security names per language, with the currently preferred language.
+#[derive(Debug, Clone, Default)]
+pub struct NameTable {
+    language: Option<String>,
+    names: HashMap<String, HashMap<(i32, String), String>>,
+}
+
+impl NameTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Preferred language; `None` keeps the names returned by OpenD.
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+
+    pub fn set_language(&mut self, language: Option<String>) {
+        self.language = language;
+    }
+
+    /// Add or replace names for `language`. Entries are (market, code, name).
+    pub fn add_names(&mut self, language: &str, entries: impl IntoIterator<Item = (i32, String, String)>) {
+        let table = self.names.entry(language.to_string()).or_default();
+        for (market, code, name) in entries {
+            table.insert((market, code), name);
+        }
+    }
+
+    /// Number of names known for `language`.
+    pub fn len(&self, language: &str) -> usize {
+        self.names.get(language).map_or(0, |t| t.len())
+    }
+
+    /// Name of the security in the preferred language, falling back to `name`.
+    pub fn localize<'a>(&'a self, market: i32, code: &str, name: &'a str) -> &'a str {
+        self.lookup(market, code).unwrap_or(name)
+    }
+
+    /// `localize` for optional names; a translation fills in a missing name.
+    pub fn localize_opt<'a>(&'a self, market: i32, code: &str, name: Option<&'a str>) -> Option<&'a str> {
+        self.lookup(market, code).or(name)
+    }
+
+    /// `localize` for trade items, whose market is a TrdSecMarket.
+    pub fn localize_trd<'a>(&'a self, sec_market: Option<i32>, code: &str, name: &'a str) -> &'a str {
+        match sec_market.and_then(trd_sec_market_to_qot_market) {
+            Some(market) => self.localize(market, code, name),
+            None => name,
+        }
+    }
+
+    fn lookup(&self, market: i32, code: &str) -> Option<&str> {
+        let table = self.names.get(self.language.as_ref()?)?;
+        table.get(&(market, code.to_string())).map(|n| n.as_str())
+    }
+}
+
+/// Map a TrdSecMarket value to the QotMarket of the same exchange.
+pub fn trd_sec_market_to_qot_market(sec_market: i32) -> Option<i32> {
+    Some(match sec_market {
+        1 => 1,   // HK
+        2 => 11,  // US
+        31 => 21, // CN_SH
+        32 => 22, // CN_SZ
+        41 => 31, // SG
+        51 => 41, // JP
+        61 => 51, // AU
+        71 => 61, // MY
+        _ => return None,
+    })
+}
+
+/// Map a QotMarket value to the TrdSecMarket of the same exchange.
+pub fn qot_market_to_trd_sec_market(market: i32) -> Option<i32> {
+    Some(match market {
+        1 => 1,
+        11 => 2,
+        21 => 31,
+        22 => 32,
+        31 => 41,
+        41 => 51,
+        51 => 61,
+        61 => 71,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> NameTable {
+        let mut t = NameTable::new();
+        t.add_names("en", vec![(1, "00700".to_string(), "Tencent".to_string())]);
+        t.add_names("zh-CN", vec![(1, "00700".to_string(), "腾讯控股".to_string())]);
+        t
+    }
+
+    #[test]
+    fn test_localize_by_language() {
+        let mut t = table();
+        assert_eq!(t.localize(1, "00700", "騰訊控股"), "騰訊控股");
+        t.set_language(Some("en".to_string()));
+        assert_eq!(t.localize(1, "00700", "騰訊控股"), "Tencent");
+        t.set_language(Some("zh-CN".to_string()));
+        assert_eq!(t.localize(1, "00700", "騰訊控股"), "腾讯控股");
+    }
+
+    #[test]
+    fn test_localize_falls_back() {
+        let mut t = table();
+        t.set_language(Some("en".to_string()));
+        assert_eq!(t.localize(1, "09988", "阿里巴巴"), "阿里巴巴");
+        assert_eq!(t.localize(11, "00700", "X"), "X");
+        assert_eq!(t.localize_opt(1, "00700", None), Some("Tencent"));
+        assert_eq!(t.localize_opt(1, "09988", None), None);
+        t.set_language(Some("ja".to_string()));
+        assert_eq!(t.localize(1, "00700", "騰訊控股"), "騰訊控股");
+        assert_eq!(t.len("en"), 1);
+        assert_eq!(t.len("ja"), 0);
+    }
+
+    #[test]
+    fn test_trd_sec_market_to_qot_market() {
+        assert_eq!(trd_sec_market_to_qot_market(2), Some(11));
+        assert_eq!(trd_sec_market_to_qot_market(31), Some(21));
+        assert_eq!(trd_sec_market_to_qot_market(0), None);
+        for sec_market in [1, 2, 31, 32, 41, 51, 61, 71] {
+            let market = trd_sec_market_to_qot_market(sec_market).unwrap();
+            assert_eq!(qot_market_to_trd_sec_market(market), Some(sec_market));
+        }
+        assert_eq!(qot_market_to_trd_sec_market(0), None);
+
+        let mut t = table();
+        t.set_language(Some("en".to_string()));
+        assert_eq!(t.localize_trd(Some(1), "00700", "騰訊控股"), "Tencent");
+        assert_eq!(t.localize_trd(None, "00700", "騰訊控股"), "騰訊控股");
+    }
+}
@@ -0,0 +1,307 @@
+//! Order books maintained locally from order book pushes.
+//!
+//! Every Qot_UpdateOrderBook push carries both sides of a security's book,
+//! down to the depth its quote right allows, each with the time Futu's
+//! server received it from the exchange. The recv loop applies the pushes
+//! to an `OrderBookEngine`, so the latest book, best bid and ask, and depth
+//! can be read at any time without decoding a push again.
+//!
+//! Pushes are applied in server time: a side older than the one held is out
+//! of order and kept out of the book. A side more than `max_gap` seconds
+//! newer than the one held counts as a gap, as updates may have been missed
+//! in between, e.g. while the connection was down; the book is still
+//! applied, and the gap reported and counted so callers can fetch a fresh
+//! one. Server times are only given for HK securities and are zero for
+//! pushes of cached data, so sides without one are always applied.
+
+use std::collections::HashMap;
+use parking_lot::Mutex;
+
+use crate::generated::qot_common::OrderBook;
+use crate::generated::qot_update_order_book;
+use super::push_stats::QuotePush;
+
+/// Server time gap after which a push counts as a gap by default.
+pub const DEFAULT_MAX_GAP: f64 = 60.0;
+
+/// One price level of a book.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Level {
+    pub price: f64,
+    pub volume: i64,
+    pub order_count: i32,
+}
+
+impl From<&OrderBook> for Level {
+    fn from(level: &OrderBook) -> Self {
+        Self { price: level.price, volume: level.volume, order_count: level.order_count }
+    }
+}
+
+/// The book of one security.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LocalBook {
+    /// Best bid first.
+    pub bids: Vec<Level>,
+    /// Best ask first.
+    pub asks: Vec<Level>,
+    /// Server receive times (Unix seconds) of the sides held.
+    pub bid_time: Option<f64>,
+    pub ask_time: Option<f64>,
+    /// Local time of the last push applied.
+    pub updated_at: f64,
+    /// Pushes applied.
+    pub sequence: u64,
+    /// Pushes that arrived after a gap.
+    pub gaps: u32,
+    /// Pushes dropped as out of order.
+    pub dropped: u32,
+}
+
+impl LocalBook {
+    pub fn best_bid(&self) -> Option<Level> {
+        self.bids.first().copied()
+    }
+
+    pub fn best_ask(&self) -> Option<Level> {
+        self.asks.first().copied()
+    }
+
+    /// Midpoint of the best bid and ask, if both sides have one.
+    pub fn mid(&self) -> Option<f64> {
+        Some((self.best_bid()?.price + self.best_ask()?.price) / 2.0)
+    }
+
+    pub fn spread(&self) -> Option<f64> {
+        Some(self.best_ask()?.price - self.best_bid()?.price)
+    }
+
+    /// The book cut to its best `levels` levels per side.
+    pub fn depth(&self, levels: usize) -> LocalBook {
+        LocalBook {
+            bids: self.bids.iter().take(levels).copied().collect(),
+            asks: self.asks.iter().take(levels).copied().collect(),
+            ..self.clone()
+        }
+    }
+
+    /// Volume of the best `levels` levels of a side.
+    pub fn volume(&self, is_bid: bool, levels: usize) -> i64 {
+        let side = if is_bid { &self.bids } else { &self.asks };
+        side.iter().take(levels).map(|level| level.volume).sum()
+    }
+}
+
+/// How a push changed its book.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BookUpdate {
+    Applied,
+    /// Applied after a server time gap of this many seconds.
+    Gap(f64),
+    /// Both sides were older than the book held.
+    Stale,
+}
+
+/// Local books of every security with order book pushes.
+#[derive(Debug)]
+pub struct OrderBookEngine {
+    books: Mutex<HashMap<(i32, String), LocalBook>>,
+    /// None disables gap detection.
+    max_gap: Mutex<Option<f64>>,
+}
+
+impl Default for OrderBookEngine {
+    fn default() -> Self {
+        Self::new(Some(DEFAULT_MAX_GAP))
+    }
+}
+
+impl OrderBookEngine {
+    pub fn new(max_gap: Option<f64>) -> Self {
+        Self { books: Mutex::new(HashMap::new()), max_gap: Mutex::new(max_gap) }
+    }
+
+    pub fn set_max_gap(&self, max_gap: Option<f64>) {
+        *self.max_gap.lock() = max_gap;
+    }
+
+    /// Record a push message. Other proto ids and undecodable bodies are ignored.
+    pub fn record_push(&self, proto_id: u32, body: &[u8], now: f64) -> Option<BookUpdate> {
+        self.record_decoded(&QuotePush::decode(proto_id, body)?, now)
+    }
+
+    /// Record a quote push already decoded; None unless it is an order book push.
+    pub fn record_decoded(&self, push: &QuotePush, now: f64) -> Option<BookUpdate> {
+        match push {
+            QuotePush::OrderBook(s2c) => Some(self.apply(s2c, now)),
+            _ => None,
+        }
+    }
+
+    /// Apply one order book push.
+    pub fn apply(&self, s2c: &qot_update_order_book::S2c, now: f64) -> BookUpdate {
+        let max_gap = *self.max_gap.lock();
+        let key = (s2c.security.market, s2c.security.code.clone());
+        let mut books = self.books.lock();
+        let book = books.entry(key).or_default();
+
+        let bid = side_update(book.bid_time, s2c.svr_recv_time_bid_timestamp, max_gap);
+        let ask = side_update(book.ask_time, s2c.svr_recv_time_ask_timestamp, max_gap);
+        if bid == BookUpdate::Stale && ask == BookUpdate::Stale {
+            book.dropped += 1;
+            return BookUpdate::Stale;
+        }
+        if bid != BookUpdate::Stale {
+            book.bids = s2c.order_book_bid_list.iter().map(Level::from).collect();
+            book.bid_time = server_time(s2c.svr_recv_time_bid_timestamp).or(book.bid_time);
+        }
+        if ask != BookUpdate::Stale {
+            book.asks = s2c.order_book_ask_list.iter().map(Level::from).collect();
+            book.ask_time = server_time(s2c.svr_recv_time_ask_timestamp).or(book.ask_time);
+        }
+        book.updated_at = now;
+        book.sequence += 1;
+
+        let gap = [bid, ask]
+            .into_iter()
+            .filter_map(|update| match update {
+                BookUpdate::Gap(seconds) => Some(seconds),
+                _ => None,
+            })
+            .reduce(f64::max);
+        match gap {
+            Some(seconds) => {
+                book.gaps += 1;
+                tracing::debug!("Order book of {:?} resumed after a {:.1}s gap", s2c.security.code, seconds);
+                BookUpdate::Gap(seconds)
+            }
+            None => BookUpdate::Applied,
+        }
+    }
+
+    /// The book of `security`, if a push of it arrived.
+    pub fn book(&self, security: &(i32, String)) -> Option<LocalBook> {
+        self.books.lock().get(security).cloned()
+    }
+
+    /// Best bid and ask of `security`.
+    pub fn best_bid_ask(&self, security: &(i32, String)) -> Option<(Option<Level>, Option<Level>)> {
+        self.books.lock().get(security).map(|book| (book.best_bid(), book.best_ask()))
+    }
+
+    /// The book of `security` cut to its best `levels` levels per side.
+    pub fn depth(&self, security: &(i32, String), levels: usize) -> Option<LocalBook> {
+        self.books.lock().get(security).map(|book| book.depth(levels))
+    }
+
+    /// Forget the book of `security`, e.g. once it is unsubscribed.
+    pub fn remove(&self, security: &(i32, String)) -> Option<LocalBook> {
+        self.books.lock().remove(security)
+    }
+
+    pub fn clear(&self) {
+        self.books.lock().clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.books.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.books.lock().is_empty()
+    }
+}
+
+/// Server times of zero mark cached data without one.
+fn server_time(timestamp: Option<f64>) -> Option<f64> {
+    timestamp.filter(|&t| t > 0.0)
+}
+
+/// How a side with server time `incoming` updates one held at `held`.
+fn side_update(held: Option<f64>, incoming: Option<f64>, max_gap: Option<f64>) -> BookUpdate {
+    let (Some(held), Some(incoming)) = (held, server_time(incoming)) else {
+        return BookUpdate::Applied;
+    };
+    if incoming < held {
+        BookUpdate::Stale
+    } else if max_gap.is_some_and(|max_gap| incoming - held > max_gap) {
+        BookUpdate::Gap(incoming - held)
+    } else {
+        BookUpdate::Applied
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+    use crate::quote::push_stats::PROTO_QOT_UPDATE_ORDER_BOOK;
+    use crate::generated::qot_common::Security;
+
+    fn level(price: f64, volume: i64) -> OrderBook {
+        OrderBook { price, volume, order_count: 1, detail_list: Vec::new() }
+    }
+
+    fn push(bid: f64, ask: f64, bid_time: f64, ask_time: f64) -> qot_update_order_book::S2c {
+        qot_update_order_book::S2c {
+            security: Security { market: 1, code: "00700".to_string() },
+            order_book_bid_list: vec![level(bid, 100), level(bid - 0.2, 300)],
+            order_book_ask_list: vec![level(ask, 200), level(ask + 0.2, 400)],
+            svr_recv_time_bid_timestamp: Some(bid_time),
+            svr_recv_time_ask_timestamp: Some(ask_time),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_pushes_applied_in_server_time() {
+        let engine = OrderBookEngine::default();
+        let key = (1, "00700".to_string());
+        assert_eq!(engine.apply(&push(300.0, 300.2, 10.0, 10.0), 1.0), BookUpdate::Applied);
+        assert_eq!(engine.apply(&push(300.2, 300.4, 11.0, 11.0), 2.0), BookUpdate::Applied);
+
+        // Both sides older: dropped
+        assert_eq!(engine.apply(&push(299.0, 299.2, 10.5, 10.5), 3.0), BookUpdate::Stale);
+        // Only the ask side is newer
+        assert_eq!(engine.apply(&push(299.0, 300.6, 10.5, 12.0), 4.0), BookUpdate::Applied);
+        let book = engine.book(&key).unwrap();
+        assert_eq!(book.best_bid().map(|l| l.price), Some(300.2));
+        assert_eq!(book.best_ask().map(|l| l.price), Some(300.6));
+        assert_eq!((book.sequence, book.dropped, book.bid_time, book.ask_time), (3, 1, Some(11.0), Some(12.0)));
+        assert_eq!(book.volume(true, 2), 400);
+        assert_eq!(engine.depth(&key, 1).unwrap().asks.len(), 1);
+        assert!((book.spread().unwrap() - 0.4).abs() < 1e-9);
+
+        // Cached data without server times is applied
+        assert_eq!(engine.apply(&push(301.0, 301.2, 0.0, 0.0), 5.0), BookUpdate::Applied);
+        assert_eq!(engine.book(&key).unwrap().bid_time, Some(11.0));
+    }
+
+    #[test]
+    fn test_gap_detection() {
+        let engine = OrderBookEngine::new(Some(30.0));
+        let key = (1, "00700".to_string());
+        engine.apply(&push(300.0, 300.2, 10.0, 10.0), 1.0);
+        assert_eq!(engine.apply(&push(300.0, 300.2, 100.0, 20.0), 2.0), BookUpdate::Gap(90.0));
+        assert_eq!(engine.book(&key).unwrap().gaps, 1);
+
+        engine.set_max_gap(None);
+        assert_eq!(engine.apply(&push(300.0, 300.2, 500.0, 500.0), 3.0), BookUpdate::Applied);
+        assert_eq!(engine.remove(&key).map(|b| b.sequence), Some(3));
+        assert!(engine.is_empty());
+    }
+
+    #[test]
+    fn test_record_push() {
+        let engine = OrderBookEngine::default();
+        let body = qot_update_order_book::Response {
+            ret_type: 0,
+            s2c: Some(push(300.0, 300.2, 10.0, 10.0)),
+            ..Default::default()
+        }.encode_to_vec();
+        assert_eq!(engine.record_push(PROTO_QOT_UPDATE_ORDER_BOOK, &body, 1.0), Some(BookUpdate::Applied));
+        assert_eq!(engine.record_push(3005, &body, 1.0), None);
+        let (bid, ask) = engine.best_bid_ask(&(1, "00700".to_string())).unwrap();
+        assert_eq!((bid.unwrap().volume, ask.unwrap().volume), (100, 200));
+    }
+}
@@ -0,0 +1,196 @@
+//! Timer-driven basic quote polling with change detection.
+//!
+//! Qot_GetBasicQot only serves subscribed securities, so the poller uses
+//! Qot_GetSecuritySnapshot (no subscription quota) and converts each snapshot
+//! into a `BasicQot`. Only quotes whose update time moved are emitted.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::client::FutuClient;
+use crate::generated::qot_common::BasicQot;
+use crate::generated::qot_get_security_snapshot::SnapshotBasicData;
+
+/// Maximum securities per Qot_GetSecuritySnapshot request.
+pub const SNAPSHOT_MAX_SECURITIES: usize = 400;
+
+/// Settings for `start_basic_qot_poller`.
+#[derive(Debug, Clone)]
+pub struct QotPollerConfig {
+    /// (market, code) pairs to poll.
+    pub securities: Vec<(i32, String)>,
+    /// Time between full polling passes.
+    pub interval: Duration,
+    /// Securities per snapshot request (clamped to 1..=400).
+    pub chunk_size: usize,
+    /// Minimum delay between consecutive snapshot requests within a pass.
+    /// OpenD allows 60 snapshot requests per 30 seconds.
+    pub request_interval: Duration,
+}
+
+impl Default for QotPollerConfig {
+    fn default() -> Self {
+        Self {
+            securities: Vec::new(),
+            interval: Duration::from_secs(3),
+            chunk_size: SNAPSHOT_MAX_SECURITIES,
+            request_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Remembers the last seen update time per security.
+#[derive(Debug, Default)]
+pub struct ChangeDetector {
+    last: HashMap<(i32, String), (Option<f64>, String)>,
+}
+
+impl ChangeDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true (and records the quote) if its update time differs from the last one seen.
+    pub fn update(&mut self, qot: &BasicQot) -> bool {
+        let key = (qot.security.market, qot.security.code.clone());
+        let stamp = (qot.update_timestamp, qot.update_time.clone());
+        match self.last.get(&key) {
+            Some(prev) if *prev == stamp => false,
+            _ => {
+                self.last.insert(key, stamp);
+                true
+            }
+        }
+    }
+}
+
+/// Convert snapshot basic data into the BasicQot shape used by push updates.
+pub fn snapshot_to_basic_qot(basic: &SnapshotBasicData) -> BasicQot {
+    BasicQot {
+        security: basic.security.clone(),
+        name: basic.name.clone(),
+        is_suspended: basic.is_suspend,
+        list_time: basic.list_time.clone(),
+        price_spread: basic.price_spread,
+        update_time: basic.update_time.clone(),
+        high_price: basic.high_price,
+        open_price: basic.open_price,
+        low_price: basic.low_price,
+        cur_price: basic.cur_price,
+        last_close_price: basic.last_close_price,
+        volume: basic.volume,
+        turnover: basic.turnover,
+        turnover_rate: basic.turnover_rate,
+        amplitude: basic.amplitude.unwrap_or_default(),
+        list_timestamp: basic.list_timestamp,
+        update_timestamp: basic.update_timestamp,
+        pre_market: basic.pre_market,
+        after_market: basic.after_market,
+        sec_status: basic.sec_status,
+        ..Default::default()
+    }
+}
+
+/// Start polling basic quotes. Each pass sends the changed quotes (if any) as one batch.
+/// The task stops when the receiver is dropped or the handle is aborted.
+pub fn start_basic_qot_poller(
+    client: Arc<FutuClient>,
+    config: QotPollerConfig,
+) -> (tokio::task::JoinHandle<()>, mpsc::UnboundedReceiver<Vec<BasicQot>>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let handle = tokio::spawn(run_basic_qot_poller(client, config, tx));
+    (handle, rx)
+}
+
+/// Polling loop behind `start_basic_qot_poller`, for callers that spawn it themselves.
+/// Returns when `tx` is closed.
+pub async fn run_basic_qot_poller(
+    client: Arc<FutuClient>,
+    config: QotPollerConfig,
+    tx: mpsc::UnboundedSender<Vec<BasicQot>>,
+) {
+    let chunk_size = config.chunk_size.clamp(1, SNAPSHOT_MAX_SECURITIES);
+    let mut detector = ChangeDetector::new();
+    let mut ticker = tokio::time::interval(config.interval);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        ticker.tick().await;
+        let mut changed = Vec::new();
+        for (i, chunk) in config.securities.chunks(chunk_size).enumerate() {
+            if i > 0 {
+                tokio::time::sleep(config.request_interval).await;
+            }
+            match super::snapshot::get_security_snapshot(&client, chunk.to_vec()).await {
+                Ok(resp) => {
+                    for snapshot in resp.s2c.map(|s| s.snapshot_list).unwrap_or_default() {
+                        let qot = snapshot_to_basic_qot(&snapshot.basic);
+                        if detector.update(&qot) {
+                            changed.push(qot);
+                        }
+                    }
+                }
+                Err(e) => tracing::warn!("Basic qot poll failed for {} securities: {}", chunk.len(), e),
+            }
+        }
+        if !changed.is_empty() && tx.send(changed).is_err() {
+            break;
+        }
+        if tx.is_closed() {
+            break;
+        }
+    }
+    tracing::debug!("Basic qot poller stopped");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generated::qot_common::Security;
+
+    fn qot(code: &str, ts: f64) -> BasicQot {
+        BasicQot {
+            security: Security { market: 1, code: code.to_string() },
+            update_timestamp: Some(ts),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_change_detector() {
+        let mut det = ChangeDetector::new();
+        assert!(det.update(&qot("00700", 1.0)));
+        assert!(!det.update(&qot("00700", 1.0)));
+        assert!(det.update(&qot("00700", 2.0)));
+        assert!(det.update(&qot("09988", 1.0)));
+    }
+
+    #[test]
+    fn test_snapshot_to_basic_qot() {
+        let basic = SnapshotBasicData {
+            security: Security { market: 11, code: "AAPL".to_string() },
+            is_suspend: true,
+            cur_price: 195.5,
+            volume: 1000,
+            amplitude: Some(1.2),
+            update_timestamp: Some(1704067200.0),
+            ..Default::default()
+        };
+        let qot = snapshot_to_basic_qot(&basic);
+        assert_eq!(qot.security.code, "AAPL");
+        assert!(qot.is_suspended);
+        assert_eq!(qot.cur_price, 195.5);
+        assert_eq!(qot.volume, 1000);
+        assert_eq!(qot.amplitude, 1.2);
+        assert_eq!(qot.update_timestamp, Some(1704067200.0));
+    }
+
+    #[test]
+    fn test_default_config() {
+        let config = QotPollerConfig::default();
+        assert_eq!(config.chunk_size, SNAPSHOT_MAX_SECURITIES);
+        assert_eq!(config.request_interval, Duration::from_millis(500));
+    }
+}
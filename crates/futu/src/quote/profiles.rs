@@ -0,0 +1,191 @@
+//! Named subscription profiles that are activated and deactivated as a unit.
+
+use std::collections::{BTreeMap, BTreeSet};
+use crate::client::FutuClient;
+use super::subscribe::{check_sub_results, subscribe, QuoteError};
+
+/// A named set of securities and sub types subscribed together.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubscriptionProfile {
+    /// (market, code) pairs.
+    pub securities: Vec<(i32, String)>,
+    /// SubType values.
+    pub sub_types: Vec<i32>,
+}
+
+impl SubscriptionProfile {
+    /// Every (security, sub_type) pair covered by this profile.
+    fn pairs(&self) -> impl Iterator<Item = ((i32, String), i32)> + '_ {
+        self.securities
+            .iter()
+            .flat_map(move |sec| self.sub_types.iter().map(move |st| (sec.clone(), *st)))
+    }
+}
+
+/// Registry of defined profiles and which of them are currently active.
+#[derive(Debug, Default)]
+pub struct ProfileRegistry {
+    profiles: BTreeMap<String, SubscriptionProfile>,
+    active: BTreeSet<String>,
+}
+
+impl ProfileRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Define or replace a profile. Returns the previous definition, if any.
+    pub fn define(&mut self, name: &str, profile: SubscriptionProfile) -> Option<SubscriptionProfile> {
+        self.profiles.insert(name.to_string(), profile)
+    }
+
+    /// Remove a profile definition. Active profiles cannot be removed.
+    pub fn remove(&mut self, name: &str) -> Option<SubscriptionProfile> {
+        if self.active.contains(name) {
+            return None;
+        }
+        self.profiles.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&SubscriptionProfile> {
+        self.profiles.get(name)
+    }
+
+    /// Names of all defined profiles, sorted.
+    pub fn names(&self) -> Vec<String> {
+        self.profiles.keys().cloned().collect()
+    }
+
+    pub fn is_active(&self, name: &str) -> bool {
+        self.active.contains(name)
+    }
+
+    /// Names of active profiles, sorted.
+    pub fn active_names(&self) -> Vec<String> {
+        self.active.iter().cloned().collect()
+    }
+
+    /// Mark a defined profile active. Returns false if it is not defined.
+    pub fn mark_active(&mut self, name: &str) -> bool {
+        if !self.profiles.contains_key(name) {
+            return false;
+        }
+        self.active.insert(name.to_string());
+        true
+    }
+
+    pub fn mark_inactive(&mut self, name: &str) {
+        self.active.remove(name);
+    }
+
+    /// Clones of all active profiles, for replaying after a reconnect.
+    pub fn active_profiles(&self) -> Vec<(String, SubscriptionProfile)> {
+        self.active
+            .iter()
+            .filter_map(|n| self.profiles.get(n).map(|p| (n.clone(), p.clone())))
+            .collect()
+    }
+
+    /// Pairs of `name` that no other active profile still needs, grouped by sub type.
+    /// These are the subscriptions safe to drop when `name` is deactivated.
+    pub fn exclusive_pairs(&self, name: &str) -> BTreeMap<i32, Vec<(i32, String)>> {
+        let mut result: BTreeMap<i32, Vec<(i32, String)>> = BTreeMap::new();
+        let Some(profile) = self.profiles.get(name) else {
+            return result;
+        };
+        let shared: BTreeSet<((i32, String), i32)> = self
+            .active
+            .iter()
+            .filter(|n| n.as_str() != name)
+            .filter_map(|n| self.profiles.get(n))
+            .flat_map(|p| p.pairs())
+            .collect();
+        for (sec, sub_type) in profile.pairs() {
+            if !shared.contains(&(sec.clone(), sub_type)) {
+                let secs = result.entry(sub_type).or_default();
+                if !secs.contains(&sec) {
+                    secs.push(sec);
+                }
+            }
+        }
+        result
+    }
+}
+
+/// Subscribe every security/sub type pair of a profile.
+pub async fn activate_profile(
+    client: &FutuClient,
+    profile: &SubscriptionProfile,
+) -> Result<(), QuoteError> {
+    let results = subscribe(client, profile.securities.clone(), profile.sub_types.clone(), true).await?;
+    check_sub_results(results)
+}
+
+/// Unsubscribe pairs previously computed with `ProfileRegistry::exclusive_pairs`.
+pub async fn deactivate_pairs(
+    client: &FutuClient,
+    pairs: BTreeMap<i32, Vec<(i32, String)>>,
+) -> Result<(), QuoteError> {
+    for (sub_type, securities) in pairs {
+        check_sub_results(subscribe(client, securities, vec![sub_type], false).await?)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(codes: &[&str], sub_types: &[i32]) -> SubscriptionProfile {
+        SubscriptionProfile {
+            securities: codes.iter().map(|c| (1, c.to_string())).collect(),
+            sub_types: sub_types.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_define_and_activate() {
+        let mut reg = ProfileRegistry::new();
+        assert!(!reg.mark_active("missing"));
+        assert!(reg.define("hk", profile(&["00700"], &[1])).is_none());
+        assert!(reg.define("hk", profile(&["00700", "09988"], &[1])).is_some());
+        assert!(reg.mark_active("hk"));
+        assert!(reg.is_active("hk"));
+        assert_eq!(reg.active_profiles()[0].1.securities.len(), 2);
+        reg.mark_inactive("hk");
+        assert!(reg.active_names().is_empty());
+    }
+
+    #[test]
+    fn test_remove_active_profile_refused() {
+        let mut reg = ProfileRegistry::new();
+        reg.define("hk", profile(&["00700"], &[1]));
+        reg.mark_active("hk");
+        assert!(reg.remove("hk").is_none());
+        reg.mark_inactive("hk");
+        assert!(reg.remove("hk").is_some());
+        assert!(reg.names().is_empty());
+    }
+
+    #[test]
+    fn test_exclusive_pairs_skip_shared() {
+        let mut reg = ProfileRegistry::new();
+        reg.define("a", profile(&["00700", "09988"], &[1, 4]));
+        reg.define("b", profile(&["00700"], &[1]));
+        reg.mark_active("a");
+        reg.mark_active("b");
+        let pairs = reg.exclusive_pairs("a");
+        assert_eq!(pairs[&1], vec![(1, "09988".to_string())]);
+        assert_eq!(pairs[&4], vec![(1, "00700".to_string()), (1, "09988".to_string())]);
+    }
+
+    #[test]
+    fn test_exclusive_pairs_ignores_inactive() {
+        let mut reg = ProfileRegistry::new();
+        reg.define("a", profile(&["00700"], &[1]));
+        reg.define("b", profile(&["00700"], &[1]));
+        reg.mark_active("a");
+        assert_eq!(reg.exclusive_pairs("a")[&1].len(), 1);
+        assert!(reg.exclusive_pairs("missing").is_empty());
+    }
+}
@@ -0,0 +1,360 @@
+//! Per-security push statistics and staleness detection.
+//!
+//! The recv loop records every quote push per (security, sub type).
+//! Subscriptions made through `subscribe` are tracked, so a subscribed
+//! security that never pushes is reported as stale too.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use parking_lot::Mutex;
+use prost::Message;
+use tokio::sync::mpsc;
+
+use crate::client::FutuClient;
+use crate::generated::get_global_state;
+
+pub(crate) const PROTO_QOT_UPDATE_BASIC_QOT: u32 = 3005;
+pub(crate) const PROTO_QOT_UPDATE_KL: u32 = 3007;
+pub(crate) const PROTO_QOT_UPDATE_TICKER: u32 = 3011;
+pub(crate) const PROTO_QOT_UPDATE_ORDER_BOOK: u32 = 3013;
+
+// SubType values
+const SUB_TYPE_BASIC: i32 = 1;
+const SUB_TYPE_ORDER_BOOK: i32 = 2;
+const SUB_TYPE_TICKER: i32 = 4;
+
+type StatKey = ((i32, String), i32);
+
+/// Push statistics for one (security, sub type) pair.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PushStat {
+    /// (market, code)
+    pub security: (i32, String),
+    pub sub_type: i32,
+    /// Pushes received.
+    pub count: u64,
+    /// Unix timestamp of the last push, if any.
+    pub last_update: Option<f64>,
+    /// Unix timestamp when tracking started (subscription or first push).
+    pub tracked_since: f64,
+    /// Whether the pair is currently subscribed through `subscribe`.
+    pub subscribed: bool,
+}
+
+impl PushStat {
+    /// Seconds since the last push, or since tracking started if none arrived.
+    pub fn age(&self, now: f64) -> f64 {
+        now - self.last_update.unwrap_or(self.tracked_since)
+    }
+}
+
+/// Thread-safe push statistics keyed by (security, sub type).
+#[derive(Debug, Default)]
+pub struct PushStats {
+    entries: Mutex<HashMap<StatKey, PushStat>>,
+}
+
+impl PushStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark pairs as subscribed. Existing counters are kept.
+    pub fn track(&self, securities: &[(i32, String)], sub_types: &[i32], now: f64) {
+        let mut entries = self.entries.lock();
+        for sec in securities {
+            for &sub_type in sub_types {
+                entries
+                    .entry((sec.clone(), sub_type))
+                    .or_insert_with(|| new_stat(sec.clone(), sub_type, now))
+                    .subscribed = true;
+            }
+        }
+    }
+
+    /// Forget unsubscribed pairs.
+    pub fn untrack(&self, securities: &[(i32, String)], sub_types: &[i32]) {
+        let mut entries = self.entries.lock();
+        for sec in securities {
+            for &sub_type in sub_types {
+                entries.remove(&(sec.clone(), sub_type));
+            }
+        }
+    }
+
+    /// Record a push message. Non-quote proto ids and undecodable bodies are ignored.
+    pub fn record(&self, proto_id: u32, body: &[u8], now: f64) {
+        if let Some(push) = QuotePush::decode(proto_id, body) {
+            self.record_decoded(&push, now);
+        }
+    }
+
+    /// Record a quote push already decoded.
+    pub fn record_decoded(&self, push: &QuotePush, now: f64) {
+        let pairs = push.pairs();
+        if pairs.is_empty() {
+            return;
+        }
+        let mut entries = self.entries.lock();
+        for (sec, sub_type) in pairs {
+            let stat = entries
+                .entry((sec.clone(), sub_type))
+                .or_insert_with(|| new_stat(sec, sub_type, now));
+            stat.count += 1;
+            stat.last_update = Some(now);
+        }
+    }
+
+    /// All statistics, sorted by security and sub type.
+    pub fn snapshot(&self) -> Vec<PushStat> {
+        let mut stats: Vec<PushStat> = self.entries.lock().values().cloned().collect();
+        stats.sort_by(|a, b| (&a.security, a.sub_type).cmp(&(&b.security, b.sub_type)));
+        stats
+    }
+
+    /// Subscribed pairs without a push for longer than `threshold` seconds.
+    pub fn stale(&self, threshold: f64, now: f64) -> Vec<PushStat> {
+        self.snapshot()
+            .into_iter()
+            .filter(|s| s.subscribed && s.age(now) > threshold)
+            .collect()
+    }
+}
+
+fn new_stat(security: (i32, String), sub_type: i32, now: f64) -> PushStat {
+    PushStat {
+        security,
+        sub_type,
+        count: 0,
+        last_update: None,
+        tracked_since: now,
+        subscribed: false,
+    }
+}
+
+/// Map a KLType to the SubType that pushes it.
+pub fn kl_type_to_sub_type(kl_type: i32) -> Option<i32> {
+    Some(match kl_type {
+        1 => 11,  // 1Min
+        2 => 6,   // Day
+        3 => 12,  // Week
+        4 => 13,  // Month
+        5 => 16,  // Year
+        6 => 7,   // 5Min
+        7 => 8,   // 15Min
+        8 => 9,   // 30Min
+        9 => 10,  // 60Min
+        10 => 17, // 3Min
+        11 => 15, // Quarter
+        _ => return None,
+    })
+}
+
+/// A quote push decoded once for all observers of the recv loop.
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuotePush {
+    BasicQot(crate::generated::qot_update_basic_qot::S2c),
+    Ticker(crate::generated::qot_update_ticker::S2c),
+    OrderBook(crate::generated::qot_update_order_book::S2c),
+    Kl(crate::generated::qot_update_kl::S2c),
+}
+
+impl QuotePush {
+    /// Decode a quote push. None for other proto ids and undecodable bodies.
+    pub fn decode(proto_id: u32, body: &[u8]) -> Option<Self> {
+        Some(match proto_id {
+            PROTO_QOT_UPDATE_BASIC_QOT => Self::BasicQot(crate::generated::qot_update_basic_qot::Response::decode(body).ok()?.s2c?),
+            PROTO_QOT_UPDATE_TICKER => Self::Ticker(crate::generated::qot_update_ticker::Response::decode(body).ok()?.s2c?),
+            PROTO_QOT_UPDATE_ORDER_BOOK => Self::OrderBook(crate::generated::qot_update_order_book::Response::decode(body).ok()?.s2c?),
+            PROTO_QOT_UPDATE_KL => Self::Kl(crate::generated::qot_update_kl::Response::decode(body).ok()?.s2c?),
+            _ => return None,
+        })
+    }
+
+    /// (security, sub type) pairs carried by the push.
+    pub fn pairs(&self) -> Vec<((i32, String), i32)> {
+        let key = |s: &crate::generated::qot_common::Security| (s.market, s.code.clone());
+        match self {
+            Self::BasicQot(s) => s.basic_qot_list.iter().map(|q| (key(&q.security), SUB_TYPE_BASIC)).collect(),
+            Self::Ticker(s) => vec![(key(&s.security), SUB_TYPE_TICKER)],
+            Self::OrderBook(s) => vec![(key(&s.security), SUB_TYPE_ORDER_BOOK)],
+            Self::Kl(s) => kl_type_to_sub_type(s.kl_type).map(|st| vec![(key(&s.security), st)]).unwrap_or_default(),
+        }
+    }
+}
+
+/// (security, sub type) pairs carried by a quote push.
+fn push_pairs(proto_id: u32, body: &[u8]) -> Vec<((i32, String), i32)> {
+    QuotePush::decode(proto_id, body).map(|push| push.pairs()).unwrap_or_default()
+}
+
+/// Securities a quote push is about, for filtering pushes by security.
+pub fn push_securities(proto_id: u32, body: &[u8]) -> Vec<(i32, String)> {
+    if proto_id == super::reminder::PROTO_QOT_UPDATE_PRICE_REMINDER {
+        return crate::generated::qot_update_price_reminder::Response::decode(body)
+            .ok()
+            .and_then(|r| r.s2c)
+            .map(|s| vec![(s.security.market, s.security.code)])
+            .unwrap_or_default();
+    }
+    push_pairs(proto_id, body).into_iter().map(|(security, _)| security).collect()
+}
+
+/// Whether `market` (QotMarket) is in a continuous trading session according to global state.
+pub fn market_in_session(market: i32, state: &get_global_state::S2c) -> bool {
+    let market_state = match market {
+        1 => state.market_hk,
+        2 => state.market_hk_future,
+        11 => state.market_us,
+        21 => state.market_sh,
+        22 => state.market_sz,
+        31 => state.market_sg_future.unwrap_or(0),
+        41 => state.market_jp_future.unwrap_or(0),
+        _ => return false,
+    };
+    // Morning, Afternoon, NightOpen, FutureDayOpen, FutureOpen, FutureBreakOver
+    matches!(market_state, 3 | 5 | 13 | 15 | 23 | 25)
+}
+
+/// Watch push statistics and send an alarm for each subscribed pair that goes
+/// stale while its market is in session. A pair alarms again only after it
+/// has received a push in between. Returns when `tx` is closed.
+pub async fn run_staleness_monitor(
+    client: Arc<FutuClient>,
+    threshold: Duration,
+    check_interval: Duration,
+    tx: mpsc::UnboundedSender<PushStat>,
+) {
+    let mut alarmed: HashSet<StatKey> = HashSet::new();
+    let clock = Arc::clone(client.clock());
+    let mut next_check = clock.now();
+    let user_id = client.init_response().map(|r| r.login_user_id).unwrap_or(0);
+
+    loop {
+        clock.sleep_until(next_check).await;
+        next_check = clock.now() + check_interval;
+        if tx.is_closed() {
+            break;
+        }
+        let state = match crate::client::init::get_global_state(&client, user_id).await {
+            Ok(resp) => match resp.s2c {
+                Some(s2c) => s2c,
+                None => continue,
+            },
+            Err(e) => {
+                tracing::warn!("Staleness check skipped, global state unavailable: {}", e);
+                continue;
+            }
+        };
+        let stale = client.push_stats().stale(threshold.as_secs_f64(), clock.unix_time());
+        let stale_keys: HashSet<StatKey> = stale
+            .iter()
+            .map(|s| (s.security.clone(), s.sub_type))
+            .collect();
+        alarmed.retain(|k| stale_keys.contains(k));
+
+        for stat in stale {
+            if !market_in_session(stat.security.0, &state) {
+                continue;
+            }
+            if alarmed.insert((stat.security.clone(), stat.sub_type)) {
+                tracing::warn!(
+                    "No push for {}.{} sub_type={} in {:.0}s",
+                    stat.security.0, stat.security.1, stat.sub_type, stat.age(clock.unix_time()),
+                );
+                if tx.send(stat).is_err() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Current Unix timestamp in seconds.
+pub fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generated::qot_common::Security;
+
+    fn sec(code: &str) -> (i32, String) {
+        (1, code.to_string())
+    }
+
+    fn ticker_push(code: &str) -> Vec<u8> {
+        crate::generated::qot_update_ticker::Response {
+            ret_type: 0,
+            ret_msg: None,
+            err_code: None,
+            s2c: Some(crate::generated::qot_update_ticker::S2c {
+                security: Security { market: 1, code: code.to_string() },
+                ..Default::default()
+            }),
+        }
+        .encode_to_vec()
+    }
+
+    #[test]
+    fn test_record_and_stale() {
+        let stats = PushStats::new();
+        stats.track(&[sec("00700"), sec("09988")], &[SUB_TYPE_TICKER], 100.0);
+        stats.record(PROTO_QOT_UPDATE_TICKER, &ticker_push("00700"), 150.0);
+
+        let stale = stats.stale(30.0, 160.0);
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].security, sec("09988"));
+        assert_eq!(stale[0].count, 0);
+
+        let all = stats.snapshot();
+        assert_eq!(all[0].count, 1);
+        assert_eq!(all[0].last_update, Some(150.0));
+        assert!(stats.stale(30.0, 190.0).iter().any(|s| s.security == sec("00700")));
+    }
+
+    #[test]
+    fn test_unsubscribed_pushes_not_stale() {
+        let stats = PushStats::new();
+        stats.record(PROTO_QOT_UPDATE_TICKER, &ticker_push("00700"), 0.0);
+        stats.record(1004, b"ignored", 0.0);
+        assert_eq!(stats.snapshot().len(), 1);
+        assert!(stats.stale(1.0, 100.0).is_empty());
+
+        stats.track(&[sec("00700")], &[SUB_TYPE_TICKER], 0.0);
+        stats.untrack(&[sec("00700")], &[SUB_TYPE_TICKER]);
+        assert!(stats.snapshot().is_empty());
+    }
+
+    #[test]
+    fn test_quote_push_decode() {
+        let push = QuotePush::decode(PROTO_QOT_UPDATE_TICKER, &ticker_push("00700")).unwrap();
+        assert_eq!(push.pairs(), vec![((1, "00700".to_string()), SUB_TYPE_TICKER)]);
+        assert_eq!(QuotePush::decode(1004, b"ignored"), None);
+        assert_eq!(QuotePush::decode(PROTO_QOT_UPDATE_TICKER, b"\xff"), None);
+
+        let stats = PushStats::new();
+        stats.record_decoded(&push, 5.0);
+        assert_eq!(stats.snapshot()[0].last_update, Some(5.0));
+    }
+
+    #[test]
+    fn test_kl_type_to_sub_type() {
+        assert_eq!(kl_type_to_sub_type(1), Some(11));
+        assert_eq!(kl_type_to_sub_type(2), Some(6));
+        assert_eq!(kl_type_to_sub_type(99), None);
+    }
+
+    #[test]
+    fn test_market_in_session() {
+        let state = get_global_state::S2c { market_hk: 3, market_us: 6, ..Default::default() };
+        assert!(market_in_session(1, &state));
+        assert!(!market_in_session(11, &state));
+        assert!(!market_in_session(99, &state));
+    }
+}
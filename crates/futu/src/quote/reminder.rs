@@ -0,0 +1,202 @@
+//! Price reminders kept by OpenD: alerts on price, change rate, volume and
+//! order book levels that OpenD checks server-side and pushes as
+//! Qot_UpdatePriceReminder when they trigger.
+//!
+//! Reminders are addressed by the key OpenD assigns when adding them.
+//! Values of percentage types (change rates, turnover rate) are given in
+//! percent, e.g. 20 for 20%.
+
+use prost::Message;
+
+use crate::client::FutuClient;
+use crate::generated::qot_common::Security;
+use crate::generated::{qot_get_price_reminder, qot_set_price_reminder};
+use super::subscribe::QuoteError;
+
+const PROTO_QOT_SET_PRICE_REMINDER: u32 = 3220;
+const PROTO_QOT_GET_PRICE_REMINDER: u32 = 3221;
+pub const PROTO_QOT_UPDATE_PRICE_REMINDER: u32 = 3019;
+
+// Qot_SetPriceReminder.SetPriceReminderOp values
+pub const OP_ADD: i32 = 1;
+pub const OP_DEL: i32 = 2;
+pub const OP_ENABLE: i32 = 3;
+pub const OP_DISABLE: i32 = 4;
+pub const OP_MODIFY: i32 = 5;
+pub const OP_DEL_ALL: i32 = 6;
+
+/// Settings of a reminder to add, or to replace those of an existing one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReminderSpec {
+    /// Qot_Common.PriceReminderType
+    pub reminder_type: i32,
+    pub value: f64,
+    /// Qot_Common.PriceReminderFreq (None: OpenD default)
+    pub freq: Option<i32>,
+    pub note: Option<String>,
+    /// Qot_Common.PriceReminderMarketStatus values the reminder is active
+    /// in (empty: OpenD default)
+    pub sessions: Vec<i32>,
+}
+
+/// Add a reminder for `security`. Returns the key OpenD assigned.
+pub async fn add_price_reminder(
+    client: &FutuClient,
+    security: (i32, String),
+    spec: ReminderSpec,
+) -> Result<i64, QuoteError> {
+    set_price_reminder(client, security, OP_ADD, None, Some(spec)).await
+}
+
+/// Replace the settings of reminder `key`.
+pub async fn update_price_reminder(
+    client: &FutuClient,
+    security: (i32, String),
+    key: i64,
+    spec: ReminderSpec,
+) -> Result<i64, QuoteError> {
+    set_price_reminder(client, security, OP_MODIFY, Some(key), Some(spec)).await
+}
+
+/// Turn reminder `key` on or off without deleting it.
+pub async fn enable_price_reminder(
+    client: &FutuClient,
+    security: (i32, String),
+    key: i64,
+    enable: bool,
+) -> Result<i64, QuoteError> {
+    let op = if enable { OP_ENABLE } else { OP_DISABLE };
+    set_price_reminder(client, security, op, Some(key), None).await
+}
+
+/// Delete reminder `key`, or every reminder of `security` if None.
+pub async fn delete_price_reminder(
+    client: &FutuClient,
+    security: (i32, String),
+    key: Option<i64>,
+) -> Result<i64, QuoteError> {
+    let op = if key.is_some() { OP_DEL } else { OP_DEL_ALL };
+    set_price_reminder(client, security, op, key, None).await
+}
+
+/// Send Qot_SetPriceReminder with `op`. Returns the key of the reminder.
+pub async fn set_price_reminder(
+    client: &FutuClient,
+    security: (i32, String),
+    op: i32,
+    key: Option<i64>,
+    spec: Option<ReminderSpec>,
+) -> Result<i64, QuoteError> {
+    let (market, code) = security;
+    let mut c2s = qot_set_price_reminder::C2s { security: Security { market, code }, op, key, ..Default::default() };
+    if let Some(spec) = spec {
+        c2s.r#type = Some(spec.reminder_type);
+        c2s.value = Some(spec.value);
+        c2s.freq = spec.freq;
+        c2s.note = spec.note;
+        c2s.reminder_session_list = spec.sessions;
+    }
+    let request = qot_set_price_reminder::Request { c2s };
+    let body = request.encode_to_vec();
+
+    let resp = client.request(PROTO_QOT_SET_PRICE_REMINDER, &body).await
+        .map_err(QuoteError::Connection)?;
+
+    let response = client.decode::<qot_set_price_reminder::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
+
+    if response.ret_type != 0 {
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
+    }
+
+    // Deleting every reminder answers without a key
+    Ok(response.s2c.map(|s| s.key).or(key).unwrap_or_default())
+}
+
+/// Reminders of `security`, or of every security of `market` when no
+/// security is given.
+pub async fn get_price_reminder(
+    client: &FutuClient,
+    security: Option<(i32, String)>,
+    market: Option<i32>,
+) -> Result<Vec<qot_get_price_reminder::PriceReminder>, QuoteError> {
+    let c2s = qot_get_price_reminder::C2s {
+        security: security.map(|(market, code)| Security { market, code }),
+        market,
+    };
+    let request = qot_get_price_reminder::Request { c2s };
+    let body = request.encode_to_vec();
+
+    let resp = client.request(PROTO_QOT_GET_PRICE_REMINDER, &body).await
+        .map_err(QuoteError::Connection)?;
+
+    let response = client.decode::<qot_get_price_reminder::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
+
+    if response.ret_type != 0 {
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
+    }
+
+    Ok(response.s2c.map(|s| s.price_reminder_list).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::mock::MockOpenD;
+    use crate::config::FutuConfig;
+    use crate::generated::qot_get_price_reminder::{PriceReminder, PriceReminderItem};
+
+    #[test]
+    fn test_set_request_encoding() {
+        let c2s = qot_set_price_reminder::C2s {
+            security: Security { market: 1, code: "00700".to_string() },
+            op: OP_ADD,
+            r#type: Some(1),
+            value: Some(400.0),
+            reminder_session_list: vec![1, 2],
+            ..Default::default()
+        };
+        let encoded = qot_set_price_reminder::Request { c2s: c2s.clone() }.encode_to_vec();
+        let decoded = qot_set_price_reminder::Request::decode(encoded.as_slice()).unwrap();
+        assert_eq!(decoded.c2s, c2s);
+    }
+
+    #[tokio::test]
+    async fn test_reminders_against_mock() {
+        let mock = MockOpenD::start().await.unwrap();
+        let config = FutuConfig { port: mock.port(), enable_encryption: false, ..FutuConfig::default() };
+        let mut client = FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+        let tencent = (1, "00700".to_string());
+
+        let added = qot_set_price_reminder::Response {
+            ret_type: 0,
+            s2c: Some(qot_set_price_reminder::S2c { key: 77 }),
+            ..Default::default()
+        };
+        mock.respond(PROTO_QOT_SET_PRICE_REMINDER, added.encode_to_vec());
+        let spec = ReminderSpec { reminder_type: 1, value: 400.0, ..Default::default() };
+        assert_eq!(add_price_reminder(&client, tencent.clone(), spec).await.unwrap(), 77);
+
+        // No s2c: the key given is returned, or 0 after deleting them all
+        mock.respond(PROTO_QOT_SET_PRICE_REMINDER, vec![0x08, 0x00]);
+        assert_eq!(enable_price_reminder(&client, tencent.clone(), 77, false).await.unwrap(), 77);
+        assert_eq!(delete_price_reminder(&client, tencent.clone(), None).await.unwrap(), 0);
+
+        let listed = qot_get_price_reminder::Response {
+            ret_type: 0,
+            s2c: Some(qot_get_price_reminder::S2c {
+                price_reminder_list: vec![PriceReminder {
+                    security: Security { market: 1, code: "00700".to_string() },
+                    item_list: vec![PriceReminderItem { key: 77, r#type: 1, value: 400.0, freq: 1, ..Default::default() }],
+                    name: Some("TENCENT".to_string()),
+                }],
+            }),
+            ..Default::default()
+        };
+        mock.respond(PROTO_QOT_GET_PRICE_REMINDER, listed.encode_to_vec());
+        let reminders = get_price_reminder(&client, Some(tencent), None).await.unwrap();
+        assert_eq!(reminders[0].item_list[0].key, 77);
+    }
+}
@@ -0,0 +1,220 @@
+//! Trading sessions of a day, per market and TradeDateType.
+//!
+//! Qot_RequestTradeDate marks every trading day as a whole day or one that
+//! holds only the morning or only the afternoon session. `day_sessions`
+//! turns that into the day's open and close times: a morning-only day keeps
+//! the sessions before the lunch break, an afternoon-only day those after
+//! it, and markets without a lunch break close early instead (13:00 in the
+//! US, 14:10 in Australia). `SessionCalendar` applies this to the trade
+//! dates of a market, and `validate_kl` counts the bars of half days with it.
+
+use std::collections::BTreeMap;
+
+use crate::client::FutuClient;
+use crate::dates::days_from_civil;
+use crate::generated::qot_request_trade_date::TradeDate;
+use super::instrument::{market_sessions, TradingSession};
+use super::subscribe::QuoteError;
+
+const US_EARLY_CLOSE: [TradingSession; 1] = [TradingSession { open: "09:30", close: "13:00" }];
+const AU_EARLY_CLOSE: [TradingSession; 1] = [TradingSession { open: "10:00", close: "14:10" }];
+
+/// Qot_Common.TradeDateType: which sessions a trading day holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeDateType {
+    Whole,
+    Morning,
+    Afternoon,
+}
+
+impl TradeDateType {
+    pub fn from_proto(value: i32) -> Option<Self> {
+        match value {
+            0 => Some(TradeDateType::Whole),
+            1 => Some(TradeDateType::Morning),
+            2 => Some(TradeDateType::Afternoon),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TradeDateType::Whole => "whole",
+            TradeDateType::Morning => "morning",
+            TradeDateType::Afternoon => "afternoon",
+        }
+    }
+}
+
+/// Sessions of a QotMarket on a day of `date_type`, in exchange local time.
+/// Empty for unknown markets.
+pub fn day_sessions(market: i32, date_type: TradeDateType) -> &'static [TradingSession] {
+    let (_, sessions) = market_sessions(market);
+    match (date_type, market) {
+        (TradeDateType::Whole, _) => sessions,
+        (TradeDateType::Morning, 11) => &US_EARLY_CLOSE,
+        (TradeDateType::Morning, 51) => &AU_EARLY_CLOSE,
+        (TradeDateType::Morning, _) => sessions.get(..1).unwrap_or_default(),
+        (TradeDateType::Afternoon, _) => sessions.get(sessions.len().saturating_sub(1)..).unwrap_or_default(),
+    }
+}
+
+/// TradeDateMarket of a QotMarket, for Qot_RequestTradeDate.
+pub(crate) fn trade_date_market(market: i32) -> Option<i32> {
+    match market {
+        1 => Some(1),       // HK
+        11 => Some(2),      // US
+        21 | 22 => Some(3), // CN
+        _ => None,
+    }
+}
+
+/// Trading days of a market with their sessions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionCalendar {
+    market: i32,
+    /// Trading days (days since 1970-01-01) with their type, or None to
+    /// assume every weekday is a whole trading day.
+    trading_days: Option<BTreeMap<i64, TradeDateType>>,
+}
+
+impl SessionCalendar {
+    /// Weekday calendar of a QotMarket.
+    pub fn for_market(market: i32) -> Self {
+        Self { market, trading_days: None }
+    }
+
+    /// Use the trading days of a Qot_RequestTradeDate response instead of
+    /// assuming weekdays. Days of an unknown type count as whole days.
+    pub fn with_trade_dates(mut self, dates: &[TradeDate]) -> Self {
+        let days = dates
+            .iter()
+            .filter_map(|d| {
+                let date_type = d.trade_date_type.and_then(TradeDateType::from_proto);
+                Some((parse_day(&d.time)?, date_type.unwrap_or(TradeDateType::Whole)))
+            })
+            .collect();
+        self.trading_days = Some(days);
+        self
+    }
+
+    pub fn market(&self) -> i32 {
+        self.market
+    }
+
+    /// IANA time zone of the session times.
+    pub fn timezone(&self) -> Option<&'static str> {
+        market_sessions(self.market).0
+    }
+
+    /// Whether trading days come from the exchange rather than weekdays.
+    pub fn has_trade_dates(&self) -> bool {
+        self.trading_days.is_some()
+    }
+
+    /// Type of `day` (days since 1970-01-01), None if it is not a trading day.
+    pub fn trade_date_type_on(&self, day: i64) -> Option<TradeDateType> {
+        match &self.trading_days {
+            Some(days) => days.get(&day).copied(),
+            // 1970-01-01 was a Thursday
+            None => ((day + 3).rem_euclid(7) < 5).then_some(TradeDateType::Whole),
+        }
+    }
+
+    /// Sessions on `day`; empty if it is not a trading day.
+    pub fn sessions_on(&self, day: i64) -> &'static [TradingSession] {
+        self.trade_date_type_on(day).map_or(&[], |t| day_sessions(self.market, t))
+    }
+
+    /// Sessions on `date` (`YYYY-MM-DD`); empty if it is not a trading day
+    /// or cannot be parsed.
+    pub fn session_times(&self, date: &str) -> &'static [TradingSession] {
+        parse_day(date).map_or(&[], |day| self.sessions_on(day))
+    }
+}
+
+/// Sessions of one day of a market.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DaySessions {
+    pub date: String,
+    /// None if the day is not a trading day.
+    pub trade_date_type: Option<TradeDateType>,
+    pub timezone: Option<&'static str>,
+    pub sessions: Vec<TradingSession>,
+    /// Whether the day was looked up in the exchange calendar rather than
+    /// assumed from the weekday.
+    pub exchange_calendar: bool,
+}
+
+/// Sessions of QotMarket `market` on `date` (`YYYY-MM-DD`). Markets
+/// Qot_RequestTradeDate does not cover assume whole weekdays.
+pub async fn session_times(client: &FutuClient, market: i32, date: &str) -> Result<DaySessions, QuoteError> {
+    let mut calendar = SessionCalendar::for_market(market);
+    if let Some(trade_date_market) = trade_date_market(market) {
+        let resp = super::snapshot::request_trade_date(
+            client, trade_date_market, date.to_string(), date.to_string(), None,
+        ).await?;
+        calendar = calendar.with_trade_dates(&resp.s2c.map(|s| s.trade_date_list).unwrap_or_default());
+    }
+    let trade_date_type = parse_day(date).and_then(|day| calendar.trade_date_type_on(day));
+    Ok(DaySessions {
+        date: date.to_string(),
+        trade_date_type,
+        timezone: calendar.timezone(),
+        sessions: calendar.session_times(date).to_vec(),
+        exchange_calendar: calendar.has_trade_dates(),
+    })
+}
+
+/// Days since 1970-01-01 of a `YYYY-MM-DD[ ...]` date.
+fn parse_day(date: &str) -> Option<i64> {
+    let date = date.trim().get(..10)?;
+    let mut parts = date.split('-').map(|p| p.parse::<i64>().ok());
+    let (y, m, d) = (parts.next()??, parts.next()??, parts.next()??);
+    if !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return None;
+    }
+    Some(days_from_civil(y, m, d))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn times(sessions: &[TradingSession]) -> Vec<(&str, &str)> {
+        sessions.iter().map(|s| (s.open, s.close)).collect()
+    }
+
+    fn trade_date(time: &str, trade_date_type: i32) -> TradeDate {
+        TradeDate { time: time.to_string(), timestamp: None, trade_date_type: Some(trade_date_type) }
+    }
+
+    #[test]
+    fn test_half_day_sessions() {
+        assert_eq!(times(day_sessions(1, TradeDateType::Whole)), vec![("09:30", "12:00"), ("13:00", "16:00")]);
+        assert_eq!(times(day_sessions(1, TradeDateType::Morning)), vec![("09:30", "12:00")]);
+        assert_eq!(times(day_sessions(21, TradeDateType::Afternoon)), vec![("13:00", "15:00")]);
+        assert_eq!(times(day_sessions(11, TradeDateType::Morning)), vec![("09:30", "13:00")]);
+        assert_eq!(times(day_sessions(11, TradeDateType::Afternoon)), vec![("09:30", "16:00")]);
+        assert!(day_sessions(0, TradeDateType::Morning).is_empty());
+        assert_eq!(TradeDateType::from_proto(2), Some(TradeDateType::Afternoon));
+        assert_eq!(TradeDateType::from_proto(7), None);
+    }
+
+    #[test]
+    fn test_calendar_session_times() {
+        // 2024-12-24 is a Tuesday, 2024-12-28 a Saturday
+        let weekdays = SessionCalendar::for_market(1);
+        assert_eq!(weekdays.session_times("2024-12-24").len(), 2);
+        assert!(weekdays.session_times("2024-12-28").is_empty());
+        assert!(weekdays.session_times("bad").is_empty());
+        assert_eq!(weekdays.timezone(), Some("Asia/Hong_Kong"));
+
+        let calendar = SessionCalendar::for_market(1)
+            .with_trade_dates(&[trade_date("2024-12-23", 0), trade_date("2024-12-24", 1), trade_date("2024-12-27", 0)]);
+        assert_eq!(times(calendar.session_times("2024-12-24")), vec![("09:30", "12:00")]);
+        // Christmas holidays
+        assert!(calendar.session_times("2024-12-25").is_empty());
+        assert_eq!(calendar.session_times("2024-12-27 00:00:00").len(), 2);
+    }
+}
@@ -12,6 +12,7 @@ const PROTO_QOT_GET_PLATE_SECURITY: u32 = 3205;
 const PROTO_QOT_GET_SUB_INFO: u32 = 3003;
 const PROTO_QOT_GET_RT: u32 = 3008;
 const PROTO_QOT_GET_BROKER: u32 = 3014;
+const PROTO_QOT_GET_ORDER_DETAIL: u32 = 3016;
 const PROTO_QOT_REQUEST_REHAB: u32 = 3105;
 const PROTO_QOT_GET_SUSPEND: u32 = 3201;
 const PROTO_QOT_GET_PLATE_SET: u32 = 3204;
@@ -27,6 +28,7 @@ const PROTO_QOT_GET_CODE_CHANGE: u32 = 3216;
 const PROTO_QOT_GET_IPO_LIST: u32 = 3217;
 const PROTO_QOT_GET_FUTURE_INFO: u32 = 3218;
 const PROTO_QOT_REQUEST_TRADE_DATE: u32 = 3219;
+const PROTO_QOT_GET_MARKET_STATE: u32 = 3223;
 const PROTO_QOT_GET_OPTION_EXPIRATION_DATE: u32 = 3224;
 
 /// Get basic quote data for securities.
@@ -46,14 +48,11 @@ pub async fn get_basic_qot(
     let resp = client.request(PROTO_QOT_GET_BASIC_QOT, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_basic_qot::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_basic_qot::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -79,14 +78,11 @@ pub async fn get_static_info(
     let resp = client.request(PROTO_QOT_GET_STATIC_INFO, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_static_info::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_static_info::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -109,26 +105,91 @@ pub async fn get_security_snapshot(
     let resp = client.request(PROTO_QOT_GET_SECURITY_SNAPSHOT, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_security_snapshot::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_security_snapshot::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
 }
 
+/// A chunk of a bulk request that failed, and why.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotChunkError {
+    pub securities: Vec<(i32, String)>,
+    pub error: String,
+}
+
+/// Snapshots of the chunks that succeeded and the errors of the others.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SnapshotBulk {
+    pub snapshots: Vec<crate::generated::qot_get_security_snapshot::Snapshot>,
+    pub errors: Vec<SnapshotChunkError>,
+}
+
+impl SnapshotBulk {
+    /// Securities of the failed chunks; pass them to
+    /// `get_security_snapshot_bulk` again to retry only those.
+    pub fn resume(&self) -> Vec<(i32, String)> {
+        self.errors.iter().flat_map(|e| e.securities.iter().cloned()).collect()
+    }
+}
+
+/// Snapshots of any number of securities, requested `chunk_size` at a time
+/// (at most 400). A failed chunk does not fail the others; it is reported
+/// in `errors`.
+pub async fn get_security_snapshot_bulk(
+    client: &FutuClient,
+    securities: Vec<(i32, String)>,
+    chunk_size: usize,
+) -> SnapshotBulk {
+    let chunk_size = chunk_size.clamp(1, super::poller::SNAPSHOT_MAX_SECURITIES);
+    let mut bulk = SnapshotBulk::default();
+    for chunk in securities.chunks(chunk_size) {
+        match get_security_snapshot(client, chunk.to_vec()).await {
+            Ok(resp) => bulk.snapshots.extend(resp.s2c.map(|s| s.snapshot_list).unwrap_or_default()),
+            Err(e) => {
+                tracing::warn!("Snapshot of {} securities failed: {}", chunk.len(), e);
+                bulk.errors.push(SnapshotChunkError { securities: chunk.to_vec(), error: e.to_string() });
+            }
+        }
+    }
+    bulk
+}
+
+/// Snapshots of any number of securities, in input order. The list is split
+/// into requests of at most 400 securities, of which up to `parallelism`
+/// are in flight at once. Unlike `get_security_snapshot_bulk`, the first
+/// failed request fails the call.
+pub async fn get_security_snapshot_all(
+    client: &FutuClient,
+    securities: Vec<(i32, String)>,
+    parallelism: usize,
+) -> Result<Vec<crate::generated::qot_get_security_snapshot::Snapshot>, QuoteError> {
+    use futures::stream::{self, StreamExt, TryStreamExt};
+
+    let chunks: Vec<Vec<(i32, String)>> = securities
+        .chunks(super::poller::SNAPSHOT_MAX_SECURITIES)
+        .map(<[_]>::to_vec)
+        .collect();
+    let responses: Vec<_> = stream::iter(chunks)
+        .map(|chunk| get_security_snapshot(client, chunk))
+        .buffered(parallelism.max(1))
+        .try_collect()
+        .await?;
+    Ok(responses.into_iter().flat_map(|resp| resp.s2c.map(|s| s.snapshot_list).unwrap_or_default()).collect())
+}
+
 /// Get order book for a single security.
 pub async fn get_order_book(
     client: &FutuClient,
-    market: i32,
+    market: impl Into<i32>,
     code: String,
     num: i32,
 ) -> Result<crate::generated::qot_get_order_book::Response, QuoteError> {
+    let market = market.into();
     let security = crate::generated::qot_common::Security { market, code };
     let c2s = crate::generated::qot_get_order_book::C2s { security, num };
     let request = crate::generated::qot_get_order_book::Request { c2s };
@@ -137,26 +198,34 @@ pub async fn get_order_book(
     let resp = client.request(PROTO_QOT_GET_ORDER_BOOK, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_order_book::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_order_book::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
 }
 
+/// Levels of an order book: the longer of its two sides. OpenD sends fewer
+/// levels than requested when the quote right of the market does not cover
+/// them, so this is the depth a caller actually has.
+pub fn book_depth(
+    asks: &[crate::generated::qot_common::OrderBook],
+    bids: &[crate::generated::qot_common::OrderBook],
+) -> usize {
+    asks.len().max(bids.len())
+}
+
 /// Get ticker (trade ticks) for a single security.
 pub async fn get_ticker(
     client: &FutuClient,
-    market: i32,
+    market: impl Into<i32>,
     code: String,
     max_ret_num: i32,
 ) -> Result<crate::generated::qot_get_ticker::Response, QuoteError> {
+    let market = market.into();
     let security = crate::generated::qot_common::Security { market, code };
     let c2s = crate::generated::qot_get_ticker::C2s { security, max_ret_num };
     let request = crate::generated::qot_get_ticker::Request { c2s };
@@ -165,14 +234,11 @@ pub async fn get_ticker(
     let resp = client.request(PROTO_QOT_GET_TICKER, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_ticker::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_ticker::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -184,12 +250,13 @@ pub async fn stock_filter(
     client: &FutuClient,
     begin: i32,
     num: i32,
-    market: i32,
+    market: impl Into<i32>,
     plate: Option<(i32, String)>,
     base_filters: Vec<crate::generated::qot_stock_filter::BaseFilter>,
     accumulate_filters: Vec<crate::generated::qot_stock_filter::AccumulateFilter>,
     financial_filters: Vec<crate::generated::qot_stock_filter::FinancialFilter>,
 ) -> Result<crate::generated::qot_stock_filter::Response, QuoteError> {
+    let market = market.into();
     let plate = plate.map(|(m, c)| crate::generated::qot_common::Security { market: m, code: c });
 
     let c2s = crate::generated::qot_stock_filter::C2s {
@@ -207,14 +274,11 @@ pub async fn stock_filter(
     let resp = client.request(PROTO_QOT_STOCK_FILTER, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_stock_filter::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_stock_filter::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -223,11 +287,12 @@ pub async fn stock_filter(
 /// Get securities in a plate/sector (Qot_GetPlateSecurity, proto 3205).
 pub async fn get_plate_security(
     client: &FutuClient,
-    plate_market: i32,
+    plate_market: impl Into<i32>,
     plate_code: String,
     sort_field: Option<i32>,
     ascend: Option<bool>,
 ) -> Result<crate::generated::qot_get_plate_security::Response, QuoteError> {
+    let plate_market = plate_market.into();
     let plate = crate::generated::qot_common::Security { market: plate_market, code: plate_code };
     let c2s = crate::generated::qot_get_plate_security::C2s {
         plate,
@@ -240,14 +305,11 @@ pub async fn get_plate_security(
     let resp = client.request(PROTO_QOT_GET_PLATE_SECURITY, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_plate_security::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_plate_security::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -265,14 +327,11 @@ pub async fn get_sub_info(
     let resp = client.request(PROTO_QOT_GET_SUB_INFO, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_sub_info::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_sub_info::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -281,9 +340,10 @@ pub async fn get_sub_info(
 /// Get real-time (time-sharing) data for a single security.
 pub async fn get_rt(
     client: &FutuClient,
-    market: i32,
+    market: impl Into<i32>,
     code: String,
 ) -> Result<crate::generated::qot_get_rt::Response, QuoteError> {
+    let market = market.into();
     let security = crate::generated::qot_common::Security { market, code };
     let c2s = crate::generated::qot_get_rt::C2s { security };
     let request = crate::generated::qot_get_rt::Request { c2s };
@@ -292,14 +352,11 @@ pub async fn get_rt(
     let resp = client.request(PROTO_QOT_GET_RT, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_rt::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_rt::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -308,9 +365,10 @@ pub async fn get_rt(
 /// Get broker queue for a single security.
 pub async fn get_broker(
     client: &FutuClient,
-    market: i32,
+    market: impl Into<i32>,
     code: String,
 ) -> Result<crate::generated::qot_get_broker::Response, QuoteError> {
+    let market = market.into();
     let security = crate::generated::qot_common::Security { market, code };
     let c2s = crate::generated::qot_get_broker::C2s { security };
     let request = crate::generated::qot_get_broker::Request { c2s };
@@ -319,14 +377,38 @@ pub async fn get_broker(
     let resp = client.request(PROTO_QOT_GET_BROKER, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_broker::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_broker::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
+    }
+
+    Ok(response)
+}
+
+/// Get the order queue detail (order count and the volume of each queued
+/// order, up to 50) of the best ask and bid of a security. Needs an
+/// ORDER_DETAIL subscription and level 2 quote rights for its market.
+pub async fn get_order_detail(
+    client: &FutuClient,
+    market: impl Into<i32>,
+    code: String,
+) -> Result<crate::generated::qot_get_order_detail::Response, QuoteError> {
+    let market = market.into();
+    let security = crate::generated::qot_common::Security { market, code };
+    let c2s = crate::generated::qot_get_order_detail::C2s { security };
+    let request = crate::generated::qot_get_order_detail::Request { c2s };
+    let body = request.encode_to_vec();
+
+    let resp = client.request(PROTO_QOT_GET_ORDER_DETAIL, &body).await
+        .map_err(QuoteError::Connection)?;
+
+    let response = client.decode::<crate::generated::qot_get_order_detail::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
+
+    if response.ret_type != 0 {
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -349,14 +431,11 @@ pub async fn get_rehab(
     let resp = client.request(PROTO_QOT_REQUEST_REHAB, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_rehab::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_rehab::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -385,14 +464,40 @@ pub async fn get_suspend(
     let resp = client.request(PROTO_QOT_GET_SUSPEND, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_suspend::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_suspend::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
+    }
+
+    Ok(response)
+}
+
+/// Get the market state (Qot_Common.QotMarketState) of each security.
+/// Securities of one market can be in different sessions, e.g. US stocks
+/// with pre-market trading while others wait for the open.
+pub async fn get_market_state(
+    client: &FutuClient,
+    securities: Vec<(i32, String)>,
+) -> Result<crate::generated::qot_get_market_state::Response, QuoteError> {
+    let security_list: Vec<crate::generated::qot_common::Security> = securities
+        .into_iter()
+        .map(|(market, code)| crate::generated::qot_common::Security { market, code })
+        .collect();
+
+    let c2s = crate::generated::qot_get_market_state::C2s { security_list };
+    let request = crate::generated::qot_get_market_state::Request { c2s };
+    let body = request.encode_to_vec();
+
+    let resp = client.request(PROTO_QOT_GET_MARKET_STATE, &body).await
+        .map_err(QuoteError::Connection)?;
+
+    let response = client.decode::<crate::generated::qot_get_market_state::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
+
+    if response.ret_type != 0 {
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -401,9 +506,11 @@ pub async fn get_suspend(
 /// Get plate set (sector list) for a market.
 pub async fn get_plate_set(
     client: &FutuClient,
-    market: i32,
-    plate_set_type: i32,
+    market: impl Into<i32>,
+    plate_set_type: impl Into<i32>,
 ) -> Result<crate::generated::qot_get_plate_set::Response, QuoteError> {
+    let market = market.into();
+    let plate_set_type = plate_set_type.into();
     let c2s = crate::generated::qot_get_plate_set::C2s { market, plate_set_type };
     let request = crate::generated::qot_get_plate_set::Request { c2s };
     let body = request.encode_to_vec();
@@ -411,14 +518,11 @@ pub async fn get_plate_set(
     let resp = client.request(PROTO_QOT_GET_PLATE_SET, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_plate_set::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_plate_set::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -427,10 +531,11 @@ pub async fn get_plate_set(
 /// Get reference data (related securities) for a single security.
 pub async fn get_reference(
     client: &FutuClient,
-    market: i32,
+    market: impl Into<i32>,
     code: String,
     reference_type: i32,
 ) -> Result<crate::generated::qot_get_reference::Response, QuoteError> {
+    let market = market.into();
     let security = crate::generated::qot_common::Security { market, code };
     let c2s = crate::generated::qot_get_reference::C2s { security, reference_type };
     let request = crate::generated::qot_get_reference::Request { c2s };
@@ -439,14 +544,11 @@ pub async fn get_reference(
     let resp = client.request(PROTO_QOT_GET_REFERENCE, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_reference::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_reference::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -469,14 +571,11 @@ pub async fn get_owner_plate(
     let resp = client.request(PROTO_QOT_GET_OWNER_PLATE, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_owner_plate::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_owner_plate::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -486,7 +585,7 @@ pub async fn get_owner_plate(
 #[allow(clippy::too_many_arguments)]
 pub async fn get_option_chain(
     client: &FutuClient,
-    owner_market: i32,
+    owner_market: impl Into<i32>,
     owner_code: String,
     begin_time: String,
     end_time: String,
@@ -495,6 +594,7 @@ pub async fn get_option_chain(
     index_option_type: Option<i32>,
     data_filter: Option<crate::generated::qot_get_option_chain::DataFilter>,
 ) -> Result<crate::generated::qot_get_option_chain::Response, QuoteError> {
+    let owner_market = owner_market.into();
     let owner = crate::generated::qot_common::Security { market: owner_market, code: owner_code };
     let c2s = crate::generated::qot_get_option_chain::C2s {
         owner,
@@ -511,14 +611,11 @@ pub async fn get_option_chain(
     let resp = client.request(PROTO_QOT_GET_OPTION_CHAIN, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_option_chain::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_option_chain::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -554,14 +651,11 @@ pub async fn get_warrant(
     let resp = client.request(PROTO_QOT_GET_WARRANT, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_warrant::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_warrant::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -570,10 +664,11 @@ pub async fn get_warrant(
 /// Get capital flow for a single security.
 pub async fn get_capital_flow(
     client: &FutuClient,
-    market: i32,
+    market: impl Into<i32>,
     code: String,
     period_type: Option<i32>,
 ) -> Result<crate::generated::qot_get_capital_flow::Response, QuoteError> {
+    let market = market.into();
     let security = crate::generated::qot_common::Security { market, code };
     let c2s = crate::generated::qot_get_capital_flow::C2s {
         security,
@@ -586,14 +681,11 @@ pub async fn get_capital_flow(
     let resp = client.request(PROTO_QOT_GET_CAPITAL_FLOW, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_capital_flow::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_capital_flow::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -602,9 +694,10 @@ pub async fn get_capital_flow(
 /// Get capital distribution for a single security.
 pub async fn get_capital_distribution(
     client: &FutuClient,
-    market: i32,
+    market: impl Into<i32>,
     code: String,
 ) -> Result<crate::generated::qot_get_capital_distribution::Response, QuoteError> {
+    let market = market.into();
     let security = crate::generated::qot_common::Security { market, code };
     let c2s = crate::generated::qot_get_capital_distribution::C2s { security };
     let request = crate::generated::qot_get_capital_distribution::Request { c2s };
@@ -613,14 +706,11 @@ pub async fn get_capital_distribution(
     let resp = client.request(PROTO_QOT_GET_CAPITAL_DISTRIBUTION, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_capital_distribution::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_capital_distribution::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -638,14 +728,11 @@ pub async fn get_user_security(
     let resp = client.request(PROTO_QOT_GET_USER_SECURITY, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_user_security::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_user_security::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -674,14 +761,11 @@ pub async fn modify_user_security(
     let resp = client.request(PROTO_QOT_MODIFY_USER_SECURITY, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_modify_user_security::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_modify_user_security::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -710,14 +794,11 @@ pub async fn get_code_change(
     let resp = client.request(PROTO_QOT_GET_CODE_CHANGE, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_code_change::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_code_change::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -726,8 +807,9 @@ pub async fn get_code_change(
 /// Get IPO list for a market.
 pub async fn get_ipo_list(
     client: &FutuClient,
-    market: i32,
+    market: impl Into<i32>,
 ) -> Result<crate::generated::qot_get_ipo_list::Response, QuoteError> {
+    let market = market.into();
     let c2s = crate::generated::qot_get_ipo_list::C2s { market };
     let request = crate::generated::qot_get_ipo_list::Request { c2s };
     let body = request.encode_to_vec();
@@ -735,14 +817,11 @@ pub async fn get_ipo_list(
     let resp = client.request(PROTO_QOT_GET_IPO_LIST, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_ipo_list::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_ipo_list::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -765,14 +844,11 @@ pub async fn get_future_info(
     let resp = client.request(PROTO_QOT_GET_FUTURE_INFO, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_future_info::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_future_info::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -781,11 +857,12 @@ pub async fn get_future_info(
 /// Request trade dates for a market.
 pub async fn request_trade_date(
     client: &FutuClient,
-    market: i32,
+    market: impl Into<i32>,
     begin_time: String,
     end_time: String,
     security: Option<(i32, String)>,
 ) -> Result<crate::generated::qot_request_trade_date::Response, QuoteError> {
+    let market = market.into();
     let security = security.map(|(m, c)| crate::generated::qot_common::Security { market: m, code: c });
 
     let c2s = crate::generated::qot_request_trade_date::C2s {
@@ -800,14 +877,11 @@ pub async fn request_trade_date(
     let resp = client.request(PROTO_QOT_REQUEST_TRADE_DATE, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_request_trade_date::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_request_trade_date::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -816,10 +890,11 @@ pub async fn request_trade_date(
 /// Get option expiration dates for an underlying security.
 pub async fn get_option_expiration_date(
     client: &FutuClient,
-    owner_market: i32,
+    owner_market: impl Into<i32>,
     owner_code: String,
     index_option_type: Option<i32>,
 ) -> Result<crate::generated::qot_get_option_expiration_date::Response, QuoteError> {
+    let owner_market = owner_market.into();
     let owner = crate::generated::qot_common::Security { market: owner_market, code: owner_code };
     let c2s = crate::generated::qot_get_option_expiration_date::C2s {
         owner,
@@ -831,14 +906,11 @@ pub async fn get_option_expiration_date(
     let resp = client.request(PROTO_QOT_GET_OPTION_EXPIRATION_DATE, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_get_option_expiration_date::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_get_option_expiration_date::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -1015,6 +1087,44 @@ mod tests {
         assert!(decoded.s2c.is_none());
     }
 
+    #[test]
+    fn test_order_detail_response_success() {
+        assert_eq!(super::PROTO_QOT_GET_ORDER_DETAIL, 3016);
+        let response = crate::generated::qot_get_order_detail::Response {
+            ret_type: 0,
+            ret_msg: None,
+            err_code: None,
+            s2c: Some(crate::generated::qot_get_order_detail::S2c {
+                security: crate::generated::qot_common::Security {
+                    market: 1,
+                    code: "00700".to_string(),
+                },
+                order_detail_ask: crate::generated::qot_common::OrderDetail {
+                    order_count: 2,
+                    order_vol: vec![300.0, 200.0],
+                },
+                order_detail_bid: crate::generated::qot_common::OrderDetail {
+                    order_count: 1,
+                    order_vol: vec![1000.0],
+                },
+                name: None,
+            }),
+        };
+        let encoded = response.encode_to_vec();
+        let decoded = crate::generated::qot_get_order_detail::Response::decode(encoded.as_slice()).unwrap();
+        let s2c = decoded.s2c.unwrap();
+        assert_eq!(s2c.order_detail_ask.order_count, 2);
+        assert_eq!(s2c.order_detail_ask.order_vol, vec![300.0, 200.0]);
+        assert_eq!(s2c.order_detail_bid.order_vol, vec![1000.0]);
+    }
+
+    #[test]
+    fn test_book_depth() {
+        let level = |price: f64| crate::generated::qot_common::OrderBook { price, volume: 100, ..Default::default() };
+        assert_eq!(super::book_depth(&[level(346.0)], &[level(345.0), level(344.8)]), 2);
+        assert_eq!(super::book_depth(&[], &[]), 0);
+    }
+
     #[test]
     fn test_ticker_request_encode_decode() {
         let security = crate::generated::qot_common::Security {
@@ -1208,4 +1318,80 @@ mod tests {
         assert_eq!(s2c.static_info_list[0].basic.security.code, "00700");
         assert_eq!(s2c.static_info_list[0].basic.name, "TENCENT");
     }
+
+    #[test]
+    fn test_snapshot_bulk_resume() {
+        let sec = |code: &str| (1, code.to_string());
+        let bulk = super::SnapshotBulk {
+            snapshots: Vec::new(),
+            errors: vec![
+                super::SnapshotChunkError { securities: vec![sec("00700"), sec("09988")], error: "timeout".to_string() },
+                super::SnapshotChunkError { securities: vec![sec("00005")], error: "busy".to_string() },
+            ],
+        };
+        assert_eq!(bulk.resume(), vec![sec("00700"), sec("09988"), sec("00005")]);
+        assert!(super::SnapshotBulk::default().resume().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_all_chunks_concurrently() {
+        use crate::client::mock::MockOpenD;
+        use crate::generated::qot_get_security_snapshot::{Response, S2c, Snapshot};
+
+        let mock = MockOpenD::start().await.unwrap();
+        let config = crate::config::FutuConfig { port: mock.port(), enable_encryption: false, ..Default::default() };
+        let mut client = crate::client::FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+
+        // The mock answers every chunk with the same single snapshot
+        let canned = Response {
+            ret_type: 0,
+            s2c: Some(S2c { snapshot_list: vec![Snapshot::default()] }),
+            ..Default::default()
+        };
+        mock.respond(PROTO_QOT_GET_SECURITY_SNAPSHOT, canned.encode_to_vec());
+        let securities: Vec<(i32, String)> = (0..900).map(|i| (1, format!("{:05}", i))).collect();
+        let snapshots = super::get_security_snapshot_all(&client, securities.clone(), 2).await.unwrap();
+        assert_eq!(snapshots.len(), 3);
+        assert_eq!(client.stats().requests[&PROTO_QOT_GET_SECURITY_SNAPSHOT].count, 3);
+        assert!(super::get_security_snapshot_all(&client, Vec::new(), 2).await.unwrap().is_empty());
+
+        let failed = Response { ret_type: -1, ret_msg: Some("unknown stock".to_string()), ..Default::default() };
+        mock.respond(PROTO_QOT_GET_SECURITY_SNAPSHOT, failed.encode_to_vec());
+        assert!(super::get_security_snapshot_all(&client, securities, 0).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_get_market_state() {
+        use crate::client::mock::MockOpenD;
+        use crate::generated::qot_common::{QotMarketState, Security};
+        use crate::generated::qot_get_market_state::{MarketInfo, Response, S2c};
+
+        let mock = MockOpenD::start().await.unwrap();
+        let config = crate::config::FutuConfig { port: mock.port(), enable_encryption: false, ..Default::default() };
+        let mut client = crate::client::FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+
+        let info = |market: i32, code: &str, state: QotMarketState| MarketInfo {
+            security: Security { market, code: code.to_string() },
+            name: String::new(),
+            market_state: state as i32,
+        };
+        let canned = Response {
+            ret_type: 0,
+            s2c: Some(S2c {
+                market_info_list: vec![info(11, "AAPL", QotMarketState::PreMarketBegin), info(1, "00700", QotMarketState::Closed)],
+            }),
+            ..Default::default()
+        };
+        mock.respond(super::PROTO_QOT_GET_MARKET_STATE, canned.encode_to_vec());
+        let securities = vec![(11, "AAPL".to_string()), (1, "00700".to_string())];
+        let s2c = super::get_market_state(&client, securities.clone()).await.unwrap().s2c.unwrap();
+        let states: Vec<_> = s2c.market_info_list.iter().map(|m| (m.security.code.as_str(), m.market_state)).collect();
+        assert_eq!(states, vec![("AAPL", QotMarketState::PreMarketBegin as i32), ("00700", QotMarketState::Closed as i32)]);
+
+        let failed = Response { ret_type: -1, ret_msg: Some("unknown stock".to_string()), ..Default::default() };
+        mock.respond(super::PROTO_QOT_GET_MARKET_STATE, failed.encode_to_vec());
+        assert!(super::get_market_state(&client, securities).await.is_err());
+    }
 }
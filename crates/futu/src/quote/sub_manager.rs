@@ -0,0 +1,420 @@
+//! Local record of the quote subscriptions of a client.
+//!
+//! `subscribe` records every (security, sub type) pair OpenD accepted and
+//! forgets the ones it unsubscribed, so `current_subscriptions` answers
+//! without a Qot_GetSubInfo request. OpenD charges one unit of subscription
+//! quota per pair; with a quota set, `subscribe` refuses requests that would
+//! exceed it before sending them. After a reconnect the client subscribes
+//! the recorded pairs again, order book detail included.
+//!
+//! Pairs subscribed with `subscribe_with_ttl` expire unless renewed, and
+//! `run_expiry_watcher` unsubscribes them once expired, so a process that
+//! subscribes per signal does not hold on to quota it no longer uses.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use parking_lot::Mutex;
+use tokio::sync::mpsc;
+
+use crate::client::FutuClient;
+use super::subscribe::{QuoteError, SubOptions, SubResult};
+
+type SecurityKey = (i32, String);
+
+// SubType value
+const SUB_TYPE_ORDER_BOOK: i32 = 2;
+
+/// Current subscription of one security.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subscription {
+    pub security: SecurityKey,
+    /// SubType values, ascending.
+    pub sub_types: Vec<i32>,
+    /// Whether the order book was subscribed with per-order detail.
+    pub order_book_detail: bool,
+}
+
+type Pair = (SecurityKey, i32);
+
+#[derive(Debug, Default)]
+struct State {
+    pairs: BTreeMap<SecurityKey, BTreeSet<i32>>,
+    order_book_detail: BTreeSet<SecurityKey>,
+    /// Expiry of pairs subscribed with a TTL.
+    expiries: BTreeMap<Pair, Instant>,
+    quota: Option<u32>,
+}
+
+/// Subscriptions made through `subscribe`, and the quota they may use.
+#[derive(Debug, Default)]
+pub struct SubscriptionManager {
+    state: Mutex<State>,
+}
+
+impl SubscriptionManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit the pairs `subscribe` may hold; None does not check locally.
+    pub fn set_quota(&self, quota: Option<u32>) {
+        self.state.lock().quota = quota;
+    }
+
+    pub fn quota(&self) -> Option<u32> {
+        self.state.lock().quota
+    }
+
+    /// Subscribed (security, sub type) pairs, i.e. the quota in use.
+    pub fn used_quota(&self) -> u32 {
+        self.state.lock().pairs.values().map(|t| t.len() as u32).sum()
+    }
+
+    /// Quota left, if a quota is set.
+    pub fn remaining_quota(&self) -> Option<u32> {
+        self.quota().map(|q| q.saturating_sub(self.used_quota()))
+    }
+
+    /// Current subscriptions, ordered by security.
+    pub fn current_subscriptions(&self) -> Vec<Subscription> {
+        let state = self.state.lock();
+        state
+            .pairs
+            .iter()
+            .map(|(security, sub_types)| Subscription {
+                security: security.clone(),
+                sub_types: sub_types.iter().copied().collect(),
+                order_book_detail: state.order_book_detail.contains(security),
+            })
+            .collect()
+    }
+
+    /// Whether `security` is subscribed to `sub_type`.
+    pub fn is_subscribed(&self, security: &SecurityKey, sub_type: i32) -> bool {
+        self.state.lock().pairs.get(security).is_some_and(|t| t.contains(&sub_type))
+    }
+
+    /// SubType values `security` is subscribed to, ascending.
+    pub fn sub_types(&self, security: &SecurityKey) -> Vec<i32> {
+        self.state.lock().pairs.get(security).map(|t| t.iter().copied().collect()).unwrap_or_default()
+    }
+
+    /// `Err(QuotaExceeded)` if subscribing `securities` to `sub_types` would
+    /// take more than the quota left. Pairs already subscribed are free.
+    pub fn check_quota(&self, securities: &[SecurityKey], sub_types: &[i32]) -> Result<(), QuoteError> {
+        let state = self.state.lock();
+        let Some(quota) = state.quota else { return Ok(()) };
+        let used: u32 = state.pairs.values().map(|t| t.len() as u32).sum();
+        let mut new_pairs = BTreeSet::new();
+        for security in securities {
+            for sub_type in sub_types {
+                if !state.pairs.get(security).is_some_and(|t| t.contains(sub_type)) {
+                    new_pairs.insert((security, *sub_type));
+                }
+            }
+        }
+        let needed = new_pairs.len() as u32;
+        if used + needed > quota {
+            return Err(QuoteError::QuotaExceeded { needed, remaining: quota.saturating_sub(used) });
+        }
+        Ok(())
+    }
+
+    /// Record a successful subscription. The pairs no longer expire.
+    pub fn record_sub(&self, securities: &[SecurityKey], sub_types: &[i32], options: &SubOptions) {
+        let mut state = self.state.lock();
+        for security in securities {
+            state.pairs.entry(security.clone()).or_default().extend(sub_types.iter().copied());
+            for &sub_type in sub_types {
+                state.expiries.remove(&(security.clone(), sub_type));
+            }
+            if options.order_book_detail && sub_types.contains(&SUB_TYPE_ORDER_BOOK) {
+                state.order_book_detail.insert(security.clone());
+            }
+        }
+    }
+
+    /// Record a successful unsubscription.
+    pub fn record_unsub(&self, securities: &[SecurityKey], sub_types: &[i32]) {
+        let mut state = self.state.lock();
+        for security in securities {
+            for &sub_type in sub_types {
+                state.expiries.remove(&(security.clone(), sub_type));
+            }
+            if let Some(types) = state.pairs.get_mut(security) {
+                types.retain(|t| !sub_types.contains(t));
+                if types.is_empty() {
+                    state.pairs.remove(security);
+                }
+            }
+            if sub_types.contains(&SUB_TYPE_ORDER_BOOK) {
+                state.order_book_detail.remove(security);
+            }
+        }
+    }
+
+    /// Forget every subscription, e.g. after unsubscribing all.
+    pub fn clear(&self) {
+        let mut state = self.state.lock();
+        state.pairs.clear();
+        state.order_book_detail.clear();
+        state.expiries.clear();
+    }
+
+    /// Let the subscribed pairs of `securities` and `sub_types` expire at
+    /// `at`. Returns the number of pairs set.
+    pub fn set_expiry(&self, securities: &[SecurityKey], sub_types: &[i32], at: Instant) -> usize {
+        let mut state = self.state.lock();
+        let mut set = 0;
+        for security in securities {
+            for &sub_type in sub_types {
+                if state.pairs.get(security).is_some_and(|t| t.contains(&sub_type)) {
+                    state.expiries.insert((security.clone(), sub_type), at);
+                    set += 1;
+                }
+            }
+        }
+        set
+    }
+
+    /// Move the expiry of pairs that have one to `at`. Pairs subscribed
+    /// without a TTL keep not expiring. Returns the number of pairs renewed.
+    pub fn renew(&self, securities: &[SecurityKey], sub_types: &[i32], at: Instant) -> usize {
+        let mut state = self.state.lock();
+        let mut renewed = 0;
+        for security in securities {
+            for &sub_type in sub_types {
+                if let Some(expiry) = state.expiries.get_mut(&(security.clone(), sub_type)) {
+                    *expiry = at;
+                    renewed += 1;
+                }
+            }
+        }
+        renewed
+    }
+
+    /// When the pair expires, None if it does not.
+    pub fn expiry(&self, security: &SecurityKey, sub_type: i32) -> Option<Instant> {
+        self.state.lock().expiries.get(&(security.clone(), sub_type)).copied()
+    }
+
+    /// Pairs expired at `now`.
+    pub fn expired(&self, now: Instant) -> Vec<Pair> {
+        self.state.lock().expiries.iter().filter(|(_, at)| **at <= now).map(|(pair, _)| pair.clone()).collect()
+    }
+
+    /// The recorded pairs as Qot_Sub requests: securities per sub type and
+    /// order book detail flag.
+    pub(crate) fn replay_batches(&self) -> Vec<(i32, SubOptions, Vec<SecurityKey>)> {
+        let state = self.state.lock();
+        let mut batches: BTreeMap<(i32, bool), Vec<SecurityKey>> = BTreeMap::new();
+        for (security, sub_types) in &state.pairs {
+            for &sub_type in sub_types {
+                let detail = sub_type == SUB_TYPE_ORDER_BOOK && state.order_book_detail.contains(security);
+                batches.entry((sub_type, detail)).or_default().push(security.clone());
+            }
+        }
+        batches
+            .into_iter()
+            .map(|((sub_type, order_book_detail), securities)| {
+                (sub_type, SubOptions { order_book_detail, ..Default::default() }, securities)
+            })
+            .collect()
+    }
+}
+
+/// `subscribe` with `options`, letting the subscribed pairs expire after
+/// `ttl` unless renewed with `SubscriptionManager::renew`.
+pub async fn subscribe_with_ttl(
+    client: &FutuClient,
+    securities: Vec<SecurityKey>,
+    sub_types: Vec<i32>,
+    ttl: Duration,
+    options: &SubOptions,
+) -> Result<Vec<SubResult>, QuoteError> {
+    let results = super::subscribe::subscribe_with_options(client, securities, sub_types.clone(), true, options).await?;
+    let done: Vec<SecurityKey> = results.iter().filter(|r| r.is_ok()).map(|r| r.security.clone()).collect();
+    client.subscriptions().set_expiry(&done, &sub_types, client.clock().now() + ttl);
+    Ok(results)
+}
+
+/// An expired pair, and the outcome of unsubscribing it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubscriptionExpired {
+    pub security: SecurityKey,
+    pub sub_type: i32,
+    /// OpenD's reason if it refused to unsubscribe the pair, which is
+    /// retried on the next check; None if it was unsubscribed.
+    pub error: Option<String>,
+}
+
+/// Unsubscribe the expired pairs of `client` every `check_interval` and
+/// send an event for each. Returns when `tx` is closed.
+pub async fn run_expiry_watcher(
+    client: Arc<FutuClient>,
+    check_interval: Duration,
+    tx: mpsc::UnboundedSender<SubscriptionExpired>,
+) {
+    let clock = Arc::clone(client.clock());
+    loop {
+        clock.sleep(check_interval).await;
+        let mut by_sub_type: BTreeMap<i32, Vec<SecurityKey>> = BTreeMap::new();
+        for (security, sub_type) in client.subscriptions().expired(clock.now()) {
+            by_sub_type.entry(sub_type).or_default().push(security);
+        }
+        for (sub_type, securities) in by_sub_type {
+            match super::subscribe::subscribe(&client, securities, vec![sub_type], false).await {
+                Ok(results) => {
+                    for r in results {
+                        let event = SubscriptionExpired { security: r.security, sub_type, error: r.error };
+                        if tx.send(event).is_err() {
+                            tracing::debug!("Subscription expiry watcher stopped");
+                            return;
+                        }
+                    }
+                }
+                // The pairs stay expired and are tried again
+                Err(e) => tracing::warn!("Unsubscribing expired sub type {} failed: {}", sub_type, e),
+            }
+        }
+        if tx.is_closed() {
+            break;
+        }
+    }
+    tracing::debug!("Subscription expiry watcher stopped");
+}
+
+/// Take the quota of `client` from OpenD: the quota left to the account
+/// plus what `client` already holds, so subscriptions of other connections
+/// count against it. Returns the quota set.
+pub async fn sync_quota(client: &FutuClient) -> Result<u32, QuoteError> {
+    let resp = super::snapshot::get_sub_info(client, Some(false)).await?;
+    let s2c = resp.s2c.ok_or_else(|| QuoteError::Decode("Qot_GetSubInfo without s2c".to_string()))?;
+    let quota = s2c.remain_quota.max(0) as u32 + client.subscriptions().used_quota();
+    client.subscriptions().set_quota(Some(quota));
+    Ok(quota)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sec(code: &str) -> SecurityKey {
+        (1, code.to_string())
+    }
+
+    #[test]
+    fn test_record_and_current_subscriptions() {
+        let manager = SubscriptionManager::new();
+        manager.record_sub(&[sec("00700"), sec("09988")], &[1, 4], &SubOptions::default());
+        manager.record_sub(&[sec("00700")], &[2], &SubOptions { order_book_detail: true, ..Default::default() });
+        manager.record_unsub(&[sec("09988")], &[1, 4]);
+        assert_eq!(
+            manager.current_subscriptions(),
+            vec![Subscription { security: sec("00700"), sub_types: vec![1, 2, 4], order_book_detail: true }],
+        );
+        assert_eq!(manager.used_quota(), 3);
+        assert!(manager.is_subscribed(&sec("00700"), 4));
+        assert!(!manager.is_subscribed(&sec("09988"), 1));
+
+        let batches = manager.replay_batches();
+        assert_eq!(batches.len(), 3);
+        assert_eq!(batches[1], (2, SubOptions { order_book_detail: true, ..Default::default() }, vec![sec("00700")]));
+    }
+
+    #[test]
+    fn test_quota_check() {
+        let manager = SubscriptionManager::new();
+        manager.record_sub(&[sec("00700")], &[1, 4], &SubOptions::default());
+        assert!(manager.check_quota(&[sec("09988")], &[1, 2, 4, 5]).is_ok());
+
+        manager.set_quota(Some(4));
+        assert_eq!(manager.remaining_quota(), Some(2));
+        // Pairs already held cost nothing
+        assert!(manager.check_quota(&[sec("00700"), sec("09988")], &[1, 4]).is_ok());
+        let err = manager.check_quota(&[sec("09988")], &[1, 2, 4]).unwrap_err();
+        assert!(matches!(err, QuoteError::QuotaExceeded { needed: 3, remaining: 2 }));
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_records_and_enforces_quota() {
+        let mock = crate::client::mock::MockOpenD::start().await.unwrap();
+        let config = crate::config::FutuConfig { port: mock.port(), sub_quota: Some(3), ..Default::default() };
+        let mut client = FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+
+        let results = super::super::subscribe::subscribe(&client, vec![sec("00700"), sec("09988")], vec![1], true).await.unwrap();
+        assert!(results.iter().all(|r| r.is_ok()));
+        assert_eq!(client.subscriptions().used_quota(), 2);
+        assert_eq!(client.subscriptions().current_subscriptions()[1].security, sec("09988"));
+
+        let err = super::super::subscribe::subscribe(&client, vec![sec("00700")], vec![4, 5], true).await.unwrap_err();
+        assert!(matches!(err, QuoteError::QuotaExceeded { needed: 2, remaining: 1 }));
+
+        super::super::subscribe::subscribe(&client, vec![sec("00700")], vec![1], false).await.unwrap();
+        assert_eq!(client.subscriptions().remaining_quota(), Some(2));
+    }
+
+    #[test]
+    fn test_expiry_and_renewal() {
+        let manager = SubscriptionManager::new();
+        let t0 = Instant::now();
+        manager.record_sub(&[sec("00700"), sec("09988")], &[1, 4], &SubOptions::default());
+        // Only subscribed pairs get an expiry
+        assert_eq!(manager.set_expiry(&[sec("00700"), sec("03690")], &[1, 4], t0 + Duration::from_secs(60)), 2);
+        assert!(manager.expired(t0 + Duration::from_secs(59)).is_empty());
+        assert_eq!(manager.expired(t0 + Duration::from_secs(60)), vec![(sec("00700"), 1), (sec("00700"), 4)]);
+
+        // Pairs without a TTL are not renewed into one
+        assert_eq!(manager.renew(&[sec("00700"), sec("09988")], &[1], t0 + Duration::from_secs(120)), 1);
+        assert_eq!(manager.expired(t0 + Duration::from_secs(60)), vec![(sec("00700"), 4)]);
+        assert_eq!(manager.expiry(&sec("09988"), 1), None);
+
+        // Subscribing again without a TTL makes the pair permanent
+        manager.record_sub(&[sec("00700")], &[4], &SubOptions::default());
+        assert_eq!(manager.expired(t0 + Duration::from_secs(90)), vec![]);
+        manager.record_unsub(&[sec("00700")], &[1]);
+        assert!(manager.expired(t0 + Duration::from_secs(200)).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_expiry_watcher_unsubscribes() {
+        let mock = crate::client::mock::MockOpenD::start().await.unwrap();
+        let config = crate::config::FutuConfig { port: mock.port(), ..Default::default() };
+        let mut client = FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+        let client = Arc::new(client);
+
+        subscribe_with_ttl(&client, vec![sec("00700")], vec![1], Duration::ZERO, &SubOptions::default()).await.unwrap();
+        super::super::subscribe::subscribe(&client, vec![sec("09988")], vec![1], true).await.unwrap();
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let watcher = tokio::spawn(run_expiry_watcher(Arc::clone(&client), Duration::from_millis(10), tx));
+
+        let event = tokio::time::timeout(Duration::from_secs(5), rx.recv()).await.unwrap().unwrap();
+        assert_eq!(event, SubscriptionExpired { security: sec("00700"), sub_type: 1, error: None });
+        let current = client.subscriptions().current_subscriptions();
+        assert_eq!(current.len(), 1);
+        assert_eq!(current[0].security, sec("09988"));
+        watcher.abort();
+    }
+
+    #[tokio::test]
+    async fn test_unsub_all_clears_record() {
+        let mock = crate::client::mock::MockOpenD::start().await.unwrap();
+        let config = crate::config::FutuConfig { port: mock.port(), ..Default::default() };
+        let mut client = FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+
+        super::super::subscribe::subscribe(&client, vec![sec("00700"), sec("09988")], vec![1, 4], true).await.unwrap();
+        assert_eq!(client.subscriptions().used_quota(), 4);
+
+        let options = SubOptions { unsub_all: true, ..Default::default() };
+        let results = super::super::subscribe::subscribe_with_options(&client, Vec::new(), Vec::<i32>::new(), false, &options)
+            .await
+            .unwrap();
+        assert!(results.is_empty());
+        assert!(client.subscriptions().current_subscriptions().is_empty());
+        assert!(client.push_stats().snapshot().is_empty());
+    }
+}
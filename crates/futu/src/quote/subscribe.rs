@@ -1,45 +1,237 @@
+use std::future::Future;
 use prost::Message;
 use crate::client::FutuClient;
 use crate::client::connection::ConnectionError;
 
-const PROTO_QOT_SUB: u32 = 3001;
+pub(crate) const PROTO_QOT_SUB: u32 = 3001;
 const PROTO_QOT_REG_PUSH: u32 = 3002;
 
-/// Subscribe to quote data for given securities.
+/// Server errors that apply to the whole Qot_Sub request (subscription
+/// quota, quote right, connection) rather than to one of its securities.
+const REQUEST_WIDE_PATTERNS: [&str; 9] = ["额度", "quota", "权限", "permission", "quote right", "连接", "网络", "connect", "network"];
+
+/// Most Qot_Sub requests one `subscribe` call sends while isolating
+/// rejected securities, so a failure bisection cannot pin down does not
+/// use up OpenD's Qot_Sub rate limit.
+const MAX_BISECT_REQUESTS: usize = 32;
+
+/// Outcome of subscribing or unsubscribing one security.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubResult {
+    pub security: (i32, String),
+    /// OpenD's reason when the security was rejected, None if it succeeded.
+    pub error: Option<String>,
+}
+
+impl SubResult {
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Qot_Sub parameters beyond the securities and sub types. Subscriptions
+/// replayed after a reconnect keep only `order_book_detail`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SubOptions {
+    /// Subscribe order books with per-order detail (`OrderBook.detail_list`).
+    /// Only SF quotes have detail; with a lower quote right OpenD pushes the
+    /// levels without it. Qot_Sub has no depth parameter, so the number of
+    /// levels pushed is set by the quote right of the market alone.
+    pub order_book_detail: bool,
+    /// Register push for the subscribed pairs; None registers, as OpenD
+    /// does not push without it. Use `reg_qot_push` to register later.
+    pub reg_push: Option<bool>,
+    /// Push the data OpenD already holds once after registering; None
+    /// leaves it to OpenD, which pushes it.
+    pub first_push: Option<bool>,
+    /// RehabType values of the K-line pushes registered; empty leaves it to
+    /// OpenD, which pushes forward adjusted K-lines.
+    pub push_rehab_types: Vec<i32>,
+    /// Include US pre-market and after-hours data; deprecated by OpenD in
+    /// favour of `session`.
+    pub extended_time: Option<bool>,
+    /// Common.Session of US quotes (RTH, ETH, overnight or all sessions).
+    pub session: Option<i32>,
+    /// When unsubscribing, drop every subscription of the connection
+    /// instead of the securities and sub types given.
+    pub unsub_all: bool,
+}
+
+/// Subscribe to (or unsubscribe from) quote data for given securities.
+///
+/// Qot_Sub fails as a whole when any security is rejected, without naming
+/// it. A rejected request is split in halves and each half retried until
+/// the rejected securities are isolated, so the others are still
+/// subscribed. Errors that apply to the whole request (quota, quote right)
+/// are not bisected, and at most `MAX_BISECT_REQUESTS` requests are sent;
+/// securities left unresolved then carry the error of their batch.
+/// Returns one result per security in input order; `Err` is only returned
+/// for failures that are not a rejection (connection, decoding,
+/// throttling) or a whole-request error before any security was settled.
 pub async fn subscribe(
     client: &FutuClient,
+    securities: Vec<(i32, String)>,
+    sub_types: impl IntoIterator<Item = impl Into<i32>>,
+    is_sub: bool,
+) -> Result<Vec<SubResult>, QuoteError> {
+    subscribe_with_options(client, securities, sub_types, is_sub, &SubOptions::default()).await
+}
+
+/// `subscribe` with `options`.
+pub async fn subscribe_with_options(
+    client: &FutuClient,
+    securities: Vec<(i32, String)>,
+    sub_types: impl IntoIterator<Item = impl Into<i32>>,
+    is_sub: bool,
+    options: &SubOptions,
+) -> Result<Vec<SubResult>, QuoteError> {
+    let sub_types: Vec<i32> = sub_types.into_iter().map(Into::into).collect();
+    if !is_sub && options.unsub_all {
+        unsubscribe_all(client).await?;
+        return Ok(securities.into_iter().map(|security| SubResult { security, error: None }).collect());
+    }
+    if is_sub {
+        client.subscriptions().check_quota(&securities, &sub_types)?;
+    }
+    let results = bisect_rejections(securities, |batch| {
+        request_sub(client, sub_c2s(batch, sub_types.clone(), is_sub, options))
+    }).await?;
+    let done: Vec<(i32, String)> = results.iter().filter(|r| r.is_ok()).map(|r| r.security.clone()).collect();
+    if is_sub {
+        client.push_stats().track(&done, &sub_types, client.clock().unix_time());
+        client.subscriptions().record_sub(&done, &sub_types, options);
+    } else {
+        client.push_stats().untrack(&done, &sub_types);
+        client.subscriptions().record_unsub(&done, &sub_types);
+    }
+    Ok(results)
+}
+
+/// Drop every subscription of the connection with a single Qot_Sub
+/// (`is_unsub_all`), including ones not made through `subscribe`, and
+/// forget the recorded subscriptions, their expiries and push statistics.
+/// Returns the number of (security, sub type) pairs that were recorded.
+///
+/// OpenD only releases the quota of a subscription a minute after it was
+/// made, so quota freed this way may not be reusable at once.
+pub async fn unsubscribe_all(client: &FutuClient) -> Result<u32, QuoteError> {
+    request_sub(client, crate::generated::qot_sub::C2s { is_unsub_all: Some(true), ..Default::default() }).await?;
+    let released = client.subscriptions().used_quota();
+    for sub in client.subscriptions().current_subscriptions() {
+        client.push_stats().untrack(std::slice::from_ref(&sub.security), &sub.sub_types);
+    }
+    client.subscriptions().clear();
+    Ok(released)
+}
+
+/// `Ok` if every security succeeded, otherwise a `SubscriptionRejected`
+/// error listing the rejected ones.
+pub fn check_sub_results(results: Vec<SubResult>) -> Result<(), QuoteError> {
+    let total = results.len();
+    let rejected: Vec<SubResult> = results.into_iter().filter(|r| !r.is_ok()).collect();
+    if rejected.is_empty() {
+        return Ok(());
+    }
+    Err(QuoteError::SubscriptionRejected { total, rejected })
+}
+
+/// Whether a Qot_Sub server error applies to the whole request.
+fn is_request_wide(msg: &str) -> bool {
+    let lower = msg.to_lowercase();
+    REQUEST_WIDE_PATTERNS.iter().any(|p| lower.contains(p))
+}
+
+/// Send `securities` through `send`, splitting rejected batches until each
+/// rejection is pinned to a single security.
+async fn bisect_rejections<F, Fut>(securities: Vec<(i32, String)>, mut send: F) -> Result<Vec<SubResult>, QuoteError>
+where
+    F: FnMut(Vec<(i32, String)>) -> Fut,
+    Fut: Future<Output = Result<(), QuoteError>>,
+{
+    let order: std::collections::HashMap<(i32, String), usize> =
+        securities.iter().cloned().enumerate().map(|(i, s)| (s, i)).collect();
+    let mut results = Vec::with_capacity(securities.len());
+    let mut pending = vec![securities];
+    let mut requests = 0;
+    while let Some(mut batch) = pending.pop() {
+        if batch.is_empty() {
+            continue;
+        }
+        requests += 1;
+        match send(batch.clone()).await {
+            Ok(()) => results.extend(batch.into_iter().map(|security| SubResult { security, error: None })),
+            Err(QuoteError::Server { ret_type, err_code, msg })
+                if is_request_wide(&msg) || requests >= MAX_BISECT_REQUESTS =>
+            {
+                if results.is_empty() {
+                    return Err(QuoteError::Server { ret_type, err_code, msg });
+                }
+                // Earlier batches are subscribed; report the rest with this error
+                tracing::warn!("Qot_Sub stopped bisecting after {} requests: {}", requests, msg);
+                for security in pending.drain(..).flatten().chain(batch) {
+                    results.push(SubResult { security, error: Some(msg.clone()) });
+                }
+            }
+            Err(QuoteError::Server { .. }) if batch.len() > 1 => {
+                let second = batch.split_off(batch.len() / 2);
+                pending.push(second);
+                pending.push(batch);
+            }
+            Err(QuoteError::Server { msg, .. }) => {
+                let security = batch.remove(0);
+                tracing::warn!("Qot_Sub rejected {}.{}: {}", security.0, security.1, msg);
+                results.push(SubResult { security, error: Some(msg) });
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    results.sort_by_key(|r| order.get(&r.security).copied().unwrap_or(usize::MAX));
+    Ok(results)
+}
+
+/// Body of a Qot_Sub request dropping every subscription of the connection.
+pub(crate) fn unsub_all_request() -> Vec<u8> {
+    let c2s = crate::generated::qot_sub::C2s { is_unsub_all: Some(true), ..Default::default() };
+    crate::generated::qot_sub::Request { c2s }.encode_to_vec()
+}
+
+pub(crate) fn sub_c2s(
     securities: Vec<(i32, String)>,
     sub_types: Vec<i32>,
     is_sub: bool,
-) -> Result<(), QuoteError> {
+    options: &SubOptions,
+) -> crate::generated::qot_sub::C2s {
     let security_list: Vec<crate::generated::qot_common::Security> = securities
         .into_iter()
         .map(|(market, code)| crate::generated::qot_common::Security { market, code })
         .collect();
 
-    let c2s = crate::generated::qot_sub::C2s {
+    crate::generated::qot_sub::C2s {
         security_list,
         sub_type_list: sub_types,
         is_sub_or_un_sub: is_sub,
-        is_reg_or_un_reg_push: Some(true),
-        ..Default::default()
-    };
+        is_reg_or_un_reg_push: Some(options.reg_push.unwrap_or(true)),
+        reg_push_rehab_type_list: options.push_rehab_types.clone(),
+        is_first_push: options.first_push,
+        is_unsub_all: (!is_sub && options.unsub_all).then_some(true),
+        is_sub_order_book_detail: options.order_book_detail.then_some(true),
+        extended_time: options.extended_time,
+        session: options.session,
+    }
+}
 
+async fn request_sub(client: &FutuClient, c2s: crate::generated::qot_sub::C2s) -> Result<(), QuoteError> {
     let request = crate::generated::qot_sub::Request { c2s };
     let body = request.encode_to_vec();
     let resp = client.request(PROTO_QOT_SUB, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_sub::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_sub::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
-
     Ok(())
 }
 
@@ -50,36 +242,61 @@ pub async fn reg_push(
     sub_types: Vec<i32>,
     is_reg: bool,
 ) -> Result<(), QuoteError> {
-    let security_list: Vec<crate::generated::qot_common::Security> = securities
-        .into_iter()
-        .map(|(market, code)| crate::generated::qot_common::Security { market, code })
-        .collect();
+    reg_qot_push(client, securities, sub_types, None, is_reg, None).await
+}
 
-    let c2s = crate::generated::qot_reg_qot_push::C2s {
-        security_list,
-        sub_type_list: sub_types,
-        is_reg_or_un_reg: is_reg,
-        ..Default::default()
-    };
+/// Register or unregister push for already subscribed securities without
+/// changing the subscriptions themselves.
+///
+/// `rehab_type` (RehabType) only applies to K-line sub types; OpenD defaults
+/// to forward adjustment. `is_first_push` controls whether data already held
+/// by OpenD is pushed once after registering; OpenD defaults to true.
+pub async fn reg_qot_push(
+    client: &FutuClient,
+    securities: Vec<(i32, String)>,
+    sub_types: Vec<i32>,
+    rehab_type: Option<i32>,
+    is_reg: bool,
+    is_first_push: Option<bool>,
+) -> Result<(), QuoteError> {
+    let c2s = reg_qot_push_c2s(securities, sub_types, rehab_type, is_reg, is_first_push);
 
     let request = crate::generated::qot_reg_qot_push::Request { c2s };
     let body = request.encode_to_vec();
     let resp = client.request(PROTO_QOT_REG_PUSH, &body).await
         .map_err(QuoteError::Connection)?;
 
-    let response = crate::generated::qot_reg_qot_push::Response::decode(resp.body.as_slice())
-        .map_err(|e| QuoteError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::qot_reg_qot_push::Response>(&resp)
+        .map_err(QuoteError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(QuoteError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(QuoteError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(())
 }
 
+fn reg_qot_push_c2s(
+    securities: Vec<(i32, String)>,
+    sub_types: Vec<i32>,
+    rehab_type: Option<i32>,
+    is_reg: bool,
+    is_first_push: Option<bool>,
+) -> crate::generated::qot_reg_qot_push::C2s {
+    let security_list: Vec<crate::generated::qot_common::Security> = securities
+        .into_iter()
+        .map(|(market, code)| crate::generated::qot_common::Security { market, code })
+        .collect();
+
+    crate::generated::qot_reg_qot_push::C2s {
+        security_list,
+        sub_type_list: sub_types,
+        rehab_type_list: rehab_type.into_iter().collect(),
+        is_reg_or_un_reg: is_reg,
+        is_first_push,
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum QuoteError {
     #[error("connection error: {0}")]
@@ -87,7 +304,37 @@ pub enum QuoteError {
     #[error("decode error: {0}")]
     Decode(String),
     #[error("server error (retType={ret_type}): {msg}")]
-    Server { ret_type: i32, msg: String },
+    Server { ret_type: i32, err_code: Option<i32>, msg: String },
+    #[error("throttled by OpenD, retry after {retry_after:?}: {msg}")]
+    Throttled { retry_after: std::time::Duration, msg: String },
+    #[error("{} of {total} securities rejected: {}", rejected.len(), format_rejected(rejected))]
+    SubscriptionRejected { total: usize, rejected: Vec<SubResult> },
+    #[error("subscription quota exceeded: {needed} new subscriptions, {remaining} left")]
+    QuotaExceeded { needed: u32, remaining: u32 },
+    #[error("history KL quota exceeded: {needed} new symbols, {remaining} left")]
+    HistoryQuotaExceeded { needed: u32, remaining: u32 },
+    #[error("no K-line pushes for KLType {0}")]
+    UnsupportedKlType(i32),
+}
+
+fn format_rejected(rejected: &[SubResult]) -> String {
+    rejected
+        .iter()
+        .map(|r| format!("{}.{} ({})", r.security.0, r.security.1, r.error.as_deref().unwrap_or_default()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl QuoteError {
+    /// Error of a failed response: `Throttled` if OpenD was busy or over a
+    /// frequency limit, `Server` otherwise.
+    pub fn server(ret_type: i32, ret_msg: Option<String>, err_code: Option<i32>) -> Self {
+        let msg = ret_msg.unwrap_or_default();
+        match crate::client::flow_control::throttle_delay(ret_type, &msg) {
+            Some(retry_after) => QuoteError::Throttled { retry_after, msg },
+            None => QuoteError::Server { ret_type, err_code, msg },
+        }
+    }
 }
 
 #[cfg(test)]
@@ -101,6 +348,85 @@ mod tests {
         assert_eq!(PROTO_QOT_REG_PUSH, 3002);
     }
 
+    #[test]
+    fn test_server_error_classification() {
+        let err = QuoteError::server(-1, Some("请求频率太高，每30秒最多请求60次".to_string()), None);
+        assert!(matches!(err, QuoteError::Throttled { retry_after, .. } if retry_after.as_secs() == 30));
+        let err = QuoteError::server(-1, Some("unknown stock".to_string()), None);
+        assert!(matches!(err, QuoteError::Server { ret_type: -1, .. }));
+        assert!(matches!(QuoteError::server(-1, None, None), QuoteError::Server { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_bisect_isolates_rejected_securities() {
+        let securities: Vec<(i32, String)> = ["00700", "BAD1", "09988", "03690", "BAD2"]
+            .iter()
+            .map(|c| (1, c.to_string()))
+            .collect();
+        let mut requests = 0;
+        let results = bisect_rejections(securities, |batch| {
+            requests += 1;
+            let bad = batch.iter().any(|(_, c)| c.starts_with("BAD"));
+            async move {
+                if bad { Err(QuoteError::server(-1, Some("unknown stock".to_string()), None)) } else { Ok(()) }
+            }
+        }).await.unwrap();
+
+        let codes: Vec<(&str, bool)> = results.iter().map(|r| (r.security.1.as_str(), r.is_ok())).collect();
+        assert_eq!(codes, vec![("00700", true), ("BAD1", false), ("09988", true), ("03690", true), ("BAD2", false)]);
+        assert_eq!(results[1].error.as_deref(), Some("unknown stock"));
+        assert_eq!(requests, 9);
+
+        let err = check_sub_results(results).unwrap_err();
+        assert_eq!(err.to_string(), "2 of 5 securities rejected: 1.BAD1 (unknown stock), 1.BAD2 (unknown stock)");
+    }
+
+    #[tokio::test]
+    async fn test_bisect_stops_on_other_errors() {
+        let securities = vec![(1, "00700".to_string()), (1, "09988".to_string())];
+        let result = bisect_rejections(securities, |_| async {
+            Err(QuoteError::server(-1, Some("请求频率太高".to_string()), None))
+        }).await;
+        assert!(matches!(result, Err(QuoteError::Throttled { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_bisect_skips_request_wide_errors() {
+        let securities: Vec<(i32, String)> = (0..400).map(|i| (1, format!("{:05}", i))).collect();
+        let mut requests = 0;
+        let result = bisect_rejections(securities.clone(), |_| {
+            requests += 1;
+            async { Err(QuoteError::server(-1, Some("订阅额度不足".to_string()), None)) }
+        }).await;
+        assert!(matches!(result, Err(QuoteError::Server { msg, .. }) if msg == "订阅额度不足"));
+        assert_eq!(requests, 1);
+
+        // The quota running out partway settles the rest with its error
+        let mut requests = 0;
+        let results = bisect_rejections(securities.clone(), |_| {
+            requests += 1;
+            let msg = match requests {
+                1 => Some("unknown stock"),
+                2 => None,
+                _ => Some("subscription quota exceeded"),
+            };
+            async move { msg.map_or(Ok(()), |m| Err(QuoteError::server(-1, Some(m.to_string()), None))) }
+        }).await.unwrap();
+        assert_eq!(requests, 3);
+        assert!(results[..200].iter().all(|r| r.is_ok()));
+        assert!(results[200..].iter().all(|r| r.error.as_deref() == Some("subscription quota exceeded")));
+
+        // Failures bisection cannot pin down stop at the request cap
+        let mut requests = 0;
+        let results = bisect_rejections(securities, |_| {
+            requests += 1;
+            async { Err(QuoteError::server(-1, Some("unknown stock".to_string()), None)) }
+        }).await.unwrap();
+        assert_eq!(requests, MAX_BISECT_REQUESTS);
+        assert_eq!(results.len(), 400);
+        assert!(results.iter().all(|r| r.error.as_deref() == Some("unknown stock")));
+    }
+
     #[test]
     fn test_subscribe_request_encode_decode() {
         let securities = vec![
@@ -131,6 +457,61 @@ mod tests {
         assert_eq!(decoded.c2s.is_reg_or_un_reg_push, Some(true));
     }
 
+    #[test]
+    fn test_sub_c2s_order_book_detail() {
+        let securities = vec![(1, "HSImain".to_string())];
+        let c2s = sub_c2s(securities.clone(), vec![2], true, &SubOptions { order_book_detail: true, ..Default::default() });
+        assert_eq!(c2s.is_sub_order_book_detail, Some(true));
+        // Left unset by default so OpenD versions without the field are unaffected
+        let c2s = sub_c2s(securities, vec![2], true, &SubOptions::default());
+        assert_eq!(c2s.is_sub_order_book_detail, None);
+    }
+
+    #[test]
+    fn test_sub_c2s_push_options() {
+        let securities = vec![(11, "AAPL".to_string())];
+        let c2s = sub_c2s(securities.clone(), vec![11], true, &SubOptions::default());
+        assert_eq!(c2s.is_reg_or_un_reg_push, Some(true));
+        assert!(c2s.reg_push_rehab_type_list.is_empty());
+        assert_eq!((c2s.is_first_push, c2s.is_unsub_all, c2s.extended_time, c2s.session), (None, None, None, None));
+
+        let options = SubOptions {
+            reg_push: Some(false),
+            first_push: Some(false),
+            push_rehab_types: vec![0, 2],
+            extended_time: Some(true),
+            session: Some(3),
+            unsub_all: true,
+            ..Default::default()
+        };
+        let c2s = sub_c2s(securities.clone(), vec![11], true, &options);
+        assert_eq!(c2s.is_reg_or_un_reg_push, Some(false));
+        assert_eq!(c2s.reg_push_rehab_type_list, vec![0, 2]);
+        assert_eq!(c2s.is_first_push, Some(false));
+        assert_eq!(c2s.extended_time, Some(true));
+        assert_eq!(c2s.session, Some(3));
+        // Unsubscribing all only applies to unsubscribe requests
+        assert_eq!(c2s.is_unsub_all, None);
+        assert_eq!(sub_c2s(securities, vec![11], false, &options).is_unsub_all, Some(true));
+    }
+
+    #[test]
+    fn test_reg_qot_push_request_encode_decode() {
+        let c2s = reg_qot_push_c2s(vec![(1, "00700".to_string())], vec![6], Some(2), false, Some(false));
+        let request = crate::generated::qot_reg_qot_push::Request { c2s };
+        let encoded = request.encode_to_vec();
+        let decoded = crate::generated::qot_reg_qot_push::Request::decode(encoded.as_slice()).unwrap();
+        assert_eq!(decoded.c2s.security_list[0].code, "00700");
+        assert_eq!(decoded.c2s.sub_type_list, vec![6]);
+        assert_eq!(decoded.c2s.rehab_type_list, vec![2]);
+        assert!(!decoded.c2s.is_reg_or_un_reg);
+        assert_eq!(decoded.c2s.is_first_push, Some(false));
+
+        let c2s = reg_qot_push_c2s(vec![], vec![1], None, true, None);
+        assert!(c2s.rehab_type_list.is_empty());
+        assert!(c2s.is_first_push.is_none());
+    }
+
     #[test]
     fn test_subscribe_response_success() {
         let response = crate::generated::qot_sub::Response {
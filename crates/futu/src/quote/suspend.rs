@@ -0,0 +1,270 @@
+//! Per-security suspension calendar built from Qot_GetSuspend results.
+//!
+//! Dates are `YYYY-MM-DD` strings (any time part in server data is ignored).
+
+use std::collections::{BTreeSet, HashMap};
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use crate::client::FutuClient;
+use crate::dates::add_days;
+use super::subscribe::QuoteError;
+
+type SecurityKey = (i32, String);
+
+/// Date range already fetched for a security.
+#[derive(Debug, Clone)]
+struct Coverage {
+    begin: String,
+    end: String,
+    fetched_at: Instant,
+}
+
+/// Suspension days per security, with the date ranges they are known for.
+#[derive(Debug, Default)]
+pub struct SuspensionCalendar {
+    days: HashMap<SecurityKey, BTreeSet<String>>,
+    coverage: HashMap<SecurityKey, Coverage>,
+}
+
+impl SuspensionCalendar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record a get_suspend result for `securities` over `[begin, end]`,
+    /// fetched at `now`. Securities absent from the response are recorded as
+    /// never suspended in that range.
+    pub fn ingest(
+        &mut self,
+        securities: &[(i32, String)],
+        begin: &str,
+        end: &str,
+        s2c: &crate::generated::qot_get_suspend::S2c,
+        now: Instant,
+    ) {
+        let (begin, end) = (date_part(begin), date_part(end));
+        for sec in securities {
+            // Refetched ranges replace what was known about them
+            if let Some(days) = self.days.get_mut(sec) {
+                days.retain(|d| d.as_str() < begin || d.as_str() > end);
+            }
+            self.coverage.insert(
+                sec.clone(),
+                Coverage {
+                    begin: begin.to_string(),
+                    end: end.to_string(),
+                    fetched_at: now,
+                },
+            );
+        }
+        for item in &s2c.security_suspend_list {
+            let key = (item.security.market, item.security.code.clone());
+            let days = self.days.entry(key).or_default();
+            for s in &item.suspend_list {
+                days.insert(date_part(&s.time).to_string());
+            }
+        }
+    }
+
+    /// Whether `date` is within the fetched range for the security and the
+    /// range is younger than `ttl` at `now`.
+    pub fn covers(&self, security: &(i32, String), date: &str, ttl: Duration, now: Instant) -> bool {
+        let date = date_part(date);
+        self.coverage.get(security).is_some_and(|c| {
+            c.begin.as_str() <= date
+                && date <= c.end.as_str()
+                && now.saturating_duration_since(c.fetched_at) < ttl
+        })
+    }
+
+    /// Whether the security is suspended on `date`; `None` if the date is not covered.
+    pub fn is_suspended_on(&self, security: &(i32, String), date: &str) -> Option<bool> {
+        let date = date_part(date);
+        let c = self.coverage.get(security)?;
+        if date < c.begin.as_str() || date > c.end.as_str() {
+            return None;
+        }
+        Some(self.days.get(security).is_some_and(|d| d.contains(date)))
+    }
+
+    /// First calendar day after the suspension run containing `date`.
+    ///
+    /// Returns `Ok(None)` if the security is not suspended on `date`, and
+    /// `Err(())` if the answer lies outside the covered range.
+    #[allow(clippy::result_unit_err)]
+    pub fn next_resume(&self, security: &(i32, String), date: &str) -> Result<Option<String>, ()> {
+        let c = self.coverage.get(security).ok_or(())?;
+        match self.is_suspended_on(security, date) {
+            None => return Err(()),
+            Some(false) => return Ok(None),
+            Some(true) => {}
+        }
+        let days = self.days.get(security).ok_or(())?;
+        let mut day = date_part(date).to_string();
+        while days.contains(&day) {
+            day = add_days(&day, 1).ok_or(())?;
+        }
+        if day.as_str() > c.end.as_str() {
+            return Err(());
+        }
+        Ok(Some(day))
+    }
+}
+
+/// Suspension calendar that fetches missing or stale ranges on demand.
+pub struct SuspensionCache {
+    calendar: Mutex<SuspensionCalendar>,
+    /// Days fetched on each side of the queried date.
+    window_days: i64,
+    /// Age after which a fetched range is refreshed.
+    ttl: Duration,
+}
+
+impl Default for SuspensionCache {
+    fn default() -> Self {
+        Self::new(30, Duration::from_secs(3600))
+    }
+}
+
+impl SuspensionCache {
+    pub fn new(window_days: i64, ttl: Duration) -> Self {
+        Self {
+            calendar: Mutex::new(SuspensionCalendar::new()),
+            window_days,
+            ttl,
+        }
+    }
+
+    /// Whether the security is suspended on `date`, fetching from OpenD if needed.
+    pub async fn is_suspended_on(
+        &self,
+        client: &FutuClient,
+        security: (i32, String),
+        date: &str,
+    ) -> Result<bool, QuoteError> {
+        self.ensure(client, &security, date).await?;
+        let calendar = self.calendar.lock().await;
+        Ok(calendar.is_suspended_on(&security, date).unwrap_or(false))
+    }
+
+    /// The day trading resumes after the suspension covering `date`, if suspended.
+    /// Extends the fetched range forward while the suspension runs past it.
+    pub async fn next_resume(
+        &self,
+        client: &FutuClient,
+        security: (i32, String),
+        date: &str,
+    ) -> Result<Option<String>, QuoteError> {
+        self.ensure(client, &security, date).await?;
+        let mut probe = add_days(date, self.window_days).unwrap_or_else(|| date_part(date).to_string());
+        // Bounded: a halt longer than ~1 year of windows is reported as unknown
+        for _ in 0..12 {
+            let result = self.calendar.lock().await.next_resume(&security, date);
+            match result {
+                Ok(resume) => return Ok(resume),
+                Err(()) => {
+                    probe = add_days(&probe, self.window_days).unwrap_or(probe);
+                    self.fetch(client, &security, date, &probe).await?;
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    async fn ensure(
+        &self,
+        client: &FutuClient,
+        security: &(i32, String),
+        date: &str,
+    ) -> Result<(), QuoteError> {
+        if self.calendar.lock().await.covers(security, date, self.ttl, client.clock().now()) {
+            return Ok(());
+        }
+        let begin = add_days(date, -self.window_days).unwrap_or_else(|| date_part(date).to_string());
+        let end = add_days(date, self.window_days).unwrap_or_else(|| date_part(date).to_string());
+        self.fetch(client, security, &begin, &end).await
+    }
+
+    async fn fetch(
+        &self,
+        client: &FutuClient,
+        security: &(i32, String),
+        begin: &str,
+        end: &str,
+    ) -> Result<(), QuoteError> {
+        let securities = vec![security.clone()];
+        let resp = super::snapshot::get_suspend(
+            client, securities.clone(), begin.to_string(), end.to_string(),
+        ).await?;
+        let s2c = resp.s2c.unwrap_or_default();
+        self.calendar.lock().await.ingest(&securities, begin, end, &s2c, client.clock().now());
+        Ok(())
+    }
+}
+
+fn date_part(s: &str) -> &str {
+    s.get(..10).unwrap_or(s)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generated::qot_common::Security;
+    use crate::generated::qot_get_suspend::{S2c, SecuritySuspend, Suspend};
+
+    fn sec() -> (i32, String) {
+        (1, "00700".to_string())
+    }
+
+    /// Fetch time of the test calendars.
+    fn start() -> Instant {
+        static START: std::sync::OnceLock<Instant> = std::sync::OnceLock::new();
+        *START.get_or_init(Instant::now)
+    }
+
+    fn calendar(days: &[&str]) -> SuspensionCalendar {
+        let s2c = S2c {
+            security_suspend_list: vec![SecuritySuspend {
+                security: Security { market: 1, code: "00700".to_string() },
+                suspend_list: days
+                    .iter()
+                    .map(|d| Suspend { time: d.to_string(), timestamp: None })
+                    .collect(),
+            }],
+        };
+        let mut cal = SuspensionCalendar::new();
+        cal.ingest(&[sec(), (1, "09988".to_string())], "2024-02-01", "2024-03-31", &s2c, start());
+        cal
+    }
+
+    #[test]
+    fn test_is_suspended_on() {
+        let cal = calendar(&["2024-02-28", "2024-02-29 00:00:00"]);
+        assert_eq!(cal.is_suspended_on(&sec(), "2024-02-29"), Some(true));
+        assert_eq!(cal.is_suspended_on(&sec(), "2024-03-01"), Some(false));
+        assert_eq!(cal.is_suspended_on(&(1, "09988".to_string()), "2024-02-29"), Some(false));
+        assert_eq!(cal.is_suspended_on(&sec(), "2024-04-01"), None);
+        assert_eq!(cal.is_suspended_on(&(11, "AAPL".to_string()), "2024-02-29"), None);
+    }
+
+    #[test]
+    fn test_next_resume() {
+        let cal = calendar(&["2024-02-28", "2024-02-29", "2024-03-31"]);
+        assert_eq!(cal.next_resume(&sec(), "2024-02-28"), Ok(Some("2024-03-01".to_string())));
+        assert_eq!(cal.next_resume(&sec(), "2024-03-01"), Ok(None));
+        // Suspension runs past the covered range
+        assert_eq!(cal.next_resume(&sec(), "2024-03-31"), Err(()));
+    }
+
+    #[test]
+    fn test_covers_respects_ttl() {
+        let cal = calendar(&[]);
+        let ttl = Duration::from_secs(60);
+        assert!(cal.covers(&sec(), "2024-02-15", ttl, start()));
+        assert!(!cal.covers(&sec(), "2024-01-15", ttl, start()));
+        assert!(!cal.covers(&sec(), "2024-02-15", Duration::ZERO, start()));
+        assert!(cal.covers(&sec(), "2024-02-15", ttl, start() + Duration::from_secs(59)));
+        assert!(!cal.covers(&sec(), "2024-02-15", ttl, start() + ttl));
+    }
+}
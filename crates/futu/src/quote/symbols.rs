@@ -0,0 +1,107 @@
+//! User-defined symbol aliases for Futu securities.
+//!
+//! Maps an alias from the caller's own symbology (e.g. "TCEHY.HK" or an
+//! internal instrument id) to a (market, code) pair and back, so inputs can
+//! name securities by alias and outputs can carry the alias alongside the
+//! Futu code. Each alias maps to one security and each security to one alias.
+
+use std::collections::HashMap;
+
+use super::names::trd_sec_market_to_qot_market;
+
+/// Bidirectional alias ↔ (QotMarket, code) table.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolMap {
+    by_alias: HashMap<String, (i32, String)>,
+    by_security: HashMap<(i32, String), String>,
+}
+
+impl SymbolMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map `alias` to (market, code), replacing any previous mapping of
+    /// either the alias or the security.
+    pub fn insert(&mut self, alias: String, market: i32, code: String) {
+        self.remove(&alias);
+        let security = (market, code);
+        if let Some(old_alias) = self.by_security.remove(&security) {
+            self.by_alias.remove(&old_alias);
+        }
+        self.by_security.insert(security.clone(), alias.clone());
+        self.by_alias.insert(alias, security);
+    }
+
+    /// Remove an alias. Returns false if it was unknown.
+    pub fn remove(&mut self, alias: &str) -> bool {
+        match self.by_alias.remove(alias) {
+            Some(security) => {
+                self.by_security.remove(&security);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// (market, code) of `alias`.
+    pub fn resolve(&self, alias: &str) -> Option<(i32, &str)> {
+        self.by_alias.get(alias).map(|(market, code)| (*market, code.as_str()))
+    }
+
+    /// Alias of the security, if one is registered.
+    pub fn alias(&self, market: i32, code: &str) -> Option<&str> {
+        if self.by_security.is_empty() {
+            return None;
+        }
+        self.by_security.get(&(market, code.to_string())).map(|a| a.as_str())
+    }
+
+    /// `alias` for trade items, whose market is a TrdSecMarket.
+    pub fn alias_trd(&self, sec_market: Option<i32>, code: &str) -> Option<&str> {
+        self.alias(sec_market.and_then(trd_sec_market_to_qot_market)?, code)
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_alias.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_alias.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_and_alias() {
+        let mut map = SymbolMap::new();
+        map.insert("TENCENT".to_string(), 1, "00700".to_string());
+        assert_eq!(map.resolve("TENCENT"), Some((1, "00700")));
+        assert_eq!(map.alias(1, "00700"), Some("TENCENT"));
+        assert_eq!(map.alias_trd(Some(1), "00700"), Some("TENCENT"));
+        assert_eq!(map.alias_trd(None, "00700"), None);
+        assert_eq!(map.resolve("AAPL"), None);
+    }
+
+    #[test]
+    fn test_insert_replaces_both_directions() {
+        let mut map = SymbolMap::new();
+        map.insert("A".to_string(), 1, "00700".to_string());
+        map.insert("A".to_string(), 11, "AAPL".to_string());
+        assert_eq!(map.alias(1, "00700"), None);
+        assert_eq!(map.resolve("A"), Some((11, "AAPL")));
+
+        map.insert("B".to_string(), 11, "AAPL".to_string());
+        assert_eq!(map.resolve("A"), None);
+        assert_eq!(map.alias(11, "AAPL"), Some("B"));
+        assert_eq!(map.len(), 1);
+
+        assert!(map.remove("B"));
+        assert!(!map.remove("B"));
+        assert!(map.is_empty());
+        assert_eq!(map.alias(11, "AAPL"), None);
+    }
+}
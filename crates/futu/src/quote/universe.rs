@@ -0,0 +1,197 @@
+//! Every listed security of a market, gathered from its plates.
+//!
+//! OpenD has no request listing a market's securities, but every security
+//! belongs to plates (industry, region and concept sectors). `get_all_securities`
+//! lists the plates of a market with Qot_GetPlateSet, then the securities
+//! of each plate with Qot_GetPlateSecurity, which returns a plate whole
+//! rather than in pages. A security found in several plates is listed once,
+//! with every plate it was found in. Qot_GetPlateSecurity is limited to 10
+//! requests per 30 seconds, so enumerating a market takes a while; enable
+//! the client's rate limit (see `client::rate_limit`) to pace the requests
+//! instead of running into OpenD's frequency errors.
+
+use std::collections::BTreeMap;
+
+use crate::client::FutuClient;
+use crate::generated::qot_common::SecurityStaticInfo;
+use super::snapshot::{get_plate_security, get_plate_set};
+use super::subscribe::QuoteError;
+
+// PlateSetType values
+pub const PLATE_SET_ALL: i32 = 0;
+pub const PLATE_SET_INDUSTRY: i32 = 1;
+pub const PLATE_SET_REGION: i32 = 2;
+pub const PLATE_SET_CONCEPT: i32 = 3;
+
+/// What `get_all_securities` gathers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UniverseOptions {
+    /// Plates to enumerate (PlateSetType). Industry plates cover nearly
+    /// every stock with a fraction of the requests of all plates.
+    pub plate_set_type: i32,
+    /// Keep only these SecurityTypes; None keeps all.
+    pub sec_types: Option<Vec<i32>>,
+    /// Keep securities OpenD marks as delisted.
+    pub include_delisted: bool,
+}
+
+impl Default for UniverseOptions {
+    fn default() -> Self {
+        Self { plate_set_type: PLATE_SET_ALL, sec_types: None, include_delisted: false }
+    }
+}
+
+/// One security of a market's universe.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListedSecurity {
+    /// (market, code)
+    pub security: (i32, String),
+    pub name: String,
+    pub lot_size: i32,
+    pub sec_type: i32,
+    pub list_time: String,
+    pub exch_type: Option<i32>,
+    pub delisted: bool,
+    /// Codes of the plates the security was found in.
+    pub plates: Vec<String>,
+}
+
+impl ListedSecurity {
+    fn from_static_info(info: &SecurityStaticInfo) -> Self {
+        let basic = &info.basic;
+        Self {
+            security: (basic.security.market, basic.security.code.clone()),
+            name: basic.name.clone(),
+            lot_size: basic.lot_size,
+            sec_type: basic.sec_type,
+            list_time: basic.list_time.clone(),
+            exch_type: basic.exch_type,
+            delisted: basic.delisting.unwrap_or(false),
+            plates: Vec::new(),
+        }
+    }
+}
+
+/// Securities of plates merged by security.
+#[derive(Debug, Default)]
+struct Universe {
+    securities: BTreeMap<(i32, String), ListedSecurity>,
+}
+
+impl Universe {
+    fn add_plate(&mut self, plate_code: &str, infos: &[SecurityStaticInfo]) {
+        for info in infos {
+            let security = &info.basic.security;
+            let entry = self
+                .securities
+                .entry((security.market, security.code.clone()))
+                .or_insert_with(|| ListedSecurity::from_static_info(info));
+            if !entry.plates.iter().any(|p| p == plate_code) {
+                entry.plates.push(plate_code.to_string());
+            }
+        }
+    }
+
+    /// The securities `options` keep, sorted by security.
+    fn into_list(self, options: &UniverseOptions) -> Vec<ListedSecurity> {
+        self.securities
+            .into_values()
+            .filter(|s| options.include_delisted || !s.delisted)
+            .filter(|s| options.sec_types.as_ref().is_none_or(|types| types.contains(&s.sec_type)))
+            .collect()
+    }
+}
+
+/// Every listed security of `market` (QotMarket), from the securities of
+/// its plates, sorted by security.
+pub async fn get_all_securities(
+    client: &FutuClient,
+    market: impl Into<i32>,
+    options: &UniverseOptions,
+) -> Result<Vec<ListedSecurity>, QuoteError> {
+    let plates = get_plate_set(client, market.into(), options.plate_set_type)
+        .await?
+        .s2c
+        .map(|s| s.plate_info_list)
+        .unwrap_or_default();
+
+    let mut universe = Universe::default();
+    for plate in &plates {
+        let response = get_plate_security(client, plate.plate.market, plate.plate.code.clone(), None, None).await?;
+        if let Some(s2c) = response.s2c {
+            universe.add_plate(&plate.plate.code, &s2c.static_info_list);
+        }
+    }
+    tracing::debug!("Enumerated {} plates, {} securities", plates.len(), universe.securities.len());
+    Ok(universe.into_list(options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generated::qot_common::{Security, SecurityStaticBasic};
+
+    fn info(code: &str, sec_type: i32, delisting: Option<bool>) -> SecurityStaticInfo {
+        SecurityStaticInfo {
+            basic: SecurityStaticBasic {
+                security: Security { market: 1, code: code.to_string() },
+                sec_type,
+                lot_size: 100,
+                delisting,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_plates_merged() {
+        let mut universe = Universe::default();
+        universe.add_plate("LIST1", &[info("00700", 3, None), info("09988", 3, Some(false))]);
+        universe.add_plate("LIST2", &[info("00700", 3, None), info("02800", 4, None), info("00001", 3, Some(true))]);
+        universe.add_plate("LIST2", &[info("00700", 3, None)]);
+
+        let all = Universe { securities: universe.securities.clone() }.into_list(&UniverseOptions::default());
+        let codes: Vec<&str> = all.iter().map(|s| s.security.1.as_str()).collect();
+        assert_eq!(codes, ["00700", "02800", "09988"]);
+        assert_eq!(all[0].plates, ["LIST1", "LIST2"]);
+
+        let options = UniverseOptions { sec_types: Some(vec![3]), include_delisted: true, ..Default::default() };
+        let codes: Vec<String> = universe.into_list(&options).into_iter().map(|s| s.security.1).collect();
+        assert_eq!(codes, ["00001", "00700", "09988"]);
+    }
+
+    #[tokio::test]
+    async fn test_get_all_securities_against_mock() {
+        use prost::Message;
+        use crate::client::mock::MockOpenD;
+        use crate::config::FutuConfig;
+        use crate::generated::qot_common::PlateInfo;
+        use crate::generated::{qot_get_plate_security, qot_get_plate_set};
+
+        let mock = MockOpenD::start().await.unwrap();
+        let config = FutuConfig { port: mock.port(), enable_encryption: false, ..FutuConfig::default() };
+        let mut client = FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+
+        let plate = |code: &str| PlateInfo { plate: Security { market: 1, code: code.to_string() }, ..Default::default() };
+        let plates = qot_get_plate_set::Response {
+            ret_type: 0,
+            s2c: Some(qot_get_plate_set::S2c { plate_info_list: vec![plate("LIST1"), plate("LIST2")] }),
+            ..Default::default()
+        };
+        mock.respond(3204, plates.encode_to_vec());
+        // The mock answers both plates alike
+        let securities = qot_get_plate_security::Response {
+            ret_type: 0,
+            s2c: Some(qot_get_plate_security::S2c { static_info_list: vec![info("00700", 3, None), info("09988", 3, None)] }),
+            ..Default::default()
+        };
+        mock.respond(3205, securities.encode_to_vec());
+
+        let universe = get_all_securities(&client, 1, &UniverseOptions::default()).await.unwrap();
+        assert_eq!(universe.len(), 2);
+        assert_eq!(universe[1].security, (1, "09988".to_string()));
+        assert_eq!(universe[1].plates, ["LIST1", "LIST2"]);
+    }
+}
@@ -0,0 +1,235 @@
+//! Periodic warrant screening that emits ranking changes as events.
+//!
+//! A `WarrantScreen` is a saved Qot_GetWarrant filter. `get_warrant_screen`
+//! pages through its results up to `max_results`, pausing between pages to
+//! stay under the protocol's request frequency limit, and the watcher diffs
+//! successive result lists into additions, removals and rank changes.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::client::FutuClient;
+use crate::generated::qot_get_warrant::WarrantData;
+use super::subscribe::QuoteError;
+
+/// Most warrants Qot_GetWarrant returns per request.
+pub const MAX_PAGE_SIZE: usize = 200;
+
+/// A saved warrant filter and how much of its ranking to watch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WarrantScreen {
+    /// Qot_Common.SortField the ranking is ordered by.
+    pub sort_field: i32,
+    pub ascend: bool,
+    /// Underlying security, or all warrants if None.
+    pub owner: Option<(i32, String)>,
+    /// Qot_Common.WarrantType values; empty for all.
+    pub type_list: Vec<i32>,
+    /// Qot_Common.Issuer values; empty for all.
+    pub issuer_list: Vec<i32>,
+    /// Length of the ranking fetched and compared.
+    pub max_results: usize,
+    /// Pause between page requests.
+    pub page_interval: Duration,
+}
+
+impl Default for WarrantScreen {
+    fn default() -> Self {
+        Self {
+            sort_field: 0,
+            ascend: false,
+            owner: None,
+            type_list: Vec::new(),
+            issuer_list: Vec::new(),
+            max_results: MAX_PAGE_SIZE,
+            page_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Fetch the first `screen.max_results` warrants of the screen, in ranking order.
+pub async fn get_warrant_screen(client: &FutuClient, screen: &WarrantScreen) -> Result<Vec<WarrantData>, QuoteError> {
+    let mut warrants: Vec<WarrantData> = Vec::new();
+    while warrants.len() < screen.max_results {
+        if !warrants.is_empty() {
+            client.clock().sleep(screen.page_interval).await;
+        }
+        let num = (screen.max_results - warrants.len()).min(MAX_PAGE_SIZE);
+        let resp = super::snapshot::get_warrant(
+            client,
+            warrants.len() as i32,
+            num as i32,
+            screen.sort_field,
+            screen.ascend,
+            screen.owner.clone(),
+            screen.type_list.clone(),
+            screen.issuer_list.clone(),
+        ).await?;
+        let Some(s2c) = resp.s2c else { break };
+        let received = s2c.warrant_data_list.len();
+        warrants.extend(s2c.warrant_data_list);
+        if s2c.last_page || received == 0 {
+            break;
+        }
+    }
+    warrants.truncate(screen.max_results);
+    Ok(warrants)
+}
+
+/// Kind of change between two screen results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarrantScreenEventKind {
+    /// The warrant entered the ranking.
+    Added,
+    /// The warrant left the ranking.
+    Removed,
+    /// The warrant moved to another rank.
+    RankChanged,
+}
+
+impl WarrantScreenEventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WarrantScreenEventKind::Added => "added",
+            WarrantScreenEventKind::Removed => "removed",
+            WarrantScreenEventKind::RankChanged => "rank_changed",
+        }
+    }
+}
+
+/// A ranking change, carrying the latest data of the warrant (the last
+/// seen data for removals). Ranks start at 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WarrantScreenEvent {
+    pub kind: WarrantScreenEventKind,
+    pub rank: Option<usize>,
+    pub prev_rank: Option<usize>,
+    pub warrant: WarrantData,
+}
+
+/// Diffs successive screen results. The first result is reported as
+/// additions, so a watcher starts with the whole ranking.
+#[derive(Debug, Default)]
+pub struct WarrantScreenTracker {
+    ranks: HashMap<(i32, String), (usize, WarrantData)>,
+}
+
+impl WarrantScreenTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compare `warrants` (in ranking order) with the previous result.
+    pub fn diff(&mut self, warrants: &[WarrantData]) -> Vec<WarrantScreenEvent> {
+        let mut previous = std::mem::take(&mut self.ranks);
+        let mut events = Vec::new();
+
+        for (i, warrant) in warrants.iter().enumerate() {
+            let rank = i + 1;
+            let key = (warrant.stock.market, warrant.stock.code.clone());
+            let event = match previous.remove(&key) {
+                None => Some((WarrantScreenEventKind::Added, None)),
+                Some((prev, _)) if prev != rank => Some((WarrantScreenEventKind::RankChanged, Some(prev))),
+                Some(_) => None,
+            };
+            if let Some((kind, prev_rank)) = event {
+                events.push(WarrantScreenEvent { kind, rank: Some(rank), prev_rank, warrant: warrant.clone() });
+            }
+            self.ranks.insert(key, (rank, warrant.clone()));
+        }
+
+        let mut removed: Vec<(usize, WarrantData)> = previous.into_values().collect();
+        removed.sort_by_key(|(rank, _)| *rank);
+        events.extend(removed.into_iter().map(|(prev, warrant)| WarrantScreenEvent {
+            kind: WarrantScreenEventKind::Removed,
+            rank: None,
+            prev_rank: Some(prev),
+            warrant,
+        }));
+        events
+    }
+}
+
+/// Re-run `screen` every `interval` and send its ranking changes. Returns
+/// when `tx` is closed.
+pub async fn run_warrant_watcher(
+    client: Arc<FutuClient>,
+    screen: WarrantScreen,
+    interval: Duration,
+    tx: mpsc::UnboundedSender<WarrantScreenEvent>,
+) {
+    let mut tracker = WarrantScreenTracker::new();
+    let clock = Arc::clone(client.clock());
+    let mut next_run = clock.now();
+
+    loop {
+        clock.sleep_until(next_run).await;
+        next_run = clock.now() + interval;
+        match get_warrant_screen(&client, &screen).await {
+            Ok(warrants) => {
+                for event in tracker.diff(&warrants) {
+                    if tx.send(event).is_err() {
+                        tracing::debug!("Warrant watcher stopped");
+                        return;
+                    }
+                }
+            }
+            // A failed run keeps the previous ranking, so nothing is reported as removed
+            Err(e) => tracing::warn!("Warrant screen failed: {}", e),
+        }
+        if tx.is_closed() {
+            break;
+        }
+    }
+    tracing::debug!("Warrant watcher stopped");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generated::qot_common::Security;
+
+    fn warrant(code: &str, cur_price: f64) -> WarrantData {
+        WarrantData {
+            stock: Security { market: 1, code: code.to_string() },
+            cur_price,
+            ..Default::default()
+        }
+    }
+
+    fn summary(events: &[WarrantScreenEvent]) -> Vec<(&'static str, &str, Option<usize>, Option<usize>)> {
+        events
+            .iter()
+            .map(|e| (e.kind.as_str(), e.warrant.stock.code.as_str(), e.rank, e.prev_rank))
+            .collect()
+    }
+
+    #[test]
+    fn test_first_result_is_added() {
+        let mut tracker = WarrantScreenTracker::new();
+        let events = tracker.diff(&[warrant("12345", 0.1), warrant("23456", 0.2)]);
+        assert_eq!(summary(&events), vec![("added", "12345", Some(1), None), ("added", "23456", Some(2), None)]);
+        assert!(tracker.diff(&[warrant("12345", 0.11), warrant("23456", 0.2)]).is_empty());
+    }
+
+    #[test]
+    fn test_ranking_changes() {
+        let mut tracker = WarrantScreenTracker::new();
+        tracker.diff(&[warrant("A", 1.0), warrant("B", 1.0), warrant("C", 1.0)]);
+        let events = tracker.diff(&[warrant("C", 1.2), warrant("A", 1.0), warrant("D", 0.5)]);
+        assert_eq!(
+            summary(&events),
+            vec![
+                ("rank_changed", "C", Some(1), Some(3)),
+                ("rank_changed", "A", Some(2), Some(1)),
+                ("added", "D", Some(3), None),
+                ("removed", "B", None, Some(2)),
+            ],
+        );
+        assert_eq!(events[0].warrant.cur_price, 1.2);
+        // Removed warrants carry their last seen data
+        assert_eq!(events[3].warrant.cur_price, 1.0);
+    }
+}
@@ -12,7 +12,35 @@ pub enum TradeError {
     #[error("decode error: {0}")]
     Decode(String),
     #[error("server error (retType={ret_type}): {msg}")]
-    Server { ret_type: i32, msg: String },
+    Server { ret_type: i32, err_code: Option<i32>, msg: String },
+    #[error("account {0} not found in account list")]
+    AccountNotFound(u64),
+    #[error("security firm {given} does not match account {acc_id} (security firm {expected})")]
+    SecurityFirmMismatch { acc_id: u64, expected: i32, given: i32 },
+    #[error("real accounts of security firms {0:?}; give acc_id or security_firm")]
+    AmbiguousSecurityFirm(Vec<i32>),
+    #[error(transparent)]
+    SessionPreempted(#[from] crate::client::session::SessionPreempted),
+    #[error("invalid time range: {0}")]
+    InvalidRange(String),
+    #[error("throttled by OpenD, retry after {retry_after:?}: {msg}")]
+    Throttled { retry_after: std::time::Duration, msg: String },
+    #[error("unsupported: {0}")]
+    Unsupported(String),
+    #[error("{0} needs funds protocols to be enabled")]
+    FundsDisabled(&'static str),
+}
+
+impl TradeError {
+    /// Error of a failed response: `Throttled` if OpenD was busy or over a
+    /// frequency limit, `Server` otherwise.
+    pub fn server(ret_type: i32, ret_msg: Option<String>, err_code: Option<i32>) -> Self {
+        let msg = ret_msg.unwrap_or_default();
+        match crate::client::flow_control::throttle_delay(ret_type, &msg) {
+            Some(retry_after) => TradeError::Throttled { retry_after, msg },
+            None => TradeError::Server { ret_type, err_code, msg },
+        }
+    }
 }
 
 /// Get the list of trading accounts.
@@ -33,14 +61,11 @@ pub async fn get_acc_list(
     let resp = client.request(PROTO_TRD_GET_ACC_LIST, &body).await
         .map_err(TradeError::Connection)?;
 
-    let response = crate::generated::trd_get_acc_list::Response::decode(resp.body.as_slice())
-        .map_err(|e| TradeError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::trd_get_acc_list::Response>(&resp)
+        .map_err(TradeError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(TradeError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(TradeError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -64,19 +89,69 @@ pub async fn unlock_trade(
     let resp = client.request(PROTO_TRD_UNLOCK_TRADE, &body).await
         .map_err(TradeError::Connection)?;
 
-    let response = crate::generated::trd_unlock_trade::Response::decode(resp.body.as_slice())
-        .map_err(|e| TradeError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::trd_unlock_trade::Response>(&resp)
+        .map_err(TradeError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(TradeError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(TradeError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(())
 }
 
+/// Pick the security firm to unlock with from the account list.
+///
+/// With `acc_id`, an explicit `security_firm` must match that account's
+/// firm; without one the account's firm is used. Without `acc_id` the firms
+/// of all real accounts are considered: an explicit `security_firm` must be
+/// one of them, and without one they must all be the same firm. Returns
+/// `None` when nothing is known, leaving the choice to OpenD.
+pub fn resolve_security_firm(
+    acc_list: &[crate::generated::trd_common::TrdAcc],
+    acc_id: Option<u64>,
+    security_firm: Option<i32>,
+) -> Result<Option<i32>, TradeError> {
+    let firm_of = |a: &crate::generated::trd_common::TrdAcc| a.security_firm.filter(|f| *f > 0).map(|f| (a.acc_id, f));
+    let candidates: Vec<(u64, i32)> = match acc_id {
+        Some(id) => {
+            let account = acc_list.iter().find(|a| a.acc_id == id).ok_or(TradeError::AccountNotFound(id))?;
+            firm_of(account).into_iter().collect()
+        }
+        None => acc_list.iter().filter(|a| a.trd_env == 1).filter_map(firm_of).collect(),
+    };
+    let mut firms: Vec<i32> = candidates.iter().map(|(_, f)| *f).collect();
+    firms.sort_unstable();
+    firms.dedup();
+
+    match (security_firm, candidates.first()) {
+        (Some(given), Some(&(acc_id, expected))) if !firms.contains(&given) => {
+            Err(TradeError::SecurityFirmMismatch { acc_id, expected, given })
+        }
+        (Some(given), _) => Ok(Some(given)),
+        (None, _) if firms.len() > 1 => Err(TradeError::AmbiguousSecurityFirm(firms)),
+        (None, _) => Ok(firms.first().copied()),
+    }
+}
+
+/// Unlock trading with the security firm detected (or validated) from the account list.
+pub async fn unlock_trade_for_account(
+    client: &FutuClient,
+    unlock: bool,
+    pwd_md5: String,
+    acc_id: Option<u64>,
+    security_firm: Option<i32>,
+) -> Result<Option<i32>, TradeError> {
+    let user_id = client.init_response().map(|r| r.login_user_id).unwrap_or(0);
+    let acc_list = get_acc_list(client, user_id, None, None)
+        .await?
+        .s2c
+        .map(|s| s.acc_list)
+        .unwrap_or_default();
+    let security_firm = resolve_security_firm(&acc_list, acc_id, security_firm)?;
+    unlock_trade(client, unlock, pwd_md5, security_firm).await?;
+    Ok(security_firm)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -119,4 +194,57 @@ mod tests {
         );
         assert_eq!(decoded.c2s.security_firm, Some(1));
     }
+
+    fn acc(acc_id: u64, trd_env: i32, security_firm: Option<i32>) -> crate::generated::trd_common::TrdAcc {
+        crate::generated::trd_common::TrdAcc {
+            trd_env,
+            acc_id,
+            security_firm,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_resolve_security_firm_detects() {
+        let accs = vec![acc(1, 0, None), acc(2, 1, Some(3)), acc(3, 1, Some(1))];
+        assert_eq!(resolve_security_firm(&accs, Some(3), None).unwrap(), Some(1));
+        // Simulated account without a firm leaves the choice to OpenD
+        assert_eq!(resolve_security_firm(&accs, Some(1), None).unwrap(), None);
+        assert_eq!(resolve_security_firm(&accs, Some(1), Some(2)).unwrap(), Some(2));
+    }
+
+    #[test]
+    fn test_resolve_security_firm_checks_all_accounts() {
+        let accs = vec![acc(1, 0, None), acc(2, 1, Some(3)), acc(3, 1, Some(1)), acc(4, 1, Some(3))];
+        // Any real account's firm is accepted, not only the first one's
+        assert_eq!(resolve_security_firm(&accs, None, Some(1)).unwrap(), Some(1));
+        assert_eq!(resolve_security_firm(&accs, None, Some(3)).unwrap(), Some(3));
+        assert!(matches!(
+            resolve_security_firm(&accs, None, Some(2)),
+            Err(TradeError::SecurityFirmMismatch { given: 2, .. })
+        ));
+        match resolve_security_firm(&accs, None, None) {
+            Err(TradeError::AmbiguousSecurityFirm(firms)) => assert_eq!(firms, vec![1, 3]),
+            other => panic!("expected ambiguity, got {:?}", other),
+        }
+        // Accounts of one firm agree
+        assert_eq!(resolve_security_firm(&accs[..2], None, None).unwrap(), Some(3));
+        assert_eq!(resolve_security_firm(&[acc(1, 0, None)], None, None).unwrap(), None);
+    }
+
+    #[test]
+    fn test_resolve_security_firm_mismatch() {
+        let accs = vec![acc(2, 1, Some(3))];
+        assert_eq!(resolve_security_firm(&accs, Some(2), Some(3)).unwrap(), Some(3));
+        match resolve_security_firm(&accs, Some(2), Some(1)) {
+            Err(TradeError::SecurityFirmMismatch { acc_id, expected, given }) => {
+                assert_eq!((acc_id, expected, given), (2, 3, 1));
+            }
+            other => panic!("expected mismatch, got {:?}", other),
+        }
+        assert!(matches!(
+            resolve_security_firm(&accs, Some(9), None),
+            Err(TradeError::AccountNotFound(9))
+        ));
+    }
 }
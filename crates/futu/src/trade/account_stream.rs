@@ -0,0 +1,299 @@
+//! One stream of all state changes of a trading account.
+//!
+//! `FutuClient::account_stream` combines the account's Trd_UpdateOrder and
+//! Trd_UpdateOrderFill pushes, delivered in order through a
+//! `TradeSequencer`, with periodic Trd_GetFunds and Trd_GetPositionList
+//! polls. OpenD pushes no funds or position updates, so `AccountReconciler`
+//! diffs successive polls into `FundsChanged` and `PositionChanged` events.
+//! A fill also brings the next poll forward, so the position it changed is
+//! reported shortly after it. The first poll reports the current funds and
+//! every open position.
+
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::client::FutuClient;
+use crate::generated::trd_common::{Funds, Order, OrderFill, Position};
+use crate::protocol::FutuMessage;
+use super::sequence::{TradeEvent, TradeSequencer, DEFAULT_MAX_HOLD, PROTO_TRD_UPDATE_ORDER, PROTO_TRD_UPDATE_ORDER_FILL};
+
+/// (trd_env, acc_id, trd_market)
+pub type Account = (i32, u64, i32);
+
+/// Delay from a fill to the poll that picks up its position change.
+const FILL_RECONCILE_DELAY: Duration = Duration::from_millis(500);
+
+/// A state change of the account.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AccountEvent {
+    /// An order was pushed.
+    OrderUpdate(Order),
+    /// A fill was pushed; it always follows the first update of its order.
+    FillUpdate(OrderFill),
+    /// Cash, frozen cash, withdrawable cash or debt changed.
+    FundsChanged(Funds),
+    /// A position opened, changed quantity or cost, or closed; a closed
+    /// position is reported with its last data and a qty of 0.
+    PositionChanged(Position),
+}
+
+/// How `account_stream` polls.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountStreamOptions {
+    /// Time between funds and position polls.
+    pub poll_interval: Duration,
+    /// Trd_Common.Currency of the funds, for universal accounts.
+    pub currency: Option<i32>,
+    /// Subscribe the account to trade pushes. Trd_SubAccPush replaces the
+    /// accounts the connection receives pushes for, so turn this off when
+    /// they are subscribed elsewhere, with this account among them.
+    pub sub_acc_push: bool,
+}
+
+impl Default for AccountStreamOptions {
+    fn default() -> Self {
+        Self { poll_interval: Duration::from_secs(5), currency: None, sub_acc_push: true }
+    }
+}
+
+/// Diffs successive funds and position polls. Only changes a strategy acts
+/// on are reported, not those from marking to market.
+#[derive(Debug, Default)]
+pub struct AccountReconciler {
+    funds: Option<Funds>,
+    positions: HashMap<u64, Position>,
+}
+
+impl AccountReconciler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compare polled funds with the previous poll.
+    pub fn funds(&mut self, funds: Funds) -> Option<AccountEvent> {
+        let key = |f: &Funds| (f.cash, f.frozen_cash, f.avl_withdrawal_cash, f.debt_cash);
+        let changed = self.funds.as_ref().is_none_or(|prev| key(prev) != key(&funds));
+        self.funds = Some(funds.clone());
+        changed.then_some(AccountEvent::FundsChanged(funds))
+    }
+
+    /// Compare polled positions with the previous poll.
+    pub fn positions(&mut self, positions: Vec<Position>) -> Vec<AccountEvent> {
+        let key = |p: &Position| (p.position_side, p.qty, p.can_sell_qty, p.cost_price);
+        let mut previous = std::mem::take(&mut self.positions);
+        let mut events = Vec::new();
+        for position in positions {
+            let changed = match previous.remove(&position.position_id) {
+                Some(prev) => key(&prev) != key(&position),
+                // Positions closed before the first poll may be listed with qty 0
+                None => position.qty != 0.0,
+            };
+            if changed {
+                events.push(AccountEvent::PositionChanged(position.clone()));
+            }
+            self.positions.insert(position.position_id, position);
+        }
+        let mut closed: Vec<Position> = previous.into_values().filter(|p| p.qty != 0.0).collect();
+        closed.sort_by_key(|p| p.position_id);
+        events.extend(closed.into_iter().map(|mut p| {
+            p.qty = 0.0;
+            p.can_sell_qty = 0.0;
+            AccountEvent::PositionChanged(p)
+        }));
+        events
+    }
+}
+
+/// Events of one account; see the module docs. Dropping the stream stops it.
+pub struct AccountStream {
+    rx: mpsc::UnboundedReceiver<AccountEvent>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl AccountStream {
+    /// Start streaming `account`. Must be called within a tokio runtime.
+    pub fn new(client: Arc<FutuClient>, account: Account, options: AccountStreamOptions) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run_account_stream(client, account, options, tx));
+        Self { rx, task }
+    }
+
+    /// The next event, or None once the stream has stopped.
+    pub async fn recv(&mut self) -> Option<AccountEvent> {
+        self.rx.recv().await
+    }
+}
+
+impl futures::Stream for AccountStream {
+    type Item = AccountEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<AccountEvent>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+impl Drop for AccountStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// The event of a trade push if it belongs to `acc_id`, with its
+/// sequencing role.
+fn push_event(client: &FutuClient, acc_id: u64, msg: &FutuMessage) -> Option<(Option<TradeEvent>, AccountEvent)> {
+    match msg.proto_id {
+        PROTO_TRD_UPDATE_ORDER => {
+            let s2c = client.decode::<crate::generated::trd_update_order::Response>(msg).ok()?.s2c?;
+            if s2c.header.acc_id != acc_id {
+                return None;
+            }
+            Some((Some(TradeEvent::Order((acc_id, s2c.order.order_id))), AccountEvent::OrderUpdate(s2c.order)))
+        }
+        PROTO_TRD_UPDATE_ORDER_FILL => {
+            let s2c = client.decode::<crate::generated::trd_update_order_fill::Response>(msg).ok()?.s2c?;
+            if s2c.header.acc_id != acc_id {
+                return None;
+            }
+            // Fills without an order id cannot be sequenced and pass as they come
+            let event = s2c.order_fill.order_id.map(|order_id| TradeEvent::Fill((acc_id, order_id)));
+            Some((event, AccountEvent::FillUpdate(s2c.order_fill)))
+        }
+        _ => None,
+    }
+}
+
+async fn poll_account(
+    client: &FutuClient,
+    account: Account,
+    options: &AccountStreamOptions,
+    reconciler: &mut AccountReconciler,
+) -> Vec<AccountEvent> {
+    let (trd_env, acc_id, trd_market) = account;
+    let (funds, positions) = tokio::join!(
+        super::query::get_funds(client, trd_env, acc_id, trd_market, options.currency),
+        super::query::get_position_list(client, trd_env, acc_id, trd_market, None),
+    );
+    let mut events = Vec::new();
+    match funds {
+        Ok(resp) => events.extend(resp.s2c.and_then(|s| s.funds).and_then(|f| reconciler.funds(f))),
+        Err(e) => tracing::warn!("Funds poll of account {} failed: {}", acc_id, e),
+    }
+    match positions {
+        Ok(resp) => events.extend(reconciler.positions(resp.s2c.map(|s| s.position_list).unwrap_or_default())),
+        // A failed poll keeps the previous positions, so nothing is reported as closed
+        Err(e) => tracing::warn!("Position poll of account {} failed: {}", acc_id, e),
+    }
+    events
+}
+
+async fn run_account_stream(
+    client: Arc<FutuClient>,
+    account: Account,
+    options: AccountStreamOptions,
+    tx: mpsc::UnboundedSender<AccountEvent>,
+) {
+    let acc_id = account.1;
+    if options.sub_acc_push {
+        if let Err(e) = super::push::sub_acc_push(&client, vec![acc_id]).await {
+            tracing::warn!("Account {} push subscription failed, only polling: {}", acc_id, e);
+        }
+    }
+    let mut orders = client.subscribe_push(PROTO_TRD_UPDATE_ORDER).await;
+    let mut fills = client.subscribe_push(PROTO_TRD_UPDATE_ORDER_FILL).await;
+    let (mut orders_open, mut fills_open) = (true, true);
+
+    let clock = Arc::clone(client.clock());
+    let flush_interval = Duration::from_secs_f64(DEFAULT_MAX_HOLD / 4.0);
+    let mut sequencer: TradeSequencer<AccountEvent> = TradeSequencer::default();
+    let mut reconciler = AccountReconciler::new();
+    let mut next_poll = clock.now();
+    let mut next_flush = clock.now() + flush_interval;
+
+    loop {
+        let mut out = Vec::new();
+        tokio::select! {
+            _ = clock.sleep_until(next_poll) => {
+                out = poll_account(&client, account, &options, &mut reconciler).await;
+                next_poll = clock.now() + options.poll_interval;
+            }
+            _ = clock.sleep_until(next_flush) => {
+                out = sequencer.flush_expired(clock.unix_time());
+                next_flush = clock.now() + flush_interval;
+            }
+            msg = orders.recv(), if orders_open => match msg {
+                Some(msg) => {
+                    if let Some((event, item)) = push_event(&client, acc_id, &msg) {
+                        out = sequencer.push(event, item, clock.unix_time());
+                    }
+                }
+                None => orders_open = false,
+            },
+            msg = fills.recv(), if fills_open => match msg {
+                Some(msg) => {
+                    if let Some((event, item)) = push_event(&client, acc_id, &msg) {
+                        out = sequencer.push(event, item, clock.unix_time());
+                        next_poll = next_poll.min(clock.now() + FILL_RECONCILE_DELAY);
+                    }
+                }
+                None => fills_open = false,
+            },
+            _ = tx.closed() => break,
+        }
+        for event in out {
+            if tx.send(event).is_err() {
+                break;
+            }
+        }
+    }
+    tracing::debug!("Account stream of {} stopped", acc_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(id: u64, qty: f64, price: f64) -> Position {
+        Position { position_id: id, code: format!("{:05}", id), qty, can_sell_qty: qty, price, ..Default::default() }
+    }
+
+    fn funds(cash: f64, market_val: f64) -> Funds {
+        Funds { cash, market_val, ..Default::default() }
+    }
+
+    fn changed_positions(events: &[AccountEvent]) -> Vec<(u64, f64)> {
+        events
+            .iter()
+            .filter_map(|e| match e {
+                AccountEvent::PositionChanged(p) => Some((p.position_id, p.qty)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_funds_changes() {
+        let mut reconciler = AccountReconciler::new();
+        assert!(reconciler.funds(funds(1000.0, 500.0)).is_some());
+        // Marking to market is not a change
+        assert!(reconciler.funds(funds(1000.0, 520.0)).is_none());
+        assert_eq!(reconciler.funds(funds(900.0, 620.0)), Some(AccountEvent::FundsChanged(funds(900.0, 620.0))));
+    }
+
+    #[test]
+    fn test_position_changes() {
+        let mut reconciler = AccountReconciler::new();
+        let events = reconciler.positions(vec![position(1, 100.0, 10.0), position(2, 200.0, 20.0), position(3, 0.0, 1.0)]);
+        assert_eq!(changed_positions(&events), vec![(1, 100.0), (2, 200.0)]);
+
+        // Price moves are ignored; qty changes, new and closed positions are reported
+        let events = reconciler.positions(vec![position(1, 100.0, 11.0), position(2, 300.0, 20.0), position(4, 50.0, 4.0)]);
+        assert_eq!(changed_positions(&events), vec![(2, 300.0), (4, 50.0)]);
+        let events = reconciler.positions(vec![position(2, 0.0, 20.0), position(4, 50.0, 4.0)]);
+        assert_eq!(changed_positions(&events), vec![(2, 0.0), (1, 0.0)]);
+        assert!(reconciler.positions(vec![position(4, 50.0, 4.0)]).is_empty());
+    }
+}
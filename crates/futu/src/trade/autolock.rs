@@ -0,0 +1,193 @@
+//! Placing an order while trading is locked.
+//!
+//! OpenD rejects orders of a real account until Trd_UnlockTrade succeeds,
+//! and unlocks expire when OpenD restarts or the password is changed
+//! elsewhere. `place_order_autolock` places the order and, when OpenD
+//! answers that trading is locked, unlocks with stored credentials and
+//! retries exactly once. Every step is reported in `AutolockOutcome`, so
+//! an unlock is never silent.
+
+use std::fmt;
+use std::future::Future;
+
+use crate::client::FutuClient;
+use super::account::TradeError;
+
+/// Unlock password kept for `place_order_autolock`.
+#[derive(Clone, PartialEq)]
+pub struct UnlockCredentials {
+    pub pwd_md5: String,
+    /// Security firm to unlock with; None detects it from the account.
+    pub security_firm: Option<i32>,
+}
+
+impl fmt::Debug for UnlockCredentials {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UnlockCredentials")
+            .field("pwd_md5", &"<redacted>")
+            .field("security_firm", &self.security_firm)
+            .finish()
+    }
+}
+
+/// A step of the place, unlock, retry sequence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AutolockStep {
+    Place,
+    Unlock,
+    Retry,
+}
+
+impl AutolockStep {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AutolockStep::Place => "place",
+            AutolockStep::Unlock => "unlock",
+            AutolockStep::Retry => "retry",
+        }
+    }
+}
+
+/// A step taken and its error, None if it succeeded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AutolockAttempt {
+    pub step: AutolockStep,
+    pub error: Option<String>,
+}
+
+/// Result of the last step taken, with every step in order.
+#[derive(Debug)]
+pub struct AutolockOutcome<T> {
+    pub result: Result<T, TradeError>,
+    pub steps: Vec<AutolockAttempt>,
+}
+
+impl<T> AutolockOutcome<T> {
+    /// Whether trading was unlocked on the way.
+    pub fn unlocked(&self) -> bool {
+        self.steps.iter().any(|s| s.step == AutolockStep::Unlock && s.error.is_none())
+    }
+}
+
+/// Whether OpenD rejected a request because trading is locked.
+pub fn is_trade_locked(error: &TradeError) -> bool {
+    match error {
+        TradeError::Server { msg, .. } => {
+            let msg = msg.to_lowercase();
+            msg.contains("解锁") || msg.contains("unlock")
+        }
+        _ => false,
+    }
+}
+
+/// Place an order with `place`, unlocking with `credentials` and retrying
+/// once if trading is locked. Without credentials the locked error is
+/// returned as is. `acc_id` selects the security firm to unlock with when
+/// the credentials name none.
+pub async fn place_order_autolock<T, F, Fut>(
+    client: &FutuClient,
+    acc_id: u64,
+    credentials: Option<&UnlockCredentials>,
+    place: F,
+) -> AutolockOutcome<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, TradeError>>,
+{
+    autolock_sequence(credentials, place, |credentials| async move {
+        super::account::unlock_trade_for_account(
+            client, true, credentials.pwd_md5.clone(), Some(acc_id), credentials.security_firm,
+        ).await.map(|_| ())
+    }).await
+}
+
+async fn autolock_sequence<'c, T, F, Fut, U, UFut>(
+    credentials: Option<&'c UnlockCredentials>,
+    mut place: F,
+    unlock: U,
+) -> AutolockOutcome<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, TradeError>>,
+    U: FnOnce(&'c UnlockCredentials) -> UFut,
+    UFut: Future<Output = Result<(), TradeError>>,
+{
+    fn attempt<R>(step: AutolockStep, result: &Result<R, TradeError>) -> AutolockAttempt {
+        AutolockAttempt { step, error: result.as_ref().err().map(|e| e.to_string()) }
+    }
+    let mut steps = Vec::new();
+
+    let result = place().await;
+    steps.push(attempt(AutolockStep::Place, &result));
+    let Some(credentials) = credentials.filter(|_| result.as_ref().is_err_and(is_trade_locked)) else {
+        return AutolockOutcome { result, steps };
+    };
+
+    tracing::info!("Trading is locked, unlocking and retrying the order");
+    let unlocked = unlock(credentials).await;
+    steps.push(attempt(AutolockStep::Unlock, &unlocked));
+    if let Err(e) = unlocked {
+        tracing::warn!("Unlock before retrying the order failed: {}", e);
+        return AutolockOutcome { result, steps };
+    }
+
+    let result = place().await;
+    steps.push(attempt(AutolockStep::Retry, &result));
+    AutolockOutcome { result, steps }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locked() -> TradeError {
+        TradeError::Server { ret_type: -1, err_code: None, msg: "请先解锁交易".to_string() }
+    }
+
+    fn credentials() -> UnlockCredentials {
+        UnlockCredentials { pwd_md5: "d41d8cd98f00b204e9800998ecf8427e".to_string(), security_firm: None }
+    }
+
+    fn steps<T>(outcome: &AutolockOutcome<T>) -> Vec<(AutolockStep, bool)> {
+        outcome.steps.iter().map(|s| (s.step, s.error.is_none())).collect()
+    }
+
+    #[tokio::test]
+    async fn test_unlocks_and_retries_once() {
+        let creds = credentials();
+        let mut calls = 0;
+        let outcome = autolock_sequence(Some(&creds), || {
+            calls += 1;
+            let result = if calls == 1 { Err(locked()) } else { Ok(42u64) };
+            async move { result }
+        }, |_| async { Ok(()) }).await;
+        assert_eq!(steps(&outcome), vec![(AutolockStep::Place, false), (AutolockStep::Unlock, true), (AutolockStep::Retry, true)]);
+        assert!(outcome.unlocked());
+        assert_eq!(outcome.result.unwrap(), 42);
+
+        // Still locked after unlocking: no second retry
+        let outcome = autolock_sequence(Some(&creds), || async { Err::<u64, _>(locked()) }, |_| async { Ok(()) }).await;
+        assert!(outcome.result.is_err());
+        assert_eq!(outcome.steps.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_no_unlock_without_credentials_or_lock() {
+        let outcome = autolock_sequence(None, || async { Err::<u64, _>(locked()) }, |_| async { Ok(()) }).await;
+        assert!(is_trade_locked(outcome.result.as_ref().unwrap_err()));
+        assert_eq!(steps(&outcome), vec![(AutolockStep::Place, false)]);
+
+        let creds = credentials();
+        let outcome = autolock_sequence(Some(&creds), || async {
+            Err::<u64, _>(TradeError::Server { ret_type: -1, err_code: None, msg: "insufficient buying power".to_string() })
+        }, |_| async { Ok(()) }).await;
+        assert_eq!(steps(&outcome), vec![(AutolockStep::Place, false)]);
+
+        let outcome = autolock_sequence(Some(&creds), || async { Err::<u64, _>(locked()) }, |_| async {
+            Err(TradeError::Server { ret_type: -1, err_code: None, msg: "wrong password".to_string() })
+        }).await;
+        assert!(!outcome.unlocked());
+        assert_eq!(steps(&outcome), vec![(AutolockStep::Place, false), (AutolockStep::Unlock, false)]);
+        assert!(!format!("{:?}", creds).contains(&creds.pwd_md5));
+    }
+}
@@ -0,0 +1,287 @@
+//! Order fill history over long date ranges.
+//!
+//! Trd_GetHistoryOrderFillList caps the number of fills per response and
+//! drops the rest without saying so. `get_history_fills` splits the range
+//! into windows, fetches a few of them at a time, and halves any window that
+//! comes back full until its fills fit or the window cannot shrink further.
+//! Adjacent windows share their boundary second, so fills are deduplicated
+//! by `fill_id_ex` and then ordered by `create_timestamp`.
+
+use std::collections::{HashSet, VecDeque};
+use std::future::Future;
+use futures::stream::{FuturesUnordered, StreamExt};
+
+use crate::client::FutuClient;
+use crate::generated::trd_common::{OrderFill, TrdFilterConditions};
+use crate::dates::{civil_from_days, days_from_civil};
+use super::account::TradeError;
+
+const SECS_PER_DAY: i64 = 86_400;
+
+/// How a fill history range is split and fetched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FillHistoryOptions {
+    /// Length of the initial windows, in seconds.
+    pub window_secs: i64,
+    /// Windows requested concurrently.
+    pub concurrency: usize,
+    /// Fill count at which a response is taken as truncated.
+    pub page_limit: usize,
+    /// Full windows are not split below this length, in seconds.
+    pub min_window_secs: i64,
+}
+
+impl Default for FillHistoryOptions {
+    fn default() -> Self {
+        Self {
+            window_secs: 7 * SECS_PER_DAY,
+            concurrency: 2,
+            page_limit: 1000,
+            min_window_secs: 60,
+        }
+    }
+}
+
+/// Fills of a range, with whether every window fit within the page limit.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FillHistory {
+    /// Unique fills ordered by create_timestamp.
+    pub fills: Vec<OrderFill>,
+    /// False if a window was still full at the minimum window length, so
+    /// fills may be missing.
+    pub is_complete: bool,
+    /// Requests made, including windows that were split.
+    pub requests: usize,
+}
+
+/// Fetch the fills between `begin` and `end` ("YYYY-MM-DD HH:MM:SS" or
+/// "YYYY-MM-DD", both inclusive) window by window.
+#[allow(clippy::too_many_arguments)]
+pub async fn get_history_fills(
+    client: &FutuClient,
+    trd_env: i32,
+    acc_id: u64,
+    trd_market: i32,
+    begin: &str,
+    end: &str,
+    code_list: Vec<String>,
+    options: &FillHistoryOptions,
+) -> Result<FillHistory, TradeError> {
+    let range = parse_range(begin, end)?;
+    fetch_windows(range, options, |from, to| {
+        let filter = TrdFilterConditions {
+            code_list: code_list.clone(),
+            begin_time: Some(format_time(from)),
+            end_time: Some(format_time(to)),
+            ..Default::default()
+        };
+        async move {
+            let resp = super::query::get_history_order_fill_list(client, trd_env, acc_id, trd_market, Some(filter)).await?;
+            Ok(resp.s2c.map(|s| s.order_fill_list).unwrap_or_default())
+        }
+    })
+    .await
+}
+
+/// Fetch `range` (seconds, inclusive) through `fetch`, splitting full windows.
+async fn fetch_windows<F, Fut>(range: (i64, i64), options: &FillHistoryOptions, fetch: F) -> Result<FillHistory, TradeError>
+where
+    F: Fn(i64, i64) -> Fut,
+    Fut: Future<Output = Result<Vec<OrderFill>, TradeError>>,
+{
+    let (begin, end) = range;
+    let step = options.window_secs.max(1);
+    let mut queue: VecDeque<(i64, i64)> = VecDeque::new();
+    let mut from = begin;
+    loop {
+        let to = from.saturating_add(step).min(end);
+        queue.push_back((from, to));
+        if to >= end {
+            break;
+        }
+        from = to;
+    }
+
+    let mut history = FillHistory { is_complete: true, ..Default::default() };
+    let mut in_flight = FuturesUnordered::new();
+    loop {
+        while in_flight.len() < options.concurrency.max(1) {
+            let Some((from, to)) = queue.pop_front() else {
+                break;
+            };
+            let fut = fetch(from, to);
+            in_flight.push(async move { (from, to, fut.await) });
+        }
+        let Some((from, to, result)) = in_flight.next().await else {
+            break;
+        };
+        history.requests += 1;
+        let fills = result?;
+        if fills.len() >= options.page_limit {
+            if to - from > options.min_window_secs {
+                let mid = from + (to - from) / 2;
+                queue.push_back((from, mid));
+                queue.push_back((mid, to));
+                continue;
+            }
+            tracing::warn!(
+                "History fills {} to {} still fill a page at the minimum window, some may be missing",
+                format_time(from), format_time(to)
+            );
+            history.is_complete = false;
+        }
+        history.fills.extend(fills);
+    }
+
+    dedup_and_sort(&mut history.fills);
+    Ok(history)
+}
+
+/// Drop repeated fills (by fill_id_ex, or fill_id when it is empty) and order
+/// by create_timestamp, falling back to create_time and fill_id.
+fn dedup_and_sort(fills: &mut Vec<OrderFill>) {
+    let mut seen = HashSet::new();
+    fills.retain(|f| {
+        let key = if f.fill_id_ex.is_empty() { f.fill_id.to_string() } else { f.fill_id_ex.clone() };
+        seen.insert(key)
+    });
+    fills.sort_by(|a, b| {
+        let ts = |f: &OrderFill| f.create_timestamp.unwrap_or(f64::INFINITY);
+        ts(a).total_cmp(&ts(b))
+            .then_with(|| a.create_time.cmp(&b.create_time))
+            .then_with(|| a.fill_id.cmp(&b.fill_id))
+    });
+}
+
+fn parse_range(begin: &str, end: &str) -> Result<(i64, i64), TradeError> {
+    let invalid = |s: &str| TradeError::InvalidRange(format!("cannot parse time '{}'", s));
+    let begin_secs = parse_time(begin, false).ok_or_else(|| invalid(begin))?;
+    let end_secs = parse_time(end, true).ok_or_else(|| invalid(end))?;
+    if end_secs < begin_secs {
+        return Err(TradeError::InvalidRange(format!("end {} is before begin {}", end, begin)));
+    }
+    Ok((begin_secs, end_secs))
+}
+
+/// Seconds since 1970-01-01 00:00:00 of a wall-clock time. A bare date is
+/// the start of the day, or its last second when `end_of_day`. Fractional
+/// seconds are dropped.
+fn parse_time(s: &str, end_of_day: bool) -> Option<i64> {
+    let s = s.trim();
+    let (date, time) = match s.split_once(' ') {
+        Some((date, time)) => (date, Some(time)),
+        None => (s, None),
+    };
+    let mut parts = date.split('-').map(|p| p.parse::<i64>().ok());
+    let (y, m, d) = (parts.next()??, parts.next()??, parts.next()??);
+    if parts.next().is_some() || !(1..=12).contains(&m) || !(1..=31).contains(&d) {
+        return None;
+    }
+    let secs = match time {
+        Some(time) => {
+            let time = time.split('.').next()?;
+            let mut parts = time.split(':').map(|p| p.parse::<i64>().ok());
+            let (h, min, sec) = (parts.next()??, parts.next()??, parts.next()??);
+            if parts.next().is_some() || h > 23 || min > 59 || sec > 59 {
+                return None;
+            }
+            h * 3600 + min * 60 + sec
+        }
+        None if end_of_day => SECS_PER_DAY - 1,
+        None => 0,
+    };
+    Some(days_from_civil(y, m, d) * SECS_PER_DAY + secs)
+}
+
+fn format_time(secs: i64) -> String {
+    let (y, m, d) = civil_from_days(secs.div_euclid(SECS_PER_DAY));
+    let t = secs.rem_euclid(SECS_PER_DAY);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", y, m, d, t / 3600, t % 3600 / 60, t % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn fill(fill_id: u64, ts: i64) -> OrderFill {
+        OrderFill {
+            fill_id,
+            fill_id_ex: format!("F{}", fill_id),
+            create_time: format_time(ts),
+            create_timestamp: Some(ts as f64),
+            ..Default::default()
+        }
+    }
+
+    /// Fake server returning at most `limit` of `fills` within the window.
+    fn server(fills: Vec<OrderFill>, limit: usize) -> impl Fn(i64, i64) -> std::future::Ready<Result<Vec<OrderFill>, TradeError>> {
+        move |from, to| {
+            let in_window = fills.iter()
+                .filter(|f| (from..=to).contains(&(f.create_timestamp.unwrap() as i64)))
+                .take(limit)
+                .cloned()
+                .collect();
+            std::future::ready(Ok(in_window))
+        }
+    }
+
+    #[test]
+    fn test_parse_and_format_time() {
+        let secs = parse_time("2024-03-01 09:30:05.250", false).unwrap();
+        assert_eq!(format_time(secs), "2024-03-01 09:30:05");
+        assert_eq!(format_time(parse_time("2024-02-29", true).unwrap()), "2024-02-29 23:59:59");
+        assert!(parse_time("2024-13-01", false).is_none());
+        assert!(parse_time("2024-01-01 25:00:00", false).is_none());
+        assert!(matches!(parse_range("2024-02-01", "2024-01-01"), Err(TradeError::InvalidRange(_))));
+    }
+
+    #[tokio::test]
+    async fn test_windows_dedup_and_order() {
+        let begin = parse_time("2024-01-01", false).unwrap();
+        // A fill on the boundary of two weekly windows is returned by both
+        let boundary = begin + 7 * SECS_PER_DAY;
+        let fills = vec![fill(3, begin + 20 * SECS_PER_DAY), fill(1, begin + 100), fill(2, boundary)];
+        let calls = Mutex::new(0);
+        let fetch = server(fills, 1000);
+        let history = fetch_windows((begin, begin + 30 * SECS_PER_DAY), &FillHistoryOptions::default(), |from, to| {
+            *calls.lock().unwrap() += 1;
+            fetch(from, to)
+        }).await.unwrap();
+
+        assert_eq!(*calls.lock().unwrap(), 5);
+        assert_eq!(history.requests, 5);
+        assert!(history.is_complete);
+        let ids: Vec<u64> = history.fills.iter().map(|f| f.fill_id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn test_full_windows_are_split() {
+        let begin = 0;
+        let fills: Vec<OrderFill> = (0..10).map(|i| fill(i, begin + i as i64 * 3600)).collect();
+        let options = FillHistoryOptions { window_secs: SECS_PER_DAY, page_limit: 4, ..Default::default() };
+        let history = fetch_windows((begin, begin + SECS_PER_DAY), &options, server(fills, 4)).await.unwrap();
+        assert!(history.is_complete);
+        assert_eq!(history.fills.len(), 10);
+        assert!(history.requests > 1);
+    }
+
+    #[tokio::test]
+    async fn test_truncation_at_minimum_window() {
+        // Five fills in the same second can never fit a page of four
+        let fills: Vec<OrderFill> = (0..5).map(|i| fill(i, 1000)).collect();
+        let options = FillHistoryOptions { page_limit: 4, ..Default::default() };
+        let history = fetch_windows((0, SECS_PER_DAY), &options, server(fills, 4)).await.unwrap();
+        assert!(!history.is_complete);
+        assert_eq!(history.fills.len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_returned() {
+        let history = fetch_windows((0, 10), &FillHistoryOptions::default(), |_, _| async {
+            Err::<Vec<OrderFill>, _>(TradeError::Decode("boom".to_string()))
+        }).await;
+        assert!(matches!(history, Err(TradeError::Decode(_))));
+    }
+}
@@ -0,0 +1,232 @@
+//! Cash flow and funds movement protocols.
+//!
+//! OpenAPI reports an account's cash flows through Trd_FlowSummary, but has
+//! no protocol for transferring funds between accounts, sweeping cash or
+//! exchanging currencies; those are only offered in the Futu apps.
+//! `FundsOperation` records which operations have a protocol, and
+//! `check_funds_operation` refuses the others with `TradeError::Unsupported`
+//! so callers can tell "not possible" from "failed". Funds protocols are off
+//! until enabled with `FutuConfig::enable_funds_protocols` or
+//! `FutuClient::set_funds_protocols`.
+
+use prost::Message;
+use crate::client::rate_limit::RateLimit;
+use crate::client::FutuClient;
+use crate::generated::trd_flow_summary::FlowSummaryInfo;
+use super::account::TradeError;
+
+const PROTO_TRD_FLOW_SUMMARY: u32 = 2226;
+
+/// OpenD's limit on Trd_FlowSummary requests. Unlike the optional
+/// `RateLimitConfig` limits it always applies, as `get_flow_summary` sends
+/// a request per day of its range.
+const FLOW_SUMMARY_LIMIT: RateLimit = RateLimit { requests: 20, window: std::time::Duration::from_secs(30) };
+
+/// Reply of an OpenD that does not know the requested protocol.
+const UNKNOWN_PROTOCOL_PATTERNS: [&str; 4] = ["未知协议", "不支持该协议", "unknown proto", "unsupported proto"];
+
+/// Funds operations users ask for, with or without an OpenAPI protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FundsOperation {
+    /// Cash flow entries of a clearing date (Trd_FlowSummary).
+    CashFlowSummary,
+    /// Moving cash between accounts of the same user.
+    FundsTransfer,
+    /// Automatic sweep of idle cash into a money market fund.
+    CashSweep,
+    /// Converting cash between currencies of a multi-currency account.
+    CurrencyExchange,
+}
+
+impl FundsOperation {
+    pub const ALL: [FundsOperation; 4] = [
+        FundsOperation::CashFlowSummary,
+        FundsOperation::FundsTransfer,
+        FundsOperation::CashSweep,
+        FundsOperation::CurrencyExchange,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FundsOperation::CashFlowSummary => "cash_flow_summary",
+            FundsOperation::FundsTransfer => "funds_transfer",
+            FundsOperation::CashSweep => "cash_sweep",
+            FundsOperation::CurrencyExchange => "currency_exchange",
+        }
+    }
+
+    /// OpenAPI protocol implementing the operation, if there is one.
+    pub fn proto_id(&self) -> Option<u32> {
+        match self {
+            FundsOperation::CashFlowSummary => Some(PROTO_TRD_FLOW_SUMMARY),
+            _ => None,
+        }
+    }
+}
+
+/// Proto ID of `op`, or why it cannot be requested on `client`.
+pub fn check_funds_operation(client: &FutuClient, op: FundsOperation) -> Result<u32, TradeError> {
+    let proto_id = op
+        .proto_id()
+        .ok_or_else(|| TradeError::Unsupported(format!("{} has no OpenAPI protocol", op.as_str())))?;
+    if !client.funds_protocols() {
+        return Err(TradeError::FundsDisabled(op.as_str()));
+    }
+    Ok(proto_id)
+}
+
+/// TrdCashFlowDirection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CashFlowDirection {
+    In,
+    Out,
+}
+
+impl CashFlowDirection {
+    pub fn from_i32(value: i32) -> Option<Self> {
+        match value {
+            1 => Some(CashFlowDirection::In),
+            2 => Some(CashFlowDirection::Out),
+            _ => None,
+        }
+    }
+
+    pub fn as_i32(&self) -> i32 {
+        match self {
+            CashFlowDirection::In => 1,
+            CashFlowDirection::Out => 2,
+        }
+    }
+}
+
+/// One cash flow entry of an account.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CashFlow {
+    pub cash_flow_id: Option<u64>,
+    pub clearing_date: String,
+    pub settlement_date: Option<String>,
+    /// Trd_Common.Currency.
+    pub currency: Option<i32>,
+    /// Type as described by the broker, e.g. a dividend or a fee.
+    pub cash_flow_type: String,
+    pub direction: Option<CashFlowDirection>,
+    /// Unsigned amount; `direction` gives the sign.
+    pub amount: f64,
+    pub remark: Option<String>,
+}
+
+impl From<FlowSummaryInfo> for CashFlow {
+    fn from(info: FlowSummaryInfo) -> Self {
+        Self {
+            cash_flow_id: info.cash_flow_id,
+            clearing_date: info.clearing_date.unwrap_or_default(),
+            settlement_date: info.settlement_date,
+            currency: info.currency,
+            cash_flow_type: info.cash_flow_type.unwrap_or_default(),
+            direction: info.cash_flow_direction.and_then(CashFlowDirection::from_i32),
+            amount: info.cash_flow_amount.unwrap_or_default(),
+            remark: info.cash_flow_remark.filter(|r| !r.is_empty()),
+        }
+    }
+}
+
+/// Cash flows of an account on `clearing_date` ("YYYY-MM-DD"), optionally
+/// only those in one direction. Waits when the client has sent 20
+/// Trd_FlowSummary requests in the last 30 seconds.
+pub async fn get_cash_flow(
+    client: &FutuClient,
+    trd_env: i32,
+    acc_id: u64,
+    trd_market: i32,
+    clearing_date: String,
+    direction: Option<CashFlowDirection>,
+) -> Result<Vec<CashFlow>, TradeError> {
+    let proto_id = check_funds_operation(client, FundsOperation::CashFlowSummary)?;
+
+    let header = crate::generated::trd_common::TrdHeader {
+        trd_env,
+        acc_id,
+        trd_market,
+    };
+    let c2s = crate::generated::trd_flow_summary::C2s {
+        header,
+        clearing_date,
+        cash_flow_direction: direction.map(|d| d.as_i32()),
+    };
+    let request = crate::generated::trd_flow_summary::Request { c2s };
+    let body = request.encode_to_vec();
+
+    client.rate_limiter().pace(proto_id, FLOW_SUMMARY_LIMIT).await;
+    let resp = client.request(proto_id, &body).await
+        .map_err(TradeError::Connection)?;
+
+    let response = client.decode::<crate::generated::trd_flow_summary::Response>(&resp)
+        .map_err(TradeError::Decode)?;
+
+    if response.ret_type != 0 {
+        return Err(unsupported_or_server(FundsOperation::CashFlowSummary, response.ret_type, response.ret_msg, response.err_code));
+    }
+
+    Ok(response.s2c.map(|s| s.flow_summary_info_list).unwrap_or_default().into_iter().map(CashFlow::from).collect())
+}
+
+/// `Unsupported` if OpenD does not know the protocol of `op` (an older
+/// version), `TradeError::server` otherwise.
+fn unsupported_or_server(op: FundsOperation, ret_type: i32, ret_msg: Option<String>, err_code: Option<i32>) -> TradeError {
+    let msg = ret_msg.as_deref().unwrap_or_default().to_lowercase();
+    if UNKNOWN_PROTOCOL_PATTERNS.iter().any(|p| msg.contains(p)) {
+        return TradeError::Unsupported(format!("{} is not supported by this OpenD version: {}", op.as_str(), msg));
+    }
+    TradeError::server(ret_type, ret_msg, err_code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operations() {
+        assert_eq!(FundsOperation::CashFlowSummary.proto_id(), Some(2226));
+        let unsupported: Vec<&str> = FundsOperation::ALL
+            .iter()
+            .filter(|op| op.proto_id().is_none())
+            .map(|op| op.as_str())
+            .collect();
+        assert_eq!(unsupported, vec!["funds_transfer", "cash_sweep", "currency_exchange"]);
+    }
+
+    #[test]
+    fn test_flow_summary_roundtrip() {
+        let resp = crate::generated::trd_flow_summary::Response {
+            ret_type: 0,
+            s2c: Some(crate::generated::trd_flow_summary::S2c {
+                header: Default::default(),
+                flow_summary_info_list: vec![FlowSummaryInfo {
+                    clearing_date: Some("2024-03-01".to_string()),
+                    currency: Some(2),
+                    cash_flow_type: Some("Dividend".to_string()),
+                    cash_flow_direction: Some(1),
+                    cash_flow_amount: Some(12.5),
+                    cash_flow_remark: Some(String::new()),
+                    cash_flow_id: Some(7),
+                    ..Default::default()
+                }],
+            }),
+            ..Default::default()
+        };
+        let decoded = crate::generated::trd_flow_summary::Response::decode(resp.encode_to_vec().as_slice()).unwrap();
+        let flow = CashFlow::from(decoded.s2c.unwrap().flow_summary_info_list.remove(0));
+        assert_eq!(flow.direction, Some(CashFlowDirection::In));
+        assert_eq!(flow.amount, 12.5);
+        assert_eq!(flow.cash_flow_id, Some(7));
+        assert!(flow.remark.is_none());
+    }
+
+    #[test]
+    fn test_unknown_protocol_response() {
+        let err = unsupported_or_server(FundsOperation::CashFlowSummary, -1, Some("未知协议".to_string()), None);
+        assert!(matches!(err, TradeError::Unsupported(_)));
+        let err = unsupported_or_server(FundsOperation::CashFlowSummary, -1, Some("账户不存在".to_string()), None);
+        assert!(matches!(err, TradeError::Server { .. }));
+    }
+}
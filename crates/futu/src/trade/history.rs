@@ -0,0 +1,372 @@
+//! Order amendment chains: placement, modifications and resulting states.
+//!
+//! `place_order` and `modify_order` append a record on success, and the recv
+//! loop fills in the order status reported by order update pushes. Status
+//! changes not caused by a local action (fills, exchange cancels, changes
+//! made from another client) are appended as `StatusChanged` records.
+//!
+//! Chains of orders in a final state are kept for a retention period and then
+//! evicted, and the number of chains is capped, so a long-running client does
+//! not keep every order it ever saw.
+
+use std::collections::{HashMap, VecDeque};
+use parking_lot::Mutex;
+use prost::Message;
+
+use crate::generated::trd_common::Order;
+use super::tracker::OrderState;
+
+/// Seconds the chain of a finished order is kept by default.
+pub const DEFAULT_RETENTION: f64 = 3600.0;
+/// Chains kept at most by default.
+pub const DEFAULT_MAX_CHAINS: usize = 10_000;
+
+const PROTO_TRD_UPDATE_ORDER: u32 = 2208;
+
+// ModifyOrderOp values
+const MODIFY_OP_NORMAL: i32 = 1;
+const MODIFY_OP_CANCEL: i32 = 2;
+const MODIFY_OP_DISABLE: i32 = 3;
+const MODIFY_OP_ENABLE: i32 = 4;
+const MODIFY_OP_DELETE: i32 = 5;
+
+/// Action that produced an amendment record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AmendmentKind {
+    Placed,
+    Modified,
+    Cancelled,
+    Disabled,
+    Enabled,
+    Deleted,
+    /// Status change reported by OpenD without a local action.
+    StatusChanged,
+}
+
+impl AmendmentKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AmendmentKind::Placed => "placed",
+            AmendmentKind::Modified => "modified",
+            AmendmentKind::Cancelled => "cancelled",
+            AmendmentKind::Disabled => "disabled",
+            AmendmentKind::Enabled => "enabled",
+            AmendmentKind::Deleted => "deleted",
+            AmendmentKind::StatusChanged => "status_changed",
+        }
+    }
+
+    /// Kind for a ModifyOrderOp, or None for unknown values.
+    pub fn from_modify_op(op: i32) -> Option<Self> {
+        Some(match op {
+            MODIFY_OP_NORMAL => AmendmentKind::Modified,
+            MODIFY_OP_CANCEL => AmendmentKind::Cancelled,
+            MODIFY_OP_DISABLE => AmendmentKind::Disabled,
+            MODIFY_OP_ENABLE => AmendmentKind::Enabled,
+            MODIFY_OP_DELETE => AmendmentKind::Deleted,
+            _ => return None,
+        })
+    }
+}
+
+/// One link in an order's amendment chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Amendment {
+    pub kind: AmendmentKind,
+    /// Unix timestamp (seconds) when the record was created.
+    pub timestamp: f64,
+    /// Order quantity after this step, if known.
+    pub qty: Option<f64>,
+    /// Order price after this step, if known.
+    pub price: Option<f64>,
+    /// OrderStatus that resulted from this step, once reported by OpenD.
+    pub order_status: Option<i32>,
+}
+
+#[derive(Debug, Default)]
+struct Chain {
+    amendments: Vec<Amendment>,
+    /// When the order reached a final state, if it has.
+    finished_at: Option<f64>,
+}
+
+#[derive(Debug, Default)]
+struct Chains {
+    by_order: HashMap<u64, Chain>,
+    /// (finished_at, order_id) of finished orders, oldest first. Entries of
+    /// orders evicted or recorded as finished again are skipped when popped.
+    finished: VecDeque<(f64, u64)>,
+}
+
+impl Chains {
+    /// The chain of `order_id`, making room for it under `max_chains`.
+    fn entry(&mut self, order_id: u64, max_chains: usize) -> &mut Chain {
+        if !self.by_order.contains_key(&order_id) && self.by_order.len() >= max_chains.max(1) {
+            self.evict_oldest();
+        }
+        self.by_order.entry(order_id).or_default()
+    }
+
+    /// Evict a chain for room: the oldest finished one, or else the one
+    /// least recently changed.
+    fn evict_oldest(&mut self) {
+        while let Some((finished_at, order_id)) = self.finished.pop_front() {
+            if self.by_order.get(&order_id).and_then(|c| c.finished_at) == Some(finished_at) {
+                self.by_order.remove(&order_id);
+                return;
+            }
+        }
+        let last_change = |c: &Chain| c.amendments.last().map_or(f64::NEG_INFINITY, |a| a.timestamp);
+        let oldest = self.by_order.iter().min_by(|a, b| last_change(a.1).total_cmp(&last_change(b.1))).map(|(id, _)| *id);
+        if let Some(order_id) = oldest {
+            self.by_order.remove(&order_id);
+        }
+    }
+
+    /// Evict the chains of orders finished more than `retention` seconds before `now`.
+    fn evict_finished(&mut self, retention: f64, now: f64) {
+        while let Some(&(finished_at, order_id)) = self.finished.front() {
+            if now - finished_at < retention {
+                break;
+            }
+            self.finished.pop_front();
+            if self.by_order.get(&order_id).and_then(|c| c.finished_at) == Some(finished_at) {
+                self.by_order.remove(&order_id);
+            }
+        }
+    }
+
+    /// Note the latest status of `order_id`'s chain.
+    fn update_finished(&mut self, order_id: u64, order_status: i32, now: f64) {
+        let Some(chain) = self.by_order.get_mut(&order_id) else {
+            return;
+        };
+        if !OrderState::from_order_status(order_status).is_final() {
+            chain.finished_at = None;
+        } else if chain.finished_at.is_none() {
+            chain.finished_at = Some(now);
+            self.finished.push_back((now, order_id));
+        }
+    }
+}
+
+/// Thread-safe amendment chains keyed by order id.
+#[derive(Debug)]
+pub struct OrderHistory {
+    chains: Mutex<Chains>,
+    /// (retention in seconds, max chains)
+    limits: Mutex<(f64, usize)>,
+}
+
+impl Default for OrderHistory {
+    fn default() -> Self {
+        Self::with_limits(DEFAULT_RETENTION, DEFAULT_MAX_CHAINS)
+    }
+}
+
+impl OrderHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// History keeping the chains of finished orders for `retention` seconds
+    /// and at most `max_chains` chains.
+    pub fn with_limits(retention: f64, max_chains: usize) -> Self {
+        Self { chains: Mutex::new(Chains::default()), limits: Mutex::new((retention, max_chains)) }
+    }
+
+    pub fn set_limits(&self, retention: f64, max_chains: usize) {
+        *self.limits.lock() = (retention, max_chains);
+    }
+
+    /// Number of chains kept.
+    pub fn len(&self) -> usize {
+        self.chains.lock().by_order.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Record a successfully placed order.
+    pub fn record_placed(&self, order_id: u64, qty: f64, price: Option<f64>, now: f64) {
+        let (retention, max_chains) = *self.limits.lock();
+        let mut chains = self.chains.lock();
+        chains.evict_finished(retention, now);
+        chains.entry(order_id, max_chains).amendments.push(Amendment {
+            kind: AmendmentKind::Placed,
+            timestamp: now,
+            qty: Some(qty),
+            price,
+            order_status: None,
+        });
+    }
+
+    /// Record a successful modify_order call. Quantity and price are only
+    /// kept for normal modifications; other operations leave them unchanged.
+    pub fn record_modified(&self, order_id: u64, modify_order_op: i32, qty: Option<f64>, price: Option<f64>, now: f64) {
+        let Some(kind) = AmendmentKind::from_modify_op(modify_order_op) else {
+            return;
+        };
+        let (retention, max_chains) = *self.limits.lock();
+        let mut chains = self.chains.lock();
+        chains.evict_finished(retention, now);
+        let chain = &mut chains.entry(order_id, max_chains).amendments;
+        let last = chain.last();
+        let (qty, price) = if kind == AmendmentKind::Modified {
+            (qty.or(last.and_then(|a| a.qty)), price.or(last.and_then(|a| a.price)))
+        } else {
+            (last.and_then(|a| a.qty), last.and_then(|a| a.price))
+        };
+        chain.push(Amendment { kind, timestamp: now, qty, price, order_status: None });
+    }
+
+    /// Apply an order update push. The status completes the latest record if
+    /// it has none yet; otherwise a changed status, quantity or price is
+    /// appended as `StatusChanged`. A final status starts the retention
+    /// period of the chain.
+    pub fn record_update(&self, order: &Order, now: f64) {
+        let (retention, max_chains) = *self.limits.lock();
+        let mut chains = self.chains.lock();
+        chains.evict_finished(retention, now);
+        append_update(&mut chains.entry(order.order_id, max_chains).amendments, order, now);
+        chains.update_finished(order.order_id, order.order_status, now);
+    }
+
+    /// Record an order update push. Other proto ids and undecodable bodies are ignored.
+    pub fn record_push(&self, proto_id: u32, body: &[u8], now: f64) {
+        if proto_id != PROTO_TRD_UPDATE_ORDER {
+            return;
+        }
+        if let Some(s2c) = crate::generated::trd_update_order::Response::decode(body).ok().and_then(|r| r.s2c) {
+            self.record_update(&s2c.order, now);
+        }
+    }
+
+    /// The amendment chain of `order_id`, oldest first. Empty if unknown
+    /// or evicted.
+    pub fn chain(&self, order_id: u64) -> Vec<Amendment> {
+        self.chains.lock().by_order.get(&order_id).map(|c| c.amendments.clone()).unwrap_or_default()
+    }
+}
+
+fn append_update(chain: &mut Vec<Amendment>, order: &Order, now: f64) {
+    if let Some(last) = chain.last_mut() {
+        if last.order_status.is_none() {
+            last.order_status = Some(order.order_status);
+            last.qty = Some(order.qty);
+            last.price = order.price.or(last.price);
+            return;
+        }
+        let unchanged = last.order_status == Some(order.order_status)
+            && last.qty == Some(order.qty)
+            && (order.price.is_none() || last.price == order.price);
+        if unchanged {
+            return;
+        }
+    }
+    chain.push(Amendment {
+        kind: AmendmentKind::StatusChanged,
+        timestamp: now,
+        qty: Some(order.qty),
+        price: order.price,
+        order_status: Some(order.order_status),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(order_id: u64, status: i32, qty: f64, price: f64) -> Order {
+        Order {
+            order_id,
+            order_status: status,
+            qty,
+            price: Some(price),
+            ..Default::default()
+        }
+    }
+
+    fn kinds(chain: &[Amendment]) -> Vec<AmendmentKind> {
+        chain.iter().map(|a| a.kind).collect()
+    }
+
+    #[test]
+    fn test_place_modify_cancel_chain() {
+        let history = OrderHistory::new();
+        history.record_placed(1, 100.0, Some(350.0), 1.0);
+        history.record_update(&order(1, 5, 100.0, 350.0), 1.1);
+        history.record_modified(1, MODIFY_OP_NORMAL, Some(200.0), None, 2.0);
+        history.record_update(&order(1, 5, 200.0, 350.0), 2.1);
+        history.record_modified(1, MODIFY_OP_CANCEL, None, None, 3.0);
+        history.record_update(&order(1, 15, 200.0, 350.0), 3.1);
+
+        let chain = history.chain(1);
+        assert_eq!(
+            kinds(&chain),
+            vec![AmendmentKind::Placed, AmendmentKind::Modified, AmendmentKind::Cancelled]
+        );
+        assert_eq!(chain[0].order_status, Some(5));
+        assert_eq!(chain[1].qty, Some(200.0));
+        assert_eq!(chain[1].price, Some(350.0));
+        assert_eq!(chain[2].timestamp, 3.0);
+        assert_eq!(chain[2].order_status, Some(15));
+    }
+
+    #[test]
+    fn test_external_status_changes() {
+        let history = OrderHistory::new();
+        history.record_update(&order(7, 5, 100.0, 10.0), 1.0);
+        history.record_update(&order(7, 5, 100.0, 10.0), 2.0);
+        history.record_update(&order(7, 11, 100.0, 10.0), 3.0);
+
+        let chain = history.chain(7);
+        assert_eq!(kinds(&chain), vec![AmendmentKind::StatusChanged; 2]);
+        assert_eq!(chain[1].order_status, Some(11));
+        assert!(history.chain(8).is_empty());
+    }
+
+    #[test]
+    fn test_finished_chains_evicted_after_retention() {
+        let history = OrderHistory::with_limits(60.0, 100);
+        history.record_placed(1, 100.0, Some(10.0), 0.0);
+        history.record_update(&order(1, 11, 100.0, 10.0), 10.0);
+        history.record_placed(2, 100.0, Some(10.0), 20.0);
+        history.record_update(&order(2, 5, 100.0, 10.0), 20.0);
+
+        // Kept within the retention period, for queries right after the fill
+        history.record_update(&order(2, 10, 100.0, 10.0), 69.0);
+        assert_eq!(history.chain(1).len(), 1);
+        history.record_update(&order(2, 10, 100.0, 10.0), 70.0);
+        assert!(history.chain(1).is_empty());
+        // Open orders are never evicted by age
+        history.record_placed(3, 1.0, None, 1000.0);
+        assert_eq!(kinds(&history.chain(2)), vec![AmendmentKind::Placed, AmendmentKind::StatusChanged]);
+        assert_eq!(history.len(), 2);
+    }
+
+    #[test]
+    fn test_max_chains_evicts_finished_first() {
+        let history = OrderHistory::with_limits(DEFAULT_RETENTION, 2);
+        history.record_placed(1, 1.0, None, 0.0);
+        history.record_placed(2, 1.0, None, 1.0);
+        history.record_update(&order(2, 15, 1.0, 1.0), 2.0);
+        history.record_placed(3, 1.0, None, 3.0);
+        assert_eq!(history.len(), 2);
+        assert!(history.chain(2).is_empty());
+
+        // Without finished orders, the least recently changed goes
+        history.record_placed(4, 1.0, None, 4.0);
+        assert!(history.chain(1).is_empty());
+        assert_eq!(history.len(), 2);
+    }
+
+    #[test]
+    fn test_unknown_modify_op_ignored() {
+        let history = OrderHistory::new();
+        history.record_modified(1, 99, Some(1.0), None, 0.0);
+        assert!(history.chain(1).is_empty());
+        assert_eq!(AmendmentKind::from_modify_op(MODIFY_OP_DELETE), Some(AmendmentKind::Deleted));
+    }
+}
@@ -1,6 +1,19 @@
 pub mod account;
+pub mod account_stream;
+pub mod autolock;
+pub mod fills;
+pub mod funds;
+pub mod history;
 pub mod order;
+#[cfg(feature = "quote")]
+pub mod portfolio;
+pub mod preview;
 pub mod push;
 pub mod query;
+#[cfg(feature = "quote")]
+pub mod report;
+pub mod sequence;
+pub mod throttle;
+pub mod tracker;
 
 pub use account::TradeError;
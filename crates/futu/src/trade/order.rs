@@ -1,19 +1,129 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use parking_lot::Mutex;
 use prost::Message;
+use crate::client::clock::{system_clock, SharedClock};
 use crate::client::FutuClient;
+use crate::generated::common::PacketId;
+use crate::generated::trd_place_order;
 use super::account::TradeError;
+use super::throttle::OrderAction;
 
 const PROTO_TRD_PLACE_ORDER: u32 = 2202;
 const PROTO_TRD_MODIFY_ORDER: u32 = 2205;
+const PROTO_TRD_RECONFIRM_ORDER: u32 = 2206;
+
+/// How long `OrderGuard` remembers a submission.
+pub const DEFAULT_GUARD_WINDOW: Duration = Duration::from_secs(60);
+
+/// What `place_order_idempotent` compares to recognize a repeated order:
+/// account, code, side, quantity and price.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OrderKey {
+    acc_id: u64,
+    code: String,
+    trd_side: i32,
+    qty: u64,
+    price: Option<u64>,
+}
+
+impl OrderKey {
+    pub fn new(acc_id: u64, code: &str, trd_side: i32, qty: f64, price: Option<f64>) -> Self {
+        // Bit patterns, so equal quantities and prices compare equal and hash alike
+        Self { acc_id, code: code.to_string(), trd_side, qty: qty.to_bits(), price: price.map(f64::to_bits) }
+    }
+}
+
+/// How to go on with a submission, decided by `OrderGuard::begin`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Submission {
+    /// Send with this PacketID: a fresh one, or that of an earlier attempt
+    /// whose outcome is unknown, so OpenD can tell the two apart from a new order.
+    Send(PacketId),
+    /// The order was placed within the window; this is the earlier response.
+    Placed(trd_place_order::Response),
+}
+
+#[derive(Debug)]
+struct Entry {
+    packet_id: PacketId,
+    submitted: Instant,
+    placed: Option<trd_place_order::Response>,
+}
+
+/// Recently submitted orders and their PacketIDs, so a call retried after a
+/// timeout or a lost connection does not place the order twice.
+///
+/// An entry lives for the window after its last submission. A placed order
+/// is answered from its entry; an order OpenD rejected is forgotten, so a
+/// retry is a new submission.
+#[derive(Debug)]
+pub struct OrderGuard {
+    entries: Mutex<HashMap<OrderKey, Entry>>,
+    window: Duration,
+    clock: SharedClock,
+}
+
+impl Default for OrderGuard {
+    fn default() -> Self {
+        Self::new(DEFAULT_GUARD_WINDOW)
+    }
+}
+
+impl OrderGuard {
+    pub fn new(window: Duration) -> Self {
+        Self::with_clock(window, system_clock())
+    }
+
+    pub fn with_clock(window: Duration, clock: SharedClock) -> Self {
+        Self { entries: Mutex::new(HashMap::new()), window, clock }
+    }
+
+    /// Start a submission of `key`. `packet_id` makes the PacketID of a new
+    /// submission and is only called when there is no earlier attempt.
+    pub fn begin(&self, key: &OrderKey, packet_id: impl FnOnce() -> PacketId) -> Submission {
+        let now = self.clock.now();
+        let mut entries = self.entries.lock();
+        entries.retain(|_, e| now.saturating_duration_since(e.submitted) < self.window);
+        let entry = entries.entry(key.clone()).or_insert_with(|| Entry { packet_id: packet_id(), submitted: now, placed: None });
+        if let Some(response) = &entry.placed {
+            return Submission::Placed(response.clone());
+        }
+        entry.submitted = now;
+        Submission::Send(entry.packet_id)
+    }
+
+    /// Record that `key` was placed.
+    pub fn placed(&self, key: &OrderKey, response: &trd_place_order::Response) {
+        if let Some(entry) = self.entries.lock().get_mut(key) {
+            entry.placed = Some(response.clone());
+        }
+    }
+
+    /// Forget `key`, e.g. after OpenD rejected it.
+    pub fn forget(&self, key: &OrderKey) {
+        self.entries.lock().remove(key);
+    }
+
+    /// Number of remembered submissions, expired ones included.
+    pub fn len(&self) -> usize {
+        self.entries.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
 
 /// Place a new order.
 #[allow(clippy::too_many_arguments)]
 pub async fn place_order(
     client: &FutuClient,
-    trd_env: i32,
+    trd_env: impl Into<i32>,
     acc_id: u64,
-    trd_market: i32,
-    trd_side: i32,
-    order_type: i32,
+    trd_market: impl Into<i32>,
+    trd_side: impl Into<i32>,
+    order_type: impl Into<i32>,
     code: String,
     qty: f64,
     price: Option<f64>,
@@ -27,19 +137,22 @@ pub async fn place_order(
     trail_value: Option<f64>,
     trail_spread: Option<f64>,
 ) -> Result<crate::generated::trd_place_order::Response, TradeError> {
+    let trd_env = trd_env.into();
+    let trd_market = trd_market.into();
+    let trd_side = trd_side.into();
+    let order_type = order_type.into();
+    client.session().check_trading()?;
+    client.order_throttle().acquire(acc_id, OrderAction::Place).await;
+
     let header = crate::generated::trd_common::TrdHeader {
         trd_env,
         acc_id,
         trd_market,
     };
 
-    let conn_id = client.connection().conn_id().await;
-    let serial_no = client.connection().next_serial();
-    let c2s = crate::generated::trd_place_order::C2s {
-        packet_id: crate::generated::common::PacketId {
-            conn_id,
-            serial_no,
-        },
+    let packet_id = new_packet_id(client).await;
+    let c2s = trd_place_order::C2s {
+        packet_id,
         header,
         trd_side,
         order_type,
@@ -58,21 +171,105 @@ pub async fn place_order(
         trail_spread,
         ..Default::default()
     };
+    send_place_order(client, c2s).await
+}
 
-    let request = crate::generated::trd_place_order::Request { c2s };
+/// Place a new order unless the same order (see `OrderKey`) was placed on
+/// this client within the guard window, in which case the earlier response
+/// is returned. A call repeated after a connection error or timeout sends
+/// the PacketID of the failed attempt again, so OpenD can recognize it.
+#[allow(clippy::too_many_arguments)]
+pub async fn place_order_idempotent(
+    client: &FutuClient,
+    trd_env: impl Into<i32>,
+    acc_id: u64,
+    trd_market: impl Into<i32>,
+    trd_side: impl Into<i32>,
+    order_type: impl Into<i32>,
+    code: String,
+    qty: f64,
+    price: Option<f64>,
+    adjust_limit: Option<f64>,
+    sec_market: Option<i32>,
+    remark: Option<String>,
+    time_in_force: Option<i32>,
+    fill_outside_rth: Option<bool>,
+    aux_price: Option<f64>,
+    trail_type: Option<i32>,
+    trail_value: Option<f64>,
+    trail_spread: Option<f64>,
+) -> Result<trd_place_order::Response, TradeError> {
+    let trd_side = trd_side.into();
+    let key = OrderKey::new(acc_id, &code, trd_side, qty, price);
+    client.session().check_trading()?;
+
+    // A fresh PacketID takes a serial number, so make it up front
+    let fresh = new_packet_id(client).await;
+    let packet_id = match client.order_guard().begin(&key, || fresh) {
+        Submission::Placed(response) => return Ok(response),
+        Submission::Send(packet_id) => packet_id,
+    };
+    client.order_throttle().acquire(acc_id, OrderAction::Place).await;
+    let c2s = trd_place_order::C2s {
+        packet_id,
+        header: crate::generated::trd_common::TrdHeader {
+            trd_env: trd_env.into(),
+            acc_id,
+            trd_market: trd_market.into(),
+        },
+        trd_side,
+        order_type: order_type.into(),
+        code,
+        qty,
+        price,
+        adjust_price: None,
+        adjust_side_and_limit: adjust_limit,
+        sec_market,
+        remark,
+        time_in_force,
+        fill_outside_rth,
+        aux_price,
+        trail_type,
+        trail_value,
+        trail_spread,
+        ..Default::default()
+    };
+
+    let result = send_place_order(client, c2s).await;
+    match &result {
+        Ok(response) => client.order_guard().placed(&key, response),
+        // The outcome of a request that got no answer is unknown: keep its PacketID
+        Err(TradeError::Connection(_)) => {}
+        Err(_) => client.order_guard().forget(&key),
+    }
+    result
+}
+
+async fn new_packet_id(client: &FutuClient) -> PacketId {
+    PacketId {
+        conn_id: client.connection().conn_id().await,
+        serial_no: client.connection().next_serial(),
+    }
+}
+
+/// Send a Trd_PlaceOrder request and record the placed order.
+async fn send_place_order(client: &FutuClient, c2s: trd_place_order::C2s) -> Result<trd_place_order::Response, TradeError> {
+    let (qty, price) = (c2s.qty, c2s.price);
+    let request = trd_place_order::Request { c2s };
     let body = request.encode_to_vec();
 
     let resp = client.request(PROTO_TRD_PLACE_ORDER, &body).await
         .map_err(TradeError::Connection)?;
 
-    let response = crate::generated::trd_place_order::Response::decode(resp.body.as_slice())
-        .map_err(|e| TradeError::Decode(e.to_string()))?;
+    let response = client.decode::<trd_place_order::Response>(&resp)
+        .map_err(TradeError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(TradeError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(TradeError::server(response.ret_type, response.ret_msg, response.err_code));
+    }
+
+    if let Some(order_id) = response.s2c.as_ref().and_then(|s| s.order_id) {
+        client.order_history().record_placed(order_id, qty, price, client.clock().unix_time());
     }
 
     Ok(response)
@@ -82,15 +279,21 @@ pub async fn place_order(
 #[allow(clippy::too_many_arguments)]
 pub async fn modify_order(
     client: &FutuClient,
-    trd_env: i32,
+    trd_env: impl Into<i32>,
     acc_id: u64,
-    trd_market: i32,
+    trd_market: impl Into<i32>,
     order_id: u64,
-    modify_order_op: i32,
+    modify_order_op: impl Into<i32>,
     qty: Option<f64>,
     price: Option<f64>,
     adjust_limit: Option<f64>,
 ) -> Result<crate::generated::trd_modify_order::Response, TradeError> {
+    let trd_env = trd_env.into();
+    let trd_market = trd_market.into();
+    let modify_order_op = modify_order_op.into();
+    client.session().check_trading()?;
+    client.order_throttle().acquire(acc_id, OrderAction::from_modify_op(modify_order_op)).await;
+
     let header = crate::generated::trd_common::TrdHeader {
         trd_env,
         acc_id,
@@ -120,14 +323,167 @@ pub async fn modify_order(
     let resp = client.request(PROTO_TRD_MODIFY_ORDER, &body).await
         .map_err(TradeError::Connection)?;
 
-    let response = crate::generated::trd_modify_order::Response::decode(resp.body.as_slice())
-        .map_err(|e| TradeError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::trd_modify_order::Response>(&resp)
+        .map_err(TradeError::Decode)?;
+
+    if response.ret_type != 0 {
+        return Err(TradeError::server(response.ret_type, response.ret_msg, response.err_code));
+    }
+
+    client.order_history().record_modified(order_id, modify_order_op, qty, price, client.clock().unix_time());
+
+    Ok(response)
+}
+
+/// ModifyOrderOp that cancels an order.
+const MODIFY_OP_CANCEL: i32 = 2;
+
+/// Order statuses an order can still be cancelled in: waiting to submit,
+/// submitting, submitted and partly filled.
+const CANCELLABLE_STATUSES: [i32; 4] = [1, 2, 5, 10];
+
+/// Outcome of `cancel_all_orders`.
+#[derive(Debug, Default)]
+pub struct CancelAllResult {
+    /// True if OpenD cancelled every order of the account in one request.
+    /// `cancelled` is empty then, as OpenD does not list them.
+    pub for_all: bool,
+    /// Orders cancelled one by one.
+    pub cancelled: Vec<u64>,
+    /// Orders whose cancel failed.
+    pub failed: Vec<(u64, TradeError)>,
+}
+
+/// Cancel the open orders of an account in `trd_market`.
+///
+/// Without `filter`, a single ModifyOrder with `forAll` cancels them all on
+/// OpenD. With `filter`, the matching orders of the order list that can
+/// still be cancelled are cancelled concurrently, each with its own
+/// ModifyOrder; a failed cancel does not stop the others.
+pub async fn cancel_all_orders(
+    client: &FutuClient,
+    trd_env: impl Into<i32>,
+    acc_id: u64,
+    trd_market: impl Into<i32>,
+    filter: Option<crate::generated::trd_common::TrdFilterConditions>,
+) -> Result<CancelAllResult, TradeError> {
+    let trd_env = trd_env.into();
+    let trd_market = trd_market.into();
+    let Some(filter) = filter else {
+        cancel_all_native(client, trd_env, acc_id, trd_market).await?;
+        return Ok(CancelAllResult { for_all: true, ..Default::default() });
+    };
+
+    client.session().check_trading()?;
+    let orders = super::query::get_order_list(client, trd_env, acc_id, trd_market, Some(filter)).await?;
+    let order_ids: Vec<u64> = orders
+        .s2c
+        .map(|s| s.order_list)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|o| CANCELLABLE_STATUSES.contains(&o.order_status))
+        .map(|o| o.order_id)
+        .collect();
+
+    let results = futures::future::join_all(order_ids.iter().map(|&order_id| {
+        modify_order(client, trd_env, acc_id, trd_market, order_id, MODIFY_OP_CANCEL, None, None, None)
+    })).await;
+
+    let mut result = CancelAllResult::default();
+    for (order_id, outcome) in order_ids.into_iter().zip(results) {
+        match outcome {
+            Ok(_) => result.cancelled.push(order_id),
+            Err(e) => result.failed.push((order_id, e)),
+        }
+    }
+    Ok(result)
+}
+
+/// Cancel every order of the account in `trd_market` with one ModifyOrder.
+async fn cancel_all_native(
+    client: &FutuClient,
+    trd_env: i32,
+    acc_id: u64,
+    trd_market: i32,
+) -> Result<crate::generated::trd_modify_order::Response, TradeError> {
+    client.session().check_trading()?;
+    client.order_throttle().acquire(acc_id, OrderAction::Cancel).await;
+
+    let conn_id = client.connection().conn_id().await;
+    let serial_no = client.connection().next_serial();
+    let c2s = crate::generated::trd_modify_order::C2s {
+        packet_id: crate::generated::common::PacketId {
+            conn_id,
+            serial_no,
+        },
+        header: crate::generated::trd_common::TrdHeader {
+            trd_env,
+            acc_id,
+            trd_market,
+        },
+        order_id: 0,
+        modify_order_op: MODIFY_OP_CANCEL,
+        for_all: Some(true),
+        trd_market: Some(trd_market),
+        ..Default::default()
+    };
+
+    let request = crate::generated::trd_modify_order::Request { c2s };
+    let body = request.encode_to_vec();
+
+    let resp = client.request(PROTO_TRD_MODIFY_ORDER, &body).await
+        .map_err(TradeError::Connection)?;
+
+    let response = client.decode::<crate::generated::trd_modify_order::Response>(&resp)
+        .map_err(TradeError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(TradeError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(TradeError::server(response.ret_type, response.ret_msg, response.err_code));
+    }
+
+    Ok(response)
+}
+
+/// Confirm an order OpenD held back with a warning, e.g. a US order in the
+/// pre- or post-market session. `reconfirm_reason` is the
+/// ReconfirmOrderReason of the warning, as reported by place_order's
+/// error code.
+pub async fn reconfirm_order(
+    client: &FutuClient,
+    trd_env: impl Into<i32>,
+    acc_id: u64,
+    trd_market: impl Into<i32>,
+    order_id: u64,
+    reconfirm_reason: i32,
+) -> Result<crate::generated::trd_reconfirm_order::Response, TradeError> {
+    client.session().check_trading()?;
+    client.order_throttle().acquire(acc_id, OrderAction::Modify).await;
+
+    let conn_id = client.connection().conn_id().await;
+    let serial_no = client.connection().next_serial();
+    let c2s = crate::generated::trd_reconfirm_order::C2s {
+        packet_id: PacketId { conn_id, serial_no },
+        header: crate::generated::trd_common::TrdHeader {
+            trd_env: trd_env.into(),
+            acc_id,
+            trd_market: trd_market.into(),
+        },
+        order_id,
+        reconfirm_reason,
+        order_id_ex: None,
+    };
+
+    let request = crate::generated::trd_reconfirm_order::Request { c2s };
+    let body = request.encode_to_vec();
+
+    let resp = client.request(PROTO_TRD_RECONFIRM_ORDER, &body).await
+        .map_err(TradeError::Connection)?;
+
+    let response = client.decode::<crate::generated::trd_reconfirm_order::Response>(&resp)
+        .map_err(TradeError::Decode)?;
+
+    if response.ret_type != 0 {
+        return Err(TradeError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -139,11 +495,84 @@ mod tests {
 
     const PROTO_TRD_PLACE_ORDER: u32 = 2202;
     const PROTO_TRD_MODIFY_ORDER: u32 = 2205;
+    const PROTO_TRD_RECONFIRM_ORDER: u32 = 2206;
 
     #[test]
     fn test_proto_id_constants() {
         assert_eq!(PROTO_TRD_PLACE_ORDER, 2202);
         assert_eq!(PROTO_TRD_MODIFY_ORDER, 2205);
+        assert_eq!(PROTO_TRD_RECONFIRM_ORDER, 2206);
+    }
+
+    #[test]
+    fn test_order_guard() {
+        use std::sync::Arc;
+        use std::time::Duration;
+        use crate::client::clock::MockClock;
+        use crate::generated::common::PacketId;
+        use super::{OrderGuard, OrderKey, Submission};
+
+        let clock = Arc::new(MockClock::new(0.0));
+        let guard = OrderGuard::with_clock(Duration::from_secs(60), clock.clone());
+        let key = OrderKey::new(1, "00700", 1, 100.0, Some(300.0));
+        let packet = |serial_no| move || PacketId { conn_id: 7, serial_no };
+
+        // Unanswered attempts share the PacketID of the first
+        assert_eq!(guard.begin(&key, packet(1)), Submission::Send(packet(1)()));
+        assert_eq!(guard.begin(&key, packet(2)), Submission::Send(packet(1)()));
+        let other = OrderKey::new(1, "00700", 1, 100.0, Some(300.2));
+        assert_eq!(guard.begin(&other, packet(3)), Submission::Send(packet(3)()));
+
+        // Rejected orders are submitted afresh
+        guard.forget(&other);
+        assert_eq!(guard.begin(&other, packet(4)), Submission::Send(packet(4)()));
+
+        let response = crate::generated::trd_place_order::Response { ret_type: 0, ..Default::default() };
+        guard.placed(&key, &response);
+        assert_eq!(guard.begin(&key, packet(5)), Submission::Placed(response));
+
+        clock.advance(Duration::from_secs(60));
+        assert_eq!(guard.begin(&key, packet(6)), Submission::Send(packet(6)()));
+        assert_eq!(guard.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_place_order_idempotent_against_mock() {
+        use crate::client::mock::MockOpenD;
+        use crate::config::FutuConfig;
+        use crate::generated::trd_place_order::{Response, S2c};
+        use crate::trade::account::TradeError;
+
+        let mock = MockOpenD::start().await.unwrap();
+        let config = FutuConfig { port: mock.port(), enable_encryption: false, ..FutuConfig::default() };
+        let mut client = crate::client::FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+        let place = |qty| {
+            super::place_order_idempotent(
+                &client, 0, 1, 1, 1, 2, "00700".to_string(), qty, Some(300.0),
+                None, None, None, None, None, None, None, None, None,
+            )
+        };
+
+        let rejected = Response { ret_type: -1, ret_msg: Some("insufficient buying power".to_string()), ..Default::default() };
+        mock.respond(PROTO_TRD_PLACE_ORDER, rejected.encode_to_vec());
+        assert!(place(100.0).await.is_err());
+        assert!(client.order_guard().is_empty());
+
+        let placed = Response {
+            ret_type: 0,
+            s2c: Some(S2c { order_id: Some(42), ..Default::default() }),
+            ..Default::default()
+        };
+        mock.respond(PROTO_TRD_PLACE_ORDER, placed.encode_to_vec());
+        assert_eq!(place(100.0).await.unwrap().s2c.unwrap().order_id, Some(42));
+        assert_eq!(place(100.0).await.unwrap().s2c.unwrap().order_id, Some(42));
+        assert_eq!(client.stats().requests[&PROTO_TRD_PLACE_ORDER].count, 2);
+
+        // Unanswered: remembered for the retry
+        client.close_requests().await;
+        assert!(matches!(place(200.0).await, Err(TradeError::Connection(_))));
+        assert_eq!(client.order_guard().len(), 2);
     }
 
     #[test]
@@ -289,4 +718,74 @@ mod tests {
         assert_eq!(decoded.ret_msg.unwrap(), "insufficient funds");
         assert!(decoded.s2c.is_none());
     }
+
+    #[tokio::test]
+    async fn test_cancel_all_orders() {
+        use crate::client::mock::MockOpenD;
+        use crate::config::FutuConfig;
+        use crate::generated::trd_common::{Order, TrdFilterConditions};
+
+        let mock = MockOpenD::start().await.unwrap();
+        let config = FutuConfig { port: mock.port(), enable_encryption: false, ..FutuConfig::default() };
+        let mut client = crate::client::FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+
+        let result = super::cancel_all_orders(&client, 0, 1, 1, None).await.unwrap();
+        assert!(result.for_all);
+        assert!(result.cancelled.is_empty());
+
+        let order = |order_id, order_status| Order { order_id, order_status, ..Default::default() };
+        let orders = crate::generated::trd_get_order_list::Response {
+            ret_type: 0,
+            s2c: Some(crate::generated::trd_get_order_list::S2c {
+                order_list: vec![order(11, 5), order(12, 11), order(13, 10), order(14, 15)],
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        mock.respond(2201, orders.encode_to_vec());
+        let filter = TrdFilterConditions { code_list: vec!["00700".to_string()], ..Default::default() };
+        let result = super::cancel_all_orders(&client, 0, 1, 1, Some(filter.clone())).await.unwrap();
+        assert!(!result.for_all);
+        assert_eq!(result.cancelled, vec![11, 13]);
+        assert_eq!(client.order_history().chain(13).len(), 1);
+
+        let rejected = crate::generated::trd_modify_order::Response {
+            ret_type: -1,
+            ret_msg: Some("order is filled".to_string()),
+            ..Default::default()
+        };
+        mock.respond(PROTO_TRD_MODIFY_ORDER, rejected.encode_to_vec());
+        let result = super::cancel_all_orders(&client, 0, 1, 1, Some(filter)).await.unwrap();
+        assert!(result.cancelled.is_empty());
+        assert_eq!(result.failed.iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![11, 13]);
+    }
+
+    #[tokio::test]
+    async fn test_reconfirm_order_against_mock() {
+        use crate::client::mock::MockOpenD;
+        use crate::config::FutuConfig;
+        use crate::generated::trd_common::TrdHeader;
+        use crate::generated::trd_reconfirm_order::{Response, S2c};
+        use crate::trade::account::TradeError;
+
+        let mock = MockOpenD::start().await.unwrap();
+        let config = FutuConfig { port: mock.port(), enable_encryption: false, ..FutuConfig::default() };
+        let mut client = crate::client::FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+
+        let confirmed = Response {
+            ret_type: 0,
+            s2c: Some(S2c { header: TrdHeader { trd_env: 1, acc_id: 7, trd_market: 2 }, order_id: 9, order_id_ex: None }),
+            ..Default::default()
+        };
+        mock.respond(PROTO_TRD_RECONFIRM_ORDER, confirmed.encode_to_vec());
+        let response = super::reconfirm_order(&client, 1, 7, 2, 9, 1).await.unwrap();
+        assert_eq!(response.s2c.unwrap().order_id, 9);
+
+        let rejected = Response { ret_type: -1, ret_msg: Some("order not found".to_string()), err_code: Some(5), ..Default::default() };
+        mock.respond(PROTO_TRD_RECONFIRM_ORDER, rejected.encode_to_vec());
+        let err = super::reconfirm_order(&client, 1, 7, 2, 9, 1).await.unwrap_err();
+        assert!(matches!(err, TradeError::Server { err_code: Some(5), .. }));
+    }
 }
@@ -0,0 +1,409 @@
+//! Live positions and P&L of a trading account, kept without polling
+//! OpenD on every read.
+//!
+//! `LivePortfolio` starts from a Trd_GetPositionList poll and follows the
+//! account from pushes: Trd_UpdateOrderFill pushes change quantity, average
+//! cost and realized P&L, and Qot_UpdateBasicQot pushes of the held
+//! securities mark the positions to market. `run_live_portfolio` polls the
+//! position list again every interval and replaces the local positions with
+//! OpenD's, so fees, corporate actions or missed pushes skew them for one
+//! interval at most. A fill also brings the next poll forward, like in
+//! `account_stream`, which limits how long a fill already counted in a poll
+//! and pushed after it is counted twice.
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+use parking_lot::Mutex;
+
+use crate::client::FutuClient;
+use crate::generated::trd_common::{OrderFill, Position};
+use crate::quote::names::trd_sec_market_to_qot_market;
+use crate::quote::push_stats::PROTO_QOT_UPDATE_BASIC_QOT;
+use super::account_stream::Account;
+use super::sequence::PROTO_TRD_UPDATE_ORDER_FILL;
+use super::TradeError;
+
+/// Delay from a fill to the poll that reconciles it.
+const FILL_RECONCILE_DELAY: Duration = Duration::from_millis(500);
+
+/// TrdSide values from Trd_Common.proto.
+const TRD_SIDE_BUY: i32 = 1;
+const TRD_SIDE_SELL: i32 = 2;
+const TRD_SIDE_SELL_SHORT: i32 = 3;
+const TRD_SIDE_BUY_BACK: i32 = 4;
+
+/// PositionSide.Short from Trd_Common.proto.
+const POSITION_SIDE_SHORT: i32 = 1;
+
+/// OrderFillStatus.OK from Trd_Common.proto.
+const FILL_STATUS_OK: i32 = 0;
+
+/// SubType.Basic from Qot_Common.proto.
+const SUB_TYPE_BASIC: i32 = 1;
+
+/// (TrdSecMarket, code) of a position; 0 when OpenD gives no market.
+type PositionKey = (i32, String);
+
+/// A position marked to market.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LivePosition {
+    pub code: String,
+    pub name: String,
+    /// TrdSecMarket of the security, if OpenD gave one.
+    pub sec_market: Option<i32>,
+    /// Net quantity, negative for short positions.
+    pub qty: f64,
+    /// Average cost per share; None while flat.
+    pub cost_price: Option<f64>,
+    /// Latest quote or fill price.
+    pub last_price: f64,
+    /// OpenD's realized P&L of the latest poll plus that of the fills
+    /// since; fills keep adding to it where OpenD reports none.
+    pub realized_pl: f64,
+    /// Currency (Trd_Common.Currency) of the position, from the polls.
+    pub currency: Option<i32>,
+    /// Unix time of `last_price`.
+    pub price_time: Option<f64>,
+}
+
+impl LivePosition {
+    fn from_poll(position: &Position, now: f64) -> Self {
+        let qty = if position.position_side == POSITION_SIDE_SHORT { -position.qty.abs() } else { position.qty };
+        Self {
+            code: position.code.clone(),
+            name: position.name.clone(),
+            sec_market: position.sec_market,
+            qty,
+            cost_price: position.average_cost_price.or(position.cost_price).filter(|_| qty != 0.0),
+            last_price: position.price,
+            realized_pl: position.realized_pl.unwrap_or_default(),
+            currency: position.currency,
+            price_time: Some(now),
+        }
+    }
+
+    /// Value at `last_price`, negative for short positions.
+    pub fn market_val(&self) -> f64 {
+        self.qty * self.last_price
+    }
+
+    /// Unrealized P&L at `last_price`; 0 while flat or without a cost.
+    pub fn unrealized_pl(&self) -> f64 {
+        self.cost_price.map_or(0.0, |cost| (self.last_price - cost) * self.qty)
+    }
+
+    /// QotMarket and code of the quotes that mark this position.
+    pub fn security(&self) -> Option<(i32, String)> {
+        let market = trd_sec_market_to_qot_market(self.sec_market?)?;
+        Some((market, self.code.clone()))
+    }
+
+    /// Add a fill of `qty` at `price`, signed by side.
+    fn fill(&mut self, delta: f64, price: f64) {
+        let held = self.qty;
+        if held == 0.0 || held.signum() == delta.signum() {
+            let cost = self.cost_price.unwrap_or(price);
+            self.cost_price = Some((cost * held.abs() + price * delta.abs()) / (held + delta).abs());
+            self.qty = held + delta;
+            return;
+        }
+        let closed = delta.abs().min(held.abs());
+        if let Some(cost) = self.cost_price {
+            self.realized_pl += (price - cost) * closed * held.signum();
+        }
+        self.qty = held + delta;
+        if self.qty == 0.0 {
+            self.cost_price = None;
+        } else if self.qty.signum() != held.signum() {
+            // Crossed through flat: the rest opened at the fill price
+            self.cost_price = Some(price);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct PortfolioState {
+    positions: BTreeMap<PositionKey, LivePosition>,
+    /// Fills applied since the last poll, as pushes may repeat.
+    fill_ids: HashSet<u64>,
+    reconciled_at: Option<f64>,
+}
+
+/// Positions of one account; see the module docs.
+#[derive(Debug)]
+pub struct LivePortfolio {
+    account: Account,
+    state: Mutex<PortfolioState>,
+}
+
+impl LivePortfolio {
+    pub fn new(account: Account) -> Self {
+        Self { account, state: Mutex::new(PortfolioState::default()) }
+    }
+
+    pub fn account(&self) -> Account {
+        self.account
+    }
+
+    /// Replace the positions with a Trd_GetPositionList poll at `now`.
+    /// P&L realized by fills since the previous poll is kept for positions
+    /// OpenD reports no realized P&L for. Returns the securities newly held.
+    pub fn reconcile(&self, positions: &[Position], now: f64) -> Vec<(i32, String)> {
+        let mut state = self.state.lock();
+        let mut previous = std::mem::take(&mut state.positions);
+        let mut added = Vec::new();
+        for position in positions {
+            let key = (position.sec_market.unwrap_or_default(), position.code.clone());
+            let mut live = LivePosition::from_poll(position, now);
+            match previous.remove(&key) {
+                Some(prev) if position.realized_pl.is_none() => live.realized_pl = prev.realized_pl,
+                Some(_) => {}
+                None => added.extend(live.security()),
+            }
+            state.positions.insert(key, live);
+        }
+        state.fill_ids.clear();
+        state.reconciled_at = Some(now);
+        added
+    }
+
+    /// Apply a pushed fill. Cancelled and changed fills and fills already
+    /// applied are skipped; the next poll accounts for them. Returns whether
+    /// the fill was applied.
+    pub fn apply_fill(&self, fill: &OrderFill, now: f64) -> bool {
+        let delta = match fill.trd_side {
+            TRD_SIDE_BUY | TRD_SIDE_BUY_BACK => fill.qty,
+            TRD_SIDE_SELL | TRD_SIDE_SELL_SHORT => -fill.qty,
+            _ => return false,
+        };
+        if fill.status.is_some_and(|s| s != FILL_STATUS_OK) || delta == 0.0 {
+            return false;
+        }
+        let mut state = self.state.lock();
+        if !state.fill_ids.insert(fill.fill_id) {
+            return false;
+        }
+        let key = (fill.sec_market.unwrap_or_default(), fill.code.clone());
+        let position = state.positions.entry(key).or_insert_with(|| LivePosition {
+            code: fill.code.clone(),
+            name: fill.name.clone(),
+            sec_market: fill.sec_market,
+            qty: 0.0,
+            cost_price: None,
+            last_price: fill.price,
+            realized_pl: 0.0,
+            currency: None,
+            price_time: None,
+        });
+        position.fill(delta, fill.price);
+        position.last_price = fill.price;
+        position.price_time = Some(fill.create_timestamp.unwrap_or(now));
+        true
+    }
+
+    /// Mark the positions in `market` (QotMarket) and `code` at `price`.
+    /// Returns whether a position was marked.
+    pub fn apply_quote(&self, market: i32, code: &str, price: f64, now: f64) -> bool {
+        if price <= 0.0 {
+            return false;
+        }
+        let mut marked = false;
+        for position in self.state.lock().positions.values_mut() {
+            if position.code == code && position.sec_market.and_then(trd_sec_market_to_qot_market) == Some(market) {
+                position.last_price = price;
+                position.price_time = Some(now);
+                marked = true;
+            }
+        }
+        marked
+    }
+
+    /// Open positions, by market and code.
+    pub fn positions(&self) -> Vec<LivePosition> {
+        self.state.lock().positions.values().filter(|p| p.qty != 0.0).cloned().collect()
+    }
+
+    /// Securities of the open positions, for quote subscriptions.
+    pub fn securities(&self) -> Vec<(i32, String)> {
+        self.positions().iter().filter_map(LivePosition::security).collect()
+    }
+
+    /// Unix time of the latest poll, None before the first.
+    pub fn reconciled_at(&self) -> Option<f64> {
+        self.state.lock().reconciled_at
+    }
+}
+
+/// How `run_live_portfolio` polls and prices.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LivePortfolioOptions {
+    /// Time between position list polls.
+    pub reconcile_interval: Duration,
+    /// Subscribe basic quotes of newly held securities, so their prices
+    /// are pushed. Off if they are subscribed elsewhere.
+    pub subscribe_quotes: bool,
+}
+
+impl Default for LivePortfolioOptions {
+    fn default() -> Self {
+        Self { reconcile_interval: Duration::from_secs(30), subscribe_quotes: true }
+    }
+}
+
+/// Poll the position list of the portfolio's account and reconcile with
+/// it. Returns the securities newly held.
+pub async fn reconcile(client: &FutuClient, portfolio: &LivePortfolio) -> Result<Vec<(i32, String)>, TradeError> {
+    let (trd_env, acc_id, trd_market) = portfolio.account();
+    let resp = super::query::get_position_list(client, trd_env, acc_id, trd_market, None).await?;
+    let positions = resp.s2c.map(|s| s.position_list).unwrap_or_default();
+    Ok(portfolio.reconcile(&positions, client.clock().unix_time()))
+}
+
+async fn subscribe_quotes(client: &FutuClient, securities: Vec<(i32, String)>) {
+    if securities.is_empty() {
+        return;
+    }
+    match crate::quote::subscribe::subscribe(client, securities, [SUB_TYPE_BASIC], true).await {
+        Ok(results) => {
+            for r in results.iter().filter(|r| !r.is_ok()) {
+                tracing::warn!("No quotes for position {}.{}: {}", r.security.0, r.security.1, r.error.as_deref().unwrap_or_default());
+            }
+        }
+        Err(e) => tracing::warn!("Quote subscription of positions failed: {}", e),
+    }
+}
+
+/// Keep `portfolio` up to date from pushes and polls until the
+/// connection's pushes stop. The first poll runs at once. Subscribes the
+/// account to trade pushes, adding it to the accounts already subscribed.
+pub async fn run_live_portfolio(client: Arc<FutuClient>, portfolio: Arc<LivePortfolio>, options: LivePortfolioOptions) {
+    let acc_id = portfolio.account().1;
+    if let Err(e) = super::push::sub_acc_push_with_mode(&client, vec![acc_id], super::push::AccPushMode::Add).await {
+        tracing::warn!("Account {} push subscription failed, only polling: {}", acc_id, e);
+    }
+    let mut fills = client.subscribe_push(PROTO_TRD_UPDATE_ORDER_FILL).await;
+    let mut quotes = client.subscribe_push(PROTO_QOT_UPDATE_BASIC_QOT).await;
+    let clock = Arc::clone(client.clock());
+    let mut next_poll = clock.now();
+
+    loop {
+        tokio::select! {
+            _ = clock.sleep_until(next_poll) => {
+                match reconcile(&client, &portfolio).await {
+                    Ok(added) if options.subscribe_quotes => subscribe_quotes(&client, added).await,
+                    Ok(_) => {}
+                    // The previous positions are kept until a poll succeeds
+                    Err(e) => tracing::warn!("Position poll of account {} failed: {}", acc_id, e),
+                }
+                next_poll = clock.now() + options.reconcile_interval;
+            }
+            msg = fills.recv() => {
+                let Some(msg) = msg else { break };
+                let Some(s2c) = client.decode::<crate::generated::trd_update_order_fill::Response>(&msg).ok().and_then(|r| r.s2c) else {
+                    continue;
+                };
+                if s2c.header.acc_id == acc_id && portfolio.apply_fill(&s2c.order_fill, clock.unix_time()) {
+                    next_poll = next_poll.min(clock.now() + FILL_RECONCILE_DELAY);
+                }
+            }
+            msg = quotes.recv() => {
+                let Some(msg) = msg else { break };
+                let Some(s2c) = client.decode::<crate::generated::qot_update_basic_qot::Response>(&msg).ok().and_then(|r| r.s2c) else {
+                    continue;
+                };
+                let now = clock.unix_time();
+                for qot in &s2c.basic_qot_list {
+                    portfolio.apply_quote(qot.security.market, &qot.security.code, qot.cur_price, now);
+                }
+            }
+        }
+    }
+    tracing::debug!("Live portfolio of account {} stopped", acc_id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACCOUNT: Account = (1, 42, 1);
+
+    fn position(code: &str, qty: f64, cost: f64, price: f64) -> Position {
+        Position {
+            code: code.to_string(),
+            sec_market: Some(1),
+            qty,
+            cost_price: Some(cost),
+            price,
+            ..Default::default()
+        }
+    }
+
+    fn fill(id: u64, code: &str, side: i32, qty: f64, price: f64) -> OrderFill {
+        OrderFill { fill_id: id, code: code.to_string(), sec_market: Some(1), trd_side: side, qty, price, ..Default::default() }
+    }
+
+    #[test]
+    fn test_reconcile_and_mark_to_market() {
+        let portfolio = LivePortfolio::new(ACCOUNT);
+        let added = portfolio.reconcile(&[position("00700", 100.0, 300.0, 310.0)], 1.0);
+        assert_eq!(added, vec![(1, "00700".to_string())]);
+        assert_eq!(portfolio.reconciled_at(), Some(1.0));
+
+        assert!(portfolio.apply_quote(1, "00700", 320.0, 2.0));
+        assert!(!portfolio.apply_quote(11, "00700", 1.0, 2.0));
+        let p = &portfolio.positions()[0];
+        assert_eq!(p.market_val(), 32000.0);
+        assert_eq!(p.unrealized_pl(), 2000.0);
+        assert_eq!(p.price_time, Some(2.0));
+
+        // Held securities are not reported again
+        assert!(portfolio.reconcile(&[position("00700", 100.0, 300.0, 315.0)], 3.0).is_empty());
+    }
+
+    #[test]
+    fn test_fills_update_cost_and_realized_pl() {
+        let portfolio = LivePortfolio::new(ACCOUNT);
+        portfolio.reconcile(&[position("00700", 100.0, 300.0, 300.0)], 1.0);
+
+        assert!(portfolio.apply_fill(&fill(1, "00700", TRD_SIDE_BUY, 100.0, 310.0), 2.0));
+        let p = &portfolio.positions()[0];
+        assert_eq!((p.qty, p.cost_price), (200.0, Some(305.0)));
+
+        // A repeated push is ignored
+        assert!(!portfolio.apply_fill(&fill(1, "00700", TRD_SIDE_BUY, 100.0, 310.0), 2.0));
+
+        assert!(portfolio.apply_fill(&fill(2, "00700", TRD_SIDE_SELL, 150.0, 315.0), 3.0));
+        let p = &portfolio.positions()[0];
+        assert_eq!((p.qty, p.cost_price, p.realized_pl), (50.0, Some(305.0), 1500.0));
+
+        // Selling through flat opens a short at the fill price
+        assert!(portfolio.apply_fill(&fill(3, "00700", TRD_SIDE_SELL_SHORT, 80.0, 300.0), 4.0));
+        let p = &portfolio.positions()[0];
+        assert_eq!((p.qty, p.cost_price, p.realized_pl), (-30.0, Some(300.0), 1250.0));
+
+        assert!(portfolio.apply_fill(&fill(4, "00700", TRD_SIDE_BUY_BACK, 30.0, 290.0), 5.0));
+        assert!(portfolio.positions().is_empty());
+
+        let cancelled = OrderFill { status: Some(1), ..fill(5, "00700", TRD_SIDE_BUY, 10.0, 1.0) };
+        assert!(!portfolio.apply_fill(&cancelled, 6.0));
+    }
+
+    #[test]
+    fn test_fill_opens_position_and_poll_replaces_it() {
+        let portfolio = LivePortfolio::new(ACCOUNT);
+        assert!(portfolio.apply_fill(&fill(1, "09988", TRD_SIDE_BUY, 200.0, 80.0), 1.0));
+        assert_eq!(portfolio.securities(), vec![(1, "09988".to_string())]);
+        let p = &portfolio.positions()[0];
+        assert_eq!((p.qty, p.cost_price, p.last_price), (200.0, Some(80.0), 80.0));
+
+        // The poll is authoritative; the fill-realized P&L is kept when it gives none
+        let mut polled = position("09988", 200.0, 80.5, 81.0);
+        polled.position_side = POSITION_SIDE_SHORT;
+        portfolio.reconcile(&[polled], 2.0);
+        let p = &portfolio.positions()[0];
+        assert_eq!((p.qty, p.cost_price), (-200.0, Some(80.5)));
+        // Fill ids are forgotten once polled
+        assert!(portfolio.apply_fill(&fill(1, "09988", TRD_SIDE_BUY_BACK, 200.0, 80.0), 3.0));
+    }
+}
@@ -0,0 +1,248 @@
+//! Pre-trade order preview built from max quantities, margin ratio and funds.
+
+use crate::client::FutuClient;
+use crate::generated::trd_common::{Funds, MaxTrdQtys};
+use crate::generated::trd_get_margin_ratio::MarginRatioInfo;
+use super::account::TradeError;
+
+/// TrdSide values from Trd_Common.proto.
+const TRD_SIDE_BUY: i32 = 1;
+const TRD_SIDE_SELL: i32 = 2;
+const TRD_SIDE_SELL_SHORT: i32 = 3;
+const TRD_SIDE_BUY_BACK: i32 = 4;
+
+/// Result of previewing an order without submitting it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderPreview {
+    /// Whether the order fits within max quantity and buying power.
+    pub feasible: bool,
+    /// Reason the order is not feasible, if any.
+    pub reason: Option<String>,
+    /// Maximum quantity tradeable on this side.
+    pub max_qty: f64,
+    /// Notional value of the order (`qty * price`).
+    pub order_value: f64,
+    /// Initial margin ratio applied, in percent (None = fully cash funded).
+    pub initial_margin_ratio: Option<f64>,
+    /// Estimated buying power consumed by the order.
+    pub buying_power_used: f64,
+    /// Buying power before the order.
+    pub buying_power: f64,
+    /// Estimated buying power after the order.
+    pub buying_power_after: f64,
+    /// Initial margin before the order, if reported by the account.
+    pub initial_margin: Option<f64>,
+    /// Estimated initial margin after the order.
+    pub initial_margin_after: Option<f64>,
+}
+
+/// Evaluate an order against already-fetched account data.
+///
+/// Sell and buy-back orders reduce exposure, so they consume no buying power.
+/// Buy and sell-short orders consume `order_value * im_ratio / 100`, or the full
+/// order value when no margin ratio is available.
+pub fn evaluate_preview(
+    trd_side: i32,
+    qty: f64,
+    price: f64,
+    max_qtys: &MaxTrdQtys,
+    margin: Option<&MarginRatioInfo>,
+    funds: &Funds,
+) -> OrderPreview {
+    let (max_qty, im_ratio, consumes) = match trd_side {
+        TRD_SIDE_BUY => (
+            max_qtys.max_cash_and_margin_buy.unwrap_or(max_qtys.max_cash_buy),
+            margin.and_then(|m| m.im_long_ratio),
+            true,
+        ),
+        TRD_SIDE_SELL => (max_qtys.max_position_sell, None, false),
+        TRD_SIDE_SELL_SHORT => (
+            max_qtys.max_sell_short.unwrap_or(0.0),
+            margin.and_then(|m| m.im_short_ratio),
+            true,
+        ),
+        TRD_SIDE_BUY_BACK => (max_qtys.max_buy_back.unwrap_or(0.0), None, false),
+        _ => (0.0, None, false),
+    };
+
+    let order_value = qty * price;
+    let buying_power_used = if consumes {
+        match im_ratio {
+            Some(ratio) => order_value * ratio / 100.0,
+            None => order_value,
+        }
+    } else {
+        0.0
+    };
+    let buying_power_after = funds.power - buying_power_used;
+    let initial_margin_after = funds.initial_margin.map(|im| im + buying_power_used);
+
+    let reason = if !(TRD_SIDE_BUY..=TRD_SIDE_BUY_BACK).contains(&trd_side) {
+        Some(format!("unsupported trd_side: {}", trd_side))
+    } else if qty <= 0.0 {
+        Some("quantity must be positive".to_string())
+    } else if qty > max_qty {
+        Some(format!("quantity {} exceeds max tradeable {}", qty, max_qty))
+    } else if consumes && buying_power_after < 0.0 {
+        Some(format!(
+            "insufficient buying power: need {:.2}, have {:.2}",
+            buying_power_used, funds.power
+        ))
+    } else {
+        None
+    };
+
+    OrderPreview {
+        feasible: reason.is_none(),
+        reason,
+        max_qty,
+        order_value,
+        initial_margin_ratio: im_ratio,
+        buying_power_used,
+        buying_power: funds.power,
+        buying_power_after,
+        initial_margin: funds.initial_margin,
+        initial_margin_after,
+    }
+}
+
+/// The margin ratio of `market` and `code`; the same code can be listed in
+/// several markets, e.g. numeric HK and SH/SZ codes.
+fn find_margin(list: Vec<MarginRatioInfo>, market: i32, code: &str) -> Option<MarginRatioInfo> {
+    list.into_iter().find(|m| m.security.market == market && m.security.code == code)
+}
+
+/// Preview an order by querying max quantities, margin ratio and funds concurrently.
+///
+/// Nothing is submitted; `market` is the QotMarket used for the margin ratio lookup.
+#[allow(clippy::too_many_arguments)]
+pub async fn preview_order(
+    client: &FutuClient,
+    trd_env: i32,
+    acc_id: u64,
+    trd_market: i32,
+    trd_side: i32,
+    order_type: i32,
+    market: i32,
+    code: String,
+    qty: f64,
+    price: f64,
+) -> Result<OrderPreview, TradeError> {
+    let (max_resp, margin_resp, funds_resp) = tokio::try_join!(
+        super::query::get_max_trd_qtys(
+            client, trd_env, acc_id, trd_market, order_type, code.clone(), price, None,
+        ),
+        super::query::get_margin_ratio(
+            client, trd_env, acc_id, trd_market, vec![(market, code.clone())],
+        ),
+        super::query::get_funds(client, trd_env, acc_id, trd_market, None),
+    )?;
+
+    let max_qtys = max_resp
+        .s2c
+        .and_then(|s| s.max_trd_qtys)
+        .ok_or_else(|| TradeError::Decode("missing maxTrdQtys in response".to_string()))?;
+    let funds = funds_resp
+        .s2c
+        .and_then(|s| s.funds)
+        .ok_or_else(|| TradeError::Decode("missing funds in response".to_string()))?;
+    let margin = margin_resp
+        .s2c
+        .and_then(|s| find_margin(s.margin_ratio_info_list, market, &code));
+
+    Ok(evaluate_preview(trd_side, qty, price, &max_qtys, margin.as_ref(), &funds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn funds(power: f64) -> Funds {
+        Funds {
+            power,
+            initial_margin: Some(1000.0),
+            ..Default::default()
+        }
+    }
+
+    fn max_qtys() -> MaxTrdQtys {
+        MaxTrdQtys {
+            max_cash_buy: 100.0,
+            max_cash_and_margin_buy: Some(300.0),
+            max_position_sell: 200.0,
+            max_sell_short: Some(50.0),
+            max_buy_back: Some(0.0),
+            ..Default::default()
+        }
+    }
+
+    fn margin() -> MarginRatioInfo {
+        MarginRatioInfo {
+            im_long_ratio: Some(25.0),
+            im_short_ratio: Some(50.0),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_find_margin_matches_market() {
+        let info = |market: i32, ratio: f64| MarginRatioInfo {
+            security: crate::generated::qot_common::Security { market, code: "600000".to_string() },
+            im_long_ratio: Some(ratio),
+            ..Default::default()
+        };
+        let list = vec![info(1, 25.0), info(21, 50.0)];
+        assert_eq!(find_margin(list.clone(), 21, "600000").and_then(|m| m.im_long_ratio), Some(50.0));
+        assert_eq!(find_margin(list, 22, "600000"), None);
+    }
+
+    #[test]
+    fn test_buy_with_margin_ratio() {
+        let p = evaluate_preview(TRD_SIDE_BUY, 200.0, 10.0, &max_qtys(), Some(&margin()), &funds(10_000.0));
+        assert!(p.feasible);
+        assert_eq!(p.max_qty, 300.0);
+        assert_eq!(p.order_value, 2000.0);
+        assert_eq!(p.buying_power_used, 500.0);
+        assert_eq!(p.buying_power_after, 9500.0);
+        assert_eq!(p.initial_margin_after, Some(1500.0));
+    }
+
+    #[test]
+    fn test_buy_without_margin_uses_full_value() {
+        let p = evaluate_preview(TRD_SIDE_BUY, 50.0, 10.0, &max_qtys(), None, &funds(10_000.0));
+        assert!(p.feasible);
+        assert_eq!(p.initial_margin_ratio, None);
+        assert_eq!(p.buying_power_used, 500.0);
+    }
+
+    #[test]
+    fn test_exceeds_max_qty() {
+        let p = evaluate_preview(TRD_SIDE_SELL_SHORT, 60.0, 10.0, &max_qtys(), Some(&margin()), &funds(10_000.0));
+        assert!(!p.feasible);
+        assert!(p.reason.unwrap().contains("exceeds max tradeable"));
+    }
+
+    #[test]
+    fn test_insufficient_buying_power() {
+        let p = evaluate_preview(TRD_SIDE_BUY, 100.0, 10.0, &max_qtys(), None, &funds(500.0));
+        assert!(!p.feasible);
+        assert_eq!(p.buying_power_after, -500.0);
+        assert!(p.reason.unwrap().contains("insufficient buying power"));
+    }
+
+    #[test]
+    fn test_sell_consumes_nothing() {
+        let p = evaluate_preview(TRD_SIDE_SELL, 200.0, 10.0, &max_qtys(), Some(&margin()), &funds(0.0));
+        assert!(p.feasible);
+        assert_eq!(p.buying_power_used, 0.0);
+        assert_eq!(p.initial_margin_after, Some(1000.0));
+    }
+
+    #[test]
+    fn test_invalid_side_and_qty() {
+        let p = evaluate_preview(0, 10.0, 10.0, &max_qtys(), None, &funds(10_000.0));
+        assert!(!p.feasible);
+        let p = evaluate_preview(TRD_SIDE_BUY, 0.0, 10.0, &max_qtys(), None, &funds(10_000.0));
+        assert_eq!(p.reason.as_deref(), Some("quantity must be positive"));
+    }
+}
@@ -1,36 +1,129 @@
 //! Subscribe to trading push notifications.
+//!
+//! Trd_SubAccPush sets the whole list of accounts a connection receives
+//! order and fill pushes for; every request replaces the previous list. The
+//! client keeps the list in `AccPushAccounts`, so `sub_acc_push` adds to it,
+//! `unsub_acc_push` removes from it, and the list is subscribed again after
+//! a reconnect, which starts OpenD off with none.
 
+use std::collections::BTreeSet;
+use parking_lot::Mutex;
 use prost::Message;
 use crate::client::FutuClient;
 use super::TradeError;
 
-const PROTO_TRD_SUB_ACC_PUSH: u32 = 2008;
+pub(crate) const PROTO_TRD_SUB_ACC_PUSH: u32 = 2008;
 
-/// Subscribe to trading account push notifications for the given account IDs.
+/// How `sub_acc_push_with_mode` changes the subscribed accounts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccPushMode {
+    /// Subscribe the accounts besides those already subscribed.
+    Add,
+    /// Subscribe exactly these accounts.
+    Replace,
+}
+
+/// Accounts the connection receives trade pushes for.
+#[derive(Debug, Default)]
+pub struct AccPushAccounts {
+    accounts: Mutex<BTreeSet<u64>>,
+    /// Held while a change is sent, so concurrent changes do not lose each other.
+    update: tokio::sync::Mutex<()>,
+}
+
+impl AccPushAccounts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribed accounts, ascending.
+    pub fn accounts(&self) -> Vec<u64> {
+        self.accounts.lock().iter().copied().collect()
+    }
+
+    pub fn contains(&self, acc_id: u64) -> bool {
+        self.accounts.lock().contains(&acc_id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.lock().is_empty()
+    }
+
+    /// Record the accounts OpenD accepted.
+    pub fn set(&self, accounts: impl IntoIterator<Item = u64>) {
+        *self.accounts.lock() = accounts.into_iter().collect();
+    }
+}
+
+/// Body of a Trd_SubAccPush request for `acc_ids`.
+pub(crate) fn sub_acc_push_request(acc_ids: Vec<u64>) -> Vec<u8> {
+    let c2s = crate::generated::trd_sub_acc_push::C2s { acc_id_list: acc_ids };
+    crate::generated::trd_sub_acc_push::Request { c2s }.encode_to_vec()
+}
+
+/// Subscribe to trading account push notifications for the given account
+/// IDs, besides those already subscribed.
 pub async fn sub_acc_push(
     client: &FutuClient,
     acc_ids: Vec<u64>,
 ) -> Result<(), TradeError> {
-    let c2s = crate::generated::trd_sub_acc_push::C2s {
-        acc_id_list: acc_ids,
-    };
-    let request = crate::generated::trd_sub_acc_push::Request { c2s };
-    let body = request.encode_to_vec();
+    sub_acc_push_with_mode(client, acc_ids, AccPushMode::Add).await?;
+    Ok(())
+}
+
+/// Change the subscribed accounts by `mode`. Returns the accounts
+/// subscribed afterwards.
+pub async fn sub_acc_push_with_mode(
+    client: &FutuClient,
+    acc_ids: Vec<u64>,
+    mode: AccPushMode,
+) -> Result<Vec<u64>, TradeError> {
+    update_acc_push(client, |accounts| match mode {
+        AccPushMode::Add => accounts.extend(acc_ids),
+        AccPushMode::Replace => *accounts = acc_ids.into_iter().collect(),
+    }).await
+}
+
+/// Stop trade pushes of the given accounts. Returns the accounts still
+/// subscribed.
+pub async fn unsub_acc_push(
+    client: &FutuClient,
+    acc_ids: Vec<u64>,
+) -> Result<Vec<u64>, TradeError> {
+    update_acc_push(client, |accounts| accounts.retain(|acc_id| !acc_ids.contains(acc_id))).await
+}
+
+/// Subscribe the recorded accounts again, e.g. after OpenD restarted
+/// without the client losing its connection. Returns them.
+pub async fn refresh_acc_push(client: &FutuClient) -> Result<Vec<u64>, TradeError> {
+    update_acc_push(client, |_| {}).await
+}
+
+/// Send the accounts `change` leaves subscribed and record them once
+/// OpenD accepts them.
+async fn update_acc_push(
+    client: &FutuClient,
+    change: impl FnOnce(&mut BTreeSet<u64>),
+) -> Result<Vec<u64>, TradeError> {
+    let accounts = client.acc_push();
+    let _update = accounts.update.lock().await;
+    let mut next = accounts.accounts.lock().clone();
+    change(&mut next);
+    let acc_ids: Vec<u64> = next.iter().copied().collect();
+    let body = sub_acc_push_request(acc_ids.clone());
 
     let resp = client.request(PROTO_TRD_SUB_ACC_PUSH, &body).await
         .map_err(TradeError::Connection)?;
 
-    let response = crate::generated::trd_sub_acc_push::Response::decode(resp.body.as_slice())
-        .map_err(|e| TradeError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::trd_sub_acc_push::Response>(&resp)
+        .map_err(TradeError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(TradeError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(TradeError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
-    Ok(())
+    accounts.set(next);
+    Ok(acc_ids)
 }
 
 #[cfg(test)]
@@ -67,4 +160,14 @@ mod tests {
         assert_eq!(decoded.ret_type, 0);
         assert!(decoded.s2c.is_some());
     }
+
+    #[test]
+    fn test_acc_push_accounts_set_sorts_and_dedups() {
+        let accounts = AccPushAccounts::new();
+        assert!(accounts.is_empty());
+        accounts.set(vec![3, 1, 3]);
+        assert_eq!(accounts.accounts(), vec![1, 3]);
+        assert!(accounts.contains(3));
+        assert!(!accounts.contains(2));
+    }
 }
@@ -1,6 +1,8 @@
 use prost::Message;
 use crate::client::FutuClient;
+use crate::dates::{civil_from_days, days_from_civil};
 use super::account::TradeError;
+use super::funds::{get_cash_flow, CashFlow, CashFlowDirection};
 
 const PROTO_TRD_GET_ORDER_LIST: u32 = 2201;
 const PROTO_TRD_GET_ORDER_FILL_LIST: u32 = 2211;
@@ -12,14 +14,19 @@ const PROTO_TRD_GET_MAX_TRD_QTYS: u32 = 2111;
 const PROTO_TRD_GET_MARGIN_RATIO: u32 = 2223;
 const PROTO_TRD_GET_ORDER_FEE: u32 = 2225;
 
+/// Longest range of clearing dates `get_flow_summary` requests.
+const MAX_FLOW_SUMMARY_DAYS: i64 = 366;
+
 /// Get the order list.
 pub async fn get_order_list(
     client: &FutuClient,
-    trd_env: i32,
+    trd_env: impl Into<i32>,
     acc_id: u64,
-    trd_market: i32,
+    trd_market: impl Into<i32>,
     filter: Option<crate::generated::trd_common::TrdFilterConditions>,
 ) -> Result<crate::generated::trd_get_order_list::Response, TradeError> {
+    let trd_env = trd_env.into();
+    let trd_market = trd_market.into();
     let header = crate::generated::trd_common::TrdHeader {
         trd_env,
         acc_id,
@@ -37,14 +44,11 @@ pub async fn get_order_list(
     let resp = client.request(PROTO_TRD_GET_ORDER_LIST, &body).await
         .map_err(TradeError::Connection)?;
 
-    let response = crate::generated::trd_get_order_list::Response::decode(resp.body.as_slice())
-        .map_err(|e| TradeError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::trd_get_order_list::Response>(&resp)
+        .map_err(TradeError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(TradeError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(TradeError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -53,11 +57,13 @@ pub async fn get_order_list(
 /// Get order fills.
 pub async fn get_order_fill_list(
     client: &FutuClient,
-    trd_env: i32,
+    trd_env: impl Into<i32>,
     acc_id: u64,
-    trd_market: i32,
+    trd_market: impl Into<i32>,
     filter: Option<crate::generated::trd_common::TrdFilterConditions>,
 ) -> Result<crate::generated::trd_get_order_fill_list::Response, TradeError> {
+    let trd_env = trd_env.into();
+    let trd_market = trd_market.into();
     let header = crate::generated::trd_common::TrdHeader {
         trd_env,
         acc_id,
@@ -75,14 +81,11 @@ pub async fn get_order_fill_list(
     let resp = client.request(PROTO_TRD_GET_ORDER_FILL_LIST, &body).await
         .map_err(TradeError::Connection)?;
 
-    let response = crate::generated::trd_get_order_fill_list::Response::decode(resp.body.as_slice())
-        .map_err(|e| TradeError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::trd_get_order_fill_list::Response>(&resp)
+        .map_err(TradeError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(TradeError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(TradeError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -91,11 +94,13 @@ pub async fn get_order_fill_list(
 /// Get position list.
 pub async fn get_position_list(
     client: &FutuClient,
-    trd_env: i32,
+    trd_env: impl Into<i32>,
     acc_id: u64,
-    trd_market: i32,
+    trd_market: impl Into<i32>,
     filter: Option<crate::generated::trd_common::TrdFilterConditions>,
 ) -> Result<crate::generated::trd_get_position_list::Response, TradeError> {
+    let trd_env = trd_env.into();
+    let trd_market = trd_market.into();
     let header = crate::generated::trd_common::TrdHeader {
         trd_env,
         acc_id,
@@ -113,14 +118,11 @@ pub async fn get_position_list(
     let resp = client.request(PROTO_TRD_GET_POSITION_LIST, &body).await
         .map_err(TradeError::Connection)?;
 
-    let response = crate::generated::trd_get_position_list::Response::decode(resp.body.as_slice())
-        .map_err(|e| TradeError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::trd_get_position_list::Response>(&resp)
+        .map_err(TradeError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(TradeError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(TradeError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -129,11 +131,13 @@ pub async fn get_position_list(
 /// Get account funds.
 pub async fn get_funds(
     client: &FutuClient,
-    trd_env: i32,
+    trd_env: impl Into<i32>,
     acc_id: u64,
-    trd_market: i32,
+    trd_market: impl Into<i32>,
     currency: Option<i32>,
 ) -> Result<crate::generated::trd_get_funds::Response, TradeError> {
+    let trd_env = trd_env.into();
+    let trd_market = trd_market.into();
     let header = crate::generated::trd_common::TrdHeader {
         trd_env,
         acc_id,
@@ -151,14 +155,11 @@ pub async fn get_funds(
     let resp = client.request(PROTO_TRD_GET_FUNDS, &body).await
         .map_err(TradeError::Connection)?;
 
-    let response = crate::generated::trd_get_funds::Response::decode(resp.body.as_slice())
-        .map_err(|e| TradeError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::trd_get_funds::Response>(&resp)
+        .map_err(TradeError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(TradeError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(TradeError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -167,12 +168,14 @@ pub async fn get_funds(
 /// Get historical order list.
 pub async fn get_history_order_list(
     client: &FutuClient,
-    trd_env: i32,
+    trd_env: impl Into<i32>,
     acc_id: u64,
-    trd_market: i32,
+    trd_market: impl Into<i32>,
     filter: Option<crate::generated::trd_common::TrdFilterConditions>,
     filter_status_list: Vec<i32>,
 ) -> Result<crate::generated::trd_get_history_order_list::Response, TradeError> {
+    let trd_env = trd_env.into();
+    let trd_market = trd_market.into();
     let header = crate::generated::trd_common::TrdHeader {
         trd_env,
         acc_id,
@@ -191,14 +194,11 @@ pub async fn get_history_order_list(
     let resp = client.request(PROTO_TRD_GET_HISTORY_ORDER_LIST, &body).await
         .map_err(TradeError::Connection)?;
 
-    let response = crate::generated::trd_get_history_order_list::Response::decode(resp.body.as_slice())
-        .map_err(|e| TradeError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::trd_get_history_order_list::Response>(&resp)
+        .map_err(TradeError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(TradeError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(TradeError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -207,11 +207,13 @@ pub async fn get_history_order_list(
 /// Get historical order fill list.
 pub async fn get_history_order_fill_list(
     client: &FutuClient,
-    trd_env: i32,
+    trd_env: impl Into<i32>,
     acc_id: u64,
-    trd_market: i32,
+    trd_market: impl Into<i32>,
     filter: Option<crate::generated::trd_common::TrdFilterConditions>,
 ) -> Result<crate::generated::trd_get_history_order_fill_list::Response, TradeError> {
+    let trd_env = trd_env.into();
+    let trd_market = trd_market.into();
     let header = crate::generated::trd_common::TrdHeader {
         trd_env,
         acc_id,
@@ -229,14 +231,11 @@ pub async fn get_history_order_fill_list(
     let resp = client.request(PROTO_TRD_GET_HISTORY_ORDER_FILL_LIST, &body).await
         .map_err(TradeError::Connection)?;
 
-    let response = crate::generated::trd_get_history_order_fill_list::Response::decode(resp.body.as_slice())
-        .map_err(|e| TradeError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::trd_get_history_order_fill_list::Response>(&resp)
+        .map_err(TradeError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(TradeError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(TradeError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -246,14 +245,17 @@ pub async fn get_history_order_fill_list(
 #[allow(clippy::too_many_arguments)]
 pub async fn get_max_trd_qtys(
     client: &FutuClient,
-    trd_env: i32,
+    trd_env: impl Into<i32>,
     acc_id: u64,
-    trd_market: i32,
-    order_type: i32,
+    trd_market: impl Into<i32>,
+    order_type: impl Into<i32>,
     code: String,
     price: f64,
     sec_market: Option<i32>,
 ) -> Result<crate::generated::trd_get_max_trd_qtys::Response, TradeError> {
+    let trd_env = trd_env.into();
+    let trd_market = trd_market.into();
+    let order_type = order_type.into();
     let header = crate::generated::trd_common::TrdHeader {
         trd_env,
         acc_id,
@@ -274,14 +276,11 @@ pub async fn get_max_trd_qtys(
     let resp = client.request(PROTO_TRD_GET_MAX_TRD_QTYS, &body).await
         .map_err(TradeError::Connection)?;
 
-    let response = crate::generated::trd_get_max_trd_qtys::Response::decode(resp.body.as_slice())
-        .map_err(|e| TradeError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::trd_get_max_trd_qtys::Response>(&resp)
+        .map_err(TradeError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(TradeError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(TradeError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -290,11 +289,13 @@ pub async fn get_max_trd_qtys(
 /// Get margin ratio for securities.
 pub async fn get_margin_ratio(
     client: &FutuClient,
-    trd_env: i32,
+    trd_env: impl Into<i32>,
     acc_id: u64,
-    trd_market: i32,
+    trd_market: impl Into<i32>,
     securities: Vec<(i32, String)>,
 ) -> Result<crate::generated::trd_get_margin_ratio::Response, TradeError> {
+    let trd_env = trd_env.into();
+    let trd_market = trd_market.into();
     let header = crate::generated::trd_common::TrdHeader {
         trd_env,
         acc_id,
@@ -316,14 +317,11 @@ pub async fn get_margin_ratio(
     let resp = client.request(PROTO_TRD_GET_MARGIN_RATIO, &body).await
         .map_err(TradeError::Connection)?;
 
-    let response = crate::generated::trd_get_margin_ratio::Response::decode(resp.body.as_slice())
-        .map_err(|e| TradeError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::trd_get_margin_ratio::Response>(&resp)
+        .map_err(TradeError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(TradeError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(TradeError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
@@ -332,11 +330,13 @@ pub async fn get_margin_ratio(
 /// Get order fee details.
 pub async fn get_order_fee(
     client: &FutuClient,
-    trd_env: i32,
+    trd_env: impl Into<i32>,
     acc_id: u64,
-    trd_market: i32,
+    trd_market: impl Into<i32>,
     order_id_ex_list: Vec<String>,
 ) -> Result<crate::generated::trd_get_order_fee::Response, TradeError> {
+    let trd_env = trd_env.into();
+    let trd_market = trd_market.into();
     let header = crate::generated::trd_common::TrdHeader {
         trd_env,
         acc_id,
@@ -353,19 +353,64 @@ pub async fn get_order_fee(
     let resp = client.request(PROTO_TRD_GET_ORDER_FEE, &body).await
         .map_err(TradeError::Connection)?;
 
-    let response = crate::generated::trd_get_order_fee::Response::decode(resp.body.as_slice())
-        .map_err(|e| TradeError::Decode(e.to_string()))?;
+    let response = client.decode::<crate::generated::trd_get_order_fee::Response>(&resp)
+        .map_err(TradeError::Decode)?;
 
     if response.ret_type != 0 {
-        return Err(TradeError::Server {
-            ret_type: response.ret_type,
-            msg: response.ret_msg.unwrap_or_default(),
-        });
+        return Err(TradeError::server(response.ret_type, response.ret_msg, response.err_code));
     }
 
     Ok(response)
 }
 
+/// Cash flows of an account with a clearing date from `begin_date` to
+/// `end_date` ("YYYY-MM-DD", both included), oldest first, optionally only
+/// those in one direction. Trd_FlowSummary takes a single clearing date, so
+/// this sends one request per day of the range, paced by `get_cash_flow`
+/// to OpenD's limit of 20 requests per 30 seconds: ranges over 20 days wait
+/// for the limit, and a year takes about 9 minutes.
+pub async fn get_flow_summary(
+    client: &FutuClient,
+    trd_env: impl Into<i32>,
+    acc_id: u64,
+    trd_market: impl Into<i32>,
+    begin_date: &str,
+    end_date: &str,
+    direction: Option<CashFlowDirection>,
+) -> Result<Vec<CashFlow>, TradeError> {
+    let trd_env = trd_env.into();
+    let trd_market = trd_market.into();
+    let mut flows = Vec::new();
+    for clearing_date in clearing_dates(begin_date, end_date)? {
+        flows.extend(get_cash_flow(client, trd_env, acc_id, trd_market, clearing_date, direction).await?);
+    }
+    Ok(flows)
+}
+
+/// The dates from `begin` to `end`, both included.
+fn clearing_dates(begin: &str, end: &str) -> Result<Vec<String>, TradeError> {
+    let parse = |date: &str| {
+        let mut parts = date.trim().split('-').map(|p| p.parse::<i64>().ok());
+        match (parts.next().flatten(), parts.next().flatten(), parts.next().flatten(), parts.next()) {
+            (Some(y), Some(m), Some(d), None) if (1..=12).contains(&m) && (1..=31).contains(&d) => Ok(days_from_civil(y, m, d)),
+            _ => Err(TradeError::InvalidRange(format!("cannot parse date '{}'", date))),
+        }
+    };
+    let (first, last) = (parse(begin)?, parse(end)?);
+    if last < first {
+        return Err(TradeError::InvalidRange(format!("end {} is before begin {}", end, begin)));
+    }
+    if last - first >= MAX_FLOW_SUMMARY_DAYS {
+        return Err(TradeError::InvalidRange(format!("{} to {} is longer than {} days", begin, end, MAX_FLOW_SUMMARY_DAYS)));
+    }
+    Ok((first..=last)
+        .map(|day| {
+            let (y, m, d) = civil_from_days(day);
+            format!("{:04}-{:02}-{:02}", y, m, d)
+        })
+        .collect())
+}
+
 #[cfg(test)]
 mod tests {
     use prost::Message;
@@ -465,4 +510,54 @@ mod tests {
         assert_eq!(decoded.ret_msg.unwrap(), "unauthorized");
         assert!(decoded.s2c.is_none());
     }
+
+    #[test]
+    fn test_flow_summary_clearing_dates() {
+        use super::{clearing_dates, TradeError};
+        assert_eq!(clearing_dates("2024-02-28", "2024-03-01").unwrap(), vec!["2024-02-28", "2024-02-29", "2024-03-01"]);
+        assert_eq!(clearing_dates("2024-05-02", "2024-05-02").unwrap(), vec!["2024-05-02"]);
+        assert!(matches!(clearing_dates("2024-03-01", "2024-02-28"), Err(TradeError::InvalidRange(_))));
+        assert!(matches!(clearing_dates("2024-13-01", "2024-12-01"), Err(TradeError::InvalidRange(_))));
+        assert!(matches!(clearing_dates("2023-01-01", "2024-12-31"), Err(TradeError::InvalidRange(_))));
+    }
+
+    #[tokio::test]
+    async fn test_flow_summary_paced_to_limit() {
+        use std::sync::Arc;
+        use std::time::Duration;
+        use crate::client::clock::MockClock;
+        use crate::client::mock::MockOpenD;
+        use crate::client::FutuClient;
+        use crate::config::FutuConfig;
+
+        let mock = MockOpenD::start().await.unwrap();
+        let clock = Arc::new(MockClock::new(1_700_000_000.0));
+        let config = FutuConfig {
+            port: mock.port(),
+            enable_encryption: false,
+            enable_funds_protocols: true,
+            clock: clock.clone(),
+            ..FutuConfig::default()
+        };
+        let mut client = FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+        let client = Arc::new(client);
+        mock.respond(2226, vec![0x08, 0x00]);
+
+        // 20 days fit the limit
+        super::get_flow_summary(&client, 0, 1, 1, "2024-01-01", "2024-01-20", None).await.unwrap();
+
+        // The next requests wait for the limit to refill
+        let idle = clock.sleepers();
+        let task = tokio::spawn({
+            let client = Arc::clone(&client);
+            async move { super::get_flow_summary(&client, 0, 1, 1, "2024-02-01", "2024-02-05", None).await }
+        });
+        while clock.sleepers() == idle {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert!(!task.is_finished());
+        clock.advance(Duration::from_secs(30));
+        task.await.unwrap().unwrap();
+    }
 }
@@ -0,0 +1,332 @@
+//! Execution-quality report: fills attributed to the 1-minute bar they
+//! executed in.
+//!
+//! OpenD keeps no historical quotes, so the benchmark for a fill is the
+//! mid-price, (high + low) / 2, of the 1-minute K-line covering the fill
+//! time. Slippage is signed so that a positive value is a cost: paying
+//! above the benchmark on a buy, or selling below it.
+
+use std::collections::HashMap;
+
+use crate::client::FutuClient;
+use crate::generated::qot_common::KLine;
+use crate::generated::trd_common::{OrderFill, TrdFilterConditions};
+use crate::quote::names::trd_sec_market_to_qot_market;
+use super::account::TradeError;
+
+const KL_TYPE_1MIN: i32 = 1;
+const REHAB_TYPE_NONE: i32 = 0;
+
+/// One fill with its benchmark and slippage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FillAttribution {
+    pub fill_id: u64,
+    pub order_id: Option<u64>,
+    pub code: String,
+    /// TrdSecMarket
+    pub sec_market: Option<i32>,
+    pub trd_side: i32,
+    pub qty: f64,
+    pub price: f64,
+    pub fill_time: String,
+    pub fill_timestamp: Option<f64>,
+    /// Mid-price of the covering 1-minute bar, if one was found.
+    pub benchmark: Option<f64>,
+    /// Signed price difference per share; positive is a cost.
+    pub slippage: Option<f64>,
+    pub slippage_bps: Option<f64>,
+}
+
+/// Implementation shortfall of one order, measured against the benchmark
+/// of its first fill (the arrival price).
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderShortfall {
+    pub order_id: u64,
+    pub code: String,
+    pub trd_side: i32,
+    pub fill_count: usize,
+    pub filled_qty: f64,
+    pub avg_price: f64,
+    pub arrival_price: Option<f64>,
+    /// Total cost versus the arrival price, in trade currency; positive is a cost.
+    pub shortfall: Option<f64>,
+    pub shortfall_bps: Option<f64>,
+}
+
+/// Fill-level attribution and order-level shortfall for one trading day.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExecutionReport {
+    /// Fills sorted by fill time.
+    pub fills: Vec<FillAttribution>,
+    /// Orders sorted by order id. Fills without an order id are not included.
+    pub orders: Vec<OrderShortfall>,
+}
+
+impl ExecutionReport {
+    /// Fill rows as CSV with a header line.
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "fill_id,order_id,code,sec_market,trd_side,qty,price,fill_time,benchmark,slippage,slippage_bps\n",
+        );
+        for f in &self.fills {
+            let row = [
+                f.fill_id.to_string(),
+                opt(f.order_id),
+                csv_field(&f.code),
+                opt(f.sec_market),
+                f.trd_side.to_string(),
+                f.qty.to_string(),
+                f.price.to_string(),
+                csv_field(&f.fill_time),
+                opt(f.benchmark),
+                opt(f.slippage),
+                opt(f.slippage_bps),
+            ];
+            csv.push_str(&row.join(","));
+            csv.push('\n');
+        }
+        csv
+    }
+}
+
+/// +1 for buys, -1 for sells (TrdSide: 1 Buy, 2 Sell, 3 SellShort, 4 BuyBack).
+fn side_sign(trd_side: i32) -> f64 {
+    match trd_side {
+        2 | 3 => -1.0,
+        _ => 1.0,
+    }
+}
+
+/// Mid-price of the first bar ending at or after `ts`. Minute K-line
+/// timestamps mark the end of the bar.
+fn bar_mid(bars: &[KLine], ts: f64) -> Option<f64> {
+    let bar = bars.iter().find(|b| !b.is_blank && b.timestamp.is_some_and(|t| t >= ts))?;
+    match (bar.high_price, bar.low_price) {
+        (Some(high), Some(low)) => Some((high + low) / 2.0),
+        _ => bar.close_price,
+    }
+}
+
+/// Build the report from fills and 1-minute bars keyed by (QotMarket, code).
+/// Bars must be sorted by timestamp.
+pub fn build_execution_report(
+    fills: &[OrderFill],
+    bars: &HashMap<(i32, String), Vec<KLine>>,
+) -> ExecutionReport {
+    let mut rows: Vec<FillAttribution> = fills
+        .iter()
+        .map(|f| {
+            let benchmark = f.sec_market.and_then(trd_sec_market_to_qot_market).and_then(|market| {
+                let security_bars = bars.get(&(market, f.code.clone()))?;
+                bar_mid(security_bars, f.create_timestamp?)
+            });
+            let slippage = benchmark.map(|b| side_sign(f.trd_side) * (f.price - b));
+            let slippage_bps = slippage.zip(benchmark).and_then(|(s, b)| (b != 0.0).then(|| s / b * 10_000.0));
+            FillAttribution {
+                fill_id: f.fill_id,
+                order_id: f.order_id,
+                code: f.code.clone(),
+                sec_market: f.sec_market,
+                trd_side: f.trd_side,
+                qty: f.qty,
+                price: f.price,
+                fill_time: f.create_time.clone(),
+                fill_timestamp: f.create_timestamp,
+                benchmark,
+                slippage,
+                slippage_bps,
+            }
+        })
+        .collect();
+    rows.sort_by(|a, b| a.fill_time.cmp(&b.fill_time).then(a.fill_id.cmp(&b.fill_id)));
+
+    let mut by_order: HashMap<u64, Vec<&FillAttribution>> = HashMap::new();
+    for row in &rows {
+        if let Some(order_id) = row.order_id {
+            by_order.entry(order_id).or_default().push(row);
+        }
+    }
+    let mut orders: Vec<OrderShortfall> = by_order
+        .into_iter()
+        .map(|(order_id, order_fills)| {
+            let first = order_fills[0];
+            let filled_qty: f64 = order_fills.iter().map(|f| f.qty).sum();
+            let notional: f64 = order_fills.iter().map(|f| f.qty * f.price).sum();
+            let avg_price = if filled_qty > 0.0 { notional / filled_qty } else { 0.0 };
+            let arrival_price = first.benchmark;
+            let shortfall = arrival_price.map(|a| side_sign(first.trd_side) * (avg_price - a) * filled_qty);
+            let shortfall_bps = shortfall
+                .zip(arrival_price)
+                .and_then(|(s, a)| (a != 0.0 && filled_qty > 0.0).then(|| s / (a * filled_qty) * 10_000.0));
+            OrderShortfall {
+                order_id,
+                code: first.code.clone(),
+                trd_side: first.trd_side,
+                fill_count: order_fills.len(),
+                filled_qty,
+                avg_price,
+                arrival_price,
+                shortfall,
+                shortfall_bps,
+            }
+        })
+        .collect();
+    orders.sort_by_key(|o| o.order_id);
+
+    ExecutionReport { fills: rows, orders }
+}
+
+/// Build the execution report of `day` ("YYYY-MM-DD") for an account.
+///
+/// Fills come from both the history and today's fill lists, so the report
+/// works for past days and the current session. Securities whose bars
+/// cannot be fetched are reported without a benchmark.
+pub async fn execution_report(
+    client: &FutuClient,
+    trd_env: i32,
+    acc_id: u64,
+    trd_market: i32,
+    day: &str,
+) -> Result<ExecutionReport, TradeError> {
+    let filter = TrdFilterConditions {
+        begin_time: Some(format!("{} 00:00:00", day)),
+        end_time: Some(format!("{} 23:59:59", day)),
+        ..Default::default()
+    };
+
+    let history = super::query::get_history_order_fill_list(client, trd_env, acc_id, trd_market, Some(filter.clone()))
+        .await?
+        .s2c
+        .map(|s| s.order_fill_list)
+        .unwrap_or_default();
+    let today = super::query::get_order_fill_list(client, trd_env, acc_id, trd_market, Some(filter))
+        .await?
+        .s2c
+        .map(|s| s.order_fill_list)
+        .unwrap_or_default();
+
+    let mut fills: Vec<OrderFill> = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+    for fill in history.into_iter().chain(today) {
+        if fill.create_time.starts_with(day) && seen.insert(fill.fill_id) {
+            fills.push(fill);
+        }
+    }
+
+    let mut bars: HashMap<(i32, String), Vec<KLine>> = HashMap::new();
+    for fill in &fills {
+        let Some(market) = fill.sec_market.and_then(trd_sec_market_to_qot_market) else {
+            continue;
+        };
+        let key = (market, fill.code.clone());
+        if bars.contains_key(&key) {
+            continue;
+        }
+        let kl_list = match crate::quote::history::get_history_kl(
+            client, market, fill.code.clone(), REHAB_TYPE_NONE, KL_TYPE_1MIN,
+            day.to_string(), day.to_string(), None,
+        ).await {
+            Ok(resp) => resp.s2c.map(|s| s.kl_list).unwrap_or_default(),
+            Err(e) => {
+                tracing::warn!("No benchmark bars for {}.{}: {}", market, fill.code, e);
+                Vec::new()
+            }
+        };
+        bars.insert(key, kl_list);
+    }
+
+    Ok(build_execution_report(&fills, &bars))
+}
+
+fn opt<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fill(fill_id: u64, order_id: u64, trd_side: i32, qty: f64, price: f64, ts: f64) -> OrderFill {
+        OrderFill {
+            trd_side,
+            fill_id,
+            order_id: Some(order_id),
+            code: "00700".to_string(),
+            qty,
+            price,
+            create_time: format!("2024-01-02 09:30:{:02}", fill_id),
+            sec_market: Some(1),
+            create_timestamp: Some(ts),
+            ..Default::default()
+        }
+    }
+
+    fn bar(ts: f64, high: f64, low: f64) -> KLine {
+        KLine {
+            high_price: Some(high),
+            low_price: Some(low),
+            close_price: Some(low),
+            timestamp: Some(ts),
+            ..Default::default()
+        }
+    }
+
+    fn bars() -> HashMap<(i32, String), Vec<KLine>> {
+        let mut bars = HashMap::new();
+        bars.insert((1, "00700".to_string()), vec![bar(60.0, 101.0, 99.0), bar(120.0, 103.0, 101.0)]);
+        bars
+    }
+
+    #[test]
+    fn test_fill_slippage_sign() {
+        let fills = vec![fill(1, 10, 1, 100.0, 100.5, 30.0), fill(2, 20, 2, 100.0, 101.5, 90.0)];
+        let report = build_execution_report(&fills, &bars());
+
+        let buy = &report.fills[0];
+        assert_eq!(buy.benchmark, Some(100.0));
+        assert_eq!(buy.slippage, Some(0.5));
+        assert_eq!(buy.slippage_bps, Some(50.0));
+
+        let sell = &report.fills[1];
+        assert_eq!(sell.benchmark, Some(102.0));
+        assert_eq!(sell.slippage, Some(0.5));
+    }
+
+    #[test]
+    fn test_order_shortfall_against_arrival() {
+        let fills = vec![fill(1, 10, 1, 100.0, 100.0, 30.0), fill(2, 10, 1, 300.0, 102.0, 90.0)];
+        let report = build_execution_report(&fills, &bars());
+
+        assert_eq!(report.orders.len(), 1);
+        let order = &report.orders[0];
+        assert_eq!(order.fill_count, 2);
+        assert_eq!(order.filled_qty, 400.0);
+        assert_eq!(order.avg_price, 101.5);
+        assert_eq!(order.arrival_price, Some(100.0));
+        assert_eq!(order.shortfall, Some(600.0));
+        assert_eq!(order.shortfall_bps, Some(150.0));
+    }
+
+    #[test]
+    fn test_missing_bars_and_csv() {
+        let mut late = fill(1, 10, 1, 100.0, 100.0, 500.0);
+        late.code = "A,B".to_string();
+        let report = build_execution_report(&[late], &bars());
+        assert_eq!(report.fills[0].benchmark, None);
+        assert_eq!(report.orders[0].shortfall, None);
+
+        let csv = report.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("fill_id,order_id,code"));
+        assert_eq!(lines[1], "1,10,\"A,B\",1,1,100,100,2024-01-02 09:30:01,,,");
+    }
+}
@@ -0,0 +1,203 @@
+//! In-order delivery of order and fill pushes.
+//!
+//! Trd_UpdateOrder and Trd_UpdateOrderFill arrive as separate protocols, and
+//! OpenD may send a fill before the order update acknowledging the order, or
+//! the two may be reordered on their way to a consumer. A strategy must not
+//! see a fill for an order it has not seen acknowledged, so
+//! `TradeSequencer` holds such fills until the first order update of their
+//! order passes and then releases them right after it. Fills of orders
+//! acknowledged before the sequencer started would wait forever, so held
+//! fills are released unacknowledged after `max_hold`.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use prost::Message;
+
+pub(crate) const PROTO_TRD_UPDATE_ORDER: u32 = 2208;
+pub(crate) const PROTO_TRD_UPDATE_ORDER_FILL: u32 = 2218;
+
+/// Default time a fill waits for its order update, in seconds.
+pub const DEFAULT_MAX_HOLD: f64 = 2.0;
+
+/// Acknowledged orders remembered; the oldest are forgotten beyond this.
+const MAX_ACKED: usize = 10_000;
+
+/// Order of a push: (acc_id, order_id).
+pub type OrderKey = (u64, u64);
+
+/// What a trade push means for sequencing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeEvent {
+    /// Order update; acknowledges the order.
+    Order(OrderKey),
+    /// Fill of an order.
+    Fill(OrderKey),
+}
+
+impl TradeEvent {
+    /// Sequencing role of a push, or None for pushes passed through as they
+    /// come (other protocols, undecodable bodies, fills without order id).
+    pub fn of_push(proto_id: u32, body: &[u8]) -> Option<Self> {
+        match proto_id {
+            PROTO_TRD_UPDATE_ORDER => {
+                let s2c = crate::generated::trd_update_order::Response::decode(body).ok()?.s2c?;
+                Some(TradeEvent::Order((s2c.header.acc_id, s2c.order.order_id)))
+            }
+            PROTO_TRD_UPDATE_ORDER_FILL => {
+                let s2c = crate::generated::trd_update_order_fill::Response::decode(body).ok()?.s2c?;
+                Some(TradeEvent::Fill((s2c.header.acc_id, s2c.order_fill.order_id?)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Reorders trade pushes so every order's fills follow its first order
+/// update. `T` is the push as handed to the consumer.
+#[derive(Debug)]
+pub struct TradeSequencer<T> {
+    max_hold: f64,
+    acked: HashSet<OrderKey>,
+    acked_order: VecDeque<OrderKey>,
+    /// Fills waiting for their order update, with the time they arrived.
+    held: HashMap<OrderKey, Vec<(f64, T)>>,
+}
+
+impl<T> Default for TradeSequencer<T> {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_HOLD)
+    }
+}
+
+impl<T> TradeSequencer<T> {
+    /// Hold early fills for at most `max_hold` seconds.
+    pub fn new(max_hold: f64) -> Self {
+        Self {
+            max_hold,
+            acked: HashSet::new(),
+            acked_order: VecDeque::new(),
+            held: HashMap::new(),
+        }
+    }
+
+    /// Accept a push and return the pushes to deliver now, in order. An
+    /// order update comes out followed by the fills held for its order.
+    pub fn push(&mut self, event: Option<TradeEvent>, item: T, now: f64) -> Vec<T> {
+        match event {
+            None => vec![item],
+            Some(TradeEvent::Fill(key)) if !self.acked.contains(&key) => {
+                tracing::debug!("Holding fill of order {} until its order update", key.1);
+                self.held.entry(key).or_default().push((now, item));
+                Vec::new()
+            }
+            Some(TradeEvent::Fill(_)) => vec![item],
+            Some(TradeEvent::Order(key)) => {
+                self.ack(key);
+                let mut out = vec![item];
+                out.extend(self.held.remove(&key).into_iter().flatten().map(|(_, fill)| fill));
+                out
+            }
+        }
+    }
+
+    /// Release fills held for `max_hold` or longer, oldest first. Their
+    /// orders count as acknowledged from then on.
+    pub fn flush_expired(&mut self, now: f64) -> Vec<T> {
+        let expired: Vec<OrderKey> = self
+            .held
+            .iter()
+            .filter(|(_, fills)| fills.first().is_some_and(|(at, _)| now - at >= self.max_hold))
+            .map(|(key, _)| *key)
+            .collect();
+        let mut out: Vec<(f64, T)> = Vec::new();
+        for key in expired {
+            tracing::warn!("No order update for order {} within {}s, releasing its fills", key.1, self.max_hold);
+            self.ack(key);
+            out.extend(self.held.remove(&key).into_iter().flatten());
+        }
+        out.sort_by(|a, b| a.0.total_cmp(&b.0));
+        out.into_iter().map(|(_, fill)| fill).collect()
+    }
+
+    /// Number of fills being held.
+    pub fn held(&self) -> usize {
+        self.held.values().map(Vec::len).sum()
+    }
+
+    fn ack(&mut self, key: OrderKey) {
+        if !self.acked.insert(key) {
+            return;
+        }
+        self.acked_order.push_back(key);
+        if self.acked_order.len() > MAX_ACKED {
+            if let Some(old) = self.acked_order.pop_front() {
+                self.acked.remove(&old);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generated::trd_common::{Order, OrderFill, TrdHeader};
+
+    const ACC: u64 = 281756;
+
+    #[test]
+    fn test_fill_before_ack_is_released_after_it() {
+        let mut seq = TradeSequencer::new(2.0);
+        let fill = Some(TradeEvent::Fill((ACC, 1)));
+        assert!(seq.push(fill, "fill-1a", 0.0).is_empty());
+        assert!(seq.push(fill, "fill-1b", 0.1).is_empty());
+        // Other orders and protocols are not held up
+        assert_eq!(seq.push(None, "ticker", 0.2), vec!["ticker"]);
+        assert_eq!(seq.push(Some(TradeEvent::Order((ACC, 2))), "order-2", 0.2), vec!["order-2"]);
+        assert_eq!(seq.held(), 2);
+
+        let out = seq.push(Some(TradeEvent::Order((ACC, 1))), "order-1", 0.3);
+        assert_eq!(out, vec!["order-1", "fill-1a", "fill-1b"]);
+        // Fills of acknowledged orders pass straight through
+        assert_eq!(seq.push(fill, "fill-1c", 0.4), vec!["fill-1c"]);
+        assert_eq!(seq.held(), 0);
+    }
+
+    #[test]
+    fn test_unacknowledged_fills_expire() {
+        let mut seq = TradeSequencer::new(2.0);
+        seq.push(Some(TradeEvent::Fill((ACC, 1))), "fill-1", 0.0);
+        seq.push(Some(TradeEvent::Fill((ACC, 3))), "fill-3", 1.5);
+        assert!(seq.flush_expired(1.9).is_empty());
+        assert_eq!(seq.flush_expired(2.0), vec!["fill-1"]);
+        assert_eq!(seq.push(Some(TradeEvent::Fill((ACC, 1))), "fill-1b", 2.1), vec!["fill-1b"]);
+        assert_eq!(seq.flush_expired(4.0), vec!["fill-3"]);
+        assert_eq!(seq.held(), 0);
+    }
+
+    #[test]
+    fn test_event_of_push() {
+        let header = TrdHeader { trd_env: 1, acc_id: ACC, trd_market: 1 };
+        let order = crate::generated::trd_update_order::Response {
+            s2c: Some(crate::generated::trd_update_order::S2c {
+                header,
+                order: Order { order_id: 7, ..Default::default() },
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            TradeEvent::of_push(PROTO_TRD_UPDATE_ORDER, &order.encode_to_vec()),
+            Some(TradeEvent::Order((ACC, 7))),
+        );
+        let fill = crate::generated::trd_update_order_fill::Response {
+            s2c: Some(crate::generated::trd_update_order_fill::S2c {
+                header,
+                order_fill: OrderFill { fill_id: 9, order_id: Some(7), ..Default::default() },
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            TradeEvent::of_push(PROTO_TRD_UPDATE_ORDER_FILL, &fill.encode_to_vec()),
+            Some(TradeEvent::Fill((ACC, 7))),
+        );
+        assert_eq!(TradeEvent::of_push(3005, &[]), None);
+    }
+}
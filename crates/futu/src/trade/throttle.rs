@@ -0,0 +1,245 @@
+//! Client-side throttle for order requests.
+//!
+//! New orders and cancel/modify requests draw from separate token buckets per
+//! account, so a burst of entries cannot use up the budget needed to pull
+//! them again. Part of the cancel/modify bucket is reserved for cancels:
+//! modifications stop short of the reserve, cancels may drain it. A bucket
+//! without a limit is not throttled at all.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use parking_lot::Mutex;
+use crate::client::clock::{system_clock, SharedClock};
+use crate::client::rate_limit::Bucket;
+
+// ModifyOrderOp values
+const MODIFY_OP_CANCEL: i32 = 2;
+const MODIFY_OP_DELETE: i32 = 5;
+
+/// Kind of order request, which decides the bucket it draws from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderAction {
+    Place,
+    Modify,
+    /// Risk-reducing: may use the cancel reserve.
+    Cancel,
+}
+
+impl OrderAction {
+    /// Action of a modify_order call. Cancels and deletes count as cancels,
+    /// every other op as a modification.
+    pub fn from_modify_op(op: i32) -> Self {
+        match op {
+            MODIFY_OP_CANCEL | MODIFY_OP_DELETE => OrderAction::Cancel,
+            _ => OrderAction::Modify,
+        }
+    }
+}
+
+/// Budgets of the order throttle. The default throttles nothing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderThrottleConfig {
+    /// place_order requests per window and account (None: unthrottled)
+    pub entry_limit: Option<u32>,
+    /// Cancel and modify requests per window and account (None: unthrottled)
+    pub cancel_limit: Option<u32>,
+    /// Requests of `cancel_limit` that only cancels may use
+    pub cancel_reserve: u32,
+    /// Window the limits refill over
+    pub window: Duration,
+}
+
+impl Default for OrderThrottleConfig {
+    fn default() -> Self {
+        Self {
+            entry_limit: None,
+            cancel_limit: None,
+            cancel_reserve: 0,
+            window: Duration::from_secs(30),
+        }
+    }
+}
+
+impl OrderThrottleConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.window.is_zero() {
+            return Err("order throttle window must be positive".to_string());
+        }
+        if self.entry_limit == Some(0) || self.cancel_limit == Some(0) {
+            return Err("order throttle limits must be positive".to_string());
+        }
+        if let Some(limit) = self.cancel_limit.filter(|l| self.cancel_reserve >= *l) {
+            return Err(format!(
+                "cancel reserve ({}) must be less than the cancel limit ({})",
+                self.cancel_reserve, limit
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct AccountBuckets {
+    entry: Option<Bucket>,
+    cancel: Option<Bucket>,
+}
+
+#[derive(Debug, Default)]
+struct ThrottleState {
+    config: OrderThrottleConfig,
+    accounts: HashMap<u64, AccountBuckets>,
+}
+
+/// Per-account order throttle shared by all trade calls of a client.
+#[derive(Debug)]
+pub struct OrderThrottle {
+    state: Mutex<ThrottleState>,
+    /// Time buckets refill by and delayed requests wait on.
+    clock: SharedClock,
+}
+
+impl Default for OrderThrottle {
+    fn default() -> Self {
+        Self::new(OrderThrottleConfig::default())
+    }
+}
+
+impl OrderThrottle {
+    pub fn new(config: OrderThrottleConfig) -> Self {
+        Self::with_clock(config, system_clock())
+    }
+
+    pub fn with_clock(config: OrderThrottleConfig, clock: SharedClock) -> Self {
+        Self { state: Mutex::new(ThrottleState { config, accounts: HashMap::new() }), clock }
+    }
+
+    pub fn config(&self) -> OrderThrottleConfig {
+        self.state.lock().config.clone()
+    }
+
+    /// Replace the budgets. Buckets start full again.
+    pub fn set_config(&self, config: OrderThrottleConfig) {
+        *self.state.lock() = ThrottleState { config, accounts: HashMap::new() };
+    }
+
+    /// Take a request from the account's budget, or return how long to wait.
+    pub fn try_acquire(&self, acc_id: u64, action: OrderAction, now: Instant) -> Result<(), Duration> {
+        let mut state = self.state.lock();
+        let ThrottleState { config, accounts } = &mut *state;
+        let buckets = accounts.entry(acc_id).or_insert_with(|| AccountBuckets {
+            entry: config.entry_limit.map(|l| Bucket::new(l, now)),
+            cancel: config.cancel_limit.map(|l| Bucket::new(l, now)),
+        });
+        let (bucket, floor) = match action {
+            OrderAction::Place => (&mut buckets.entry, 0.0),
+            OrderAction::Modify => (&mut buckets.cancel, f64::from(config.cancel_reserve)),
+            OrderAction::Cancel => (&mut buckets.cancel, 0.0),
+        };
+        match bucket {
+            Some(bucket) => bucket.take(floor, config.window, now),
+            None => Ok(()),
+        }
+    }
+
+    /// Wait until the account's budget allows the request.
+    pub async fn acquire(&self, acc_id: u64, action: OrderAction) {
+        while let Err(wait) = self.try_acquire(acc_id, action, self.clock.now()) {
+            tracing::debug!("Order throttle: {:?} on account {} delayed by {:?}", action, acc_id, wait);
+            self.clock.sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn throttle(entry: Option<u32>, cancel: Option<u32>, reserve: u32) -> OrderThrottle {
+        OrderThrottle::new(OrderThrottleConfig {
+            entry_limit: entry,
+            cancel_limit: cancel,
+            cancel_reserve: reserve,
+            window: Duration::from_secs(30),
+        })
+    }
+
+    #[test]
+    fn test_entry_and_cancel_budgets_are_independent() {
+        let t = throttle(Some(2), Some(2), 0);
+        let now = Instant::now();
+        assert!(t.try_acquire(1, OrderAction::Place, now).is_ok());
+        assert!(t.try_acquire(1, OrderAction::Place, now).is_ok());
+        // One token refills every 15s
+        let wait = t.try_acquire(1, OrderAction::Place, now).unwrap_err();
+        assert!((wait.as_secs_f64() - 15.0).abs() < 1e-6, "{:?}", wait);
+
+        // Entries used up: cancels still go through, other accounts too
+        assert!(t.try_acquire(1, OrderAction::Cancel, now).is_ok());
+        assert!(t.try_acquire(2, OrderAction::Place, now).is_ok());
+
+        assert!(t.try_acquire(1, OrderAction::Place, now + Duration::from_secs(16)).is_ok());
+    }
+
+    #[test]
+    fn test_modifies_leave_the_cancel_reserve() {
+        let t = throttle(None, Some(3), 2);
+        let now = Instant::now();
+        assert!(t.try_acquire(1, OrderAction::Modify, now).is_ok());
+        assert!(t.try_acquire(1, OrderAction::Modify, now).is_err());
+        assert!(t.try_acquire(1, OrderAction::Cancel, now).is_ok());
+        assert!(t.try_acquire(1, OrderAction::Cancel, now).is_ok());
+        assert!(t.try_acquire(1, OrderAction::Cancel, now).is_err());
+        // No entry limit: never throttled
+        for _ in 0..100 {
+            assert!(t.try_acquire(1, OrderAction::Place, now).is_ok());
+        }
+    }
+
+    #[test]
+    fn test_modify_op_and_config_validation() {
+        assert_eq!(OrderAction::from_modify_op(2), OrderAction::Cancel);
+        assert_eq!(OrderAction::from_modify_op(5), OrderAction::Cancel);
+        assert_eq!(OrderAction::from_modify_op(1), OrderAction::Modify);
+
+        assert!(OrderThrottleConfig::default().validate().is_ok());
+        let config = OrderThrottleConfig { cancel_limit: Some(5), cancel_reserve: 5, ..Default::default() };
+        assert!(config.validate().is_err());
+        let config = OrderThrottleConfig { entry_limit: Some(0), ..Default::default() };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_set_config_refills() {
+        let t = throttle(Some(1), None, 0);
+        let now = Instant::now();
+        assert!(t.try_acquire(1, OrderAction::Place, now).is_ok());
+        assert!(t.try_acquire(1, OrderAction::Place, now).is_err());
+        t.set_config(OrderThrottleConfig::default());
+        assert!(t.try_acquire(1, OrderAction::Place, now).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_acquire_waits_on_the_clock() {
+        use crate::client::clock::MockClock;
+        use std::sync::Arc;
+
+        let clock = Arc::new(MockClock::new(0.0));
+        let config = OrderThrottleConfig { entry_limit: Some(2), ..Default::default() };
+        let t = Arc::new(OrderThrottle::with_clock(config, clock.clone()));
+        t.acquire(1, OrderAction::Place).await;
+        t.acquire(1, OrderAction::Place).await;
+
+        let waiter = tokio::spawn({
+            let t = Arc::clone(&t);
+            async move { t.acquire(1, OrderAction::Place).await }
+        });
+        tokio::task::yield_now().await;
+        assert_eq!(clock.sleepers(), 1);
+        clock.advance(Duration::from_secs(14));
+        tokio::task::yield_now().await;
+        assert!(!waiter.is_finished());
+        // One token refills every 15s
+        clock.advance(Duration::from_secs(1));
+        waiter.await.unwrap();
+    }
+}
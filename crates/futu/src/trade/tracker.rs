@@ -0,0 +1,445 @@
+//! Order lifecycle events synthesized from trade pushes.
+//!
+//! Trd_UpdateOrder reports an order's OpenD status and Trd_UpdateOrderFill
+//! its fills, as separate pushes that may arrive in either order or more
+//! than once. `OrderTracker` keeps a state machine per order that folds both
+//! into one lifecycle (pending submit → submitted → partially filled →
+//! filled, cancelled or rejected) and reports every change of state, and
+//! every new fill, as an `OrderEvent`. Pushes that would move an order
+//! backwards, such as a late "submitted" after a fill or anything after a
+//! final state, are ignored.
+//!
+//! `OrderEventStream` runs a tracker over the pushes of all accounts the
+//! connection receives trade pushes for (see `trade::push::sub_acc_push`).
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+
+use crate::client::FutuClient;
+use crate::generated::trd_common::{Order, OrderFill, OrderStatus};
+use crate::protocol::FutuMessage;
+use super::sequence::{OrderKey, PROTO_TRD_UPDATE_ORDER, PROTO_TRD_UPDATE_ORDER_FILL};
+
+/// Finished orders remembered; the oldest are forgotten beyond this.
+const MAX_FINISHED: usize = 10_000;
+
+/// Fill quantities within this of the order quantity complete it.
+const QTY_EPSILON: f64 = 1e-9;
+
+/// Lifecycle state of an order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OrderState {
+    /// Not yet accepted: unsubmitted, waiting to submit or submitting.
+    PendingSubmit,
+    /// Accepted and working, nothing filled.
+    Submitted,
+    /// Working with part of the quantity filled.
+    PartiallyFilled,
+    /// Cancellation requested and not yet confirmed.
+    PendingCancel,
+    /// Disabled; may be enabled again.
+    Disabled,
+    /// The whole quantity filled.
+    Filled,
+    /// Cancelled, possibly after partial fills.
+    Cancelled,
+    /// Refused by OpenD or the exchange, or its fills were reversed.
+    Rejected,
+    /// Deleted without fills.
+    Deleted,
+    /// Status OpenD reports as unknown or timed out.
+    Unknown,
+}
+
+impl OrderState {
+    /// State of a Trd_Common.OrderStatus value.
+    pub fn from_order_status(status: i32) -> Self {
+        match OrderStatus::try_from(status) {
+            Ok(OrderStatus::Unsubmitted | OrderStatus::WaitingSubmit | OrderStatus::Submitting) => OrderState::PendingSubmit,
+            Ok(OrderStatus::Submitted) => OrderState::Submitted,
+            Ok(OrderStatus::FilledPart) => OrderState::PartiallyFilled,
+            Ok(OrderStatus::FilledAll) => OrderState::Filled,
+            Ok(OrderStatus::CancellingPart | OrderStatus::CancellingAll) => OrderState::PendingCancel,
+            Ok(OrderStatus::CancelledPart | OrderStatus::CancelledAll) => OrderState::Cancelled,
+            Ok(OrderStatus::SubmitFailed | OrderStatus::Failed | OrderStatus::FillCancelled) => OrderState::Rejected,
+            Ok(OrderStatus::Disabled) => OrderState::Disabled,
+            Ok(OrderStatus::Deleted) => OrderState::Deleted,
+            Ok(OrderStatus::Unknown | OrderStatus::TimeOut) | Err(_) => OrderState::Unknown,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderState::PendingSubmit => "pending_submit",
+            OrderState::Submitted => "submitted",
+            OrderState::PartiallyFilled => "partially_filled",
+            OrderState::PendingCancel => "pending_cancel",
+            OrderState::Disabled => "disabled",
+            OrderState::Filled => "filled",
+            OrderState::Cancelled => "cancelled",
+            OrderState::Rejected => "rejected",
+            OrderState::Deleted => "deleted",
+            OrderState::Unknown => "unknown",
+        }
+    }
+
+    /// Whether the order can no longer change.
+    pub fn is_final(&self) -> bool {
+        matches!(self, OrderState::Filled | OrderState::Cancelled | OrderState::Rejected | OrderState::Deleted)
+    }
+}
+
+/// A change of an order's lifecycle.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderEvent {
+    pub acc_id: u64,
+    pub order_id: u64,
+    pub code: String,
+    /// State before the event; None for the first event of an order.
+    pub prev_state: Option<OrderState>,
+    pub state: OrderState,
+    /// Order quantity; 0 until an order push reported it.
+    pub qty: f64,
+    /// Quantity filled so far.
+    pub filled_qty: f64,
+    /// Average fill price reported by the last order push, if any.
+    pub fill_avg_price: Option<f64>,
+    /// The fill that caused the event, for events from fill pushes.
+    pub fill: Option<OrderFill>,
+    /// Unix timestamp (seconds) when the push was processed.
+    pub timestamp: f64,
+}
+
+#[derive(Debug)]
+struct TrackedOrder {
+    code: String,
+    state: Option<OrderState>,
+    qty: f64,
+    /// Fill quantity reported by the last order push.
+    reported_fill_qty: f64,
+    /// Sum of the fill pushes seen; the same fills as `reported_fill_qty`,
+    /// so the two are compared rather than added.
+    fills_qty: f64,
+    fill_avg_price: Option<f64>,
+    fill_ids: HashSet<u64>,
+}
+
+impl TrackedOrder {
+    fn filled_qty(&self) -> f64 {
+        self.reported_fill_qty.max(self.fills_qty)
+    }
+}
+
+/// Per-order lifecycle state machines; see the module docs.
+#[derive(Debug, Default)]
+pub struct OrderTracker {
+    orders: HashMap<OrderKey, TrackedOrder>,
+    /// Orders in a final state, oldest first.
+    finished: VecDeque<OrderKey>,
+}
+
+impl OrderTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Current state of an order, or None if it was never pushed.
+    pub fn state(&self, acc_id: u64, order_id: u64) -> Option<OrderState> {
+        self.orders.get(&(acc_id, order_id)).and_then(|o| o.state)
+    }
+
+    /// Orders tracked, including remembered finished ones.
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    /// Apply an order update push of account `acc_id`.
+    pub fn on_order(&mut self, acc_id: u64, order: &Order, now: f64) -> Option<OrderEvent> {
+        let key = (acc_id, order.order_id);
+        let tracked = self.entry(key, &order.code);
+        let prev_state = tracked.state;
+        if prev_state.is_some_and(|s| s.is_final()) {
+            return None;
+        }
+        tracked.qty = order.qty;
+        tracked.reported_fill_qty = tracked.reported_fill_qty.max(order.fill_qty.unwrap_or_default());
+        tracked.fill_avg_price = order.fill_avg_price.or(tracked.fill_avg_price);
+
+        let mut state = OrderState::from_order_status(order.order_status);
+        if tracked.filled_qty() > 0.0 && matches!(state, OrderState::PendingSubmit | OrderState::Submitted) {
+            // A late update from before the first fill
+            state = OrderState::PartiallyFilled;
+        }
+        if state == OrderState::PendingSubmit && prev_state.is_some_and(|s| s != OrderState::PendingSubmit) {
+            return None;
+        }
+        if prev_state == Some(state) {
+            return None;
+        }
+        tracked.state = Some(state);
+        let event = event(key, tracked, prev_state, None, now);
+        self.finish(key, state);
+        Some(event)
+    }
+
+    /// Apply a fill push of account `acc_id`. Fills without an order id and
+    /// fills already seen are ignored.
+    pub fn on_fill(&mut self, acc_id: u64, fill: &OrderFill, now: f64) -> Option<OrderEvent> {
+        let key = (acc_id, fill.order_id?);
+        let tracked = self.entry(key, &fill.code);
+        if !tracked.fill_ids.insert(fill.fill_id) {
+            return None;
+        }
+        tracked.fills_qty += fill.qty;
+        let prev_state = tracked.state;
+        let state = match prev_state {
+            Some(state) if state.is_final() || state == OrderState::PendingCancel => state,
+            _ if tracked.qty > 0.0 && tracked.filled_qty() + QTY_EPSILON >= tracked.qty => OrderState::Filled,
+            _ => OrderState::PartiallyFilled,
+        };
+        tracked.state = Some(state);
+        let event = event(key, tracked, prev_state, Some(fill.clone()), now);
+        if prev_state != Some(state) {
+            self.finish(key, state);
+        }
+        Some(event)
+    }
+
+    fn entry(&mut self, key: OrderKey, code: &str) -> &mut TrackedOrder {
+        self.orders.entry(key).or_insert_with(|| TrackedOrder {
+            code: code.to_string(),
+            state: None,
+            qty: 0.0,
+            reported_fill_qty: 0.0,
+            fills_qty: 0.0,
+            fill_avg_price: None,
+            fill_ids: HashSet::new(),
+        })
+    }
+
+    /// Remember an order that reached a final state, forgetting the oldest
+    /// finished orders beyond `MAX_FINISHED`.
+    fn finish(&mut self, key: OrderKey, state: OrderState) {
+        if !state.is_final() {
+            return;
+        }
+        self.finished.push_back(key);
+        if self.finished.len() > MAX_FINISHED {
+            if let Some(old) = self.finished.pop_front() {
+                self.orders.remove(&old);
+            }
+        }
+    }
+}
+
+fn event(key: OrderKey, tracked: &TrackedOrder, prev_state: Option<OrderState>, fill: Option<OrderFill>, now: f64) -> OrderEvent {
+    OrderEvent {
+        acc_id: key.0,
+        order_id: key.1,
+        code: tracked.code.clone(),
+        prev_state,
+        state: tracked.state.unwrap_or(OrderState::Unknown),
+        qty: tracked.qty,
+        filled_qty: tracked.filled_qty(),
+        fill_avg_price: tracked.fill_avg_price,
+        fill,
+        timestamp: now,
+    }
+}
+
+/// Lifecycle events of every pushed order. Dropping the stream stops it.
+pub struct OrderEventStream {
+    rx: mpsc::UnboundedReceiver<OrderEvent>,
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl OrderEventStream {
+    /// Start tracking. Pushes received once this returns are not missed.
+    pub async fn start(client: Arc<FutuClient>) -> Self {
+        let orders = client.subscribe_push(PROTO_TRD_UPDATE_ORDER).await;
+        let fills = client.subscribe_push(PROTO_TRD_UPDATE_ORDER_FILL).await;
+        let (tx, rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run_order_tracker(client, orders, fills, tx));
+        Self { rx, task }
+    }
+
+    /// The next event, or None once the stream has stopped.
+    pub async fn recv(&mut self) -> Option<OrderEvent> {
+        self.rx.recv().await
+    }
+}
+
+impl futures::Stream for OrderEventStream {
+    type Item = OrderEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<OrderEvent>> {
+        self.rx.poll_recv(cx)
+    }
+}
+
+impl Drop for OrderEventStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Feed a trade push to `tracker`.
+fn apply_push(client: &FutuClient, tracker: &mut OrderTracker, msg: &FutuMessage, now: f64) -> Option<OrderEvent> {
+    match msg.proto_id {
+        PROTO_TRD_UPDATE_ORDER => {
+            let s2c = client.decode::<crate::generated::trd_update_order::Response>(msg).ok()?.s2c?;
+            tracker.on_order(s2c.header.acc_id, &s2c.order, now)
+        }
+        PROTO_TRD_UPDATE_ORDER_FILL => {
+            let s2c = client.decode::<crate::generated::trd_update_order_fill::Response>(msg).ok()?.s2c?;
+            tracker.on_fill(s2c.header.acc_id, &s2c.order_fill, now)
+        }
+        _ => None,
+    }
+}
+
+async fn run_order_tracker(
+    client: Arc<FutuClient>,
+    mut orders: mpsc::UnboundedReceiver<FutuMessage>,
+    mut fills: mpsc::UnboundedReceiver<FutuMessage>,
+    tx: mpsc::UnboundedSender<OrderEvent>,
+) {
+    let mut tracker = OrderTracker::new();
+    let (mut orders_open, mut fills_open) = (true, true);
+    while orders_open || fills_open {
+        let msg = tokio::select! {
+            msg = orders.recv(), if orders_open => {
+                orders_open = msg.is_some();
+                msg
+            }
+            msg = fills.recv(), if fills_open => {
+                fills_open = msg.is_some();
+                msg
+            }
+            _ = tx.closed() => break,
+        };
+        let Some(msg) = msg else { continue };
+        if let Some(event) = apply_push(&client, &mut tracker, &msg, client.clock().unix_time()) {
+            if tx.send(event).is_err() {
+                break;
+            }
+        }
+    }
+    tracing::debug!("Order tracker stopped");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+    use crate::client::mock::MockOpenD;
+    use crate::config::FutuConfig;
+    use crate::generated::trd_common::TrdHeader;
+
+    const ACC: u64 = 281756;
+
+    fn order(order_id: u64, status: OrderStatus, qty: f64, fill_qty: f64) -> Order {
+        Order {
+            order_id,
+            order_status: status as i32,
+            code: "00700".to_string(),
+            qty,
+            fill_qty: Some(fill_qty),
+            ..Default::default()
+        }
+    }
+
+    fn fill(fill_id: u64, order_id: u64, qty: f64) -> OrderFill {
+        OrderFill { fill_id, order_id: Some(order_id), code: "00700".to_string(), qty, price: 350.0, ..Default::default() }
+    }
+
+    fn transition(event: Option<OrderEvent>) -> Option<(Option<OrderState>, OrderState)> {
+        event.map(|e| (e.prev_state, e.state))
+    }
+
+    #[test]
+    fn test_submit_fill_lifecycle() {
+        use OrderState::*;
+        let mut t = OrderTracker::new();
+        assert_eq!(transition(t.on_order(ACC, &order(1, OrderStatus::Submitting, 200.0, 0.0), 1.0)), Some((None, PendingSubmit)));
+        assert_eq!(transition(t.on_order(ACC, &order(1, OrderStatus::Submitted, 200.0, 0.0), 2.0)), Some((Some(PendingSubmit), Submitted)));
+        // Repeated status: no event
+        assert!(t.on_order(ACC, &order(1, OrderStatus::Submitted, 200.0, 0.0), 2.5).is_none());
+
+        let event = t.on_fill(ACC, &fill(10, 1, 100.0), 3.0).unwrap();
+        assert_eq!((event.prev_state, event.state, event.filled_qty), (Some(Submitted), PartiallyFilled, 100.0));
+        assert_eq!(event.fill.unwrap().fill_id, 10);
+        // The order push of the same fill changes nothing; a repeated fill is ignored
+        assert!(t.on_order(ACC, &order(1, OrderStatus::FilledPart, 200.0, 100.0), 3.1).is_none());
+        assert!(t.on_fill(ACC, &fill(10, 1, 100.0), 3.2).is_none());
+
+        let event = t.on_fill(ACC, &fill(11, 1, 100.0), 4.0).unwrap();
+        assert_eq!((event.prev_state, event.state, event.filled_qty), (Some(PartiallyFilled), Filled, 200.0));
+        // Nothing moves a final order
+        assert!(t.on_order(ACC, &order(1, OrderStatus::FilledAll, 200.0, 200.0), 4.1).is_none());
+        assert!(t.on_order(ACC, &order(1, OrderStatus::Submitted, 200.0, 0.0), 4.2).is_none());
+        assert_eq!(t.state(ACC, 1), Some(Filled));
+    }
+
+    #[test]
+    fn test_out_of_order_and_cancel() {
+        use OrderState::*;
+        let mut t = OrderTracker::new();
+        // A fill before any order update
+        assert_eq!(transition(t.on_fill(ACC, &fill(20, 2, 50.0), 1.0)), Some((None, PartiallyFilled)));
+        // A stale "submitted" after the fill keeps it partially filled
+        assert!(t.on_order(ACC, &order(2, OrderStatus::Submitted, 200.0, 0.0), 1.5).is_none());
+        assert!(t.on_order(ACC, &order(2, OrderStatus::WaitingSubmit, 200.0, 0.0), 1.6).is_none());
+
+        assert_eq!(transition(t.on_order(ACC, &order(2, OrderStatus::CancellingPart, 200.0, 50.0), 2.0)), Some((Some(PartiallyFilled), PendingCancel)));
+        // A fill racing the cancel is reported without leaving pending cancel
+        assert_eq!(transition(t.on_fill(ACC, &fill(21, 2, 50.0), 2.5)), Some((Some(PendingCancel), PendingCancel)));
+        let event = t.on_order(ACC, &order(2, OrderStatus::CancelledPart, 200.0, 100.0), 3.0).unwrap();
+        assert_eq!((event.state, event.filled_qty), (Cancelled, 100.0));
+
+        assert_eq!(transition(t.on_order(ACC, &order(3, OrderStatus::Failed, 100.0, 0.0), 4.0)), Some((None, Rejected)));
+        // Orders of different accounts are separate
+        assert_eq!(transition(t.on_order(1, &order(3, OrderStatus::Submitted, 100.0, 0.0), 4.0)), Some((None, Submitted)));
+        assert_eq!(t.len(), 3);
+        assert_eq!(OrderState::from_order_status(99), Unknown);
+    }
+
+    #[tokio::test]
+    async fn test_stream_against_mock() {
+        let mock = MockOpenD::start().await.unwrap();
+        let config = FutuConfig { port: mock.port(), enable_encryption: false, ..FutuConfig::default() };
+        let mut client = FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+        let client = Arc::new(client);
+        let mut stream = client.order_events().await;
+
+        let header = TrdHeader { trd_env: 1, acc_id: ACC, trd_market: 1 };
+        let push = crate::generated::trd_update_order::Response {
+            ret_type: 0,
+            s2c: Some(crate::generated::trd_update_order::S2c {
+                header,
+                order: order(5, OrderStatus::Submitted, 100.0, 0.0),
+            }),
+            ..Default::default()
+        };
+        mock.push(PROTO_TRD_UPDATE_ORDER, push.encode_to_vec());
+        let first = stream.recv().await.unwrap();
+        assert_eq!((first.acc_id, first.order_id, first.state), (ACC, 5, OrderState::Submitted));
+
+        let fill_push = crate::generated::trd_update_order_fill::Response {
+            ret_type: 0,
+            s2c: Some(crate::generated::trd_update_order_fill::S2c { header, order_fill: fill(50, 5, 100.0) }),
+            ..Default::default()
+        };
+        mock.push(PROTO_TRD_UPDATE_ORDER_FILL, fill_push.encode_to_vec());
+        let second = stream.recv().await.unwrap();
+        assert_eq!((second.prev_state, second.state), (Some(OrderState::Submitted), OrderState::Filled));
+    }
+}
@@ -0,0 +1,74 @@
+//! Typed enums for the integer codes of the Futu API.
+//!
+//! The protos carry markets, sub types, sides and the like as `i32`. The
+//! enums generated for them are re-exported here under one path. Each
+//! converts into its `i32` with `From` and back with `TryFrom<i32>`, which
+//! fails with `prost::UnknownEnumValue` for codes the protos do not define.
+//!
+//! The quote and trade wrappers take `impl Into<i32>` for these codes, so
+//! they accept the enums as well as raw integers:
+//!
+//! ```ignore
+//! use nautilus_futu::types::{TrdEnv, TrdMarket, TrdSide, OrderType};
+//! place_order(&client, TrdEnv::Simulate, acc_id, TrdMarket::Hk, TrdSide::Buy, OrderType::Normal, ...)
+//! ```
+
+pub use crate::generated::qot_common::{
+    KlType, QotMarket, QotMarketState, RehabType, SecurityType, SubType, TradeDateMarket,
+};
+#[cfg(any(feature = "quote", feature = "trade"))]
+pub use crate::generated::trd_common::{
+    Currency, ModifyOrderOp, OrderStatus, OrderType, TimeInForce, TrailType, TrdEnv, TrdMarket,
+    TrdSecMarket, TrdSide,
+};
+
+/// A (market, code) pair, as taken by the securities lists of the quote
+/// wrappers.
+pub fn security(market: impl Into<i32>, code: impl Into<String>) -> (i32, String) {
+    (market.into(), code.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(any(feature = "quote", feature = "trade"))]
+    #[test]
+    fn test_conversions_round_trip() {
+        assert_eq!(i32::from(QotMarket::UsSecurity), 11);
+        assert_eq!(QotMarket::try_from(21), Ok(QotMarket::CnshSecurity));
+        assert_eq!(SubType::try_from(14), Ok(SubType::Broker));
+        assert_eq!(i32::from(TrdSide::SellShort), 3);
+        assert_eq!(OrderStatus::try_from(-1), Ok(OrderStatus::Unknown));
+        assert_eq!(TrdEnv::try_from(1), Ok(TrdEnv::Real));
+        assert!(TrdMarket::try_from(7).is_err());
+    }
+
+    #[test]
+    fn test_security_pair() {
+        assert_eq!(security(QotMarket::HkSecurity, "00700"), (1, "00700".to_string()));
+        assert_eq!(security(11, "AAPL".to_string()), (11, "AAPL".to_string()));
+    }
+
+    #[cfg(feature = "quote")]
+    #[tokio::test]
+    async fn test_wrappers_accept_enums_and_integers() {
+        use crate::client::mock::MockOpenD;
+        use crate::config::FutuConfig;
+        use crate::quote::subscribe::subscribe;
+
+        let mock = MockOpenD::start().await.unwrap();
+        let config = FutuConfig { port: mock.port(), enable_encryption: false, ..FutuConfig::default() };
+        let mut client = crate::client::FutuClient::connect(config).await.unwrap();
+        client.init().await.unwrap();
+
+        let hk = security(QotMarket::HkSecurity, "00700");
+        subscribe(&client, vec![hk.clone()], [SubType::Basic, SubType::Ticker], true).await.unwrap();
+        subscribe(&client, vec![hk.clone()], vec![2], true).await.unwrap();
+        assert!(client.subscriptions().is_subscribed(&hk, SubType::Ticker.into()));
+        assert!(client.subscriptions().is_subscribed(&hk, 2));
+
+        crate::quote::snapshot::get_broker(&client, QotMarket::HkSecurity, "00700".to_string()).await.unwrap();
+        crate::quote::snapshot::get_broker(&client, 1, "00700".to_string()).await.unwrap();
+    }
+}
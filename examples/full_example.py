@@ -150,8 +150,7 @@ def demo_rust_client():
     print("\n=== 盘口 ===")
     client.subscribe([(1, "00700")], [2], True)
     order_book = client.get_order_book(
-        market=1,
-        code="00700",
+        security=(1, "00700"),  # (市场, 代码) 或已注册的别名
         num=5,  # 档位数量，默认 10
     )
     print("  卖盘:")
@@ -171,8 +170,7 @@ def demo_rust_client():
     print("\n=== 逐笔成交 ===")
     client.subscribe([(1, "00700")], [4], True)
     tickers = client.get_ticker(
-        market=1,
-        code="00700",
+        security=(1, "00700"),
         max_ret_num=5,  # 返回条数，默认 100
     )
     for t in tickers[:3]:
@@ -186,8 +184,7 @@ def demo_rust_client():
     # 获取指定时间段的历史K线数据
     print("\n=== 历史K线 ===")
     klines = client.get_history_kl(
-        market=1,
-        code="00700",
+        security=(1, "00700"),
         rehab_type=1,    # 复权类型: 0=不复权, 1=前复权, 2=后复权
         kl_type=2,       # K线类型: 1=1分钟, 2=日线, 3=周线, 4=月线,
                          #          6=5分钟, 7=15分钟, 8=30分钟, 9=60分钟
@@ -344,7 +341,7 @@ def demo_rust_client():
         trd_market=1,       # 1=港股
         trd_side=1,         # 1=买入, 2=卖出
         order_type=1,       # 1=普通限价单, 2=市价单, 5=竞价限价单, 6=竞价市价单
-        code="00700",       # 证券代码
+        security="00700",   # 证券代码，或 (市场, 代码) / 别名（此时无需 sec_market）
         qty=100.0,          # 数量
         price=300.0,        # 价格（市价单可不传）
         sec_market=1,       # TrdSecMarket: 1=港股, 2=美股, 31=沪股, 32=深股, 41=新加坡
@@ -61,8 +61,7 @@ async def main():
 
         # Get historical daily K-lines
         klines = client.get_history_kl(
-            market=1,
-            code="00700",
+            security=(1, "00700"),
             rehab_type=1,  # Forward adjustment (前复权)
             kl_type=2,  # Day
             begin_time="2024-01-01",
@@ -12,6 +12,8 @@ def __getattr__(name):
         "FutuLiveDataClientFactory": "nautilus_futu.factories",
         "FutuLiveExecClientFactory": "nautilus_futu.factories",
         "FutuInstrumentProvider": "nautilus_futu.providers",
+        "init_logging": "nautilus_futu._rust",
+        "set_log_filter": "nautilus_futu._rust",
     }
     if name in _imports:
         import importlib
@@ -29,4 +31,6 @@ __all__ = [
     "FutuLiveDataClientFactory",
     "FutuLiveExecClientFactory",
     "FutuLiveExecutionClient",
+    "init_logging",
+    "set_log_filter",
 ]
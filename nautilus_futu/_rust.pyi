@@ -0,0 +1,1266 @@
+"""Type stubs of the `nautilus_futu._rust` extension module.
+
+Kept in step with the `#[pymethods]` of `crates/futu/src/python`; see
+`tests/python/test_stubs.py`.
+"""
+
+import os
+from typing import Any, Literal, overload
+
+Security = tuple[int, str] | str
+"""A (market, code) pair or a symbol alias registered with `set_symbol_aliases()`."""
+
+class FutuError(RuntimeError):
+    """Base class of the errors of failed client calls."""
+
+class FutuConnectionError(FutuError):
+    """The connection to OpenD failed or was closed."""
+
+class FutuServerError(FutuError):
+    """OpenD answered with a failure. Attributes: ret_type, err_code (None if OpenD gave none)."""
+
+class FutuDecodeError(FutuError):
+    """A response or push could not be decrypted or decoded."""
+
+class FutuTimeoutError(FutuError):
+    """Connecting to or talking with OpenD timed out."""
+
+class SecurityFirmMismatchError(ValueError):
+    """Explicit security_firm does not match the account's security firm."""
+
+class SessionPreemptedError(RuntimeError):
+    """Trading is paused because another login of the account preempted the session."""
+
+class SubscriptionRejectedError(RuntimeError):
+    """OpenD rejected some securities of a subscribe call; the others were applied. args: (message, results)."""
+
+class ThrottledError(RuntimeError):
+    """OpenD was busy or over a request frequency limit. args: (message, retry_after_secs)."""
+
+class BasicQot:
+    """A row of `get_basic_qot(typed=True)`."""
+
+    @property
+    def market(self) -> int: ...
+    @property
+    def code(self) -> str: ...
+    @property
+    def alias(self) -> str | None: ...
+    @property
+    def name(self) -> str | None: ...
+    @property
+    def cur_price(self) -> float: ...
+    @property
+    def price_spread(self) -> float: ...
+    @property
+    def open_price(self) -> float: ...
+    @property
+    def high_price(self) -> float: ...
+    @property
+    def low_price(self) -> float: ...
+    @property
+    def last_close_price(self) -> float: ...
+    @property
+    def volume(self) -> int: ...
+    @property
+    def turnover(self) -> float: ...
+    @property
+    def turnover_rate(self) -> float: ...
+    @property
+    def update_timestamp(self) -> float | None: ...
+    def to_dict(self) -> dict[str, Any]:
+        """The fields as a dict, in the layout of the untyped result."""
+
+class Kline:
+    """A K-line of `get_history_kl(typed=True)`."""
+
+    @property
+    def time(self) -> str: ...
+    @property
+    def is_blank(self) -> bool: ...
+    @property
+    def open_price(self) -> float | None: ...
+    @property
+    def high_price(self) -> float | None: ...
+    @property
+    def low_price(self) -> float | None: ...
+    @property
+    def close_price(self) -> float | None: ...
+    @property
+    def last_close_price(self) -> float | None: ...
+    @property
+    def volume(self) -> int | None: ...
+    @property
+    def turnover(self) -> float | None: ...
+    @property
+    def timestamp(self) -> float | None: ...
+    def to_dict(self) -> dict[str, Any]:
+        """The fields as a dict, in the layout of the untyped result."""
+
+class Order:
+    """An order of `get_order_list(typed=True)`."""
+
+    @property
+    def trd_side(self) -> int: ...
+    @property
+    def order_type(self) -> int: ...
+    @property
+    def order_status(self) -> int: ...
+    @property
+    def order_id(self) -> int: ...
+    @property
+    def order_id_ex(self) -> str: ...
+    @property
+    def code(self) -> str: ...
+    @property
+    def alias(self) -> str | None: ...
+    @property
+    def name(self) -> str: ...
+    @property
+    def qty(self) -> float: ...
+    @property
+    def price(self) -> float | None: ...
+    @property
+    def create_time(self) -> str: ...
+    @property
+    def update_time(self) -> str: ...
+    @property
+    def fill_qty(self) -> float | None: ...
+    @property
+    def fill_avg_price(self) -> float | None: ...
+    @property
+    def sec_market(self) -> int | None: ...
+    @property
+    def create_timestamp(self) -> float | None: ...
+    @property
+    def update_timestamp(self) -> float | None: ...
+    @property
+    def time_in_force(self) -> int | None: ...
+    @property
+    def remark(self) -> str | None: ...
+    @property
+    def last_err_msg(self) -> str | None: ...
+    def to_dict(self) -> dict[str, Any]:
+        """The fields as a dict, in the layout of the untyped result."""
+
+class Position:
+    """A position of `get_position_list(typed=True)`."""
+
+    @property
+    def position_id(self) -> int: ...
+    @property
+    def position_side(self) -> int: ...
+    @property
+    def code(self) -> str: ...
+    @property
+    def alias(self) -> str | None: ...
+    @property
+    def name(self) -> str: ...
+    @property
+    def qty(self) -> float: ...
+    @property
+    def can_sell_qty(self) -> float: ...
+    @property
+    def price(self) -> float: ...
+    @property
+    def cost_price(self) -> float | None: ...
+    @property
+    def val(self) -> float: ...
+    @property
+    def pl_val(self) -> float: ...
+    @property
+    def pl_ratio(self) -> float | None: ...
+    @property
+    def sec_market(self) -> int | None: ...
+    @property
+    def unrealized_pl(self) -> float | None: ...
+    @property
+    def realized_pl(self) -> float | None: ...
+    @property
+    def currency(self) -> int | None: ...
+    def to_dict(self) -> dict[str, Any]:
+        """The fields as a dict, in the layout of the untyped result."""
+
+def init_logging(level: str = "info", to_python: bool = True, file: str | os.PathLike[str] | None = None, filters: dict[str, str] | None = None) -> bool:
+    """Install the subscriber that makes the crate's Rust logs visible.
+    level: default level ("error", "warn", "info", "debug" or "trace")
+    to_python: hand events to Python `logging`, to the logger named after
+    the module, e.g. "nautilus_futu.client.keepalive"
+    file: also append events as text lines to this file
+    filters: per-module levels, e.g. {"nautilus_futu.client": "debug"}
+    Returns True if installed. If `init_logging()` ran before, only the
+    filter is replaced and False is returned. Raises RuntimeError if
+    another tracing subscriber was installed in the process.
+    """
+
+def set_log_filter(level: str, filters: dict[str, str] | None = None) -> None:
+    """Replace the level filter of `init_logging()`, e.g. to turn on debug logs
+    of one module while running. Takes the same level and filters.
+    """
+
+class PyFutuClient:
+    def __init__(self) -> None: ...
+    def connect(self, host: str, port: int, client_id: str, client_ver: int, fallback_endpoints: list[tuple[str, int]] | None = None, trace_dump_path: str | os.PathLike[str] | None = None, decode_mode: str | None = None, mock: bool = False, gateway_log_dir: str | os.PathLike[str] | None = None, rsa_key_path: str | os.PathLike[str] | None = None, aes_padding: str | None = None) -> None:
+        """Connect to Futu OpenD gateway.
+        fallback_endpoints: optional list of (host, port) tried in order if the
+        primary endpoint is unreachable. IPv6 hosts may be bare or bracketed.
+        trace_dump_path: optional file that message trace dumps are appended to.
+        decode_mode: "lenient" (default) decodes messages lacking required
+        fields with defaults and logs a warning; "strict" rejects them.
+        mock: connect to an in-process mock OpenD instead of host and port,
+        also enabled by the environment variable FUTU_MOCK=1. Requests succeed
+        without data unless `mock_response()` registered one; `mock_push()`
+        sends pushes.
+        gateway_log_dir: OpenD's log directory; rejected requests are then
+        reported by `gateway_rejections()` with the gateway's log lines.
+        rsa_key_path: PEM private key configured in OpenD; the handshake is
+        then RSA-encrypted and later messages AES-encrypted.
+        aes_padding: "compatible" (default) decrypts either padding OpenD
+        uses, "futu" and "pkcs7" only FTAES_ECB or standard AES_ECB bodies.
+        """
+    def mock_response(self, proto_id: int, body: bytes) -> None:
+        """Answer requests of `proto_id` with `body`, an encoded Response
+        message, on the mock OpenD of `connect(mock=True)`.
+        """
+    def mock_push(self, proto_id: int, body: bytes) -> int:
+        """Send a push of `proto_id` with `body`, an encoded Response message,
+        from the mock OpenD of `connect(mock=True)`. Returns the number of
+        connections it reached.
+        """
+    def disconnect(self, grace_period: float = 5.0) -> None:
+        """Disconnect from Futu OpenD. Requests in flight, e.g. order
+        placements, are given up to grace_period seconds to be answered
+        before the connection is closed; new ones fail at once. Quote
+        subscriptions are dropped at OpenD.
+        """
+    def subscribe(self, securities: list[Security], sub_types: list[int], is_sub: bool, raise_on_reject: bool = True, order_book_detail: bool = False, ttl_secs: float | None = None, reg_push: bool = True, first_push: bool | None = None, push_rehab_types: list[int] | None = None, extended_time: bool | None = None, session: int | None = None, unsub_all: bool = False) -> list[Any]:
+        """Subscribe to quote data.
+        securities: list of (market, code) tuples or symbol aliases
+        sub_types: list of SubType integers
+        is_sub: True to subscribe, False to unsubscribe
+        Securities OpenD rejects are isolated and the others still applied.
+        Returns a list of dicts with market, code, alias, ok and error (OpenD's
+        reason, None if ok), in input order. If any security was rejected,
+        raises SubscriptionRejectedError with args (message, results) unless
+        raise_on_reject is False.
+        order_book_detail: subscribe order books with per-order detail, SF
+        quotes only. Qot_Sub has no depth parameter: order book pushes carry
+        as many levels as the quote right of the market allows, reported as
+        "depth" in each push, and lack detail without the SF right.
+        ttl_secs: unsubscribe the subscribed pairs automatically after this
+        many seconds unless renewed with `renew_subscriptions()`; expiries
+        are reported by `poll_subscription_expiry()`. Subscribing a pair
+        again without ttl_secs keeps it subscribed.
+        reg_push: register push for the pairs (default); False subscribes
+        without pushes, e.g. for polling with get_basic_qot()
+        first_push: push the data OpenD already holds once after
+        registering (None = OpenD default, True)
+        push_rehab_types: RehabType values of K-line pushes (None = OpenD
+        default, forward)
+        extended_time: include US pre-market and after-hours data
+        (superseded by session)
+        session: Session of US quotes, e.g. 3 for all sessions
+        unsub_all: with is_sub False, drop every subscription of the
+        connection in one request; securities and sub_types are ignored
+        except for shaping the results
+        Only order_book_detail is kept when subscriptions are replayed after
+        a reconnect.
+        """
+    def unsubscribe_all(self) -> int:
+        """Drop every quote subscription of the connection in one request, also
+        ones made outside `subscribe()`, and clear the local record of them,
+        including TTLs and push statistics. Returns the number of (security,
+        sub type) pairs that were recorded. OpenD frees the quota of a
+        subscription no sooner than a minute after it was made.
+        """
+    def reg_qot_push(self, securities: list[Security], sub_types: list[int], is_reg: bool = True, rehab_type: int | None = None, is_first_push: bool | None = None) -> None:
+        """Register or unregister push for subscribed securities without changing
+        the subscriptions.
+        securities: list of (market, code) tuples or symbol aliases
+        sub_types: list of SubType integers
+        rehab_type: RehabType for K-line pushes (None = OpenD default, forward)
+        is_first_push: push data already held by OpenD once after registering
+        (None = OpenD default, True)
+        """
+    def define_profile(self, name: str, securities: list[Security], sub_types: list[int]) -> None:
+        """Define (or replace) a named subscription profile.
+        securities: list of (market, code) tuples or symbol aliases
+        sub_types: list of SubType integers
+        """
+    def remove_profile(self, name: str) -> bool:
+        """Remove a profile definition.
+        Returns False if the profile is unknown or still active.
+        """
+    def activate_profile(self, name: str) -> None:
+        """Subscribe all securities/sub types of a profile and mark it active.
+        Active profiles are re-subscribed automatically on (re)connect.
+        """
+    def deactivate_profile(self, name: str) -> None:
+        """Unsubscribe a profile's subscriptions that no other active profile
+        still needs, and mark it inactive.
+        """
+    def list_profiles(self) -> list[Any]:
+        """List subscription profiles.
+        Returns list of dicts with name, securities, sub_types and active.
+        """
+    def get_static_info(self, securities: list[Security]) -> list[Any]:
+        """Get static info for securities.
+        securities: list of (market, code) tuples or symbol aliases
+        Returns list of dicts with static info.
+        """
+    def get_order_book(self, security: Security, num: int = 10, include_detail: bool = False) -> Any:
+        """Get order book for a single security.
+        Returns a dict with asks and bids lists and depth, the levels
+        returned. Depth is below num when the quote right of the market does
+        not cover num levels.
+        include_detail: add the per-order "details" (order_id, volume) of
+        each level, which only SF quotes carry.
+        """
+    def local_order_book(self, security: Security, depth: int | None = None) -> Any | None:
+        """Order book of a security kept from its order book pushes, without
+        a request to OpenD. Returns a dict with asks and bids (best first,
+        cut to depth levels if given), bid_time and ask_time (server receive
+        times), updated_at, sequence (pushes applied), gaps (pushes after a
+        gap in server time) and dropped (out-of-order pushes); None before
+        the first push.
+        """
+    def best_bid_ask(self, security: Security) -> Any | None:
+        """Best bid and ask of a security's local order book (see
+        `local_order_book()`). Returns a dict with bid, bid_volume, ask and
+        ask_volume (None for an empty side), or None before the first push.
+        """
+    def get_ticker(self, security: Security, max_ret_num: int = 100) -> list[Any]:
+        """Get ticker (trade ticks) for a single security.
+        Returns a list of ticker dicts.
+        """
+    @overload
+    def get_basic_qot(self, securities: list[Security], fields: list[str] | None = None, typed: Literal[False] = False) -> list[dict[str, Any]]:
+        """Get basic quote data.
+        fields: names of the fields to include (market and code are always
+        included); None includes all. Unknown names raise ValueError.
+        typed: return `BasicQot` objects with every field instead of dicts
+        """
+    @overload
+    def get_basic_qot(self, securities: list[Security], fields: list[str] | None = None, *, typed: Literal[True]) -> list[BasicQot]: ...
+    @overload
+    def get_history_kl(self, security: Security, rehab_type: int, kl_type: int, begin_time: str, end_time: str, max_count: int | None = None, typed: Literal[False] = False) -> list[dict[str, Any]]:
+        """Get historical K-line data.
+        typed: return `Kline` objects instead of dicts
+        """
+    @overload
+    def get_history_kl(self, security: Security, rehab_type: int, kl_type: int, begin_time: str, end_time: str, max_count: int | None = None, *, typed: Literal[True]) -> list[Kline]: ...
+    @overload
+    def get_history_kl_all(self, security: Security, rehab_type: int, kl_type: int, begin_time: str, end_time: str, max_count: int | None = None, max_pages: int | None = None, typed: Literal[False] = False) -> list[dict[str, Any]]:
+        """Get every historical K-line of a range, following OpenD's pagination
+        until it is exhausted.
+        max_count: K-lines per request; None lets OpenD choose
+        max_pages: stop after this many requests; None follows every page
+        typed: return `Kline` objects instead of dicts
+        Returns list of dicts as `get_history_kl()`. Any failed request
+        fails the call; see `download_history_kl()` to keep partial data.
+        """
+    @overload
+    def get_history_kl_all(self, security: Security, rehab_type: int, kl_type: int, begin_time: str, end_time: str, max_count: int | None = None, max_pages: int | None = None, *, typed: Literal[True]) -> list[Kline]: ...
+    def get_history_kl_arrow(self, security: Security, rehab_type: int, kl_type: int, begin_time: str, end_time: str, max_count: int | None = None, max_pages: int | None = None) -> Any:
+        """`get_history_kl_all()` as a pyarrow RecordBatch, one row per K-line,
+        with the columns time, timestamp, is_blank, open_price, high_price,
+        low_price, close_price, last_close_price, volume, turnover,
+        turnover_rate, pe and change_rate. The columns are shared with
+        pyarrow, not copied, so e.g. `polars.from_arrow()` loads them
+        directly. Needs the `arrow` feature and pyarrow.
+        """
+    def download_history_kl(self, security: Security, rehab_type: int, kl_type: int, begin_time: str, end_time: str, page_size: int | None = None, max_pages: int = 1000, validate: bool = False, resume_key: bytes | None = None) -> Any:
+        """Download all historical K-lines of a range, following OpenD's
+        pagination, and optionally check them for gaps against the trading
+        calendar, duplicate times and inconsistent OHLC values.
+        page_size: K-lines per request; None lets OpenD choose
+        max_pages: stop after this many requests
+        validate: add an integrity report
+        resume_key: the `resume_key` of an earlier, incomplete download to
+        continue it instead of starting at begin_time
+        Returns a dict with `kl_list` (in the `get_history_kl()` layout),
+        `pages`, `is_complete`, `error` (of the page that stopped the
+        download, or None), `resume_key` (bytes, or None once complete) and
+        `report` (None unless validated). Only a failed first request raises.
+        """
+    def get_history_kl_quota(self) -> Any:
+        """History K-line quota as a dict with `used`, `remaining` and
+        `consumed`, a list of (market, code, time) of the symbols that used
+        quota in the last 30 days, time in Unix seconds.
+        """
+    def download_history_kl_batch(self, securities: list[Security], rehab_type: int, kl_type: int, begin_time: str, end_time: str, ledger_path: str | None = None, allow_over_quota: bool = False, page_size: int | None = None, max_pages: int = 1000, validate: bool = False) -> Any:
+        """Download historical K-lines of several securities within the history
+        K-line quota. Securities already paid for in the last 30 days are
+        downloaded first; a batch needing more new symbols than the quota
+        has left raises unless allow_over_quota. Symbols throttled by OpenD
+        are retried after a backoff.
+        ledger_path: file of the symbols that used quota, read before and
+        updated after the batch
+        page_size, max_pages, validate: as for `download_history_kl()`
+        Returns a dict with `paid` and `new` (the plan, as (market, code)
+        lists), `downloads` (a list of dicts with `market`, `code` and the
+        `download_history_kl()` fields), `failed` (a list of (market, code,
+        error)) and `quota` (the quota after the batch, or None).
+        """
+    def get_acc_list(self, trd_category: int | None = None, need_general_sec_account: bool | None = None) -> list[Any]:
+        """Get account list."""
+    def unlock_trade(self, unlock: bool, pwd_md5: str, security_firm: int | None = None, acc_id: int | None = None) -> int | None:
+        """Unlock trading.
+        security_firm: 1=FutuSecurities, 2=FutuInc, 3=FutuSG, etc. If None, it is
+        detected from the account; an explicit value must match the account's
+        firm or SecurityFirmMismatchError is raised.
+        acc_id: account whose firm is used; defaults to the default account.
+        Without either, security_firm must be the firm of one of the real
+        accounts, and ValueError is raised if they are of several firms and
+        security_firm is None. Returns the security firm used (None if unknown).
+        """
+    def place_order(self, trd_side: int, order_type: int, security: Security, qty: float, price: float | None = None, sec_market: int | None = None, *, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None) -> Any:
+        """Place an order.
+        security: (market, code) tuple or symbol alias, which also sets the
+        security market; any other string is a bare code in sec_market
+        (1=HK, 2=US, 31=CN_SH, 32=CN_SZ, etc.)
+        """
+    def place_order_idempotent(self, trd_side: int, order_type: int, security: Security, qty: float, price: float | None = None, sec_market: int | None = None, *, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None) -> Any:
+        """Place an order unless the same order (account, code, side, qty and
+        price) was placed by this client within the last minute, in which case
+        the earlier order_id is returned. Safe to call again after a timeout or
+        connection error: the retry sends the PacketID of the failed attempt,
+        so OpenD does not take it for a second order.
+        Takes the arguments of place_order().
+        """
+    def place_order_autolock(self, trd_side: int, order_type: int, security: Security, qty: float, price: float | None = None, sec_market: int | None = None, allow_unlock: bool = False, *, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None) -> Any:
+        """Place an order, unlocking trading and retrying once if OpenD answers
+        that trading is locked. Unlocking needs allow_unlock=True and
+        credentials stored with set_unlock_credentials(); otherwise the locked
+        error is returned like any other.
+        Never raises for a rejected order: returns a dict with ok, order_id,
+        order_id_ex, error (None if ok), unlocked, and steps, a list of dicts
+        with step ("place", "unlock" or "retry") and error (None if it
+        succeeded) in the order taken.
+        """
+    def modify_order(self, order_id: int, modify_op: int, qty: float | None = None, price: float | None = None, *, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None) -> None:
+        """Modify an order."""
+    def reconfirm_order(self, order_id: int, reconfirm_reason: int, *, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None) -> int:
+        """Confirm an order OpenD held back with a warning, e.g. a US order in
+        the pre- or post-market session. reconfirm_reason is the warning's
+        ReconfirmOrderReason, as given by the err_code of the place_order
+        error. Returns the order ID.
+        """
+    def cancel_all_orders(self, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None, codes: list[Security] | None = None, order_ids: list[int] | None = None) -> Any:
+        """Cancel the open orders of an account. Without `codes` or
+        `order_ids` OpenD cancels them all in one request; otherwise the
+        matching open orders are cancelled concurrently. `codes` takes codes,
+        (market, code) tuples or symbol aliases. Returns a dict with
+        for_all (True for the single request), cancelled (order IDs) and
+        failed (list of (order_id, error) tuples).
+        """
+    def gateway_rejections(self) -> list[Any]:
+        """Recent requests OpenD rejected (non-zero retType), oldest first, as
+        dicts with conn_id, proto_id, serial_no, ret_type, timestamp and
+        gateway_log (the matching lines of OpenD's log). Empty unless
+        connected with `gateway_log_dir`.
+        """
+    def order_history(self, order_id: int) -> list[Any]:
+        """Amendment chain of an order placed, modified or updated on this
+        connection, oldest first. Returns list of dicts with kind (placed,
+        modified, cancelled, disabled, enabled, deleted, status_changed),
+        timestamp, qty, price and order_status (None until OpenD reports it).
+        Chains of finished orders are dropped an hour after they finished.
+        """
+    @overload
+    def get_order_list(self, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None, typed: Literal[False] = False) -> list[dict[str, Any]]:
+        """Get order list.
+        Returns list of dicts with order details, or `Order` objects if typed.
+        """
+    @overload
+    def get_order_list(self, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None, *, typed: Literal[True]) -> list[Order]: ...
+    def get_order_fill_list(self, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None) -> list[Any]:
+        """Get order fill list.
+        Returns list of dicts with fill details.
+        """
+    @overload
+    def get_position_list(self, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None, typed: Literal[False] = False) -> list[dict[str, Any]]:
+        """Get position list.
+        Returns list of dicts with position details, or `Position` objects
+        if typed.
+        """
+    @overload
+    def get_position_list(self, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None, *, typed: Literal[True]) -> list[Position]: ...
+    def start_live_portfolio(self, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None, interval_secs: float = 30.0, subscribe_quotes: bool = True) -> None:
+        """Follow the positions of an account from fill and quote pushes,
+        reconciling with `get_position_list()` every `interval_secs`; read
+        them with `get_live_portfolio()`. With `subscribe_quotes`, basic
+        quotes of held securities are subscribed so they are marked to
+        market. Replaces a portfolio already followed.
+        """
+    def get_live_portfolio(self) -> list[Any]:
+        """Open positions of `start_live_portfolio()`, marked to the latest
+        pushed price, without a request to OpenD. Returns a list of dicts
+        with code, alias, name, sec_market, qty (negative when short),
+        cost_price, last_price, price_time, market_val, unrealized_pl,
+        realized_pl, currency and reconciled_at (time of the latest
+        position list poll, None before the first).
+        """
+    def get_funds(self, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None, currency: int | None = None) -> Any:
+        """Get account funds.
+        Returns a dict with fund details.
+        """
+    def get_security_snapshot(self, securities: list[Security], fields: list[str] | None = None) -> list[Any]:
+        """Get security snapshot.
+        securities: list of (market, code) tuples or symbol aliases
+        Returns list of dicts with snapshot data. The data of the security's
+        type is a dict under equity_ex_data (PE, PB, market cap, shares),
+        warrant_ex_data, option_ex_data (greeks, open interest),
+        index_ex_data, plate_ex_data, future_ex_data or trust_ex_data; the
+        keys of other types are absent.
+        fields: names of the fields to include (market and code are always
+        included); None includes all. Unknown names raise ValueError.
+        """
+    def get_security_snapshot_all(self, securities: list[Security], fields: list[str] | None = None, parallelism: int = 4) -> list[Any]:
+        """Snapshots of any number of securities, in input order, requested 400
+        at a time with up to `parallelism` requests in flight. Unlike
+        `get_security_snapshot_bulk()`, any failed request fails the call.
+        Returns list of dicts as `get_security_snapshot()`.
+        """
+    def get_security_snapshot_bulk(self, securities: list[Security], fields: list[str] | None = None, chunk_size: int = 400) -> Any:
+        """Snapshots of any number of securities, requested chunk_size (at
+        most 400) at a time. A failed chunk does not fail the call.
+        Returns a dict with `snapshots` (in the `get_security_snapshot()`
+        layout), `errors` (dicts of the failed chunks' securities and error)
+        and `resume` (the failed securities, to pass back to retry them).
+        """
+    def sub_acc_push(self, acc_ids: list[int], replace: bool = False) -> list[int]:
+        """Subscribe to trade account push notifications.
+        acc_ids: list of account IDs to subscribe
+        replace: subscribe exactly acc_ids instead of adding them to the
+        accounts already subscribed
+        Returns the accounts subscribed afterwards.
+        """
+    def unsub_acc_push(self, acc_ids: list[int]) -> list[int]:
+        """Stop trade account push notifications of the given account IDs.
+        Returns the accounts still subscribed.
+        """
+    def refresh_acc_push(self) -> list[int]:
+        """Subscribe the recorded trade push accounts again.
+        Returns them.
+        """
+    def get_acc_push_accounts(self) -> list[int]:
+        """Accounts currently subscribed to trade pushes, ascending."""
+    def set_default_account(self, trd_env: int, acc_id: int, trd_market: int) -> None:
+        """Set the default trade account used when trd_env, acc_id or trd_market
+        is omitted (or None) in trade methods. Per-call values still override.
+        """
+    def get_default_account(self) -> tuple[int, int, int] | None:
+        """Get the default trade account as (trd_env, acc_id, trd_market), or None."""
+    def set_language(self, language: str | None = None) -> None:
+        """Set the preferred language for security names, or None for the names
+        returned by OpenD. OpenD has no per-connection locale, so names are
+        translated locally from tables added with `add_security_names`.
+        """
+    def get_language(self) -> str | None:
+        """Get the preferred language for security names, or None."""
+    def add_security_names(self, language: str, names: list[tuple[int, str, str]]) -> int:
+        """Add security names for `language` as (market, code, name) tuples.
+        Returns the number of names known for the language.
+        """
+    def add_brokers(self, brokers: list[tuple[int, str, str | None]]) -> int:
+        """Add broker seats as (id, name, group) tuples; a group of None groups
+        the seat by its name. Returns the number of known seats.
+        """
+    def load_broker_table(self, path: str) -> int:
+        """Load a broker reference table from a CSV file of `id,name[,group]`
+        lines. Returns the number of known seats.
+        """
+    def broker_info(self, broker_id: int) -> Any | None:
+        """Name and group of a broker seat as a dict with "id", "name" and
+        "group", or None if the seat is unknown.
+        """
+    def broker_seats(self, group: str) -> list[int]:
+        """IDs of the known seats of broker group `group`, ascending."""
+    def add_symbol_aliases(self, aliases: list[tuple[str, int, str]]) -> int:
+        """Register symbol aliases as (alias, market, code) tuples. Aliases are
+        accepted wherever a securities list is taken, and outputs carrying a
+        market and code (pushes included) get an "alias" key (None if unmapped).
+        Re-registering an alias or a security replaces its old mapping.
+        Returns the number of registered aliases.
+        """
+    def remove_symbol_alias(self, alias: str) -> bool:
+        """Remove a symbol alias. Returns False if it was unknown."""
+    def resolve_symbol(self, alias: str) -> tuple[int, str] | None:
+        """(market, code) of a symbol alias, or None."""
+    def symbol_alias(self, market: int, code: str) -> str | None:
+        """Alias registered for (market, code), or None."""
+    def clear_default_account(self) -> None:
+        """Clear the default trade account."""
+    def is_connected(self) -> bool:
+        """Check if the client is connected to Futu OpenD."""
+    def get_message_trace(self) -> list[Any]:
+        """Get the recent message trace (oldest first).
+        Returns list of dicts with direction, proto_id, serial_no, body_len,
+        timestamp and ret_type.
+        """
+    def start_capture(self, path: str | os.PathLike[str], include_wire: bool = True) -> None:
+        """Start capturing every packet to `path` (see the `futu_capture` tool).
+        include_wire: also record the encrypted form of each body.
+        Credential-bearing bodies (InitConnect, UnlockTrade) are never written.
+        """
+    def stop_capture(self) -> int | None:
+        """Stop the running packet capture. Returns the number of packets
+        written, or None if no capture was running.
+        """
+    def health(self) -> list[Any]:
+        """Health report of background tasks: the client's keepalive and recv
+        loops (when connected) followed by push forwarders and pollers.
+        Returns list of dicts with name, state, restarts, last_error and started_at.
+        """
+    def get_stats(self) -> Any:
+        """Connection-level counters as a dict: endpoint, uptime_secs,
+        total_requests, avg_latency_ms, push_rate (pushes per second since
+        connecting), bytes_sent, bytes_received, reconnects, key_rotations,
+        pending_requests, requests ({proto_id: {count, failures, throttled,
+        retries, avg_latency_ms}}) and pushes ({proto_id: {received, dropped,
+        per_sec}}). Latencies are None before any response.
+        """
+    def connection_health(self) -> Any:
+        """Keepalive health as a dict: last_sent, last_reply and last_received
+        (Unix timestamps, None before the first), heartbeats_sent, replies,
+        missed (heartbeats since the last reply), stale, stale_count and
+        rtt_last_ms, rtt_min_ms, rtt_max_ms, rtt_avg_ms (None before the
+        first reply).
+        """
+    def metrics(self) -> str:
+        """Client metrics in the Prometheus text format: request latencies, push
+        counts and drops, quote pushes per security, pending requests, task
+        restarts, session preemption, connects and push channel backlogs.
+        """
+    def start_metrics_server(self, host: str = "127.0.0.1", port: int = 9464) -> int:
+        """Serve `metrics()` on http://host:port/metrics for Prometheus to
+        scrape, across reconnects, until `stop_metrics_server()` or
+        `disconnect()`. Starting it again replaces the running server. Port 0
+        picks a free port. Returns the bound port. Only available when built
+        with the `server` feature.
+        """
+    def stop_metrics_server(self) -> bool:
+        """Stop the server started by `start_metrics_server()`, closing the
+        port. Returns False if none was running.
+        """
+    def start_push(self, proto_ids: list[int], decode_on_worker: bool = False, include_detail: bool = False) -> int:
+        """Start receiving push notifications for the given proto_ids.
+        Each call creates a **new** channel pair and returns its index.
+        Data and execution clients should each call this once and store
+        their own `channel_id` for use with `poll_push()`.
+        decode_on_worker: parse pushes on the runtime as they arrive, so
+        polling only builds the Python objects. Keeps poll latency flat
+        under bursts at the cost of decoding messages that are never polled.
+        include_detail: keep the per-order "details" of order book levels
+        (order_id, volume) that books subscribed with order_book_detail
+        carry; without it they are dropped before reaching the queue.
+        """
+    def stop_push(self, channel_id: int, proto_ids: list[int]) -> list[int]:
+        """Stop forwarding the given proto_ids to a `start_push()` channel.
+        Their dispatcher handlers are unregistered and forwarder tasks
+        aborted; messages already queued stay on the channel. Returns the
+        proto_ids that were stopped.
+        """
+    def update_push_filter(self, channel_id: int, proto_ids: list[int] | None = None, securities: list[Security] | None = None, clear_securities: bool = False) -> Any:
+        """Change what a `start_push()` channel receives without reconnecting.
+        proto_ids: the complete proto set; forwarders are started and
+        stopped to match. None keeps the current set.
+        securities: only quote pushes of these securities are delivered;
+        basic quote lists are trimmed to them. Trade pushes are never
+        filtered. None keeps the current filter.
+        clear_securities: remove the security filter.
+        Returns a dict with the resulting `proto_ids` and `securities`
+        (None when unfiltered).
+        """
+    def start_qot_poller(self, securities: list[Security], interval_ms: int = 3000, chunk_size: int = 400) -> int:
+        """Start polling basic quotes for securities that are not subscribed.
+        Changed quotes (by update time) are delivered on a new push channel as
+        Qot_UpdateBasicQot (proto 3005) messages, so `poll_push()` handles them
+        like regular basic quote pushes. Returns the channel_id.
+        interval_ms: time between polling passes
+        chunk_size: securities per snapshot request (max 400)
+        """
+    def set_push_callback(self, channel_id: int, callback: Any | None = None, queue_size: int | None = None, max_batch: int = 256, batch: bool = False) -> None:
+        """Deliver the messages of a push channel to `callback` instead of
+        polling them. A thread of the channel waits for messages and calls
+        `callback(message)` with the GIL held, one `poll_push()` dict per
+        call; the channel must not be polled meanwhile. Exceptions raised by
+        the callback are reported through `sys.unraisablehook` and do not
+        stop delivery. Replaces an earlier callback of the channel; a
+        callback of None stops delivery, leaving later messages queued.
+        queue_size: messages that may wait while the callback is busy; the
+        oldest quote pushes beyond it are dropped. Order and fill pushes
+        are never dropped. None queues without limit.
+        max_batch: messages delivered per GIL acquisition
+        batch: call `callback(messages)` once per batch with a list instead
+        """
+    def push_callback_stats(self, channel_id: int) -> Any | None:
+        """Counters of the callback of a push channel as a dict with
+        `delivered`, `dropped` (by queue_size), `errors` (failed conversions
+        and raised callbacks) and `queued`, or None without a callback.
+        """
+    def poll_push(self, channel_id: int, timeout_ms: int = 100) -> Any | None:
+        """Poll for the next push message on a specific channel.
+        channel_id: index returned by `start_push()`
+        timeout_ms: how long to wait for a message (in milliseconds)
+        """
+    def poll_push_batch(self, channel_id: int, max_items: int = 256, timeout_ms: int = 100) -> list[Any]:
+        """Poll up to `max_items` push messages on a channel in one call.
+        Waits up to timeout_ms for the first message, then takes whatever else
+        is already queued without waiting. Returns a list of dicts in the
+        `poll_push()` layout; empty on timeout, closed or unknown channel.
+        """
+    def drain_push(self, channel_id: int) -> list[Any]:
+        """Take every push message currently queued on a channel without waiting.
+        Same dict layout as `poll_push()`.
+        """
+    def start_bar_push(self, partial_bars: str = "skip") -> int:
+        """Start a bar aggregation channel. Bars of the specs added with
+        `add_bar_spec()` are built from ticker pushes (LAST) and order book
+        pushes (BID, ASK, MID); read them with `poll_bars()`. The securities
+        need the matching TICKER or ORDER_BOOK subscription. Returns the channel_id.
+        partial_bars: "skip" (default) drops bars that do not cover their whole
+        interval; "emit" delivers them with partial=True.
+        """
+    def add_bar_spec(self, channel_id: int, security: Security, bar_spec: str) -> bool:
+        """Build `bar_spec` bars (e.g. "1-MINUTE-LAST", "5-MINUTE-MID") for a
+        security on a bar channel. Supported aggregations are SECOND, MINUTE
+        and HOUR; price types BID, ASK, MID and LAST.
+        Returns False if the spec was already added.
+        """
+    def remove_bar_spec(self, channel_id: int, security: Security, bar_spec: str) -> bool:
+        """Stop building `bar_spec` bars for a security. Its open bar is delivered
+        if the channel emits partial bars. Returns False if it was not added.
+        """
+    def poll_bars(self, channel_id: int, max_items: int = 256, timeout_ms: int = 100) -> list[Any]:
+        """Poll up to `max_items` bars of a bar channel, waiting up to timeout_ms
+        for the first. Returns a list of dicts with market, code, alias,
+        bar_spec, open, high, low, close, volume, tick_count, ts_open,
+        ts_close (the bar's event time) and partial; empty on timeout.
+        """
+    def watch_ipo(self, markets: list[int], interval_secs: int = 3600) -> int:
+        """Watch the IPO lists of `markets`, polling every `interval_secs`.
+        Emits events for newly announced IPOs, pricing updates and listing
+        days; read them with `poll_ipo_event()`. Returns the watcher_id.
+        """
+    def poll_ipo_event(self, watcher_id: int, timeout_ms: int = 100) -> Any | None:
+        """Poll for the next IPO event of a watcher.
+        Returns a dict with `event` ("announced", "pricing_updated" or
+        "listing_day") plus the IPO fields of `get_ipo_list()`, or None on timeout.
+        """
+    def watch_warrant_screen(self, filter: dict[str, Any], interval_secs: int = 60) -> int:
+        """Re-run a saved warrant filter every `interval_secs` and emit ranking
+        changes; read them with `poll_warrant_event()`. `filter` takes the
+        `get_warrant()` arguments sort_field, ascend, owner, type_list and
+        issuer_list, plus max_results (ranking length watched, default 200)
+        and page_interval_ms (pause between page requests, default 500).
+        The first run reports the whole ranking as added. Returns the watcher_id.
+        """
+    def poll_warrant_event(self, watcher_id: int, timeout_ms: int = 100) -> Any | None:
+        """Poll for the next ranking change of a warrant watcher.
+        Returns a dict with `event` ("added", "removed" or "rank_changed"),
+        `rank` and `prev_rank` (1-based, None where not ranked) plus the
+        warrant fields of `get_warrant()`, or None on timeout.
+        """
+    def watch_listings(self, securities: list[Security] | None = None, interval_secs: int = 86400) -> int:
+        """Scan the securities of the instrument cache (those `instrument()` or
+        order rounding looked up) plus `securities` every `interval_secs`
+        (daily by default) for suspensions, delistings and code changes,
+        and update the cached instruments; read the changes with
+        `poll_listing_event()`. The first scan reports the securities that
+        are already suspended or delisted. Returns the watcher_id.
+        """
+    def poll_listing_event(self, watcher_id: int, timeout_ms: int = 100) -> Any | None:
+        """Poll for the next listing change of a listing watcher.
+        Returns a dict with `event` ("suspended", "resumed", "delisted" or
+        "code_changed"), market, code and alias, or None on timeout. Code
+        changes add type, related_market, related_code, effective_time and
+        end_time.
+        """
+    def push_stats(self) -> list[Any]:
+        """Quote push statistics per security and sub type.
+        Returns list of dicts with market, code, sub_type, count, last_update,
+        tracked_since and subscribed.
+        """
+    def set_dedup_pushes(self, enabled: bool) -> None:
+        """Drop pushes a push channel already received, such as the latest
+        quote OpenD pushes again when a subscribed security is subscribed
+        by another profile. Applies to the current and future connections.
+        Off by default.
+        """
+    def renew_subscriptions(self, securities: list[Security], sub_types: list[int], ttl_secs: float) -> int:
+        """Extend subscriptions made with ttl_secs to expire `ttl_secs` from
+        now. Pairs subscribed without a TTL are left alone. Returns the
+        number of (security, sub type) pairs renewed.
+        """
+    def poll_subscription_expiry(self, timeout_ms: int = 100) -> Any | None:
+        """Poll for the next expired subscription. Returns a dict with market,
+        code, alias, sub_type and error (OpenD's reason if unsubscribing
+        failed, retried every second; None once unsubscribed), or None on
+        timeout or if nothing was subscribed with a TTL.
+        """
+    def current_subscriptions(self) -> list[Any]:
+        """Current quote subscriptions made through `subscribe()`, recorded
+        locally without asking OpenD. Returns list of dicts with market, code,
+        alias, sub_types and order_book_detail.
+        """
+    def set_sub_quota(self, quota: int | None = None) -> None:
+        """Refuse `subscribe()` calls that would hold more than `quota`
+        (security, sub type) pairs, before sending them; None leaves the
+        check to OpenD. Applies to the current and future connections.
+        """
+    def sync_sub_quota(self) -> int:
+        """Take the subscription quota of the current connection from OpenD
+        (Qot_GetSubInfo) and check it locally from now on. Returns the quota.
+        """
+    def sub_quota(self) -> Any:
+        """Subscription quota in use and left, a dict with used, quota and
+        remaining (None without a quota).
+        """
+    def suppressed_duplicates(self) -> dict[int, int]:
+        """Pushes suppressed as duplicates since connecting, a dict keyed by
+        proto_id. A push suppressed for two channels counts twice.
+        """
+    def stale_securities(self, threshold_secs: float) -> list[Any]:
+        """Subscribed securities without a push for more than `threshold_secs`,
+        regardless of market hours. Same dict layout as `push_stats()`.
+        """
+    def start_staleness_monitor(self, threshold_secs: float = 60.0, check_interval_secs: float = 10.0) -> int:
+        """Start checking every `check_interval_secs` for subscribed securities
+        that stop pushing for more than `threshold_secs` while their market is
+        in session. Read alarms with `poll_stale_alarm()`. Returns the monitor_id.
+        """
+    def poll_stale_alarm(self, monitor_id: int, timeout_ms: int = 100) -> Any | None:
+        """Poll for the next staleness alarm of a monitor.
+        Returns a dict in the `push_stats()` layout, or None on timeout.
+        """
+    def set_pause_trading_on_preempt(self, enabled: bool) -> None:
+        """Refuse place_order/modify_order with SessionPreemptedError while
+        another login of the account has preempted the session (OpenD kicked
+        out, or quote rights taken over). Applies to the current and future
+        connections. Off by default.
+        """
+    def set_auto_round_orders(self, enabled: bool) -> None:
+        """Round place_order qty down to whole lots and price onto the
+        security's tick table (buys down, sells up) instead of letting OpenD
+        reject them. Needs sec_market to find the security. Off by default.
+        """
+    def set_unlock_credentials(self, pwd_md5: str | None = None, security_firm: int | None = None) -> None:
+        """Store the trading password (MD5) place_order_autolock() unlocks with,
+        or clear it with None. It is kept in memory only.
+        security_firm: as for unlock_trade(); None detects it from the account.
+        """
+    def set_order_throttle(self, entry_limit: int | None = None, cancel_limit: int | None = None, cancel_reserve: int = 0, window_secs: float = 30.0) -> None:
+        """Client-side budgets for order requests, per account. place_order
+        draws from entry_limit; modify_order from cancel_limit, of which
+        cancel_reserve requests are kept for cancels (modify_op 2 or 5).
+        Requests over budget wait until it refills over window_secs. A limit
+        of None leaves those requests unthrottled. Applies to the current and
+        future connections. Off by default.
+        """
+    def set_retry_policy(self, max_retries: int = 2, max_delay_secs: float = 2.0) -> None:
+        """Resending of requests OpenD answers as busy or over its frequency
+        limit. A throttled request is resent up to max_retries times when
+        the suggested wait is at most max_delay_secs; otherwise the error is
+        returned (ThrottledError from place_order/modify_order, which are
+        never resent). Applies to the current and future connections.
+        """
+    def set_socket_options(self, connect_timeout: float | None = Some(10.0), tcp_keepalive: float | None = None, read_timeout: float | None = None, nodelay: bool = True) -> None:
+        """TCP settings of later connections, in seconds: connect_timeout per
+        endpoint (connect() raises FutuTimeoutError when it passes),
+        tcp_keepalive idle time before keep-alive probes, read_timeout after
+        which a silent OpenD counts as disconnected, and nodelay for
+        TCP_NODELAY. None disables a timeout.
+        """
+    def set_slow_request_threshold(self, secs: float | None = None) -> None:
+        """Log a warning, with proto_id and serial_no, for every request still
+        unanswered after `secs` seconds, and another when it is answered.
+        None turns the warnings off. Applies to the current and future
+        connections. Off by default.
+        """
+    def set_response_cache(self, enabled: bool, ttls: dict[int, float] | None = None, max_entries: int | None = None) -> None:
+        """Cache responses of reference data requests. `ttls` maps proto_id to
+        seconds and is merged into the current TTLs; 0 stops caching a
+        protocol. By default static info, future info and trading calendars
+        are kept a day, plate sets, plate members, references, owner plates,
+        code changes and option expirations an hour; snapshots and other
+        quotes are never cached. Applies to the current and future
+        connections. Off by default.
+        """
+    def response_cache_stats(self) -> Any:
+        """Response cache counters: a dict with hits, misses, evictions,
+        invalidations, entries and per_proto (proto_id -> dict of hits,
+        misses and entries), plus enabled and ttls (proto_id -> seconds).
+        """
+    def invalidate_response_cache(self, proto_id: int | None = None) -> int:
+        """Drop cached responses of `proto_id`, or all of them if None.
+        Returns the number dropped.
+        """
+    def set_rate_limit(self, enabled: bool, mode: str = "delay", limits: dict[int, tuple[int, float]] | None = None) -> None:
+        """Client-side request rate limits per protocol. `limits` maps
+        proto_id to (requests, window_secs) and is merged into the current
+        limits; by default OpenD's documented limits of snapshots, history
+        K-lines, plates, option chains, warrants, capital flows, stock
+        filters and trade history queries are used. In mode "delay" a
+        request over its limit waits for the budget; in mode "fail_fast" it
+        raises ThrottledError. Applies to the current and future
+        connections. Off by default.
+        """
+    def rate_limit_stats(self) -> Any:
+        """Rate limiter state: a dict with enabled, mode, limits (proto_id ->
+        (requests, window_secs)) and limited (proto_id -> requests delayed
+        or rejected).
+        """
+    def set_funds_protocols(self, enabled: bool) -> None:
+        """Allow the cash flow and funds protocols (`get_cash_flow()`), which
+        are refused until enabled. Applies to the current and future
+        connections. Off by default.
+        """
+    def funds_capabilities(self) -> Any:
+        """Funds operations and whether they can be used: a dict keyed by
+        operation ("cash_flow_summary", "funds_transfer", "cash_sweep",
+        "currency_exchange") of dicts with proto_id (None when OpenAPI has
+        no protocol for it), supported and enabled.
+        """
+    def session_preempted(self) -> Any | None:
+        """Current preemption as a dict with reason ("kicked_out" or
+        "quote_right_lost"), desc and timestamp, or None.
+        """
+    def resume_trading(self) -> None:
+        """Clear a preemption manually, e.g. after logging OpenD back in."""
+    def rekey(self) -> Any:
+        """Rotate the connection's AES key by repeating the InitConnect
+        handshake on the open connection; subscriptions are kept. Returns a
+        dict with conn_id, server_ver, keep_alive_interval and key_rotations
+        (rotations so far, also exported as futu_aes_key_rotations_total).
+        """
+    def poll_session_event(self, timeout_ms: int = 100) -> Any | None:
+        """Poll for the next session event of the current connection.
+        Returns a dict with event ("preempted" or "resolved") and timestamp,
+        plus reason and desc for preemptions, or None on timeout.
+        """
+    def poll_order_events(self, timeout_ms: int = 100, max_events: int = 256) -> list[Any]:
+        """Poll up to `max_events` order lifecycle events of the current
+        connection, synthesized from order and fill pushes of the accounts
+        subscribed with `sub_acc_push()`. Waits up to timeout_ms for the
+        first event, then takes whatever else is queued. Each event is a dict
+        with acc_id, order_id, code, prev_state (None for an order's first
+        event), state ("pending_submit", "submitted", "partially_filled",
+        "pending_cancel", "disabled", "filled", "cancelled", "rejected",
+        "deleted" or "unknown"), qty, filled_qty, fill_avg_price, fill (the
+        `get_order_fill_list()` dict of the fill behind the event, or None)
+        and timestamp. A new fill is reported even if the state stays the same.
+        """
+    def stock_filter(self, market: int, begin: int = 0, num: int = 200, base_filters: list[tuple[int, float | None, float | None, int | None]] | None = None, accumulate_filters: list[tuple[int, int, float | None, float | None, int | None]] | None = None, financial_filters: list[tuple[int, int, float | None, float | None, int | None]] | None = None) -> Any:
+        """Filter stocks by conditions (Qot_StockFilter, proto 3215).
+        base_filters: list of (fieldName, filterMin, filterMax, sortDir)
+        accumulate_filters: list of (fieldName, days, filterMin, filterMax, sortDir)
+        financial_filters: list of (fieldName, quarter, filterMin, filterMax, sortDir)
+        """
+    def get_plate_security(self, plate: Security, sort_field: int | None = None, ascend: bool | None = None) -> list[Any]:
+        """Get securities in a plate/sector (Qot_GetPlateSecurity, proto 3205).
+        Returns a list of static info dicts (same format as get_static_info).
+        """
+    @overload
+    def get_history_order_list(self, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None, filter_status_list: list[int] | None = None, typed: Literal[False] = False) -> list[dict[str, Any]]:
+        """Get historical order list.
+        Returns list of dicts with order details, or `Order` objects if typed.
+        """
+    @overload
+    def get_history_order_list(self, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None, filter_status_list: list[int] | None = None, *, typed: Literal[True]) -> list[Order]: ...
+    def get_history_order_fill_list(self, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None) -> list[Any]:
+        """Get historical order fill list.
+        Returns list of dicts with fill details.
+        """
+    def get_history_fills(self, begin_time: str, end_time: str, code_list: list[Security] | None = None, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None, window_days: float = 7.0, concurrency: int = 2, page_limit: int = 1000) -> Any:
+        """Historical fills between begin_time and end_time ("YYYY-MM-DD
+        HH:MM:SS" or "YYYY-MM-DD", inclusive), fetched in windows of
+        window_days, concurrency windows at a time. Windows returning
+        page_limit fills are split until they fit. Fills are deduplicated by
+        fill_id_ex and ordered by create_timestamp. code_list takes codes,
+        (market, code) tuples or symbol aliases.
+        Returns a dict with "fills" (same keys as get_history_order_fill_list),
+        "is_complete" (False if a one-minute window was still full, so fills
+        may be missing) and "requests".
+        """
+    def execution_report(self, day: str, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None) -> Any:
+        """Execution-quality report for one day ("YYYY-MM-DD"): each fill is
+        compared with the mid-price of the 1-minute bar it executed in.
+        Returns a dict with "fills" (fill_id, order_id, code, sec_market,
+        trd_side, qty, price, fill_time, fill_timestamp, benchmark, slippage,
+        slippage_bps), "orders" (order_id, code, trd_side, fill_count,
+        filled_qty, avg_price, arrival_price, shortfall, shortfall_bps) and
+        "csv" (the fill rows as CSV). Positive slippage and shortfall are costs.
+        """
+    def get_max_trd_qtys(self, order_type: int, security: Security, price: float, sec_market: int | None = None, *, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None) -> Any:
+        """Get maximum tradeable quantities.
+        Returns a dict with max qty fields.
+        """
+    def get_margin_ratio(self, securities: list[Security], *, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None) -> list[Any]:
+        """Get margin ratio for securities.
+        Returns list of dicts with margin ratio info.
+        """
+    def get_order_fee(self, order_id_ex_list: list[str], *, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None) -> list[Any]:
+        """Get order fee details.
+        Returns list of dicts with fee info.
+        """
+    def get_cash_flow(self, clearing_date: str, direction: int | None = None, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None) -> list[Any]:
+        """Cash flow entries of an account on a clearing date ("YYYY-MM-DD").
+        direction: 1 = in, 2 = out; None returns both.
+        Needs `set_funds_protocols(True)`.
+        Returns list of dicts with cash flow info.
+        """
+    def get_flow_summary(self, begin_date: str, end_date: str | None = None, direction: int | None = None, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None) -> list[Any]:
+        """Cash flow entries of an account with a clearing date from begin_date
+        to end_date ("YYYY-MM-DD", both included; end_date defaults to
+        begin_date), oldest first. One request per day, at most 366 days.
+        OpenD allows 20 Trd_FlowSummary requests per 30 seconds, so longer
+        ranges are paced to that limit: 60 days take about a minute and a
+        year about 9 minutes.
+        direction: 1 = in, 2 = out; None returns both.
+        Needs `set_funds_protocols(True)`.
+        Returns list of dicts with cash flow info, as `get_cash_flow()`.
+        """
+    def preview_order(self, trd_side: int, order_type: int, security: Security, qty: float, price: float, *, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None) -> Any:
+        """Preview an order without submitting it.
+        Combines max tradeable quantities, margin ratio and funds.
+        security: (market, code) tuple or symbol alias, with the QotMarket
+        (used for the margin ratio lookup).
+        Returns a dict with feasibility and estimated buying-power impact.
+        """
+    def get_sub_info(self, is_req_all_conn: bool | None = None) -> Any:
+        """Get subscription info.
+        Returns a dict with quota and subscription details.
+        """
+    def get_rt(self, security: Security) -> Any:
+        """Get real-time (time-sharing) data for a single security.
+        Returns a dict with security info and rt_list.
+        """
+    def get_broker(self, security: Security) -> Any:
+        """Get broker queue for a single security.
+        Returns a dict with broker_ask_list and broker_bid_list. Entries carry
+        the seat's "group" (None if unknown) and take their name from the
+        broker table when one was loaded.
+        """
+    def get_order_detail(self, security: Security) -> Any:
+        """Get the order queue detail of the best ask and bid of a security
+        (level 2 quote rights and an ORDER_DETAIL subscription needed).
+        Returns a dict with `order_detail_ask` and `order_detail_bid`, each
+        a dict with `order_count` and `order_vol`, the volumes of the queued
+        orders in queue order (at most 50).
+        """
+    def get_rehab(self, securities: list[Security]) -> list[Any]:
+        """Get rehabilitation (adjustment) data for securities.
+        Returns list of dicts with security and rehab_list.
+        """
+    def get_suspend(self, securities: list[Security], begin_time: str, end_time: str) -> list[Any]:
+        """Get suspension info for securities.
+        Returns list of dicts with security and suspend_list.
+        """
+    def get_market_state(self, securities: list[Security]) -> list[Any]:
+        """Get the market state of each security; securities of one market can
+        be in different sessions.
+        Returns list of dicts with market, code, alias, name and
+        market_state (QotMarketState).
+        """
+    def is_suspended_on(self, security: Security, date: str) -> bool:
+        """Whether a security is suspended on `date` (YYYY-MM-DD).
+        Suspension data is fetched on demand and cached per security.
+        """
+    def next_resume(self, security: Security, date: str) -> str | None:
+        """Date (YYYY-MM-DD) trading resumes after the suspension covering `date`.
+        Returns None if the security is not suspended on `date` or no resume
+        date is known yet.
+        """
+    def instrument(self, security: Security, trd_env: int | None = None, acc_id: int | None = None, trd_market: int | None = None, refresh: bool = False) -> Any:
+        """Consolidated record of a security for onboarding a symbol: static
+        info, tick table, margin and short-sell flags, trading sessions and
+        currency. With an account (given or the default one) the account's
+        margin ratios are added under "margin". Records are cached for an
+        hour; `refresh=True` fetches them again.
+        """
+    def round_qty(self, security: Security, qty: float) -> float:
+        """`qty` of `security` rounded down to a whole number of lots."""
+    def round_price(self, security: Security, price: float, trd_side: int) -> float | None:
+        """`price` of `security` rounded onto its tick table for an order of
+        `trd_side`: buys (1, 4) round down, sells (2, 3) round up, other
+        sides to the nearest tick. None if the tick table is unknown.
+        """
+    def get_plate_set(self, market: int, plate_set_type: int) -> list[Any]:
+        """Get plate set (sector list) for a market.
+        Returns list of dicts with plate info.
+        """
+    def get_all_securities(self, market: int, plate_set_type: int = 0, sec_types: list[int] | None = None, include_delisted: bool = False) -> list[Any]:
+        """Every listed security of a market, gathered from the securities of
+        its plates (see `quote::universe`). Takes one Qot_GetPlateSecurity
+        request per plate, which OpenD limits to 10 per 30 seconds; enable
+        the rate limit to pace them. Returns a list of dicts sorted by
+        security with market, code, alias, name, lot_size, sec_type,
+        list_time, exch_type, delisted and plates (plate codes).
+        """
+    def get_reference(self, security: Security, reference_type: int) -> list[Any]:
+        """Get reference data (related securities) for a single security.
+        Returns list of static info dicts.
+        """
+    def get_owner_plate(self, securities: list[Security]) -> list[Any]:
+        """Get owner plates (sectors) for securities.
+        Returns list of dicts with security and plate_info_list.
+        """
+    def get_option_chain(self, owner: Security, begin_time: str, end_time: str, option_type: int | None = None, condition: int | None = None, index_option_type: int | None = None, data_filter: dict[str, float | None] | None = None) -> list[Any]:
+        """Get option chain for an underlying security.
+        `data_filter` narrows the chain by greeks and market data: a dict of
+        bounds keyed `<field>_min`/`<field>_max`, where field is one of
+        implied_volatility (percent), delta, gamma, vega, theta, rho,
+        net_open_interest, open_interest and vol; None values are ignored.
+        Returns list of dicts with strike_time and option items.
+        """
+    def get_warrant(self, begin: int, num: int, sort_field: int, ascend: bool, owner: Security | None = None, type_list: list[int] | None = None, issuer_list: list[int] | None = None) -> Any:
+        """Get warrant list.
+        Returns a dict with last_page, all_count, and data list.
+        """
+    def get_capital_flow(self, security: Security, period_type: int | None = None) -> Any:
+        """Get capital flow for a single security.
+        Returns a dict with flow_item_list.
+        """
+    def download_capital_flow(self, securities: list[Security], begin_time: str, end_time: str, period_type: int = 2, chunk_days: int = 365, request_interval: float = 1.0) -> Any:
+        """Download daily (period_type=2), weekly (3) or monthly (4) capital
+        flow of several securities between begin_time and end_time
+        ("YYYY-MM-DD"), in chunks of chunk_days with request_interval seconds
+        between requests. Returns a dict with rows (one merged table, by
+        security then time), failed (securities whose download failed, with
+        error and the begin_time to download them again from) and requests.
+        """
+    def get_capital_distribution(self, security: Security) -> Any:
+        """Get capital distribution for a single security.
+        Returns a dict with capital in/out fields.
+        """
+    def get_user_security(self, group_name: str) -> list[Any]:
+        """Get user security group.
+        Returns list of static info dicts.
+        """
+    def modify_user_security(self, group_name: str, op: int, securities: list[Security]) -> Any:
+        """Modify user security group.
+        Returns an empty dict (S2C has no fields).
+        """
+    def set_price_reminder(self, security: Security, op: int, key: int | None = None, reminder_type: int | None = None, value: float | None = None, freq: int | None = None, note: str | None = None, sessions: list[int] | None = None) -> int:
+        """Add, modify, enable, disable or delete a price reminder kept by OpenD.
+        op: 1=add, 2=delete, 3=enable, 4=disable, 5=modify, 6=delete all of
+        the security. key is required by every op but add and delete all.
+        reminder_type and value are required by add and modify; values of
+        percentage types are in percent (20 for 20%).
+        freq: PriceReminderFreq; sessions: PriceReminderMarketStatus values.
+        Returns the key of the reminder. Triggered reminders arrive as pushes
+        of proto 3019 on `start_push()` channels.
+        """
+    def get_price_reminder(self, security: Security | None = None, market: int | None = None) -> list[Any]:
+        """Price reminders of a security, or of every security of market
+        (QotMarket) when no security is given. Returns a list of dicts with
+        market, code, alias, name, key, reminder_type, value, note, freq,
+        is_enable and sessions, one per reminder.
+        """
+    def get_code_change(self, securities: list[Security], type_list: list[int] | None = None) -> list[Any]:
+        """Get code change info for securities.
+        Returns list of dicts with code change details.
+        """
+    def get_ipo_list(self, market: int) -> list[Any]:
+        """Get IPO list for a market.
+        Returns list of dicts with IPO data.
+        """
+    def get_future_info(self, securities: list[Security]) -> list[Any]:
+        """Get future info for securities.
+        Returns list of dicts with future contract details.
+        """
+    def request_trade_date(self, market: int, begin_time: str, end_time: str, security: Security | None = None) -> list[Any]:
+        """Request trade dates for a market.
+        Returns list of dicts with trade date info.
+        """
+    def session_times(self, market: int, date: str) -> Any:
+        """Sessions of QotMarket `market` on `date` ("YYYY-MM-DD"), with
+        half days from the exchange calendar where OpenD has one. Returns a
+        dict with date, trade_date_type ("whole", "morning", "afternoon" or
+        None on a non-trading day), timezone, sessions (list of (open, close)
+        "HH:MM" tuples in exchange local time) and exchange_calendar.
+        """
+    def get_option_expiration_date(self, owner: Security, index_option_type: int | None = None) -> list[Any]:
+        """Get option expiration dates for an underlying security.
+        Returns list of dicts with expiration date info.
+        """
+    def get_global_state(self) -> Any:
+        """Get global state from Futu OpenD (proto 1002).
+        Returns a dict with market states and connection info.
+        """
+    def start_market_session(self, interval_secs: float = 30.0) -> None:
+        """Poll GetGlobalState every interval_secs on the current connection
+        and report market state changes to `poll_market_events()`. Does
+        nothing if the poller already runs; it stops on disconnect.
+        """
+    def refresh_market_session(self) -> list[Any]:
+        """Query GetGlobalState now and apply it to the market states.
+        Returns the changes, in the `poll_market_events()` layout; they are
+        also reported to `poll_market_events()`.
+        """
+    def market_states(self) -> Any:
+        """Latest market states, keyed by market ("hk", "us", "sh", "sz",
+        "hk_future", "us_future", "sg_future", "jp_future"). Each value is a
+        dict with state (Qot_Common.QotMarketState) and phase ("pre_open",
+        "open", "break", "closing", "extended" or "closed"). Empty until the
+        first refresh.
+        """
+    def poll_market_events(self, timeout_ms: int = 100, max_events: int = 256) -> list[Any]:
+        """Poll up to `max_events` market state changes of the poller started
+        by `start_market_session()`. Waits up to timeout_ms for the first
+        event, then takes whatever else is queued. Each event is a dict with
+        market, prev_state and prev_phase (None for the first answer), state,
+        phase and timestamp.
+        """
+    def get_user_info(self, flag: int | None = None) -> Any:
+        """Get the logged-in user's info from Futu OpenD (proto 1005).
+        Returns a dict with the API level, quote rights per market
+        (Qot_Common.QotRight values) and subscription/history K-line quotas.
+        """
+
+class PyFutuClientPool:
+    def __init__(self) -> None: ...
+    def connect(self, endpoints: list[tuple[str, int]], client_id: str = "nautilus_futu", client_ver: int = 100, strategy: str = "least_loaded", sub_quota: int | None = None, mock: bool = False) -> None:
+        """Connect one client per (host, port) endpoint. strategy routes
+        securities to connections: "least_loaded" (most quota left, then
+        fewest subscriptions), "round_robin" or "hash" (by market and code).
+        sub_quota is checked locally per connection. With mock=True every
+        connection goes to one in-process mock OpenD.
+        """
+    def is_connected(self) -> bool: ...
+    def __len__(self) -> int: ...
+    def subscribe(self, securities: list[tuple[int, str]], sub_types: list[int], is_sub: bool = True, order_book_detail: bool = False, reg_push: bool = True, first_push: bool | None = None, push_rehab_types: list[int] | None = None, session: int | None = None) -> list[Any]:
+        """Subscribe (or unsubscribe) securities, each on the connection the
+        pool routes it to. Returns one dict per security with market, code,
+        ok, error and connection (index of the connection serving it, None
+        once it is no longer subscribed). reg_push, first_push,
+        push_rehab_types and session as in `PyFutuClient.subscribe()`.
+        """
+    def unsubscribe_all(self) -> list[int]:
+        """Drop every quote subscription of every connection and forget the
+        routes. Returns the pairs released per connection.
+        """
+    def route_of(self, market: int, code: str) -> int | None:
+        """Index of the connection serving (market, code), or None if the
+        pool does not hold a subscription of it.
+        """
+    def loads(self) -> list[Any]:
+        """Load per connection: list of dicts with index, subscriptions,
+        remaining_quota and pending_requests.
+        """
+    def start_push(self, proto_ids: list[int]) -> None:
+        """Forward pushes of `proto_ids` from every connection to one queue
+        read by `poll_push()`. Calling it again adds proto_ids to the queue.
+        """
+    def poll_push(self, timeout_ms: int = 100) -> Any | None:
+        """Next push of any connection as a dict with proto_id and data, in the
+        layout of `PyFutuClient.poll_push()`; None on timeout.
+        """
+    def mock_push(self, proto_id: int, body: bytes) -> int:
+        """Send a push of `proto_id` with `body` from the mock OpenD of
+        `connect(mock=True)`. Returns the number of connections it reached.
+        """
+    def disconnect(self) -> None:
+        """Disconnect every connection of the pool."""
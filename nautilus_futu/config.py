@@ -19,7 +19,7 @@ class FutuDataClientConfig(LiveDataClientConfig, frozen=True):
     client_ver : int, default 100
         Client version number.
     rsa_key_path : str | None, default None
-        Path to RSA private key for encrypted connections.
+        PEM private key configured in OpenD; the handshake is then RSA-encrypted.
     instrument_provider : InstrumentProviderConfig
         The instrument provider configuration.
     rehab_type : int, default 1
@@ -28,6 +28,56 @@ class FutuDataClientConfig(LiveDataClientConfig, frozen=True):
         Whether to auto-reconnect on connection loss.
     reconnect_interval : float, default 5.0
         Seconds to wait between reconnection attempts.
+    disconnect_grace_period : float, default 5.0
+        Seconds requests in flight are given to be answered on disconnect
+        before the connection is closed.
+    fallback_endpoints : tuple[tuple[str, int], ...], default ()
+        Additional (host, port) OpenD endpoints tried in order when the
+        primary endpoint is unreachable, on connect and reconnect.
+    subscription_profiles : dict[str, tuple[tuple[tuple[int, str], ...], tuple[int, ...]]] | None, default None
+        Named subscription profiles as ``name -> (securities, sub_types)``,
+        where securities are (market, code) pairs. Defined on connect.
+    active_profiles : tuple[str, ...], default ()
+        Profile names activated on connect and replayed on reconnect.
+    language : str | None, default None
+        Preferred language for security names. OpenD has no locale setting per
+        connection, so names are translated from ``security_names``.
+    security_names : dict[str, dict[str, str]] | None, default None
+        Security names per language as ``language -> {instrument_id: name}``,
+        e.g. ``{"en": {"00700.HKEX": "Tencent"}}``. Loaded on connect.
+    partial_bars : str, default "skip"
+        Bars aggregated from ticker or order book pushes (bar types without a
+        Futu K-line, e.g. 5-MINUTE-MID or 10-SECOND-LAST) that do not cover
+        their whole interval: "skip" drops them, "emit" publishes them. This
+        applies to the first bar after subscribing and the last one on
+        unsubscribing.
+    decode_mode : str, default "lenient"
+        How OpenD messages lacking required fields are decoded: "lenient"
+        fills in defaults and logs a warning, "strict" rejects the message.
+        Lenient decoding tolerates OpenD versions whose messages differ.
+    gateway_log_dir : str | None, default None
+        OpenD's log directory. Requests OpenD rejects are then reported with
+        the lines OpenD logged for them (``client.gateway_rejections()``).
+    decode_push_on_worker : bool, default False
+        Parse quote pushes on the Rust runtime as they arrive instead of in
+        the push loop, so each poll only builds Python objects. Smooths poll
+        latency in bursty markets.
+    response_cache : bool, default False
+        Answer repeated reference data requests (static info, plate sets,
+        trading calendars, ...) from a cache instead of OpenD while they are
+        fresh. Snapshots and other quotes are never cached.
+    response_cache_ttls : dict[int, float] | None, default None
+        Cache TTL in seconds per proto_id, overriding the defaults; 0 stops
+        caching a protocol.
+    order_book_detail : bool, default False
+        Subscribe order books with per-order detail, attached to each level
+        as ``details``. Only SF quotes (HK futures and stocks with the SF
+        right) carry detail; other books are pushed without it.
+    dedup_pushes : bool, default False
+        Drop pushes already delivered, such as the latest quote OpenD pushes
+        again when overlapping subscription profiles subscribe a security
+        twice. Suppressed pushes are counted by
+        ``client.suppressed_duplicates()``.
     """
 
     host: str = "127.0.0.1"
@@ -39,6 +89,20 @@ class FutuDataClientConfig(LiveDataClientConfig, frozen=True):
     rehab_type: int = 1
     reconnect: bool = True
     reconnect_interval: float = 5.0
+    disconnect_grace_period: float = 5.0
+    fallback_endpoints: tuple[tuple[str, int], ...] = ()
+    subscription_profiles: dict[str, tuple[tuple[tuple[int, str], ...], tuple[int, ...]]] | None = None
+    active_profiles: tuple[str, ...] = ()
+    language: str | None = None
+    security_names: dict[str, dict[str, str]] | None = None
+    partial_bars: str = "skip"
+    decode_mode: str = "lenient"
+    gateway_log_dir: str | None = None
+    decode_push_on_worker: bool = False
+    response_cache: bool = False
+    response_cache_ttls: dict[int, float] | None = None
+    order_book_detail: bool = False
+    dedup_pushes: bool = False
 
 
 class FutuExecClientConfig(LiveExecClientConfig, frozen=True):
@@ -55,7 +119,7 @@ class FutuExecClientConfig(LiveExecClientConfig, frozen=True):
     client_ver : int, default 100
         Client version number.
     rsa_key_path : str | None, default None
-        Path to RSA private key for encrypted connections.
+        PEM private key configured in OpenD; the handshake is then RSA-encrypted.
     instrument_provider : InstrumentProviderConfig
         The instrument provider configuration.
     trd_env : int, default 0
@@ -66,10 +130,57 @@ class FutuExecClientConfig(LiveExecClientConfig, frozen=True):
         Trading market: 1=HK, 2=US, 3=CN, etc.
     unlock_pwd_md5 : str, default ""
         MD5 hash of trading unlock password (required for real trading).
+    security_firm : int | None, default None
+        Security firm used to unlock trading: 1=FutuSecurities, 2=FutuInc,
+        3=FutuSG, etc. None detects it from the trading account.
     reconnect : bool, default True
         Whether to auto-reconnect on connection loss.
     reconnect_interval : float, default 5.0
         Seconds to wait between reconnection attempts.
+    disconnect_grace_period : float, default 5.0
+        Seconds requests in flight are given to be answered on disconnect
+        before the connection is closed.
+    fallback_endpoints : tuple[tuple[str, int], ...], default ()
+        Additional (host, port) OpenD endpoints tried in order when the
+        primary endpoint is unreachable, on connect and reconnect.
+    pause_trading_on_preempt : bool, default False
+        Reject new orders and modifications while another login of the same
+        Futu account has preempted the session (OpenD kicked out or quote
+        rights taken over), until the session recovers.
+    decode_mode : str, default "lenient"
+        How OpenD messages lacking required fields are decoded: "lenient"
+        fills in defaults and logs a warning, "strict" rejects the message.
+        Lenient decoding tolerates OpenD versions whose messages differ.
+    gateway_log_dir : str | None, default None
+        OpenD's log directory. Requests OpenD rejects are then reported with
+        the lines OpenD logged for them (``client.gateway_rejections()``).
+    order_entry_limit : int | None, default None
+        New orders allowed per ``order_throttle_window`` and account before
+        further orders are delayed. None leaves new orders unthrottled.
+    order_cancel_limit : int | None, default None
+        Cancels and modifications allowed per ``order_throttle_window`` and
+        account, budgeted separately from new orders so cancels are never
+        starved by entries. None leaves them unthrottled.
+    order_cancel_reserve : int, default 0
+        Part of ``order_cancel_limit`` that only cancels may use;
+        modifications are delayed once the remaining budget reaches it.
+    order_throttle_window : float, default 30.0
+        Seconds over which the order budgets refill.
+    enable_funds_protocols : bool, default False
+        Allow the cash flow protocol (``get_cash_flow``). OpenAPI has no
+        funds transfer, cash sweep or currency exchange protocol; see
+        ``funds_capabilities()``.
+    auto_round_orders : bool, default False
+        Round order quantities down to whole lots and limit prices onto the
+        security's tick table (buys down, sells up) before submitting,
+        instead of letting OpenD reject them.
+    unlock_keyring : tuple[str, str] | None, default None
+        ``(service, username)`` of a system keyring entry holding the MD5
+        unlock password, read with the ``keyring`` package when
+        ``unlock_pwd_md5`` is empty.
+    auto_unlock : bool, default False
+        When OpenD rejects an order because trading is locked, unlock with
+        the configured password and resubmit it once. Each unlock is logged.
     """
 
     host: str = "127.0.0.1"
@@ -82,5 +193,19 @@ class FutuExecClientConfig(LiveExecClientConfig, frozen=True):
     acc_id: int = 0
     trd_market: int = 1
     unlock_pwd_md5: str = ""
+    security_firm: int | None = None
     reconnect: bool = True
     reconnect_interval: float = 5.0
+    disconnect_grace_period: float = 5.0
+    fallback_endpoints: tuple[tuple[str, int], ...] = ()
+    pause_trading_on_preempt: bool = False
+    decode_mode: str = "lenient"
+    gateway_log_dir: str | None = None
+    order_entry_limit: int | None = None
+    order_cancel_limit: int | None = None
+    order_cancel_reserve: int = 0
+    order_throttle_window: float = 30.0
+    enable_funds_protocols: bool = False
+    auto_round_orders: bool = False
+    unlock_keyring: tuple[str, str] | None = None
+    auto_unlock: bool = False
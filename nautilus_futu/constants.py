@@ -90,6 +90,7 @@ FUTU_SUB_TYPE_KL_15MIN = 8
 FUTU_SUB_TYPE_KL_30MIN = 9
 FUTU_SUB_TYPE_KL_60MIN = 10
 FUTU_SUB_TYPE_KL_1MIN = 11
+FUTU_SUB_TYPE_ORDER_DETAIL = 18
 
 # Futu KLType values
 FUTU_KL_TYPE_1MIN = 1
@@ -193,5 +194,9 @@ FUTU_PROTO_BASIC_QOT = 3005
 FUTU_PROTO_KL = 3007
 FUTU_PROTO_TICKER = 3011
 FUTU_PROTO_ORDER_BOOK = 3013
+FUTU_PROTO_PRICE_REMINDER = 3019
 FUTU_PROTO_TRD_ORDER = 2208
 FUTU_PROTO_TRD_FILL = 2218
+
+# Futu request protocol IDs
+FUTU_PROTO_TRD_PLACE_ORDER = 2202
@@ -10,6 +10,7 @@ from nautilus_trader.common.component import LiveClock, MessageBus
 from nautilus_trader.data.messages import RequestBars
 from nautilus_trader.live.data_client import LiveMarketDataClient
 from nautilus_trader.model.data import BarType
+from nautilus_trader.model.enums import PriceType
 from nautilus_trader.model.identifiers import ClientId, InstrumentId
 
 from nautilus_futu.common import (
@@ -78,14 +79,24 @@ class FutuLiveDataClient(LiveMarketDataClient):
         self._subscribed_quote_ticks: set[InstrumentId] = set()
         self._subscribed_trade_ticks: set[InstrumentId] = set()
         self._subscribed_order_books: set[InstrumentId] = set()
+        # Requested levels per book (0 = all OpenD pushes) and books already
+        # warned about having fewer levels than requested
+        self._order_book_depths: dict[InstrumentId, int] = {}
+        self._shallow_order_books: set[InstrumentId] = set()
         self._subscribed_bars: set[BarType] = set()
+        self._aggregated_bars: set[BarType] = set()
         self._push_task: asyncio.Task | None = None
         self._push_channel_id: int | None = None
+        self._bar_channel_id: int | None = None
 
     async def _connect(self) -> None:
         """Connect to Futu OpenD."""
         self._log.info("Connecting to Futu OpenD...")
         try:
+            if self._config.response_cache:
+                self._client.set_response_cache(True, ttls=self._config.response_cache_ttls)
+            if self._config.dedup_pushes:
+                self._client.set_dedup_pushes(True)
             async with self._connect_lock:
                 # Skip connect if already connected (shared client)
                 if not self._client.is_connected():
@@ -95,16 +106,27 @@ class FutuLiveDataClient(LiveMarketDataClient):
                         self._config.port,
                         self._config.client_id,
                         self._config.client_ver,
+                        list(self._config.fallback_endpoints),
+                        decode_mode=self._config.decode_mode,
+                        gateway_log_dir=self._config.gateway_log_dir,
+                        rsa_key_path=self._config.rsa_key_path,
                     )
                     self._log.info("Connected to Futu OpenD")
                 else:
                     self._log.info("Reusing existing Futu OpenD connection")
 
+            self._load_security_names()
             await self._instrument_provider.initialize()
+            await self._activate_profiles()
 
             self._push_channel_id = await asyncio.to_thread(
                 self._client.start_push,
                 [FUTU_PROTO_BASIC_QOT, FUTU_PROTO_TICKER, FUTU_PROTO_ORDER_BOOK, FUTU_PROTO_KL],
+                self._config.decode_push_on_worker,
+                self._config.order_book_detail,
+            )
+            self._bar_channel_id = await asyncio.to_thread(
+                self._client.start_bar_push, self._config.partial_bars,
             )
             self._push_task = self.create_task(self._run_push_loop())
             self._log.info(f"Push loop started (channel_id={self._push_channel_id})")
@@ -112,6 +134,33 @@ class FutuLiveDataClient(LiveMarketDataClient):
             self._log.error(f"Failed to connect to Futu OpenD: {e}")
             raise
 
+    def _load_security_names(self) -> None:
+        """Load configured security name translations and set the preferred language."""
+        for language, names in (self._config.security_names or {}).items():
+            entries = []
+            for instrument_id, name in names.items():
+                market, code = instrument_id_to_futu_security(InstrumentId.from_str(instrument_id))
+                entries.append((market, code, name))
+            self._client.add_security_names(language, entries)
+        if self._config.language is not None:
+            self._client.set_language(self._config.language)
+            self._log.info(f"Security name language set to '{self._config.language}'")
+
+    async def _activate_profiles(self) -> None:
+        """Define configured subscription profiles and activate the requested ones."""
+        for name, (securities, sub_types) in (self._config.subscription_profiles or {}).items():
+            self._client.define_profile(
+                name,
+                [tuple(sec) for sec in securities],
+                list(sub_types),
+            )
+        for name in self._config.active_profiles:
+            try:
+                await asyncio.to_thread(self._client.activate_profile, name)
+                self._log.info(f"Activated subscription profile '{name}'")
+            except Exception as e:
+                self._log.error(f"Failed to activate subscription profile '{name}': {e}")
+
     async def _disconnect(self) -> None:
         """Disconnect from Futu OpenD."""
         self._log.info("Disconnecting from Futu OpenD...")
@@ -123,11 +172,37 @@ class FutuLiveDataClient(LiveMarketDataClient):
                 pass
             self._push_task = None
         try:
-            await asyncio.to_thread(self._client.disconnect)
+            await asyncio.to_thread(
+                self._client.disconnect, self._config.disconnect_grace_period
+            )
             self._log.info("Disconnected from Futu OpenD")
         except Exception as e:
             self._log.error(f"Error disconnecting: {e}")
 
+    def _dispatch_push(self, proto_id: int, data: Any) -> None:
+        """Dispatch one decoded push message to its handler."""
+        try:
+            if proto_id == FUTU_PROTO_BASIC_QOT:
+                self._handle_push_basic_qot(data)
+            elif proto_id == FUTU_PROTO_TICKER:
+                self._handle_push_ticker(data)
+            elif proto_id == FUTU_PROTO_ORDER_BOOK:
+                self._handle_push_order_book(data)
+            elif proto_id == FUTU_PROTO_KL:
+                self._handle_push_kl(data)
+        except Exception as e:
+            self._log.error(f"Error handling push proto_id={proto_id}: {e}")
+
+    def _poll_aggregated_bars(self) -> None:
+        """Dispatch bars already built by the aggregation channel, without waiting."""
+        if self._bar_channel_id is None:
+            return
+        for data in self._client.poll_bars(self._bar_channel_id, 256, 0):
+            try:
+                self._handle_aggregated_bar(data)
+            except Exception as e:
+                self._log.error(f"Error handling aggregated bar {data.get('bar_spec')}: {e}")
+
     async def _run_push_loop(self) -> None:
         """Background loop that polls for push messages and dispatches them."""
         self._log.debug("Push loop running")
@@ -135,7 +210,9 @@ class FutuLiveDataClient(LiveMarketDataClient):
         try:
             while True:
                 try:
-                    msg = await asyncio.to_thread(self._client.poll_push, self._push_channel_id, 100)
+                    msgs = await asyncio.to_thread(
+                        self._client.poll_push_batch, self._push_channel_id, 256, 100,
+                    )
                     consecutive_errors = 0
                 except Exception as e:
                     consecutive_errors += 1
@@ -149,24 +226,15 @@ class FutuLiveDataClient(LiveMarketDataClient):
                         await asyncio.sleep(0.5)
                     continue
 
-                if msg is None:
+                # Bars also close on a timer, so poll them even without pushes
+                self._poll_aggregated_bars()
+
+                if not msgs:
                     await asyncio.sleep(0)  # yield to event loop
                     continue
 
-                proto_id = msg["proto_id"]
-                data = msg["data"]
-
-                try:
-                    if proto_id == FUTU_PROTO_BASIC_QOT:
-                        self._handle_push_basic_qot(data)
-                    elif proto_id == FUTU_PROTO_TICKER:
-                        self._handle_push_ticker(data)
-                    elif proto_id == FUTU_PROTO_ORDER_BOOK:
-                        self._handle_push_order_book(data)
-                    elif proto_id == FUTU_PROTO_KL:
-                        self._handle_push_kl(data)
-                except Exception as e:
-                    self._log.error(f"Error handling push proto_id={proto_id}: {e}")
+                for msg in msgs:
+                    self._dispatch_push(msg["proto_id"], msg["data"])
         except asyncio.CancelledError:
             self._log.debug("Push loop cancelled")
 
@@ -187,10 +255,19 @@ class FutuLiveDataClient(LiveMarketDataClient):
                 self._config.port,
                 self._config.client_id,
                 self._config.client_ver,
+                list(self._config.fallback_endpoints),
+                decode_mode=self._config.decode_mode,
+                gateway_log_dir=self._config.gateway_log_dir,
+                rsa_key_path=self._config.rsa_key_path,
             )
             self._push_channel_id = await asyncio.to_thread(
                 self._client.start_push,
                 [FUTU_PROTO_BASIC_QOT, FUTU_PROTO_TICKER, FUTU_PROTO_ORDER_BOOK, FUTU_PROTO_KL],
+                self._config.decode_push_on_worker,
+                self._config.order_book_detail,
+            )
+            self._bar_channel_id = await asyncio.to_thread(
+                self._client.start_bar_push, self._config.partial_bars,
             )
             # Re-subscribe all previously subscribed instruments
             await self._restore_subscriptions()
@@ -200,7 +277,10 @@ class FutuLiveDataClient(LiveMarketDataClient):
 
     async def _restore_subscriptions(self) -> None:
         """Re-subscribe all instruments after reconnection."""
-        from nautilus_futu.parsing.market_data import bar_spec_to_futu_sub_type
+        from nautilus_futu.parsing.market_data import (
+            bar_spec_to_aggregation_sub_type,
+            bar_spec_to_futu_sub_type,
+        )
 
         for instrument_id in self._subscribed_quote_ticks:
             market, code = instrument_id_to_futu_security(instrument_id)
@@ -225,6 +305,7 @@ class FutuLiveDataClient(LiveMarketDataClient):
             try:
                 await asyncio.to_thread(
                     self._client.subscribe, [(market, code)], [FUTU_SUB_TYPE_ORDER_BOOK], True,
+                    order_book_detail=self._config.order_book_detail,
                 )
             except Exception as e:
                 self._log.warning(f"Failed to re-subscribe order book for {instrument_id}: {e}")
@@ -241,9 +322,25 @@ class FutuLiveDataClient(LiveMarketDataClient):
                 except Exception as e:
                     self._log.warning(f"Failed to re-subscribe bars for {bar_type}: {e}")
 
+        for bar_type in self._aggregated_bars:
+            market, code = instrument_id_to_futu_security(bar_type.instrument_id)
+            try:
+                await asyncio.to_thread(
+                    self._client.subscribe,
+                    [(market, code)],
+                    [bar_spec_to_aggregation_sub_type(bar_type.spec)],
+                    True,
+                )
+                await asyncio.to_thread(
+                    self._client.add_bar_spec, self._bar_channel_id, (market, code), str(bar_type.spec),
+                )
+            except Exception as e:
+                self._log.warning(f"Failed to re-subscribe aggregated bars for {bar_type}: {e}")
+
         total = (
             len(self._subscribed_quote_ticks) + len(self._subscribed_trade_ticks)
             + len(self._subscribed_order_books) + len(self._subscribed_bars)
+            + len(self._aggregated_bars)
         )
         if total > 0:
             self._log.info(f"Restored {total} subscriptions after reconnection")
@@ -286,8 +383,17 @@ class FutuLiveDataClient(LiveMarketDataClient):
         if instrument_id not in self._subscribed_order_books:
             return
 
+        depth = self._order_book_depths.get(instrument_id, 0)
+        available = data.get("depth")
+        if depth and available is not None and available < depth and instrument_id not in self._shallow_order_books:
+            self._shallow_order_books.add(instrument_id)
+            self._log.warning(
+                f"Order book for {instrument_id} has {available} of the {depth} levels requested; "
+                "the quote right for the market may not cover more"
+            )
+
         ts_init = self._clock.timestamp_ns()
-        deltas = parse_push_order_book(data, instrument_id, ts_init)
+        deltas = parse_push_order_book(data, instrument_id, ts_init, depth=depth or None)
         self._handle_data(deltas)
 
     def _handle_push_kl(self, data: dict) -> None:
@@ -315,6 +421,23 @@ class FutuLiveDataClient(LiveMarketDataClient):
         for bar in bars:
             self._handle_data(bar)
 
+    def _handle_aggregated_bar(self, data: dict) -> None:
+        """Handle a bar from the aggregation channel."""
+        from nautilus_futu.parsing.market_data import parse_futu_aggregated_bar
+
+        instrument_id = futu_security_to_instrument_id(data["market"], data["code"])
+        bar_type = BarType.from_str(f"{instrument_id}-{data['bar_spec']}-EXTERNAL")
+
+        instrument = self._cache.instrument(instrument_id)
+        price_precision = None
+        if instrument is not None:
+            price_precision = instrument.price_precision
+            if bar_type.spec.price_type == PriceType.MID:
+                price_precision += 1
+
+        bar = parse_futu_aggregated_bar(data, bar_type, self._clock.timestamp_ns(), price_precision)
+        self._handle_data(bar)
+
     async def _subscribe_quote_ticks(self, command) -> None:
         """Subscribe to quote tick updates."""
         instrument_id = getattr(command, "instrument_id", command)
@@ -348,7 +471,12 @@ class FutuLiveDataClient(LiveMarketDataClient):
             self._log.error(f"Failed to subscribe trade ticks for {instrument_id}: {e}")
 
     async def _subscribe_order_book_deltas(self, command) -> None:
-        """Subscribe to order book updates."""
+        """Subscribe to order book updates.
+
+        Futu has no depth parameter: OpenD pushes as many levels as the quote
+        right for the market allows. The command's depth truncates the pushed
+        book; a book with fewer levels than requested is logged once.
+        """
         instrument_id = getattr(command, "instrument_id", command)
         market, code = instrument_id_to_futu_security(instrument_id)
         try:
@@ -357,22 +485,32 @@ class FutuLiveDataClient(LiveMarketDataClient):
                 [(market, code)],
                 [FUTU_SUB_TYPE_ORDER_BOOK],
                 True,
+                order_book_detail=self._config.order_book_detail,
             )
+            self._order_book_depths[instrument_id] = getattr(command, "depth", 0) or 0
+            self._shallow_order_books.discard(instrument_id)
             self._subscribed_order_books.add(instrument_id)
             self._log.info(f"Subscribed to order book for {instrument_id}")
         except Exception as e:
             self._log.error(f"Failed to subscribe order book for {instrument_id}: {e}")
 
     async def _subscribe_bars(self, command) -> None:
-        """Subscribe to bar updates."""
-        from nautilus_futu.parsing.market_data import bar_spec_to_futu_sub_type
+        """Subscribe to bar updates.
+
+        LAST bars with a Futu K-line period use K-line pushes. Other SECOND,
+        MINUTE and HOUR bars are aggregated from ticker or order book pushes.
+        """
+        from nautilus_futu.parsing.market_data import (
+            bar_spec_to_aggregation_sub_type,
+            bar_spec_to_futu_sub_type,
+        )
 
         bar_type = getattr(command, "bar_type", command)
         instrument_id = bar_type.instrument_id
         market, code = instrument_id_to_futu_security(instrument_id)
         sub_type = bar_spec_to_futu_sub_type(bar_type.spec)
 
-        if sub_type is not None:
+        if sub_type is not None and bar_type.spec.price_type == PriceType.LAST:
             try:
                 await asyncio.to_thread(
                     self._client.subscribe,
@@ -384,8 +522,27 @@ class FutuLiveDataClient(LiveMarketDataClient):
                 self._log.info(f"Subscribed to bars for {bar_type}")
             except Exception as e:
                 self._log.error(f"Failed to subscribe bars for {bar_type}: {e}")
-        else:
+            return
+
+        agg_sub_type = bar_spec_to_aggregation_sub_type(bar_type.spec)
+        if agg_sub_type is None or self._bar_channel_id is None:
             self._log.warning(f"Unsupported bar type: {bar_type.spec}")
+            return
+
+        try:
+            await asyncio.to_thread(
+                self._client.subscribe,
+                [(market, code)],
+                [agg_sub_type],
+                True,
+            )
+            await asyncio.to_thread(
+                self._client.add_bar_spec, self._bar_channel_id, (market, code), str(bar_type.spec),
+            )
+            self._aggregated_bars.add(bar_type)
+            self._log.info(f"Subscribed to aggregated bars for {bar_type}")
+        except Exception as e:
+            self._log.error(f"Failed to subscribe bars for {bar_type}: {e}")
 
     async def _unsubscribe_quote_ticks(self, command) -> None:
         """Unsubscribe from quote tick updates."""
@@ -406,6 +563,10 @@ class FutuLiveDataClient(LiveMarketDataClient):
         """Unsubscribe from trade tick updates."""
         instrument_id = getattr(command, "instrument_id", command)
         market, code = instrument_id_to_futu_security(instrument_id)
+        if self._aggregation_uses(instrument_id, FUTU_SUB_TYPE_TICKER):
+            # Aggregated bars still need the pushes
+            self._subscribed_trade_ticks.discard(instrument_id)
+            return
         try:
             await asyncio.to_thread(
                 self._client.subscribe,
@@ -421,6 +582,11 @@ class FutuLiveDataClient(LiveMarketDataClient):
         """Unsubscribe from order book updates."""
         instrument_id = getattr(command, "instrument_id", command)
         market, code = instrument_id_to_futu_security(instrument_id)
+        self._order_book_depths.pop(instrument_id, None)
+        if self._aggregation_uses(instrument_id, FUTU_SUB_TYPE_ORDER_BOOK):
+            # Aggregated bars still need the pushes
+            self._subscribed_order_books.discard(instrument_id)
+            return
         try:
             await asyncio.to_thread(
                 self._client.subscribe,
@@ -434,11 +600,34 @@ class FutuLiveDataClient(LiveMarketDataClient):
 
     async def _unsubscribe_bars(self, command) -> None:
         """Unsubscribe from bar updates."""
-        from nautilus_futu.parsing.market_data import bar_spec_to_futu_sub_type
+        from nautilus_futu.parsing.market_data import (
+            bar_spec_to_aggregation_sub_type,
+            bar_spec_to_futu_sub_type,
+        )
 
         bar_type = getattr(command, "bar_type", command)
         instrument_id = bar_type.instrument_id
         market, code = instrument_id_to_futu_security(instrument_id)
+
+        if bar_type in self._aggregated_bars:
+            self._aggregated_bars.discard(bar_type)
+            agg_sub_type = bar_spec_to_aggregation_sub_type(bar_type.spec)
+            try:
+                await asyncio.to_thread(
+                    self._client.remove_bar_spec, self._bar_channel_id, (market, code), str(bar_type.spec),
+                )
+                # Keep the ticker/order book subscription while anything else uses it
+                if not self._push_sub_type_in_use(instrument_id, agg_sub_type):
+                    await asyncio.to_thread(
+                        self._client.subscribe,
+                        [(market, code)],
+                        [agg_sub_type],
+                        False,
+                    )
+            except Exception as e:
+                self._log.error(f"Failed to unsubscribe bars for {bar_type}: {e}")
+            return
+
         sub_type = bar_spec_to_futu_sub_type(bar_type.spec)
 
         if sub_type is not None:
@@ -453,6 +642,24 @@ class FutuLiveDataClient(LiveMarketDataClient):
             except Exception as e:
                 self._log.error(f"Failed to unsubscribe bars for {bar_type}: {e}")
 
+    def _push_sub_type_in_use(self, instrument_id: InstrumentId, sub_type: int) -> bool:
+        """Whether tick, order book or aggregated bar subscriptions need ``sub_type``."""
+        if sub_type == FUTU_SUB_TYPE_TICKER and instrument_id in self._subscribed_trade_ticks:
+            return True
+        if sub_type == FUTU_SUB_TYPE_ORDER_BOOK and instrument_id in self._subscribed_order_books:
+            return True
+        return self._aggregation_uses(instrument_id, sub_type)
+
+    def _aggregation_uses(self, instrument_id: InstrumentId, sub_type: int) -> bool:
+        """Whether aggregated bars of the instrument are built from ``sub_type`` pushes."""
+        from nautilus_futu.parsing.market_data import bar_spec_to_aggregation_sub_type
+
+        return any(
+            bar_type.instrument_id == instrument_id
+            and bar_spec_to_aggregation_sub_type(bar_type.spec) == sub_type
+            for bar_type in self._aggregated_bars
+        )
+
     async def _request_instrument(self, request) -> None:
         """Request a single instrument definition."""
         from nautilus_futu.parsing.instruments import parse_futu_instrument
@@ -505,7 +712,7 @@ class FutuLiveDataClient(LiveMarketDataClient):
         max_ret = limit if limit and limit > 0 else 100
         try:
             result = await asyncio.to_thread(
-                self._client.get_ticker, market, code, max_ret
+                self._client.get_ticker, (market, code), max_ret
             )
             ts_init = self._clock.timestamp_ns()
             ticks = []
@@ -542,8 +749,7 @@ class FutuLiveDataClient(LiveMarketDataClient):
         try:
             result = await asyncio.to_thread(
                 self._client.get_history_kl,
-                market,
-                code,
+                (market, code),
                 self._config.rehab_type,
                 kl_type,
                 begin_str,
@@ -3,6 +3,7 @@
 from __future__ import annotations
 
 import asyncio
+import time
 from typing import Any
 
 from nautilus_trader.cache.cache import Cache
@@ -37,12 +38,9 @@ from nautilus_futu.common import (
 )
 from nautilus_futu.config import FutuExecClientConfig
 from nautilus_futu.constants import (
-    FUTU_ORDER_STATUS_FILLED_PART,
-    FUTU_ORDER_STATUS_SUBMITTED,
-    FUTU_ORDER_STATUS_SUBMITTING,
-    FUTU_ORDER_STATUS_WAITING_SUBMIT,
     FUTU_PROTO_TRD_FILL,
     FUTU_PROTO_TRD_ORDER,
+    FUTU_PROTO_TRD_PLACE_ORDER,
     FUTU_TRD_MARKET_CN,
     FUTU_TRD_MARKET_HK,
     FUTU_TRD_MARKET_HKCC,
@@ -105,6 +103,19 @@ def parse_funds_to_balance(funds: dict, currency: Currency) -> AccountBalance:
     return AccountBalance(total=total, locked=locked, free=free)
 
 
+def resolve_unlock_pwd_md5(config: FutuExecClientConfig) -> str:
+    """Return the unlock password MD5: ``unlock_pwd_md5`` if set, otherwise
+    the ``unlock_keyring`` entry, or "" when neither is available."""
+    if config.unlock_pwd_md5 or config.unlock_keyring is None:
+        return config.unlock_pwd_md5
+    try:
+        import keyring
+    except ImportError as e:
+        raise RuntimeError("unlock_keyring needs the keyring package") from e
+    service, username = config.unlock_keyring
+    return keyring.get_password(service, username) or ""
+
+
 class FutuLiveExecutionClient(LiveExecutionClient):
     """Provides an execution client for Futu OpenD.
 
@@ -158,11 +169,29 @@ class FutuLiveExecutionClient(LiveExecutionClient):
         self._push_task: asyncio.Task | None = None
         self._push_channel_id: int | None = None
         self._trd_market_auth_list: list[int] = [config.trd_market]
+        # Resolved on connect, from the config or the keyring
+        self._unlock_pwd_md5 = config.unlock_pwd_md5
 
     async def _connect(self) -> None:
         """Connect to Futu OpenD for trading."""
         self._log.info("Connecting execution client to Futu OpenD...")
         try:
+            if self._config.pause_trading_on_preempt:
+                self._client.set_pause_trading_on_preempt(True)
+            if self._config.enable_funds_protocols:
+                self._client.set_funds_protocols(True)
+            if self._config.auto_round_orders:
+                self._client.set_auto_round_orders(True)
+            self._unlock_pwd_md5 = resolve_unlock_pwd_md5(self._config)
+            if self._config.auto_unlock and self._unlock_pwd_md5:
+                self._client.set_unlock_credentials(self._unlock_pwd_md5, self._config.security_firm)
+            if self._config.order_entry_limit is not None or self._config.order_cancel_limit is not None:
+                self._client.set_order_throttle(
+                    self._config.order_entry_limit,
+                    self._config.order_cancel_limit,
+                    self._config.order_cancel_reserve,
+                    self._config.order_throttle_window,
+                )
             async with self._connect_lock:
                 # Skip connect if already connected (shared client)
                 if not self._client.is_connected():
@@ -172,6 +201,10 @@ class FutuLiveExecutionClient(LiveExecutionClient):
                         self._config.port,
                         self._config.client_id,
                         self._config.client_ver,
+                        list(self._config.fallback_endpoints),
+                        decode_mode=self._config.decode_mode,
+                        gateway_log_dir=self._config.gateway_log_dir,
+                        rsa_key_path=self._config.rsa_key_path,
                     )
                     self._log.info("Connected to Futu OpenD")
                 else:
@@ -221,11 +254,13 @@ class FutuLiveExecutionClient(LiveExecutionClient):
             await self._register_venue_account_aliases()
 
             # Unlock trade if password provided
-            if self._config.unlock_pwd_md5:
+            if self._unlock_pwd_md5:
                 await asyncio.to_thread(
                     self._client.unlock_trade,
                     True,
-                    self._config.unlock_pwd_md5,
+                    self._unlock_pwd_md5,
+                    self._config.security_firm,
+                    self._acc_id or None,
                 )
                 self._log.info("Trade unlocked")
 
@@ -356,12 +391,18 @@ class FutuLiveExecutionClient(LiveExecutionClient):
                 pass
             self._push_task = None
         try:
-            await asyncio.to_thread(self._client.disconnect)
+            await asyncio.to_thread(
+                self._client.disconnect, self._config.disconnect_grace_period
+            )
         except Exception as e:
             self._log.error(f"Error disconnecting execution client: {e}")
 
     async def _run_push_loop(self) -> None:
-        """Background loop polling for trade push messages."""
+        """Background loop polling for trade push messages.
+
+        The push channel holds back fills that arrive before the first order
+        update of their order, so an order is always accepted before it fills.
+        """
         self._log.debug("Execution push loop running")
         consecutive_errors = 0
         try:
@@ -415,13 +456,19 @@ class FutuLiveExecutionClient(LiveExecutionClient):
                 self._config.port,
                 self._config.client_id,
                 self._config.client_ver,
+                list(self._config.fallback_endpoints),
+                decode_mode=self._config.decode_mode,
+                gateway_log_dir=self._config.gateway_log_dir,
+                rsa_key_path=self._config.rsa_key_path,
             )
             # Re-unlock trade if password was configured
-            if self._config.unlock_pwd_md5:
+            if self._unlock_pwd_md5:
                 await asyncio.to_thread(
                     self._client.unlock_trade,
                     True,
-                    self._config.unlock_pwd_md5,
+                    self._unlock_pwd_md5,
+                    self._config.security_firm,
+                    self._acc_id or None,
                 )
                 self._log.info("Trade re-unlocked after reconnection")
 
@@ -574,6 +621,7 @@ class FutuLiveExecutionClient(LiveExecutionClient):
         price = float(order.price) if hasattr(order, "price") and order.price is not None else None
         qty = float(order.quantity)
         sec_market = VENUE_TO_FUTU_TRD_SEC_MARKET.get(instrument_id.venue)
+        submitted_at = time.time()
 
         try:
             self.generate_order_submitted(
@@ -583,18 +631,37 @@ class FutuLiveExecutionClient(LiveExecutionClient):
                 ts_event=self._clock.timestamp_ns(),
             )
 
-            result = await asyncio.to_thread(
-                self._client.place_order,
-                self._trd_env,
-                self._acc_id,
-                self._trd_market,
-                trd_side,
-                order_type,
-                code,
-                qty,
-                price,
-                sec_market,
-            )
+            if self._config.auto_unlock:
+                result = await asyncio.to_thread(
+                    self._client.place_order_autolock,
+                    trd_side,
+                    order_type,
+                    code,
+                    qty,
+                    price,
+                    sec_market,
+                    allow_unlock=True,
+                    trd_env=self._trd_env,
+                    acc_id=self._acc_id,
+                    trd_market=self._trd_market,
+                )
+                if result["unlocked"]:
+                    self._log.warning(f"Trading was locked; unlocked and resubmitted {order.client_order_id}")
+                if not result["ok"]:
+                    raise RuntimeError(result["error"])
+            else:
+                result = await asyncio.to_thread(
+                    self._client.place_order,
+                    trd_side,
+                    order_type,
+                    code,
+                    qty,
+                    price,
+                    sec_market,
+                    trd_env=self._trd_env,
+                    acc_id=self._acc_id,
+                    trd_market=self._trd_market,
+                )
 
             if result and "order_id" in result:
                 venue_order_id = VenueOrderId(str(result["order_id"]))
@@ -605,15 +672,30 @@ class FutuLiveExecutionClient(LiveExecutionClient):
                     f"Order submitted: {order.client_order_id} -> {venue_order_id}"
                 )
         except Exception as e:
-            self._log.error(f"Failed to submit order: {e}")
+            reason = str(e)
+            gateway_log = self._gateway_log_lines(FUTU_PROTO_TRD_PLACE_ORDER, submitted_at)
+            if gateway_log:
+                reason += " (OpenD log: " + " | ".join(gateway_log) + ")"
+            self._log.error(f"Failed to submit order: {reason}")
             self.generate_order_rejected(
                 strategy_id=order.strategy_id,
                 instrument_id=instrument_id,
                 client_order_id=order.client_order_id,
-                reason=str(e),
+                reason=reason,
                 ts_event=self._clock.timestamp_ns(),
             )
 
+    def _gateway_log_lines(self, proto_id: int, since: float) -> list[str]:
+        """OpenD log lines of the last request of ``proto_id`` rejected since ``since``."""
+        if self._config.gateway_log_dir is None:
+            return []
+        try:
+            rejections = self._client.gateway_rejections()
+        except Exception:
+            return []
+        matching = [r for r in rejections if r["proto_id"] == proto_id and r["timestamp"] >= since]
+        return matching[-1]["gateway_log"] if matching else []
+
     async def _modify_order(self, command: Any) -> None:
         """Modify an existing order."""
         order = command.order
@@ -637,13 +719,13 @@ class FutuLiveExecutionClient(LiveExecutionClient):
 
             await asyncio.to_thread(
                 self._client.modify_order,
-                self._trd_env,
-                self._acc_id,
-                self._trd_market,
                 int(venue_order_id.value),
                 1,  # ModifyOrderOp_Normal
                 qty,
                 price,
+                trd_env=self._trd_env,
+                acc_id=self._acc_id,
+                trd_market=self._trd_market,
             )
             self._log.info(f"Order modified: {venue_order_id}")
         except Exception as e:
@@ -669,13 +751,13 @@ class FutuLiveExecutionClient(LiveExecutionClient):
 
             await asyncio.to_thread(
                 self._client.modify_order,
-                self._trd_env,
-                self._acc_id,
-                self._trd_market,
                 int(venue_order_id.value),
                 2,  # ModifyOrderOp_Cancel
                 None,
                 None,
+                trd_env=self._trd_env,
+                acc_id=self._acc_id,
+                trd_market=self._trd_market,
             )
             self._log.info(f"Order cancelled: {venue_order_id}")
         except Exception as e:
@@ -842,42 +924,18 @@ class FutuLiveExecutionClient(LiveExecutionClient):
 
     async def _cancel_all_orders(self, command: Any) -> None:
         """Cancel all active orders across all authorized markets."""
-        active_statuses = {
-            FUTU_ORDER_STATUS_WAITING_SUBMIT,
-            FUTU_ORDER_STATUS_SUBMITTING,
-            FUTU_ORDER_STATUS_SUBMITTED,
-            FUTU_ORDER_STATUS_FILLED_PART,
-        }
-        cancelled = 0
         for market in self._trd_market_auth_list:
             try:
-                orders = await asyncio.to_thread(
-                    self._client.get_order_list,
+                result = await asyncio.to_thread(
+                    self._client.cancel_all_orders,
                     self._trd_env,
                     self._acc_id,
                     market,
                 )
             except Exception as e:
-                self._log.error(f"Failed to get order list (market={market}) for cancel all: {e}")
+                self._log.error(f"Failed to cancel all orders (market={market}): {e}")
                 continue
 
-            for order_dict in orders:
-                if order_dict["order_status"] in active_statuses:
-                    try:
-                        await asyncio.to_thread(
-                            self._client.modify_order,
-                            self._trd_env,
-                            self._acc_id,
-                            market,
-                            order_dict["order_id"],
-                            2,  # ModifyOrderOp_Cancel
-                            None,
-                            None,
-                        )
-                        cancelled += 1
-                    except Exception as e:
-                        self._log.warning(
-                            f"Failed to cancel order {order_dict['order_id']}: {e}"
-                        )
-
-        self._log.info(f"Cancelled {cancelled} orders")
+            for order_id, error in result["failed"]:
+                self._log.warning(f"Failed to cancel order {order_id}: {error}")
+            self._log.info(f"Cancelled all orders (market={market})")
@@ -39,6 +39,8 @@ from nautilus_futu.constants import (
     FUTU_SUB_TYPE_KL_30MIN,
     FUTU_SUB_TYPE_KL_60MIN,
     FUTU_SUB_TYPE_KL_DAY,
+    FUTU_SUB_TYPE_ORDER_BOOK,
+    FUTU_SUB_TYPE_TICKER,
     FUTU_TICKER_DIR_ASK,
     FUTU_TICKER_DIR_BID,
 )
@@ -96,6 +98,21 @@ def bar_spec_to_futu_kl_type(spec: BarSpecification) -> int | None:
     return None
 
 
+def bar_spec_to_aggregation_sub_type(spec: BarSpecification) -> int | None:
+    """Futu SubType whose pushes feed client-side aggregation of ``spec`` bars.
+
+    LAST bars are built from tickers and BID/ASK/MID bars from the order book.
+    Returns None for aggregations other than SECOND, MINUTE and HOUR.
+    """
+    if spec.aggregation not in (BarAggregation.SECOND, BarAggregation.MINUTE, BarAggregation.HOUR):
+        return None
+    if spec.price_type == PriceType.LAST:
+        return FUTU_SUB_TYPE_TICKER
+    if spec.price_type in (PriceType.BID, PriceType.ASK, PriceType.MID):
+        return FUTU_SUB_TYPE_ORDER_BOOK
+    return None
+
+
 def parse_futu_quote_tick(
     data: dict[str, Any],
     instrument_id: InstrumentId,
@@ -181,6 +198,35 @@ def parse_futu_bars(
     return bars
 
 
+def parse_futu_aggregated_bar(
+    data: dict[str, Any],
+    bar_type: BarType,
+    ts_init: int,
+    price_precision: int | None = None,
+) -> Bar:
+    """Parse a bar from ``poll_bars()`` to a NautilusTrader Bar.
+
+    Prices are rounded to ``price_precision`` when given, since MID prices
+    can carry more digits than the instrument.
+    """
+
+    def price(value: float) -> Price:
+        if price_precision is None:
+            return Price.from_str(str(value))
+        return Price(value, price_precision)
+
+    return Bar(
+        bar_type=bar_type,
+        open=price(data["open"]),
+        high=price(data["high"]),
+        low=price(data["low"]),
+        close=price(data["close"]),
+        volume=Quantity.from_int(max(int(data.get("volume") or 0), 1)),  # avoid zero-quantity
+        ts_event=int(data["ts_close"] * 1e9),
+        ts_init=ts_init,
+    )
+
+
 # KLType -> BarSpecification reverse mapping
 _KL_TYPE_TO_BAR_SPEC: dict[int, BarSpecification] = {
     FUTU_KL_TYPE_1MIN: BarSpecification(1, BarAggregation.MINUTE, PriceType.LAST),
@@ -203,10 +249,12 @@ def parse_push_order_book(
     data: dict[str, Any],
     instrument_id: InstrumentId,
     ts_init: int,
+    depth: int | None = None,
 ) -> OrderBookDeltas:
     """Parse Futu push order book data to NautilusTrader OrderBookDeltas.
 
-    Uses full snapshot mode: CLEAR then ADD for each level.
+    Uses full snapshot mode: CLEAR then ADD for each level. With ``depth``,
+    only the best ``depth`` levels of each side are kept.
     """
     deltas: list[OrderBookDelta] = []
 
@@ -221,7 +269,7 @@ def parse_push_order_book(
     )
 
     # Add bid levels
-    for bid in data.get("bids", []):
+    for bid in data.get("bids", [])[:depth]:
         order = BookOrder(
             side=OrderSide.BUY,
             price=Price.from_str(str(bid["price"])),
@@ -241,7 +289,7 @@ def parse_push_order_book(
         )
 
     # Add ask levels
-    for ask in data.get("asks", []):
+    for ask in data.get("asks", [])[:depth]:
         order = BookOrder(
             side=OrderSide.SELL,
             price=Price.from_str(str(ask["price"])),
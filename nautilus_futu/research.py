@@ -0,0 +1,65 @@
+"""Bulk data downloads for research workflows."""
+
+from __future__ import annotations
+
+from typing import Any
+
+CAPITAL_FLOW_COLUMNS = (
+    "market",
+    "code",
+    "alias",
+    "time",
+    "timestamp",
+    "in_flow",
+    "main_in_flow",
+    "super_in_flow",
+    "big_in_flow",
+    "mid_in_flow",
+    "sml_in_flow",
+)
+
+
+def capital_flow_table(rows: list[dict[str, Any]]):
+    """Build a pyarrow Table with one column per capital flow field."""
+    import pyarrow as pa
+
+    return pa.table({col: [row.get(col) for row in rows] for col in CAPITAL_FLOW_COLUMNS})
+
+
+def download_capital_flow(
+    client: Any,
+    securities: list,
+    begin_time: str,
+    end_time: str,
+    period_type: int = 2,
+    chunk_days: int = 365,
+    request_interval: float = 1.0,
+    parquet_path: str | None = None,
+):
+    """Download the capital flow of ``securities`` into one pyarrow Table.
+
+    Runs ``PyFutuClient.download_capital_flow`` (chunked and rate limited)
+    and normalizes its rows into a table sorted by security then time,
+    optionally written to ``parquet_path``. A failed security does not stop
+    the download; rows fetched for it before the failure are kept.
+
+    Returns
+    -------
+    tuple[pyarrow.Table, list[dict]]
+        The table and the failed securities (market, code, alias, error and
+        the begin_time to download them again from).
+    """
+    result = client.download_capital_flow(
+        securities,
+        begin_time,
+        end_time,
+        period_type=period_type,
+        chunk_days=chunk_days,
+        request_interval=request_interval,
+    )
+    table = capital_flow_table(result["rows"])
+    if parquet_path is not None:
+        import pyarrow.parquet as pq
+
+        pq.write_table(table, parquet_path)
+    return table, result["failed"]
@@ -98,7 +98,7 @@ def main():
     print("\n[6] Getting history K-line (00700.HK, daily, last 5)...")
     try:
         klines = client.get_history_kl(
-            1, "00700",     # market, code
+            (1, "00700"),   # security
             1,              # rehab_type: 1=forward adjust
             1,              # kl_type: 1=Day
             "2025-01-01",   # begin
@@ -144,15 +144,15 @@ def main():
             # Place a limit buy order for 00700.HK at a very low price
             # trd_market: 1=HK, trd_side: 1=Buy, order_type: 2=Limit
             order = client.place_order(
-                0,                      # trd_env: SIMULATE
-                sim_acc["acc_id"],      # acc_id
-                1,                      # trd_market: HK
                 1,                      # trd_side: Buy
                 2,                      # order_type: Limit (Normal)
-                "00700",                # code
+                "00700",                # security (bare code)
                 100,                    # qty (1 lot)
                 100.0,                  # price (very low, won't fill)
                 1,                      # sec_market: HK
+                trd_env=0,              # SIMULATE
+                acc_id=sim_acc["acc_id"],
+                trd_market=1,           # HK
             )
             order_id = order.get("order_id", 0)
             print(f"    {PASS} Order placed: id={order_id}, id_ex={order.get('order_id_ex', '')}")
@@ -160,11 +160,11 @@ def main():
             # Cancel the order (modify_op: 4=Cancel)
             time.sleep(0.5)
             client.modify_order(
-                0,                      # trd_env: SIMULATE
-                sim_acc["acc_id"],      # acc_id
-                1,                      # trd_market: HK
                 order_id,               # order_id
                 2,                      # modify_op: Cancel
+                trd_env=0,              # SIMULATE
+                acc_id=sim_acc["acc_id"],
+                trd_market=1,           # HK
             )
             print(f"    {PASS} Order cancelled!")
             results.append(("Place+Cancel order", True))
@@ -62,6 +62,40 @@ class TestFutuDataClientConfig:
         assert config.reconnect is False
         assert config.reconnect_interval == 10.0
 
+    def test_fallback_endpoints(self):
+        from nautilus_futu.config import FutuDataClientConfig
+
+        assert FutuDataClientConfig().fallback_endpoints == ()
+        config = FutuDataClientConfig(
+            host="::1",
+            fallback_endpoints=(("10.0.0.2", 11111), ("fe80::1", 11112)),
+        )
+        assert config.host == "::1"
+        assert config.fallback_endpoints[1] == ("fe80::1", 11112)
+
+    def test_subscription_profiles(self):
+        from nautilus_futu.config import FutuDataClientConfig
+
+        config = FutuDataClientConfig(
+            subscription_profiles={"hk_watch": (((1, "00700"),), (1, 4))},
+            active_profiles=("hk_watch",),
+        )
+        securities, sub_types = config.subscription_profiles["hk_watch"]
+        assert securities == ((1, "00700"),)
+        assert sub_types == (1, 4)
+        assert config.active_profiles == ("hk_watch",)
+
+    def test_language(self):
+        from nautilus_futu.config import FutuDataClientConfig
+
+        assert FutuDataClientConfig().language is None
+        config = FutuDataClientConfig(
+            language="en",
+            security_names={"en": {"00700.HKEX": "Tencent"}},
+        )
+        assert config.language == "en"
+        assert config.security_names["en"]["00700.HKEX"] == "Tencent"
+
 
 class TestFutuExecClientConfig:
     """Tests for FutuExecClientConfig."""
@@ -74,6 +108,7 @@ class TestFutuExecClientConfig:
         assert config.acc_id == 0
         assert config.trd_market == 1
         assert config.unlock_pwd_md5 == ""
+        assert config.security_firm is None
 
     def test_real_trading_config(self):
         from nautilus_futu.config import FutuExecClientConfig
@@ -88,6 +123,101 @@ class TestFutuExecClientConfig:
         assert config.acc_id == 123456
         assert config.unlock_pwd_md5 == "abc123"
 
+    def test_security_firm(self):
+        from nautilus_futu.config import FutuExecClientConfig
+
+        config = FutuExecClientConfig(trd_env=1, security_firm=3)
+        assert config.security_firm == 3
+
+    def test_pause_trading_on_preempt(self):
+        from nautilus_futu.config import FutuExecClientConfig
+
+        assert FutuExecClientConfig().pause_trading_on_preempt is False
+        config = FutuExecClientConfig(pause_trading_on_preempt=True)
+        assert config.pause_trading_on_preempt is True
+
+    def test_enable_funds_protocols(self):
+        from nautilus_futu.config import FutuExecClientConfig
+
+        assert FutuExecClientConfig().enable_funds_protocols is False
+        config = FutuExecClientConfig(enable_funds_protocols=True)
+        assert config.enable_funds_protocols is True
+
+    def test_auto_unlock(self):
+        from nautilus_futu.config import FutuExecClientConfig
+
+        config = FutuExecClientConfig()
+        assert config.auto_unlock is False
+        assert config.unlock_keyring is None
+        config = FutuExecClientConfig(auto_unlock=True, unlock_keyring=("futu", "trader"))
+        assert config.auto_unlock is True
+        assert config.unlock_keyring == ("futu", "trader")
+
+    def test_auto_round_orders(self):
+        from nautilus_futu.config import FutuExecClientConfig
+
+        assert FutuExecClientConfig().auto_round_orders is False
+        config = FutuExecClientConfig(auto_round_orders=True)
+        assert config.auto_round_orders is True
+
+    def test_order_throttle(self):
+        from nautilus_futu.config import FutuExecClientConfig
+
+        config = FutuExecClientConfig()
+        assert config.order_entry_limit is None
+        assert config.order_cancel_limit is None
+        assert config.order_cancel_reserve == 0
+        assert config.order_throttle_window == 30.0
+        config = FutuExecClientConfig(order_entry_limit=10, order_cancel_limit=15, order_cancel_reserve=5)
+        assert config.order_cancel_reserve == 5
+
+    def test_partial_bars(self):
+        from nautilus_futu.config import FutuDataClientConfig
+
+        assert FutuDataClientConfig().partial_bars == "skip"
+        assert FutuDataClientConfig(partial_bars="emit").partial_bars == "emit"
+
+    def test_decode_mode(self):
+        from nautilus_futu.config import FutuDataClientConfig, FutuExecClientConfig
+
+        assert FutuDataClientConfig().decode_mode == "lenient"
+        assert FutuExecClientConfig().decode_mode == "lenient"
+        assert FutuDataClientConfig(decode_mode="strict").decode_mode == "strict"
+
+    def test_gateway_log_dir(self):
+        from nautilus_futu.config import FutuDataClientConfig, FutuExecClientConfig
+
+        assert FutuDataClientConfig().gateway_log_dir is None
+        assert FutuExecClientConfig(gateway_log_dir="/opt/opend/log").gateway_log_dir == "/opt/opend/log"
+
+    def test_decode_push_on_worker(self):
+        from nautilus_futu.config import FutuDataClientConfig
+
+        assert FutuDataClientConfig().decode_push_on_worker is False
+        assert FutuDataClientConfig(decode_push_on_worker=True).decode_push_on_worker is True
+
+    def test_order_book_detail(self):
+        from nautilus_futu.config import FutuDataClientConfig
+
+        assert FutuDataClientConfig().order_book_detail is False
+        assert FutuDataClientConfig(order_book_detail=True).order_book_detail is True
+
+    def test_dedup_pushes(self):
+        from nautilus_futu.config import FutuDataClientConfig
+
+        assert FutuDataClientConfig().dedup_pushes is False
+        assert FutuDataClientConfig(dedup_pushes=True).dedup_pushes is True
+
+    def test_response_cache(self):
+        from nautilus_futu.config import FutuDataClientConfig
+
+        config = FutuDataClientConfig()
+        assert config.response_cache is False
+        assert config.response_cache_ttls is None
+        config = FutuDataClientConfig(response_cache=True, response_cache_ttls={3203: 1.0})
+        assert config.response_cache is True
+        assert config.response_cache_ttls == {3203: 1.0}
+
     def test_default_reconnect(self):
         from nautilus_futu.config import FutuExecClientConfig
 
@@ -39,6 +39,7 @@ from nautilus_futu.constants import (
     FUTU_SUB_TYPE_KL_30MIN,
     FUTU_SUB_TYPE_KL_60MIN,
     FUTU_SUB_TYPE_KL_1MIN,
+    FUTU_SUB_TYPE_ORDER_DETAIL,
     FUTU_KL_TYPE_1MIN,
     FUTU_KL_TYPE_DAY,
     FUTU_KL_TYPE_WEEK,
@@ -65,6 +66,7 @@ from nautilus_futu.constants import (
     FUTU_PROTO_KL,
     FUTU_PROTO_TICKER,
     FUTU_PROTO_ORDER_BOOK,
+    FUTU_PROTO_PRICE_REMINDER,
     FUTU_PROTO_TRD_ORDER,
     FUTU_PROTO_TRD_FILL,
 )
@@ -124,13 +126,14 @@ class TestSubTypeConstants:
         assert FUTU_SUB_TYPE_KL_30MIN == 9
         assert FUTU_SUB_TYPE_KL_60MIN == 10
         assert FUTU_SUB_TYPE_KL_1MIN == 11
+        assert FUTU_SUB_TYPE_ORDER_DETAIL == 18
 
     def test_all_unique(self):
         values = [
             FUTU_SUB_TYPE_BASIC, FUTU_SUB_TYPE_ORDER_BOOK, FUTU_SUB_TYPE_TICKER,
             FUTU_SUB_TYPE_RT, FUTU_SUB_TYPE_KL_DAY, FUTU_SUB_TYPE_KL_5MIN,
             FUTU_SUB_TYPE_KL_15MIN, FUTU_SUB_TYPE_KL_30MIN, FUTU_SUB_TYPE_KL_60MIN,
-            FUTU_SUB_TYPE_KL_1MIN,
+            FUTU_SUB_TYPE_KL_1MIN, FUTU_SUB_TYPE_ORDER_DETAIL,
         ]
         assert len(values) == len(set(values))
 
@@ -251,12 +254,14 @@ class TestProtocolIdConstants:
         assert FUTU_PROTO_KL == 3007
         assert FUTU_PROTO_TICKER == 3011
         assert FUTU_PROTO_ORDER_BOOK == 3013
+        assert FUTU_PROTO_PRICE_REMINDER == 3019
         assert FUTU_PROTO_TRD_ORDER == 2208
         assert FUTU_PROTO_TRD_FILL == 2218
 
     def test_all_unique(self):
         values = [
             FUTU_PROTO_BASIC_QOT, FUTU_PROTO_KL, FUTU_PROTO_TICKER,
-            FUTU_PROTO_ORDER_BOOK, FUTU_PROTO_TRD_ORDER, FUTU_PROTO_TRD_FILL,
+            FUTU_PROTO_ORDER_BOOK, FUTU_PROTO_PRICE_REMINDER, FUTU_PROTO_TRD_ORDER,
+            FUTU_PROTO_TRD_FILL,
         ]
         assert len(values) == len(set(values))
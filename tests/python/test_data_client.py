@@ -31,7 +31,7 @@ class MockClient:
         self.get_basic_qot_result = []
         self.get_ticker_result = []
 
-    def subscribe(self, securities, sub_types, is_sub):
+    def subscribe(self, securities, sub_types, is_sub, order_book_detail=False):
         self.subscribe_calls.append((securities, sub_types, is_sub))
 
     def get_static_info(self, securities):
@@ -40,7 +40,7 @@ class MockClient:
     def get_basic_qot(self, securities):
         return self.get_basic_qot_result
 
-    def get_ticker(self, market, code, max_ret_num):
+    def get_ticker(self, security, max_ret_num):
         return self.get_ticker_result
 
 
@@ -168,7 +168,7 @@ class TestRequestTradeTicks:
             },
         ]
 
-        result = mock_client.get_ticker(1, "00700", 100)
+        result = mock_client.get_ticker((1, "00700"), 100)
         assert len(result) == 2
 
         from nautilus_futu.parsing.market_data import parse_futu_trade_tick
@@ -190,5 +190,5 @@ class TestRequestTradeTicks:
         mock_client = MockClient()
         mock_client.get_ticker_result = []
 
-        result = mock_client.get_ticker(1, "00700", 100)
+        result = mock_client.get_ticker((1, "00700"), 100)
         assert len(result) == 0
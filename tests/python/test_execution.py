@@ -2,11 +2,14 @@
 
 from __future__ import annotations
 
+import sys
+from types import SimpleNamespace
 from unittest.mock import MagicMock
 
 from nautilus_trader.model.objects import Currency
 
-from nautilus_futu.execution import FutuLiveExecutionClient, parse_funds_to_balance
+from nautilus_futu.config import FutuExecClientConfig
+from nautilus_futu.execution import FutuLiveExecutionClient, parse_funds_to_balance, resolve_unlock_pwd_md5
 
 
 USD = Currency.from_str("USD")
@@ -275,3 +278,27 @@ class TestPushFillDefensive:
         """Completely empty data dict should not raise."""
         mock = _make_mock_self()
         FutuLiveExecutionClient._handle_push_fill(mock, {})
+
+
+class TestResolveUnlockPassword:
+    """The unlock password comes from the config, then the keyring."""
+
+    def test_config_password_wins(self, monkeypatch):
+        keyring = SimpleNamespace(get_password=MagicMock(return_value="from-keyring"))
+        monkeypatch.setitem(sys.modules, "keyring", keyring)
+        config = FutuExecClientConfig(unlock_pwd_md5="abc", unlock_keyring=("futu", "trader"))
+        assert resolve_unlock_pwd_md5(config) == "abc"
+        keyring.get_password.assert_not_called()
+
+    def test_keyring_entry(self, monkeypatch):
+        keyring = SimpleNamespace(get_password=MagicMock(return_value="from-keyring"))
+        monkeypatch.setitem(sys.modules, "keyring", keyring)
+        config = FutuExecClientConfig(unlock_keyring=("futu", "trader"))
+        assert resolve_unlock_pwd_md5(config) == "from-keyring"
+        keyring.get_password.assert_called_once_with("futu", "trader")
+
+        keyring.get_password.return_value = None
+        assert resolve_unlock_pwd_md5(config) == ""
+
+    def test_no_password(self):
+        assert resolve_unlock_pwd_md5(FutuExecClientConfig()) == ""
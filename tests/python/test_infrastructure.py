@@ -81,6 +81,55 @@ class TestStartPushAppendMode:
         result = client.poll_push(10)
         assert result is None
 
+    def test_poll_push_batch_without_start_returns_empty(self):
+        """poll_push_batch and drain_push on an unknown channel return []."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        assert client.poll_push_batch(10, 100, 0) == []
+        assert client.drain_push(10) == []
+
+
+class TestPushCallback:
+    """Tests for callback delivery of push channels."""
+
+    def test_unknown_channel(self):
+        """set_push_callback needs an existing channel; stats are None without a callback."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(ValueError, match="Unknown push channel"):
+            client.set_push_callback(10, print)
+        assert client.push_callback_stats(10) is None
+
+    def test_callback_against_mock(self):
+        """Pushes reach the callback as poll_push() dicts, in batches when asked."""
+        import time
+
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        client.connect("127.0.0.1", 0, "test", 100, mock=True)
+        try:
+            channel_id = client.start_push([3005])
+            with pytest.raises(ValueError, match="callable"):
+                client.set_push_callback(channel_id, 42)
+
+            received = []
+            client.set_push_callback(channel_id, received.append, batch=True, queue_size=8)
+            # retType 0 and an empty basic quote list
+            client.mock_push(3005, b"\x08\x00\x22\x00")
+            deadline = time.monotonic() + 5
+            while not received and time.monotonic() < deadline:
+                time.sleep(0.01)
+            assert received == [[{"proto_id": 3005, "data": []}]]
+            assert client.push_callback_stats(channel_id)["delivered"] == 1
+
+            client.set_push_callback(channel_id, None)
+            assert client.push_callback_stats(channel_id) is None
+        finally:
+            client.disconnect()
+
 
 class TestGetGlobalState:
     """Tests for get_global_state method."""
@@ -93,6 +142,479 @@ class TestGetGlobalState:
         with pytest.raises(RuntimeError, match="Not connected"):
             client.get_global_state()
 
+    def test_get_user_info_against_mock(self):
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.get_user_info()
+        client.connect("127.0.0.1", 0, "test", 100, mock=True)
+        try:
+            # retType 0, apiLevel "LV1", hkQotRight 3, subQuota 100
+            client.mock_response(1005, b"\x08\x00\x22\x09\x1a\x03LV1\x20\x03\x70\x64")
+            info = client.get_user_info()
+            assert info["api_level"] == "LV1"
+            assert info["hk_qot_right"] == 3
+            assert info["sub_quota"] == 100
+            assert info["us_qot_right"] is None
+        finally:
+            client.disconnect()
+
+
+class TestOrderDetail:
+    """Tests for order queue details."""
+
+    def test_get_order_detail_requires_connection(self):
+        """get_order_detail should raise when not connected."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.get_order_detail((1, "00700"))
+
+
+class TestRegQotPush:
+    """Tests for reg_qot_push method."""
+
+    def test_reg_qot_push_requires_connection(self):
+        """reg_qot_push should raise when not connected."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.reg_qot_push([(1, "00700")], [6], is_reg=False, rehab_type=1)
+
+
+class TestExecutionReport:
+    """Tests for execution_report method."""
+
+    def test_execution_report_requires_connection(self):
+        """execution_report should raise when not connected."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.execution_report("2024-01-02", 0, 1, 1)
+
+
+
+class TestLivePortfolio:
+    """Tests for the push-maintained position snapshot."""
+
+    def test_live_portfolio_requires_start(self):
+        """get_live_portfolio should raise before start_live_portfolio."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(RuntimeError, match="not started"):
+            client.get_live_portfolio()
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.start_live_portfolio(1, 42, 1)
+
+
+class TestHistoryFills:
+    """Tests for the windowed history fill query."""
+
+    def test_get_history_fills_validates_options(self):
+        """Options are checked before the connection."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(ValueError, match="must be positive"):
+            client.get_history_fills("2024-01-01", "2024-06-30", window_days=0)
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.get_history_fills("2024-01-01", "2024-06-30", None, 0, 1, 1)
+
+
+class TestInstrument:
+    """Tests for the consolidated instrument record."""
+
+    def test_instrument_requires_connection(self):
+        """Aliases and account fields are resolved before the connection."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(ValueError, match="Unknown symbol alias"):
+            client.instrument("NOPE")
+        with pytest.raises(ValueError, match="acc_id not given"):
+            client.instrument((1, "00700"), trd_env=0)
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.instrument((1, "00700"))
+
+
+class TestSymbolAliases:
+    """Tests for symbol alias registration."""
+
+    def test_alias_roundtrip(self):
+        """Aliases resolve both ways without a connection."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        assert client.add_symbol_aliases([("TENCENT", 1, "00700"), ("APPLE", 11, "AAPL")]) == 2
+        assert client.resolve_symbol("TENCENT") == (1, "00700")
+        assert client.symbol_alias(11, "AAPL") == "APPLE"
+        assert client.remove_symbol_alias("APPLE") is True
+        assert client.symbol_alias(11, "AAPL") is None
+
+    def test_unknown_alias_rejected(self):
+        """Unknown aliases in security lists raise ValueError."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(ValueError, match="Unknown symbol alias"):
+            client.define_profile("watch", ["NOPE"], [1])
+
+    def test_single_security_methods_resolve_aliases(self):
+        """Single-security methods take aliases; order methods fall back to a bare code."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        client.add_symbol_aliases([("TENCENT", 1, "00700")])
+        for call in (
+            lambda security: client.get_order_book(security),
+            lambda security: client.get_history_kl(security, 1, 2, "2024-01-01", "2024-01-31"),
+            lambda security: client.download_history_kl_batch([security], 1, 2, "2024-01-02", "2024-01-05"),
+            lambda security: client.get_option_chain(security, "2024-01-01", "2024-01-31"),
+        ):
+            with pytest.raises(ValueError, match="Unknown symbol alias"):
+                call("NOPE")
+            with pytest.raises(RuntimeError, match="Not connected"):
+                call("TENCENT")
+        for security in ("TENCENT", "00700", (1, "00700")):
+            with pytest.raises(RuntimeError, match="Not connected"):
+                client.place_order(1, 2, security, 100, 300.0)
+
+
+class TestSnapshotFields:
+    """Tests for snapshot field selection."""
+
+    def test_unknown_field_rejected(self):
+        """Unknown field names raise ValueError before any request."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(ValueError, match="unknown field"):
+            client.get_security_snapshot([(1, "00700")], fields=["cur_prise"])
+        with pytest.raises(ValueError, match="unknown field"):
+            client.get_basic_qot([(1, "00700")], fields=["bogus"])
+
+    def test_known_fields_require_connection(self):
+        """Valid field selections proceed to the request."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.get_security_snapshot([(1, "00700")], fields=["cur_price", "bid_price"])
+
+    def test_ex_data_fields_selectable(self):
+        """The per-type ex-data sections can be selected like other fields."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        fields = ["equity_ex_data", "option_ex_data", "warrant_ex_data", "pre_market", "name"]
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.get_security_snapshot([(1, "00700")], fields=fields)
+
+
+class TestLogging:
+    """Tests for the tracing to Python logging bridge."""
+
+    def test_exported(self):
+        """init_logging and set_log_filter are reachable from the package."""
+        import nautilus_futu
+
+        assert callable(nautilus_futu.init_logging)
+        assert callable(nautilus_futu.set_log_filter)
+
+    def test_invalid_filter_rejected(self):
+        """A malformed level raises ValueError before anything is installed."""
+        from nautilus_futu._rust import init_logging
+
+        with pytest.raises(ValueError, match="Invalid log filter"):
+            init_logging("info", filters={"nautilus_futu.client": "[bad"})
+
+
+class TestMarketSession:
+    """Tests for the market session poller."""
+
+    def test_without_connection(self):
+        """Market states are empty and no events arrive before connect."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        assert client.market_states() == {}
+        assert client.poll_market_events(0) == []
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.start_market_session()
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.refresh_market_session()
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.get_market_state([(11, "AAPL")])
+
+
+class TestSessionPreemption:
+    """Tests for session preemption support."""
+
+    def test_error_type_exported(self):
+        """SessionPreemptedError is a RuntimeError subclass."""
+        from nautilus_futu._rust import SessionPreemptedError
+
+        assert issubclass(SessionPreemptedError, RuntimeError)
+
+    def test_poll_session_event_without_connection(self):
+        """poll_session_event returns None before connect."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        client.set_pause_trading_on_preempt(True)
+        assert client.poll_session_event(0) is None
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.session_preempted()
+
+    def test_notify_push_decoded(self):
+        """Notify pushes (proto 1003) arrive as flat dicts."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        client.connect("127.0.0.1", 0, "test", 100, mock=True)
+        try:
+            channel_id = client.start_push([1003])
+            # retType 0, type APIQuota, subQuota 300, historyKLQuota 100
+            client.mock_push(1003, b"\x08\x00\x22\x09\x08\x06\x3a\x05\x08\xac\x02\x10\x64")
+            msg = client.poll_push(channel_id, 5000)
+            assert msg["proto_id"] == 1003
+            assert msg["data"] == {"type": 6, "sub_quota": 300, "history_kl_quota": 100}
+        finally:
+            client.disconnect()
+
+
+class TestSubscriptionResults:
+    """Tests for per-security subscribe results."""
+
+    def test_error_type_exported(self):
+        """SubscriptionRejectedError is a RuntimeError subclass."""
+        from nautilus_futu._rust import SubscriptionRejectedError
+
+        assert issubclass(SubscriptionRejectedError, RuntimeError)
+
+    def test_subscribe_requires_connection(self):
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.subscribe([(1, "00700")], [1], True, raise_on_reject=False)
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.unsubscribe_all()
+
+
+class TestThrottledResponses:
+    """Tests for OpenD busy/frequency limit handling."""
+
+    def test_error_type_exported(self):
+        """ThrottledError is a RuntimeError subclass."""
+        from nautilus_futu._rust import ThrottledError
+
+        assert issubclass(ThrottledError, RuntimeError)
+
+    def test_set_retry_policy_without_connection(self):
+        """The policy is stored for the next connection and validated."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        client.set_retry_policy()
+        client.set_retry_policy(max_retries=0)
+        with pytest.raises(ValueError, match="max_delay_secs"):
+            client.set_retry_policy(max_delay_secs=-1.0)
+
+
+class TestKeyRotation:
+    """Tests for AES key rotation."""
+
+    def test_rekey_requires_connection(self):
+        """rekey needs an open connection."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.rekey()
+
+
+class TestPushFilter:
+    """Tests for changing push channels at runtime."""
+
+    def test_push_filter_requires_connection(self):
+        """stop_push and update_push_filter need an open connection."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.stop_push(0, [3005])
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.update_push_filter(0, proto_ids=[3005, 3011])
+
+    def test_exclusive_security_arguments(self):
+        """A security list cannot be set and cleared at once."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(ValueError, match="exclusive"):
+            client.update_push_filter(0, securities=[(1, "00700")], clear_securities=True)
+
+
+class TestHistoryDownload:
+    """Tests for paged history downloads."""
+
+    def test_download_requires_connection(self):
+        """download_history_kl needs an open connection."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.download_history_kl((1, "00700"), 1, 1, "2024-01-02", "2024-01-05", validate=True)
+
+    def test_resume_and_bulk_snapshot_require_connection(self):
+        """Resumed downloads and bulk snapshots need an open connection."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.download_history_kl((1, "00700"), 1, 1, "2024-01-02", "2024-01-05", resume_key=b"\x01\x02")
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.get_security_snapshot_bulk([(1, "00700"), (1, "09988")], chunk_size=1)
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.get_security_snapshot_all([(1, "00700"), (1, "09988")], parallelism=2)
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.get_history_kl_all((1, "00700"), 1, 2, "2024-01-01", "2024-12-31", max_pages=5)
+
+    def test_history_kl_arrow_requires_connection(self):
+        """The Arrow export is only built with the arrow feature."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        if not hasattr(client, "get_history_kl_arrow"):
+            pytest.skip("built without the arrow feature")
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.get_history_kl_arrow((1, "00700"), 1, 2, "2024-01-01", "2024-12-31")
+
+    def test_quota_batch_requires_connection(self):
+        """History quota and batch downloads need an open connection."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.get_history_kl_quota()
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.download_history_kl_batch([(1, "00700")], 1, 2, "2024-01-02", "2024-01-05", allow_over_quota=True)
+
+
+class TestFundsProtocols:
+    """Tests for funds protocol detection and enablement."""
+
+    def test_capabilities(self):
+        """Only the cash flow summary has an OpenAPI protocol."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        caps = client.funds_capabilities()
+        assert caps["cash_flow_summary"] == {"proto_id": 2226, "supported": True, "enabled": False}
+        for op in ("funds_transfer", "cash_sweep", "currency_exchange"):
+            assert caps[op] == {"proto_id": None, "supported": False, "enabled": False}
+        client.set_funds_protocols(True)
+        assert client.funds_capabilities()["cash_flow_summary"]["enabled"] is True
+        assert client.funds_capabilities()["currency_exchange"]["enabled"] is False
+
+    def test_cash_flow_requires_connection(self):
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(ValueError, match="direction"):
+            client.get_cash_flow("2024-03-01", direction=3)
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.get_cash_flow("2024-03-01", trd_env=0, acc_id=1, trd_market=1)
+
+    def test_flow_summary_requires_connection(self):
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(ValueError, match="direction"):
+            client.get_flow_summary("2024-03-01", "2024-03-05", direction=0)
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.get_flow_summary("2024-03-01", "2024-03-05", trd_env=0, acc_id=1, trd_market=1)
+
+
+class TestResponseCache:
+    """Tests for the response cache settings."""
+
+    def test_settings_before_connect(self):
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        client.set_response_cache(True, ttls={3202: 60.0, 3204: 0.0}, max_entries=16)
+        with pytest.raises(ValueError, match="Invalid TTL"):
+            client.set_response_cache(True, ttls={3202: -1.0})
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.response_cache_stats()
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.invalidate_response_cache()
+
+
+class TestMetrics:
+    """Tests for the Prometheus metrics text."""
+
+    def test_metrics_without_connection(self):
+        """Only client-level metrics are rendered before connect."""
+        from nautilus_futu._rust import PyFutuClient
+
+        text = PyFutuClient().metrics()
+        assert "# TYPE futu_connects_total counter\nfutu_connects_total 0\n" in text
+        assert "futu_request_duration_seconds" not in text
+
+    def test_stats_requires_connection(self):
+        from nautilus_futu._rust import PyFutuClient
+
+        with pytest.raises(RuntimeError, match="Not connected"):
+            PyFutuClient().get_stats()
+
+
+class TestOrderThrottle:
+    """Tests for the client-side order throttle."""
+
+    def test_set_order_throttle_without_connection(self):
+        """Budgets can be set before connecting; invalid ones are rejected."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        client.set_order_throttle(10, 15, 5, 30.0)
+        client.set_order_throttle()
+        with pytest.raises(ValueError, match="cancel reserve"):
+            client.set_order_throttle(cancel_limit=5, cancel_reserve=5)
+        with pytest.raises(ValueError):
+            client.set_order_throttle(entry_limit=10, window_secs=-1.0)
+
+
+class TestBarAggregation:
+    """Tests for the bar aggregation channel."""
+
+    def test_bar_push_without_connection(self):
+        """start_bar_push needs a connection; unknown channels poll empty."""
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(ValueError, match="partial bar policy"):
+            client.start_bar_push("sometimes")
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.start_bar_push("emit")
+        assert client.poll_bars(0, 256, 0) == []
+
+    def test_add_bar_spec_validates_spec_and_channel(self):
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(ValueError, match="bar specification"):
+            client.add_bar_spec(0, (1, "00700"), "MINUTE-1")
+        with pytest.raises(ValueError, match="Unknown bar channel"):
+            client.add_bar_spec(0, (1, "00700"), "5-MINUTE-MID")
+
 
 class TestRehabTypeConfig:
     """Tests for rehab_type configuration flow."""
@@ -153,3 +675,417 @@ class TestReconnectConfig:
 
         config = FutuDataClientConfig(reconnect_interval=15.0)
         assert config.reconnect_interval == 15.0
+
+    def test_disconnect_grace_period_default(self):
+        from nautilus_futu.config import FutuDataClientConfig, FutuExecClientConfig
+
+        assert FutuDataClientConfig().disconnect_grace_period == 5.0
+        assert FutuExecClientConfig(disconnect_grace_period=0.0).disconnect_grace_period == 0.0
+
+    def test_disconnect_grace_period(self):
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        client.connect("127.0.0.1", 0, "test", 100, mock=True)
+        with pytest.raises(ValueError, match="grace_period"):
+            client.disconnect(-1.0)
+        assert client.is_connected()
+        client.disconnect(0.5)
+        assert not client.is_connected()
+
+
+class TestOrderRounding:
+    """Tests for qty/price rounding helpers."""
+
+    def test_rounding_requires_connection(self):
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        client.set_auto_round_orders(True)
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.round_qty((1, "00700"), 250)
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.round_price((1, "00700"), 300.13, 1)
+
+
+class TestErrorClasses:
+    """Tests for the exception types of failed calls."""
+
+    def test_hierarchy(self):
+        from nautilus_futu import _rust
+
+        for name in ("FutuConnectionError", "FutuServerError", "FutuDecodeError", "FutuTimeoutError"):
+            assert issubclass(getattr(_rust, name), _rust.FutuError)
+        assert issubclass(_rust.FutuError, RuntimeError)
+
+    def test_not_connected_is_connection_error(self):
+        from nautilus_futu._rust import FutuConnectionError, PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(FutuConnectionError, match="Not connected"):
+            client.get_global_state()
+
+    def test_unreadable_rsa_key_is_connection_error(self, tmp_path):
+        from nautilus_futu._rust import FutuConnectionError, PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(FutuConnectionError, match="cannot read"):
+            client.connect("127.0.0.1", 0, "test", 100, mock=True, rsa_key_path=str(tmp_path / "missing.pem"))
+        assert not client.is_connected()
+
+    def test_unknown_aes_padding_is_value_error(self):
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(ValueError, match="unknown AES padding"):
+            client.connect("127.0.0.1", 0, "test", 100, mock=True, aes_padding="cbc")
+        client.connect("127.0.0.1", 0, "test", 100, mock=True, aes_padding="futu")
+        assert client.is_connected()
+        client.disconnect()
+
+    def test_errors_against_mock(self):
+        """Failed responses raise FutuServerError; undecodable ones FutuDecodeError."""
+        from nautilus_futu._rust import FutuDecodeError, FutuServerError, PyFutuClient
+
+        client = PyFutuClient()
+        client.connect("127.0.0.1", 0, "test", 100, mock=True)
+        try:
+            # retType -1, retMsg "unknown stock", errCode 1234
+            body = b"\x08" + b"\xff" * 9 + b"\x01" + b"\x12\x0dunknown stock" + b"\x18\xd2\x09"
+            client.mock_response(3203, body)
+            with pytest.raises(FutuServerError, match="unknown stock") as exc_info:
+                client.get_security_snapshot([(1, "00700")])
+            assert exc_info.value.ret_type == -1
+            assert exc_info.value.err_code == 1234
+
+            client.mock_response(3203, b"\xff\xff")
+            with pytest.raises(FutuDecodeError):
+                client.get_security_snapshot([(1, "00700")])
+        finally:
+            client.disconnect()
+
+
+class TestIdempotentOrders:
+    """Tests for place_order_idempotent and reconfirm_order."""
+
+    def test_repeated_order_placed_once(self):
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.place_order_idempotent(1, 2, "00700", 100, 300.0, trd_env=0, acc_id=1, trd_market=1)
+
+        client.connect("127.0.0.1", 0, "test", 100, mock=True)
+        try:
+            # retType 0, s2c with an empty header and orderID 42
+            client.mock_response(2202, b"\x08\x00\x22\x08\x0a\x04\x08\x00\x10\x00\x10\x2a")
+            first = client.place_order_idempotent(1, 2, "00700", 100, 300.0, trd_env=0, acc_id=1, trd_market=1)
+            assert first["order_id"] == 42
+            assert client.place_order_idempotent(1, 2, "00700", 100, 300.0, trd_env=0, acc_id=1, trd_market=1) == first
+            assert client.get_stats()["requests"][2202]["count"] == 1
+        finally:
+            client.disconnect()
+
+    def test_reconfirm_order(self):
+        from nautilus_futu._rust import FutuServerError, PyFutuClient
+
+        client = PyFutuClient()
+        client.connect("127.0.0.1", 0, "test", 100, mock=True)
+        try:
+            # retType 0, s2c with header (env 1, acc 7, market 2) and orderID 9
+            client.mock_response(2206, b"\x08\x00\x22\x0a\x0a\x06\x08\x01\x10\x07\x18\x02\x10\x09")
+            assert client.reconfirm_order(9, 1, trd_env=1, acc_id=7, trd_market=2) == 9
+
+            # retType -1, retMsg "order not found", errCode 5
+            body = b"\x08" + b"\xff" * 9 + b"\x01" + b"\x12\x0forder not found" + b"\x18\x05"
+            client.mock_response(2206, body)
+            with pytest.raises(FutuServerError, match="order not found") as exc_info:
+                client.reconfirm_order(9, 1, trd_env=1, acc_id=7, trd_market=2)
+            assert exc_info.value.err_code == 5
+        finally:
+            client.disconnect()
+
+
+class TestOptionalTradeArgs:
+    """Trade methods take the account as optional keywords."""
+
+    def test_account_optional(self):
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        calls = [
+            lambda: client.place_order(1, 2, "00700", 100),
+            lambda: client.place_order_idempotent(1, 2, "00700", 100),
+            lambda: client.place_order_autolock(1, 2, "00700", 100),
+            lambda: client.modify_order(9, 2),
+            lambda: client.reconfirm_order(9, 1),
+            lambda: client.get_max_trd_qtys(2, "00700", 300.0),
+            lambda: client.get_margin_ratio([(1, "00700")]),
+            lambda: client.get_order_fee(["abc"]),
+            lambda: client.preview_order(1, 2, (1, "00700"), 100, 300.0),
+        ]
+        for call in calls:
+            with pytest.raises(RuntimeError, match="Not connected"):
+                call()
+        client.set_unlock_credentials()
+
+        client.connect("127.0.0.1", 0, "test", 100, mock=True)
+        try:
+            with pytest.raises(ValueError, match="no default account"):
+                client.place_order(1, 2, "00700", 100, 300.0)
+            client.set_default_account(0, 1, 1)
+            # retType 0, s2c with an empty header and orderID 42
+            client.mock_response(2202, b"\x08\x00\x22\x08\x0a\x04\x08\x00\x10\x00\x10\x2a")
+            assert client.place_order(1, 2, "00700", 100, 300.0)["order_id"] == 42
+            assert client.modify_order(42, 2) is None
+        finally:
+            client.disconnect()
+
+
+class TestOrderEvents:
+    """Tests for order lifecycle events."""
+
+    def test_poll_order_events(self):
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        assert client.poll_order_events(timeout_ms=0) == []
+
+        client.connect("127.0.0.1", 0, "test", 100, mock=True)
+        try:
+            # Account 7, order 9 of 00700 for 100 shares, Submitted
+            push = (
+                b"\x08\x00\x22\x1e\x0a\x06\x08\x01\x10\x07\x18\x01\x12\x14\x18\x05\x20\x09"
+                b"\x32\x05\x30\x30\x37\x30\x30\x41\x00\x00\x00\x00\x00\x00\x59\x40"
+            )
+            client.mock_push(2208, push)
+            client.mock_push(2208, push)
+            events = client.poll_order_events(timeout_ms=1000)
+            assert len(events) == 1
+            event = events[0]
+            assert (event["acc_id"], event["order_id"], event["code"]) == (7, 9, "00700")
+            assert (event["prev_state"], event["state"]) == (None, "submitted")
+            assert event["qty"] == 100.0
+            assert event["fill"] is None
+            # The repeated push is not a transition
+            assert client.poll_order_events(timeout_ms=100) == []
+        finally:
+            client.disconnect()
+        assert client.poll_order_events(timeout_ms=0) == []
+
+
+class TestConnectionHealth:
+    """Tests for keepalive health reporting."""
+
+    def test_connection_health(self):
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.connection_health()
+        client.connect("127.0.0.1", 0, "test", 100, mock=True)
+        try:
+            client.get_global_state()
+            health = client.connection_health()
+            # No heartbeat is due right after connecting
+            assert health["heartbeats_sent"] == 0
+            assert health["missed"] == 0
+            assert health["stale"] is False
+            assert health["rtt_avg_ms"] is None
+            assert health["last_received"] is not None
+        finally:
+            client.disconnect()
+
+
+class TestLocalOrderBook:
+    """Tests for order books kept from order book pushes."""
+
+    def test_local_order_book_from_pushes(self):
+        import struct
+        import time
+
+        from nautilus_futu._rust import PyFutuClient
+
+        def level(price, volume):
+            body = b"\x09" + struct.pack("<d", price) + b"\x10" + bytes([volume]) + b"\x18\x01"
+            return bytes([len(body)]) + body
+
+        def push(bid, ask, svr_time):
+            s2c = b"\x0a\x09\x08\x01\x12\x0500700"
+            s2c += b"\x12" + level(ask, 20) + b"\x12" + level(ask + 0.2, 40)
+            s2c += b"\x1a" + level(bid, 10)
+            s2c += b"\x29" + struct.pack("<d", svr_time) + b"\x39" + struct.pack("<d", svr_time)
+            return b"\x08\x00\x22" + bytes([len(s2c)]) + s2c
+
+        client = PyFutuClient()
+        client.connect("127.0.0.1", 0, "test", 100, mock=True)
+        try:
+            assert client.local_order_book((1, "00700")) is None
+            client.mock_push(3013, push(300.0, 300.2, 1000.0))
+            # Out of order: dropped
+            client.mock_push(3013, push(299.0, 299.2, 999.0))
+            deadline = time.monotonic() + 5
+            while (client.local_order_book((1, "00700")) or {}).get("dropped") != 1 and time.monotonic() < deadline:
+                time.sleep(0.01)
+
+            book = client.local_order_book((1, "00700"), depth=1)
+            assert book["bids"] == [{"price": 300.0, "volume": 10, "order_count": 1}]
+            assert [level["price"] for level in book["asks"]] == [300.2]
+            assert (book["sequence"], book["dropped"], book["gaps"]) == (1, 1, 0)
+            assert book["bid_time"] == 1000.0
+            assert client.best_bid_ask((1, "00700")) == {"bid": 300.0, "bid_volume": 10, "ask": 300.2, "ask_volume": 20}
+            assert client.best_bid_ask((1, "09988")) is None
+            client.add_symbol_aliases([("TENCENT", 1, "00700")])
+            assert client.local_order_book("TENCENT", depth=1) == book
+        finally:
+            client.disconnect()
+
+
+class TestUniverse:
+    """Tests for enumerating a market's securities from its plates."""
+
+    def test_get_all_securities_against_mock(self):
+        from nautilus_futu._rust import PyFutuClient
+
+        def message(tag, body):
+            return bytes([tag << 3 | 2, len(body)]) + body
+
+        def security(code):
+            return b"\x08\x01" + message(2, code.encode())
+
+        def static_info(code, sec_type):
+            basic = message(1, security(code)) + b"\x10\x01\x18\x64" + bytes([0x20, sec_type])
+            basic += message(5, b"") + message(6, b"")
+            return message(1, message(1, basic))
+
+        client = PyFutuClient()
+        client.connect("127.0.0.1", 0, "test", 100, mock=True)
+        try:
+            plates = message(1, message(1, security("LIST1"))) + message(1, message(1, security("LIST2")))
+            client.mock_response(3204, b"\x08\x00" + message(4, plates))
+            # The mock answers both plates alike
+            securities = static_info("09988", 3) + static_info("00700", 3) + static_info("02800", 4)
+            client.mock_response(3205, b"\x08\x00" + message(4, securities))
+
+            result = client.get_all_securities(1, sec_types=[3])
+            assert [(s["market"], s["code"]) for s in result] == [(1, "00700"), (1, "09988")]
+            assert result[0]["plates"] == ["LIST1", "LIST2"]
+            assert result[0]["lot_size"] == 100
+            assert result[0]["delisted"] is False
+        finally:
+            client.disconnect()
+
+
+class TestSocketOptions:
+    """Tests for TCP connect, keep-alive and read settings."""
+
+    def test_set_socket_options(self):
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        client.set_socket_options()
+        client.set_socket_options(connect_timeout=None, tcp_keepalive=30.0, read_timeout=60.0, nodelay=False)
+        with pytest.raises(ValueError, match="read_timeout"):
+            client.set_socket_options(read_timeout=-1.0)
+
+
+class TestRateLimit:
+    """Tests for the client-side request rate limits."""
+
+    def test_set_rate_limit_validates(self):
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        client.set_rate_limit(True, limits={3203: (30, 30.0)})
+        with pytest.raises(ValueError, match="Unknown rate limit mode"):
+            client.set_rate_limit(True, mode="drop")
+        with pytest.raises(ValueError, match="must be positive"):
+            client.set_rate_limit(True, limits={3203: (0, 30.0)})
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.rate_limit_stats()
+
+    def test_fail_fast_against_mock(self):
+        from nautilus_futu._rust import PyFutuClient, ThrottledError
+
+        client = PyFutuClient()
+        client.set_rate_limit(True, mode="fail_fast", limits={3221: (1, 30.0)})
+        client.connect("127.0.0.1", 0, "test", 100, mock=True)
+        try:
+            client.mock_response(3221, b"\x08\x00")
+            assert client.get_price_reminder(market=1) == []
+            with pytest.raises(ThrottledError):
+                client.get_price_reminder(market=1)
+            stats = client.rate_limit_stats()
+            assert stats["mode"] == "fail_fast"
+            assert stats["limits"][3221] == (1, 30.0)
+            assert stats["limited"] == {3221: 1}
+        finally:
+            client.disconnect()
+
+
+class TestPriceReminders:
+    """Tests for price reminder management."""
+
+    def test_set_price_reminder_validates(self):
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        with pytest.raises(ValueError, match="reminder_type and value"):
+            client.set_price_reminder((1, "00700"), 1)
+        with pytest.raises(ValueError, match="key is required"):
+            client.set_price_reminder((1, "00700"), 3)
+        with pytest.raises(ValueError, match="security or market"):
+            client.get_price_reminder()
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.set_price_reminder((1, "00700"), 1, reminder_type=1, value=400.0)
+
+    def test_price_reminders_against_mock(self):
+        from nautilus_futu._rust import PyFutuClient
+
+        client = PyFutuClient()
+        client.connect("127.0.0.1", 0, "test", 100, mock=True)
+        try:
+            # retType 0 and key 77
+            client.mock_response(3220, b"\x08\x00\x22\x02\x08\x4d")
+            assert client.set_price_reminder((1, "00700"), 1, reminder_type=1, value=400.0) == 77
+            assert client.get_price_reminder(market=1) == []
+        finally:
+            client.disconnect()
+
+
+class TestClientPool:
+    """Tests for spreading subscriptions across several OpenD connections."""
+
+    def test_pool_against_mock(self):
+        from nautilus_futu._rust import PyFutuClientPool
+
+        pool = PyFutuClientPool()
+        with pytest.raises(ValueError, match="routing strategy"):
+            pool.connect([("127.0.0.1", 0)], strategy="random", mock=True)
+        pool.connect([("127.0.0.1", 0), ("127.0.0.1", 0)], strategy="round_robin", sub_quota=10, mock=True)
+        try:
+            assert len(pool) == 2
+            results = pool.subscribe([(1, "00700"), (1, "09988")], [1])
+            assert [r["ok"] for r in results] == [True, True]
+            assert sorted(r["connection"] for r in results) == [0, 1]
+            assert pool.route_of(1, "00700") == results[0]["connection"]
+            assert [load["subscriptions"] for load in pool.loads()] == [1, 1]
+            assert [load["remaining_quota"] for load in pool.loads()] == [9, 9]
+
+            results = pool.subscribe([(1, "00700"), (1, "00005")], [1], is_sub=False)
+            assert results[0]["ok"] is True
+            assert results[0]["connection"] is None
+            assert results[1]["error"] == "not subscribed in the pool"
+
+            pool.start_push([3005])
+            # Both connections receive the push
+            assert pool.mock_push(3005, b"\x08\x00\x22\x00") == 2
+            for _ in range(2):
+                assert pool.poll_push(5000) == {"proto_id": 3005, "data": []}
+
+            assert pool.unsubscribe_all() == [0, 1]
+            assert [load["subscriptions"] for load in pool.loads()] == [0, 0]
+            assert pool.route_of(1, "09988") is None
+        finally:
+            pool.disconnect()
+        assert not pool.is_connected()
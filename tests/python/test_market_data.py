@@ -15,8 +15,10 @@ from nautilus_trader.model.objects import Price, Quantity
 from nautilus_futu.common import futu_security_to_instrument_id
 from nautilus_futu.constants import HKEX_VENUE, NYSE_VENUE
 from nautilus_futu.parsing.market_data import (
+    bar_spec_to_aggregation_sub_type,
     bar_spec_to_futu_kl_type,
     bar_spec_to_futu_sub_type,
+    parse_futu_aggregated_bar,
     parse_futu_bars,
     parse_futu_quote_tick,
     parse_futu_trade_tick,
@@ -30,6 +32,8 @@ from nautilus_futu.constants import (
     FUTU_SUB_TYPE_KL_15MIN,
     FUTU_SUB_TYPE_KL_30MIN,
     FUTU_SUB_TYPE_KL_60MIN,
+    FUTU_SUB_TYPE_ORDER_BOOK,
+    FUTU_SUB_TYPE_TICKER,
 )
 
 
@@ -288,3 +292,36 @@ class TestTickEdgeCases:
         data = {"price": 100.0, "volume": 0, "dir": 1, "sequence": 1}
         tick = parse_futu_trade_tick(data, instrument_id, ts_init=0)
         assert str(tick.size) == "1"
+
+
+class TestAggregatedBars:
+    """Tests for bars aggregated from ticker and order book pushes."""
+
+    def test_aggregation_sub_type(self):
+        last = BarSpecification(10, BarAggregation.SECOND, PriceType.LAST)
+        mid = BarSpecification(5, BarAggregation.MINUTE, PriceType.MID)
+        ask = BarSpecification(1, BarAggregation.HOUR, PriceType.ASK)
+        day = BarSpecification(1, BarAggregation.DAY, PriceType.MID)
+        assert bar_spec_to_aggregation_sub_type(last) == FUTU_SUB_TYPE_TICKER
+        assert bar_spec_to_aggregation_sub_type(mid) == FUTU_SUB_TYPE_ORDER_BOOK
+        assert bar_spec_to_aggregation_sub_type(ask) == FUTU_SUB_TYPE_ORDER_BOOK
+        assert bar_spec_to_aggregation_sub_type(day) is None
+
+    def test_parse_aggregated_bar(self, hk_instrument_id):
+        bar_type = BarType.from_str(f"{hk_instrument_id}-5-MINUTE-MID-EXTERNAL")
+        data = {
+            "bar_spec": "5-MINUTE-MID",
+            "open": 350.1,
+            "high": 350.35000000000002,
+            "low": 349.9,
+            "close": 350.2,
+            "volume": 1500.5,
+            "ts_close": 1704164400.0,
+        }
+        bar = parse_futu_aggregated_bar(data, bar_type, ts_init=1, price_precision=3)
+        assert isinstance(bar, Bar)
+        assert bar.bar_type == bar_type
+        assert str(bar.high) == "350.350"
+        assert bar.volume == Quantity.from_int(1500)
+        assert bar.ts_event == 1704164400 * 1_000_000_000
+        assert bar.ts_init == 1
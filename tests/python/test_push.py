@@ -175,6 +175,21 @@ class TestPushOrderBook:
         assert deltas.deltas[3].order.side == OrderSide.SELL
         assert float(deltas.deltas[3].order.price) == 345.2
 
+    def test_order_book_depth(self):
+        data = {
+            "market": 1,
+            "code": "00700",
+            "bids": [{"price": 345.0 - i * 0.2, "volume": 100, "order_count": 1} for i in range(10)],
+            "asks": [{"price": 345.2 + i * 0.2, "volume": 100, "order_count": 1} for i in range(3)],
+            "depth": 10,
+        }
+        instrument_id = futu_security_to_instrument_id(1, "00700")
+        deltas = parse_push_order_book(data, instrument_id, 0, depth=5)
+        # 1 CLEAR + 5 bids + 3 asks
+        assert len(deltas.deltas) == 9
+        assert float(deltas.deltas[5].order.price) == 344.2
+        assert len(parse_push_order_book(data, instrument_id, 0).deltas) == 14
+
     def test_order_book_empty(self):
         data = {
             "market": 1,
@@ -0,0 +1,56 @@
+"""Tests for research bulk downloads."""
+
+import pytest
+
+from nautilus_futu.research import CAPITAL_FLOW_COLUMNS, download_capital_flow
+
+pa = pytest.importorskip("pyarrow")
+
+
+class FakeClient:
+    def __init__(self, result):
+        self.result = result
+        self.calls = []
+
+    def download_capital_flow(self, securities, begin_time, end_time, **kwargs):
+        self.calls.append((securities, begin_time, end_time, kwargs))
+        return self.result
+
+
+def _row(code, time, in_flow):
+    return {
+        "market": 1, "code": code, "alias": None, "time": time, "timestamp": None,
+        "in_flow": in_flow, "main_in_flow": in_flow / 2, "super_in_flow": None,
+        "big_in_flow": None, "mid_in_flow": None, "sml_in_flow": None,
+    }
+
+
+class TestDownloadCapitalFlow:
+    def test_table_and_failures(self):
+        failed = [{"market": 1, "code": "09988", "alias": None, "error": "server error"}]
+        client = FakeClient({
+            "rows": [_row("00700", "2024-05-06", 1.0), _row("00700", "2024-05-07", -2.0)],
+            "failed": failed,
+            "requests": 3,
+        })
+        table, failures = download_capital_flow(
+            client, [(1, "00700"), (1, "09988")], "2024-05-01", "2024-05-31", chunk_days=30,
+        )
+        assert tuple(table.column_names) == CAPITAL_FLOW_COLUMNS
+        assert table.num_rows == 2
+        assert table.column("in_flow").to_pylist() == [1.0, -2.0]
+        assert failures == failed
+        assert client.calls[0][3]["chunk_days"] == 30
+
+    def test_parquet_output(self, tmp_path):
+        pq = pytest.importorskip("pyarrow.parquet")
+        client = FakeClient({"rows": [_row("00700", "2024-05-06", 1.0)], "failed": [], "requests": 1})
+        path = tmp_path / "flow.parquet"
+        download_capital_flow(client, [(1, "00700")], "2024-05-01", "2024-05-31", parquet_path=str(path))
+        assert pq.read_table(path).column("code").to_pylist() == ["00700"]
+
+    def test_empty_download(self):
+        client = FakeClient({"rows": [], "failed": [], "requests": 1})
+        table, _ = download_capital_flow(client, [(1, "00700")], "2024-05-01", "2024-05-31")
+        assert table.num_rows == 0
+        assert tuple(table.column_names) == CAPITAL_FLOW_COLUMNS
@@ -0,0 +1,67 @@
+"""Tests that the type stubs of the extension module match it."""
+
+from __future__ import annotations
+
+import ast
+import inspect
+from pathlib import Path
+
+import pytest
+
+STUB_PATH = Path(__file__).parents[2] / "nautilus_futu" / "_rust.pyi"
+
+
+def _stub_classes() -> dict[str, set[str]]:
+    tree = ast.parse(STUB_PATH.read_text(encoding="utf-8"))
+    return {
+        node.name: {
+            item.name
+            for item in node.body
+            if isinstance(item, (ast.FunctionDef, ast.AsyncFunctionDef))
+        }
+        for node in tree.body
+        if isinstance(node, ast.ClassDef)
+    }
+
+
+def _public_methods(cls: type) -> set[str]:
+    return {
+        name
+        for name, _ in inspect.getmembers(cls)
+        if not name.startswith("_") or name == "__len__"
+    }
+
+
+class TestStubs:
+    """Tests for nautilus_futu/_rust.pyi."""
+
+    def test_stub_parses(self):
+        """The stub is valid Python and declares the client classes."""
+        classes = _stub_classes()
+        assert "PyFutuClient" in classes
+        assert "PyFutuClientPool" in classes
+        assert {"BasicQot", "Kline", "Order", "Position"} <= classes.keys()
+
+    @pytest.mark.parametrize("name", ["PyFutuClient", "PyFutuClientPool", "BasicQot", "Kline", "Order", "Position"])
+    def test_stub_covers_methods(self, name):
+        """Every public method and attribute of a class is in its stub."""
+        rust = pytest.importorskip("nautilus_futu._rust")
+        missing = _public_methods(getattr(rust, name)) - _stub_classes()[name]
+        assert not missing, f"{name} methods missing from _rust.pyi: {sorted(missing)}"
+
+
+class TestTypedResults:
+    """Tests for the typed result classes."""
+
+    def test_classes_exported(self):
+        """The typed result classes are importable from the extension module."""
+        rust = pytest.importorskip("nautilus_futu._rust")
+        for name in ("BasicQot", "Kline", "Order", "Position"):
+            assert isinstance(getattr(rust, name), type)
+
+    def test_typed_without_connection(self):
+        """typed=True calls still need a connection."""
+        rust = pytest.importorskip("nautilus_futu._rust")
+        client = rust.PyFutuClient()
+        with pytest.raises(RuntimeError, match="Not connected"):
+            client.get_order_list(1, 1, 1, typed=True)